@@ -0,0 +1,4 @@
+-- Add reply threads to tweets
+ALTER TABLE tweets ADD COLUMN IF NOT EXISTS parent_tweet_id UUID REFERENCES tweets(id) ON DELETE CASCADE;
+
+CREATE INDEX idx_tweets_parent_tweet_id ON tweets(parent_tweet_id);
@@ -0,0 +1,16 @@
+-- Create retweets table
+CREATE TABLE IF NOT EXISTS retweets (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    tweet_id UUID NOT NULL REFERENCES tweets(id) ON DELETE CASCADE,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP,
+    UNIQUE(user_id, tweet_id)
+);
+
+CREATE INDEX idx_retweets_user_id ON retweets(user_id);
+CREATE INDEX idx_retweets_tweet_id ON retweets(tweet_id);
+
+-- Quote tweets reference the tweet they quote
+ALTER TABLE tweets ADD COLUMN IF NOT EXISTS quoted_tweet_id UUID REFERENCES tweets(id) ON DELETE SET NULL;
+
+CREATE INDEX idx_tweets_quoted_tweet_id ON tweets(quoted_tweet_id);
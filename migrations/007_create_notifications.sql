@@ -0,0 +1,18 @@
+-- Per-follow notification preference ("notify me about this account's tweets")
+ALTER TABLE follows ADD COLUMN IF NOT EXISTS notify VARCHAR(20) NOT NULL DEFAULT 'off'
+    CHECK (notify IN ('all', 'replies_off', 'off'));
+
+CREATE INDEX idx_follows_following_id_notify ON follows(following_id) WHERE notify <> 'off';
+
+-- Create notifications table
+CREATE TABLE IF NOT EXISTS notifications (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    actor_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    kind VARCHAR(20) NOT NULL,
+    tweet_id UUID REFERENCES tweets(id) ON DELETE CASCADE,
+    read_at TIMESTAMP WITH TIME ZONE,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX idx_notifications_user_id_created_at ON notifications(user_id, created_at DESC);
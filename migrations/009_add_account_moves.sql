@@ -0,0 +1,14 @@
+-- "Moved to" redirect: a local account (moved_to_id) or a remote handle/URL.
+-- moved_to always holds the link shown on the old profile.
+ALTER TABLE users ADD COLUMN IF NOT EXISTS moved_to_id UUID REFERENCES users(id) ON DELETE SET NULL;
+ALTER TABLE users ADD COLUMN IF NOT EXISTS moved_to TEXT;
+ALTER TABLE users ADD COLUMN IF NOT EXISTS moved_at TIMESTAMP WITH TIME ZONE;
+
+-- Create account_aliases table ("also known as": accounts allowed to move here)
+CREATE TABLE IF NOT EXISTS account_aliases (
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    alias_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (user_id, alias_id),
+    CHECK (user_id != alias_id)
+);
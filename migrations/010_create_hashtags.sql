@@ -0,0 +1,17 @@
+-- Create hashtags table (tags are stored lowercased)
+CREATE TABLE IF NOT EXISTS hashtags (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    tag VARCHAR(100) UNIQUE NOT NULL,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP
+);
+
+-- Create tweet_hashtags join table
+CREATE TABLE IF NOT EXISTS tweet_hashtags (
+    tweet_id UUID NOT NULL REFERENCES tweets(id) ON DELETE CASCADE,
+    hashtag_id UUID NOT NULL REFERENCES hashtags(id) ON DELETE CASCADE,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (tweet_id, hashtag_id)
+);
+
+CREATE INDEX idx_tweet_hashtags_hashtag_id ON tweet_hashtags(hashtag_id);
+CREATE INDEX idx_tweet_hashtags_created_at ON tweet_hashtags(created_at);
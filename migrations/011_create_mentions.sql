@@ -0,0 +1,12 @@
+-- Create mentions table (users @mentioned in a tweet)
+CREATE TABLE IF NOT EXISTS mentions (
+    tweet_id UUID NOT NULL REFERENCES tweets(id) ON DELETE CASCADE,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (tweet_id, user_id)
+);
+
+CREATE INDEX idx_mentions_user_id_created_at ON mentions(user_id, created_at DESC);
+
+-- Mentions are resolved case-insensitively
+CREATE INDEX idx_users_username_lower ON users(lower(username));
@@ -0,0 +1,2 @@
+-- Tweet length is an instance setting now, enforced by the API
+ALTER TABLE tweets ALTER COLUMN content TYPE TEXT;
@@ -0,0 +1,5 @@
+-- Trigram indexes for user search / typeahead (prefix LIKE and similarity)
+CREATE EXTENSION IF NOT EXISTS pg_trgm;
+
+CREATE INDEX idx_users_username_trgm ON users USING GIN (lower(username) gin_trgm_ops);
+CREATE INDEX idx_users_display_name_trgm ON users USING GIN (lower(display_name) gin_trgm_ops);
@@ -0,0 +1,2 @@
+-- Unread badge counts
+CREATE INDEX idx_notifications_user_id_unread ON notifications(user_id) WHERE read_at IS NULL;
@@ -0,0 +1,17 @@
+-- Senders caught by the abuse heuristics can't post until this passes
+ALTER TABLE users ADD COLUMN IF NOT EXISTS throttled_until TIMESTAMP WITH TIME ZONE;
+
+-- Create abuse_events table (moderator log of heuristic hits)
+CREATE TABLE IF NOT EXISTS abuse_events (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    kind VARCHAR(30) NOT NULL,
+    tweet_id UUID REFERENCES tweets(id) ON DELETE SET NULL,
+    details TEXT NOT NULL,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX idx_abuse_events_created_at ON abuse_events(created_at DESC);
+
+-- Duplicate-reply lookups
+CREATE INDEX idx_tweets_user_id_created_at ON tweets(user_id, created_at DESC);
@@ -0,0 +1,24 @@
+-- Publish new tweets, notifications and follow changes on the 'realtime'
+-- channel so the WebSocket hub can push them to connected clients
+CREATE OR REPLACE FUNCTION notify_realtime() RETURNS trigger AS $$
+BEGIN
+    IF TG_TABLE_NAME = 'tweets' THEN
+        PERFORM pg_notify('realtime', json_build_object('type', 'tweet', 'id', NEW.id, 'user_id', NEW.user_id)::text);
+    ELSIF TG_TABLE_NAME = 'notifications' THEN
+        PERFORM pg_notify('realtime', json_build_object('type', 'notification', 'id', NEW.id, 'user_id', NEW.user_id)::text);
+    ELSIF TG_TABLE_NAME = 'follows' AND TG_OP = 'DELETE' THEN
+        PERFORM pg_notify('realtime', json_build_object('type', 'unfollow', 'follower_id', OLD.follower_id, 'following_id', OLD.following_id)::text);
+        RETURN OLD;
+    ELSIF TG_TABLE_NAME = 'follows' THEN
+        PERFORM pg_notify('realtime', json_build_object('type', 'follow', 'follower_id', NEW.follower_id, 'following_id', NEW.following_id)::text);
+    END IF;
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER tweets_notify_realtime AFTER INSERT ON tweets
+    FOR EACH ROW EXECUTE FUNCTION notify_realtime();
+CREATE TRIGGER notifications_notify_realtime AFTER INSERT ON notifications
+    FOR EACH ROW EXECUTE FUNCTION notify_realtime();
+CREATE TRIGGER follows_notify_realtime AFTER INSERT OR DELETE ON follows
+    FOR EACH ROW EXECUTE FUNCTION notify_realtime();
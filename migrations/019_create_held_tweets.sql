@@ -0,0 +1,13 @@
+-- Create held_tweets table (posts from accounts on probation awaiting review)
+CREATE TABLE IF NOT EXISTS held_tweets (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    content TEXT NOT NULL,
+    image_url TEXT,
+    parent_tweet_id UUID REFERENCES tweets(id) ON DELETE CASCADE,
+    quoted_tweet_id UUID REFERENCES tweets(id) ON DELETE CASCADE,
+    reason TEXT NOT NULL,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX idx_held_tweets_created_at ON held_tweets(created_at DESC);
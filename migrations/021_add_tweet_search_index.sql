@@ -0,0 +1,2 @@
+-- Full-text search over tweet content
+CREATE INDEX IF NOT EXISTS idx_tweets_content_fts ON tweets USING GIN (to_tsvector('english', content));
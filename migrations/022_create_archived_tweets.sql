@@ -0,0 +1,17 @@
+-- Create archived_tweets table (cold storage for old tweets moved out of the
+-- hot tweets table; parent/quote references are kept without foreign keys)
+CREATE TABLE IF NOT EXISTS archived_tweets (
+    id UUID PRIMARY KEY,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    parent_tweet_id UUID,
+    quoted_tweet_id UUID,
+    content TEXT NOT NULL,
+    image_url TEXT,
+    likes_count INTEGER DEFAULT 0,
+    retweets_count INTEGER DEFAULT 0,
+    replies_count INTEGER DEFAULT 0,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL,
+    archived_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX idx_archived_tweets_user_id_created_at ON archived_tweets(user_id, created_at DESC);
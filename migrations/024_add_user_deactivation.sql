@@ -0,0 +1,3 @@
+-- Deactivated accounts are hidden (profile and tweets) until the owner signs
+-- in again
+ALTER TABLE users ADD COLUMN IF NOT EXISTS deactivated_at TIMESTAMP WITH TIME ZONE;
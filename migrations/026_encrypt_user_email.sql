@@ -0,0 +1,9 @@
+-- Emails are sealed by the app (see crypto.rs), so the column holds
+-- ciphertext and lookups go through a keyed blind index instead. The app
+-- fills email_hash and re-seals existing rows at startup.
+ALTER TABLE users ALTER COLUMN email TYPE TEXT;
+ALTER TABLE users ADD COLUMN IF NOT EXISTS email_hash TEXT;
+CREATE UNIQUE INDEX IF NOT EXISTS idx_users_email_hash ON users(email_hash);
+DROP INDEX IF EXISTS idx_users_email;
+
+ALTER TABLE email_changes ALTER COLUMN new_email TYPE TEXT;
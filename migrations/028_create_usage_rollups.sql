@@ -0,0 +1,12 @@
+-- Create usage_rollups table (anonymous hourly request counts per route
+-- pattern; no user or client identifiers)
+CREATE TABLE IF NOT EXISTS usage_rollups (
+    bucket TIMESTAMP WITH TIME ZONE NOT NULL,
+    route TEXT NOT NULL,
+    method VARCHAR(10) NOT NULL,
+    status SMALLINT NOT NULL,
+    country CHAR(2) NOT NULL,
+    requests BIGINT NOT NULL DEFAULT 0,
+    total_ms BIGINT NOT NULL DEFAULT 0,
+    PRIMARY KEY (bucket, route, method, status, country)
+);
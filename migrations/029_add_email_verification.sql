@@ -0,0 +1,16 @@
+-- Accounts confirm their address through a mailed link. Accounts created
+-- before verification existed are treated as verified.
+ALTER TABLE users ADD COLUMN IF NOT EXISTS verified_email BOOLEAN NOT NULL DEFAULT FALSE;
+UPDATE users SET verified_email = TRUE;
+
+-- Create email_verifications table (only the SHA-256 of each mailed token is
+-- stored)
+CREATE TABLE IF NOT EXISTS email_verifications (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    token_hash TEXT NOT NULL UNIQUE,
+    expires_at TIMESTAMP WITH TIME ZONE NOT NULL,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX idx_email_verifications_user_id ON email_verifications(user_id);
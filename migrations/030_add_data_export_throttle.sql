@@ -0,0 +1,2 @@
+-- Last quick data download (GET /api/users/me/data.json), limited to one an hour
+ALTER TABLE users ADD COLUMN IF NOT EXISTS data_exported_at TIMESTAMP WITH TIME ZONE;
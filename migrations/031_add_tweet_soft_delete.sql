@@ -0,0 +1,5 @@
+-- Deleting a tweet only marks it; the row (and the thread around it) stays
+-- until an admin purges it
+ALTER TABLE tweets ADD COLUMN IF NOT EXISTS deleted_at TIMESTAMP WITH TIME ZONE;
+
+CREATE INDEX IF NOT EXISTS idx_tweets_deleted_at ON tweets(deleted_at) WHERE deleted_at IS NOT NULL;
@@ -0,0 +1,9 @@
+-- Create short_link_referrals table (hourly `/t/{short_id}` clicks per tweet
+-- and referring site; no user or client identifiers)
+CREATE TABLE IF NOT EXISTS short_link_referrals (
+    bucket TIMESTAMP WITH TIME ZONE NOT NULL,
+    tweet_id UUID NOT NULL REFERENCES tweets(id) ON DELETE CASCADE,
+    source TEXT NOT NULL,
+    clicks BIGINT NOT NULL DEFAULT 0,
+    PRIMARY KEY (tweet_id, bucket, source)
+);
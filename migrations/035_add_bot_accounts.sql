@@ -0,0 +1,5 @@
+-- Self-declared automated accounts. automated_by holds the owner's username
+-- shown on the profile; bot_owner_id the account it points at.
+ALTER TABLE users ADD COLUMN IF NOT EXISTS is_bot BOOLEAN NOT NULL DEFAULT FALSE;
+ALTER TABLE users ADD COLUMN IF NOT EXISTS bot_owner_id UUID REFERENCES users(id) ON DELETE SET NULL;
+ALTER TABLE users ADD COLUMN IF NOT EXISTS automated_by TEXT;
@@ -0,0 +1,40 @@
+-- Recurring posts for bot accounts (5-field cron expressions, UTC)
+CREATE TABLE IF NOT EXISTS bot_schedules (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    cron TEXT NOT NULL,
+    content TEXT NOT NULL,
+    next_run_at TIMESTAMP WITH TIME ZONE NOT NULL,
+    last_run_at TIMESTAMP WITH TIME ZONE,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS idx_bot_schedules_next_run_at ON bot_schedules(next_run_at);
+CREATE INDEX IF NOT EXISTS idx_bot_schedules_user_id ON bot_schedules(user_id);
+
+-- Keyword auto-replies to tweets mentioning a bot account. checked_until is
+-- how far the bot's mentions have been scanned.
+CREATE TABLE IF NOT EXISTS bot_auto_replies (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    keyword TEXT NOT NULL,
+    reply TEXT NOT NULL,
+    checked_until TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS idx_bot_auto_replies_user_id ON bot_auto_replies(user_id);
+
+-- Audit log of every rule execution
+CREATE TABLE IF NOT EXISTS bot_rule_runs (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    rule_kind VARCHAR(20) NOT NULL,
+    rule_id UUID NOT NULL,
+    tweet_id UUID REFERENCES tweets(id) ON DELETE SET NULL,
+    outcome VARCHAR(20) NOT NULL,
+    details TEXT,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS idx_bot_rule_runs_user_id_created_at ON bot_rule_runs(user_id, created_at DESC);
@@ -0,0 +1,29 @@
+-- Who can see a tweet: everyone ('public'), the author's followers
+-- ('followers_only') or the accounts in the author's circle ('circle')
+ALTER TABLE tweets ADD COLUMN IF NOT EXISTS visibility VARCHAR(20) NOT NULL DEFAULT 'public'
+    CHECK (visibility IN ('public', 'followers_only', 'circle'));
+ALTER TABLE archived_tweets ADD COLUMN IF NOT EXISTS visibility VARCHAR(20) NOT NULL DEFAULT 'public';
+ALTER TABLE held_tweets ADD COLUMN IF NOT EXISTS visibility VARCHAR(20) NOT NULL DEFAULT 'public';
+
+-- Create circle_members table (accounts an author shares circle tweets with)
+CREATE TABLE IF NOT EXISTS circle_members (
+    owner_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    member_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (owner_id, member_id),
+    CHECK (owner_id != member_id)
+);
+
+-- Whether `viewer_id` may see a tweet by `author_id`; NULL is an anonymous
+-- viewer. Every query that returns tweets to someone other than the author
+-- filters on this.
+CREATE OR REPLACE FUNCTION tweet_visible_to(author_id UUID, visibility TEXT, viewer_id UUID) RETURNS BOOLEAN AS $$
+    SELECT visibility = 'public'
+        OR (viewer_id IS NOT NULL AND (
+            author_id = viewer_id
+            OR (visibility = 'followers_only'
+                AND EXISTS (SELECT 1 FROM follows f WHERE f.follower_id = viewer_id AND f.following_id = author_id))
+            OR (visibility = 'circle'
+                AND EXISTS (SELECT 1 FROM circle_members c WHERE c.owner_id = author_id AND c.member_id = viewer_id))
+        ))
+$$ LANGUAGE sql STABLE;
@@ -0,0 +1,36 @@
+-- User-configured triggers that POST to a webhook URL when a condition is met
+-- ('tweet_likes': one of my tweets reached `threshold` likes;
+-- 'follower_reach': an account with at least `threshold` followers followed
+-- me). Only events after armed_at (creation or last re-enable) fire.
+CREATE TABLE IF NOT EXISTS triggers (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    kind VARCHAR(30) NOT NULL,
+    threshold INTEGER NOT NULL CHECK (threshold > 0),
+    url TEXT NOT NULL,
+    secret TEXT NOT NULL,
+    enabled BOOLEAN NOT NULL DEFAULT TRUE,
+    armed_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS idx_triggers_user_id ON triggers(user_id);
+
+-- Outbox of webhook calls. A trigger fires at most once per subject (the
+-- tweet or the follower).
+CREATE TABLE IF NOT EXISTS trigger_deliveries (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    trigger_id UUID NOT NULL REFERENCES triggers(id) ON DELETE CASCADE,
+    subject_id UUID NOT NULL,
+    payload JSONB NOT NULL,
+    status VARCHAR(20) NOT NULL DEFAULT 'pending',
+    attempts INTEGER NOT NULL DEFAULT 0,
+    last_error TEXT,
+    next_attempt_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    delivered_at TIMESTAMP WITH TIME ZONE,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP,
+    UNIQUE(trigger_id, subject_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_trigger_deliveries_pending ON trigger_deliveries(next_attempt_at) WHERE status = 'pending';
+CREATE INDEX IF NOT EXISTS idx_trigger_deliveries_trigger_id_created_at ON trigger_deliveries(trigger_id, created_at DESC);
@@ -0,0 +1,13 @@
+-- Post-by-email: each user can have one secret address, stored hashed like
+-- other tokens
+ALTER TABLE users ADD COLUMN IF NOT EXISTS post_email_token_hash TEXT UNIQUE;
+
+-- Messages already turned into tweets, so provider retries don't post twice
+CREATE TABLE IF NOT EXISTS inbound_emails (
+    message_id TEXT PRIMARY KEY,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    tweet_id UUID REFERENCES tweets(id) ON DELETE SET NULL,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS idx_inbound_emails_created_at ON inbound_emails(created_at);
@@ -0,0 +1,34 @@
+-- Cross-posting integrations. `credentials` is the Telegram bot token or the
+-- Discord webhook URL, sealed like personal data; `target` is the Telegram
+-- chat. Only tweets posted after armed_at (creation or last re-enable) are
+-- cross-posted.
+CREATE TABLE IF NOT EXISTS integrations (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    provider VARCHAR(20) NOT NULL,
+    credentials TEXT NOT NULL,
+    target TEXT,
+    enabled BOOLEAN NOT NULL DEFAULT TRUE,
+    armed_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    last_posted_at TIMESTAMP WITH TIME ZONE,
+    last_error TEXT,
+    last_error_at TIMESTAMP WITH TIME ZONE,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS idx_integrations_user_id ON integrations(user_id);
+
+-- Outbox of cross-posts, one per integration and tweet
+CREATE TABLE IF NOT EXISTS integration_posts (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    integration_id UUID NOT NULL REFERENCES integrations(id) ON DELETE CASCADE,
+    tweet_id UUID NOT NULL REFERENCES tweets(id) ON DELETE CASCADE,
+    status VARCHAR(20) NOT NULL DEFAULT 'pending',
+    attempts INTEGER NOT NULL DEFAULT 0,
+    last_error TEXT,
+    next_attempt_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP,
+    UNIQUE(integration_id, tweet_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_integration_posts_pending ON integration_posts(next_attempt_at) WHERE status = 'pending';
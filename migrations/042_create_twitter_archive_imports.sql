@@ -0,0 +1,31 @@
+-- Twitter archive imports. The uploaded ZIP is kept until the import job
+-- finishes so any worker can pick it up.
+CREATE TABLE IF NOT EXISTS twitter_archive_imports (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    status VARCHAR(20) NOT NULL DEFAULT 'pending'
+        CHECK (status IN ('pending', 'running', 'completed', 'failed')),
+    archive BYTEA,
+    total_tweets INTEGER NOT NULL DEFAULT 0,
+    processed_tweets INTEGER NOT NULL DEFAULT 0,
+    imported_count INTEGER NOT NULL DEFAULT 0,
+    duplicate_count INTEGER NOT NULL DEFAULT 0,
+    skipped_count INTEGER NOT NULL DEFAULT 0,
+    error TEXT,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP,
+    completed_at TIMESTAMP WITH TIME ZONE
+);
+
+CREATE INDEX IF NOT EXISTS idx_twitter_archive_imports_user_id ON twitter_archive_imports(user_id, created_at DESC);
+
+-- Archive tweets already imported, by their Twitter ID, so re-importing the
+-- same or a newer archive only adds what's new. No foreign key on tweet_id:
+-- the entry outlives the tweet being archived or deleted, so neither brings
+-- it back on the next import.
+CREATE TABLE IF NOT EXISTS imported_tweets (
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    source_id TEXT NOT NULL,
+    tweet_id UUID NOT NULL,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (user_id, source_id)
+);
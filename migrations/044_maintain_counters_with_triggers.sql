@@ -0,0 +1,92 @@
+-- Counter columns are kept by triggers instead of by each handler, so every
+-- path that adds or removes a row (including ON DELETE CASCADE when an account
+-- is deleted) keeps them right.
+
+-- users.followers_count / following_count
+CREATE OR REPLACE FUNCTION follows_count_trigger() RETURNS trigger AS $$
+BEGIN
+    IF TG_OP = 'INSERT' THEN
+        UPDATE users SET following_count = following_count + 1 WHERE id = NEW.follower_id;
+        UPDATE users SET followers_count = followers_count + 1 WHERE id = NEW.following_id;
+    ELSE
+        UPDATE users SET following_count = following_count - 1 WHERE id = OLD.follower_id;
+        UPDATE users SET followers_count = followers_count - 1 WHERE id = OLD.following_id;
+    END IF;
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS follows_count ON follows;
+CREATE TRIGGER follows_count AFTER INSERT OR DELETE ON follows
+    FOR EACH ROW EXECUTE FUNCTION follows_count_trigger();
+
+-- tweets.likes_count
+CREATE OR REPLACE FUNCTION likes_count_trigger() RETURNS trigger AS $$
+BEGIN
+    IF TG_OP = 'INSERT' THEN
+        UPDATE tweets SET likes_count = likes_count + 1 WHERE id = NEW.tweet_id;
+    ELSE
+        UPDATE tweets SET likes_count = likes_count - 1 WHERE id = OLD.tweet_id;
+    END IF;
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS likes_count ON likes;
+CREATE TRIGGER likes_count AFTER INSERT OR DELETE ON likes
+    FOR EACH ROW EXECUTE FUNCTION likes_count_trigger();
+
+-- tweets.retweets_count
+CREATE OR REPLACE FUNCTION retweets_count_trigger() RETURNS trigger AS $$
+BEGIN
+    IF TG_OP = 'INSERT' THEN
+        UPDATE tweets SET retweets_count = retweets_count + 1 WHERE id = NEW.tweet_id;
+    ELSE
+        UPDATE tweets SET retweets_count = retweets_count - 1 WHERE id = OLD.tweet_id;
+    END IF;
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS retweets_count ON retweets;
+CREATE TRIGGER retweets_count AFTER INSERT OR DELETE ON retweets
+    FOR EACH ROW EXECUTE FUNCTION retweets_count_trigger();
+
+-- tweets.replies_count counts live replies. Soft-deleting a reply takes it
+-- off its parent's count. Removing the row only does when the reply was live
+-- and went with its author's account: archiving moves live replies out of
+-- `tweets` but keeps them counted, and purged replies were deleted already.
+CREATE OR REPLACE FUNCTION replies_count_trigger() RETURNS trigger AS $$
+BEGIN
+    IF TG_OP = 'INSERT' THEN
+        IF NEW.parent_tweet_id IS NOT NULL AND NEW.deleted_at IS NULL THEN
+            UPDATE tweets SET replies_count = replies_count + 1 WHERE id = NEW.parent_tweet_id;
+        END IF;
+    ELSIF TG_OP = 'UPDATE' THEN
+        IF NEW.parent_tweet_id IS NOT NULL AND OLD.deleted_at IS NULL AND NEW.deleted_at IS NOT NULL THEN
+            UPDATE tweets SET replies_count = replies_count - 1 WHERE id = NEW.parent_tweet_id;
+        ELSIF NEW.parent_tweet_id IS NOT NULL AND OLD.deleted_at IS NOT NULL AND NEW.deleted_at IS NULL THEN
+            UPDATE tweets SET replies_count = replies_count + 1 WHERE id = NEW.parent_tweet_id;
+        END IF;
+    ELSIF OLD.parent_tweet_id IS NOT NULL AND OLD.deleted_at IS NULL
+          AND NOT EXISTS (SELECT 1 FROM users WHERE id = OLD.user_id) THEN
+        UPDATE tweets SET replies_count = replies_count - 1 WHERE id = OLD.parent_tweet_id;
+    END IF;
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS replies_count ON tweets;
+CREATE TRIGGER replies_count AFTER INSERT OR DELETE OR UPDATE OF deleted_at ON tweets
+    FOR EACH ROW EXECUTE FUNCTION replies_count_trigger();
+
+-- Start from correct values
+UPDATE users u SET
+    followers_count = (SELECT COUNT(*) FROM follows f WHERE f.following_id = u.id),
+    following_count = (SELECT COUNT(*) FROM follows f WHERE f.follower_id = u.id);
+
+UPDATE tweets t SET
+    likes_count = (SELECT COUNT(*) FROM likes l WHERE l.tweet_id = t.id),
+    retweets_count = (SELECT COUNT(*) FROM retweets r WHERE r.tweet_id = t.id),
+    replies_count = (SELECT COUNT(*) FROM tweets c WHERE c.parent_tweet_id = t.id AND c.deleted_at IS NULL)
+                  + (SELECT COUNT(*) FROM archived_tweets a WHERE a.parent_tweet_id = t.id);
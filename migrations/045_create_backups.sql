@@ -0,0 +1,32 @@
+-- Content backups to a bucket the user owns, one per user. The secret key is
+-- sealed like personal data.
+CREATE TABLE IF NOT EXISTS backup_targets (
+    user_id UUID PRIMARY KEY REFERENCES users(id) ON DELETE CASCADE,
+    endpoint TEXT NOT NULL,
+    bucket TEXT NOT NULL,
+    region VARCHAR(50) NOT NULL,
+    prefix TEXT NOT NULL DEFAULT '',
+    access_key_id TEXT NOT NULL,
+    secret_access_key TEXT NOT NULL,
+    last_backup_at TIMESTAMP WITH TIME ZONE,
+    last_error TEXT,
+    last_error_at TIMESTAMP WITH TIME ZONE,
+    failure_notified_at TIMESTAMP WITH TIME ZONE,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP
+);
+
+-- Outbox of tweets to upload, one per user and tweet
+CREATE TABLE IF NOT EXISTS backup_items (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL REFERENCES backup_targets(user_id) ON DELETE CASCADE,
+    tweet_id UUID NOT NULL REFERENCES tweets(id) ON DELETE CASCADE,
+    status VARCHAR(20) NOT NULL DEFAULT 'pending',
+    attempts INTEGER NOT NULL DEFAULT 0,
+    last_error TEXT,
+    next_attempt_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    backed_up_at TIMESTAMP WITH TIME ZONE,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP,
+    UNIQUE(user_id, tweet_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_backup_items_pending ON backup_items(next_attempt_at) WHERE status = 'pending';
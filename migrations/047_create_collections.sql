@@ -0,0 +1,25 @@
+-- Curated, public collections of tweets. A user's featured collections are
+-- shown on their profile.
+CREATE TABLE IF NOT EXISTS collections (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    title VARCHAR(100) NOT NULL,
+    description TEXT NOT NULL DEFAULT '',
+    featured BOOLEAN NOT NULL DEFAULT FALSE,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS idx_collections_user ON collections(user_id, updated_at DESC);
+
+-- Entries leave with their tweet, including when it is archived
+CREATE TABLE IF NOT EXISTS collection_tweets (
+    collection_id UUID NOT NULL REFERENCES collections(id) ON DELETE CASCADE,
+    tweet_id UUID NOT NULL REFERENCES tweets(id) ON DELETE CASCADE,
+    position INTEGER NOT NULL,
+    added_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (collection_id, tweet_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_collection_tweets_position ON collection_tweets(collection_id, position);
+CREATE INDEX IF NOT EXISTS idx_collection_tweets_tweet ON collection_tweets(tweet_id);
@@ -0,0 +1,12 @@
+-- Staff roles replace the is_admin flag. Moderators handle users and content;
+-- admins also run the instance and manage roles.
+ALTER TABLE users ADD COLUMN IF NOT EXISTS role VARCHAR(20) NOT NULL DEFAULT 'user'
+    CHECK (role IN ('user', 'moderator', 'admin'));
+UPDATE users SET role = 'admin' WHERE is_admin;
+ALTER TABLE users DROP COLUMN IF EXISTS is_admin;
+
+CREATE INDEX IF NOT EXISTS idx_users_staff ON users(role) WHERE role <> 'user';
+
+-- Suspended accounts can't sign in and are hidden like deactivated ones
+ALTER TABLE users ADD COLUMN IF NOT EXISTS suspended_at TIMESTAMP WITH TIME ZONE;
+ALTER TABLE users ADD COLUMN IF NOT EXISTS suspension_reason TEXT;
@@ -0,0 +1,11 @@
+-- A tweet can name one co-author. The tweet shows on the co-author's profile
+-- once they accept; pending tags are only visible to the two accounts.
+CREATE TABLE IF NOT EXISTS tweet_coauthors (
+    tweet_id UUID PRIMARY KEY REFERENCES tweets(id) ON DELETE CASCADE,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    accepted_at TIMESTAMP WITH TIME ZONE,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS idx_tweet_coauthors_accepted ON tweet_coauthors(user_id) WHERE accepted_at IS NOT NULL;
+CREATE INDEX IF NOT EXISTS idx_tweet_coauthors_pending ON tweet_coauthors(user_id, created_at DESC) WHERE accepted_at IS NULL;
@@ -0,0 +1,27 @@
+-- User reports about tweets and accounts, worked through by moderators.
+-- Tweet reports keep the author in user_id, so they stay attributable after
+-- the tweet is gone.
+CREATE TABLE IF NOT EXISTS reports (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    reporter_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    tweet_id UUID REFERENCES tweets(id) ON DELETE SET NULL,
+    reason VARCHAR(20) NOT NULL
+        CHECK (reason IN ('spam', 'harassment', 'hate', 'violence', 'self_harm', 'sexual_content', 'impersonation', 'other')),
+    details TEXT NOT NULL DEFAULT '',
+    status VARCHAR(20) NOT NULL DEFAULT 'open' CHECK (status IN ('open', 'resolved', 'dismissed')),
+    action VARCHAR(20) CHECK (action IN ('none', 'hide_tweet', 'suspend_user')),
+    resolved_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    resolved_at TIMESTAMP WITH TIME ZONE,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS idx_reports_status_created_at ON reports(status, created_at DESC);
+CREATE INDEX IF NOT EXISTS idx_reports_user_open ON reports(user_id) WHERE status = 'open';
+CREATE INDEX IF NOT EXISTS idx_reports_tweet_open ON reports(tweet_id) WHERE status = 'open';
+
+-- One open report per reporter and target
+CREATE UNIQUE INDEX IF NOT EXISTS idx_reports_open_tweet_unique ON reports(reporter_id, tweet_id)
+    WHERE status = 'open' AND tweet_id IS NOT NULL;
+CREATE UNIQUE INDEX IF NOT EXISTS idx_reports_open_user_unique ON reports(reporter_id, user_id)
+    WHERE status = 'open' AND tweet_id IS NULL;
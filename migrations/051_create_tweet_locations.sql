@@ -0,0 +1,30 @@
+-- Location tags on tweets, either coordinates or a named place, and the
+-- account setting that shows or hides them. Distance queries use
+-- earthdistance (cube-based, no PostGIS needed).
+CREATE EXTENSION IF NOT EXISTS cube;
+CREATE EXTENSION IF NOT EXISTS earthdistance;
+
+ALTER TABLE users ADD COLUMN IF NOT EXISTS share_location BOOLEAN NOT NULL DEFAULT FALSE;
+
+CREATE TABLE IF NOT EXISTS places (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    name VARCHAR(100) NOT NULL,
+    country_code CHAR(2),
+    latitude DOUBLE PRECISION NOT NULL CHECK (latitude BETWEEN -90 AND 90),
+    longitude DOUBLE PRECISION NOT NULL CHECK (longitude BETWEEN -180 AND 180),
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS idx_places_name ON places(lower(name) text_pattern_ops);
+
+-- Tagged by place, the place's coordinates are copied in so every tag can be
+-- searched by distance
+CREATE TABLE IF NOT EXISTS tweet_locations (
+    tweet_id UUID PRIMARY KEY REFERENCES tweets(id) ON DELETE CASCADE,
+    place_id UUID REFERENCES places(id) ON DELETE SET NULL,
+    latitude DOUBLE PRECISION NOT NULL CHECK (latitude BETWEEN -90 AND 90),
+    longitude DOUBLE PRECISION NOT NULL CHECK (longitude BETWEEN -180 AND 180),
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS idx_tweet_locations_earth ON tweet_locations USING gist (ll_to_earth(latitude, longitude));
@@ -0,0 +1,4 @@
+-- Suspensions can end on their own; NULL keeps one in place until lifted
+ALTER TABLE users ADD COLUMN IF NOT EXISTS suspended_until TIMESTAMP WITH TIME ZONE;
+
+CREATE INDEX IF NOT EXISTS idx_users_suspended_until ON users(suspended_until) WHERE suspended_at IS NOT NULL;
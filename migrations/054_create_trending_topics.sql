@@ -0,0 +1,29 @@
+-- Trend regions, woeid style: 1 is worldwide, the rest are circles around a
+-- point and count the location-tagged tweets inside them. computed_at is
+-- when the region's trends were last refreshed.
+CREATE TABLE IF NOT EXISTS trend_regions (
+    woeid INTEGER PRIMARY KEY CHECK (woeid > 0),
+    name VARCHAR(100) NOT NULL,
+    latitude DOUBLE PRECISION CHECK (latitude BETWEEN -90 AND 90),
+    longitude DOUBLE PRECISION CHECK (longitude BETWEEN -180 AND 180),
+    radius_km DOUBLE PRECISION CHECK (radius_km > 0),
+    computed_at TIMESTAMP WITH TIME ZONE,
+    CHECK ((latitude IS NULL) = (longitude IS NULL) AND (latitude IS NULL) = (radius_km IS NULL))
+);
+
+INSERT INTO trend_regions (woeid, name) VALUES (1, 'Worldwide') ON CONFLICT DO NOTHING;
+
+-- The latest ranking per region and window, replaced on every refresh
+CREATE TABLE IF NOT EXISTS trending_topics (
+    woeid INTEGER NOT NULL REFERENCES trend_regions(woeid) ON DELETE CASCADE,
+    time_window VARCHAR(3) NOT NULL CHECK (time_window IN ('1h', '24h')),
+    topic VARCHAR(200) NOT NULL,
+    kind VARCHAR(10) NOT NULL CHECK (kind IN ('hashtag', 'phrase')),
+    tweet_count INTEGER NOT NULL,
+    author_count INTEGER NOT NULL,
+    rank INTEGER NOT NULL,
+    computed_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (woeid, time_window, topic)
+);
+
+CREATE INDEX IF NOT EXISTS idx_trending_topics_rank ON trending_topics(woeid, time_window, rank);
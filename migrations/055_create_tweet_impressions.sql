@@ -0,0 +1,11 @@
+-- Create tweet_impressions table (hourly counts of tweets served in feeds and
+-- on their own page; no viewer identifiers)
+CREATE TABLE IF NOT EXISTS tweet_impressions (
+    bucket TIMESTAMP WITH TIME ZONE NOT NULL,
+    tweet_id UUID NOT NULL REFERENCES tweets(id) ON DELETE CASCADE,
+    impressions BIGINT NOT NULL DEFAULT 0,
+    PRIMARY KEY (tweet_id, bucket)
+);
+
+-- Weekly recaps look up new followers by when they followed
+CREATE INDEX IF NOT EXISTS idx_follows_following_created ON follows(following_id, created_at);
@@ -0,0 +1,32 @@
+-- Composer drafts, saved over the REST API or autosaved over the WebSocket.
+-- `version` goes up with every save so devices editing the same draft can
+-- tell when theirs is out of date; `device` is the client label of the last
+-- save.
+CREATE TABLE IF NOT EXISTS drafts (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    content TEXT NOT NULL DEFAULT '',
+    parent_tweet_id UUID REFERENCES tweets(id) ON DELETE SET NULL,
+    version BIGINT NOT NULL DEFAULT 1,
+    device VARCHAR(64),
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS idx_drafts_user ON drafts(user_id, updated_at DESC);
+
+-- Saves and deletions go out on the 'realtime' channel so the user's other
+-- sessions see them
+CREATE OR REPLACE FUNCTION notify_draft_changed() RETURNS trigger AS $$
+BEGIN
+    IF TG_OP = 'DELETE' THEN
+        PERFORM pg_notify('realtime', json_build_object('type', 'draft_deleted', 'id', OLD.id, 'user_id', OLD.user_id)::text);
+        RETURN OLD;
+    END IF;
+    PERFORM pg_notify('realtime', json_build_object('type', 'draft', 'id', NEW.id, 'user_id', NEW.user_id)::text);
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER drafts_notify_realtime AFTER INSERT OR UPDATE OR DELETE ON drafts
+    FOR EACH ROW EXECUTE FUNCTION notify_draft_changed();
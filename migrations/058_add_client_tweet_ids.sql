@@ -0,0 +1,5 @@
+-- Tweets may be created with a client-chosen id. A held tweet keeps that id
+-- so it's published under it once approved.
+ALTER TABLE held_tweets ADD COLUMN IF NOT EXISTS tweet_id UUID;
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_held_tweets_tweet_id ON held_tweets(tweet_id);
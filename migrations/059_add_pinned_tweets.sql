@@ -0,0 +1,2 @@
+-- The tweet a user shows at the top of their profile
+ALTER TABLE users ADD COLUMN IF NOT EXISTS pinned_tweet_id UUID REFERENCES tweets(id) ON DELETE SET NULL;
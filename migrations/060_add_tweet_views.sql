@@ -0,0 +1,4 @@
+-- Views and profile clicks reported by clients, rolled up hourly next to the
+-- impressions the server counts when it serves a tweet
+ALTER TABLE tweet_impressions ADD COLUMN IF NOT EXISTS views BIGINT NOT NULL DEFAULT 0;
+ALTER TABLE tweet_impressions ADD COLUMN IF NOT EXISTS profile_clicks BIGINT NOT NULL DEFAULT 0;
@@ -0,0 +1,12 @@
+-- Daily calls to deprecated routes per caller, so staff can see who still
+-- has to move before a route is sunset (user_id is NULL for signed-out calls)
+CREATE TABLE IF NOT EXISTS deprecated_route_calls (
+    day DATE NOT NULL,
+    route TEXT NOT NULL,
+    method VARCHAR(10) NOT NULL,
+    user_id UUID REFERENCES users(id) ON DELETE CASCADE,
+    calls BIGINT NOT NULL DEFAULT 0
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_deprecated_route_calls_key ON deprecated_route_calls
+    (day, route, method, COALESCE(user_id, '00000000-0000-0000-0000-000000000000'::uuid));
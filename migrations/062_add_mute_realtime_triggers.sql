@@ -0,0 +1,23 @@
+-- Mutes and muted keywords go out on the 'realtime' channel so open
+-- WebSocket sessions stop (or resume) pushing the muted content at once
+CREATE OR REPLACE FUNCTION notify_mute_changed() RETURNS trigger AS $$
+BEGIN
+    IF TG_TABLE_NAME = 'mutes' AND TG_OP = 'DELETE' THEN
+        PERFORM pg_notify('realtime', json_build_object('type', 'unmute', 'muter_id', OLD.muter_id, 'muted_id', OLD.muted_id)::text);
+        RETURN OLD;
+    ELSIF TG_TABLE_NAME = 'mutes' THEN
+        PERFORM pg_notify('realtime', json_build_object('type', 'mute', 'muter_id', NEW.muter_id, 'muted_id', NEW.muted_id)::text);
+    ELSIF TG_OP = 'DELETE' THEN
+        PERFORM pg_notify('realtime', json_build_object('type', 'unmuted_keyword', 'user_id', OLD.user_id, 'keyword', OLD.keyword)::text);
+        RETURN OLD;
+    ELSE
+        PERFORM pg_notify('realtime', json_build_object('type', 'muted_keyword', 'user_id', NEW.user_id, 'keyword', NEW.keyword)::text);
+    END IF;
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER mutes_notify_realtime AFTER INSERT OR DELETE ON mutes
+    FOR EACH ROW EXECUTE FUNCTION notify_mute_changed();
+CREATE TRIGGER muted_keywords_notify_realtime AFTER INSERT OR DELETE ON muted_keywords
+    FOR EACH ROW EXECUTE FUNCTION notify_mute_changed();
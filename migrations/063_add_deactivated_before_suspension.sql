@@ -0,0 +1,3 @@
+-- A suspension hides the account through deactivated_at; this keeps the
+-- account's own deactivation so lifting the suspension puts it back
+ALTER TABLE users ADD COLUMN IF NOT EXISTS deactivated_before_suspension TIMESTAMP WITH TIME ZONE;
@@ -0,0 +1,158 @@
+use chrono::{DateTime, Utc};
+use sqlx::{PgConnection, PgPool};
+use uuid::Uuid;
+
+use crate::config;
+use crate::error::ApiError;
+use crate::mentions;
+
+// Spam heuristics applied when a tweet is posted. A flagged tweet is still
+// published, but it notifies nobody, the sender is throttled for a while,
+// and the hit is logged in `abuse_events` for moderators.
+#[derive(Debug, Clone)]
+pub struct AbuseLimits {
+    // More distinct @mentions than this in one tweet is a mass mention
+    pub max_mentions: usize,
+    // This many identical replies to different tweets within the window is
+    // reply spam
+    pub duplicate_reply_limit: i64,
+    pub duplicate_reply_window_minutes: i32,
+    pub throttle_minutes: i32,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum Signal {
+    MassMention { mentions: usize },
+    ReplySpam { duplicates: i64 },
+}
+
+impl Signal {
+    fn kind(&self) -> &'static str {
+        match self {
+            Signal::MassMention { .. } => "mass_mention",
+            Signal::ReplySpam { .. } => "reply_spam",
+        }
+    }
+
+    fn details(&self) -> String {
+        match self {
+            Signal::MassMention { mentions } => format!("{} users mentioned in one tweet", mentions),
+            Signal::ReplySpam { duplicates } => format!("same reply sent to {} tweets", duplicates),
+        }
+    }
+}
+
+impl AbuseLimits {
+    pub fn from_env() -> Result<Self, String> {
+        Ok(AbuseLimits {
+            max_mentions: config::parse_var("ABUSE_MAX_MENTIONS", 10)?,
+            duplicate_reply_limit: config::parse_var("ABUSE_DUPLICATE_REPLY_LIMIT", 5)?,
+            duplicate_reply_window_minutes: config::parse_var("ABUSE_DUPLICATE_REPLY_WINDOW_MINUTES", 10)?,
+            throttle_minutes: config::parse_var("ABUSE_THROTTLE_MINUTES", 30)?,
+        })
+    }
+
+    // Rejects posting while the sender is throttled.
+    pub async fn ensure_not_throttled(&self, db: &PgPool, user_id: Uuid) -> Result<(), ApiError> {
+        let throttled_until = sqlx::query_scalar::<_, DateTime<Utc>>(
+            "SELECT throttled_until FROM users WHERE id = $1 AND throttled_until > NOW()"
+        )
+        .bind(user_id)
+        .fetch_optional(db)
+        .await?;
+
+        match throttled_until {
+            Some(until) => Err(ApiError::TooManyRequests(format!(
+                "Posting is temporarily limited until {}",
+                until.to_rfc3339()
+            ))),
+            None => Ok(()),
+        }
+    }
+
+    // Checks a tweet that is about to be inserted. Must run before the insert
+    // so the tweet doesn't count as its own duplicate.
+    pub async fn check(
+        &self,
+        conn: &mut PgConnection,
+        user_id: Uuid,
+        content: &str,
+        parent_tweet_id: Option<Uuid>,
+    ) -> Result<Option<Signal>, sqlx::Error> {
+        let mentions = mentions::extract(content).len();
+        if mentions > self.max_mentions {
+            return Ok(Some(Signal::MassMention { mentions }));
+        }
+
+        let Some(parent_tweet_id) = parent_tweet_id else {
+            return Ok(None);
+        };
+
+        let previous = sqlx::query_scalar::<_, i64>(
+            "SELECT COUNT(DISTINCT parent_tweet_id) FROM tweets
+             WHERE user_id = $1
+               AND parent_tweet_id IS NOT NULL AND parent_tweet_id <> $2
+               AND content = $3
+               AND created_at > NOW() - make_interval(mins => $4)"
+        )
+        .bind(user_id)
+        .bind(parent_tweet_id)
+        .bind(content)
+        .bind(self.duplicate_reply_window_minutes)
+        .fetch_one(&mut *conn)
+        .await?;
+
+        let duplicates = previous + 1;
+        if duplicates >= self.duplicate_reply_limit {
+            return Ok(Some(Signal::ReplySpam { duplicates }));
+        }
+
+        Ok(None)
+    }
+
+    // Logs the hit for moderators and throttles the sender. For reply spam,
+    // unread notifications from the earlier copies are withdrawn too.
+    pub async fn record(
+        &self,
+        conn: &mut PgConnection,
+        user_id: Uuid,
+        tweet_id: Uuid,
+        content: &str,
+        signal: Signal,
+    ) -> Result<(), sqlx::Error> {
+        log::warn!("abuse heuristic {} triggered by {}: {}", signal.kind(), user_id, signal.details());
+
+        sqlx::query("INSERT INTO abuse_events (user_id, kind, tweet_id, details) VALUES ($1, $2, $3, $4)")
+            .bind(user_id)
+            .bind(signal.kind())
+            .bind(tweet_id)
+            .bind(signal.details())
+            .execute(&mut *conn)
+            .await?;
+
+        if let Signal::ReplySpam { .. } = signal {
+            sqlx::query(
+                "DELETE FROM notifications
+                 WHERE actor_id = $1 AND read_at IS NULL
+                   AND tweet_id IN (
+                       SELECT id FROM tweets
+                       WHERE user_id = $1 AND content = $2
+                         AND created_at > NOW() - make_interval(mins => $3)
+                   )"
+            )
+            .bind(user_id)
+            .bind(content)
+            .bind(self.duplicate_reply_window_minutes)
+            .execute(&mut *conn)
+            .await?;
+        }
+
+        sqlx::query("UPDATE users SET throttled_until = NOW() + make_interval(mins => $2) WHERE id = $1")
+            .bind(user_id)
+            .bind(self.throttle_minutes)
+            .execute(&mut *conn)
+            .await?;
+
+        Ok(())
+    }
+}
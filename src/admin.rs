@@ -0,0 +1,439 @@
+use actix_web::body::{EitherBody, MessageBody};
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::http::Method;
+use actix_web::middleware::Next;
+use actix_web::{web, Error, HttpResponse};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, PgPool};
+use uuid::Uuid;
+
+use crate::auth::{self, AdminUser, ModeratorUser, Role};
+use crate::error::{ApiError, ApiResult};
+use crate::models::ApiResponse;
+use crate::pagination::{self, Cursor, PageQuery};
+use crate::handlers::tweets::soft_delete_tweet;
+use crate::handlers::search::like_prefix;
+use crate::{analytics, AppState};
+
+// Staff routes for managing accounts and content. Moderators list and
+// suspend users, delete tweets and see platform stats; changing roles and
+// the verified badge is for admins. Staff only act on accounts ranked below
+// them, and never on themselves.
+//
+// A suspension lasts until lifted, or until `until` when one is given (the
+// scheduler lifts expired ones). The account is hidden like a deactivated
+// one, so its tweets drop out of timelines and search, but it can still sign
+// in to see its status: `suspension_guard` turns away every write it makes.
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.route("/api/admin/users", web::get().to(list_users))
+        .route("/api/admin/users/{id}/suspend", web::post().to(suspend_user))
+        .route("/api/admin/users/{id}/unsuspend", web::post().to(unsuspend_user))
+        .route("/api/admin/users/{id}/role", web::put().to(set_role))
+        .route("/api/admin/users/{id}/verified", web::put().to(set_verified))
+        .route("/api/admin/tweets/{id}", web::delete().to(delete_tweet))
+        .route("/api/admin/stats", web::get().to(get_stats));
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UserFilter {
+    // Username prefix
+    pub q: Option<String>,
+    pub role: Option<Role>,
+    pub suspended: Option<bool>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SuspendRequest {
+    pub reason: Option<String>,
+    // Indefinite when missing
+    pub until: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RoleRequest {
+    pub role: Role,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct VerifiedRequest {
+    pub verified: bool,
+}
+
+#[derive(Debug, Serialize, FromRow)]
+pub struct ManagedUser {
+    pub id: Uuid,
+    pub username: String,
+    pub display_name: String,
+    pub role: String,
+    pub verified: bool,
+    pub followers_count: i32,
+    pub deactivated_at: Option<DateTime<Utc>>,
+    pub suspended_at: Option<DateTime<Utc>>,
+    pub suspended_until: Option<DateTime<Utc>>,
+    pub suspension_reason: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, FromRow)]
+pub struct PlatformStats {
+    pub users: i64,
+    pub active_users: i64,
+    pub suspended_users: i64,
+    pub moderators: i64,
+    pub admins: i64,
+    pub new_users_7d: i64,
+    pub tweets: i64,
+    pub deleted_tweets: i64,
+    pub archived_tweets: i64,
+    pub new_tweets_7d: i64,
+    pub held_tweets: i64,
+    pub abuse_events_7d: i64,
+    pub open_reports: i64,
+    #[sqlx(skip)]
+    pub monthly_active_users: i64,
+}
+
+const MANAGED_USER_COLUMNS: &str = "id, username, display_name, role, verified, followers_count,
+    deactivated_at, suspended_at, suspended_until, suspension_reason, created_at";
+
+async fn list_users(
+    state: web::Data<AppState>,
+    _moderator: ModeratorUser,
+    filter: web::Query<UserFilter>,
+    page: web::Query<PageQuery>,
+) -> ApiResult<HttpResponse> {
+    let limit = page.limit();
+    let scope = "admin_users";
+    let cursor = page.cursor(&state.cursors, scope).map_err(ApiError::BadRequest)?;
+    let prefix = filter
+        .q
+        .as_deref()
+        .map(|q| q.trim().trim_start_matches('@').to_lowercase())
+        .filter(|q| !q.is_empty())
+        .map(|q| like_prefix(&q));
+
+    let mut users = sqlx::query_as::<_, ManagedUser>(&format!(
+        "SELECT {} FROM users
+         WHERE ($1::text IS NULL OR lower(username) LIKE $1)
+           AND ($2::text IS NULL OR role = $2)
+           AND ($3::boolean IS NULL OR (suspended_at IS NOT NULL) = $3)
+           AND ($4::timestamptz IS NULL OR (created_at, id) < ($4, $5))
+         ORDER BY created_at DESC, id DESC
+         LIMIT $6",
+        MANAGED_USER_COLUMNS
+    ))
+    .bind(prefix)
+    .bind(filter.role.map(|role| role.as_str()))
+    .bind(filter.suspended)
+    .bind(cursor.map(|c| c.created_at))
+    .bind(cursor.map(|c| c.id))
+    .bind(limit + 1)
+    .fetch_all(&state.db)
+    .await?;
+
+    let next_cursor = pagination::next_cursor(&mut users, limit, &state.cursors, scope, |u| Cursor {
+        created_at: u.created_at,
+        id: u.id,
+    });
+
+    Ok(HttpResponse::Ok().json(ApiResponse {
+        success: true,
+        data: Some(users),
+        message: None,
+        next_cursor,
+    }))
+}
+
+// Locks the target account and checks the acting staff member outranks it.
+async fn lock_managed_user(conn: &mut sqlx::PgConnection, staff_id: Uuid, target_id: Uuid) -> ApiResult<ManagedUser> {
+    if staff_id == target_id {
+        return Err(ApiError::Forbidden("You can't change your own account here".to_string()));
+    }
+
+    let staff_role = sqlx::query_scalar::<_, String>("SELECT role FROM users WHERE id = $1")
+        .bind(staff_id)
+        .fetch_one(&mut *conn)
+        .await?;
+    let target = sqlx::query_as::<_, ManagedUser>(&format!("SELECT {} FROM users WHERE id = $1 FOR UPDATE", MANAGED_USER_COLUMNS))
+        .bind(target_id)
+        .fetch_optional(&mut *conn)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("User not found".to_string()))?;
+
+    if !outranks(&staff_role, &target.role) {
+        return Err(ApiError::Forbidden("You can only manage accounts with a lower role".to_string()));
+    }
+    Ok(target)
+}
+
+fn outranks(staff_role: &str, target_role: &str) -> bool {
+    match (staff_role.parse::<Role>(), target_role.parse::<Role>()) {
+        (Ok(staff), Ok(target)) => staff > target,
+        _ => false,
+    }
+}
+
+async fn suspend_user(
+    state: web::Data<AppState>,
+    ModeratorUser(moderator_id): ModeratorUser,
+    user_id: web::Path<Uuid>,
+    req: web::Json<SuspendRequest>,
+) -> ApiResult<HttpResponse> {
+    let mut tx = state.db.begin().await?;
+    let user = suspend(&mut tx, moderator_id, user_id.into_inner(), req.reason.as_deref(), req.until).await?;
+    tx.commit().await?;
+
+    Ok(HttpResponse::Ok().json(ApiResponse {
+        success: true,
+        data: Some(user),
+        message: None,
+        next_cursor: None,
+    }))
+}
+
+// Suspends an account on behalf of a staff member who outranks it. Also used
+// when a moderator acts on a report.
+pub async fn suspend(
+    conn: &mut sqlx::PgConnection,
+    staff_id: Uuid,
+    user_id: Uuid,
+    reason: Option<&str>,
+    until: Option<DateTime<Utc>>,
+) -> ApiResult<ManagedUser> {
+    let reason = reason.map(str::trim).filter(|r| !r.is_empty());
+    if until.is_some_and(|until| until <= Utc::now()) {
+        return Err(ApiError::BadRequest("until must be in the future".to_string()));
+    }
+
+    let target = lock_managed_user(&mut *conn, staff_id, user_id).await?;
+    if target.suspended_at.is_some() {
+        return Err(ApiError::Conflict("User is already suspended".to_string()));
+    }
+
+    let user = sqlx::query_as::<_, ManagedUser>(&format!(
+        "UPDATE users
+         SET suspended_at = NOW(), suspended_until = $3, suspension_reason = $2,
+             deactivated_before_suspension = deactivated_at,
+             deactivated_at = COALESCE(deactivated_at, NOW())
+         WHERE id = $1
+         RETURNING {}",
+        MANAGED_USER_COLUMNS
+    ))
+    .bind(user_id)
+    .bind(reason)
+    .bind(until)
+    .fetch_one(&mut *conn)
+    .await?;
+
+    log::info!("{} suspended user {}", staff_id, user_id);
+    Ok(user)
+}
+
+// An account the user had deactivated themselves stays deactivated
+const LIFT_SUSPENSION: &str = "suspended_at = NULL, suspended_until = NULL, suspension_reason = NULL,
+     deactivated_at = deactivated_before_suspension, deactivated_before_suspension = NULL";
+
+// Run by the scheduler. Returns how many accounts were reinstated.
+pub async fn lift_expired_suspensions(db: &PgPool) -> Result<u64, sqlx::Error> {
+    let lifted = sqlx::query(&format!(
+        "UPDATE users SET {} WHERE suspended_at IS NOT NULL AND suspended_until <= NOW()",
+        LIFT_SUSPENSION
+    ))
+    .execute(db)
+    .await?
+    .rows_affected();
+
+    if lifted > 0 {
+        log::info!("Lifted {} expired suspensions", lifted);
+    }
+    Ok(lifted)
+}
+
+// Answers 403 to any write from a suspended account, except signing in and
+// out. Reads still work, so the account can see its own status.
+pub async fn suspension_guard<B: MessageBody + 'static>(
+    req: ServiceRequest,
+    next: Next<B>,
+) -> Result<ServiceResponse<EitherBody<B>>, Error> {
+    let is_write = !matches!(*req.method(), Method::GET | Method::HEAD | Method::OPTIONS);
+    let exempt = matches!(req.path(), "/api/auth/login" | "/api/auth/refresh" | "/api/auth/logout");
+    let state = req.app_data::<web::Data<AppState>>().cloned();
+
+    let (Some(state), true, false) = (state, is_write, exempt) else {
+        return next.call(req).await.map(ServiceResponse::map_into_left_body);
+    };
+    // Requests without a valid token are left to the handler's own 401
+    let Ok(user_id) = auth::request_user_id(req.request()) else {
+        return next.call(req).await.map(ServiceResponse::map_into_left_body);
+    };
+
+    let suspended = sqlx::query_scalar::<_, bool>(
+        "SELECT EXISTS(
+             SELECT 1 FROM users
+             WHERE id = $1 AND suspended_at IS NOT NULL AND (suspended_until IS NULL OR suspended_until > NOW())
+         )"
+    )
+    .bind(user_id)
+    .fetch_one(&state.db)
+    .await
+    .map_err(ApiError::from)?;
+
+    if suspended {
+        let error = ApiError::Forbidden("This account is suspended".to_string());
+        return Ok(req.error_response(error).map_into_right_body());
+    }
+
+    next.call(req).await.map(ServiceResponse::map_into_left_body)
+}
+
+async fn unsuspend_user(
+    state: web::Data<AppState>,
+    ModeratorUser(moderator_id): ModeratorUser,
+    user_id: web::Path<Uuid>,
+) -> ApiResult<HttpResponse> {
+    let user_id = user_id.into_inner();
+    let mut tx = state.db.begin().await?;
+
+    let target = lock_managed_user(&mut tx, moderator_id, user_id).await?;
+    if target.suspended_at.is_none() {
+        return Err(ApiError::Conflict("User is not suspended".to_string()));
+    }
+
+    let user = sqlx::query_as::<_, ManagedUser>(&format!(
+        "UPDATE users SET {}
+         WHERE id = $1
+         RETURNING {}",
+        LIFT_SUSPENSION, MANAGED_USER_COLUMNS
+    ))
+    .bind(user_id)
+    .fetch_one(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+    log::info!("{} unsuspended user {}", moderator_id, user_id);
+
+    Ok(HttpResponse::Ok().json(ApiResponse {
+        success: true,
+        data: Some(user),
+        message: None,
+        next_cursor: None,
+    }))
+}
+
+async fn set_role(
+    state: web::Data<AppState>,
+    AdminUser(admin_id): AdminUser,
+    user_id: web::Path<Uuid>,
+    req: web::Json<RoleRequest>,
+) -> ApiResult<HttpResponse> {
+    let user_id = user_id.into_inner();
+    let mut tx = state.db.begin().await?;
+
+    lock_managed_user(&mut tx, admin_id, user_id).await?;
+    let user = sqlx::query_as::<_, ManagedUser>(&format!("UPDATE users SET role = $2 WHERE id = $1 RETURNING {}", MANAGED_USER_COLUMNS))
+        .bind(user_id)
+        .bind(req.role.as_str())
+        .fetch_one(&mut *tx)
+        .await?;
+
+    tx.commit().await?;
+    log::info!("{} set the role of user {} to {}", admin_id, user_id, req.role.as_str());
+
+    Ok(HttpResponse::Ok().json(ApiResponse {
+        success: true,
+        data: Some(user),
+        message: None,
+        next_cursor: None,
+    }))
+}
+
+async fn set_verified(
+    state: web::Data<AppState>,
+    _admin: AdminUser,
+    user_id: web::Path<Uuid>,
+    req: web::Json<VerifiedRequest>,
+) -> ApiResult<HttpResponse> {
+    let user = sqlx::query_as::<_, ManagedUser>(&format!("UPDATE users SET verified = $2 WHERE id = $1 RETURNING {}", MANAGED_USER_COLUMNS))
+        .bind(user_id.into_inner())
+        .bind(req.verified)
+        .fetch_optional(&state.db)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("User not found".to_string()))?;
+
+    Ok(HttpResponse::Ok().json(ApiResponse {
+        success: true,
+        data: Some(user),
+        message: None,
+        next_cursor: None,
+    }))
+}
+
+async fn delete_tweet(
+    state: web::Data<AppState>,
+    ModeratorUser(moderator_id): ModeratorUser,
+    tweet_id: web::Path<Uuid>,
+) -> ApiResult<HttpResponse> {
+    let tweet_id = tweet_id.into_inner();
+    soft_delete_tweet(&state.db, None, tweet_id).await?;
+    log::info!("{} deleted tweet {}", moderator_id, tweet_id);
+
+    Ok(HttpResponse::Ok().json(ApiResponse {
+        success: true,
+        data: Some("Tweet deleted successfully"),
+        message: None,
+        next_cursor: None,
+    }))
+}
+
+async fn get_stats(state: web::Data<AppState>, _moderator: ModeratorUser) -> ApiResult<HttpResponse> {
+    let mut stats = sqlx::query_as::<_, PlatformStats>(
+        "SELECT
+             (SELECT COUNT(*) FROM users) AS users,
+             (SELECT COUNT(*) FROM users WHERE deactivated_at IS NULL) AS active_users,
+             (SELECT COUNT(*) FROM users WHERE suspended_at IS NOT NULL) AS suspended_users,
+             (SELECT COUNT(*) FROM users WHERE role = 'moderator') AS moderators,
+             (SELECT COUNT(*) FROM users WHERE role = 'admin') AS admins,
+             (SELECT COUNT(*) FROM users WHERE created_at > NOW() - INTERVAL '7 days') AS new_users_7d,
+             (SELECT COUNT(*) FROM tweets WHERE deleted_at IS NULL) AS tweets,
+             (SELECT COUNT(*) FROM tweets WHERE deleted_at IS NOT NULL) AS deleted_tweets,
+             (SELECT COUNT(*) FROM archived_tweets) AS archived_tweets,
+             (SELECT COUNT(*) FROM tweets WHERE created_at > NOW() - INTERVAL '7 days') AS new_tweets_7d,
+             (SELECT COUNT(*) FROM held_tweets) AS held_tweets,
+             (SELECT COUNT(*) FROM abuse_events WHERE created_at > NOW() - INTERVAL '7 days') AS abuse_events_7d,
+             (SELECT COUNT(*) FROM reports WHERE status = 'open') AS open_reports"
+    )
+    .fetch_one(&state.db)
+    .await?;
+    stats.monthly_active_users = analytics::instance_stats(&state.db).await?.monthly_active_users;
+
+    Ok(HttpResponse::Ok().json(ApiResponse {
+        success: true,
+        data: Some(stats),
+        message: None,
+        next_cursor: None,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn roles_are_ordered() {
+        assert!(Role::Admin > Role::Moderator);
+        assert!(Role::Moderator > Role::User);
+    }
+
+    #[test]
+    fn staff_only_manage_lower_roles() {
+        assert!(outranks("admin", "moderator"));
+        assert!(outranks("admin", "user"));
+        assert!(outranks("moderator", "user"));
+        assert!(!outranks("moderator", "moderator"));
+        assert!(!outranks("moderator", "admin"));
+        assert!(!outranks("admin", "admin"));
+        assert!(!outranks("user", "user"));
+        assert!(!outranks("admin", "unknown"));
+    }
+}
@@ -0,0 +1,723 @@
+use actix_web::body::MessageBody;
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::http::header::HeaderName;
+use actix_web::middleware::Next;
+use actix_web::{web, Error};
+use reqwest::Url;
+use chrono::{DateTime, NaiveDate, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, PgPool};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+use uuid::Uuid;
+
+use crate::AppState;
+use crate::config;
+
+const ACTIVE_USER_DAYS: i32 = 30;
+const TWEETS_PER_DAY_DAYS: i32 = 30;
+
+// Coarse, anonymous usage counters. Each request is reduced to its route
+// pattern (`/api/tweets/{id}`, never the concrete path), method, status and
+// the visitor's country as reported by the CDN, bucketed by hour. Nothing
+// that identifies a user or a client is kept. Counts accumulate in memory and
+// the scheduler folds them into `usage_rollups`.
+//
+// Short link clicks are counted the same way per tweet, keeping only the host
+// of the referring page (`direct` when there is none), and so are impressions:
+// each time a tweet is served on a timeline, a profile or its own page.
+// Clients add what the server can't see, in the same hourly rollups: views
+// (the tweet was actually on screen) and clicks through to its author's profile.
+#[derive(Clone)]
+pub struct Analytics {
+    // Header carrying an ISO country code, e.g. CF-IPCountry behind
+    // Cloudflare. Without one every request counts as `XX`.
+    country_header: Option<HeaderName>,
+    pending: Arc<Mutex<HashMap<RollupKey, RollupCounts>>>,
+    referrals: Arc<Mutex<HashMap<ReferralKey, i64>>>,
+    impressions: Arc<Mutex<HashMap<ImpressionKey, ImpressionCounts>>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct RollupKey {
+    bucket: DateTime<Utc>,
+    route: String,
+    method: String,
+    status: i16,
+    country: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct ReferralKey {
+    bucket: DateTime<Utc>,
+    tweet_id: Uuid,
+    source: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct ImpressionKey {
+    bucket: DateTime<Utc>,
+    tweet_id: Uuid,
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+struct ImpressionCounts {
+    served: i64,
+    views: i64,
+    profile_clicks: i64,
+}
+
+impl ImpressionCounts {
+    fn add(&mut self, other: ImpressionCounts) {
+        self.served += other.served;
+        self.views += other.views;
+        self.profile_clicks += other.profile_clicks;
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+struct RollupCounts {
+    requests: i64,
+    total_ms: i64,
+}
+
+#[derive(Debug, Serialize, FromRow)]
+pub struct DailyCount {
+    pub day: NaiveDate,
+    pub count: i64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct InstanceStats {
+    pub monthly_active_users: i64,
+    pub tweets_per_day: Vec<DailyCount>,
+}
+
+#[derive(Debug, Serialize, FromRow)]
+pub struct ReferralCount {
+    pub day: NaiveDate,
+    pub source: String,
+    pub clicks: i64,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Interval {
+    Hour,
+    #[default]
+    Day,
+}
+
+impl Interval {
+    fn as_str(self) -> &'static str {
+        match self {
+            Interval::Hour => "hour",
+            Interval::Day => "day",
+        }
+    }
+
+    fn step(self) -> chrono::Duration {
+        match self {
+            Interval::Hour => chrono::Duration::hours(1),
+            Interval::Day => chrono::Duration::days(1),
+        }
+    }
+
+    // Start of the hour or (UTC) day `at` falls in
+    fn truncate(self, at: DateTime<Utc>) -> DateTime<Utc> {
+        match self {
+            Interval::Hour => hour_bucket(at),
+            Interval::Day => at.date_naive().and_hms_opt(0, 0, 0).unwrap().and_utc(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, FromRow)]
+pub struct EngagementCounts {
+    pub impressions: i64,
+    pub views: i64,
+    pub profile_clicks: i64,
+    pub likes: i64,
+    pub retweets: i64,
+    pub replies: i64,
+}
+
+impl EngagementCounts {
+    fn add(&mut self, other: &EngagementCounts) {
+        self.impressions += other.impressions;
+        self.views += other.views;
+        self.profile_clicks += other.profile_clicks;
+        self.likes += other.likes;
+        self.retweets += other.retweets;
+        self.replies += other.replies;
+    }
+}
+
+#[derive(Debug, Serialize, FromRow)]
+pub struct EngagementPoint {
+    pub at: DateTime<Utc>,
+    #[serde(flatten)]
+    #[sqlx(flatten)]
+    pub counts: EngagementCounts,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TweetEngagement {
+    pub interval: Interval,
+    pub since: DateTime<Utc>,
+    pub totals: EngagementCounts,
+    pub series: Vec<EngagementPoint>,
+}
+
+// How far back an analytics export reaches
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+pub enum ExportRange {
+    #[serde(rename = "7d")]
+    Week,
+    #[default]
+    #[serde(rename = "30d")]
+    Month,
+    #[serde(rename = "90d")]
+    Quarter,
+}
+
+impl ExportRange {
+    pub fn days(self) -> i64 {
+        match self {
+            ExportRange::Week => 7,
+            ExportRange::Month => 30,
+            ExportRange::Quarter => 90,
+        }
+    }
+}
+
+#[derive(Debug, FromRow)]
+pub struct TweetTotals {
+    pub tweet_id: Uuid,
+    pub created_at: DateTime<Utc>,
+    #[sqlx(flatten)]
+    pub counts: EngagementCounts,
+}
+
+impl TweetTotals {
+    pub const CSV_HEADER: &'static str = "tweet_id,created_at,impressions,views,profile_clicks,likes,retweets,replies\n";
+
+    pub fn csv_row(&self) -> String {
+        let c = &self.counts;
+        format!(
+            "{},{},{},{},{},{},{},{}\n",
+            self.tweet_id,
+            self.created_at.to_rfc3339(),
+            c.impressions,
+            c.views,
+            c.profile_clicks,
+            c.likes,
+            c.retweets,
+            c.replies
+        )
+    }
+}
+
+#[derive(Debug, Serialize, FromRow)]
+pub struct RouteUsage {
+    pub route: String,
+    pub method: String,
+    pub status: i16,
+    pub country: String,
+    pub requests: i64,
+    pub avg_ms: i64,
+}
+
+impl Analytics {
+    pub fn from_env() -> Result<Self, String> {
+        let country_header = match config::var("ANALYTICS_COUNTRY_HEADER") {
+            Some(name) => Some(HeaderName::try_from(name).map_err(|_| "Invalid ANALYTICS_COUNTRY_HEADER".to_string())?),
+            None => None,
+        };
+
+        Ok(Analytics {
+            country_header,
+            pending: Arc::new(Mutex::new(HashMap::new())),
+            referrals: Arc::new(Mutex::new(HashMap::new())),
+            impressions: Arc::new(Mutex::new(HashMap::new())),
+        })
+    }
+
+    fn count(&self, key: RollupKey, elapsed_ms: i64) {
+        let mut pending = self.pending.lock().unwrap();
+        let counts = pending.entry(key).or_default();
+        counts.requests += 1;
+        counts.total_ms += elapsed_ms;
+    }
+
+    // A click on a tweet's short link; `referer` is the raw Referer header.
+    pub fn count_referral(&self, tweet_id: Uuid, referer: Option<&str>) {
+        let key = ReferralKey {
+            bucket: hour_bucket(Utc::now()),
+            tweet_id,
+            source: referer.and_then(referral_source).unwrap_or_else(|| "direct".to_string()),
+        };
+        *self.referrals.lock().unwrap().entry(key).or_default() += 1;
+    }
+
+    // Tweets served to a reader, one impression each.
+    pub fn count_impressions(&self, tweet_ids: impl IntoIterator<Item = Uuid>) {
+        self.count_tweets(tweet_ids, ImpressionCounts { served: 1, ..Default::default() });
+    }
+
+    // Tweets a client reported as seen, one view each.
+    pub fn count_views(&self, tweet_ids: impl IntoIterator<Item = Uuid>) {
+        self.count_tweets(tweet_ids, ImpressionCounts { views: 1, ..Default::default() });
+    }
+
+    // Tweets whose author's profile a client reported being opened from.
+    pub fn count_profile_clicks(&self, tweet_ids: impl IntoIterator<Item = Uuid>) {
+        self.count_tweets(tweet_ids, ImpressionCounts { profile_clicks: 1, ..Default::default() });
+    }
+
+    fn count_tweets(&self, tweet_ids: impl IntoIterator<Item = Uuid>, counts: ImpressionCounts) {
+        let bucket = hour_bucket(Utc::now());
+        let mut impressions = self.impressions.lock().unwrap();
+        for tweet_id in tweet_ids {
+            impressions.entry(ImpressionKey { bucket, tweet_id }).or_default().add(counts);
+        }
+    }
+
+    // Run by the scheduler: writes the counts gathered since the last flush.
+    // On failure they're kept for the next attempt.
+    pub async fn flush(&self, db: &PgPool) -> Result<(), sqlx::Error> {
+        self.flush_rollups(db).await?;
+        self.flush_referrals(db).await?;
+        self.flush_impressions(db).await
+    }
+
+    async fn flush_rollups(&self, db: &PgPool) -> Result<(), sqlx::Error> {
+        let pending = std::mem::take(&mut *self.pending.lock().unwrap());
+        if pending.is_empty() {
+            return Ok(());
+        }
+
+        let mut buckets = Vec::with_capacity(pending.len());
+        let mut routes = Vec::with_capacity(pending.len());
+        let mut methods = Vec::with_capacity(pending.len());
+        let mut statuses = Vec::with_capacity(pending.len());
+        let mut countries = Vec::with_capacity(pending.len());
+        let mut requests = Vec::with_capacity(pending.len());
+        let mut total_ms = Vec::with_capacity(pending.len());
+        for (key, counts) in &pending {
+            buckets.push(key.bucket);
+            routes.push(key.route.clone());
+            methods.push(key.method.clone());
+            statuses.push(key.status);
+            countries.push(key.country.clone());
+            requests.push(counts.requests);
+            total_ms.push(counts.total_ms);
+        }
+
+        let result = sqlx::query(
+            "INSERT INTO usage_rollups (bucket, route, method, status, country, requests, total_ms)
+             SELECT * FROM UNNEST($1::timestamptz[], $2::text[], $3::text[], $4::smallint[], $5::text[], $6::bigint[], $7::bigint[])
+             ON CONFLICT (bucket, route, method, status, country) DO UPDATE
+             SET requests = usage_rollups.requests + EXCLUDED.requests,
+                 total_ms = usage_rollups.total_ms + EXCLUDED.total_ms"
+        )
+        .bind(&buckets)
+        .bind(&routes)
+        .bind(&methods)
+        .bind(&statuses)
+        .bind(&countries)
+        .bind(&requests)
+        .bind(&total_ms)
+        .execute(db)
+        .await;
+
+        if let Err(e) = result {
+            let mut current = self.pending.lock().unwrap();
+            for (key, counts) in pending {
+                let merged = current.entry(key).or_default();
+                merged.requests += counts.requests;
+                merged.total_ms += counts.total_ms;
+            }
+            return Err(e);
+        }
+
+        Ok(())
+    }
+
+    async fn flush_referrals(&self, db: &PgPool) -> Result<(), sqlx::Error> {
+        let pending = std::mem::take(&mut *self.referrals.lock().unwrap());
+        if pending.is_empty() {
+            return Ok(());
+        }
+
+        let mut buckets = Vec::with_capacity(pending.len());
+        let mut tweet_ids = Vec::with_capacity(pending.len());
+        let mut sources = Vec::with_capacity(pending.len());
+        let mut clicks = Vec::with_capacity(pending.len());
+        for (key, count) in &pending {
+            buckets.push(key.bucket);
+            tweet_ids.push(key.tweet_id);
+            sources.push(key.source.clone());
+            clicks.push(*count);
+        }
+
+        // Clicks on tweets purged or archived since are dropped rather than failing
+        // the batch
+        let result = sqlx::query(
+            "INSERT INTO short_link_referrals (bucket, tweet_id, source, clicks)
+             SELECT r.bucket, r.tweet_id, r.source, r.clicks
+             FROM UNNEST($1::timestamptz[], $2::uuid[], $3::text[], $4::bigint[]) AS r(bucket, tweet_id, source, clicks)
+             WHERE EXISTS (SELECT 1 FROM tweets t WHERE t.id = r.tweet_id)
+             ON CONFLICT (tweet_id, bucket, source) DO UPDATE
+             SET clicks = short_link_referrals.clicks + EXCLUDED.clicks"
+        )
+        .bind(&buckets)
+        .bind(&tweet_ids)
+        .bind(&sources)
+        .bind(&clicks)
+        .execute(db)
+        .await;
+
+        if let Err(e) = result {
+            let mut current = self.referrals.lock().unwrap();
+            for (key, count) in pending {
+                *current.entry(key).or_default() += count;
+            }
+            return Err(e);
+        }
+
+        Ok(())
+    }
+
+    async fn flush_impressions(&self, db: &PgPool) -> Result<(), sqlx::Error> {
+        let pending = std::mem::take(&mut *self.impressions.lock().unwrap());
+        if pending.is_empty() {
+            return Ok(());
+        }
+
+        let mut buckets = Vec::with_capacity(pending.len());
+        let mut tweet_ids = Vec::with_capacity(pending.len());
+        let mut impressions = Vec::with_capacity(pending.len());
+        let mut views = Vec::with_capacity(pending.len());
+        let mut profile_clicks = Vec::with_capacity(pending.len());
+        for (key, counts) in &pending {
+            buckets.push(key.bucket);
+            tweet_ids.push(key.tweet_id);
+            impressions.push(counts.served);
+            views.push(counts.views);
+            profile_clicks.push(counts.profile_clicks);
+        }
+
+        let result = sqlx::query(
+            "INSERT INTO tweet_impressions (bucket, tweet_id, impressions, views, profile_clicks)
+             SELECT i.bucket, i.tweet_id, i.impressions, i.views, i.profile_clicks
+             FROM UNNEST($1::timestamptz[], $2::uuid[], $3::bigint[], $4::bigint[], $5::bigint[])
+                  AS i(bucket, tweet_id, impressions, views, profile_clicks)
+             WHERE EXISTS (SELECT 1 FROM tweets t WHERE t.id = i.tweet_id)
+             ON CONFLICT (tweet_id, bucket) DO UPDATE
+             SET impressions = tweet_impressions.impressions + EXCLUDED.impressions,
+                 views = tweet_impressions.views + EXCLUDED.views,
+                 profile_clicks = tweet_impressions.profile_clicks + EXCLUDED.profile_clicks"
+        )
+        .bind(&buckets)
+        .bind(&tweet_ids)
+        .bind(&impressions)
+        .bind(&views)
+        .bind(&profile_clicks)
+        .execute(db)
+        .await;
+
+        if let Err(e) = result {
+            let mut current = self.impressions.lock().unwrap();
+            for (key, counts) in pending {
+                current.entry(key).or_default().add(counts);
+            }
+            return Err(e);
+        }
+
+        Ok(())
+    }
+}
+
+// Public instance numbers. Active users are accounts that signed in or
+// refreshed a session in the last 30 days; only the total leaves the query.
+pub async fn instance_stats(db: &PgPool) -> Result<InstanceStats, sqlx::Error> {
+    let monthly_active_users = sqlx::query_scalar::<_, i64>(
+        "SELECT COUNT(DISTINCT user_id) FROM refresh_tokens WHERE created_at > NOW() - make_interval(days => $1)"
+    )
+    .bind(ACTIVE_USER_DAYS)
+    .fetch_one(db)
+    .await?;
+
+    let tweets_per_day = sqlx::query_as::<_, DailyCount>(
+        "SELECT (created_at AT TIME ZONE 'UTC')::date AS day, COUNT(*) AS count
+         FROM tweets
+         WHERE created_at > date_trunc('day', NOW() AT TIME ZONE 'UTC') AT TIME ZONE 'UTC' - make_interval(days => $1 - 1)
+         GROUP BY day
+         ORDER BY day"
+    )
+    .bind(TWEETS_PER_DAY_DAYS)
+    .fetch_all(db)
+    .await?;
+
+    Ok(InstanceStats {
+        monthly_active_users,
+        tweets_per_day,
+    })
+}
+
+// Per-route totals over the last `days` days, busiest first.
+pub async fn route_usage(db: &PgPool, days: i32) -> Result<Vec<RouteUsage>, sqlx::Error> {
+    sqlx::query_as::<_, RouteUsage>(
+        "SELECT route, method, status, country, SUM(requests)::bigint AS requests,
+                (SUM(total_ms) / GREATEST(SUM(requests), 1))::bigint AS avg_ms
+         FROM usage_rollups
+         WHERE bucket > NOW() - make_interval(days => $1)
+         GROUP BY route, method, status, country
+         ORDER BY requests DESC"
+    )
+    .bind(days)
+    .fetch_all(db)
+    .await
+}
+
+// Daily short link clicks for one tweet over the last `days` days.
+pub async fn tweet_referrals(db: &PgPool, tweet_id: Uuid, days: i32) -> Result<Vec<ReferralCount>, sqlx::Error> {
+    sqlx::query_as::<_, ReferralCount>(
+        "SELECT (bucket AT TIME ZONE 'UTC')::date AS day, source, SUM(clicks)::bigint AS clicks
+         FROM short_link_referrals
+         WHERE tweet_id = $1 AND bucket > NOW() - make_interval(days => $2)
+         GROUP BY day, source
+         ORDER BY day, clicks DESC, source"
+    )
+    .bind(tweet_id)
+    .bind(days)
+    .fetch_all(db)
+    .await
+}
+
+// One tweet's impressions, views, profile clicks, likes, retweets and replies
+// per hour or day since the start of the interval `days` days ago, with every
+// interval in the series, counted or not. Likes and retweets since undone
+// aren't counted.
+pub async fn tweet_engagement(
+    db: &PgPool,
+    tweet_id: Uuid,
+    days: i32,
+    interval: Interval,
+) -> Result<TweetEngagement, sqlx::Error> {
+    let now = Utc::now();
+    let since = interval.truncate(now - chrono::Duration::days(days as i64));
+
+    let rows = sqlx::query_as::<_, EngagementPoint>(
+        "SELECT date_trunc($3, e.at AT TIME ZONE 'UTC') AT TIME ZONE 'UTC' AS at,
+                SUM(e.impressions)::bigint AS impressions, SUM(e.views)::bigint AS views,
+                SUM(e.profile_clicks)::bigint AS profile_clicks, SUM(e.likes)::bigint AS likes,
+                SUM(e.retweets)::bigint AS retweets, SUM(e.replies)::bigint AS replies
+         FROM (
+             SELECT bucket AS at, impressions, views, profile_clicks, 0 AS likes, 0 AS retweets, 0 AS replies
+             FROM tweet_impressions WHERE tweet_id = $1 AND bucket >= $2
+             UNION ALL
+             SELECT created_at, 0, 0, 0, 1, 0, 0 FROM likes WHERE tweet_id = $1 AND created_at >= $2
+             UNION ALL
+             SELECT created_at, 0, 0, 0, 0, 1, 0 FROM retweets WHERE tweet_id = $1 AND created_at >= $2
+             UNION ALL
+             SELECT created_at, 0, 0, 0, 0, 0, 1 FROM tweets
+             WHERE parent_tweet_id = $1 AND deleted_at IS NULL AND created_at >= $2
+         ) e
+         GROUP BY 1"
+    )
+    .bind(tweet_id)
+    .bind(since)
+    .bind(interval.as_str())
+    .fetch_all(db)
+    .await?;
+
+    let series = fill_series(since, interval.truncate(now), interval, rows);
+    let mut totals = EngagementCounts::default();
+    for point in &series {
+        totals.add(&point.counts);
+    }
+
+    Ok(TweetEngagement {
+        interval,
+        since,
+        totals,
+        series,
+    })
+}
+
+// One page of an author's per-tweet totals since `since`, newest tweet first,
+// for tweets posted or served in that time. `before` is the last tweet of the
+// previous page. Same counting rules as `tweet_engagement`.
+pub async fn author_totals(
+    db: &PgPool,
+    user_id: Uuid,
+    since: DateTime<Utc>,
+    before: Option<(DateTime<Utc>, Uuid)>,
+    limit: i64,
+) -> Result<Vec<TweetTotals>, sqlx::Error> {
+    sqlx::query_as::<_, TweetTotals>(
+        "SELECT t.id AS tweet_id, t.created_at,
+                COALESCE(i.impressions, 0)::bigint AS impressions, COALESCE(i.views, 0)::bigint AS views,
+                COALESCE(i.profile_clicks, 0)::bigint AS profile_clicks,
+                (SELECT COUNT(*) FROM likes l WHERE l.tweet_id = t.id AND l.created_at >= $2) AS likes,
+                (SELECT COUNT(*) FROM retweets r WHERE r.tweet_id = t.id AND r.created_at >= $2) AS retweets,
+                (SELECT COUNT(*) FROM tweets r
+                 WHERE r.parent_tweet_id = t.id AND r.deleted_at IS NULL AND r.created_at >= $2) AS replies
+         FROM tweets t
+         LEFT JOIN LATERAL (
+             SELECT SUM(impressions) AS impressions, SUM(views) AS views, SUM(profile_clicks) AS profile_clicks
+             FROM tweet_impressions WHERE tweet_id = t.id AND bucket >= $2
+         ) i ON TRUE
+         WHERE t.user_id = $1 AND t.deleted_at IS NULL
+           AND (t.created_at >= $2 OR i.impressions IS NOT NULL)
+           AND ($3::timestamptz IS NULL OR (t.created_at, t.id) < ($3, $4))
+         ORDER BY t.created_at DESC, t.id DESC
+         LIMIT $5"
+    )
+    .bind(user_id)
+    .bind(since)
+    .bind(before.map(|(created_at, _)| created_at))
+    .bind(before.map(|(_, id)| id))
+    .bind(limit)
+    .fetch_all(db)
+    .await
+}
+
+// Every interval from `first` to `last`, in order, with the counts of the
+// rows that fall in it.
+fn fill_series(first: DateTime<Utc>, last: DateTime<Utc>, interval: Interval, rows: Vec<EngagementPoint>) -> Vec<EngagementPoint> {
+    let mut counted: BTreeMap<DateTime<Utc>, EngagementCounts> = BTreeMap::new();
+    for row in rows {
+        counted.entry(interval.truncate(row.at)).or_default().add(&row.counts);
+    }
+
+    let mut series = Vec::new();
+    let mut at = first;
+    while at <= last {
+        series.push(EngagementPoint {
+            at,
+            counts: counted.remove(&at).unwrap_or_default(),
+        });
+        at += interval.step();
+    }
+    series
+}
+
+pub async fn record<B: MessageBody + 'static>(
+    req: ServiceRequest,
+    next: Next<B>,
+) -> Result<ServiceResponse<B>, Error> {
+    let Some(state) = req.app_data::<web::Data<AppState>>().cloned() else {
+        return next.call(req).await;
+    };
+
+    let started = Instant::now();
+    let method = req.method().to_string();
+    let country = state
+        .analytics
+        .country_header
+        .as_ref()
+        .and_then(|name| req.headers().get(name))
+        .and_then(|value| value.to_str().ok())
+        .and_then(country_code)
+        .unwrap_or_else(|| "XX".to_string());
+
+    let result = next.call(req).await;
+
+    let (route, status) = match &result {
+        Ok(res) => (res.request().match_pattern(), res.status().as_u16()),
+        Err(e) => (None, e.as_response_error().status_code().as_u16()),
+    };
+    let now = Utc::now();
+    state.analytics.count(
+        RollupKey {
+            bucket: hour_bucket(now),
+            route: route.unwrap_or_else(|| "unmatched".to_string()),
+            method,
+            status: status as i16,
+            country,
+        },
+        started.elapsed().as_millis() as i64,
+    );
+
+    result
+}
+
+fn hour_bucket(at: DateTime<Utc>) -> DateTime<Utc> {
+    let ts = at.timestamp();
+    Utc.timestamp_opt(ts - ts.rem_euclid(3600), 0).unwrap()
+}
+
+// Two ASCII letters, upper-cased; anything else (including CDN placeholders
+// like `T1` for Tor) is unknown.
+fn country_code(value: &str) -> Option<String> {
+    let value = value.trim();
+    (value.len() == 2 && value.chars().all(|c| c.is_ascii_alphabetic())).then(|| value.to_ascii_uppercase())
+}
+
+// Host of the referring page, lower-cased and without `www.`; the path and
+// query can carry personal data and are never kept.
+fn referral_source(referer: &str) -> Option<String> {
+    let url = Url::parse(referer.trim()).ok()?;
+    if !matches!(url.scheme(), "http" | "https") {
+        return None;
+    }
+    let host = url.host_str()?.to_ascii_lowercase();
+    Some(host.strip_prefix("www.").map(str::to_string).unwrap_or(host))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn buckets_truncate_to_the_hour() {
+        let at = Utc.with_ymd_and_hms(2024, 5, 6, 13, 47, 12).unwrap();
+        assert_eq!(hour_bucket(at), Utc.with_ymd_and_hms(2024, 5, 6, 13, 0, 0).unwrap());
+    }
+
+    #[test]
+    fn engagement_series_has_every_interval() {
+        let first = Utc.with_ymd_and_hms(2024, 5, 6, 0, 0, 0).unwrap();
+        let rows = vec![
+            EngagementPoint {
+                at: first + chrono::Duration::hours(30),
+                counts: EngagementCounts { likes: 2, ..Default::default() },
+            },
+            EngagementPoint {
+                at: first,
+                counts: EngagementCounts { impressions: 5, ..Default::default() },
+            },
+        ];
+        let series = fill_series(first, first + chrono::Duration::days(2), Interval::Day, rows);
+
+        assert_eq!(series.iter().map(|p| p.at).collect::<Vec<_>>(), vec![
+            first,
+            first + chrono::Duration::days(1),
+            first + chrono::Duration::days(2),
+        ]);
+        assert_eq!(series[0].counts.impressions, 5);
+        assert_eq!(series[1].counts.likes, 2);
+        assert_eq!(series[2].counts, EngagementCounts::default());
+    }
+
+    #[test]
+    fn only_plain_country_codes_are_kept() {
+        assert_eq!(country_code("de").as_deref(), Some("DE"));
+        assert_eq!(country_code(" US ").as_deref(), Some("US"));
+        assert_eq!(country_code("T1"), None);
+        assert_eq!(country_code("USA"), None);
+        assert_eq!(country_code(""), None);
+    }
+
+    #[test]
+    fn referral_sources_keep_only_the_host() {
+        assert_eq!(referral_source("https://www.Example.com/some/path?q=1").as_deref(), Some("example.com"));
+        assert_eq!(referral_source("http://news.site.org").as_deref(), Some("news.site.org"));
+        assert_eq!(referral_source("android-app://com.example"), None);
+        assert_eq!(referral_source("garbage"), None);
+    }
+}
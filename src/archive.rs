@@ -0,0 +1,122 @@
+use sqlx::PgPool;
+
+use crate::config;
+
+// Cold storage for old tweets. Tweets older than `after_years` are moved from
+// `tweets` to `archived_tweets` a batch per scheduler tick, keeping the hot
+// table small; the tweet detail endpoint reads through to the archive so
+// permalinks keep working. Unset TWEET_ARCHIVE_AFTER_YEARS to disable.
+//
+// A tweet is only archived once no live or held tweet replies to or quotes
+// it, so threads are archived leaf first and live tweets never lose their
+// parent.
+// Archiving drops the tweet's likes, retweets, mentions, hashtags and
+// notifications (they cascade with the row); the counters are kept.
+//
+// Deleted tweets are never archived. They stay in `tweets`, hidden, until an
+// admin purges those deleted more than TWEET_PURGE_AFTER_DAYS ago.
+#[derive(Debug, Clone)]
+pub struct ArchivePolicy {
+    after_years: Option<i32>,
+    batch_size: i64,
+    purge_after_days: i32,
+}
+
+impl ArchivePolicy {
+    pub fn from_env() -> Result<Self, String> {
+        let purge_after_days = config::parse_var("TWEET_PURGE_AFTER_DAYS", 30)?;
+        if purge_after_days < 0 {
+            return Err("TWEET_PURGE_AFTER_DAYS must not be negative".to_string());
+        }
+
+        Ok(ArchivePolicy {
+            // Unset or 0 leaves tweets where they are
+            after_years: Some(config::parse_var("TWEET_ARCHIVE_AFTER_YEARS", 0)?).filter(|years| *years > 0),
+            batch_size: config::positive_var("TWEET_ARCHIVE_BATCH_SIZE", 500)?,
+            purge_after_days,
+        })
+    }
+
+    // Run by the scheduler. Returns how many tweets were archived.
+    pub async fn tick(&self, db: &PgPool) -> Result<u64, sqlx::Error> {
+        let Some(after_years) = self.after_years else {
+            return Ok(0);
+        };
+
+        let archived = sqlx::query(
+            "WITH moved AS (
+                 DELETE FROM tweets
+                 WHERE id IN (
+                     SELECT t.id FROM tweets t
+                     WHERE t.created_at < NOW() - make_interval(years => $1)
+                       AND t.deleted_at IS NULL
+                       AND NOT EXISTS (
+                           SELECT 1 FROM tweets c WHERE c.parent_tweet_id = t.id OR c.quoted_tweet_id = t.id
+                       )
+                       AND NOT EXISTS (
+                           SELECT 1 FROM held_tweets h WHERE h.parent_tweet_id = t.id OR h.quoted_tweet_id = t.id
+                       )
+                     ORDER BY t.created_at
+                     LIMIT $2
+                     FOR UPDATE SKIP LOCKED
+                 )
+                 RETURNING id, user_id, parent_tweet_id, quoted_tweet_id, content, image_url,
+                           likes_count, retweets_count, replies_count, visibility, created_at
+             )
+             INSERT INTO archived_tweets (id, user_id, parent_tweet_id, quoted_tweet_id, content, image_url,
+                                          likes_count, retweets_count, replies_count, visibility, created_at)
+             SELECT * FROM moved"
+        )
+        .bind(after_years)
+        .bind(self.batch_size)
+        .execute(db)
+        .await?
+        .rows_affected();
+
+        if archived > 0 {
+            log::info!("Archived {} tweets older than {} years", archived, after_years);
+        }
+
+        Ok(archived)
+    }
+
+    // Hard-deletes soft-deleted tweets, batch by batch. Like archiving it goes
+    // leaf first: a deleted tweet with live replies would take them with it
+    // (replies cascade), so it waits until they're gone too. Returns how many
+    // tweets were purged.
+    pub async fn purge_deleted(&self, db: &PgPool) -> Result<u64, sqlx::Error> {
+        let mut purged = 0;
+
+        loop {
+            let batch = sqlx::query(
+                "DELETE FROM tweets
+                 WHERE id IN (
+                     SELECT t.id FROM tweets t
+                     WHERE t.deleted_at < NOW() - make_interval(days => $1)
+                       AND NOT EXISTS (SELECT 1 FROM tweets c WHERE c.parent_tweet_id = t.id)
+                       AND NOT EXISTS (
+                           SELECT 1 FROM held_tweets h WHERE h.parent_tweet_id = t.id OR h.quoted_tweet_id = t.id
+                       )
+                     LIMIT $2
+                     FOR UPDATE SKIP LOCKED
+                 )"
+            )
+            .bind(self.purge_after_days)
+            .bind(self.batch_size)
+            .execute(db)
+            .await?
+            .rows_affected();
+
+            purged += batch;
+            if batch == 0 {
+                break;
+            }
+        }
+
+        if purged > 0 {
+            log::info!("Purged {} tweets deleted more than {} days ago", purged, self.purge_after_days);
+        }
+
+        Ok(purged)
+    }
+}
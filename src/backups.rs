@@ -0,0 +1,519 @@
+use actix_web::{web, HttpResponse};
+use chrono::{DateTime, Utc};
+use reqwest::{Method, Url};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sqlx::{FromRow, PgPool};
+use uuid::Uuid;
+
+use crate::auth::AuthenticatedUser;
+use crate::config;
+use crate::crypto::{self, Pii};
+use crate::error::{ApiError, ApiResult};
+use crate::models::ApiResponse;
+use crate::storage::{MediaStorage, S3Storage};
+use crate::{short_links, AppState};
+
+const MAX_ERROR_LENGTH: usize = 500;
+
+// Continuous backup of a user's tweets, and the images attached to them, to an
+// S3-compatible bucket the user owns. Each tweet becomes
+// `{prefix}tweets/{id}.json`; uploaded images are copied to
+// `{prefix}media/{file}` and referenced from the JSON.
+//
+// The scheduler queues every live tweet of a connected user into an outbox
+// and uploads it with retries, so a new connection backfills the history and
+// tweets posted while the bucket was unreachable are caught up later. Items
+// that keep failing are given up on; the user is emailed about it at most
+// once a day, and reconnecting retries them.
+//
+// Endpoints must be https and are reached through the SSRF-safe client, so
+// only the standard port works. The secret key is sealed with the
+// personal-data cipher and never returned.
+#[derive(Debug, Clone)]
+pub struct Backups {
+    max_attempts: i32,
+    batch_size: i64,
+    queue_size: i64,
+}
+
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.route("/api/backup", web::get().to(get_backup))
+        .route("/api/backup", web::put().to(connect_backup))
+        .route("/api/backup", web::delete().to(disconnect_backup))
+        .route("/api/backup/test", web::post().to(test_backup));
+}
+
+#[derive(Debug, Serialize, FromRow)]
+pub struct BackupTarget {
+    pub endpoint: String,
+    pub bucket: String,
+    pub region: String,
+    pub prefix: String,
+    pub access_key_id: String,
+    pub last_backup_at: Option<DateTime<Utc>>,
+    pub last_error: Option<String>,
+    pub last_error_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub pending: i64,
+    pub backed_up: i64,
+    pub failed: i64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ConnectBackupRequest {
+    pub endpoint: String,
+    pub bucket: String,
+    pub region: Option<String>,
+    pub prefix: Option<String>,
+    pub access_key_id: String,
+    pub secret_access_key: String,
+}
+
+#[derive(Debug, FromRow)]
+struct Credentials {
+    endpoint: String,
+    bucket: String,
+    region: String,
+    prefix: String,
+    access_key_id: String,
+    secret_access_key: Pii,
+}
+
+#[derive(Debug, FromRow)]
+struct DueItem {
+    id: Uuid,
+    user_id: Uuid,
+    attempts: i32,
+    #[sqlx(flatten)]
+    credentials: Credentials,
+    tweet_id: Uuid,
+    parent_tweet_id: Option<Uuid>,
+    quoted_tweet_id: Option<Uuid>,
+    content: String,
+    image_url: Option<String>,
+    visibility: String,
+    likes_count: i32,
+    retweets_count: i32,
+    replies_count: i32,
+    created_at: DateTime<Utc>,
+    // Set when the image is one of the user's uploads, which get copied
+    media_url: Option<String>,
+    media_content_type: Option<String>,
+}
+
+const TARGET_COLUMNS: &str = "b.endpoint, b.bucket, b.region, b.prefix, b.access_key_id,
+    b.last_backup_at, b.last_error, b.last_error_at, b.created_at,
+    (SELECT COUNT(*) FROM backup_items i WHERE i.user_id = b.user_id AND i.status = 'pending') AS pending,
+    (SELECT COUNT(*) FROM backup_items i WHERE i.user_id = b.user_id AND i.status = 'backed_up') AS backed_up,
+    (SELECT COUNT(*) FROM backup_items i WHERE i.user_id = b.user_id AND i.status = 'failed') AS failed";
+
+impl Backups {
+    pub fn from_env() -> Result<Self, String> {
+        Ok(Backups {
+            max_attempts: config::parse_var("BACKUP_MAX_ATTEMPTS", 8)?,
+            batch_size: 100,
+            queue_size: 1000,
+        })
+    }
+
+    // Run by the scheduler.
+    pub async fn tick(&self, state: &AppState) -> Result<(), sqlx::Error> {
+        let db = &state.db;
+
+        // Oldest first, a slice per tick, so a large history backfills
+        // gradually
+        sqlx::query(
+            "INSERT INTO backup_items (user_id, tweet_id)
+             SELECT t.user_id, t.id
+             FROM backup_targets b
+             INNER JOIN tweets t ON t.user_id = b.user_id
+             WHERE t.deleted_at IS NULL
+               AND NOT EXISTS (SELECT 1 FROM backup_items i WHERE i.user_id = t.user_id AND i.tweet_id = t.id)
+             ORDER BY t.created_at
+             LIMIT $1
+             ON CONFLICT (user_id, tweet_id) DO NOTHING"
+        )
+        .bind(self.queue_size)
+        .execute(db)
+        .await?;
+
+        // Leases the batch so another instance's tick doesn't upload it too
+        let due = sqlx::query_as::<_, DueItem>(
+            "WITH claimed AS (
+                 UPDATE backup_items SET next_attempt_at = NOW() + INTERVAL '5 minutes'
+                 WHERE id IN (
+                     SELECT id FROM backup_items
+                     WHERE status = 'pending' AND next_attempt_at <= NOW()
+                     ORDER BY next_attempt_at
+                     LIMIT $1
+                     FOR UPDATE SKIP LOCKED
+                 )
+                 RETURNING id, user_id, tweet_id, attempts
+             )
+             SELECT c.id, c.user_id, c.attempts,
+                    b.endpoint, b.bucket, b.region, b.prefix, b.access_key_id, b.secret_access_key,
+                    t.id AS tweet_id, t.parent_tweet_id, t.quoted_tweet_id, t.content, t.image_url, t.visibility,
+                    t.likes_count, t.retweets_count, t.replies_count, t.created_at,
+                    m.url AS media_url, m.content_type AS media_content_type
+             FROM claimed c
+             INNER JOIN backup_targets b ON b.user_id = c.user_id
+             INNER JOIN tweets t ON t.id = c.tweet_id
+             LEFT JOIN LATERAL (
+                 SELECT url, content_type FROM media
+                 WHERE user_id = t.user_id AND url = t.image_url
+                 LIMIT 1
+             ) m ON TRUE
+             WHERE t.deleted_at IS NULL"
+        )
+        .bind(self.batch_size)
+        .fetch_all(db)
+        .await?;
+
+        for item in due {
+            let attempts = item.attempts + 1;
+
+            match upload(state, &item).await {
+                Ok(()) => {
+                    sqlx::query(
+                        "UPDATE backup_items SET status = 'backed_up', attempts = $2, last_error = NULL, backed_up_at = NOW()
+                         WHERE id = $1"
+                    )
+                    .bind(item.id)
+                    .bind(attempts)
+                    .execute(db)
+                    .await?;
+                    sqlx::query("UPDATE backup_targets SET last_backup_at = NOW() WHERE user_id = $1")
+                        .bind(item.user_id)
+                        .execute(db)
+                        .await?;
+                }
+                Err(error) => {
+                    let error = truncate_error(&error);
+                    let gave_up = attempts >= self.max_attempts;
+                    sqlx::query(
+                        "UPDATE backup_items
+                         SET status = $2, attempts = $3, last_error = $4,
+                             next_attempt_at = NOW() + make_interval(mins => power(2, $3)::int)
+                         WHERE id = $1"
+                    )
+                    .bind(item.id)
+                    .bind(if gave_up { "failed" } else { "pending" })
+                    .bind(attempts)
+                    .bind(&error)
+                    .execute(db)
+                    .await?;
+                    record_error(db, item.user_id, &error).await?;
+
+                    if gave_up {
+                        notify_failure(state, item.user_id, &error).await?;
+                    }
+                }
+            }
+        }
+
+        // Items for tweets deleted before they were uploaded
+        sqlx::query(
+            "UPDATE backup_items i SET status = 'skipped'
+             FROM tweets t
+             WHERE t.id = i.tweet_id AND i.status = 'pending' AND t.deleted_at IS NOT NULL"
+        )
+        .execute(db)
+        .await?;
+
+        Ok(())
+    }
+}
+
+fn bucket(state: &AppState, credentials: &Credentials) -> Result<S3Storage, String> {
+    S3Storage::user_bucket(
+        &credentials.endpoint,
+        &credentials.bucket,
+        &credentials.region,
+        &credentials.access_key_id,
+        &credentials.secret_access_key.0,
+        state.http.clone(),
+    )
+}
+
+async fn upload(state: &AppState, item: &DueItem) -> Result<(), String> {
+    let storage = bucket(state, &item.credentials)?;
+    let prefix = &item.credentials.prefix;
+
+    let media_key = match (&item.media_url, &item.media_content_type) {
+        (Some(url), Some(content_type)) => {
+            let file = media_file_name(url).ok_or_else(|| format!("unexpected media URL {}", url))?;
+            let bytes = read_media(state, url, file).await?;
+            let key = format!("{}media/{}", prefix, file);
+            storage.put(&key, bytes, content_type).await.map_err(|e| e.to_string())?;
+            Some(format!("media/{}", file))
+        }
+        _ => None,
+    };
+
+    let permalink = format!("{}/t/{}", state.base_url, short_links::encode(item.tweet_id));
+    let document = tweet_document(item, &permalink, media_key.as_deref());
+    storage
+        .put(&format!("{}tweets/{}.json", prefix, item.tweet_id), document.to_string().into_bytes(), "application/json")
+        .await
+        .map_err(|e| e.to_string())
+}
+
+fn tweet_document(item: &DueItem, permalink: &str, media_key: Option<&str>) -> serde_json::Value {
+    json!({
+        "id": item.tweet_id,
+        "url": permalink,
+        "created_at": item.created_at,
+        "content": item.content,
+        "visibility": item.visibility,
+        "parent_tweet_id": item.parent_tweet_id,
+        "quoted_tweet_id": item.quoted_tweet_id,
+        "image_url": item.image_url,
+        "media": media_key,
+        "likes_count": item.likes_count,
+        "retweets_count": item.retweets_count,
+        "replies_count": item.replies_count,
+    })
+}
+
+// Uploads are stored flat under a generated name, the last segment of their URL
+fn media_file_name(url: &str) -> Option<&str> {
+    url.rsplit('/').next().filter(|name| !name.is_empty() && !name.starts_with('.'))
+}
+
+async fn read_media(state: &AppState, url: &str, file: &str) -> Result<Vec<u8>, String> {
+    if let Some(dir) = state.media.storage.local_dir() {
+        return tokio::fs::read(dir.join(file)).await.map_err(|e| format!("failed to read {}: {}", file, e));
+    }
+
+    let url = Url::parse(url).map_err(|_| format!("invalid media URL {}", url))?;
+    let response = state.http.trusted(Method::GET, url).send().await.map_err(|e| e.to_string())?;
+    if !response.status().is_success() {
+        return Err(format!("fetching {} failed with {}", file, response.status()));
+    }
+    let bytes = response.bytes().await.map_err(|e| e.to_string())?;
+    if bytes.len() > state.media.max_bytes {
+        return Err(format!("{} is larger than the upload limit", file));
+    }
+    Ok(bytes.to_vec())
+}
+
+// Writes a small object to check the bucket accepts uploads with these
+// credentials.
+async fn test_connection(storage: &S3Storage, prefix: &str) -> Result<(), String> {
+    let body = format!("Backup connection test at {}\n", Utc::now().to_rfc3339());
+    storage
+        .put(&format!("{}connection-test.txt", prefix), body.into_bytes(), "text/plain")
+        .await
+        .map_err(|e| e.to_string())
+}
+
+async fn record_error(db: &PgPool, user_id: Uuid, error: &str) -> Result<(), sqlx::Error> {
+    sqlx::query("UPDATE backup_targets SET last_error = $2, last_error_at = NOW() WHERE user_id = $1")
+        .bind(user_id)
+        .bind(error)
+        .execute(db)
+        .await?;
+    Ok(())
+}
+
+async fn notify_failure(state: &AppState, user_id: Uuid, error: &str) -> Result<(), sqlx::Error> {
+    // Claims the notification first, so a failing batch sends one email
+    let email = sqlx::query_scalar::<_, Pii>(
+        "UPDATE backup_targets b SET failure_notified_at = NOW()
+         FROM users u
+         WHERE b.user_id = $1 AND u.id = b.user_id
+           AND (b.failure_notified_at IS NULL OR b.failure_notified_at < NOW() - INTERVAL '1 day')
+         RETURNING u.email"
+    )
+    .bind(user_id)
+    .fetch_optional(&state.db)
+    .await?;
+
+    if let Some(Pii(email)) = email {
+        let text = format!(
+            "Some of your tweets could not be backed up to your bucket on {} and won't be retried until you reconnect it.\n\nThe last error was:\n\n{}\n\nCheck the bucket and its credentials, then save your backup settings again.",
+            state.instance.name, error
+        );
+        if let Err(e) = state.mailer.send(&email, "Your backup is failing", &text).await {
+            log::error!("failed to send backup failure email for {}: {}", user_id, e);
+        }
+    }
+    Ok(())
+}
+
+fn truncate_error(error: &str) -> String {
+    error.chars().take(MAX_ERROR_LENGTH).collect()
+}
+
+// `path/to/dir` -> `path/to/dir/`, so keys can be appended directly
+fn normalize_prefix(prefix: &str) -> Result<String, String> {
+    let prefix = prefix.trim().trim_matches('/');
+    if prefix.is_empty() {
+        return Ok(String::new());
+    }
+    if prefix.split('/').any(|segment| segment.is_empty() || segment == "." || segment == "..")
+        || prefix.chars().any(|c| c.is_control())
+    {
+        return Err("Invalid prefix".to_string());
+    }
+    Ok(format!("{}/", prefix))
+}
+
+async fn fetch_target(db: &PgPool, user_id: Uuid) -> ApiResult<BackupTarget> {
+    sqlx::query_as::<_, BackupTarget>(&format!("SELECT {} FROM backup_targets b WHERE b.user_id = $1", TARGET_COLUMNS))
+        .bind(user_id)
+        .fetch_optional(db)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("No backup bucket connected".to_string()))
+}
+
+async fn get_backup(state: web::Data<AppState>, AuthenticatedUser(user_id): AuthenticatedUser) -> ApiResult<HttpResponse> {
+    let target = fetch_target(&state.db, user_id).await?;
+
+    Ok(HttpResponse::Ok().json(ApiResponse {
+        success: true,
+        data: Some(target),
+        message: None,
+        next_cursor: None,
+    }))
+}
+
+// Connects a bucket, or replaces the connected one, once a test upload
+// succeeds. Items that had been given up on are retried.
+async fn connect_backup(
+    state: web::Data<AppState>,
+    AuthenticatedUser(user_id): AuthenticatedUser,
+    req: web::Json<ConnectBackupRequest>,
+) -> ApiResult<HttpResponse> {
+    let req = req.into_inner();
+    let credentials = Credentials {
+        endpoint: req.endpoint.trim().to_string(),
+        bucket: req.bucket.trim().to_string(),
+        region: req.region.map(|r| r.trim().to_string()).filter(|r| !r.is_empty()).unwrap_or_else(|| "us-east-1".to_string()),
+        prefix: normalize_prefix(req.prefix.as_deref().unwrap_or_default()).map_err(ApiError::BadRequest)?,
+        access_key_id: req.access_key_id.trim().to_string(),
+        secret_access_key: Pii(req.secret_access_key.trim().to_string()),
+    };
+    if credentials.access_key_id.is_empty() || credentials.secret_access_key.0.is_empty() {
+        return Err(ApiError::BadRequest("access_key_id and secret_access_key are required".to_string()));
+    }
+    if credentials.region.len() > 50 {
+        return Err(ApiError::BadRequest("Invalid region".to_string()));
+    }
+
+    let storage = bucket(&state, &credentials).map_err(ApiError::BadRequest)?;
+    test_connection(&storage, &credentials.prefix)
+        .await
+        .map_err(|e| ApiError::BadRequest(format!("Could not write to the bucket: {}", e)))?;
+
+    let mut tx = state.db.begin().await?;
+
+    sqlx::query(
+        "INSERT INTO backup_targets (user_id, endpoint, bucket, region, prefix, access_key_id, secret_access_key)
+         VALUES ($1, $2, $3, $4, $5, $6, $7)
+         ON CONFLICT (user_id) DO UPDATE
+         SET endpoint = $2, bucket = $3, region = $4, prefix = $5, access_key_id = $6, secret_access_key = $7,
+             last_error = NULL, last_error_at = NULL, failure_notified_at = NULL"
+    )
+    .bind(user_id)
+    .bind(&credentials.endpoint)
+    .bind(&credentials.bucket)
+    .bind(&credentials.region)
+    .bind(&credentials.prefix)
+    .bind(&credentials.access_key_id)
+    .bind(crypto::seal(&credentials.secret_access_key.0))
+    .execute(&mut *tx)
+    .await?;
+
+    sqlx::query(
+        "UPDATE backup_items SET status = 'pending', attempts = 0, last_error = NULL, next_attempt_at = NOW()
+         WHERE user_id = $1 AND status = 'failed'"
+    )
+    .bind(user_id)
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+
+    let target = fetch_target(&state.db, user_id).await?;
+
+    Ok(HttpResponse::Ok().json(ApiResponse {
+        success: true,
+        data: Some(target),
+        message: None,
+        next_cursor: None,
+    }))
+}
+
+async fn test_backup(state: web::Data<AppState>, AuthenticatedUser(user_id): AuthenticatedUser) -> ApiResult<HttpResponse> {
+    let credentials = sqlx::query_as::<_, Credentials>(
+        "SELECT endpoint, bucket, region, prefix, access_key_id, secret_access_key FROM backup_targets WHERE user_id = $1"
+    )
+    .bind(user_id)
+    .fetch_optional(&state.db)
+    .await?
+    .ok_or_else(|| ApiError::NotFound("No backup bucket connected".to_string()))?;
+
+    let storage = bucket(&state, &credentials).map_err(ApiError::BadRequest)?;
+    if let Err(e) = test_connection(&storage, &credentials.prefix).await {
+        let error = truncate_error(&e);
+        record_error(&state.db, user_id, &error).await?;
+        return Err(ApiError::BadRequest(format!("Could not write to the bucket: {}", error)));
+    }
+
+    Ok(HttpResponse::Ok().json(ApiResponse {
+        success: true,
+        data: Some("Connection works"),
+        message: None,
+        next_cursor: None,
+    }))
+}
+
+// Objects already in the bucket are left there.
+async fn disconnect_backup(state: web::Data<AppState>, AuthenticatedUser(user_id): AuthenticatedUser) -> ApiResult<HttpResponse> {
+    let result = sqlx::query("DELETE FROM backup_targets WHERE user_id = $1")
+        .bind(user_id)
+        .execute(&state.db)
+        .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(ApiError::NotFound("No backup bucket connected".to_string()));
+    }
+
+    Ok(HttpResponse::Ok().json(ApiResponse {
+        success: true,
+        data: Some("Backup disconnected"),
+        message: None,
+        next_cursor: None,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalizes_prefixes() {
+        assert_eq!(normalize_prefix("").unwrap(), "");
+        assert_eq!(normalize_prefix(" / ").unwrap(), "");
+        assert_eq!(normalize_prefix("backups").unwrap(), "backups/");
+        assert_eq!(normalize_prefix("/a/b/").unwrap(), "a/b/");
+        assert!(normalize_prefix("a//b").is_err());
+        assert!(normalize_prefix("a/../b").is_err());
+        assert!(normalize_prefix("a\nb").is_err());
+    }
+
+    #[test]
+    fn takes_media_file_from_url() {
+        assert_eq!(media_file_name("https://cdn.example/media/abc.jpg"), Some("abc.jpg"));
+        assert_eq!(media_file_name("https://cdn.example/media/"), None);
+        assert_eq!(media_file_name("https://cdn.example/.."), None);
+    }
+
+    #[test]
+    fn truncates_long_errors() {
+        assert_eq!(truncate_error("short"), "short");
+        assert_eq!(truncate_error(&"é".repeat(1000)).chars().count(), MAX_ERROR_LENGTH);
+    }
+}
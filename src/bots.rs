@@ -0,0 +1,627 @@
+use actix_web::{web, HttpResponse};
+use chrono::{DateTime, Datelike, Duration, NaiveDate, TimeZone, Timelike, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, PgPool};
+use uuid::Uuid;
+
+use crate::auth::AuthenticatedUser;
+use crate::config;
+use crate::error::{ApiError, ApiResult};
+use crate::models::{ApiResponse, NewTweet, TweetVisibility};
+use crate::pagination::{self, Cursor, PageQuery};
+use crate::handlers::tweets::{submit_tweet, Submitted};
+use crate::AppState;
+
+// Automation rules for bot accounts (see `update_automation`): recurring
+// posts on a cron schedule, and canned replies to mentions containing a
+// keyword. The scheduler runs due rules every tick; each account may have at
+// most BOT_MAX_RULES rules and post at most BOT_MAX_POSTS_PER_HOUR tweets
+// through them. Every run lands in `bot_rule_runs` so owners can see what
+// their bot did and why something wasn't posted.
+#[derive(Debug, Clone)]
+pub struct BotRules {
+    max_rules: i64,
+    max_posts_per_hour: i64,
+    batch_size: i64,
+}
+
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.route("/api/bots/schedules", web::get().to(get_schedules))
+        .route("/api/bots/schedules", web::post().to(create_schedule))
+        .route("/api/bots/schedules/{id}", web::delete().to(delete_schedule))
+        .route("/api/bots/auto_replies", web::get().to(get_auto_replies))
+        .route("/api/bots/auto_replies", web::post().to(create_auto_reply))
+        .route("/api/bots/auto_replies/{id}", web::delete().to(delete_auto_reply))
+        .route("/api/bots/runs", web::get().to(get_runs));
+}
+
+#[derive(Debug, Serialize, FromRow)]
+pub struct BotSchedule {
+    pub id: Uuid,
+    #[serde(skip)]
+    pub user_id: Uuid,
+    pub cron: String,
+    pub content: String,
+    pub next_run_at: DateTime<Utc>,
+    pub last_run_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, FromRow)]
+pub struct AutoReply {
+    pub id: Uuid,
+    #[serde(skip)]
+    pub user_id: Uuid,
+    pub keyword: String,
+    pub reply: String,
+    #[serde(skip)]
+    pub checked_until: DateTime<Utc>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, FromRow)]
+pub struct BotRun {
+    pub id: Uuid,
+    pub rule_kind: String,
+    pub rule_id: Uuid,
+    pub tweet_id: Option<Uuid>,
+    pub outcome: String,
+    pub details: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateScheduleRequest {
+    pub cron: String,
+    pub content: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateAutoReplyRequest {
+    pub keyword: String,
+    pub reply: String,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum RuleKind {
+    Schedule,
+    AutoReply,
+}
+
+impl RuleKind {
+    fn as_str(self) -> &'static str {
+        match self {
+            RuleKind::Schedule => "schedule",
+            RuleKind::AutoReply => "auto_reply",
+        }
+    }
+}
+
+impl BotRules {
+    pub fn from_env() -> Result<Self, String> {
+        Ok(BotRules {
+            max_rules: config::parse_var("BOT_MAX_RULES", 10)?,
+            max_posts_per_hour: config::parse_var("BOT_MAX_POSTS_PER_HOUR", 30)?,
+            batch_size: 100,
+        })
+    }
+
+    // Run by the scheduler.
+    pub async fn tick(&self, state: &AppState) -> Result<(), sqlx::Error> {
+        self.run_schedules(state).await?;
+        self.run_auto_replies(state).await
+    }
+
+    async fn run_schedules(&self, state: &AppState) -> Result<(), sqlx::Error> {
+        let due = sqlx::query_as::<_, BotSchedule>(
+            "SELECT * FROM bot_schedules WHERE next_run_at <= NOW() ORDER BY next_run_at LIMIT $1"
+        )
+        .bind(self.batch_size)
+        .fetch_all(&state.db)
+        .await?;
+
+        for schedule in due {
+            // Missed runs are skipped, not caught up
+            let next_run_at = CronSchedule::parse(&schedule.cron)
+                .ok()
+                .and_then(|cron| cron.next_after(Utc::now()));
+            let Some(next_run_at) = next_run_at else {
+                sqlx::query("DELETE FROM bot_schedules WHERE id = $1").bind(schedule.id).execute(&state.db).await?;
+                record_run(&state.db, schedule.user_id, RuleKind::Schedule, schedule.id, None, "removed", Some("schedule never fires again")).await?;
+                continue;
+            };
+
+            // Claims the run; another instance may have got there first
+            let claimed = sqlx::query(
+                "UPDATE bot_schedules SET next_run_at = $1, last_run_at = NOW()
+                 WHERE id = $2 AND next_run_at = $3"
+            )
+            .bind(next_run_at)
+            .bind(schedule.id)
+            .bind(schedule.next_run_at)
+            .execute(&state.db)
+            .await?;
+            if claimed.rows_affected() == 0 {
+                continue;
+            }
+
+            let new_tweet = NewTweet {
+                id: None,
+                content: schedule.content,
+                image_url: None,
+                parent_tweet_id: None,
+                quoted_tweet_id: None,
+                visibility: TweetVisibility::Public,
+            };
+            self.post(state, schedule.user_id, RuleKind::Schedule, schedule.id, new_tweet).await?;
+        }
+
+        Ok(())
+    }
+
+    async fn run_auto_replies(&self, state: &AppState) -> Result<(), sqlx::Error> {
+        let rules = sqlx::query_as::<_, AutoReply>(
+            "SELECT a.* FROM bot_auto_replies a
+             INNER JOIN users u ON u.id = a.user_id
+             WHERE u.is_bot AND u.deactivated_at IS NULL"
+        )
+        .fetch_all(&state.db)
+        .await?;
+
+        for rule in rules {
+            // Mentions of the bot since the last scan that contain the keyword
+            // and haven't had a reply from it yet. Other bots are ignored so
+            // two bots can't keep answering each other.
+            let matches = sqlx::query_as::<_, (Uuid, DateTime<Utc>)>(
+                "SELECT t.id, t.created_at FROM mentions m
+                 INNER JOIN tweets t ON t.id = m.tweet_id
+                 INNER JOIN users u ON u.id = t.user_id
+                 WHERE m.user_id = $1 AND t.created_at > $2 AND t.created_at <= NOW()
+                   AND t.user_id <> $1 AND t.deleted_at IS NULL AND NOT u.is_bot
+                   AND tweet_visible_to(t.user_id, t.visibility, $1)
+                   AND strpos(lower(t.content), $3) > 0
+                   AND NOT EXISTS (SELECT 1 FROM tweets r WHERE r.parent_tweet_id = t.id AND r.user_id = $1)
+                 ORDER BY t.created_at
+                 LIMIT $4"
+            )
+            .bind(rule.user_id)
+            .bind(rule.checked_until)
+            .bind(&rule.keyword)
+            .bind(self.batch_size)
+            .fetch_all(&state.db)
+            .await?;
+
+            let checked_until = match matches.last() {
+                Some((_, created_at)) if matches.len() as i64 == self.batch_size => *created_at,
+                _ => Utc::now(),
+            };
+            sqlx::query("UPDATE bot_auto_replies SET checked_until = $1 WHERE id = $2")
+                .bind(checked_until)
+                .bind(rule.id)
+                .execute(&state.db)
+                .await?;
+
+            for (tweet_id, _) in matches {
+                let new_tweet = NewTweet {
+                    id: None,
+                    content: rule.reply.clone(),
+                    image_url: None,
+                    parent_tweet_id: Some(tweet_id),
+                    quoted_tweet_id: None,
+                    visibility: TweetVisibility::Public,
+                };
+                self.post(state, rule.user_id, RuleKind::AutoReply, rule.id, new_tweet).await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    // Posts on behalf of a rule through the regular posting checks, within
+    // the account's hourly budget, and records the outcome.
+    async fn post(&self, state: &AppState, user_id: Uuid, kind: RuleKind, rule_id: Uuid, new_tweet: NewTweet) -> Result<(), sqlx::Error> {
+        let active = sqlx::query_scalar::<_, bool>("SELECT is_bot AND deactivated_at IS NULL FROM users WHERE id = $1")
+            .bind(user_id)
+            .fetch_optional(&state.db)
+            .await?
+            .unwrap_or(false);
+        if !active {
+            return record_run(&state.db, user_id, kind, rule_id, None, "skipped", Some("account is not an active bot")).await;
+        }
+
+        let posted_last_hour = sqlx::query_scalar::<_, i64>(
+            "SELECT COUNT(*) FROM bot_rule_runs
+             WHERE user_id = $1 AND outcome = 'posted' AND created_at > NOW() - INTERVAL '1 hour'"
+        )
+        .bind(user_id)
+        .fetch_one(&state.db)
+        .await?;
+        if posted_last_hour >= self.max_posts_per_hour {
+            let details = format!("limit of {} posts per hour reached", self.max_posts_per_hour);
+            return record_run(&state.db, user_id, kind, rule_id, None, "rate_limited", Some(&details)).await;
+        }
+
+        match submit_tweet(state, user_id, new_tweet).await {
+            Ok(Submitted::Published(tweet)) => record_run(&state.db, user_id, kind, rule_id, Some(tweet.id), "posted", None).await,
+            Ok(Submitted::Held(_)) => record_run(&state.db, user_id, kind, rule_id, None, "held", Some("held for review")).await,
+            Err(e) => {
+                log::warn!("bot rule {} of {} failed: {}", rule_id, user_id, e);
+                record_run(&state.db, user_id, kind, rule_id, None, "failed", Some(&e.to_string())).await
+            }
+        }
+    }
+
+    async fn ensure_can_add_rule(&self, db: &PgPool, user_id: Uuid) -> ApiResult<()> {
+        let (is_bot, rules) = sqlx::query_as::<_, (bool, i64)>(
+            "SELECT u.is_bot,
+                    (SELECT COUNT(*) FROM bot_schedules WHERE user_id = u.id)
+                  + (SELECT COUNT(*) FROM bot_auto_replies WHERE user_id = u.id)
+             FROM users u WHERE u.id = $1"
+        )
+        .bind(user_id)
+        .fetch_one(db)
+        .await?;
+
+        if !is_bot {
+            return Err(ApiError::Forbidden("Only bot accounts can have automation rules".to_string()));
+        }
+        if rules >= self.max_rules {
+            return Err(ApiError::Forbidden(format!("Bot accounts can have at most {} rules", self.max_rules)));
+        }
+        Ok(())
+    }
+}
+
+async fn record_run(
+    db: &PgPool,
+    user_id: Uuid,
+    kind: RuleKind,
+    rule_id: Uuid,
+    tweet_id: Option<Uuid>,
+    outcome: &str,
+    details: Option<&str>,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "INSERT INTO bot_rule_runs (user_id, rule_kind, rule_id, tweet_id, outcome, details)
+         VALUES ($1, $2, $3, $4, $5, $6)"
+    )
+    .bind(user_id)
+    .bind(kind.as_str())
+    .bind(rule_id)
+    .bind(tweet_id)
+    .bind(outcome)
+    .bind(details)
+    .execute(db)
+    .await?;
+    Ok(())
+}
+
+async fn get_schedules(state: web::Data<AppState>, AuthenticatedUser(user_id): AuthenticatedUser) -> ApiResult<HttpResponse> {
+    let schedules = sqlx::query_as::<_, BotSchedule>("SELECT * FROM bot_schedules WHERE user_id = $1 ORDER BY created_at")
+        .bind(user_id)
+        .fetch_all(&state.db)
+        .await?;
+
+    Ok(HttpResponse::Ok().json(ApiResponse {
+        success: true,
+        data: Some(schedules),
+        message: None,
+        next_cursor: None,
+    }))
+}
+
+async fn create_schedule(
+    state: web::Data<AppState>,
+    AuthenticatedUser(user_id): AuthenticatedUser,
+    req: web::Json<CreateScheduleRequest>,
+) -> ApiResult<HttpResponse> {
+    state.bots.ensure_can_add_rule(&state.db, user_id).await?;
+    state.instance.check_tweet(&req.content, None)?;
+    if req.content.trim().is_empty() {
+        return Err(ApiError::BadRequest("Content cannot be blank".to_string()));
+    }
+
+    let cron = CronSchedule::parse(&req.cron).map_err(ApiError::BadRequest)?;
+    let next_run_at = cron
+        .next_after(Utc::now())
+        .ok_or_else(|| ApiError::BadRequest("Schedule never fires".to_string()))?;
+
+    let schedule = sqlx::query_as::<_, BotSchedule>(
+        "INSERT INTO bot_schedules (user_id, cron, content, next_run_at) VALUES ($1, $2, $3, $4) RETURNING *"
+    )
+    .bind(user_id)
+    .bind(req.cron.trim())
+    .bind(&req.content)
+    .bind(next_run_at)
+    .fetch_one(&state.db)
+    .await?;
+
+    Ok(HttpResponse::Created().json(ApiResponse {
+        success: true,
+        data: Some(schedule),
+        message: None,
+        next_cursor: None,
+    }))
+}
+
+async fn delete_schedule(state: web::Data<AppState>, AuthenticatedUser(user_id): AuthenticatedUser, schedule_id: web::Path<Uuid>) -> ApiResult<HttpResponse> {
+    let result = sqlx::query("DELETE FROM bot_schedules WHERE id = $1 AND user_id = $2")
+        .bind(schedule_id.into_inner())
+        .bind(user_id)
+        .execute(&state.db)
+        .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(ApiError::NotFound("Schedule not found".to_string()));
+    }
+
+    Ok(HttpResponse::Ok().json(ApiResponse {
+        success: true,
+        data: Some("Schedule deleted successfully"),
+        message: None,
+        next_cursor: None,
+    }))
+}
+
+async fn get_auto_replies(state: web::Data<AppState>, AuthenticatedUser(user_id): AuthenticatedUser) -> ApiResult<HttpResponse> {
+    let rules = sqlx::query_as::<_, AutoReply>("SELECT * FROM bot_auto_replies WHERE user_id = $1 ORDER BY created_at")
+        .bind(user_id)
+        .fetch_all(&state.db)
+        .await?;
+
+    Ok(HttpResponse::Ok().json(ApiResponse {
+        success: true,
+        data: Some(rules),
+        message: None,
+        next_cursor: None,
+    }))
+}
+
+// Keywords match case-insensitively anywhere in the mentioning tweet. Only
+// mentions from after the rule was created are answered.
+async fn create_auto_reply(
+    state: web::Data<AppState>,
+    AuthenticatedUser(user_id): AuthenticatedUser,
+    req: web::Json<CreateAutoReplyRequest>,
+) -> ApiResult<HttpResponse> {
+    state.bots.ensure_can_add_rule(&state.db, user_id).await?;
+    state.instance.check_tweet(&req.reply, None)?;
+
+    let keyword = req.keyword.trim().to_lowercase();
+    if keyword.is_empty() || keyword.chars().count() > 100 {
+        return Err(ApiError::BadRequest("Keyword must be 1 to 100 characters".to_string()));
+    }
+    if req.reply.trim().is_empty() {
+        return Err(ApiError::BadRequest("Reply cannot be blank".to_string()));
+    }
+
+    let rule = sqlx::query_as::<_, AutoReply>(
+        "INSERT INTO bot_auto_replies (user_id, keyword, reply) VALUES ($1, $2, $3) RETURNING *"
+    )
+    .bind(user_id)
+    .bind(&keyword)
+    .bind(&req.reply)
+    .fetch_one(&state.db)
+    .await?;
+
+    Ok(HttpResponse::Created().json(ApiResponse {
+        success: true,
+        data: Some(rule),
+        message: None,
+        next_cursor: None,
+    }))
+}
+
+async fn delete_auto_reply(state: web::Data<AppState>, AuthenticatedUser(user_id): AuthenticatedUser, rule_id: web::Path<Uuid>) -> ApiResult<HttpResponse> {
+    let result = sqlx::query("DELETE FROM bot_auto_replies WHERE id = $1 AND user_id = $2")
+        .bind(rule_id.into_inner())
+        .bind(user_id)
+        .execute(&state.db)
+        .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(ApiError::NotFound("Auto-reply not found".to_string()));
+    }
+
+    Ok(HttpResponse::Ok().json(ApiResponse {
+        success: true,
+        data: Some("Auto-reply deleted successfully"),
+        message: None,
+        next_cursor: None,
+    }))
+}
+
+// The account's rule audit log, newest first.
+async fn get_runs(state: web::Data<AppState>, AuthenticatedUser(user_id): AuthenticatedUser, page: web::Query<PageQuery>) -> ApiResult<HttpResponse> {
+    let limit = page.limit();
+    let scope = format!("bot_runs:{}", user_id);
+    let cursor = page.cursor(&state.cursors, &scope).map_err(ApiError::BadRequest)?;
+
+    let mut runs = sqlx::query_as::<_, BotRun>(
+        "SELECT id, rule_kind, rule_id, tweet_id, outcome, details, created_at FROM bot_rule_runs
+         WHERE user_id = $1
+           AND ($2::timestamptz IS NULL OR (created_at, id) < ($2, $3))
+         ORDER BY created_at DESC, id DESC
+         LIMIT $4"
+    )
+    .bind(user_id)
+    .bind(cursor.map(|c| c.created_at))
+    .bind(cursor.map(|c| c.id))
+    .bind(limit + 1)
+    .fetch_all(&state.db)
+    .await?;
+
+    let next_cursor = pagination::next_cursor(&mut runs, limit, &state.cursors, &scope, |r| Cursor {
+        created_at: r.created_at,
+        id: r.id,
+    });
+
+    Ok(HttpResponse::Ok().json(ApiResponse {
+        success: true,
+        data: Some(runs),
+        message: None,
+        next_cursor,
+    }))
+}
+
+// A standard 5-field cron expression (minute, hour, day of month, month, day
+// of week), evaluated in UTC. Fields take `*`, numbers, ranges `a-b`, steps
+// `*/n` or `a-b/n` and comma-separated lists; day of week is 0-7 with both 0
+// and 7 meaning Sunday. As in cron, when both day fields are restricted a day
+// matching either one fires.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CronSchedule {
+    minutes: u64,
+    hours: u64,
+    days: u64,
+    months: u64,
+    weekdays: u64,
+    any_day: bool,
+    any_weekday: bool,
+}
+
+impl CronSchedule {
+    pub fn parse(expr: &str) -> Result<Self, String> {
+        let fields: Vec<&str> = expr.split_whitespace().collect();
+        let [minute, hour, day, month, weekday] = fields.as_slice() else {
+            return Err("Cron expression must have 5 fields: minute hour day month weekday".to_string());
+        };
+
+        let mut weekdays = parse_field(weekday, 0, 7)?;
+        if weekdays & (1 << 7) != 0 {
+            weekdays = (weekdays | 1) & !(1 << 7);
+        }
+
+        Ok(CronSchedule {
+            minutes: parse_field(minute, 0, 59)?,
+            hours: parse_field(hour, 0, 23)?,
+            days: parse_field(day, 1, 31)?,
+            months: parse_field(month, 1, 12)?,
+            weekdays,
+            any_day: *day == "*",
+            any_weekday: *weekday == "*",
+        })
+    }
+
+    fn day_matches(&self, date: NaiveDate) -> bool {
+        let day = self.days & (1 << date.day()) != 0;
+        let weekday = self.weekdays & (1 << date.weekday().num_days_from_sunday()) != 0;
+        match (self.any_day, self.any_weekday) {
+            (false, false) => day || weekday,
+            (true, false) => weekday,
+            (false, true) => day,
+            (true, true) => true,
+        }
+    }
+
+    // First matching minute strictly after `after`, looking up to five years
+    // ahead (enough for any satisfiable expression, including Feb 29).
+    pub fn next_after(&self, after: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let start = after.with_second(0)?.with_nanosecond(0)? + Duration::minutes(1);
+        let end = start + Duration::days(5 * 366);
+        let mut t = start;
+
+        while t < end {
+            let date = t.date_naive();
+            if self.months & (1 << t.month()) == 0 {
+                let (year, month) = if t.month() == 12 { (t.year() + 1, 1) } else { (t.year(), t.month() + 1) };
+                t = Utc.from_utc_datetime(&NaiveDate::from_ymd_opt(year, month, 1)?.and_hms_opt(0, 0, 0)?);
+            } else if !self.day_matches(date) {
+                t = Utc.from_utc_datetime(&date.succ_opt()?.and_hms_opt(0, 0, 0)?);
+            } else if self.hours & (1 << t.hour()) == 0 {
+                t = t.with_minute(0)? + Duration::hours(1);
+            } else if self.minutes & (1 << t.minute()) == 0 {
+                t += Duration::minutes(1);
+            } else {
+                return Some(t);
+            }
+        }
+
+        None
+    }
+}
+
+fn parse_field(spec: &str, min: u32, max: u32) -> Result<u64, String> {
+    let invalid = || format!("Invalid cron field {:?}", spec);
+    let number = |s: &str| -> Result<u32, String> {
+        let n: u32 = s.parse().map_err(|_| invalid())?;
+        if n < min || n > max {
+            return Err(format!("Cron value {} is outside {}-{}", n, min, max));
+        }
+        Ok(n)
+    };
+
+    let mut bits = 0u64;
+    for part in spec.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => (range, Some(step.parse::<u32>().ok().filter(|s| *s > 0).ok_or_else(invalid)?)),
+            None => (part, None),
+        };
+        let (from, to) = match range {
+            "*" => (min, max),
+            _ => match range.split_once('-') {
+                Some((a, b)) => (number(a)?, number(b)?),
+                // `5/15` means every 15 starting at 5
+                None if step.is_some() => (number(range)?, max),
+                None => (number(range)?, number(range)?),
+            },
+        };
+        if from > to {
+            return Err(invalid());
+        }
+
+        for n in (from..=to).step_by(step.unwrap_or(1) as usize) {
+            bits |= 1 << n;
+        }
+    }
+
+    Ok(bits)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(y: i32, mo: u32, d: u32, h: u32, mi: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(y, mo, d, h, mi, 0).unwrap()
+    }
+
+    #[test]
+    fn every_fifteen_minutes() {
+        let cron = CronSchedule::parse("*/15 * * * *").unwrap();
+        assert_eq!(cron.next_after(at(2024, 5, 6, 13, 47)), Some(at(2024, 5, 6, 14, 0)));
+        assert_eq!(cron.next_after(at(2024, 5, 6, 14, 0)), Some(at(2024, 5, 6, 14, 15)));
+    }
+
+    #[test]
+    fn weekday_mornings() {
+        // 2024-05-10 is a Friday
+        let cron = CronSchedule::parse("30 9 * * 1-5").unwrap();
+        assert_eq!(cron.next_after(at(2024, 5, 10, 10, 0)), Some(at(2024, 5, 13, 9, 30)));
+    }
+
+    #[test]
+    fn day_fields_combine_with_or() {
+        // The 1st of the month or any Sunday; 2024-06-02 is a Sunday
+        let cron = CronSchedule::parse("0 0 1 * 0").unwrap();
+        assert_eq!(cron.next_after(at(2024, 5, 30, 0, 0)), Some(at(2024, 6, 1, 0, 0)));
+        assert_eq!(cron.next_after(at(2024, 6, 1, 0, 0)), Some(at(2024, 6, 2, 0, 0)));
+        assert_eq!(CronSchedule::parse("0 0 * * 7").unwrap(), CronSchedule::parse("0 0 * * 0").unwrap());
+    }
+
+    #[test]
+    fn yearly_and_impossible_schedules() {
+        let leap = CronSchedule::parse("0 12 29 2 *").unwrap();
+        assert_eq!(leap.next_after(at(2024, 3, 1, 0, 0)), Some(at(2028, 2, 29, 12, 0)));
+        assert_eq!(CronSchedule::parse("0 0 30 2 *").unwrap().next_after(at(2024, 1, 1, 0, 0)), None);
+    }
+
+    #[test]
+    fn rejects_malformed_expressions() {
+        assert!(CronSchedule::parse("* * * *").is_err());
+        assert!(CronSchedule::parse("60 * * * *").is_err());
+        assert!(CronSchedule::parse("*/0 * * * *").is_err());
+        assert!(CronSchedule::parse("5-1 * * * *").is_err());
+        assert!(CronSchedule::parse("a * * * *").is_err());
+    }
+}
@@ -0,0 +1,284 @@
+use actix_web::{web, HttpResponse};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::auth::AuthenticatedUser;
+use crate::error::{ApiError, ApiResult};
+use crate::models::{ApiResponse, PublicUserResponse, TweetResponse, TweetWithUser, User};
+use crate::notifications::{self, Kind};
+use crate::repo::postgres::can_view_tweet;
+use crate::handlers::social::is_blocked;
+use crate::AppState;
+
+// Co-authored tweets: the author tags one other account, which gets a
+// notification and can accept or decline. Once accepted the tweet also shows
+// on the co-author's profile and the author is notified. Either side can
+// remove the tag later; the tweet itself always stays the author's.
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.route("/api/tweets/{id}/coauthor", web::get().to(get_coauthor))
+        .route("/api/tweets/{id}/coauthor", web::put().to(tag_coauthor))
+        .route("/api/tweets/{id}/coauthor", web::delete().to(remove_coauthor))
+        .route("/api/coauthor_requests", web::get().to(get_requests))
+        .route("/api/coauthor_requests/{tweet_id}/accept", web::post().to(accept_request))
+        .route("/api/coauthor_requests/{tweet_id}", web::delete().to(decline_request));
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TagCoauthorRequest {
+    pub username: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CoauthorResponse {
+    pub user: PublicUserResponse,
+    pub accepted: bool,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CoauthorRequestResponse {
+    pub tweet: TweetResponse,
+    pub requested_at: DateTime<Utc>,
+}
+
+#[derive(sqlx::FromRow)]
+struct PendingRequest {
+    #[sqlx(flatten)]
+    tweet: TweetWithUser,
+    requested_at: DateTime<Utc>,
+}
+
+#[derive(sqlx::FromRow)]
+struct Coauthor {
+    user_id: Uuid,
+    accepted_at: Option<DateTime<Utc>>,
+    created_at: DateTime<Utc>,
+}
+
+async fn fetch_coauthor(conn: &mut sqlx::PgConnection, tweet_id: Uuid) -> Result<Option<Coauthor>, sqlx::Error> {
+    sqlx::query_as::<_, Coauthor>("SELECT user_id, accepted_at, created_at FROM tweet_coauthors WHERE tweet_id = $1")
+        .bind(tweet_id)
+        .fetch_optional(conn)
+        .await
+}
+
+// The accepted co-author, for anyone who can see the tweet. A pending tag is
+// only shown to the author and the tagged account.
+async fn get_coauthor(
+    state: web::Data<AppState>,
+    viewer: Option<AuthenticatedUser>,
+    tweet_id: web::Path<Uuid>,
+) -> ApiResult<HttpResponse> {
+    let tweet_id = tweet_id.into_inner();
+    let viewer_id = viewer.map(|AuthenticatedUser(id)| id);
+    let mut conn = state.db.acquire().await?;
+
+    let author_id = sqlx::query_scalar::<_, Uuid>(
+        "SELECT user_id FROM tweets WHERE id = $1 AND deleted_at IS NULL AND tweet_visible_to(user_id, visibility, $2)"
+    )
+    .bind(tweet_id)
+    .bind(viewer_id)
+    .fetch_optional(&mut *conn)
+    .await?
+    .ok_or_else(|| ApiError::NotFound("Tweet not found".to_string()))?;
+
+    let coauthor = fetch_coauthor(&mut conn, tweet_id)
+        .await?
+        .filter(|c| c.accepted_at.is_some() || viewer_id == Some(author_id) || viewer_id == Some(c.user_id))
+        .ok_or_else(|| ApiError::NotFound("Tweet has no co-author".to_string()))?;
+
+    let user = sqlx::query_as::<_, User>("SELECT * FROM users WHERE id = $1 AND deactivated_at IS NULL")
+        .bind(coauthor.user_id)
+        .fetch_optional(&mut *conn)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("Tweet has no co-author".to_string()))?;
+
+    Ok(HttpResponse::Ok().json(ApiResponse {
+        success: true,
+        data: Some(CoauthorResponse {
+            user: user.into(),
+            accepted: coauthor.accepted_at.is_some(),
+            created_at: coauthor.created_at,
+        }),
+        message: None,
+        next_cursor: None,
+    }))
+}
+
+async fn tag_coauthor(
+    state: web::Data<AppState>,
+    AuthenticatedUser(user_id): AuthenticatedUser,
+    tweet_id: web::Path<Uuid>,
+    req: web::Json<TagCoauthorRequest>,
+) -> ApiResult<HttpResponse> {
+    let tweet_id = tweet_id.into_inner();
+    let mut tx = state.db.begin().await?;
+
+    sqlx::query_scalar::<_, Uuid>("SELECT id FROM tweets WHERE id = $1 AND user_id = $2 AND deleted_at IS NULL FOR UPDATE")
+        .bind(tweet_id)
+        .bind(user_id)
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("Tweet not found".to_string()))?;
+
+    let coauthor_id = sqlx::query_scalar::<_, Uuid>("SELECT id FROM users WHERE username = $1 AND deactivated_at IS NULL")
+        .bind(req.username.trim().trim_start_matches('@'))
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("User not found".to_string()))?;
+
+    if coauthor_id == user_id {
+        return Err(ApiError::BadRequest("You can't co-author your own tweet".to_string()));
+    }
+    if is_blocked(&mut tx, coauthor_id, user_id).await? || is_blocked(&mut tx, user_id, coauthor_id).await? {
+        return Err(ApiError::Forbidden("You can't tag this user".to_string()));
+    }
+    if !can_view_tweet(&mut tx, tweet_id, coauthor_id).await? {
+        return Err(ApiError::BadRequest("The co-author must be able to see the tweet".to_string()));
+    }
+    if fetch_coauthor(&mut tx, tweet_id).await?.is_some() {
+        return Err(ApiError::Conflict("Tweet already has a co-author".to_string()));
+    }
+
+    sqlx::query("INSERT INTO tweet_coauthors (tweet_id, user_id) VALUES ($1, $2)")
+        .bind(tweet_id)
+        .bind(coauthor_id)
+        .execute(&mut *tx)
+        .await?;
+    notifications::notify(&mut tx, coauthor_id, user_id, Kind::CoauthorRequest, Some(tweet_id)).await?;
+
+    tx.commit().await?;
+
+    Ok(HttpResponse::Created().json(ApiResponse::<()> {
+        success: true,
+        data: None,
+        message: Some("Co-author request sent".to_string()),
+        next_cursor: None,
+    }))
+}
+
+// The author withdraws the tag, or the co-author leaves the tweet.
+async fn remove_coauthor(
+    state: web::Data<AppState>,
+    AuthenticatedUser(user_id): AuthenticatedUser,
+    tweet_id: web::Path<Uuid>,
+) -> ApiResult<HttpResponse> {
+    let tweet_id = tweet_id.into_inner();
+    let mut tx = state.db.begin().await?;
+
+    let author_id = sqlx::query_scalar::<_, Uuid>(
+        "DELETE FROM tweet_coauthors c
+         USING tweets t
+         WHERE c.tweet_id = $1 AND t.id = c.tweet_id AND (t.user_id = $2 OR c.user_id = $2)
+         RETURNING t.user_id"
+    )
+    .bind(tweet_id)
+    .bind(user_id)
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or_else(|| ApiError::NotFound("Tweet has no co-author".to_string()))?;
+    notifications::retract(&mut tx, author_id, Kind::CoauthorRequest, Some(tweet_id), None).await?;
+
+    tx.commit().await?;
+
+    Ok(HttpResponse::Ok().json(ApiResponse::<()> {
+        success: true,
+        data: None,
+        message: Some("Co-author removed".to_string()),
+        next_cursor: None,
+    }))
+}
+
+// Pending requests for the current user, newest first.
+async fn get_requests(state: web::Data<AppState>, AuthenticatedUser(user_id): AuthenticatedUser) -> ApiResult<HttpResponse> {
+    let requests = sqlx::query_as::<_, PendingRequest>(
+        "SELECT t.id, t.user_id, t.parent_tweet_id, t.quoted_tweet_id, t.content, t.image_url,
+                t.likes_count, t.retweets_count, t.replies_count, t.visibility, t.created_at,
+                u.username as user_username, u.display_name as user_display_name,
+                u.bio as user_bio,
+                u.profile_image as user_profile_image, u.banner_image as user_banner_image,
+                u.followers_count as user_followers_count, u.following_count as user_following_count,
+                u.verified as user_verified, u.is_bot as user_is_bot, u.created_at as user_created_at,
+                c.created_at as requested_at
+         FROM tweet_coauthors c
+         INNER JOIN tweets t ON t.id = c.tweet_id
+         INNER JOIN users u ON u.id = t.user_id
+         WHERE c.user_id = $1 AND c.accepted_at IS NULL
+           AND t.deleted_at IS NULL AND u.deactivated_at IS NULL
+         ORDER BY c.created_at DESC"
+    )
+    .bind(user_id)
+    .fetch_all(&state.db)
+    .await?;
+
+    let responses: Vec<CoauthorRequestResponse> = requests
+        .into_iter()
+        .map(|r| CoauthorRequestResponse {
+            tweet: r.tweet.into_response(false, false),
+            requested_at: r.requested_at,
+        })
+        .collect();
+
+    Ok(HttpResponse::Ok().json(ApiResponse {
+        success: true,
+        data: Some(responses),
+        message: None,
+        next_cursor: None,
+    }))
+}
+
+async fn accept_request(
+    state: web::Data<AppState>,
+    AuthenticatedUser(user_id): AuthenticatedUser,
+    tweet_id: web::Path<Uuid>,
+) -> ApiResult<HttpResponse> {
+    let tweet_id = tweet_id.into_inner();
+    let mut tx = state.db.begin().await?;
+
+    let author_id = sqlx::query_scalar::<_, Uuid>(
+        "UPDATE tweet_coauthors c SET accepted_at = NOW()
+         FROM tweets t
+         WHERE c.tweet_id = $1 AND c.user_id = $2 AND c.accepted_at IS NULL
+           AND t.id = c.tweet_id AND t.deleted_at IS NULL
+         RETURNING t.user_id"
+    )
+    .bind(tweet_id)
+    .bind(user_id)
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or_else(|| ApiError::NotFound("Co-author request not found".to_string()))?;
+    notifications::notify(&mut tx, author_id, user_id, Kind::CoauthorAccept, Some(tweet_id)).await?;
+
+    tx.commit().await?;
+
+    Ok(HttpResponse::Ok().json(ApiResponse::<()> {
+        success: true,
+        data: None,
+        message: Some("Co-author request accepted".to_string()),
+        next_cursor: None,
+    }))
+}
+
+async fn decline_request(
+    state: web::Data<AppState>,
+    AuthenticatedUser(user_id): AuthenticatedUser,
+    tweet_id: web::Path<Uuid>,
+) -> ApiResult<HttpResponse> {
+    let result = sqlx::query("DELETE FROM tweet_coauthors WHERE tweet_id = $1 AND user_id = $2 AND accepted_at IS NULL")
+        .bind(tweet_id.into_inner())
+        .bind(user_id)
+        .execute(&state.db)
+        .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(ApiError::NotFound("Co-author request not found".to_string()));
+    }
+
+    Ok(HttpResponse::Ok().json(ApiResponse::<()> {
+        success: true,
+        data: None,
+        message: Some("Co-author request declined".to_string()),
+        next_cursor: None,
+    }))
+}
@@ -0,0 +1,441 @@
+use actix_web::{web, HttpResponse};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use std::collections::HashSet;
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::auth::AuthenticatedUser;
+use crate::error::{ApiError, ApiResult};
+use crate::models::{ApiResponse, CollectionSummary, TweetResponse, TweetWithUser};
+use crate::AppState;
+
+const MAX_COLLECTIONS_PER_USER: i64 = 100;
+const MAX_TWEETS_PER_COLLECTION: i64 = 500;
+const MAX_FEATURED_ON_PROFILE: i64 = 5;
+
+// Collections: public, ordered lists of tweets a user curates, their own or
+// anyone's. Only public tweets can be added, so a collection reads the same
+// for everyone; entries disappear when their tweet is deleted or archived.
+// Collections marked `featured` are listed on the curator's profile.
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.route("/api/collections", web::post().to(create_collection))
+        .route("/api/collections/{id}", web::get().to(get_collection))
+        .route("/api/collections/{id}", web::put().to(update_collection))
+        .route("/api/collections/{id}", web::delete().to(delete_collection))
+        .route("/api/collections/{id}/tweets", web::post().to(add_tweet))
+        .route("/api/collections/{id}/tweets", web::put().to(reorder_tweets))
+        .route("/api/collections/{id}/tweets/{tweet_id}", web::delete().to(remove_tweet))
+        .route("/api/users/{username}/collections", web::get().to(get_user_collections));
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct CreateCollectionRequest {
+    #[validate(length(min = 1, max = 100))]
+    pub title: String,
+    #[serde(default)]
+    #[validate(length(max = 500))]
+    pub description: String,
+    #[serde(default)]
+    pub featured: bool,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct UpdateCollectionRequest {
+    #[validate(length(min = 1, max = 100))]
+    pub title: Option<String>,
+    #[validate(length(max = 500))]
+    pub description: Option<String>,
+    pub featured: Option<bool>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AddTweetRequest {
+    pub tweet_id: Uuid,
+    // 0-based; appended when missing
+    pub position: Option<i32>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ReorderTweetsRequest {
+    // Every tweet in the collection, in the new order
+    pub tweet_ids: Vec<Uuid>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CollectionResponse {
+    #[serde(flatten)]
+    pub collection: CollectionSummary,
+    pub curator: String,
+    pub tweets: Vec<TweetResponse>,
+}
+
+const SUMMARY_COLUMNS: &str = "c.id, c.user_id, c.title, c.description, c.featured,
+    (SELECT COUNT(*) FROM collection_tweets ct WHERE ct.collection_id = c.id) AS tweets_count,
+    c.created_at, c.updated_at";
+
+// Collection entries still on show: the tweet is live and its author active
+const COLLECTION_TWEETS_QUERY: &str = "SELECT t.id, t.user_id, t.parent_tweet_id, t.quoted_tweet_id, t.content, t.image_url,
+        t.likes_count, t.retweets_count, t.replies_count, t.visibility, t.created_at,
+        u.username as user_username, u.display_name as user_display_name,
+        u.bio as user_bio,
+        u.profile_image as user_profile_image, u.banner_image as user_banner_image,
+        u.followers_count as user_followers_count, u.following_count as user_following_count,
+        u.verified as user_verified, u.is_bot as user_is_bot, u.created_at as user_created_at
+     FROM collection_tweets ct
+     INNER JOIN tweets t ON t.id = ct.tweet_id
+     INNER JOIN users u ON u.id = t.user_id
+     WHERE ct.collection_id = $1 AND t.deleted_at IS NULL AND u.deactivated_at IS NULL
+       AND t.visibility = 'public'
+     ORDER BY ct.position, ct.added_at";
+
+// For the profile page.
+pub async fn featured(db: &PgPool, user_id: Uuid) -> Result<Vec<CollectionSummary>, sqlx::Error> {
+    sqlx::query_as::<_, CollectionSummary>(&format!(
+        "SELECT {} FROM collections c WHERE c.user_id = $1 AND c.featured ORDER BY c.updated_at DESC LIMIT $2",
+        SUMMARY_COLUMNS
+    ))
+    .bind(user_id)
+    .bind(MAX_FEATURED_ON_PROFILE)
+    .fetch_all(db)
+    .await
+}
+
+async fn fetch_summary(db: &PgPool, collection_id: Uuid) -> ApiResult<CollectionSummary> {
+    sqlx::query_as::<_, CollectionSummary>(&format!("SELECT {} FROM collections c WHERE c.id = $1", SUMMARY_COLUMNS))
+        .bind(collection_id)
+        .fetch_optional(db)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("Collection not found".to_string()))
+}
+
+// Locks the collection for a change by its curator.
+async fn lock_owned(conn: &mut sqlx::PgConnection, collection_id: Uuid, user_id: Uuid) -> ApiResult<()> {
+    sqlx::query_scalar::<_, Uuid>("SELECT id FROM collections WHERE id = $1 AND user_id = $2 FOR UPDATE")
+        .bind(collection_id)
+        .bind(user_id)
+        .fetch_optional(conn)
+        .await?
+        .map(|_| ())
+        .ok_or_else(|| ApiError::NotFound("Collection not found".to_string()))
+}
+
+async fn touch(conn: &mut sqlx::PgConnection, collection_id: Uuid) -> Result<(), sqlx::Error> {
+    sqlx::query("UPDATE collections SET updated_at = NOW() WHERE id = $1")
+        .bind(collection_id)
+        .execute(conn)
+        .await?;
+    Ok(())
+}
+
+async fn create_collection(
+    state: web::Data<AppState>,
+    AuthenticatedUser(user_id): AuthenticatedUser,
+    req: web::Json<CreateCollectionRequest>,
+) -> ApiResult<HttpResponse> {
+    req.validate()?;
+
+    let count = sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM collections WHERE user_id = $1")
+        .bind(user_id)
+        .fetch_one(&state.db)
+        .await?;
+    if count >= MAX_COLLECTIONS_PER_USER {
+        return Err(ApiError::Forbidden(format!("You can have at most {} collections", MAX_COLLECTIONS_PER_USER)));
+    }
+
+    let collection_id = sqlx::query_scalar::<_, Uuid>(
+        "INSERT INTO collections (user_id, title, description, featured) VALUES ($1, $2, $3, $4) RETURNING id"
+    )
+    .bind(user_id)
+    .bind(req.title.trim())
+    .bind(req.description.trim())
+    .bind(req.featured)
+    .fetch_one(&state.db)
+    .await?;
+
+    Ok(HttpResponse::Created().json(ApiResponse {
+        success: true,
+        data: Some(fetch_summary(&state.db, collection_id).await?),
+        message: None,
+        next_cursor: None,
+    }))
+}
+
+// Public; signing in isn't needed to view a collection.
+async fn get_collection(state: web::Data<AppState>, collection_id: web::Path<Uuid>) -> ApiResult<HttpResponse> {
+    let collection = fetch_summary(&state.db, collection_id.into_inner()).await?;
+    let curator = sqlx::query_scalar::<_, String>("SELECT username FROM users WHERE id = $1 AND deactivated_at IS NULL")
+        .bind(collection.user_id)
+        .fetch_optional(&state.db)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("Collection not found".to_string()))?;
+
+    let tweets = sqlx::query_as::<_, TweetWithUser>(COLLECTION_TWEETS_QUERY)
+        .bind(collection.id)
+        .fetch_all(&state.db)
+        .await?
+        .into_iter()
+        .map(|tweet| tweet.into_response(false, false))
+        .collect();
+
+    Ok(HttpResponse::Ok().json(ApiResponse {
+        success: true,
+        data: Some(CollectionResponse { collection, curator, tweets }),
+        message: None,
+        next_cursor: None,
+    }))
+}
+
+async fn get_user_collections(state: web::Data<AppState>, username: web::Path<String>) -> ApiResult<HttpResponse> {
+    let collections = sqlx::query_as::<_, CollectionSummary>(&format!(
+        "SELECT {} FROM collections c
+         INNER JOIN users u ON u.id = c.user_id
+         WHERE u.username = $1 AND u.deactivated_at IS NULL
+         ORDER BY c.updated_at DESC",
+        SUMMARY_COLUMNS
+    ))
+    .bind(username.as_str())
+    .fetch_all(&state.db)
+    .await?;
+
+    Ok(HttpResponse::Ok().json(ApiResponse {
+        success: true,
+        data: Some(collections),
+        message: None,
+        next_cursor: None,
+    }))
+}
+
+async fn update_collection(
+    state: web::Data<AppState>,
+    AuthenticatedUser(user_id): AuthenticatedUser,
+    collection_id: web::Path<Uuid>,
+    req: web::Json<UpdateCollectionRequest>,
+) -> ApiResult<HttpResponse> {
+    req.validate()?;
+    let collection_id = collection_id.into_inner();
+
+    let result = sqlx::query(
+        "UPDATE collections
+         SET title = COALESCE($3, title),
+             description = COALESCE($4, description),
+             featured = COALESCE($5, featured),
+             updated_at = NOW()
+         WHERE id = $1 AND user_id = $2"
+    )
+    .bind(collection_id)
+    .bind(user_id)
+    .bind(req.title.as_deref().map(str::trim))
+    .bind(req.description.as_deref().map(str::trim))
+    .bind(req.featured)
+    .execute(&state.db)
+    .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(ApiError::NotFound("Collection not found".to_string()));
+    }
+
+    Ok(HttpResponse::Ok().json(ApiResponse {
+        success: true,
+        data: Some(fetch_summary(&state.db, collection_id).await?),
+        message: None,
+        next_cursor: None,
+    }))
+}
+
+async fn delete_collection(
+    state: web::Data<AppState>,
+    AuthenticatedUser(user_id): AuthenticatedUser,
+    collection_id: web::Path<Uuid>,
+) -> ApiResult<HttpResponse> {
+    let result = sqlx::query("DELETE FROM collections WHERE id = $1 AND user_id = $2")
+        .bind(collection_id.into_inner())
+        .bind(user_id)
+        .execute(&state.db)
+        .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(ApiError::NotFound("Collection not found".to_string()));
+    }
+
+    Ok(HttpResponse::Ok().json(ApiResponse {
+        success: true,
+        data: Some("Collection deleted successfully"),
+        message: None,
+        next_cursor: None,
+    }))
+}
+
+// Inserting at a position shifts the entries from there on down by one.
+async fn add_tweet(
+    state: web::Data<AppState>,
+    AuthenticatedUser(user_id): AuthenticatedUser,
+    collection_id: web::Path<Uuid>,
+    req: web::Json<AddTweetRequest>,
+) -> ApiResult<HttpResponse> {
+    let collection_id = collection_id.into_inner();
+    let mut tx = state.db.begin().await?;
+    lock_owned(&mut tx, collection_id, user_id).await?;
+
+    // Public and live, and not by someone who blocked the curator
+    let addable = sqlx::query_scalar::<_, bool>(
+        "SELECT EXISTS(
+             SELECT 1 FROM tweets t
+             WHERE t.id = $1 AND t.deleted_at IS NULL AND t.visibility = 'public'
+               AND NOT EXISTS (SELECT 1 FROM blocks b WHERE b.blocker_id = t.user_id AND b.blocked_id = $2)
+         )"
+    )
+    .bind(req.tweet_id)
+    .bind(user_id)
+    .fetch_one(&mut *tx)
+    .await?;
+    if !addable {
+        return Err(ApiError::NotFound("Tweet not found".to_string()));
+    }
+
+    let (count, already) = sqlx::query_as::<_, (i64, bool)>(
+        "SELECT COUNT(*), COALESCE(BOOL_OR(tweet_id = $2), FALSE) FROM collection_tweets WHERE collection_id = $1"
+    )
+    .bind(collection_id)
+    .bind(req.tweet_id)
+    .fetch_one(&mut *tx)
+    .await?;
+    if already {
+        return Err(ApiError::Conflict("Tweet is already in this collection".to_string()));
+    }
+    if count >= MAX_TWEETS_PER_COLLECTION {
+        return Err(ApiError::Forbidden(format!("A collection can hold at most {} tweets", MAX_TWEETS_PER_COLLECTION)));
+    }
+
+    let position = insert_position(req.position, count);
+    sqlx::query("UPDATE collection_tweets SET position = position + 1 WHERE collection_id = $1 AND position >= $2")
+        .bind(collection_id)
+        .bind(position)
+        .execute(&mut *tx)
+        .await?;
+    sqlx::query("INSERT INTO collection_tweets (collection_id, tweet_id, position) VALUES ($1, $2, $3)")
+        .bind(collection_id)
+        .bind(req.tweet_id)
+        .bind(position)
+        .execute(&mut *tx)
+        .await?;
+    touch(&mut tx, collection_id).await?;
+
+    tx.commit().await?;
+
+    Ok(HttpResponse::Created().json(ApiResponse {
+        success: true,
+        data: Some(fetch_summary(&state.db, collection_id).await?),
+        message: None,
+        next_cursor: None,
+    }))
+}
+
+async fn remove_tweet(
+    state: web::Data<AppState>,
+    AuthenticatedUser(user_id): AuthenticatedUser,
+    path: web::Path<(Uuid, Uuid)>,
+) -> ApiResult<HttpResponse> {
+    let (collection_id, tweet_id) = path.into_inner();
+    let mut tx = state.db.begin().await?;
+    lock_owned(&mut tx, collection_id, user_id).await?;
+
+    let position = sqlx::query_scalar::<_, i32>(
+        "DELETE FROM collection_tweets WHERE collection_id = $1 AND tweet_id = $2 RETURNING position"
+    )
+    .bind(collection_id)
+    .bind(tweet_id)
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or_else(|| ApiError::NotFound("Tweet is not in this collection".to_string()))?;
+
+    sqlx::query("UPDATE collection_tweets SET position = position - 1 WHERE collection_id = $1 AND position > $2")
+        .bind(collection_id)
+        .bind(position)
+        .execute(&mut *tx)
+        .await?;
+    touch(&mut tx, collection_id).await?;
+
+    tx.commit().await?;
+
+    Ok(HttpResponse::Ok().json(ApiResponse {
+        success: true,
+        data: Some(fetch_summary(&state.db, collection_id).await?),
+        message: None,
+        next_cursor: None,
+    }))
+}
+
+async fn reorder_tweets(
+    state: web::Data<AppState>,
+    AuthenticatedUser(user_id): AuthenticatedUser,
+    collection_id: web::Path<Uuid>,
+    req: web::Json<ReorderTweetsRequest>,
+) -> ApiResult<HttpResponse> {
+    let collection_id = collection_id.into_inner();
+    let mut tx = state.db.begin().await?;
+    lock_owned(&mut tx, collection_id, user_id).await?;
+
+    let current = sqlx::query_scalar::<_, Uuid>("SELECT tweet_id FROM collection_tweets WHERE collection_id = $1")
+        .bind(collection_id)
+        .fetch_all(&mut *tx)
+        .await?;
+    if !is_permutation(&current, &req.tweet_ids) {
+        return Err(ApiError::BadRequest("tweet_ids must list every tweet in the collection exactly once".to_string()));
+    }
+
+    sqlx::query(
+        "UPDATE collection_tweets ct SET position = o.position - 1
+         FROM UNNEST($2::uuid[]) WITH ORDINALITY AS o(tweet_id, position)
+         WHERE ct.collection_id = $1 AND ct.tweet_id = o.tweet_id"
+    )
+    .bind(collection_id)
+    .bind(&req.tweet_ids)
+    .execute(&mut *tx)
+    .await?;
+    touch(&mut tx, collection_id).await?;
+
+    tx.commit().await?;
+
+    Ok(HttpResponse::Ok().json(ApiResponse {
+        success: true,
+        data: Some(fetch_summary(&state.db, collection_id).await?),
+        message: None,
+        next_cursor: None,
+    }))
+}
+
+fn insert_position(requested: Option<i32>, count: i64) -> i32 {
+    let end = count as i32;
+    requested.map_or(end, |position| position.clamp(0, end))
+}
+
+fn is_permutation(current: &[Uuid], requested: &[Uuid]) -> bool {
+    let requested_set: HashSet<_> = requested.iter().collect();
+    requested.len() == current.len()
+        && requested_set.len() == requested.len()
+        && current.iter().all(|id| requested_set.contains(id))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn clamps_insert_position() {
+        assert_eq!(insert_position(None, 3), 3);
+        assert_eq!(insert_position(Some(0), 3), 0);
+        assert_eq!(insert_position(Some(10), 3), 3);
+        assert_eq!(insert_position(Some(-1), 3), 0);
+    }
+
+    #[test]
+    fn reorder_must_list_every_tweet_once() {
+        let (a, b, c) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+        assert!(is_permutation(&[a, b, c], &[c, a, b]));
+        assert!(is_permutation(&[], &[]));
+        assert!(!is_permutation(&[a, b, c], &[a, b]));
+        assert!(!is_permutation(&[a, b], &[a, a]));
+        assert!(!is_permutation(&[a, b], &[a, c]));
+    }
+}
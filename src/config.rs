@@ -0,0 +1,378 @@
+use reqwest::Url;
+use std::env;
+use std::fmt;
+use std::path::Path;
+use std::str::FromStr;
+
+use crate::abuse::AbuseLimits;
+use crate::analytics::Analytics;
+use crate::archive::ArchivePolicy;
+use crate::auth::{JwtKeys, TokenLifetimes};
+use crate::backups::Backups;
+use crate::bots::BotRules;
+use crate::crypto::FieldCipher;
+use crate::experiments::Experiments;
+use crate::http_client::HttpClient;
+use crate::inbound_email::InboundEmail;
+use crate::instance::InstanceSettings;
+use crate::integrations::CrossPosting;
+use crate::jobs::Jobs;
+use crate::mailer::{self, Mailer};
+use crate::maintenance::Maintenance;
+use crate::media::MediaUploads;
+use crate::media_proxy::MediaProxy;
+use crate::pagination::CursorCodec;
+use crate::probation::ProbationPolicy;
+use crate::ranking::RankingWeights;
+use crate::rate_limit::RateLimiter;
+use crate::secrets;
+use crate::share_cards::ShareCards;
+use crate::trends::Trends;
+use crate::triggers::Triggers;
+use crate::twitter_archive::TwitterArchive;
+use crate::unread::UnreadCounts;
+
+// Startup configuration. Everything is read from the environment, after
+// loading the KEY=VALUE file named by CONFIG_FILE (if any); variables already
+// set in the environment win over the file, so a deployment can override
+// single values. Every section is validated before the server starts and all
+// problems are reported together instead of panicking on the first.
+//
+// This module is the only reader of the environment: the sections' own
+// `from_env` constructors go through `var`, `parse_var` and `flag` below.
+pub struct Config {
+    pub server: ServerConfig,
+    pub database: DatabaseConfig,
+    pub jwt_keys: JwtKeys,
+    pub cors: CorsConfig,
+    pub admin_usernames: Vec<String>,
+    pub http: HttpClient,
+    pub rate_limits: RateLimiter,
+    pub media: MediaUploads,
+    pub mailer: Box<dyn Mailer>,
+    pub instance: InstanceSettings,
+    pub analytics: Analytics,
+    pub pii: FieldCipher,
+    pub cursors: CursorCodec,
+    pub tokens: TokenLifetimes,
+    pub ranking: RankingWeights,
+    pub media_proxy: MediaProxy,
+    pub maintenance: Maintenance,
+    pub abuse: AbuseLimits,
+    pub probation: ProbationPolicy,
+    pub archive: ArchivePolicy,
+    pub bots: BotRules,
+    pub triggers: Triggers,
+    pub inbound_email: InboundEmail,
+    pub cross_posting: CrossPosting,
+    // Without handlers; `setup` registers those
+    pub jobs: Jobs,
+    pub twitter_archive: TwitterArchive,
+    pub backups: Backups,
+    pub trends: Trends,
+    pub share_cards: ShareCards,
+    pub experiments: Experiments,
+    pub unread: UnreadCounts,
+    // INDEX_AUDIT_CREATE_MISSING: build missing indexes at startup instead of
+    // only warning about them
+    pub create_missing_indexes: bool,
+}
+
+#[derive(Clone)]
+pub struct ServerConfig {
+    pub host: String,
+    pub port: u16,
+    // Public URL of this server, without a trailing slash
+    pub base_url: String,
+}
+
+pub struct DatabaseConfig {
+    pub url: String,
+    // Overrides any password in the URL
+    pub password: Option<String>,
+}
+
+#[derive(Clone)]
+pub struct CorsConfig {
+    // None allows any origin
+    pub allowed_origins: Option<Vec<String>>,
+}
+
+#[derive(Debug)]
+pub struct ConfigError(Vec<String>);
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Invalid configuration:")?;
+        for problem in &self.0 {
+            writeln!(f, "  - {}", problem)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+impl Config {
+    pub async fn load() -> Result<Config, ConfigError> {
+        if let Ok(path) = env::var("CONFIG_FILE") {
+            load_file(Path::new(&path)).map_err(|e| ConfigError(vec![e]))?;
+        }
+
+        // Secrets may be fetched over HTTP, so the client comes first
+        let http = HttpClient::from_env().map_err(|e| ConfigError(vec![e]))?;
+        secrets::load(&http).await.map_err(|e| ConfigError(vec![e]))?;
+
+        let mut problems = Vec::new();
+        match Config::from_env(http, &mut problems) {
+            Some(config) if problems.is_empty() => Ok(config),
+            _ => Err(ConfigError(problems)),
+        }
+    }
+
+    // Reads every section before giving up, so `problems` ends up with all of
+    // them; None if any section failed.
+    fn from_env(http: HttpClient, problems: &mut Vec<String>) -> Option<Config> {
+        let server = collect(problems, ServerConfig::from_env());
+        let database = collect(problems, DatabaseConfig::from_env());
+        let jwt_secret = collect(problems, required_secret("JWT_SECRET"));
+        let jwt_keys = jwt_secret.as_ref().and_then(|secret| collect(problems, JwtKeys::from_env(secret)));
+        let pii = jwt_secret.as_ref().and_then(|secret| collect(problems, FieldCipher::from_env(secret)));
+        let cursors = jwt_secret.as_ref().and_then(|secret| collect(problems, CursorCodec::from_env(secret)));
+        let cors = collect(problems, CorsConfig::from_env());
+        let rate_limits = collect(problems, RateLimiter::from_env());
+        let media = server
+            .as_ref()
+            .and_then(|server| collect(problems, MediaUploads::from_env(&server.base_url, http.clone())));
+        let mailer = collect(problems, mailer::from_env(http.clone()));
+        let instance = collect(problems, InstanceSettings::from_env());
+        let analytics = collect(problems, Analytics::from_env());
+        let tokens = collect(problems, TokenLifetimes::from_env());
+        let ranking = collect(problems, RankingWeights::from_env());
+        let media_proxy = collect(problems, MediaProxy::from_env(http.clone()));
+        let maintenance = collect(problems, Maintenance::from_env());
+        let abuse = collect(problems, AbuseLimits::from_env());
+        let probation = collect(problems, ProbationPolicy::from_env());
+        let archive = collect(problems, ArchivePolicy::from_env());
+        let bots = collect(problems, BotRules::from_env());
+        let triggers = collect(problems, Triggers::from_env());
+        let cross_posting = collect(problems, CrossPosting::from_env());
+        let jobs = collect(problems, Jobs::from_env());
+        let twitter_archive = collect(problems, TwitterArchive::from_env());
+        let backups = collect(problems, Backups::from_env());
+        let trends = collect(problems, Trends::from_env());
+        let share_cards = collect(problems, ShareCards::from_env());
+        let unread = collect(problems, UnreadCounts::from_env());
+        let create_missing_indexes = collect(problems, flag("INDEX_AUDIT_CREATE_MISSING", false));
+
+        Some(Config {
+            server: server?,
+            database: database?,
+            jwt_keys: jwt_keys?,
+            cors: cors?,
+            admin_usernames: list("ADMIN_USERNAMES"),
+            http,
+            rate_limits: rate_limits?,
+            media: media?,
+            mailer: mailer?,
+            instance: instance?,
+            analytics: analytics?,
+            pii: pii?,
+            cursors: cursors?,
+            tokens: tokens?,
+            ranking: ranking?,
+            media_proxy: media_proxy?,
+            maintenance: maintenance?,
+            abuse: abuse?,
+            probation: probation?,
+            archive: archive?,
+            bots: bots?,
+            triggers: triggers?,
+            inbound_email: InboundEmail::from_env(),
+            cross_posting: cross_posting?,
+            jobs: jobs?,
+            twitter_archive: twitter_archive?,
+            backups: backups?,
+            trends: trends?,
+            share_cards: share_cards?,
+            experiments: Experiments::from_env(),
+            unread: unread?,
+            create_missing_indexes: create_missing_indexes?,
+        })
+    }
+}
+
+impl ServerConfig {
+    fn from_env() -> Result<Self, String> {
+        let host = var("SERVER_HOST").unwrap_or_else(|| "127.0.0.1".to_string());
+        let port = match var("SERVER_PORT") {
+            Some(port) => parse_port(&port).ok_or_else(|| format!("SERVER_PORT must be a port number, got '{}'", port))?,
+            None => 3000,
+        };
+        let base_url = match var("PUBLIC_BASE_URL") {
+            Some(url) if is_http_url(&url) => url,
+            Some(url) => return Err(format!("PUBLIC_BASE_URL must be an http(s) URL, got '{}'", url)),
+            None => format!("http://{}:{}", host, port),
+        };
+
+        Ok(ServerConfig {
+            host,
+            port,
+            base_url: base_url.trim_end_matches('/').to_string(),
+        })
+    }
+}
+
+impl DatabaseConfig {
+    fn from_env() -> Result<Self, String> {
+        Ok(DatabaseConfig {
+            url: required_secret("DATABASE_URL")?,
+            password: secrets::var("DATABASE_PASSWORD"),
+        })
+    }
+}
+
+impl CorsConfig {
+    // CORS_ALLOWED_ORIGINS: comma-separated origins, or `*` (the default)
+    fn from_env() -> Result<Self, String> {
+        let origins = list("CORS_ALLOWED_ORIGINS");
+        if origins.is_empty() || origins.iter().any(|origin| origin == "*") {
+            return Ok(CorsConfig { allowed_origins: None });
+        }
+        if let Some(invalid) = origins.iter().find(|origin| !is_origin(origin)) {
+            return Err(format!(
+                "CORS_ALLOWED_ORIGINS entries must look like https://example.com, got '{}'",
+                invalid
+            ));
+        }
+        Ok(CorsConfig { allowed_origins: Some(origins) })
+    }
+}
+
+fn collect<T>(problems: &mut Vec<String>, result: Result<T, String>) -> Option<T> {
+    result.map_err(|e| problems.push(e)).ok()
+}
+
+fn required_secret(key: &str) -> Result<String, String> {
+    secrets::var(key)
+        .filter(|value| !value.trim().is_empty())
+        .ok_or_else(|| format!("{} must be set", key))
+}
+
+pub fn var(key: &str) -> Option<String> {
+    env::var(key).ok()
+}
+
+// An optional setting parsed as `T`, `default` when unset
+pub fn parse_var<T: FromStr>(key: &str, default: T) -> Result<T, String> {
+    match var(key) {
+        Some(value) => value.trim().parse().map_err(|_| format!("{} must be a number, got '{}'", key, value)),
+        None => Ok(default),
+    }
+}
+
+// `parse_var` for counts and durations where zero would break things
+pub fn positive_var<T: FromStr + PartialOrd + Default>(key: &str, default: T) -> Result<T, String> {
+    let value = parse_var(key, default)?;
+    if value > T::default() {
+        Ok(value)
+    } else {
+        Err(format!("{} must be greater than 0", key))
+    }
+}
+
+// An on/off switch: true/1 or false/0
+pub fn flag(key: &str, default: bool) -> Result<bool, String> {
+    match var(key).as_deref().map(str::trim) {
+        Some("true" | "1") => Ok(true),
+        Some("false" | "0") => Ok(false),
+        Some(value) => Err(format!("{} must be true or false, got '{}'", key, value)),
+        None => Ok(default),
+    }
+}
+
+// Comma-separated values, blanks dropped
+pub fn list(key: &str) -> Vec<String> {
+    var(key)
+        .unwrap_or_default()
+        .split(',')
+        .map(|item| item.trim().to_string())
+        .filter(|item| !item.is_empty())
+        .collect()
+}
+
+fn load_file(path: &Path) -> Result<(), String> {
+    dotenv::from_path(path).map_err(|e| format!("Failed to read CONFIG_FILE {}: {}", path.display(), e))
+}
+
+fn parse_port(value: &str) -> Option<u16> {
+    value.trim().parse().ok().filter(|port| *port > 0)
+}
+
+fn is_http_url(value: &str) -> bool {
+    Url::parse(value).is_ok_and(|url| matches!(url.scheme(), "http" | "https") && url.host_str().is_some())
+}
+
+// Scheme and host (and port), nothing else
+fn is_origin(value: &str) -> bool {
+    Url::parse(value).is_ok_and(|url| {
+        matches!(url.scheme(), "http" | "https")
+            && url.host_str().is_some()
+            && url.path() == "/"
+            && !value.ends_with('/')
+            && url.query().is_none()
+            && url.fragment().is_none()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_ports() {
+        assert_eq!(parse_port("8080"), Some(8080));
+        assert_eq!(parse_port(" 80 "), Some(80));
+        assert_eq!(parse_port("0"), None);
+        assert_eq!(parse_port("70000"), None);
+        assert_eq!(parse_port("http"), None);
+    }
+
+    #[test]
+    fn accepts_only_bare_origins() {
+        assert!(is_origin("https://example.com"));
+        assert!(is_origin("http://localhost:5173"));
+        assert!(!is_origin("https://example.com/"));
+        assert!(!is_origin("https://example.com/app"));
+        assert!(!is_origin("example.com"));
+        assert!(!is_origin("ftp://example.com"));
+    }
+
+    #[test]
+    fn rejects_malformed_settings_instead_of_defaulting() {
+        // Keys unique to this test; the environment is shared between threads
+        env::set_var("CONFIG_TEST_COUNT", "12");
+        env::set_var("CONFIG_TEST_BAD_COUNT", "twelve");
+        env::set_var("CONFIG_TEST_ZERO", "0");
+        env::set_var("CONFIG_TEST_FLAG", "1");
+        env::set_var("CONFIG_TEST_BAD_FLAG", "yes");
+
+        assert_eq!(parse_var("CONFIG_TEST_COUNT", 5u32), Ok(12));
+        assert_eq!(parse_var("CONFIG_TEST_UNSET", 5u32), Ok(5));
+        assert!(parse_var("CONFIG_TEST_BAD_COUNT", 5u32).is_err());
+        assert_eq!(parse_var("CONFIG_TEST_ZERO", 5u32), Ok(0));
+        assert!(positive_var("CONFIG_TEST_ZERO", 5u32).is_err());
+        assert_eq!(flag("CONFIG_TEST_FLAG", false), Ok(true));
+        assert_eq!(flag("CONFIG_TEST_UNSET", true), Ok(true));
+        assert!(flag("CONFIG_TEST_BAD_FLAG", false).is_err());
+    }
+
+    #[test]
+    fn lists_every_problem() {
+        let error = ConfigError(vec!["JWT_SECRET must be set".to_string(), "DATABASE_URL must be set".to_string()]);
+        assert_eq!(
+            error.to_string(),
+            "Invalid configuration:\n  - JWT_SECRET must be set\n  - DATABASE_URL must be set\n"
+        );
+    }
+}
@@ -0,0 +1,279 @@
+use aes_gcm::aead::{Aead, KeyInit};
+use aes_gcm::{Aes256Gcm, Nonce};
+use base64::engine::general_purpose::{STANDARD, URL_SAFE_NO_PAD};
+use base64::Engine;
+use hmac::{Hmac, Mac};
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use sqlx::postgres::{PgTypeInfo, PgValueRef};
+use sqlx::{Decode, PgPool, Postgres, Type};
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::OnceLock;
+use uuid::Uuid;
+
+use crate::secrets;
+
+const PREFIX: &str = "enc:";
+const NONCE_LEN: usize = 12;
+const BACKFILL_BATCH: i64 = 500;
+
+static CIPHER: OnceLock<FieldCipher> = OnceLock::new();
+
+// Field-level encryption for personal data (currently the account email,
+// pending email changes and cross-posting credentials). Values are sealed with AES-256-GCM as
+// `enc:<key id>:<nonce + ciphertext>`, so a database dump alone reveals
+// nothing. Equality lookups go through a keyed blind index (HMAC-SHA256)
+// stored next to the ciphertext instead.
+//
+// PII_KEYS lists `id:base64 key` pairs, newest first. New values are sealed
+// with the first key; older ones stay readable until the startup backfill has
+// re-sealed them. Without PII_KEYS values are stored in the clear (but still
+// indexed), so existing installs keep working until keys are configured.
+pub struct FieldCipher {
+    current: Option<String>,
+    keys: HashMap<String, Aes256Gcm>,
+    index_key: Vec<u8>,
+}
+
+impl FieldCipher {
+    // PII_INDEX_KEY defaults to the JWT secret, domain-separated like the
+    // cursor key. Changing it invalidates every stored index.
+    pub fn from_env(jwt_secret: &str) -> Result<Self, String> {
+        let index_secret = secrets::var("PII_INDEX_KEY").unwrap_or_else(|| jwt_secret.to_string());
+        Self::new(secrets::var("PII_KEYS").as_deref().unwrap_or_default(), &index_secret)
+    }
+
+    fn new(keys_spec: &str, index_secret: &str) -> Result<Self, String> {
+        let mut current = None;
+        let mut keys = HashMap::new();
+
+        for entry in keys_spec.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+            let (id, key) = entry.split_once(':').ok_or("PII_KEYS entries must look like id:base64key")?;
+            if id.is_empty() || !id.chars().all(|c| c.is_ascii_alphanumeric()) {
+                return Err(format!("Invalid PII key id {:?}", id));
+            }
+            let key = STANDARD.decode(key).map_err(|_| format!("PII key {} is not valid base64", id))?;
+            let cipher = Aes256Gcm::new_from_slice(&key).map_err(|_| format!("PII key {} must be 32 bytes", id))?;
+
+            current.get_or_insert_with(|| id.to_string());
+            if keys.insert(id.to_string(), cipher).is_some() {
+                return Err(format!("Duplicate PII key id {}", id));
+            }
+        }
+
+        Ok(FieldCipher {
+            current,
+            keys,
+            index_key: format!("pii-blind-index:{}", index_secret).into_bytes(),
+        })
+    }
+
+    fn seal(&self, plaintext: &str) -> String {
+        let Some(id) = &self.current else {
+            return plaintext.to_string();
+        };
+
+        let mut nonce = [0u8; NONCE_LEN];
+        rand::thread_rng().fill_bytes(&mut nonce);
+        let ciphertext = self.keys[id]
+            .encrypt(Nonce::from_slice(&nonce), plaintext.as_bytes())
+            .expect("AES-GCM encryption failed");
+
+        let mut sealed = nonce.to_vec();
+        sealed.extend_from_slice(&ciphertext);
+        format!("{}{}:{}", PREFIX, id, URL_SAFE_NO_PAD.encode(sealed))
+    }
+
+    fn open(&self, stored: &str) -> Result<String, String> {
+        let Some(rest) = stored.strip_prefix(PREFIX) else {
+            return Ok(stored.to_string());
+        };
+
+        let (id, payload) = rest.split_once(':').ok_or("Malformed encrypted field")?;
+        let cipher = self.keys.get(id).ok_or_else(|| format!("Unknown PII key id {}", id))?;
+        let sealed = URL_SAFE_NO_PAD.decode(payload).map_err(|_| "Malformed encrypted field")?;
+        if sealed.len() < NONCE_LEN {
+            return Err("Malformed encrypted field".to_string());
+        }
+
+        let (nonce, ciphertext) = sealed.split_at(NONCE_LEN);
+        let plaintext = cipher
+            .decrypt(Nonce::from_slice(nonce), ciphertext)
+            .map_err(|_| format!("Failed to decrypt field sealed with key {}", id))?;
+        String::from_utf8(plaintext).map_err(|_| "Encrypted field is not UTF-8".to_string())
+    }
+
+    // Whether a stored value is already in the form `seal` would produce now
+    fn is_current(&self, stored: &str) -> bool {
+        match &self.current {
+            Some(id) => stored.starts_with(&format!("{}{}:", PREFIX, id)),
+            None => !stored.starts_with(PREFIX),
+        }
+    }
+
+    fn blind_index(&self, plaintext: &str) -> String {
+        let mut mac = <Hmac<Sha256> as Mac>::new_from_slice(&self.index_key).expect("HMAC accepts any key length");
+        mac.update(plaintext.as_bytes());
+        hex::encode(mac.finalize().into_bytes())
+    }
+}
+
+pub fn init(cipher: FieldCipher) {
+    if cipher.current.is_none() {
+        log::warn!("PII_KEYS is not set: personal data is stored unencrypted");
+    }
+    if CIPHER.set(cipher).is_err() {
+        panic!("field cipher initialised twice");
+    }
+}
+
+fn cipher() -> &'static FieldCipher {
+    CIPHER.get().expect("field cipher used before crypto::init")
+}
+
+// Value to store in an encrypted column.
+pub fn seal(plaintext: &str) -> String {
+    cipher().seal(plaintext)
+}
+
+// Value to store in (and look up by in) the matching blind index column.
+pub fn blind_index(plaintext: &str) -> String {
+    cipher().blind_index(plaintext)
+}
+
+// A personal-data column, decrypted when the row is read. Writes must go
+// through `seal` explicitly, so there's no Encode impl.
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Pii(pub String);
+
+impl fmt::Debug for Pii {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Pii(..)")
+    }
+}
+
+impl Type<Postgres> for Pii {
+    fn type_info() -> PgTypeInfo {
+        <String as Type<Postgres>>::type_info()
+    }
+
+    fn compatible(ty: &PgTypeInfo) -> bool {
+        <String as Type<Postgres>>::compatible(ty)
+    }
+}
+
+impl<'r> Decode<'r, Postgres> for Pii {
+    fn decode(value: PgValueRef<'r>) -> Result<Self, sqlx::error::BoxDynError> {
+        let stored = <&str as Decode<Postgres>>::decode(value)?;
+        Ok(Pii(cipher().open(stored)?))
+    }
+}
+
+// Fills in missing blind indexes and re-seals emails stored in the clear or
+// under an older key. Runs at startup; safe to interrupt.
+pub async fn backfill(db: &PgPool) -> Result<u64, sqlx::Error> {
+    let cipher = cipher();
+    let mut updated = 0;
+    let mut after = Uuid::nil();
+
+    loop {
+        let rows = sqlx::query_as::<_, (Uuid, String, Option<String>)>(
+            "SELECT id, email, email_hash FROM users WHERE id > $1 ORDER BY id LIMIT $2"
+        )
+        .bind(after)
+        .bind(BACKFILL_BATCH)
+        .fetch_all(db)
+        .await?;
+
+        let Some((last, _, _)) = rows.last() else {
+            break;
+        };
+        after = *last;
+
+        for (id, stored, email_hash) in rows {
+            if email_hash.is_some() && cipher.is_current(&stored) {
+                continue;
+            }
+            let email = match cipher.open(&stored) {
+                Ok(email) => email,
+                Err(e) => {
+                    log::error!("cannot re-seal email of user {}: {}", id, e);
+                    continue;
+                }
+            };
+
+            sqlx::query("UPDATE users SET email = $1, email_hash = $2 WHERE id = $3")
+                .bind(cipher.seal(&email))
+                .bind(cipher.blind_index(&email))
+                .bind(id)
+                .execute(db)
+                .await?;
+            updated += 1;
+        }
+    }
+
+    Ok(updated)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KEY_A: &str = "AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA=";
+    const KEY_B: &str = "AQEBAQEBAQEBAQEBAQEBAQEBAQEBAQEBAQEBAQEBAQE=";
+
+    #[test]
+    fn seal_round_trips_and_hides_plaintext() {
+        let cipher = FieldCipher::new(&format!("a:{}", KEY_A), "index").unwrap();
+        let sealed = cipher.seal("alice@example.com");
+        assert!(sealed.starts_with("enc:a:"));
+        assert!(!sealed.contains("alice"));
+        assert_ne!(sealed, cipher.seal("alice@example.com"));
+        assert_eq!(cipher.open(&sealed).unwrap(), "alice@example.com");
+    }
+
+    #[test]
+    fn old_keys_stay_readable_after_rotation() {
+        let old = FieldCipher::new(&format!("a:{}", KEY_A), "index").unwrap();
+        let rotated = FieldCipher::new(&format!("b:{},a:{}", KEY_B, KEY_A), "index").unwrap();
+        let sealed = old.seal("bob@example.com");
+        assert_eq!(rotated.open(&sealed).unwrap(), "bob@example.com");
+        assert!(!rotated.is_current(&sealed));
+        assert!(rotated.is_current(&rotated.seal("bob@example.com")));
+    }
+
+    #[test]
+    fn plaintext_passes_through_without_keys() {
+        let cipher = FieldCipher::new("", "index").unwrap();
+        assert_eq!(cipher.seal("carol@example.com"), "carol@example.com");
+        assert_eq!(cipher.open("carol@example.com").unwrap(), "carol@example.com");
+        assert!(cipher.is_current("carol@example.com"));
+    }
+
+    #[test]
+    fn tampered_values_are_rejected() {
+        let cipher = FieldCipher::new(&format!("a:{}", KEY_A), "index").unwrap();
+        let mut sealed = cipher.seal("dave@example.com");
+        sealed.push('A');
+        assert!(cipher.open(&sealed).is_err());
+        assert!(cipher.open("enc:zz:AAAA").is_err());
+    }
+
+    #[test]
+    fn blind_index_is_deterministic_and_keyed() {
+        let a = FieldCipher::new("", "one").unwrap();
+        let b = FieldCipher::new("", "two").unwrap();
+        assert_eq!(a.blind_index("erin@example.com"), a.blind_index("erin@example.com"));
+        assert_ne!(a.blind_index("erin@example.com"), b.blind_index("erin@example.com"));
+    }
+
+    #[test]
+    fn rejects_bad_key_specs() {
+        assert!(FieldCipher::new("nokey", "index").is_err());
+        assert!(FieldCipher::new("a:AAAA", "index").is_err());
+        assert!(FieldCipher::new(&format!("a:{},a:{}", KEY_A, KEY_B), "index").is_err());
+    }
+}
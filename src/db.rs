@@ -1,10 +1,51 @@
-use sqlx::{postgres::PgPoolOptions, PgPool};
+use futures_util::future::LocalBoxFuture;
+use sqlx::migrate::Migrator;
+use sqlx::postgres::{PgConnectOptions, PgPoolOptions};
+use sqlx::{PgConnection, PgPool};
+use std::str::FromStr;
 use std::time::Duration;
 
-pub async fn create_pool(database_url: &str) -> Result<PgPool, sqlx::Error> {
+// `password`, when given, overrides any password in the URL so it can be
+// kept in a secret of its own.
+pub async fn create_pool(database_url: &str, password: Option<&str>) -> Result<PgPool, sqlx::Error> {
+    let mut options = PgConnectOptions::from_str(database_url)?;
+    if let Some(password) = password {
+        options = options.password(password);
+    }
+
     PgPoolOptions::new()
         .max_connections(5)
         .acquire_timeout(Duration::from_secs(3))
-        .connect(database_url)
+        .connect_with(options)
         .await
 }
+
+// Migrations embedded at build time; the highest version is the schema this
+// build expects.
+pub static MIGRATOR: Migrator = sqlx::migrate!("./migrations");
+
+// Runs `f` in a transaction, committed when it returns Ok and rolled back
+// when it returns Err:
+//
+//   db::with_tx(&state.db, move |conn| Box::pin(async move { ... })).await?
+//
+// The closure can't borrow from the handler, so it takes what it needs by value.
+pub async fn with_tx<T, E, F>(db: &PgPool, f: F) -> Result<T, E>
+where
+    F: for<'c> FnOnce(&'c mut PgConnection) -> LocalBoxFuture<'c, Result<T, E>>,
+    E: From<sqlx::Error>,
+{
+    let mut tx = db.begin().await?;
+    match f(&mut tx).await {
+        Ok(value) => {
+            tx.commit().await?;
+            Ok(value)
+        }
+        Err(e) => {
+            if let Err(rollback) = tx.rollback().await {
+                log::warn!("rolling back failed: {}", rollback);
+            }
+            Err(e)
+        }
+    }
+}
@@ -0,0 +1,283 @@
+use actix_web::body::MessageBody;
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::http::header::{HeaderName, HeaderValue, LINK};
+use actix_web::middleware::Next;
+use actix_web::{web, Error, HttpResponse};
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::Serialize;
+use sqlx::{FromRow, PgPool};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use uuid::Uuid;
+
+use crate::auth::{self, AdminUser};
+use crate::error::ApiResult;
+use crate::models::{ApiResponse, UsageQuery};
+use crate::AppState;
+
+// Deprecated routes, by method and route pattern. Responses from them carry
+// `Deprecation` (RFC 9745), `Sunset` (RFC 8594) once a date is set and a
+// `Link` to the successor, and the `meta` block says the same in words.
+// Calls are counted per day and caller, so staff can see who still has to
+// move before a route goes away.
+//
+// To deprecate a route, add it here; dates are UTC days, `YYYY-MM-DD`.
+pub const DEPRECATED_ROUTES: &[DeprecatedRoute] = &[DeprecatedRoute {
+    method: "GET",
+    route: "/api/hashtags/trending",
+    since: "2026-10-15",
+    sunset: None,
+    successor: Some("/api/trends"),
+}];
+
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.route("/api/admin/deprecations", web::get().to(get_report));
+}
+
+#[derive(Debug)]
+pub struct DeprecatedRoute {
+    pub method: &'static str,
+    pub route: &'static str,
+    pub since: &'static str,
+    pub sunset: Option<&'static str>,
+    pub successor: Option<&'static str>,
+}
+
+impl DeprecatedRoute {
+    pub fn warning(&self) -> String {
+        let mut warning = format!("{} {} is deprecated", self.method, self.route);
+        if let Some(sunset) = self.sunset {
+            warning.push_str(&format!(" and stops working on {}", sunset));
+        }
+        if let Some(successor) = self.successor {
+            warning.push_str(&format!("; use {} instead", successor));
+        }
+        warning
+    }
+
+    fn deprecation_header(&self) -> String {
+        format!("@{}", day_start(self.since).timestamp())
+    }
+
+    fn sunset_header(&self) -> Option<String> {
+        self.sunset.map(|day| day_start(day).format("%a, %d %b %Y %H:%M:%S GMT").to_string())
+    }
+}
+
+fn day_start(day: &str) -> DateTime<Utc> {
+    NaiveDate::parse_from_str(day, "%Y-%m-%d")
+        .expect("deprecation dates are YYYY-MM-DD")
+        .and_hms_opt(0, 0, 0)
+        .unwrap()
+        .and_utc()
+}
+
+pub fn lookup(method: &str, route: &str) -> Option<&'static DeprecatedRoute> {
+    DEPRECATED_ROUTES.iter().find(|d| d.method == method && d.route == route)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct CallKey {
+    day: NaiveDate,
+    method: &'static str,
+    route: &'static str,
+    user_id: Option<Uuid>,
+}
+
+// Calls to deprecated routes, gathered in memory until the scheduler flushes
+// them to `deprecated_route_calls`.
+#[derive(Clone, Default)]
+pub struct Deprecations {
+    calls: Arc<Mutex<HashMap<CallKey, i64>>>,
+}
+
+impl Deprecations {
+    fn count(&self, route: &'static DeprecatedRoute, user_id: Option<Uuid>) {
+        let key = CallKey {
+            day: Utc::now().date_naive(),
+            method: route.method,
+            route: route.route,
+            user_id,
+        };
+        *self.calls.lock().unwrap().entry(key).or_default() += 1;
+    }
+
+    // On failure the counts are kept for the next attempt.
+    pub async fn flush(&self, db: &PgPool) -> Result<(), sqlx::Error> {
+        let pending = std::mem::take(&mut *self.calls.lock().unwrap());
+        if pending.is_empty() {
+            return Ok(());
+        }
+
+        let mut days = Vec::with_capacity(pending.len());
+        let mut methods = Vec::with_capacity(pending.len());
+        let mut routes = Vec::with_capacity(pending.len());
+        let mut user_ids = Vec::with_capacity(pending.len());
+        let mut calls = Vec::with_capacity(pending.len());
+        for (key, count) in &pending {
+            days.push(key.day);
+            methods.push(key.method);
+            routes.push(key.route);
+            user_ids.push(key.user_id);
+            calls.push(*count);
+        }
+
+        // Calls by accounts deleted since are dropped rather than failing the batch
+        let result = sqlx::query(
+            "INSERT INTO deprecated_route_calls (day, method, route, user_id, calls)
+             SELECT c.day, c.method, c.route, c.user_id, c.calls
+             FROM UNNEST($1::date[], $2::text[], $3::text[], $4::uuid[], $5::bigint[]) AS c(day, method, route, user_id, calls)
+             WHERE c.user_id IS NULL OR EXISTS (SELECT 1 FROM users u WHERE u.id = c.user_id)
+             ON CONFLICT (day, route, method, COALESCE(user_id, '00000000-0000-0000-0000-000000000000'::uuid)) DO UPDATE
+             SET calls = deprecated_route_calls.calls + EXCLUDED.calls"
+        )
+        .bind(&days)
+        .bind(&methods)
+        .bind(&routes)
+        .bind(&user_ids)
+        .bind(&calls)
+        .execute(db)
+        .await;
+
+        if let Err(e) = result {
+            let mut current = self.calls.lock().unwrap();
+            for (key, count) in pending {
+                *current.entry(key).or_default() += count;
+            }
+            return Err(e);
+        }
+
+        Ok(())
+    }
+}
+
+// Adds the deprecation headers to responses from deprecated routes and
+// counts the call.
+pub async fn annotate<B: MessageBody + 'static>(req: ServiceRequest, next: Next<B>) -> Result<ServiceResponse<B>, Error> {
+    let Some(deprecated) = req.match_pattern().and_then(|route| lookup(req.method().as_str(), &route)) else {
+        return next.call(req).await;
+    };
+
+    if let Some(state) = req.app_data::<web::Data<AppState>>() {
+        state.deprecations.count(deprecated, auth::request_user_id(req.request()).ok());
+    }
+
+    let mut res = next.call(req).await?;
+    let headers = res.headers_mut();
+    if let Ok(value) = HeaderValue::from_str(&deprecated.deprecation_header()) {
+        headers.insert(HeaderName::from_static("deprecation"), value);
+    }
+    if let Some(Ok(value)) = deprecated.sunset_header().map(|date| HeaderValue::from_str(&date)) {
+        headers.insert(HeaderName::from_static("sunset"), value);
+    }
+    if let Some(Ok(value)) = deprecated.successor.map(|route| HeaderValue::from_str(&format!("<{}>; rel=\"successor-version\"", route))) {
+        headers.append(LINK, value);
+    }
+    Ok(res)
+}
+
+#[derive(Debug, Serialize)]
+pub struct DeprecationReport {
+    pub method: &'static str,
+    pub route: &'static str,
+    pub since: &'static str,
+    pub sunset: Option<&'static str>,
+    pub successor: Option<&'static str>,
+    pub calls: i64,
+    pub anonymous_calls: i64,
+    // Signed-in callers, most calls first
+    pub callers: Vec<Caller>,
+}
+
+#[derive(Debug, Serialize, FromRow)]
+pub struct Caller {
+    #[serde(skip)]
+    method: String,
+    #[serde(skip)]
+    route: String,
+    pub user_id: Option<Uuid>,
+    pub username: Option<String>,
+    pub calls: i64,
+    pub last_called: NaiveDate,
+}
+
+// Every deprecated route with its calls over the last `days` days (30 by
+// default) and who made them. A route nobody calls any more is safe to remove.
+async fn get_report(state: web::Data<AppState>, _admin: AdminUser, query: web::Query<UsageQuery>) -> ApiResult<HttpResponse> {
+    let days = query.days.unwrap_or(30).clamp(1, 365);
+
+    let callers = sqlx::query_as::<_, Caller>(
+        "SELECT c.method, c.route, c.user_id, u.username, SUM(c.calls)::bigint AS calls, MAX(c.day) AS last_called
+         FROM deprecated_route_calls c
+         LEFT JOIN users u ON u.id = c.user_id
+         WHERE c.day > CURRENT_DATE - $1
+         GROUP BY c.method, c.route, c.user_id, u.username
+         ORDER BY calls DESC, u.username"
+    )
+    .bind(days)
+    .fetch_all(&state.db)
+    .await?;
+
+    let mut reports: Vec<DeprecationReport> = DEPRECATED_ROUTES
+        .iter()
+        .map(|d| DeprecationReport {
+            method: d.method,
+            route: d.route,
+            since: d.since,
+            sunset: d.sunset,
+            successor: d.successor,
+            calls: 0,
+            anonymous_calls: 0,
+            callers: Vec::new(),
+        })
+        .collect();
+    for caller in callers {
+        let Some(report) = reports.iter_mut().find(|r| r.method == caller.method && r.route == caller.route) else {
+            continue;
+        };
+        report.calls += caller.calls;
+        if caller.user_id.is_some() {
+            report.callers.push(caller);
+        } else {
+            report.anonymous_calls += caller.calls;
+        }
+    }
+
+    Ok(HttpResponse::Ok().json(ApiResponse {
+        success: true,
+        data: Some(reports),
+        message: None,
+        next_cursor: None,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn deprecated_routes_have_valid_dates() {
+        for route in DEPRECATED_ROUTES {
+            assert!(NaiveDate::parse_from_str(route.since, "%Y-%m-%d").is_ok(), "{}", route.route);
+            if let Some(sunset) = route.sunset {
+                assert!(NaiveDate::parse_from_str(sunset, "%Y-%m-%d").unwrap() > NaiveDate::parse_from_str(route.since, "%Y-%m-%d").unwrap());
+            }
+        }
+    }
+
+    #[test]
+    fn formats_headers_and_warning() {
+        let route = DeprecatedRoute {
+            method: "GET",
+            route: "/api/old",
+            since: "2024-05-06",
+            sunset: Some("2024-11-06"),
+            successor: Some("/api/new"),
+        };
+        assert_eq!(route.deprecation_header(), "@1714953600");
+        assert_eq!(route.sunset_header().as_deref(), Some("Wed, 06 Nov 2024 00:00:00 GMT"));
+        assert_eq!(route.warning(), "GET /api/old is deprecated and stops working on 2024-11-06; use /api/new instead");
+        assert!(lookup("GET", "/api/hashtags/trending").is_some());
+        assert!(lookup("POST", "/api/hashtags/trending").is_none());
+    }
+}
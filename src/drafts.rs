@@ -0,0 +1,274 @@
+use actix_web::{web, HttpResponse};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, PgPool};
+use std::time::Duration;
+use uuid::Uuid;
+
+use crate::auth::AuthenticatedUser;
+use crate::error::{ApiError, ApiResult};
+use crate::models::ApiResponse;
+use crate::repo::postgres::can_view_tweet;
+use crate::AppState;
+
+const MAX_DRAFTS_PER_USER: i64 = 100;
+// Generous on purpose: a draft can run past the tweet limit while it's edited
+pub const MAX_DRAFT_LENGTH: usize = 10_000;
+pub const MAX_DEVICE_LENGTH: usize = 64;
+// How often a WebSocket session writes the drafts it was sent
+pub const AUTOSAVE_INTERVAL: Duration = Duration::from_secs(3);
+
+// Drafts: composer text kept server-side so it survives a crash or a switch
+// of device. Every save names the `version` it was based on; a save based on
+// an older version than the stored one is a conflict and changes nothing,
+// so one device never silently overwrites another's newer text. The client
+// then decides, and saves again on top of the current version to overwrite.
+//
+// Besides these endpoints, the composer can stream drafts over the realtime
+// WebSocket (see `realtime::run_session`), which writes them every few
+// seconds. Saved and deleted drafts are pushed to the user's sessions.
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.route("/api/drafts", web::get().to(get_drafts))
+        .route("/api/drafts", web::post().to(create_draft))
+        .route("/api/drafts/{id}", web::get().to(get_draft))
+        .route("/api/drafts/{id}", web::put().to(update_draft))
+        .route("/api/drafts/{id}", web::delete().to(delete_draft));
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateDraftRequest {
+    #[serde(default)]
+    pub content: String,
+    pub parent_tweet_id: Option<Uuid>,
+    pub device: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateDraftRequest {
+    pub content: String,
+    pub base_version: i64,
+    pub device: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct Draft {
+    pub id: Uuid,
+    pub content: String,
+    pub parent_tweet_id: Option<Uuid>,
+    pub version: i64,
+    pub device: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+pub enum SaveOutcome {
+    Saved(Draft),
+    // The stored draft, which is newer than the save's base
+    Conflict(Draft),
+}
+
+const DRAFT_COLUMNS: &str = "id, content, parent_tweet_id, version, device, created_at, updated_at";
+
+pub fn check_content(content: &str) -> ApiResult<()> {
+    if content.chars().count() > MAX_DRAFT_LENGTH {
+        return Err(ApiError::BadRequest(format!("Drafts can be at most {} characters", MAX_DRAFT_LENGTH)));
+    }
+    Ok(())
+}
+
+// Device labels are free text from the client, kept short and never empty.
+pub fn device_label(device: Option<&str>) -> Option<String> {
+    device
+        .map(str::trim)
+        .filter(|d| !d.is_empty())
+        .map(|d| d.chars().take(MAX_DEVICE_LENGTH).collect())
+}
+
+pub async fn fetch(db: &PgPool, user_id: Uuid, draft_id: Uuid) -> Result<Option<Draft>, sqlx::Error> {
+    sqlx::query_as::<_, Draft>(&format!("SELECT {} FROM drafts WHERE id = $1 AND user_id = $2", DRAFT_COLUMNS))
+        .bind(draft_id)
+        .bind(user_id)
+        .fetch_optional(db)
+        .await
+}
+
+// Writes `content` if the draft is still at `base_version`. Resending text
+// that's already stored isn't a conflict, whatever the base.
+pub async fn save(
+    db: &PgPool,
+    user_id: Uuid,
+    draft_id: Uuid,
+    base_version: i64,
+    content: &str,
+    device: Option<&str>,
+) -> ApiResult<SaveOutcome> {
+    check_content(content)?;
+
+    let saved = sqlx::query_as::<_, Draft>(&format!(
+        "UPDATE drafts SET content = $4, device = $5, version = version + 1, updated_at = NOW()
+         WHERE id = $1 AND user_id = $2 AND version = $3
+         RETURNING {}",
+        DRAFT_COLUMNS
+    ))
+    .bind(draft_id)
+    .bind(user_id)
+    .bind(base_version)
+    .bind(content)
+    .bind(device)
+    .fetch_optional(db)
+    .await?;
+    if let Some(draft) = saved {
+        return Ok(SaveOutcome::Saved(draft));
+    }
+
+    let current = fetch(db, user_id, draft_id)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("Draft not found".to_string()))?;
+    if current.content == content {
+        Ok(SaveOutcome::Saved(current))
+    } else {
+        Ok(SaveOutcome::Conflict(current))
+    }
+}
+
+// Most recently edited first.
+async fn get_drafts(state: web::Data<AppState>, AuthenticatedUser(user_id): AuthenticatedUser) -> ApiResult<HttpResponse> {
+    let drafts = sqlx::query_as::<_, Draft>(&format!(
+        "SELECT {} FROM drafts WHERE user_id = $1 ORDER BY updated_at DESC",
+        DRAFT_COLUMNS
+    ))
+    .bind(user_id)
+    .fetch_all(&state.db)
+    .await?;
+
+    Ok(HttpResponse::Ok().json(ApiResponse {
+        success: true,
+        data: Some(drafts),
+        message: None,
+        next_cursor: None,
+    }))
+}
+
+async fn create_draft(
+    state: web::Data<AppState>,
+    AuthenticatedUser(user_id): AuthenticatedUser,
+    req: web::Json<CreateDraftRequest>,
+) -> ApiResult<HttpResponse> {
+    check_content(&req.content)?;
+
+    let mut conn = state.db.acquire().await?;
+    if let Some(parent_id) = req.parent_tweet_id {
+        if !can_view_tweet(&mut conn, parent_id, user_id).await? {
+            return Err(ApiError::NotFound("Parent tweet not found".to_string()));
+        }
+    }
+
+    let count = sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM drafts WHERE user_id = $1")
+        .bind(user_id)
+        .fetch_one(&mut *conn)
+        .await?;
+    if count >= MAX_DRAFTS_PER_USER {
+        return Err(ApiError::Forbidden(format!("You can have at most {} drafts", MAX_DRAFTS_PER_USER)));
+    }
+
+    let draft = sqlx::query_as::<_, Draft>(&format!(
+        "INSERT INTO drafts (user_id, content, parent_tweet_id, device) VALUES ($1, $2, $3, $4) RETURNING {}",
+        DRAFT_COLUMNS
+    ))
+    .bind(user_id)
+    .bind(&req.content)
+    .bind(req.parent_tweet_id)
+    .bind(device_label(req.device.as_deref()))
+    .fetch_one(&mut *conn)
+    .await?;
+
+    Ok(HttpResponse::Created().json(ApiResponse {
+        success: true,
+        data: Some(draft),
+        message: None,
+        next_cursor: None,
+    }))
+}
+
+async fn get_draft(
+    state: web::Data<AppState>,
+    AuthenticatedUser(user_id): AuthenticatedUser,
+    draft_id: web::Path<Uuid>,
+) -> ApiResult<HttpResponse> {
+    let draft = fetch(&state.db, user_id, draft_id.into_inner())
+        .await?
+        .ok_or_else(|| ApiError::NotFound("Draft not found".to_string()))?;
+
+    Ok(HttpResponse::Ok().json(ApiResponse {
+        success: true,
+        data: Some(draft),
+        message: None,
+        next_cursor: None,
+    }))
+}
+
+// A conflict answers 409; fetch the draft to see the newer text.
+async fn update_draft(
+    state: web::Data<AppState>,
+    AuthenticatedUser(user_id): AuthenticatedUser,
+    draft_id: web::Path<Uuid>,
+    req: web::Json<UpdateDraftRequest>,
+) -> ApiResult<HttpResponse> {
+    let device = device_label(req.device.as_deref());
+    let outcome = save(&state.db, user_id, draft_id.into_inner(), req.base_version, &req.content, device.as_deref()).await?;
+
+    match outcome {
+        SaveOutcome::Saved(draft) => Ok(HttpResponse::Ok().json(ApiResponse {
+            success: true,
+            data: Some(draft),
+            message: None,
+            next_cursor: None,
+        })),
+        SaveOutcome::Conflict(current) => Err(ApiError::Conflict(format!(
+            "Draft was changed on another device (now at version {})",
+            current.version
+        ))),
+    }
+}
+
+async fn delete_draft(
+    state: web::Data<AppState>,
+    AuthenticatedUser(user_id): AuthenticatedUser,
+    draft_id: web::Path<Uuid>,
+) -> ApiResult<HttpResponse> {
+    let result = sqlx::query("DELETE FROM drafts WHERE id = $1 AND user_id = $2")
+        .bind(draft_id.into_inner())
+        .bind(user_id)
+        .execute(&state.db)
+        .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(ApiError::NotFound("Draft not found".to_string()));
+    }
+
+    Ok(HttpResponse::Ok().json(ApiResponse::<()> {
+        success: true,
+        data: None,
+        message: Some("Draft deleted".to_string()),
+        next_cursor: None,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn device_labels_are_trimmed_and_bounded() {
+        assert_eq!(device_label(Some("  laptop ")).as_deref(), Some("laptop"));
+        assert_eq!(device_label(Some("   ")), None);
+        assert_eq!(device_label(None), None);
+        assert_eq!(device_label(Some(&"x".repeat(100))).map(|d| d.len()), Some(MAX_DEVICE_LENGTH));
+    }
+
+    #[test]
+    fn bounds_draft_length() {
+        assert!(check_content(&"a".repeat(MAX_DRAFT_LENGTH)).is_ok());
+        assert!(check_content(&"a".repeat(MAX_DRAFT_LENGTH + 1)).is_err());
+    }
+}
@@ -0,0 +1,93 @@
+use actix_web::{http::StatusCode, HttpResponse, ResponseError};
+use serde::Serialize;
+use std::fmt;
+
+use crate::models::ApiResponse;
+use crate::request_log;
+
+// Crate-wide handler error. Every variant renders as the usual
+// `ApiResponse` envelope with `success: false`, so handlers can bail out
+// with `?` and still produce consistent bodies and status codes. The body also
+// carries the request ID, to quote when reporting a problem.
+#[derive(Debug)]
+pub enum ApiError {
+    Db(sqlx::Error),
+    Validation(validator::ValidationErrors),
+    BadRequest(String),
+    Unauthorized(String),
+    Forbidden(String),
+    NotFound(String),
+    Conflict(String),
+    TooManyRequests(String),
+    Internal(String),
+    BadGateway(String),
+    ServiceUnavailable(String),
+}
+
+pub type ApiResult<T> = Result<T, ApiError>;
+
+impl fmt::Display for ApiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ApiError::Db(e) => write!(f, "Database error: {}", e),
+            ApiError::Validation(e) => write!(f, "Validation error: {}", e),
+            ApiError::BadRequest(msg)
+            | ApiError::Unauthorized(msg)
+            | ApiError::Forbidden(msg)
+            | ApiError::NotFound(msg)
+            | ApiError::Conflict(msg)
+            | ApiError::TooManyRequests(msg)
+            | ApiError::Internal(msg)
+            | ApiError::BadGateway(msg)
+            | ApiError::ServiceUnavailable(msg) => write!(f, "{}", msg),
+        }
+    }
+}
+
+impl ResponseError for ApiError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            ApiError::Db(_) | ApiError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            ApiError::Validation(_) | ApiError::BadRequest(_) => StatusCode::BAD_REQUEST,
+            ApiError::Unauthorized(_) => StatusCode::UNAUTHORIZED,
+            ApiError::Forbidden(_) => StatusCode::FORBIDDEN,
+            ApiError::NotFound(_) => StatusCode::NOT_FOUND,
+            ApiError::Conflict(_) => StatusCode::CONFLICT,
+            ApiError::TooManyRequests(_) => StatusCode::TOO_MANY_REQUESTS,
+            ApiError::BadGateway(_) => StatusCode::BAD_GATEWAY,
+            ApiError::ServiceUnavailable(_) => StatusCode::SERVICE_UNAVAILABLE,
+        }
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        HttpResponse::build(self.status_code()).json(ErrorBody {
+            response: ApiResponse {
+                success: false,
+                data: None,
+                message: Some(self.to_string()),
+                next_cursor: None,
+            },
+            request_id: request_log::current(),
+        })
+    }
+}
+
+#[derive(Serialize)]
+struct ErrorBody {
+    #[serde(flatten)]
+    response: ApiResponse<()>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    request_id: Option<String>,
+}
+
+impl From<sqlx::Error> for ApiError {
+    fn from(e: sqlx::Error) -> Self {
+        ApiError::Db(e)
+    }
+}
+
+impl From<validator::ValidationErrors> for ApiError {
+    fn from(e: validator::ValidationErrors) -> Self {
+        ApiError::Validation(e)
+    }
+}
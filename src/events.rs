@@ -0,0 +1,404 @@
+use actix_web::{web, HttpResponse};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, PgPool};
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::auth::{AdminUser, AuthenticatedUser};
+use crate::error::{ApiError, ApiResult};
+use crate::models::{ApiResponse, TweetResponse, TweetWithUser};
+use crate::pagination::{self, Cursor, PageQuery};
+use crate::handlers::tweets::tweet_page_bounds;
+use crate::handlers::hashtags::hashtag_tweets;
+use crate::{hashtags, AppState};
+
+const MAX_SLUG_LENGTH: usize = 60;
+const MAX_FEATURED_TWEETS: i64 = 20;
+
+// Event pages for news-style coverage: admins pick a hashtag, write a short
+// description and pin a few public tweets; `GET /api/events/{slug}` returns
+// all of that with a page of the hashtag's feed. Later feed pages come from
+// the same endpoint with `cursor`. Events are listed until their `ends_at`,
+// and their page stays reachable after that.
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.route("/api/events", web::get().to(list_events))
+        .route("/api/events/{slug}", web::get().to(get_event))
+        .route("/api/admin/events", web::post().to(create_event))
+        .route("/api/admin/events/{slug}", web::put().to(update_event))
+        .route("/api/admin/events/{slug}", web::delete().to(delete_event))
+        .route("/api/admin/events/{slug}/featured", web::post().to(feature_tweet))
+        .route("/api/admin/events/{slug}/featured/{tweet_id}", web::delete().to(unfeature_tweet));
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct CreateEventRequest {
+    pub slug: String,
+    #[validate(length(min = 1, max = 100))]
+    pub title: String,
+    #[serde(default)]
+    #[validate(length(max = 2000))]
+    pub description: String,
+    pub hashtag: String,
+    pub banner_image: Option<String>,
+    pub ends_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct UpdateEventRequest {
+    #[validate(length(min = 1, max = 100))]
+    pub title: Option<String>,
+    #[validate(length(max = 2000))]
+    pub description: Option<String>,
+    pub hashtag: Option<String>,
+    pub banner_image: Option<String>,
+    pub ends_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct FeatureTweetRequest {
+    pub tweet_id: Uuid,
+}
+
+#[derive(Debug, Serialize, FromRow)]
+pub struct Event {
+    pub id: Uuid,
+    pub slug: String,
+    pub title: String,
+    pub description: String,
+    pub hashtag: String,
+    pub banner_image: Option<String>,
+    pub ends_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct EventPage {
+    #[serde(flatten)]
+    pub event: Event,
+    // Only on the first page
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub featured: Option<Vec<TweetResponse>>,
+    pub tweets: Vec<TweetResponse>,
+}
+
+const EVENT_COLUMNS: &str = "id, slug, title, description, hashtag, banner_image, ends_at, created_at, updated_at";
+
+// Public tweets only, so a page reads the same for everyone
+const FEATURED_QUERY: &str = "SELECT t.id, t.user_id, t.parent_tweet_id, t.quoted_tweet_id, t.content, t.image_url,
+        t.likes_count, t.retweets_count, t.replies_count, t.visibility, t.created_at,
+        u.username as user_username, u.display_name as user_display_name,
+        u.bio as user_bio,
+        u.profile_image as user_profile_image, u.banner_image as user_banner_image,
+        u.followers_count as user_followers_count, u.following_count as user_following_count,
+        u.verified as user_verified, u.is_bot as user_is_bot, u.created_at as user_created_at
+     FROM event_featured_tweets f
+     INNER JOIN tweets t ON t.id = f.tweet_id
+     INNER JOIN users u ON u.id = t.user_id
+     WHERE f.event_id = $1 AND t.deleted_at IS NULL AND u.deactivated_at IS NULL
+       AND t.visibility = 'public'
+     ORDER BY f.position, f.added_at";
+
+fn valid_slug(slug: &str) -> bool {
+    !slug.is_empty()
+        && slug.len() <= MAX_SLUG_LENGTH
+        && slug.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+        && !slug.starts_with('-')
+        && !slug.ends_with('-')
+}
+
+// "#WorldCup" -> "worldcup", as stored by `hashtags::attach`
+fn normalize_hashtag(input: &str) -> Option<String> {
+    let tag = input.trim().trim_start_matches('#').to_lowercase();
+    match hashtags::extract(&format!("#{}", tag)).as_slice() {
+        [extracted] if *extracted == tag => Some(tag),
+        _ => None,
+    }
+}
+
+fn parse_hashtag(input: &str) -> ApiResult<String> {
+    normalize_hashtag(input).ok_or_else(|| ApiError::BadRequest("hashtag must be a single valid hashtag".to_string()))
+}
+
+async fn fetch_event(db: &PgPool, slug: &str) -> ApiResult<Event> {
+    sqlx::query_as::<_, Event>(&format!("SELECT {} FROM events WHERE slug = $1", EVENT_COLUMNS))
+        .bind(slug)
+        .fetch_optional(db)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("Event not found".to_string()))
+}
+
+// Events still running, soonest to end first, open-ended ones last.
+async fn list_events(state: web::Data<AppState>) -> ApiResult<HttpResponse> {
+    let events = sqlx::query_as::<_, Event>(&format!(
+        "SELECT {} FROM events
+         WHERE ends_at IS NULL OR ends_at > NOW()
+         ORDER BY ends_at NULLS LAST, created_at DESC",
+        EVENT_COLUMNS
+    ))
+    .fetch_all(&state.db)
+    .await?;
+
+    Ok(HttpResponse::Ok().json(ApiResponse {
+        success: true,
+        data: Some(events),
+        message: None,
+        next_cursor: None,
+    }))
+}
+
+async fn get_event(
+    state: web::Data<AppState>,
+    viewer: Option<AuthenticatedUser>,
+    slug: web::Path<String>,
+    page: web::Query<PageQuery>,
+) -> ApiResult<HttpResponse> {
+    let event = fetch_event(&state.db, &slug).await?;
+    let limit = page.limit();
+    let scope = format!("event:{}", event.id);
+    let (cursor, since) = tweet_page_bounds(state.repos.tweets.as_ref(), &page, &state.cursors, &scope).await?;
+
+    let featured = match page.cursor {
+        Some(_) => None,
+        None => Some(
+            sqlx::query_as::<_, TweetWithUser>(FEATURED_QUERY)
+                .bind(event.id)
+                .fetch_all(&state.db)
+                .await?
+                .into_iter()
+                .map(|tweet| tweet.into_response(false, false))
+                .collect(),
+        ),
+    };
+
+    let viewer_id = viewer.map(|AuthenticatedUser(id)| id);
+    let mut tweets = hashtag_tweets(&state.db, &event.hashtag, cursor, since, limit + 1, viewer_id).await?;
+    let next_cursor = pagination::next_cursor(&mut tweets, limit, &state.cursors, &scope, |t| Cursor {
+        created_at: t.created_at,
+        id: t.id,
+    });
+
+    Ok(HttpResponse::Ok().json(ApiResponse {
+        success: true,
+        data: Some(EventPage {
+            event,
+            featured,
+            tweets: tweets.into_iter().map(|tweet| tweet.into_response(false, false)).collect(),
+        }),
+        message: None,
+        next_cursor,
+    }))
+}
+
+async fn create_event(state: web::Data<AppState>, AdminUser(admin_id): AdminUser, req: web::Json<CreateEventRequest>) -> ApiResult<HttpResponse> {
+    req.validate()?;
+    if !valid_slug(&req.slug) {
+        return Err(ApiError::BadRequest(format!(
+            "slug must be at most {} lowercase letters, digits and dashes",
+            MAX_SLUG_LENGTH
+        )));
+    }
+    let hashtag = parse_hashtag(&req.hashtag)?;
+
+    let taken = sqlx::query_scalar::<_, bool>("SELECT EXISTS(SELECT 1 FROM events WHERE slug = $1)")
+        .bind(&req.slug)
+        .fetch_one(&state.db)
+        .await?;
+    if taken {
+        return Err(ApiError::Conflict("An event with this slug already exists".to_string()));
+    }
+
+    let event = sqlx::query_as::<_, Event>(&format!(
+        "INSERT INTO events (slug, title, description, hashtag, banner_image, ends_at, created_by)
+         VALUES ($1, $2, $3, $4, $5, $6, $7)
+         RETURNING {}",
+        EVENT_COLUMNS
+    ))
+    .bind(&req.slug)
+    .bind(req.title.trim())
+    .bind(req.description.trim())
+    .bind(&hashtag)
+    .bind(&req.banner_image)
+    .bind(req.ends_at)
+    .bind(admin_id)
+    .fetch_one(&state.db)
+    .await?;
+
+    Ok(HttpResponse::Created().json(ApiResponse {
+        success: true,
+        data: Some(event),
+        message: None,
+        next_cursor: None,
+    }))
+}
+
+async fn update_event(
+    state: web::Data<AppState>,
+    _admin: AdminUser,
+    slug: web::Path<String>,
+    req: web::Json<UpdateEventRequest>,
+) -> ApiResult<HttpResponse> {
+    req.validate()?;
+    let hashtag = req.hashtag.as_deref().map(parse_hashtag).transpose()?;
+
+    let event = sqlx::query_as::<_, Event>(&format!(
+        "UPDATE events
+         SET title = COALESCE($2, title),
+             description = COALESCE($3, description),
+             hashtag = COALESCE($4, hashtag),
+             banner_image = COALESCE($5, banner_image),
+             ends_at = COALESCE($6, ends_at),
+             updated_at = NOW()
+         WHERE slug = $1
+         RETURNING {}",
+        EVENT_COLUMNS
+    ))
+    .bind(slug.as_str())
+    .bind(req.title.as_deref().map(str::trim))
+    .bind(req.description.as_deref().map(str::trim))
+    .bind(hashtag)
+    .bind(&req.banner_image)
+    .bind(req.ends_at)
+    .fetch_optional(&state.db)
+    .await?
+    .ok_or_else(|| ApiError::NotFound("Event not found".to_string()))?;
+
+    Ok(HttpResponse::Ok().json(ApiResponse {
+        success: true,
+        data: Some(event),
+        message: None,
+        next_cursor: None,
+    }))
+}
+
+async fn delete_event(state: web::Data<AppState>, _admin: AdminUser, slug: web::Path<String>) -> ApiResult<HttpResponse> {
+    let result = sqlx::query("DELETE FROM events WHERE slug = $1")
+        .bind(slug.as_str())
+        .execute(&state.db)
+        .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(ApiError::NotFound("Event not found".to_string()));
+    }
+
+    Ok(HttpResponse::Ok().json(ApiResponse {
+        success: true,
+        data: Some("Event deleted successfully"),
+        message: None,
+        next_cursor: None,
+    }))
+}
+
+// Appends a public tweet to the event's featured list.
+async fn feature_tweet(
+    state: web::Data<AppState>,
+    _admin: AdminUser,
+    slug: web::Path<String>,
+    req: web::Json<FeatureTweetRequest>,
+) -> ApiResult<HttpResponse> {
+    let mut tx = state.db.begin().await?;
+
+    let event_id = sqlx::query_scalar::<_, Uuid>("SELECT id FROM events WHERE slug = $1 FOR UPDATE")
+        .bind(slug.as_str())
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("Event not found".to_string()))?;
+
+    let public = sqlx::query_scalar::<_, bool>(
+        "SELECT EXISTS(SELECT 1 FROM tweets WHERE id = $1 AND deleted_at IS NULL AND visibility = 'public')"
+    )
+    .bind(req.tweet_id)
+    .fetch_one(&mut *tx)
+    .await?;
+    if !public {
+        return Err(ApiError::NotFound("Tweet not found".to_string()));
+    }
+
+    let (count, next_position) = sqlx::query_as::<_, (i64, i32)>(
+        "SELECT COUNT(*), COALESCE(MAX(position) + 1, 0) FROM event_featured_tweets WHERE event_id = $1"
+    )
+    .bind(event_id)
+    .fetch_one(&mut *tx)
+    .await?;
+    if count >= MAX_FEATURED_TWEETS {
+        return Err(ApiError::BadRequest(format!("An event can feature at most {} tweets", MAX_FEATURED_TWEETS)));
+    }
+
+    let inserted = sqlx::query(
+        "INSERT INTO event_featured_tweets (event_id, tweet_id, position) VALUES ($1, $2, $3)
+         ON CONFLICT DO NOTHING"
+    )
+    .bind(event_id)
+    .bind(req.tweet_id)
+    .bind(next_position)
+    .execute(&mut *tx)
+    .await?;
+    if inserted.rows_affected() == 0 {
+        return Err(ApiError::Conflict("Tweet is already featured".to_string()));
+    }
+
+    sqlx::query("UPDATE events SET updated_at = NOW() WHERE id = $1")
+        .bind(event_id)
+        .execute(&mut *tx)
+        .await?;
+    tx.commit().await?;
+
+    Ok(HttpResponse::Created().json(ApiResponse::<()> {
+        success: true,
+        data: None,
+        message: Some("Tweet featured".to_string()),
+        next_cursor: None,
+    }))
+}
+
+async fn unfeature_tweet(
+    state: web::Data<AppState>,
+    _admin: AdminUser,
+    path: web::Path<(String, Uuid)>,
+) -> ApiResult<HttpResponse> {
+    let (slug, tweet_id) = path.into_inner();
+    let result = sqlx::query(
+        "DELETE FROM event_featured_tweets f USING events e
+         WHERE f.event_id = e.id AND e.slug = $1 AND f.tweet_id = $2"
+    )
+    .bind(&slug)
+    .bind(tweet_id)
+    .execute(&state.db)
+    .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(ApiError::NotFound("Tweet is not featured on this event".to_string()));
+    }
+
+    Ok(HttpResponse::Ok().json(ApiResponse::<()> {
+        success: true,
+        data: None,
+        message: Some("Tweet removed from featured".to_string()),
+        next_cursor: None,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accepts_url_friendly_slugs() {
+        assert!(valid_slug("world-cup-2026"));
+        assert!(!valid_slug(""));
+        assert!(!valid_slug("World-Cup"));
+        assert!(!valid_slug("-world"));
+        assert!(!valid_slug("world cup"));
+        assert!(!valid_slug(&"a".repeat(61)));
+    }
+
+    #[test]
+    fn normalizes_hashtags_like_tweets_do() {
+        assert_eq!(normalize_hashtag("#WorldCup"), Some("worldcup".to_string()));
+        assert_eq!(normalize_hashtag("election_2026"), Some("election_2026".to_string()));
+        assert_eq!(normalize_hashtag("#2026"), None);
+        assert_eq!(normalize_hashtag("two words"), None);
+        assert_eq!(normalize_hashtag(""), None);
+    }
+}
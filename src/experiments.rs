@@ -0,0 +1,85 @@
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use uuid::Uuid;
+
+use crate::config;
+
+// Experiments: features rolled out to a share of accounts, configured as
+// `EXPERIMENTS=compact_timeline:10,new_composer:50` (name and percentage).
+// Whether an account is in an experiment follows from a hash of its ID and
+// the experiment's name, so the assignment is the same on every request and
+// instance, and independent between experiments. Clients learn theirs from
+// the `meta` block of API responses; signed-out requests get none.
+#[derive(Debug, Clone, Default)]
+pub struct Experiments {
+    rollouts: Vec<(String, u8)>,
+}
+
+impl Experiments {
+    pub fn from_env() -> Self {
+        Experiments {
+            rollouts: config::var("EXPERIMENTS").map(|spec| parse(&spec)).unwrap_or_default(),
+        }
+    }
+
+    pub fn assignments(&self, user_id: Uuid) -> BTreeMap<String, bool> {
+        self.rollouts
+            .iter()
+            .map(|(name, percent)| (name.clone(), bucket(user_id, name) < *percent))
+            .collect()
+    }
+}
+
+// Entries that don't parse are skipped with a warning.
+fn parse(spec: &str) -> Vec<(String, u8)> {
+    spec.split(',')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+        .filter_map(|entry| {
+            let rollout = entry.split_once(':').and_then(|(name, percent)| {
+                let name = name.trim();
+                let percent = percent.trim().parse::<u8>().ok().filter(|p| *p <= 100)?;
+                (!name.is_empty()).then(|| (name.to_string(), percent))
+            });
+            if rollout.is_none() {
+                log::warn!("Ignoring experiment {:?}: expected name:percentage", entry);
+            }
+            rollout
+        })
+        .collect()
+}
+
+// Where an account falls for an experiment, from 0 to 99
+fn bucket(user_id: Uuid, name: &str) -> u8 {
+    let digest = Sha256::new().chain_update(user_id.as_bytes()).chain_update(name.as_bytes()).finalize();
+    (u16::from_be_bytes([digest[0], digest[1]]) % 100) as u8
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_rollouts() {
+        assert_eq!(
+            parse(" compact_timeline:10, new_composer : 100,,bad,over:101,:5"),
+            vec![("compact_timeline".to_string(), 10), ("new_composer".to_string(), 100)]
+        );
+    }
+
+    #[test]
+    fn assignments_are_stable_and_follow_the_percentage() {
+        let experiments = Experiments {
+            rollouts: vec![("none".to_string(), 0), ("half".to_string(), 50), ("all".to_string(), 100)],
+        };
+        let users: Vec<Uuid> = (0..1000).map(|_| Uuid::new_v4()).collect();
+        let enrolled = users.iter().filter(|id| experiments.assignments(**id)["half"]).count();
+        assert!((400..600).contains(&enrolled));
+
+        let user = users[0];
+        let assignments = experiments.assignments(user);
+        assert!(!assignments["none"]);
+        assert!(assignments["all"]);
+        assert_eq!(assignments, experiments.assignments(user));
+    }
+}
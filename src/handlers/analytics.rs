@@ -0,0 +1,172 @@
+use actix_web::{web, HttpRequest, HttpResponse};
+use chrono::Utc;
+use futures_util::StreamExt;
+use uuid::Uuid;
+
+use crate::auth::AuthenticatedUser;
+use crate::error::{ApiError, ApiResult};
+use crate::models::*;
+use crate::{analytics, short_links, AppState};
+
+// Ahead of the tweet routes, which would take "impressions" for an id
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.route("/t/{short_id}", web::get().to(redirect_short_link))
+        .route("/api/tweets/impressions", web::post().to(record_tweet_views))
+        .route("/api/tweets/{id}/referrals", web::get().to(get_tweet_referrals))
+        .route("/api/tweets/{id}/analytics", web::get().to(get_tweet_analytics))
+        .route("/api/users/me/analytics/export", web::get().to(export_analytics));
+}
+
+// `/t/{short_id}`: sends shared links on to the tweet's page in the web app,
+// counting the click and where it came from. Archived tweets resolve too.
+async fn redirect_short_link(state: web::Data<AppState>, req: HttpRequest, short_id: web::Path<String>) -> ApiResult<HttpResponse> {
+    let tweet_id = short_links::decode(&short_id).ok_or_else(|| ApiError::NotFound("Tweet not found".to_string()))?;
+
+    let username = sqlx::query_scalar::<_, String>(
+        "SELECT u.username FROM (
+             SELECT id, user_id FROM tweets WHERE deleted_at IS NULL
+             UNION ALL
+             SELECT id, user_id FROM archived_tweets
+         ) t
+         INNER JOIN users u ON t.user_id = u.id
+         WHERE t.id = $1 AND u.deactivated_at IS NULL"
+    )
+    .bind(tweet_id)
+    .fetch_optional(&state.db)
+    .await?
+    .ok_or_else(|| ApiError::NotFound("Tweet not found".to_string()))?;
+
+    let referer = req.headers().get("Referer").and_then(|v| v.to_str().ok());
+    state.analytics.count_referral(tweet_id, referer);
+
+    Ok(HttpResponse::Found()
+        .insert_header(("Location", format!("{}/{}/status/{}", state.base_url, username, tweet_id)))
+        .finish())
+}
+
+// Short link clicks per day and referring site, for the tweet's author.
+async fn get_tweet_referrals(
+    state: web::Data<AppState>,
+    AuthenticatedUser(user_id): AuthenticatedUser,
+    tweet_id: web::Path<Uuid>,
+    query: web::Query<UsageQuery>,
+) -> ApiResult<HttpResponse> {
+    let tweet_id = tweet_id.into_inner();
+    let days = query.days.unwrap_or(30).clamp(1, 90);
+
+    let owned = sqlx::query_scalar::<_, bool>(
+        "SELECT EXISTS(SELECT 1 FROM tweets WHERE id = $1 AND user_id = $2 AND deleted_at IS NULL)"
+    )
+    .bind(tweet_id)
+    .bind(user_id)
+    .fetch_one(&state.db)
+    .await?;
+
+    if !owned {
+        return Err(ApiError::NotFound("Tweet not found or unauthorized".to_string()));
+    }
+
+    Ok(HttpResponse::Ok().json(ApiResponse {
+        success: true,
+        data: Some(analytics::tweet_referrals(&state.db, tweet_id, days).await?),
+        message: None,
+        next_cursor: None,
+    }))
+}
+
+const MAX_VIEWS_PER_BATCH: usize = 200;
+
+// Views and profile clicks reported by clients, e.g. as tweets scroll into
+// view. Batches are counted as they come, duplicates within one dropped;
+// nothing about the reader is kept.
+async fn record_tweet_views(state: web::Data<AppState>, req: web::Json<TweetViewsRequest>) -> ApiResult<HttpResponse> {
+    let TweetViewsRequest { mut tweet_ids, mut profile_clicks } = req.into_inner();
+    if tweet_ids.len() + profile_clicks.len() > MAX_VIEWS_PER_BATCH {
+        return Err(ApiError::BadRequest(format!("At most {} tweets per batch", MAX_VIEWS_PER_BATCH)));
+    }
+
+    for ids in [&mut tweet_ids, &mut profile_clicks] {
+        ids.sort_unstable();
+        ids.dedup();
+    }
+    state.analytics.count_views(tweet_ids);
+    state.analytics.count_profile_clicks(profile_clicks);
+
+    Ok(HttpResponse::Accepted().json(ApiResponse::<()> {
+        success: true,
+        data: None,
+        message: None,
+        next_cursor: None,
+    }))
+}
+
+// Impressions, views, profile clicks and engagement per hour or day, for the
+// tweet's author. Hourly series cover a week at most.
+async fn get_tweet_analytics(
+    state: web::Data<AppState>,
+    AuthenticatedUser(user_id): AuthenticatedUser,
+    tweet_id: web::Path<Uuid>,
+    query: web::Query<TweetAnalyticsQuery>,
+) -> ApiResult<HttpResponse> {
+    let tweet_id = tweet_id.into_inner();
+    let max_days = match query.interval {
+        analytics::Interval::Hour => 7,
+        analytics::Interval::Day => 90,
+    };
+    let days = query.days.unwrap_or(7).clamp(1, max_days);
+
+    let owned = sqlx::query_scalar::<_, bool>(
+        "SELECT EXISTS(SELECT 1 FROM tweets WHERE id = $1 AND user_id = $2 AND deleted_at IS NULL)"
+    )
+    .bind(tweet_id)
+    .bind(user_id)
+    .fetch_one(&state.db)
+    .await?;
+
+    if !owned {
+        return Err(ApiError::NotFound("Tweet not found or unauthorized".to_string()));
+    }
+
+    Ok(HttpResponse::Ok().json(ApiResponse {
+        success: true,
+        data: Some(analytics::tweet_engagement(&state.db, tweet_id, days, query.interval).await?),
+        message: None,
+        next_cursor: None,
+    }))
+}
+
+const ANALYTICS_EXPORT_PAGE: i64 = 500;
+
+// Per-tweet totals over the range as CSV, one page of tweets at a time so a
+// prolific author's export never sits in memory whole.
+async fn export_analytics(
+    state: web::Data<AppState>,
+    AuthenticatedUser(user_id): AuthenticatedUser,
+    query: web::Query<AnalyticsExportQuery>,
+) -> HttpResponse {
+    let since = Utc::now() - chrono::Duration::days(query.range.days());
+    let db = state.db.clone();
+
+    // None once the last page is out
+    let pages = futures_util::stream::unfold(Some(None), move |before| {
+        let db = db.clone();
+        async move {
+            let before = before?;
+            match analytics::author_totals(&db, user_id, since, before, ANALYTICS_EXPORT_PAGE).await {
+                Ok(rows) => {
+                    let next = (rows.len() as i64 == ANALYTICS_EXPORT_PAGE)
+                        .then(|| rows.last().map(|row| (row.created_at, row.tweet_id)));
+                    let csv: String = rows.iter().map(analytics::TweetTotals::csv_row).collect();
+                    Some((Ok(web::Bytes::from(csv)), next))
+                }
+                Err(e) => Some((Err(e), None)),
+            }
+        }
+    });
+    let header = futures_util::stream::once(async { Ok(web::Bytes::from_static(analytics::TweetTotals::CSV_HEADER.as_bytes())) });
+
+    HttpResponse::Ok()
+        .content_type("text/csv; charset=utf-8")
+        .insert_header(("Content-Disposition", "attachment; filename=\"analytics.csv\""))
+        .streaming(header.chain(pages))
+}
@@ -0,0 +1,112 @@
+use actix_web::{web, HttpResponse};
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::auth::{AdminUser, AuthenticatedUser};
+use crate::error::{ApiError, ApiResult};
+use crate::models::*;
+use crate::AppState;
+
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.route("/api/announcements", web::get().to(get_announcements))
+        .route("/api/announcements/{id}/dismiss", web::post().to(dismiss_announcement))
+        .route("/api/admin/announcements", web::post().to(create_announcement))
+        .route("/api/admin/announcements/{id}", web::delete().to(delete_announcement));
+}
+
+async fn get_announcements(state: web::Data<AppState>, viewer: Option<AuthenticatedUser>) -> ApiResult<HttpResponse> {
+    // Currently active announcements; `dismissed` is only ever true for a
+    // signed-in viewer who dismissed it
+    let announcements = sqlx::query_as::<_, Announcement>(
+        "SELECT a.id, a.title, a.body, a.is_banner, a.starts_at, a.ends_at, a.created_at,
+                EXISTS(SELECT 1 FROM announcement_dismissals d WHERE d.announcement_id = a.id AND d.user_id = $1) AS dismissed
+         FROM announcements a
+         WHERE a.starts_at <= NOW() AND (a.ends_at IS NULL OR a.ends_at > NOW())
+         ORDER BY a.starts_at DESC"
+    )
+    .bind(viewer.map(|AuthenticatedUser(id)| id))
+    .fetch_all(&state.db)
+    .await?;
+
+    Ok(HttpResponse::Ok().json(ApiResponse {
+        success: true,
+        data: Some(announcements),
+        message: None,
+        next_cursor: None,
+    }))
+}
+
+async fn dismiss_announcement(state: web::Data<AppState>, AuthenticatedUser(user_id): AuthenticatedUser, announcement_id: web::Path<Uuid>) -> ApiResult<HttpResponse> {
+    let announcement_id = announcement_id.into_inner();
+
+    let exists = sqlx::query_scalar::<_, bool>("SELECT EXISTS(SELECT 1 FROM announcements WHERE id = $1)")
+        .bind(announcement_id)
+        .fetch_one(&state.db)
+        .await?;
+
+    if !exists {
+        return Err(ApiError::NotFound("Announcement not found".to_string()));
+    }
+
+    sqlx::query("INSERT INTO announcement_dismissals (announcement_id, user_id) VALUES ($1, $2) ON CONFLICT DO NOTHING")
+        .bind(announcement_id)
+        .bind(user_id)
+        .execute(&state.db)
+        .await?;
+
+    Ok(HttpResponse::Ok().json(ApiResponse {
+        success: true,
+        data: Some("Announcement dismissed"),
+        message: None,
+        next_cursor: None,
+    }))
+}
+
+async fn create_announcement(state: web::Data<AppState>, AdminUser(admin_id): AdminUser, req: web::Json<CreateAnnouncementRequest>) -> ApiResult<HttpResponse> {
+    req.validate()?;
+
+    if let (Some(starts_at), Some(ends_at)) = (req.starts_at, req.ends_at) {
+        if ends_at <= starts_at {
+            return Err(ApiError::BadRequest("ends_at must be after starts_at".to_string()));
+        }
+    }
+
+    let announcement = sqlx::query_as::<_, Announcement>(
+        "INSERT INTO announcements (author_id, title, body, is_banner, starts_at, ends_at)
+         VALUES ($1, $2, $3, $4, COALESCE($5, NOW()), $6)
+         RETURNING id, title, body, is_banner, starts_at, ends_at, created_at, FALSE AS dismissed"
+    )
+    .bind(admin_id)
+    .bind(&req.title)
+    .bind(&req.body)
+    .bind(req.is_banner)
+    .bind(req.starts_at)
+    .bind(req.ends_at)
+    .fetch_one(&state.db)
+    .await?;
+
+    Ok(HttpResponse::Created().json(ApiResponse {
+        success: true,
+        data: Some(announcement),
+        message: Some("Announcement created successfully".to_string()),
+        next_cursor: None,
+    }))
+}
+
+async fn delete_announcement(state: web::Data<AppState>, _admin: AdminUser, announcement_id: web::Path<Uuid>) -> ApiResult<HttpResponse> {
+    let result = sqlx::query("DELETE FROM announcements WHERE id = $1")
+        .bind(announcement_id.into_inner())
+        .execute(&state.db)
+        .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(ApiError::NotFound("Announcement not found".to_string()));
+    }
+
+    Ok(HttpResponse::Ok().json(ApiResponse {
+        success: true,
+        data: Some("Announcement deleted successfully"),
+        message: None,
+        next_cursor: None,
+    }))
+}
@@ -0,0 +1,527 @@
+use actix_web::{web, HttpResponse};
+use chrono::Utc;
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::auth::AuthenticatedUser;
+use crate::crypto::Pii;
+use crate::error::{ApiError, ApiResult};
+use crate::models::*;
+use crate::repo::postgres::insert_refresh_token;
+use crate::repo::{Rotation, SessionRepo, UserRepo};
+use crate::{auth, crypto, AppState};
+
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.route("/api/auth/register", web::post().to(register))
+        .route("/api/auth/login", web::post().to(login))
+        .route("/api/auth/refresh", web::post().to(refresh))
+        .route("/api/auth/logout", web::post().to(logout))
+        .route("/api/auth/password", web::put().to(change_password))
+        .route("/api/auth/email", web::put().to(change_email))
+        .route("/api/auth/email/confirm", web::post().to(confirm_email))
+        .route("/api/auth/verify", web::get().to(verify_email))
+        .route("/api/auth/verify/resend", web::post().to(resend_verification))
+        .route("/api/auth/forgot-password", web::post().to(forgot_password))
+        .route("/api/auth/reset-password", web::post().to(reset_password))
+        .route("/api/auth/me", web::get().to(get_me))
+        .route("/.well-known/jwks.json", web::get().to(get_jwks));
+}
+
+// How long a mailed email-change confirmation code stays valid
+const EMAIL_CHANGE_TTL: chrono::Duration = chrono::Duration::hours(24);
+// How long a mailed password reset code stays valid
+const PASSWORD_RESET_TTL: chrono::Duration = chrono::Duration::hours(1);
+// How long the verification link sent on registration stays valid
+const EMAIL_VERIFICATION_TTL: chrono::Duration = chrono::Duration::hours(48);
+
+// Public keys for verifying access tokens elsewhere, in the plain JWK Set
+// format verifiers expect rather than the usual envelope. Empty unless
+// JWT_PRIVATE_KEYS is set.
+async fn get_jwks(state: web::Data<AppState>) -> HttpResponse {
+    HttpResponse::Ok()
+        .insert_header(("Cache-Control", "public, max-age=300"))
+        .json(state.jwt_keys.jwks())
+}
+
+async fn register(
+    state: web::Data<AppState>,
+    users: web::Data<dyn UserRepo>,
+    sessions: web::Data<dyn SessionRepo>,
+    req: web::Json<RegisterRequest>,
+) -> ApiResult<HttpResponse> {
+    // Validate input
+    req.validate()?;
+
+    // Hash password
+    let password_hash = auth::hash_password(&req.password)
+        .map_err(|_| ApiError::Internal("Failed to hash password".to_string()))?;
+
+    // Insert user, unless the email or username is taken
+    let user = users
+        .create(&req, &crypto::blind_index(&req.email), &password_hash)
+        .await?
+        .ok_or_else(|| ApiError::Conflict("User with this email or username already exists".to_string()))?;
+
+    send_verification_email(&state, user.id, &req.email).await?;
+
+    // Start a new session
+    let tokens = start_session(&state, sessions.as_ref(), user).await?;
+
+    Ok(HttpResponse::Created().json(ApiResponse {
+        success: true,
+        data: Some(tokens),
+        message: Some("User registered successfully".to_string()),
+        next_cursor: None,
+    }))
+}
+
+async fn login(
+    state: web::Data<AppState>,
+    users: web::Data<dyn UserRepo>,
+    sessions: web::Data<dyn SessionRepo>,
+    req: web::Json<LoginRequest>,
+) -> ApiResult<HttpResponse> {
+    // Validate input
+    req.validate()?;
+
+    // Find user by email
+    let user = users
+        .find_by_email_hash(&crypto::blind_index(&req.email))
+        .await?
+        .ok_or_else(|| ApiError::Unauthorized("Invalid credentials".to_string()))?;
+
+    // Verify password
+    if !auth::verify_password(&req.password, &user.password_hash).unwrap_or(false) {
+        return Err(ApiError::Unauthorized("Invalid credentials".to_string()));
+    }
+
+    // Suspended accounts may sign in to see their status, but stay hidden
+    let suspended = user.suspension().is_some();
+
+    // Signing in is what reactivates a deactivated account
+    if user.deactivated_at.is_some() && !suspended {
+        users.reactivate(user.id).await?;
+    }
+
+    // Start a new session
+    let tokens = start_session(&state, sessions.as_ref(), user).await?;
+
+    Ok(HttpResponse::Ok().json(ApiResponse {
+        success: true,
+        data: Some(tokens),
+        message: Some(if suspended { "This account is suspended" } else { "Login successful" }.to_string()),
+        next_cursor: None,
+    }))
+}
+
+// Exchanges a refresh token for a new access token and rotates it: the
+// presented token is marked used and a fresh one in the same family is
+// returned. A used token showing up again means it was copied, so the whole
+// family (session) is revoked and the user has to sign in again.
+async fn refresh(
+    state: web::Data<AppState>,
+    sessions: web::Data<dyn SessionRepo>,
+    req: web::Json<RefreshTokenRequest>,
+) -> ApiResult<HttpResponse> {
+    let refresh_token = auth::generate_token();
+    let rotation = sessions
+        .rotate(
+            &auth::hash_token(&req.refresh_token),
+            &auth::hash_token(&refresh_token),
+            Utc::now() + state.tokens.refresh,
+        )
+        .await?;
+
+    let user = match rotation {
+        Rotation::Rotated(user) => *user,
+        Rotation::Unknown => return Err(ApiError::Unauthorized("Invalid refresh token".to_string())),
+        Rotation::Revoked => return Err(ApiError::Unauthorized("Refresh token has been revoked".to_string())),
+        Rotation::Expired => return Err(ApiError::Unauthorized("Refresh token has expired".to_string())),
+        Rotation::Reused { user_id, family_id } => {
+            log::warn!("Refresh token reuse for user {}; revoked family {}", user_id, family_id);
+            return Err(ApiError::Unauthorized("Refresh token reuse detected, please sign in again".to_string()));
+        }
+    };
+
+    let tokens = sign_tokens(&state, user, refresh_token)?;
+
+    Ok(HttpResponse::Ok().json(ApiResponse {
+        success: true,
+        data: Some(tokens),
+        message: None,
+        next_cursor: None,
+    }))
+}
+
+// Ends the session the refresh token belongs to. Access tokens already handed
+// out stay valid until they expire.
+async fn logout(sessions: web::Data<dyn SessionRepo>, req: web::Json<RefreshTokenRequest>) -> ApiResult<HttpResponse> {
+    sessions.end_session(&auth::hash_token(&req.refresh_token)).await?;
+
+    Ok(HttpResponse::Ok().json(ApiResponse::<()> {
+        success: true,
+        data: None,
+        message: Some("Logged out".to_string()),
+        next_cursor: None,
+    }))
+}
+
+// Sets a new password and ends every session, then starts a fresh one for
+// the caller so only this client stays signed in.
+async fn change_password(
+    state: web::Data<AppState>,
+    users: web::Data<dyn UserRepo>,
+    AuthenticatedUser(user_id): AuthenticatedUser,
+    req: web::Json<ChangePasswordRequest>,
+) -> ApiResult<HttpResponse> {
+    req.validate()?;
+
+    let user = users
+        .find_by_id(user_id)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("User not found".to_string()))?;
+
+    if !auth::verify_password(&req.current_password, &user.password_hash).unwrap_or(false) {
+        return Err(ApiError::Forbidden("Incorrect password".to_string()));
+    }
+
+    let password_hash = auth::hash_password(&req.new_password)
+        .map_err(|_| ApiError::Internal("Failed to hash password".to_string()))?;
+
+    let mut tx = state.db.begin().await?;
+
+    sqlx::query("UPDATE users SET password_hash = $1 WHERE id = $2")
+        .bind(&password_hash)
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await?;
+
+    revoke_all_sessions(&mut tx, user_id).await?;
+    let refresh_token = auth::generate_token();
+    insert_refresh_token(&mut tx, user_id, Uuid::new_v4(), &auth::hash_token(&refresh_token), Utc::now() + state.tokens.refresh).await?;
+    let tokens = sign_tokens(&state, user, refresh_token)?;
+
+    tx.commit().await?;
+
+    Ok(HttpResponse::Ok().json(ApiResponse {
+        success: true,
+        data: Some(tokens),
+        message: Some("Password changed, other sessions have been signed out".to_string()),
+        next_cursor: None,
+    }))
+}
+
+// Starts an email change. The address only changes once the token mailed to
+// it is confirmed; a newer request replaces any pending one.
+async fn change_email(
+    state: web::Data<AppState>,
+    users: web::Data<dyn UserRepo>,
+    AuthenticatedUser(user_id): AuthenticatedUser,
+    req: web::Json<ChangeEmailRequest>,
+) -> ApiResult<HttpResponse> {
+    req.validate()?;
+
+    let password_hash = users
+        .password_hash(user_id)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("User not found".to_string()))?;
+
+    if !auth::verify_password(&req.password, &password_hash).unwrap_or(false) {
+        return Err(ApiError::Forbidden("Incorrect password".to_string()));
+    }
+
+    if users.find_by_email_hash(&crypto::blind_index(&req.new_email)).await?.is_some() {
+        return Err(ApiError::Conflict("Email is already in use".to_string()));
+    }
+
+    let token = auth::generate_token();
+    let mut tx = state.db.begin().await?;
+
+    sqlx::query("DELETE FROM email_changes WHERE user_id = $1")
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await?;
+
+    sqlx::query("INSERT INTO email_changes (user_id, new_email, token_hash, expires_at) VALUES ($1, $2, $3, $4)")
+        .bind(user_id)
+        .bind(crypto::seal(&req.new_email))
+        .bind(auth::hash_token(&token))
+        .bind(Utc::now() + EMAIL_CHANGE_TTL)
+        .execute(&mut *tx)
+        .await?;
+
+    let text = format!(
+        "Confirm your new email address for {} with this code:\n\n{}\n\nIt expires in {} hours. If you didn't ask for this, ignore this email.",
+        state.instance.name,
+        token,
+        EMAIL_CHANGE_TTL.num_hours()
+    );
+    state
+        .mailer
+        .send(&req.new_email, "Confirm your new email address", &text)
+        .await
+        .map_err(|e| {
+            log::error!("failed to send email change confirmation for {}: {}", user_id, e);
+            ApiError::ServiceUnavailable("Could not send the confirmation email, try again later".to_string())
+        })?;
+
+    tx.commit().await?;
+
+    Ok(HttpResponse::Accepted().json(ApiResponse::<()> {
+        success: true,
+        data: None,
+        message: Some("Check the new address for a confirmation code".to_string()),
+        next_cursor: None,
+    }))
+}
+
+// Applies a pending email change. The token itself proves the request, so no
+// session is needed: the link may be opened on another device.
+async fn confirm_email(state: web::Data<AppState>, req: web::Json<ConfirmEmailRequest>) -> ApiResult<HttpResponse> {
+    let mut tx = state.db.begin().await?;
+
+    let (user_id, Pii(new_email)) = sqlx::query_as::<_, (Uuid, Pii)>(
+        "DELETE FROM email_changes WHERE token_hash = $1 AND expires_at > NOW() RETURNING user_id, new_email"
+    )
+    .bind(auth::hash_token(&req.token))
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or_else(|| ApiError::BadRequest("Invalid or expired confirmation code".to_string()))?;
+
+    let user = sqlx::query_as::<_, User>(
+        "UPDATE users SET email = $1, email_hash = $2, verified_email = TRUE
+         WHERE id = $3 AND NOT EXISTS(SELECT 1 FROM users WHERE email_hash = $2)
+         RETURNING *"
+    )
+    .bind(crypto::seal(&new_email))
+    .bind(crypto::blind_index(&new_email))
+    .bind(user_id)
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or_else(|| ApiError::Conflict("Email is already in use".to_string()))?;
+
+    tx.commit().await?;
+
+    Ok(HttpResponse::Ok().json(ApiResponse {
+        success: true,
+        data: Some(PrivateUserResponse::from(user)),
+        message: Some("Email address changed".to_string()),
+        next_cursor: None,
+    }))
+}
+
+// Stores a fresh verification token (replacing any earlier one) and mails the
+// link. Delivery failures are only logged; the user can ask for a new link.
+async fn send_verification_email(state: &AppState, user_id: Uuid, email: &str) -> ApiResult<()> {
+    let token = auth::generate_token();
+    let mut tx = state.db.begin().await?;
+
+    sqlx::query("DELETE FROM email_verifications WHERE user_id = $1")
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await?;
+
+    sqlx::query("INSERT INTO email_verifications (user_id, token_hash, expires_at) VALUES ($1, $2, $3)")
+        .bind(user_id)
+        .bind(auth::hash_token(&token))
+        .bind(Utc::now() + EMAIL_VERIFICATION_TTL)
+        .execute(&mut *tx)
+        .await?;
+
+    tx.commit().await?;
+
+    let text = format!(
+        "Welcome to {}! Confirm your email address by opening this link:\n\n{}/api/auth/verify?token={}\n\nIt expires in {} hours.",
+        state.instance.name,
+        state.base_url,
+        token,
+        EMAIL_VERIFICATION_TTL.num_hours()
+    );
+    if let Err(e) = state.mailer.send(email, "Confirm your email address", &text).await {
+        log::error!("failed to send verification email for {}: {}", user_id, e);
+    }
+
+    Ok(())
+}
+
+async fn verify_email(state: web::Data<AppState>, query: web::Query<VerifyEmailQuery>) -> ApiResult<HttpResponse> {
+    let mut tx = state.db.begin().await?;
+
+    let user_id = sqlx::query_scalar::<_, Uuid>(
+        "DELETE FROM email_verifications WHERE token_hash = $1 AND expires_at > NOW() RETURNING user_id"
+    )
+    .bind(auth::hash_token(&query.token))
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or_else(|| ApiError::BadRequest("Invalid or expired verification link".to_string()))?;
+
+    sqlx::query("UPDATE users SET verified_email = TRUE WHERE id = $1")
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await?;
+
+    tx.commit().await?;
+
+    Ok(HttpResponse::Ok().json(ApiResponse::<()> {
+        success: true,
+        data: None,
+        message: Some("Email address verified".to_string()),
+        next_cursor: None,
+    }))
+}
+
+async fn resend_verification(
+    state: web::Data<AppState>,
+    users: web::Data<dyn UserRepo>,
+    AuthenticatedUser(user_id): AuthenticatedUser,
+) -> ApiResult<HttpResponse> {
+    let user = users
+        .find_by_id(user_id)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("User not found".to_string()))?;
+
+    if user.verified_email {
+        return Err(ApiError::Conflict("Email address is already verified".to_string()));
+    }
+
+    send_verification_email(&state, user_id, &user.email.0).await?;
+
+    Ok(HttpResponse::Accepted().json(ApiResponse::<()> {
+        success: true,
+        data: None,
+        message: Some("Verification link sent".to_string()),
+        next_cursor: None,
+    }))
+}
+
+// Mails a single-use reset code. The response is the same whether or not the
+// address belongs to an account, so it can't be used to probe for users.
+async fn forgot_password(
+    state: web::Data<AppState>,
+    users: web::Data<dyn UserRepo>,
+    req: web::Json<ForgotPasswordRequest>,
+) -> ApiResult<HttpResponse> {
+    req.validate()?;
+
+    let user = users.find_by_email_hash(&crypto::blind_index(&req.email)).await?;
+
+    if let Some(user_id) = user.map(|user| user.id) {
+        let token = auth::generate_token();
+        let mut tx = state.db.begin().await?;
+
+        // Only the newest code works
+        sqlx::query("DELETE FROM password_reset_tokens WHERE user_id = $1 AND used_at IS NULL")
+            .bind(user_id)
+            .execute(&mut *tx)
+            .await?;
+
+        sqlx::query("INSERT INTO password_reset_tokens (user_id, token_hash, expires_at) VALUES ($1, $2, $3)")
+            .bind(user_id)
+            .bind(auth::hash_token(&token))
+            .bind(Utc::now() + PASSWORD_RESET_TTL)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+
+        let text = format!(
+            "Someone asked to reset the password of your {} account. Use this code to choose a new one:\n\n{}\n\nIt expires in {} minutes. If you didn't ask for this, ignore this email.",
+            state.instance.name,
+            token,
+            PASSWORD_RESET_TTL.num_minutes()
+        );
+        if let Err(e) = state.mailer.send(&req.email, "Reset your password", &text).await {
+            log::error!("failed to send password reset for {}: {}", user_id, e);
+        }
+    }
+
+    Ok(HttpResponse::Accepted().json(ApiResponse::<()> {
+        success: true,
+        data: None,
+        message: Some("If an account uses this address, a reset code is on its way".to_string()),
+        next_cursor: None,
+    }))
+}
+
+// Sets a new password from a mailed reset code and signs out every session.
+async fn reset_password(state: web::Data<AppState>, req: web::Json<ResetPasswordRequest>) -> ApiResult<HttpResponse> {
+    req.validate()?;
+
+    let password_hash = auth::hash_password(&req.new_password)
+        .map_err(|_| ApiError::Internal("Failed to hash password".to_string()))?;
+
+    let mut tx = state.db.begin().await?;
+
+    let user_id = sqlx::query_scalar::<_, Uuid>(
+        "UPDATE password_reset_tokens SET used_at = NOW()
+         WHERE token_hash = $1 AND used_at IS NULL AND expires_at > NOW()
+         RETURNING user_id"
+    )
+    .bind(auth::hash_token(&req.token))
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or_else(|| ApiError::BadRequest("Invalid or expired reset code".to_string()))?;
+
+    // The code arrived by email, so the address is proven too
+    sqlx::query("UPDATE users SET password_hash = $1, verified_email = TRUE WHERE id = $2")
+        .bind(&password_hash)
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await?;
+
+    revoke_all_sessions(&mut tx, user_id).await?;
+
+    tx.commit().await?;
+
+    Ok(HttpResponse::Ok().json(ApiResponse::<()> {
+        success: true,
+        data: None,
+        message: Some("Password reset, please sign in again".to_string()),
+        next_cursor: None,
+    }))
+}
+
+// Signs a new access token and starts a session with a new refresh token.
+async fn start_session(state: &AppState, sessions: &dyn SessionRepo, user: User) -> ApiResult<AuthResponse> {
+    let refresh_token = auth::generate_token();
+    sessions
+        .insert_refresh_token(user.id, Uuid::new_v4(), &auth::hash_token(&refresh_token), Utc::now() + state.tokens.refresh)
+        .await?;
+    sign_tokens(state, user, refresh_token)
+}
+
+// Signs a new access token to hand out with a refresh token that's already
+// stored.
+fn sign_tokens(state: &AppState, user: User, refresh_token: String) -> ApiResult<AuthResponse> {
+    let token = auth::create_jwt(user.id, user.is_bot, &state.jwt_keys, state.tokens.access)
+        .map_err(|_| ApiError::Internal("Failed to create token".to_string()))?;
+
+    Ok(AuthResponse {
+        token,
+        expires_in: state.tokens.access.num_seconds(),
+        refresh_token,
+        user: user.into(),
+    })
+}
+
+pub async fn revoke_all_sessions(conn: &mut sqlx::PgConnection, user_id: Uuid) -> Result<(), sqlx::Error> {
+    sqlx::query("UPDATE refresh_tokens SET revoked_at = NOW() WHERE user_id = $1 AND revoked_at IS NULL")
+        .bind(user_id)
+        .execute(conn)
+        .await?;
+    Ok(())
+}
+
+async fn get_me(users: web::Data<dyn UserRepo>, AuthenticatedUser(user_id): AuthenticatedUser) -> ApiResult<HttpResponse> {
+    let user = users
+        .find_by_id(user_id)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("User not found".to_string()))?;
+
+    Ok(HttpResponse::Ok().json(ApiResponse {
+        success: true,
+        data: Some(PrivateUserResponse::from(user)),
+        message: None,
+        next_cursor: None,
+    }))
+}
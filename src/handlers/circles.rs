@@ -0,0 +1,86 @@
+use actix_web::{web, HttpResponse};
+use uuid::Uuid;
+
+use crate::auth::AuthenticatedUser;
+use crate::error::{ApiError, ApiResult};
+use crate::models::*;
+use crate::AppState;
+
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.route("/api/users/me/circle", web::get().to(get_circle))
+        .route("/api/users/me/circle/{username}", web::post().to(add_to_circle))
+        .route("/api/users/me/circle/{username}", web::delete().to(remove_from_circle));
+}
+
+// The circle is the audience of the owner's `circle` tweets. Members aren't
+// told they were added.
+async fn get_circle(state: web::Data<AppState>, AuthenticatedUser(user_id): AuthenticatedUser) -> ApiResult<HttpResponse> {
+    let members = sqlx::query_scalar::<_, String>(
+        "SELECT u.username FROM circle_members c
+         JOIN users u ON u.id = c.member_id
+         WHERE c.owner_id = $1
+         ORDER BY c.created_at"
+    )
+    .bind(user_id)
+    .fetch_all(&state.db)
+    .await?;
+
+    Ok(HttpResponse::Ok().json(ApiResponse {
+        success: true,
+        data: Some(members),
+        message: None,
+        next_cursor: None,
+    }))
+}
+
+async fn add_to_circle(state: web::Data<AppState>, AuthenticatedUser(owner_id): AuthenticatedUser, username: web::Path<String>) -> ApiResult<HttpResponse> {
+    let member_id = sqlx::query_scalar::<_, Uuid>("SELECT id FROM users WHERE username = $1")
+        .bind(username.as_str())
+        .fetch_optional(&state.db)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("User not found".to_string()))?;
+
+    if owner_id == member_id {
+        return Err(ApiError::BadRequest("Cannot add yourself to your circle".to_string()));
+    }
+
+    let result = sqlx::query("INSERT INTO circle_members (owner_id, member_id) VALUES ($1, $2) ON CONFLICT DO NOTHING")
+        .bind(owner_id)
+        .bind(member_id)
+        .execute(&state.db)
+        .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(ApiError::Conflict("Already in your circle".to_string()));
+    }
+
+    Ok(HttpResponse::Ok().json(ApiResponse {
+        success: true,
+        data: Some("User added to circle"),
+        message: None,
+        next_cursor: None,
+    }))
+}
+
+async fn remove_from_circle(state: web::Data<AppState>, AuthenticatedUser(owner_id): AuthenticatedUser, username: web::Path<String>) -> ApiResult<HttpResponse> {
+    let result = sqlx::query(
+        "DELETE FROM circle_members
+         WHERE owner_id = $1
+           AND member_id = (SELECT id FROM users WHERE username = $2)"
+    )
+    .bind(owner_id)
+    .bind(username.as_str())
+    .execute(&state.db)
+    .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(ApiError::NotFound("Not in your circle".to_string()));
+    }
+
+    Ok(HttpResponse::Ok().json(ApiResponse {
+        success: true,
+        data: Some("User removed from circle"),
+        message: None,
+        next_cursor: None,
+    }))
+}
@@ -0,0 +1,111 @@
+use actix_web::{web, HttpResponse};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use super::tweets::tweet_page_bounds;
+use crate::auth::AuthenticatedUser;
+use crate::error::ApiResult;
+use crate::models::*;
+use crate::pagination::{self, Cursor, PageQuery};
+use crate::AppState;
+
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.route("/api/hashtags/trending", web::get().to(get_trending_hashtags))
+        .route("/api/hashtags/{tag}/tweets", web::get().to(get_hashtag_tweets));
+}
+
+async fn get_hashtag_tweets(
+    state: web::Data<AppState>,
+    viewer: Option<AuthenticatedUser>,
+    tag: web::Path<String>,
+    page: web::Query<PageQuery>,
+) -> ApiResult<HttpResponse> {
+    let limit = page.limit();
+    let tag = tag.trim_start_matches('#').to_lowercase();
+    let scope = format!("hashtag:{}", tag);
+    let (cursor, since) = tweet_page_bounds(state.repos.tweets.as_ref(), &page, &state.cursors, &scope).await?;
+
+    let mut tweets = hashtag_tweets(&state.db, &tag, cursor, since, limit + 1, viewer.map(|AuthenticatedUser(id)| id)).await?;
+
+    let next_cursor = pagination::next_cursor(&mut tweets, limit, &state.cursors, &scope, |t| Cursor {
+        created_at: t.created_at,
+        id: t.id,
+    });
+    let tweet_responses: Vec<TweetResponse> = tweets
+        .into_iter()
+        .map(|tweet| tweet.into_response(false, false))
+        .collect();
+
+    Ok(HttpResponse::Ok().json(ApiResponse {
+        success: true,
+        data: Some(tweet_responses),
+        message: None,
+        next_cursor,
+    }))
+}
+
+// One page of a hashtag feed, newest first. Also backs event pages.
+pub async fn hashtag_tweets(
+    db: &PgPool,
+    tag: &str,
+    cursor: Option<Cursor>,
+    since: Option<Cursor>,
+    limit: i64,
+    viewer_id: Option<Uuid>,
+) -> Result<Vec<TweetWithUser>, sqlx::Error> {
+    sqlx::query_as::<_, TweetWithUser>(
+        "SELECT t.id, t.user_id, t.parent_tweet_id, t.quoted_tweet_id, t.content, t.image_url, t.likes_count, t.retweets_count, 
+                t.replies_count, t.visibility, t.created_at,
+                u.username as user_username, u.display_name as user_display_name, 
+                u.bio as user_bio, 
+                u.profile_image as user_profile_image, u.banner_image as user_banner_image,
+                u.followers_count as user_followers_count, u.following_count as user_following_count,
+                u.verified as user_verified, u.is_bot as user_is_bot, u.created_at as user_created_at
+         FROM tweets t
+         INNER JOIN users u ON t.user_id = u.id
+         INNER JOIN tweet_hashtags th ON th.tweet_id = t.id
+         INNER JOIN hashtags h ON h.id = th.hashtag_id
+         WHERE h.tag = $1 AND u.deactivated_at IS NULL AND t.deleted_at IS NULL
+           AND tweet_visible_to(t.user_id, t.visibility, $7)
+           AND ($2::timestamptz IS NULL OR (t.created_at, t.id) < ($2, $3))
+           AND ($5::timestamptz IS NULL OR (t.created_at, t.id) > ($5, $6))
+         ORDER BY t.created_at DESC, t.id DESC
+         LIMIT $4"
+    )
+    .bind(tag)
+    .bind(cursor.map(|c| c.created_at))
+    .bind(cursor.map(|c| c.id))
+    .bind(limit)
+    .bind(since.map(|c| c.created_at))
+    .bind(since.map(|c| c.id))
+    .bind(viewer_id)
+    .fetch_all(db)
+    .await
+}
+
+async fn get_trending_hashtags(state: web::Data<AppState>, query: web::Query<TrendingQuery>) -> ApiResult<HttpResponse> {
+    let hours = query.hours.unwrap_or(24).clamp(1, 24 * 7);
+    let limit = query.limit.unwrap_or(10).clamp(1, 50);
+
+    // Tags ranked by how many tweets used them in the window
+    let trending = sqlx::query_as::<_, TrendingHashtag>(
+        "SELECT h.tag, COUNT(*) AS tweet_count
+         FROM tweet_hashtags th
+         INNER JOIN hashtags h ON h.id = th.hashtag_id
+         WHERE th.created_at > NOW() - make_interval(hours => $1)
+         GROUP BY h.tag
+         ORDER BY tweet_count DESC, h.tag
+         LIMIT $2"
+    )
+    .bind(hours)
+    .bind(limit)
+    .fetch_all(&state.db)
+    .await?;
+
+    Ok(HttpResponse::Ok().json(ApiResponse {
+        success: true,
+        data: Some(trending),
+        message: None,
+        next_cursor: None,
+    }))
+}
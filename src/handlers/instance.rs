@@ -0,0 +1,40 @@
+use actix_web::{web, HttpResponse};
+
+use crate::error::ApiResult;
+use crate::models::*;
+use crate::{analytics, AppState};
+
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.route("/api/instance", web::get().to(get_instance))
+        .route("/api/instance/stats", web::get().to(get_instance_stats));
+}
+
+async fn get_instance_stats(state: web::Data<AppState>) -> ApiResult<HttpResponse> {
+    Ok(HttpResponse::Ok().json(ApiResponse {
+        success: true,
+        data: Some(analytics::instance_stats(&state.db).await?),
+        message: None,
+        next_cursor: None,
+    }))
+}
+
+async fn get_instance(state: web::Data<AppState>) -> ApiResult<HttpResponse> {
+    let maintenance = sqlx::query_as::<_, MaintenanceWindow>(
+        "SELECT id, reason, starts_at, ends_at FROM maintenance_windows
+         WHERE ends_at > NOW()
+         ORDER BY starts_at"
+    )
+    .fetch_all(&state.db)
+    .await?;
+
+    Ok(HttpResponse::Ok().json(ApiResponse {
+        success: true,
+        data: Some(InstanceResponse {
+            settings: &state.instance,
+            read_only: state.maintenance.is_read_only(),
+            maintenance,
+        }),
+        message: None,
+        next_cursor: None,
+    }))
+}
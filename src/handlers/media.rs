@@ -0,0 +1,115 @@
+use actix_multipart::Multipart;
+use actix_web::{web, HttpResponse};
+use futures_util::StreamExt;
+use uuid::Uuid;
+
+use crate::auth::AuthenticatedUser;
+use crate::error::{ApiError, ApiResult};
+use crate::models::*;
+use crate::{media, AppState};
+
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.route("/api/media/upload", web::post().to(upload_media))
+        .route("/proxy/media", web::get().to(proxy_media));
+}
+
+// Accepts a multipart form with a single `file` part holding an image.
+async fn upload_media(
+    state: web::Data<AppState>,
+    AuthenticatedUser(user_id): AuthenticatedUser,
+    mut payload: Multipart,
+) -> ApiResult<HttpResponse> {
+    let mut file = None;
+
+    while let Some(field) = payload.next().await {
+        let mut field = field.map_err(|e| ApiError::BadRequest(format!("Invalid multipart body: {}", e)))?;
+        if field.name() != Some("file") {
+            continue;
+        }
+
+        if field.content_type().is_none_or(|mime| mime.type_() != actix_web::mime::IMAGE) {
+            return Err(ApiError::BadRequest("File must be an image".to_string()));
+        }
+
+        let mut bytes = Vec::new();
+        while let Some(chunk) = field.next().await {
+            let chunk = chunk.map_err(|e| ApiError::BadRequest(format!("Invalid multipart body: {}", e)))?;
+            if bytes.len() + chunk.len() > state.media.max_bytes {
+                return Err(ApiError::BadRequest(format!(
+                    "File must be at most {} bytes",
+                    state.media.max_bytes
+                )));
+            }
+            bytes.extend_from_slice(&chunk);
+        }
+        file = Some(bytes);
+        break;
+    }
+
+    let bytes = file.ok_or_else(|| ApiError::BadRequest("Missing file part".to_string()))?;
+    let media = store_image(&state, user_id, bytes).await?;
+
+    Ok(HttpResponse::Created().json(ApiResponse {
+        success: true,
+        data: Some(media),
+        message: None,
+        next_cursor: None,
+    }))
+}
+
+// Validates an uploaded image, stores it with its thumbnail and records it
+// as the user's media. Also used for post-by-email attachments.
+pub async fn store_image(state: &AppState, user_id: Uuid, bytes: Vec<u8>) -> ApiResult<Media> {
+    let (bytes, image) = web::block(move || media::process_image(&bytes).map(|image| (bytes, image)))
+        .await
+        .map_err(|e| ApiError::Internal(e.to_string()))??;
+
+    let id = Uuid::new_v4();
+    let key = format!("{}.{}", id, image.extension);
+    let thumbnail_key = format!("{}_thumb.jpg", id);
+    let size_bytes = bytes.len() as i32;
+
+    state
+        .media
+        .storage
+        .put(&key, bytes, image.content_type)
+        .await
+        .map_err(|e| ApiError::Internal(format!("Failed to store media: {}", e)))?;
+    state
+        .media
+        .storage
+        .put(&thumbnail_key, image.thumbnail, "image/jpeg")
+        .await
+        .map_err(|e| ApiError::Internal(format!("Failed to store media: {}", e)))?;
+
+    let media = sqlx::query_as::<_, Media>(
+        "INSERT INTO media (id, user_id, content_type, size_bytes, width, height, url, thumbnail_url)
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+         RETURNING id, content_type, size_bytes, width, height, url, thumbnail_url, created_at"
+    )
+    .bind(id)
+    .bind(user_id)
+    .bind(image.content_type)
+    .bind(size_bytes)
+    .bind(image.width as i32)
+    .bind(image.height as i32)
+    .bind(state.media.storage.url(&key))
+    .bind(state.media.storage.url(&thumbnail_key))
+    .fetch_one(&state.db)
+    .await?;
+
+    Ok(media)
+}
+
+// ============ MEDIA PROXY ============
+
+async fn proxy_media(state: web::Data<AppState>, query: web::Query<ProxyMediaQuery>) -> ApiResult<HttpResponse> {
+    let media = state.media_proxy.get(&query.url).await?;
+
+    Ok(HttpResponse::Ok()
+        .content_type(media.content_type)
+        .insert_header(("Cache-Control", "public, max-age=86400"))
+        .insert_header(("X-Content-Type-Options", "nosniff"))
+        .insert_header(("Content-Security-Policy", "default-src 'none'; sandbox"))
+        .body(media.body))
+}
@@ -0,0 +1,88 @@
+use actix_web::{web, HttpResponse};
+use std::collections::HashSet;
+use uuid::Uuid;
+
+use super::tweets::tweet_page_bounds;
+use crate::auth::AuthenticatedUser;
+use crate::error::ApiResult;
+use crate::models::*;
+use crate::pagination::{self, Cursor, PageQuery};
+use crate::AppState;
+
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.route("/api/users/me/mentions", web::get().to(get_mentions));
+}
+
+async fn get_mentions(
+    state: web::Data<AppState>,
+    AuthenticatedUser(user_id): AuthenticatedUser,
+    page: web::Query<PageQuery>,
+) -> ApiResult<HttpResponse> {
+    let limit = page.limit();
+    let scope = format!("mentions:{}", user_id);
+    let (cursor, since) = tweet_page_bounds(state.repos.tweets.as_ref(), &page, &state.cursors, &scope).await?;
+
+    let mut tweets = sqlx::query_as::<_, TweetWithUser>(
+        "SELECT t.id, t.user_id, t.parent_tweet_id, t.quoted_tweet_id, t.content, t.image_url, t.likes_count, t.retweets_count, 
+                t.replies_count, t.visibility, t.created_at,
+                u.username as user_username, u.display_name as user_display_name, 
+                u.bio as user_bio, 
+                u.profile_image as user_profile_image, u.banner_image as user_banner_image,
+                u.followers_count as user_followers_count, u.following_count as user_following_count,
+                u.verified as user_verified, u.is_bot as user_is_bot, u.created_at as user_created_at
+         FROM tweets t
+         INNER JOIN users u ON t.user_id = u.id
+         INNER JOIN mentions m ON m.tweet_id = t.id
+         WHERE m.user_id = $1 AND u.deactivated_at IS NULL AND t.deleted_at IS NULL
+           AND tweet_visible_to(t.user_id, t.visibility, $1)
+           AND ($2::timestamptz IS NULL OR (t.created_at, t.id) < ($2, $3))
+           AND ($5::timestamptz IS NULL OR (t.created_at, t.id) > ($5, $6))
+         ORDER BY t.created_at DESC, t.id DESC
+         LIMIT $4"
+    )
+    .bind(user_id)
+    .bind(cursor.map(|c| c.created_at))
+    .bind(cursor.map(|c| c.id))
+    .bind(limit + 1)
+    .bind(since.map(|c| c.created_at))
+    .bind(since.map(|c| c.id))
+    .fetch_all(&state.db)
+    .await?;
+
+    let next_cursor = pagination::next_cursor(&mut tweets, limit, &state.cursors, &scope, |t| Cursor {
+        created_at: t.created_at,
+        id: t.id,
+    });
+
+    let tweet_ids: Vec<Uuid> = tweets.iter().map(|t| t.id).collect();
+    let liked: HashSet<Uuid> = sqlx::query_scalar::<_, Uuid>("SELECT tweet_id FROM likes WHERE user_id = $1 AND tweet_id = ANY($2)")
+        .bind(user_id)
+        .bind(&tweet_ids)
+        .fetch_all(&state.db)
+        .await?
+        .into_iter()
+        .collect();
+    let retweeted: HashSet<Uuid> = sqlx::query_scalar::<_, Uuid>("SELECT tweet_id FROM retweets WHERE user_id = $1 AND tweet_id = ANY($2)")
+        .bind(user_id)
+        .bind(&tweet_ids)
+        .fetch_all(&state.db)
+        .await?
+        .into_iter()
+        .collect();
+
+    let tweet_responses: Vec<TweetResponse> = tweets
+        .into_iter()
+        .map(|tweet| {
+            let is_liked = liked.contains(&tweet.id);
+            let is_retweeted = retweeted.contains(&tweet.id);
+            tweet.into_response(is_liked, is_retweeted)
+        })
+        .collect();
+
+    Ok(HttpResponse::Ok().json(ApiResponse {
+        success: true,
+        data: Some(tweet_responses),
+        message: None,
+        next_cursor,
+    }))
+}
@@ -0,0 +1,201 @@
+use actix_web::{web, HttpResponse};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::auth::AuthenticatedUser;
+use crate::error::{ApiError, ApiResult};
+use crate::models::*;
+use crate::repo::postgres::{delete_follow, insert_follow};
+use crate::{db, AppState};
+
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.route("/api/users/me/aliases", web::get().to(get_aliases))
+        .route("/api/users/me/aliases", web::post().to(add_alias))
+        .route("/api/users/me/aliases/{username}", web::delete().to(remove_alias))
+        .route("/api/users/me/move", web::post().to(move_account))
+        .route("/api/users/me/move", web::delete().to(undo_move_account));
+}
+
+async fn get_aliases(state: web::Data<AppState>, AuthenticatedUser(user_id): AuthenticatedUser) -> ApiResult<HttpResponse> {
+    let aliases = sqlx::query_scalar::<_, String>(
+        "SELECT u.username FROM account_aliases a
+         JOIN users u ON u.id = a.alias_id
+         WHERE a.user_id = $1
+         ORDER BY a.created_at"
+    )
+    .bind(user_id)
+    .fetch_all(&state.db)
+    .await?;
+
+    Ok(HttpResponse::Ok().json(ApiResponse {
+        success: true,
+        data: Some(aliases),
+        message: None,
+        next_cursor: None,
+    }))
+}
+
+// Marks another local account as "also known as" this one, which is what
+// allows that account to move here.
+async fn add_alias(state: web::Data<AppState>, AuthenticatedUser(user_id): AuthenticatedUser, alias_req: web::Json<AliasRequest>) -> ApiResult<HttpResponse> {
+    let alias_id = sqlx::query_scalar::<_, Uuid>("SELECT id FROM users WHERE username = $1")
+        .bind(alias_req.username.trim_start_matches('@'))
+        .fetch_optional(&state.db)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("User not found".to_string()))?;
+
+    if alias_id == user_id {
+        return Err(ApiError::BadRequest("Cannot alias yourself".to_string()));
+    }
+
+    sqlx::query("INSERT INTO account_aliases (user_id, alias_id) VALUES ($1, $2) ON CONFLICT DO NOTHING")
+        .bind(user_id)
+        .bind(alias_id)
+        .execute(&state.db)
+        .await?;
+
+    Ok(HttpResponse::Ok().json(ApiResponse {
+        success: true,
+        data: Some("Alias added successfully"),
+        message: None,
+        next_cursor: None,
+    }))
+}
+
+async fn remove_alias(state: web::Data<AppState>, AuthenticatedUser(user_id): AuthenticatedUser, username: web::Path<String>) -> ApiResult<HttpResponse> {
+    let result = sqlx::query(
+        "DELETE FROM account_aliases
+         WHERE user_id = $1
+           AND alias_id = (SELECT id FROM users WHERE username = $2)"
+    )
+    .bind(user_id)
+    .bind(username.as_str())
+    .execute(&state.db)
+    .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(ApiError::NotFound("Alias not found".to_string()));
+    }
+
+    Ok(HttpResponse::Ok().json(ApiResponse {
+        success: true,
+        data: Some("Alias removed successfully"),
+        message: None,
+        next_cursor: None,
+    }))
+}
+
+async fn move_account(state: web::Data<AppState>, AuthenticatedUser(user_id): AuthenticatedUser, move_req: web::Json<MoveAccountRequest>) -> ApiResult<HttpResponse> {
+    let target = move_req.target.trim();
+
+    let (moved_to_id, moved_to) = if target.starts_with("https://") || target.starts_with("http://") {
+        (None, target.to_string())
+    } else if let Some((name, domain)) = target.trim_start_matches('@').split_once('@') {
+        if name.is_empty() || domain.is_empty() {
+            return Err(ApiError::BadRequest("Invalid move target".to_string()));
+        }
+        (None, format!("@{}@{}", name, domain))
+    } else {
+        let new_account = sqlx::query_as::<_, User>("SELECT * FROM users WHERE username = $1")
+            .bind(target.trim_start_matches('@'))
+            .fetch_optional(&state.db)
+            .await?
+            .ok_or_else(|| ApiError::NotFound("User not found".to_string()))?;
+
+        if new_account.id == user_id {
+            return Err(ApiError::BadRequest("Cannot move to yourself".to_string()));
+        }
+        if new_account.moved_to.is_some() {
+            return Err(ApiError::BadRequest("Target account has itself moved".to_string()));
+        }
+
+        let aliased = sqlx::query_scalar::<_, bool>(
+            "SELECT EXISTS(SELECT 1 FROM account_aliases WHERE user_id = $1 AND alias_id = $2)"
+        )
+        .bind(new_account.id)
+        .bind(user_id)
+        .fetch_one(&state.db)
+        .await?;
+
+        if !aliased {
+            return Err(ApiError::BadRequest(
+                "Target account must list this account as an alias first".to_string(),
+            ));
+        }
+
+        (Some(new_account.id), format!("{}/{}", state.base_url, new_account.username))
+    };
+
+    if move_req.migrate_followers && moved_to_id.is_none() {
+        return Err(ApiError::BadRequest(
+            "Followers can only be migrated to local accounts".to_string(),
+        ));
+    }
+
+    let user = sqlx::query_as::<_, User>(
+        "UPDATE users SET moved_to_id = $1, moved_to = $2, moved_at = NOW() WHERE id = $3 RETURNING *"
+    )
+    .bind(moved_to_id)
+    .bind(&moved_to)
+    .bind(user_id)
+    .fetch_one(&state.db)
+    .await?;
+
+    let message = match moved_to_id {
+        Some(new_account_id) if move_req.migrate_followers => {
+            let db = state.db.clone();
+            actix_web::rt::spawn(async move {
+                if let Err(e) = migrate_followers(&db, user_id, new_account_id).await {
+                    log::error!("follower migration from {} failed: {}", user_id, e);
+                }
+            });
+            "Account moved, followers are being migrated"
+        }
+        _ => "Account moved",
+    };
+
+    Ok(HttpResponse::Ok().json(ApiResponse {
+        success: true,
+        data: Some(PrivateUserResponse::from(user)),
+        message: Some(message.to_string()),
+        next_cursor: None,
+    }))
+}
+
+async fn undo_move_account(state: web::Data<AppState>, AuthenticatedUser(user_id): AuthenticatedUser) -> ApiResult<HttpResponse> {
+    let user = sqlx::query_as::<_, User>(
+        "UPDATE users SET moved_to_id = NULL, moved_to = NULL, moved_at = NULL WHERE id = $1 RETURNING *"
+    )
+    .bind(user_id)
+    .fetch_one(&state.db)
+    .await?;
+
+    Ok(HttpResponse::Ok().json(ApiResponse {
+        success: true,
+        data: Some(PrivateUserResponse::from(user)),
+        message: Some("Account move cancelled".to_string()),
+        next_cursor: None,
+    }))
+}
+
+// Moves every local follower of `old_id` over to `new_id`: follows the new
+// account and drops the follow of the old one.
+async fn migrate_followers(db: &PgPool, old_id: Uuid, new_id: Uuid) -> Result<(), sqlx::Error> {
+    let follower_ids = sqlx::query_scalar::<_, Uuid>("SELECT follower_id FROM follows WHERE following_id = $1")
+        .bind(old_id)
+        .fetch_all(db)
+        .await?;
+
+    db::with_tx(db, move |conn| {
+        Box::pin(async move {
+            for follower_id in follower_ids {
+                if follower_id != new_id {
+                    insert_follow(conn, follower_id, new_id).await?;
+                }
+                delete_follow(conn, follower_id, old_id).await?;
+            }
+            Ok(())
+        })
+    })
+    .await
+}
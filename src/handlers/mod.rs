@@ -0,0 +1,19 @@
+// Handlers for the core resources, one module per area. Each module's
+// `configure` registers its routes, in the order they have to be matched.
+pub mod analytics;
+pub mod announcements;
+pub mod auth;
+pub mod circles;
+pub mod hashtags;
+pub mod instance;
+pub mod media;
+pub mod mentions;
+pub mod migration;
+pub mod moderation;
+pub mod notifications;
+pub mod operations;
+pub mod realtime;
+pub mod search;
+pub mod social;
+pub mod tweets;
+pub mod users;
@@ -0,0 +1,121 @@
+use actix_web::{web, HttpResponse};
+use uuid::Uuid;
+
+use super::tweets::publish_tweet;
+use crate::auth::ModeratorUser;
+use crate::error::{ApiError, ApiResult};
+use crate::models::*;
+use crate::pagination::{self, Cursor, PageQuery};
+use crate::AppState;
+
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.route("/api/admin/abuse_events", web::get().to(get_abuse_events))
+        .route("/api/admin/held_tweets", web::get().to(get_held_tweets))
+        .route("/api/admin/held_tweets/{id}/approve", web::post().to(approve_held_tweet))
+        .route("/api/admin/held_tweets/{id}", web::delete().to(reject_held_tweet));
+}
+
+async fn get_abuse_events(state: web::Data<AppState>, _moderator: ModeratorUser, page: web::Query<PageQuery>) -> ApiResult<HttpResponse> {
+    let limit = page.limit();
+    let scope = "abuse_events";
+    let cursor = page.cursor(&state.cursors, scope).map_err(ApiError::BadRequest)?;
+
+    let mut events = sqlx::query_as::<_, AbuseEvent>(
+        "SELECT e.id, e.user_id, u.username, e.kind, e.tweet_id, e.details, e.created_at
+         FROM abuse_events e
+         INNER JOIN users u ON u.id = e.user_id
+         WHERE ($1::timestamptz IS NULL OR (e.created_at, e.id) < ($1, $2))
+         ORDER BY e.created_at DESC, e.id DESC
+         LIMIT $3"
+    )
+    .bind(cursor.map(|c| c.created_at))
+    .bind(cursor.map(|c| c.id))
+    .bind(limit + 1)
+    .fetch_all(&state.db)
+    .await?;
+
+    let next_cursor = pagination::next_cursor(&mut events, limit, &state.cursors, scope, |e| Cursor {
+        created_at: e.created_at,
+        id: e.id,
+    });
+
+    Ok(HttpResponse::Ok().json(ApiResponse {
+        success: true,
+        data: Some(events),
+        message: None,
+        next_cursor,
+    }))
+}
+
+async fn get_held_tweets(state: web::Data<AppState>, _moderator: ModeratorUser, page: web::Query<PageQuery>) -> ApiResult<HttpResponse> {
+    let limit = page.limit();
+    let scope = "held_tweets";
+    let cursor = page.cursor(&state.cursors, scope).map_err(ApiError::BadRequest)?;
+
+    let mut held = sqlx::query_as::<_, HeldTweet>(
+        "SELECT * FROM held_tweets
+         WHERE ($1::timestamptz IS NULL OR (created_at, id) < ($1, $2))
+         ORDER BY created_at DESC, id DESC
+         LIMIT $3"
+    )
+    .bind(cursor.map(|c| c.created_at))
+    .bind(cursor.map(|c| c.id))
+    .bind(limit + 1)
+    .fetch_all(&state.db)
+    .await?;
+
+    let next_cursor = pagination::next_cursor(&mut held, limit, &state.cursors, scope, |h| Cursor {
+        created_at: h.created_at,
+        id: h.id,
+    });
+
+    Ok(HttpResponse::Ok().json(ApiResponse {
+        success: true,
+        data: Some(held),
+        message: None,
+        next_cursor,
+    }))
+}
+
+async fn approve_held_tweet(state: web::Data<AppState>, _moderator: ModeratorUser, held_id: web::Path<Uuid>) -> ApiResult<HttpResponse> {
+    let held = sqlx::query_as::<_, HeldTweet>("DELETE FROM held_tweets WHERE id = $1 RETURNING *")
+        .bind(held_id.into_inner())
+        .fetch_optional(&state.db)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("Held tweet not found".to_string()))?;
+
+    let new_tweet = NewTweet {
+        id: held.tweet_id,
+        content: held.content,
+        image_url: held.image_url,
+        parent_tweet_id: held.parent_tweet_id,
+        quoted_tweet_id: held.quoted_tweet_id,
+        visibility: held.visibility.parse().map_err(ApiError::Internal)?,
+    };
+    let tweet = publish_tweet(&state, held.user_id, &new_tweet).await?;
+
+    Ok(HttpResponse::Ok().json(ApiResponse {
+        success: true,
+        data: Some(tweet.id),
+        message: Some("Tweet approved and published".to_string()),
+        next_cursor: None,
+    }))
+}
+
+async fn reject_held_tweet(state: web::Data<AppState>, _moderator: ModeratorUser, held_id: web::Path<Uuid>) -> ApiResult<HttpResponse> {
+    let result = sqlx::query("DELETE FROM held_tweets WHERE id = $1")
+        .bind(held_id.into_inner())
+        .execute(&state.db)
+        .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(ApiError::NotFound("Held tweet not found".to_string()));
+    }
+
+    Ok(HttpResponse::Ok().json(ApiResponse {
+        success: true,
+        data: Some("Held tweet rejected"),
+        message: None,
+        next_cursor: None,
+    }))
+}
@@ -0,0 +1,118 @@
+use actix_web::{web, HttpResponse};
+use std::collections::HashMap;
+use uuid::Uuid;
+
+use crate::auth::AuthenticatedUser;
+use crate::error::{ApiError, ApiResult};
+use crate::models::*;
+use crate::pagination::{self, Cursor, PageQuery};
+use crate::{unread, AppState};
+
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.route("/api/notifications", web::get().to(get_notifications))
+        .route("/api/notifications/read", web::post().to(mark_notifications_read))
+        .route("/api/notifications/unread_count", web::get().to(get_unread_notification_count));
+}
+
+async fn get_notifications(
+    state: web::Data<AppState>,
+    AuthenticatedUser(user_id): AuthenticatedUser,
+    page: web::Query<PageQuery>,
+) -> ApiResult<HttpResponse> {
+    let limit = page.limit();
+    let scope = format!("notifications:{}", user_id);
+    let cursor = page.cursor(&state.cursors, &scope).map_err(ApiError::BadRequest)?;
+
+    let mut notifications = sqlx::query_as::<_, Notification>(
+        "SELECT id, actor_id, kind, tweet_id, read_at, created_at FROM notifications n
+         WHERE user_id = $1
+           AND NOT EXISTS (SELECT 1 FROM mutes m WHERE m.muter_id = $1 AND m.muted_id = n.actor_id)
+           AND NOT EXISTS (
+               SELECT 1 FROM tweets t INNER JOIN muted_keywords k ON k.user_id = $1
+               WHERE t.id = n.tweet_id AND strpos(lower(t.content), k.keyword) > 0
+           )
+           AND ($2::timestamptz IS NULL OR (created_at, id) < ($2, $3))
+         ORDER BY created_at DESC, id DESC
+         LIMIT $4"
+    )
+    .bind(user_id)
+    .bind(cursor.map(|c| c.created_at))
+    .bind(cursor.map(|c| c.id))
+    .bind(limit + 1)
+    .fetch_all(&state.db)
+    .await?;
+
+    let next_cursor = pagination::next_cursor(&mut notifications, limit, &state.cursors, &scope, |n| Cursor {
+        created_at: n.created_at,
+        id: n.id,
+    });
+
+    let actor_ids: Vec<Uuid> = notifications.iter().map(|n| n.actor_id).collect();
+    let actors: HashMap<Uuid, User> = sqlx::query_as::<_, User>("SELECT * FROM users WHERE id = ANY($1)")
+        .bind(&actor_ids)
+        .fetch_all(&state.db)
+        .await?
+        .into_iter()
+        .map(|user| (user.id, user))
+        .collect();
+
+    let responses: Vec<NotificationResponse> = notifications
+        .into_iter()
+        .filter_map(|n| {
+            let actor = actors.get(&n.actor_id)?.clone();
+            Some(NotificationResponse {
+                id: n.id,
+                kind: n.kind,
+                actor: actor.into(),
+                tweet_id: n.tweet_id,
+                read: n.read_at.is_some(),
+                created_at: n.created_at,
+            })
+        })
+        .collect();
+
+    Ok(HttpResponse::Ok().json(ApiResponse {
+        success: true,
+        data: Some(responses),
+        message: None,
+        next_cursor,
+    }))
+}
+
+// Marks the given notifications as read, or all of them when no ids are sent.
+async fn mark_notifications_read(
+    state: web::Data<AppState>,
+    AuthenticatedUser(user_id): AuthenticatedUser,
+    req: Option<web::Json<MarkReadRequest>>,
+) -> ApiResult<HttpResponse> {
+    let ids = req.and_then(|req| req.into_inner().ids);
+
+    let result = sqlx::query(
+        "UPDATE notifications SET read_at = NOW()
+         WHERE user_id = $1 AND read_at IS NULL
+           AND ($2::uuid[] IS NULL OR id = ANY($2))"
+    )
+    .bind(user_id)
+    .bind(ids)
+    .execute(&state.db)
+    .await?;
+    state.unread.invalidate(user_id);
+
+    Ok(HttpResponse::Ok().json(ApiResponse {
+        success: true,
+        data: Some(result.rows_affected()),
+        message: Some("Notifications marked as read".to_string()),
+        next_cursor: None,
+    }))
+}
+
+async fn get_unread_notification_count(state: web::Data<AppState>, AuthenticatedUser(user_id): AuthenticatedUser) -> ApiResult<HttpResponse> {
+    let unread = unread::unread_notifications(&state.db, user_id).await?;
+
+    Ok(HttpResponse::Ok().json(ApiResponse {
+        success: true,
+        data: Some(UnreadCountResponse { unread }),
+        message: None,
+        next_cursor: None,
+    }))
+}
@@ -0,0 +1,132 @@
+use actix_web::{web, HttpResponse};
+use chrono::Utc;
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::auth::AdminUser;
+use crate::error::{ApiError, ApiResult};
+use crate::models::*;
+use crate::{analytics, index_audit, AppState};
+
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.route("/api/admin/indexes", web::get().to(get_index_audit))
+        .route("/api/admin/indexes", web::post().to(create_missing_indexes))
+        .route("/api/admin/outbound_http", web::get().to(get_outbound_http_stats))
+        .route("/api/admin/usage", web::get().to(get_route_usage))
+        .route("/api/admin/tweets/purge", web::post().to(purge_deleted_tweets))
+        .route("/api/admin/maintenance", web::post().to(create_maintenance_window))
+        .route("/api/admin/maintenance/{id}", web::delete().to(delete_maintenance_window));
+}
+
+async fn get_index_audit(state: web::Data<AppState>, _admin: AdminUser) -> ApiResult<HttpResponse> {
+    let indexes = index_audit::audit(&state.db).await?;
+
+    Ok(HttpResponse::Ok().json(ApiResponse {
+        success: true,
+        data: Some(indexes),
+        message: None,
+        next_cursor: None,
+    }))
+}
+
+// Builds the missing expected indexes. Runs until every build has finished,
+// which can take a while on large tables.
+async fn create_missing_indexes(state: web::Data<AppState>, _admin: AdminUser) -> ApiResult<HttpResponse> {
+    let created = index_audit::create_missing(&state.db).await?;
+
+    Ok(HttpResponse::Ok().json(ApiResponse {
+        success: true,
+        message: Some(format!("Created {} index(es)", created.len())),
+        data: Some(created),
+        next_cursor: None,
+    }))
+}
+
+// ============ OUTBOUND HTTP ============
+
+async fn get_outbound_http_stats(state: web::Data<AppState>, _admin: AdminUser) -> ApiResult<HttpResponse> {
+    Ok(HttpResponse::Ok().json(ApiResponse {
+        success: true,
+        data: Some(state.http.report()),
+        message: None,
+        next_cursor: None,
+    }))
+}
+
+async fn get_route_usage(state: web::Data<AppState>, _admin: AdminUser, query: web::Query<UsageQuery>) -> ApiResult<HttpResponse> {
+    let days = query.days.unwrap_or(7).clamp(1, 90);
+
+    Ok(HttpResponse::Ok().json(ApiResponse {
+        success: true,
+        data: Some(analytics::route_usage(&state.db, days).await?),
+        message: None,
+        next_cursor: None,
+    }))
+}
+
+// Permanently removes tweets deleted more than TWEET_PURGE_AFTER_DAYS ago
+async fn purge_deleted_tweets(state: web::Data<AppState>, _admin: AdminUser) -> ApiResult<HttpResponse> {
+    let purged = state.archive.purge_deleted(&state.db).await?;
+
+    Ok(HttpResponse::Ok().json(ApiResponse {
+        success: true,
+        data: Some(purged),
+        message: Some(format!("Purged {} deleted tweet(s)", purged)),
+        next_cursor: None,
+    }))
+}
+
+// ============ MAINTENANCE HANDLERS ============
+
+async fn create_maintenance_window(state: web::Data<AppState>, AdminUser(admin_id): AdminUser, req: web::Json<CreateMaintenanceWindowRequest>) -> ApiResult<HttpResponse> {
+    req.validate()?;
+
+    if req.ends_at <= req.starts_at {
+        return Err(ApiError::BadRequest("ends_at must be after starts_at".to_string()));
+    }
+    if req.ends_at <= Utc::now() {
+        return Err(ApiError::BadRequest("Maintenance window is already over".to_string()));
+    }
+
+    let window = sqlx::query_as::<_, MaintenanceWindow>(
+        "INSERT INTO maintenance_windows (created_by, reason, starts_at, ends_at)
+         VALUES ($1, $2, $3, $4)
+         RETURNING id, reason, starts_at, ends_at"
+    )
+    .bind(admin_id)
+    .bind(&req.reason)
+    .bind(req.starts_at)
+    .bind(req.ends_at)
+    .fetch_one(&state.db)
+    .await?;
+
+    // Apply immediately rather than on the next scheduler tick
+    state.maintenance.tick(&state.db).await?;
+
+    Ok(HttpResponse::Created().json(ApiResponse {
+        success: true,
+        data: Some(window),
+        message: Some("Maintenance window scheduled".to_string()),
+        next_cursor: None,
+    }))
+}
+
+async fn delete_maintenance_window(state: web::Data<AppState>, _admin: AdminUser, window_id: web::Path<Uuid>) -> ApiResult<HttpResponse> {
+    let result = sqlx::query("DELETE FROM maintenance_windows WHERE id = $1")
+        .bind(window_id.into_inner())
+        .execute(&state.db)
+        .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(ApiError::NotFound("Maintenance window not found".to_string()));
+    }
+
+    state.maintenance.tick(&state.db).await?;
+
+    Ok(HttpResponse::Ok().json(ApiResponse {
+        success: true,
+        data: Some("Maintenance window cancelled"),
+        message: None,
+        next_cursor: None,
+    }))
+}
@@ -0,0 +1,37 @@
+use actix_web::{web, HttpRequest, HttpResponse};
+
+use crate::error::ApiError;
+use crate::models::*;
+use crate::{auth, drafts, realtime, AppState};
+
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.route("/api/ws", web::get().to(ws_connect));
+}
+
+// Browsers can't set headers on WebSocket handshakes, so the token may also
+// be passed as `?token=`.
+async fn ws_connect(
+    state: web::Data<AppState>,
+    req: HttpRequest,
+    body: web::Payload,
+    query: web::Query<WsQuery>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let bearer = query.token.as_ref().map(|token| format!("Bearer {}", token));
+    let auth_header = bearer
+        .as_deref()
+        .or_else(|| req.headers().get("Authorization").and_then(|h| h.to_str().ok()));
+    let user_id = auth::get_user_id_from_token(auth_header, &state.jwt_keys).map_err(ApiError::Unauthorized)?;
+
+    let (response, session, stream) = actix_ws::handle(&req, body)?;
+
+    actix_web::rt::spawn(realtime::run_session(
+        state.db.clone(),
+        state.realtime.subscribe(),
+        session,
+        stream,
+        user_id,
+        drafts::device_label(query.device.as_deref()),
+    ));
+
+    Ok(response)
+}
@@ -0,0 +1,62 @@
+use actix_web::{web, HttpResponse};
+
+use crate::auth::AuthenticatedUser;
+use crate::error::{ApiError, ApiResult};
+use crate::models::*;
+use crate::AppState;
+
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.route("/api/search/users", web::get().to(search_users));
+}
+
+// $1 normalized query, $2 escaped LIKE prefix, $3 viewer, $4 limit. Shared
+// with the debug query plan endpoint.
+pub const USER_SEARCH_QUERY: &str = "SELECT u.* FROM users u
+         WHERE u.deactivated_at IS NULL
+           AND (lower(u.username) LIKE $2
+                OR lower(u.display_name) LIKE $2
+                OR lower(u.username) % $1
+                OR lower(u.display_name) % $1)
+         ORDER BY (lower(u.username) LIKE $2 OR lower(u.display_name) LIKE $2) DESC,
+                  EXISTS(SELECT 1 FROM follows f WHERE f.follower_id = $3 AND f.following_id = u.id) DESC,
+                  GREATEST(similarity(lower(u.username), $1), similarity(lower(u.display_name), $1)) DESC,
+                  u.followers_count DESC,
+                  u.username
+         LIMIT $4";
+
+// LIKE pattern matching everything that starts with `q`
+pub fn like_prefix(q: &str) -> String {
+    format!("{}%", q.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_"))
+}
+
+async fn search_users(
+    state: web::Data<AppState>,
+    viewer: Option<AuthenticatedUser>,
+    query: web::Query<SearchQuery>,
+) -> ApiResult<HttpResponse> {
+    let q = query.q.trim().trim_start_matches('@').to_lowercase();
+    if q.is_empty() {
+        return Err(ApiError::BadRequest("Search query is required".to_string()));
+    }
+    let limit = query.limit.unwrap_or(10).clamp(1, 20);
+    let prefix = like_prefix(&q);
+
+    // Prefix matches first (what a typeahead expects), then accounts the
+    // viewer follows, then fuzzy trigram matches by similarity
+    let users = sqlx::query_as::<_, User>(USER_SEARCH_QUERY)
+        .bind(&q)
+        .bind(&prefix)
+        .bind(viewer.map(|AuthenticatedUser(id)| id))
+        .bind(limit)
+        .fetch_all(&state.db)
+        .await?;
+
+    let users: Vec<PublicUserResponse> = users.into_iter().map(PublicUserResponse::from).collect();
+
+    Ok(HttpResponse::Ok().json(ApiResponse {
+        success: true,
+        data: Some(users),
+        message: None,
+        next_cursor: None,
+    }))
+}
@@ -0,0 +1,848 @@
+use actix_web::{web, HttpResponse};
+use futures_util::future::LocalBoxFuture;
+use sqlx::PgPool;
+use std::time::Duration;
+use uuid::Uuid;
+use validator::Validate;
+
+use super::tweets::{held_response, resolve_tweet_image, submit_tweet, Submitted};
+use crate::auth::AuthenticatedUser;
+use crate::error::{ApiError, ApiResult};
+use crate::models::*;
+use crate::repo::postgres::insert_follow;
+use crate::repo::{SocialRepo, UserRepo};
+use crate::{jobs, AppState};
+
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.route("/api/tweets/{id}/like", web::post().to(like_tweet))
+        .route("/api/tweets/{id}/unlike", web::delete().to(unlike_tweet))
+        .route("/api/tweets/{id}/retweet", web::post().to(retweet))
+        .route("/api/tweets/{id}/unretweet", web::delete().to(unretweet))
+        .route("/api/tweets/{id}/quote", web::post().to(quote_tweet))
+        .route("/api/users/{username}/follow", web::post().to(follow_user))
+        .route("/api/users/{username}/unfollow", web::delete().to(unfollow_user))
+        .route("/api/users/{username}/notify", web::post().to(set_follow_notifications))
+        .route("/api/users/{username}/block", web::post().to(block_user))
+        .route("/api/users/{username}/unblock", web::delete().to(unblock_user))
+        .route("/api/users/{username}/mute", web::post().to(mute_user))
+        .route("/api/users/{username}/unmute", web::delete().to(unmute_user))
+        .route("/api/users/me/mutes", web::get().to(get_mutes))
+        .route("/api/users/me/muted_keywords", web::get().to(get_muted_keywords))
+        .route("/api/users/me/muted_keywords", web::post().to(add_muted_keyword))
+        .route("/api/users/me/muted_keywords/{id}", web::delete().to(remove_muted_keyword))
+        .route("/api/users/me/following/export", web::get().to(export_following))
+        .route("/api/users/me/following/import", web::post().to(import_following))
+        .route("/api/users/me/following/import/{id}", web::get().to(get_follow_import));
+}
+
+// ============ LIKE HANDLERS ============
+
+// Liking a tweet that's already liked changes nothing and still succeeds, as
+// does unliking one that isn't; both answer with the like's current state.
+async fn like_tweet(social: web::Data<dyn SocialRepo>, AuthenticatedUser(user_id): AuthenticatedUser, tweet_id: web::Path<Uuid>) -> ApiResult<HttpResponse> {
+    let like = social
+        .like(user_id, tweet_id.into_inner())
+        .await?
+        .ok_or_else(|| ApiError::NotFound("Tweet not found".to_string()))?;
+
+    Ok(HttpResponse::Ok().json(ApiResponse {
+        success: true,
+        message: Some(if like.changed { "Tweet liked successfully" } else { "Tweet already liked" }.to_string()),
+        data: Some(like.state),
+        next_cursor: None,
+    }))
+}
+
+async fn unlike_tweet(social: web::Data<dyn SocialRepo>, AuthenticatedUser(user_id): AuthenticatedUser, tweet_id: web::Path<Uuid>) -> ApiResult<HttpResponse> {
+    let like = social
+        .unlike(user_id, tweet_id.into_inner())
+        .await?
+        .ok_or_else(|| ApiError::NotFound("Tweet not found".to_string()))?;
+
+    Ok(HttpResponse::Ok().json(ApiResponse {
+        success: true,
+        message: Some(if like.changed { "Tweet unliked successfully" } else { "Tweet wasn't liked" }.to_string()),
+        data: Some(like.state),
+        next_cursor: None,
+    }))
+}
+
+// ============ RETWEET HANDLERS ============
+
+// Repeating either is harmless too, and both answer with the tweet's current
+// state, like liking does.
+async fn retweet(social: web::Data<dyn SocialRepo>, AuthenticatedUser(user_id): AuthenticatedUser, tweet_id: web::Path<Uuid>) -> ApiResult<HttpResponse> {
+    let retweet = social
+        .retweet(user_id, tweet_id.into_inner())
+        .await?
+        .ok_or_else(|| ApiError::NotFound("Tweet not found".to_string()))?;
+
+    Ok(HttpResponse::Ok().json(ApiResponse {
+        success: true,
+        message: Some(if retweet.changed { "Tweet retweeted successfully" } else { "Tweet already retweeted" }.to_string()),
+        data: Some(retweet.state),
+        next_cursor: None,
+    }))
+}
+
+async fn unretweet(social: web::Data<dyn SocialRepo>, AuthenticatedUser(user_id): AuthenticatedUser, tweet_id: web::Path<Uuid>) -> ApiResult<HttpResponse> {
+    let retweet = social
+        .unretweet(user_id, tweet_id.into_inner())
+        .await?
+        .ok_or_else(|| ApiError::NotFound("Tweet not found".to_string()))?;
+
+    Ok(HttpResponse::Ok().json(ApiResponse {
+        success: true,
+        message: Some(if retweet.changed { "Tweet unretweeted successfully" } else { "Tweet wasn't retweeted" }.to_string()),
+        data: Some(retweet.state),
+        next_cursor: None,
+    }))
+}
+
+async fn quote_tweet(
+    state: web::Data<AppState>,
+    users: web::Data<dyn UserRepo>,
+    AuthenticatedUser(user_id): AuthenticatedUser,
+    tweet_id: web::Path<Uuid>,
+    quote_req: web::Json<QuoteTweetRequest>,
+) -> ApiResult<HttpResponse> {
+    quote_req.validate()?;
+    let quote_req = quote_req.into_inner();
+    let image_url = resolve_tweet_image(&state, user_id, quote_req.image_url, quote_req.media_id).await?;
+
+    let new_tweet = NewTweet {
+        id: None,
+        content: quote_req.content,
+        image_url,
+        parent_tweet_id: None,
+        quoted_tweet_id: Some(tweet_id.into_inner()),
+        visibility: quote_req.visibility,
+    };
+
+    let tweet = match submit_tweet(&state, user_id, new_tweet).await? {
+        Submitted::Published(tweet) => tweet,
+        Submitted::Held(held) => return Ok(held_response(held)),
+    };
+
+    let user = users
+        .find_by_id(user_id)
+        .await?
+        .ok_or_else(|| ApiError::Internal("Failed to fetch user data".to_string()))?;
+
+    Ok(HttpResponse::Created().json(ApiResponse {
+        success: true,
+        data: Some(TweetResponse {
+            id: tweet.id,
+            parent_tweet_id: tweet.parent_tweet_id,
+            quoted_tweet_id: tweet.quoted_tweet_id,
+            content: tweet.content,
+            image_url: tweet.image_url,
+            likes_count: tweet.likes_count,
+            retweets_count: tweet.retweets_count,
+            replies_count: tweet.replies_count,
+            visibility: tweet.visibility,
+            created_at: tweet.created_at,
+            user: user.into(),
+            is_liked: false,
+            is_retweeted: false,
+        }),
+        message: Some("Tweet quoted successfully".to_string()),
+        next_cursor: None,
+    }))
+}
+
+// ============ FOLLOW HANDLERS ============
+
+// Following an account that's already followed changes nothing and still
+// succeeds, as does unfollowing one that isn't; both answer with the follow's
+// current state. Blocks are still refused.
+async fn follow_user(
+    users: web::Data<dyn UserRepo>,
+    social: web::Data<dyn SocialRepo>,
+    AuthenticatedUser(follower_id): AuthenticatedUser,
+    username: web::Path<String>,
+) -> ApiResult<HttpResponse> {
+    let following = users
+        .find_by_username(&username)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("User not found".to_string()))?;
+
+    if follower_id == following.id {
+        return Err(ApiError::BadRequest("Cannot follow yourself".to_string()));
+    }
+    if social.blocks(following.id, follower_id).await? {
+        return Err(ApiError::Forbidden("You can't follow this user".to_string()));
+    }
+    if social.blocks(follower_id, following.id).await? {
+        return Err(ApiError::Conflict("Unblock this user before following them".to_string()));
+    }
+
+    let follow = social.follow(follower_id, following.id).await?;
+
+    Ok(HttpResponse::Ok().json(ApiResponse {
+        success: true,
+        message: Some(if follow.changed { "User followed successfully" } else { "Already following this user" }.to_string()),
+        data: Some(follow.state),
+        next_cursor: None,
+    }))
+}
+
+async fn unfollow_user(
+    users: web::Data<dyn UserRepo>,
+    social: web::Data<dyn SocialRepo>,
+    AuthenticatedUser(follower_id): AuthenticatedUser,
+    username: web::Path<String>,
+) -> ApiResult<HttpResponse> {
+    let following_id = users
+        .id_by_username(&username)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("User not found".to_string()))?;
+
+    let follow = social.unfollow(follower_id, following_id).await?;
+
+    Ok(HttpResponse::Ok().json(ApiResponse {
+        success: true,
+        message: Some(if follow.changed { "User unfollowed successfully" } else { "Not following this user" }.to_string()),
+        data: Some(follow.state),
+        next_cursor: None,
+    }))
+}
+
+async fn set_follow_notifications(
+    users: web::Data<dyn UserRepo>,
+    social: web::Data<dyn SocialRepo>,
+    AuthenticatedUser(follower_id): AuthenticatedUser,
+    username: web::Path<String>,
+    notify_req: web::Json<NotifyRequest>,
+) -> ApiResult<HttpResponse> {
+    let follow = match users.id_by_username(&username).await? {
+        Some(following_id) => social.set_follow_notifications(follower_id, following_id, notify_req.mode.as_str()).await?,
+        None => None,
+    }
+    .ok_or_else(|| ApiError::NotFound("Not following this user".to_string()))?;
+
+    Ok(HttpResponse::Ok().json(ApiResponse {
+        success: true,
+        data: Some(follow),
+        message: Some("Notification preference updated".to_string()),
+        next_cursor: None,
+    }))
+}
+
+// ============ BLOCK HANDLERS ============
+
+pub async fn is_blocked(conn: &mut sqlx::PgConnection, blocker_id: Uuid, blocked_id: Uuid) -> Result<bool, sqlx::Error> {
+    sqlx::query_scalar::<_, bool>("SELECT EXISTS(SELECT 1 FROM blocks WHERE blocker_id = $1 AND blocked_id = $2)")
+        .bind(blocker_id)
+        .bind(blocked_id)
+        .fetch_one(conn)
+        .await
+}
+
+// Blocking cuts every follow edge between the two accounts, in both
+// directions, in the same transaction as the block itself.
+async fn block_user(state: web::Data<AppState>, AuthenticatedUser(blocker_id): AuthenticatedUser, username: web::Path<String>) -> ApiResult<HttpResponse> {
+    let blocked_id = sqlx::query_scalar::<_, Uuid>("SELECT id FROM users WHERE username = $1")
+        .bind(username.as_str())
+        .fetch_optional(&state.db)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("User not found".to_string()))?;
+
+    if blocker_id == blocked_id {
+        return Err(ApiError::BadRequest("Cannot block yourself".to_string()));
+    }
+
+    if !insert_block(&state.db, blocker_id, blocked_id).await? {
+        return Err(ApiError::Conflict("Already blocked this user".to_string()));
+    }
+
+    Ok(HttpResponse::Ok().json(ApiResponse {
+        success: true,
+        data: Some("User blocked successfully"),
+        message: None,
+        next_cursor: None,
+    }))
+}
+
+// Returns false if the block already existed
+pub async fn insert_block(db: &PgPool, blocker_id: Uuid, blocked_id: Uuid) -> Result<bool, sqlx::Error> {
+    let mut tx = db.begin().await?;
+
+    let result = sqlx::query("INSERT INTO blocks (blocker_id, blocked_id) VALUES ($1, $2) ON CONFLICT DO NOTHING")
+        .bind(blocker_id)
+        .bind(blocked_id)
+        .execute(&mut *tx)
+        .await?;
+
+    if result.rows_affected() == 0 {
+        return Ok(false);
+    }
+
+    sqlx::query(
+        "DELETE FROM follows
+         WHERE (follower_id = $1 AND following_id = $2) OR (follower_id = $2 AND following_id = $1)"
+    )
+    .bind(blocker_id)
+    .bind(blocked_id)
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+
+    Ok(true)
+}
+
+async fn unblock_user(state: web::Data<AppState>, AuthenticatedUser(blocker_id): AuthenticatedUser, username: web::Path<String>) -> ApiResult<HttpResponse> {
+    let result = sqlx::query(
+        "DELETE FROM blocks
+         WHERE blocker_id = $1
+           AND blocked_id = (SELECT id FROM users WHERE username = $2)"
+    )
+    .bind(blocker_id)
+    .bind(username.as_str())
+    .execute(&state.db)
+    .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(ApiError::NotFound("Not blocking this user".to_string()));
+    }
+
+    Ok(HttpResponse::Ok().json(ApiResponse {
+        success: true,
+        data: Some("User unblocked successfully"),
+        message: None,
+        next_cursor: None,
+    }))
+}
+
+// ============ MUTE HANDLERS ============
+
+// Muting only hides the account's tweets and notifications from the muter;
+// unlike blocking it leaves follows alone and the muted account can't tell.
+async fn mute_user(state: web::Data<AppState>, AuthenticatedUser(muter_id): AuthenticatedUser, username: web::Path<String>) -> ApiResult<HttpResponse> {
+    let muted_id = sqlx::query_scalar::<_, Uuid>("SELECT id FROM users WHERE username = $1")
+        .bind(username.as_str())
+        .fetch_optional(&state.db)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("User not found".to_string()))?;
+
+    if muter_id == muted_id {
+        return Err(ApiError::BadRequest("Cannot mute yourself".to_string()));
+    }
+
+    let result = sqlx::query("INSERT INTO mutes (muter_id, muted_id) VALUES ($1, $2) ON CONFLICT DO NOTHING")
+        .bind(muter_id)
+        .bind(muted_id)
+        .execute(&state.db)
+        .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(ApiError::Conflict("Already muted this user".to_string()));
+    }
+
+    Ok(HttpResponse::Ok().json(ApiResponse {
+        success: true,
+        data: Some("User muted successfully"),
+        message: None,
+        next_cursor: None,
+    }))
+}
+
+async fn unmute_user(state: web::Data<AppState>, AuthenticatedUser(muter_id): AuthenticatedUser, username: web::Path<String>) -> ApiResult<HttpResponse> {
+    let result = sqlx::query(
+        "DELETE FROM mutes
+         WHERE muter_id = $1
+           AND muted_id = (SELECT id FROM users WHERE username = $2)"
+    )
+    .bind(muter_id)
+    .bind(username.as_str())
+    .execute(&state.db)
+    .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(ApiError::NotFound("Not muting this user".to_string()));
+    }
+
+    Ok(HttpResponse::Ok().json(ApiResponse {
+        success: true,
+        data: Some("User unmuted successfully"),
+        message: None,
+        next_cursor: None,
+    }))
+}
+
+async fn get_mutes(state: web::Data<AppState>, AuthenticatedUser(user_id): AuthenticatedUser) -> ApiResult<HttpResponse> {
+    let muted = sqlx::query_scalar::<_, String>(
+        "SELECT u.username FROM mutes m
+         JOIN users u ON u.id = m.muted_id
+         WHERE m.muter_id = $1
+         ORDER BY m.created_at"
+    )
+    .bind(user_id)
+    .fetch_all(&state.db)
+    .await?;
+
+    Ok(HttpResponse::Ok().json(ApiResponse {
+        success: true,
+        data: Some(muted),
+        message: None,
+        next_cursor: None,
+    }))
+}
+
+async fn get_muted_keywords(state: web::Data<AppState>, AuthenticatedUser(user_id): AuthenticatedUser) -> ApiResult<HttpResponse> {
+    let keywords = sqlx::query_as::<_, MutedKeyword>(
+        "SELECT id, keyword, created_at FROM muted_keywords WHERE user_id = $1 ORDER BY created_at"
+    )
+    .bind(user_id)
+    .fetch_all(&state.db)
+    .await?;
+
+    Ok(HttpResponse::Ok().json(ApiResponse {
+        success: true,
+        data: Some(keywords),
+        message: None,
+        next_cursor: None,
+    }))
+}
+
+// Keywords match case-insensitively anywhere in a tweet's text.
+async fn add_muted_keyword(
+    state: web::Data<AppState>,
+    AuthenticatedUser(user_id): AuthenticatedUser,
+    req: web::Json<MuteKeywordRequest>,
+) -> ApiResult<HttpResponse> {
+    req.validate()?;
+    let keyword = req.keyword.trim().to_lowercase();
+    if keyword.is_empty() {
+        return Err(ApiError::BadRequest("Keyword cannot be blank".to_string()));
+    }
+
+    let muted = sqlx::query_as::<_, MutedKeyword>(
+        "INSERT INTO muted_keywords (user_id, keyword) VALUES ($1, $2)
+         ON CONFLICT (user_id, keyword) DO NOTHING
+         RETURNING id, keyword, created_at"
+    )
+    .bind(user_id)
+    .bind(&keyword)
+    .fetch_optional(&state.db)
+    .await?
+    .ok_or_else(|| ApiError::Conflict("Keyword already muted".to_string()))?;
+
+    Ok(HttpResponse::Created().json(ApiResponse {
+        success: true,
+        data: Some(muted),
+        message: None,
+        next_cursor: None,
+    }))
+}
+
+async fn remove_muted_keyword(state: web::Data<AppState>, AuthenticatedUser(user_id): AuthenticatedUser, keyword_id: web::Path<Uuid>) -> ApiResult<HttpResponse> {
+    let result = sqlx::query("DELETE FROM muted_keywords WHERE id = $1 AND user_id = $2")
+        .bind(keyword_id.into_inner())
+        .bind(user_id)
+        .execute(&state.db)
+        .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(ApiError::NotFound("Muted keyword not found".to_string()));
+    }
+
+    Ok(HttpResponse::Ok().json(ApiResponse {
+        success: true,
+        data: Some("Keyword unmuted successfully"),
+        message: None,
+        next_cursor: None,
+    }))
+}
+
+// ============ FOLLOW IMPORT/EXPORT ============
+
+pub const MAX_FOLLOW_IMPORT_ROWS: usize = 5000;
+// Pause between follows so a large import doesn't flood the follow tables
+// (and the followed users' notifications) in one burst.
+pub const FOLLOW_IMPORT_DELAY: Duration = Duration::from_millis(200);
+
+async fn export_following(state: web::Data<AppState>, AuthenticatedUser(user_id): AuthenticatedUser) -> ApiResult<HttpResponse> {
+    let usernames = sqlx::query_scalar::<_, String>(
+        "SELECT u.username FROM follows f
+         JOIN users u ON u.id = f.following_id
+         WHERE f.follower_id = $1
+         ORDER BY f.created_at, u.username"
+    )
+    .bind(user_id)
+    .fetch_all(&state.db)
+    .await?;
+
+    let mut csv = String::from("username,profile_url\n");
+    for username in usernames {
+        let profile_url = format!("{}/{}", state.base_url, username);
+        csv.push_str(&format!("{},{}\n", csv_field(&username), csv_field(&profile_url)));
+    }
+
+    Ok(HttpResponse::Ok()
+        .content_type("text/csv; charset=utf-8")
+        .insert_header(("Content-Disposition", "attachment; filename=\"following.csv\""))
+        .body(csv))
+}
+
+async fn import_following(state: web::Data<AppState>, AuthenticatedUser(user_id): AuthenticatedUser, body: String) -> ApiResult<HttpResponse> {
+    let rows = parse_follow_import(&body);
+
+    if rows.is_empty() {
+        return Err(ApiError::BadRequest("No usernames found in import".to_string()));
+    }
+    if rows.len() > MAX_FOLLOW_IMPORT_ROWS {
+        return Err(ApiError::BadRequest(format!(
+            "Imports are limited to {} rows",
+            MAX_FOLLOW_IMPORT_ROWS
+        )));
+    }
+
+    let running = sqlx::query_scalar::<_, bool>(
+        "SELECT EXISTS(SELECT 1 FROM follow_imports WHERE user_id = $1 AND status IN ('pending', 'running'))"
+    )
+    .bind(user_id)
+    .fetch_one(&state.db)
+    .await?;
+
+    if running {
+        return Err(ApiError::Conflict("An import is already in progress".to_string()));
+    }
+
+    let mut tx = state.db.begin().await?;
+
+    let import = sqlx::query_as::<_, FollowImport>(
+        "INSERT INTO follow_imports (user_id, total_rows) VALUES ($1, $2) RETURNING *"
+    )
+    .bind(user_id)
+    .bind(rows.len() as i32)
+    .fetch_one(&mut *tx)
+    .await?;
+
+    jobs::enqueue(
+        &mut tx,
+        FollowImportJob::KIND,
+        &FollowImportPayload { import_id: import.id, user_id, rows },
+    )
+    .await?;
+
+    tx.commit().await?;
+
+    Ok(HttpResponse::Accepted().json(ApiResponse {
+        success: true,
+        data: Some(import),
+        message: Some("Import started".to_string()),
+        next_cursor: None,
+    }))
+}
+
+async fn get_follow_import(state: web::Data<AppState>, AuthenticatedUser(user_id): AuthenticatedUser, import_id: web::Path<Uuid>) -> ApiResult<HttpResponse> {
+    let import = sqlx::query_as::<_, FollowImport>(
+        "SELECT * FROM follow_imports WHERE id = $1 AND user_id = $2"
+    )
+    .bind(import_id.into_inner())
+    .bind(user_id)
+    .fetch_optional(&state.db)
+    .await?
+    .ok_or_else(|| ApiError::NotFound("Import not found".to_string()))?;
+
+    Ok(HttpResponse::Ok().json(ApiResponse {
+        success: true,
+        data: Some(import),
+        message: None,
+        next_cursor: None,
+    }))
+}
+
+pub struct FollowImportJob;
+
+impl FollowImportJob {
+    const KIND: &'static str = "follow_import";
+}
+
+impl jobs::JobHandler for FollowImportJob {
+    fn kind(&self) -> &'static str {
+        Self::KIND
+    }
+
+    fn run<'a>(&'a self, state: &'a AppState, job: &'a jobs::Job) -> LocalBoxFuture<'a, jobs::JobResult> {
+        Box::pin(async move {
+            let payload: FollowImportPayload = job.payload()?;
+            run_follow_import(&state.db, payload.import_id, payload.user_id, payload.rows).await?;
+            Ok(())
+        })
+    }
+
+    fn failed<'a>(&'a self, state: &'a AppState, job: &'a jobs::Job, _error: &'a str) -> LocalBoxFuture<'a, jobs::JobResult> {
+        Box::pin(async move {
+            let payload: FollowImportPayload = job.payload()?;
+            sqlx::query("UPDATE follow_imports SET status = 'failed', completed_at = NOW() WHERE id = $1")
+                .bind(payload.import_id)
+                .execute(&state.db)
+                .await?;
+            Ok(())
+        })
+    }
+}
+
+// Follows each row in turn, appending its result as it goes so the status
+// endpoint reports progress while the job runs. A retried job picks up after
+// the last recorded row.
+async fn run_follow_import(
+    db: &PgPool,
+    import_id: Uuid,
+    user_id: Uuid,
+    rows: Vec<(usize, String, Option<String>)>,
+) -> Result<(), sqlx::Error> {
+    let processed = sqlx::query_scalar::<_, i32>(
+        "UPDATE follow_imports SET status = 'running' WHERE id = $1 RETURNING processed_rows"
+    )
+    .bind(import_id)
+    .fetch_optional(db)
+    .await?;
+
+    // The import was deleted along with its user
+    let Some(processed) = processed else {
+        return Ok(());
+    };
+
+    for (row, input, username) in rows.into_iter().skip(processed as usize) {
+        let status = match &username {
+            None => FollowImportRowStatus::Invalid,
+            Some(username) => follow_import_row(db, user_id, username).await,
+        };
+
+        sqlx::query(
+            "UPDATE follow_imports
+             SET processed_rows = processed_rows + 1, results = results || $2
+             WHERE id = $1"
+        )
+        .bind(import_id)
+        .bind(sqlx::types::Json(vec![FollowImportRow { row, input, username, status }]))
+        .execute(db)
+        .await?;
+
+        if matches!(status, FollowImportRowStatus::Followed) {
+            actix_web::rt::time::sleep(FOLLOW_IMPORT_DELAY).await;
+        }
+    }
+
+    sqlx::query("UPDATE follow_imports SET status = 'completed', completed_at = NOW() WHERE id = $1")
+        .bind(import_id)
+        .execute(db)
+        .await?;
+
+    Ok(())
+}
+
+pub async fn follow_import_row(db: &PgPool, user_id: Uuid, username: &str) -> FollowImportRowStatus {
+    let following_id = match sqlx::query_scalar::<_, Uuid>("SELECT id FROM users WHERE username = $1")
+        .bind(username)
+        .fetch_optional(db)
+        .await
+    {
+        Ok(Some(id)) => id,
+        Ok(None) => return FollowImportRowStatus::NotFound,
+        Err(_) => return FollowImportRowStatus::Error,
+    };
+
+    if following_id == user_id {
+        return FollowImportRowStatus::SelfFollow;
+    }
+
+    let blocked = sqlx::query_scalar::<_, bool>(
+        "SELECT EXISTS(SELECT 1 FROM blocks WHERE (blocker_id = $1 AND blocked_id = $2) OR (blocker_id = $2 AND blocked_id = $1))"
+    )
+    .bind(user_id)
+    .bind(following_id)
+    .fetch_one(db)
+    .await;
+    match blocked {
+        Ok(true) => return FollowImportRowStatus::Blocked,
+        Ok(false) => {}
+        Err(_) => return FollowImportRowStatus::Error,
+    }
+
+    let mut conn = match db.acquire().await {
+        Ok(conn) => conn,
+        Err(_) => return FollowImportRowStatus::Error,
+    };
+    match insert_follow(&mut conn, user_id, following_id).await {
+        Ok(true) => FollowImportRowStatus::Followed,
+        Ok(false) => FollowImportRowStatus::AlreadyFollowing,
+        Err(_) => FollowImportRowStatus::Error,
+    }
+}
+
+// Reads the first column of each CSV line as a username or profile URL.
+// Returns (row number, raw input, resolved username) with the username left
+// empty for rows that can't be one. A leading "username" header is skipped.
+fn parse_follow_import(body: &str) -> Vec<(usize, String, Option<String>)> {
+    body.lines()
+        .enumerate()
+        .filter_map(|(i, line)| {
+            let input = csv_first_field(line);
+            if input.is_empty() || (i == 0 && input.eq_ignore_ascii_case("username")) {
+                return None;
+            }
+            let username = username_from_import(&input);
+            Some((i + 1, input, username))
+        })
+        .collect()
+}
+
+pub fn username_from_import(input: &str) -> Option<String> {
+    let input = input.split(['?', '#']).next().unwrap_or_default();
+    let username = if input.contains('/') {
+        input.rsplit('/').find(|segment| !segment.is_empty())?
+    } else {
+        input
+    };
+    let username = username.trim_start_matches('@');
+
+    if (3..=30).contains(&username.chars().count()) && !username.contains(':') {
+        Some(username.to_string())
+    } else {
+        None
+    }
+}
+
+pub fn csv_first_field(line: &str) -> String {
+    let line = line.trim();
+    match line.strip_prefix('"') {
+        Some(quoted) => {
+            let mut field = String::new();
+            let mut chars = quoted.chars().peekable();
+            while let Some(c) = chars.next() {
+                if c == '"' {
+                    if chars.peek() == Some(&'"') {
+                        chars.next();
+                    } else {
+                        break;
+                    }
+                }
+                field.push(c);
+            }
+            field.trim().to_string()
+        }
+        None => line.split(',').next().unwrap_or_default().trim().to_string(),
+    }
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::repo::memory::MemoryRepo;
+    use actix_web::body::to_bytes;
+    use actix_web::http::StatusCode;
+    use serde_json::json;
+    use std::sync::Arc;
+
+    async fn json(res: HttpResponse) -> serde_json::Value {
+        assert_eq!(res.status(), StatusCode::OK);
+        serde_json::from_slice(&to_bytes(res.into_body()).await.unwrap()).unwrap()
+    }
+
+    fn status(result: ApiResult<HttpResponse>) -> StatusCode {
+        match result {
+            Ok(res) => res.status(),
+            Err(e) => actix_web::ResponseError::status_code(&e),
+        }
+    }
+
+    #[actix_web::test]
+    async fn likes_and_unlikes_can_be_repeated() {
+        let social: Arc<dyn SocialRepo> = Arc::new(MemoryRepo::default());
+        let (user, tweet) = (Uuid::new_v4(), Uuid::new_v4());
+        let like = || like_tweet(web::Data::from(social.clone()), AuthenticatedUser(user), web::Path::from(tweet));
+        let unlike = || unlike_tweet(web::Data::from(social.clone()), AuthenticatedUser(user), web::Path::from(tweet));
+
+        let first = json(like().await.unwrap()).await;
+        let again = json(like().await.unwrap()).await;
+        assert_eq!(first["message"], "Tweet liked successfully");
+        assert_eq!(again["message"], "Tweet already liked");
+        assert_eq!(again["data"]["liked"], true);
+        assert_eq!(again["data"]["likes_count"], 1);
+
+        json(unlike().await.unwrap()).await;
+        let again = json(unlike().await.unwrap()).await;
+        assert_eq!(again["message"], "Tweet wasn't liked");
+        assert_eq!(again["data"]["liked"], false);
+        assert_eq!(again["data"]["likes_count"], 0);
+    }
+
+    #[actix_web::test]
+    async fn retweets_and_unretweets_can_be_repeated() {
+        let social: Arc<dyn SocialRepo> = Arc::new(MemoryRepo::default());
+        let (user, tweet) = (Uuid::new_v4(), Uuid::new_v4());
+        let retweet = || retweet(web::Data::from(social.clone()), AuthenticatedUser(user), web::Path::from(tweet));
+        let unretweet = || unretweet(web::Data::from(social.clone()), AuthenticatedUser(user), web::Path::from(tweet));
+
+        let first = json(retweet().await.unwrap()).await;
+        let again = json(retweet().await.unwrap()).await;
+        assert_eq!(first["message"], "Tweet retweeted successfully");
+        assert_eq!(again["message"], "Tweet already retweeted");
+        assert_eq!(again["data"]["retweeted"], true);
+        assert_eq!(again["data"]["retweets_count"], 1);
+
+        json(unretweet().await.unwrap()).await;
+        let again = json(unretweet().await.unwrap()).await;
+        assert_eq!(again["message"], "Tweet wasn't retweeted");
+        assert_eq!(again["data"]["retweeted"], false);
+        assert_eq!(again["data"]["retweets_count"], 0);
+    }
+
+    #[actix_web::test]
+    async fn follows_and_unfollows_can_be_repeated() {
+        let repo = Arc::new(MemoryRepo::default());
+        let alice = repo.add_user("alice");
+        let bob = repo.add_user("bob");
+        let (users, social): (Arc<dyn UserRepo>, Arc<dyn SocialRepo>) = (repo.clone(), repo.clone());
+        let follow = |username: &str| {
+            follow_user(web::Data::from(users.clone()), web::Data::from(social.clone()), AuthenticatedUser(bob), web::Path::from(username.to_string()))
+        };
+        let unfollow = || {
+            unfollow_user(web::Data::from(users.clone()), web::Data::from(social.clone()), AuthenticatedUser(bob), web::Path::from("alice".to_string()))
+        };
+        let notify = |mode: &str| {
+            set_follow_notifications(
+                web::Data::from(users.clone()),
+                web::Data::from(social.clone()),
+                AuthenticatedUser(bob),
+                web::Path::from("alice".to_string()),
+                web::Json(serde_json::from_value(json!({ "mode": mode })).unwrap()),
+            )
+        };
+
+        assert_eq!(status(notify("all").await), StatusCode::NOT_FOUND);
+        json(follow("alice").await.unwrap()).await;
+        let again = json(follow("alice").await.unwrap()).await;
+        assert_eq!(again["message"], "Already following this user");
+        assert_eq!(again["data"]["following"], true);
+        assert_eq!(again["data"]["followers_count"], 1);
+        assert_eq!(again["data"]["notify"], "off");
+
+        let notified = json(notify("all").await.unwrap()).await;
+        assert_eq!(notified["data"]["notify"], "all");
+
+        json(unfollow().await.unwrap()).await;
+        let again = json(unfollow().await.unwrap()).await;
+        assert_eq!(again["message"], "Not following this user");
+        assert_eq!(again["data"]["following"], false);
+        assert_eq!(again["data"]["followers_count"], 0);
+
+        assert_eq!(status(follow("bob").await), StatusCode::BAD_REQUEST);
+        assert_eq!(status(follow("nobody").await), StatusCode::NOT_FOUND);
+        repo.block(alice, bob);
+        assert_eq!(status(follow("alice").await), StatusCode::FORBIDDEN);
+    }
+}
@@ -0,0 +1,568 @@
+use actix_web::{web, HttpResponse};
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+use std::collections::{HashMap, HashSet};
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::auth::AuthenticatedUser;
+use crate::error::{ApiError, ApiResult};
+use crate::models::*;
+use crate::pagination::{Cursor, PageQuery};
+use crate::repo::{InsertRefused, SocialRepo, TweetPage, TweetRepo, UserRepo};
+use crate::{pagination, probation, ranking, short_links, AppState};
+
+// Other routes under /api/tweets/ with a literal second segment (nearby,
+// impressions) have to be registered ahead of this, or `{id}` takes them.
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.route("/api/tweets", web::post().to(create_tweet))
+        .route("/api/tweets/timeline", web::get().to(get_timeline))
+        .route("/api/tweets/{id}", web::get().to(get_tweet))
+        .route("/api/tweets/{id}", web::delete().to(delete_tweet))
+        .route("/api/tweets/{id}/replies", web::get().to(get_replies))
+        .route("/api/users/{username}/tweets", web::get().to(get_user_tweets));
+}
+
+// The viewer's likes and retweets among `tweet_ids`; none when signed out
+async fn engagement(tweets: &dyn TweetRepo, viewer_id: Option<Uuid>, tweet_ids: &[Uuid]) -> ApiResult<(HashSet<Uuid>, HashSet<Uuid>)> {
+    Ok(match viewer_id {
+        Some(viewer_id) => tweets.engagement(viewer_id, tweet_ids).await?,
+        None => (HashSet::new(), HashSet::new()),
+    })
+}
+
+// Inserts a tweet with everything that hangs off it: reply/quote bookkeeping,
+// hashtags, mentions, notifications and the abuse heuristics. Shared by the
+// posting handlers and by moderators releasing held tweets.
+pub async fn publish_tweet(state: &AppState, user_id: Uuid, new_tweet: &NewTweet) -> ApiResult<Tweet> {
+    let tweets = state.repos.tweets.as_ref();
+    let inserted = tweets.insert(user_id, new_tweet, &state.abuse).await?.map_err(|refused| match refused {
+        InsertRefused::ParentNotFound => ApiError::NotFound("Parent tweet not found".to_string()),
+        InsertRefused::ReplyBlocked => ApiError::Forbidden("You can't reply to this user".to_string()),
+        InsertRefused::QuotedNotFound => ApiError::NotFound("Tweet not found".to_string()),
+        InsertRefused::IdTaken => ApiError::Conflict("Tweet id is already in use".to_string()),
+    })?;
+
+    // Notify followers who turned on the bell for this account
+    if !inserted.flagged {
+        if let Err(e) = tweets.notify_subscribers(&inserted.tweet).await {
+            log::warn!("failed to notify followers of tweet {}: {}", inserted.tweet.id, e);
+        }
+    }
+
+    Ok(inserted.tweet)
+}
+
+// Runs the probation policy for the author. Tweets it holds are parked in
+// `held_tweets` for moderators and returned as a 202 response instead.
+async fn hold_if_on_probation(state: &AppState, user_id: Uuid, new_tweet: &NewTweet) -> ApiResult<Option<HeldTweet>> {
+    let account = state.repos.users.find_by_id(user_id).await?.ok_or(sqlx::Error::RowNotFound)?;
+
+    let probation::Verdict::Hold(reason) = state.probation.review_tweet(account.created_at, &new_tweet.content)? else {
+        return Ok(None);
+    };
+
+    let held = sqlx::query_as::<_, HeldTweet>(
+        "INSERT INTO held_tweets (user_id, content, image_url, parent_tweet_id, quoted_tweet_id, visibility, reason, tweet_id)
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+         ON CONFLICT (tweet_id) DO NOTHING
+         RETURNING *"
+    )
+    .bind(user_id)
+    .bind(&new_tweet.content)
+    .bind(&new_tweet.image_url)
+    .bind(new_tweet.parent_tweet_id)
+    .bind(new_tweet.quoted_tweet_id)
+    .bind(new_tweet.visibility.as_str())
+    .bind(reason)
+    .bind(new_tweet.id)
+    .fetch_optional(&state.db)
+    .await?
+    .ok_or_else(|| ApiError::Conflict("Tweet id is already in use".to_string()))?;
+
+    Ok(Some(held))
+}
+
+pub enum Submitted {
+    Published(Tweet),
+    Held(HeldTweet),
+}
+
+// Everything a new tweet goes through before it's posted: instance limits,
+// email verification, abuse throttling and probation review. Shared by the
+// posting handlers and the Twitter v2 shim.
+pub async fn submit_tweet(state: &AppState, user_id: Uuid, new_tweet: NewTweet) -> ApiResult<Submitted> {
+    state.instance.check_tweet(&new_tweet.content, new_tweet.image_url.as_deref())?;
+    state.instance.ensure_verified(&state.db, user_id).await?;
+    state.abuse.ensure_not_throttled(&state.db, user_id).await?;
+
+    if let Some(held) = hold_if_on_probation(state, user_id, &new_tweet).await? {
+        return Ok(Submitted::Held(held));
+    }
+
+    Ok(Submitted::Published(publish_tweet(state, user_id, &new_tweet).await?))
+}
+
+pub fn held_response(held: HeldTweet) -> HttpResponse {
+    HttpResponse::Accepted().json(ApiResponse {
+        success: true,
+        data: Some(held),
+        message: Some("Tweet held for review".to_string()),
+        next_cursor: None,
+    })
+}
+
+// A tweet's image is either an external URL or one of the author's uploads.
+pub async fn resolve_tweet_image(
+    state: &AppState,
+    user_id: Uuid,
+    image_url: Option<String>,
+    media_id: Option<Uuid>,
+) -> ApiResult<Option<String>> {
+    let Some(media_id) = media_id else {
+        return Ok(image_url);
+    };
+    if image_url.is_some() {
+        return Err(ApiError::BadRequest("Use either image_url or media_id, not both".to_string()));
+    }
+
+    sqlx::query_scalar::<_, String>("SELECT url FROM media WHERE id = $1 AND user_id = $2")
+        .bind(media_id)
+        .bind(user_id)
+        .fetch_optional(&state.db)
+        .await?
+        .map(Some)
+        .ok_or_else(|| ApiError::NotFound("Media not found".to_string()))
+}
+
+// A create retried with the id it was first sent with: the tweet, or held
+// tweet, that it already made. The id being used for anything else is a
+// conflict.
+async fn replay_tweet(state: &AppState, tweets: &dyn TweetRepo, user_id: Uuid, id: Uuid, content: &str) -> ApiResult<Option<Submitted>> {
+    if let Some(tweet) = tweets.find_posted(id, user_id, content).await? {
+        return Ok(Some(Submitted::Published(tweet)));
+    }
+
+    let held = sqlx::query_as::<_, HeldTweet>("SELECT * FROM held_tweets WHERE tweet_id = $1 AND user_id = $2 AND content = $3")
+        .bind(id)
+        .bind(user_id)
+        .bind(content)
+        .fetch_optional(&state.db)
+        .await?;
+    if let Some(held) = held {
+        return Ok(Some(Submitted::Held(held)));
+    }
+
+    if tweets.id_taken(id).await? {
+        return Err(ApiError::Conflict("Tweet id is already in use".to_string()));
+    }
+
+    Ok(None)
+}
+
+// With a client-chosen id, a retry answers 200 with the tweet the first
+// attempt created (or 202 again while it's held) rather than posting twice.
+async fn create_tweet(
+    state: web::Data<AppState>,
+    users: web::Data<dyn UserRepo>,
+    tweets: web::Data<dyn TweetRepo>,
+    AuthenticatedUser(user_id): AuthenticatedUser,
+    tweet_req: web::Json<CreateTweetRequest>,
+) -> ApiResult<HttpResponse> {
+    tweet_req.validate()?;
+    let tweet_req = tweet_req.into_inner();
+
+    let replayed = match tweet_req.id {
+        Some(id) => replay_tweet(&state, tweets.as_ref(), user_id, id, &tweet_req.content).await?,
+        None => None,
+    };
+    let (submitted, created) = match replayed {
+        Some(submitted) => (submitted, false),
+        None => {
+            let image_url = resolve_tweet_image(&state, user_id, tweet_req.image_url, tweet_req.media_id).await?;
+            let new_tweet = NewTweet {
+                id: tweet_req.id,
+                content: tweet_req.content.clone(),
+                image_url,
+                parent_tweet_id: tweet_req.parent_tweet_id,
+                quoted_tweet_id: None,
+                visibility: tweet_req.visibility,
+            };
+
+            match (submit_tweet(&state, user_id, new_tweet).await, tweet_req.id) {
+                (Ok(submitted), _) => (submitted, true),
+                // Lost a race with a concurrent retry
+                (Err(ApiError::Conflict(message)), Some(id)) => match replay_tweet(&state, tweets.as_ref(), user_id, id, &tweet_req.content).await? {
+                    Some(submitted) => (submitted, false),
+                    None => return Err(ApiError::Conflict(message)),
+                },
+                (Err(e), _) => return Err(e),
+            }
+        }
+    };
+
+    let tweet = match submitted {
+        Submitted::Published(tweet) => tweet,
+        Submitted::Held(held) => return Ok(held_response(held)),
+    };
+
+    // Get user info
+    let user = users
+        .find_by_id(user_id)
+        .await?
+        .ok_or_else(|| ApiError::Internal("Failed to fetch user data".to_string()))?;
+
+    // A replayed tweet may have been liked or retweeted since it was posted
+    let (liked, retweeted) = if created {
+        (HashSet::new(), HashSet::new())
+    } else {
+        engagement(tweets.as_ref(), Some(user_id), &[tweet.id]).await?
+    };
+
+    let (mut response, message) = if created {
+        (HttpResponse::Created(), "Tweet created successfully")
+    } else {
+        (HttpResponse::Ok(), "Tweet already created")
+    };
+    Ok(response.json(ApiResponse {
+        success: true,
+        data: Some(TweetResponse {
+            id: tweet.id,
+            parent_tweet_id: tweet.parent_tweet_id,
+            quoted_tweet_id: tweet.quoted_tweet_id,
+            content: tweet.content,
+            image_url: tweet.image_url,
+            likes_count: tweet.likes_count,
+            retweets_count: tweet.retweets_count,
+            replies_count: tweet.replies_count,
+            visibility: tweet.visibility,
+            created_at: tweet.created_at,
+            user: user.into(),
+            is_liked: liked.contains(&tweet.id),
+            is_retweeted: retweeted.contains(&tweet.id),
+        }),
+        message: Some(message.to_string()),
+        next_cursor: None,
+    }))
+}
+
+// Page bounds for a tweet timeline: the upper bound from the cursor, or from
+// `max_id` on a first page, and the lower bound from `since_id`.
+pub async fn tweet_page_bounds(
+    tweets: &dyn TweetRepo,
+    page: &PageQuery,
+    codec: &pagination::CursorCodec,
+    scope: &str,
+) -> ApiResult<(Option<Cursor>, Option<Cursor>)> {
+    let mut cursor = page.cursor(codec, scope).map_err(ApiError::BadRequest)?;
+    if let (None, Some(max_id)) = (cursor, page.max_id) {
+        let max = tweets.position(max_id).await?.ok_or_else(|| ApiError::BadRequest("Unknown max_id".to_string()))?;
+        cursor = Some(max.just_after());
+    }
+
+    let since = match page.since_id {
+        Some(since_id) => Some(tweets.position(since_id).await?.ok_or_else(|| ApiError::BadRequest("Unknown since_id".to_string()))?),
+        None => None,
+    };
+
+    Ok((cursor, since))
+}
+
+async fn get_timeline(
+    state: web::Data<AppState>,
+    tweets: web::Data<dyn TweetRepo>,
+    AuthenticatedUser(user_id): AuthenticatedUser,
+    page: web::Query<PageQuery>,
+) -> ApiResult<HttpResponse> {
+    let limit = page.limit();
+    let scope = format!("timeline:{}", user_id);
+    let (cursor, since) = tweet_page_bounds(tweets.as_ref(), &page, &state.cursors, &scope).await?;
+    let snapshot = page.snapshot(&state.cursors, &scope).map_err(ApiError::BadRequest)?;
+
+    // Get tweets from followed users + own tweets
+    let page = TweetPage {
+        before: cursor,
+        since,
+        limit: limit + 1,
+    };
+    let mut tweets = tweets.timeline(user_id, page, snapshot.map(|s| s.max_created_at)).await?;
+
+    // Clients that didn't send a snapshot get one anchored to the newest tweet
+    // they can see now, to send back with the following pages
+    let snapshot = snapshot.unwrap_or_else(|| pagination::Snapshot {
+        max_created_at: tweets.first().map_or_else(Utc::now, |t| t.tweet.created_at),
+    });
+
+    let next_cursor = pagination::next_cursor(&mut tweets, limit, &state.cursors, &scope, |t| Cursor {
+        created_at: t.tweet.created_at,
+        id: t.tweet.id,
+    });
+    state.analytics.count_impressions(tweets.iter().map(|t| t.tweet.id));
+    let tweet_responses: Vec<TweetResponse> = tweets
+        .into_iter()
+        .map(|row| row.tweet.into_response(row.is_liked, row.is_retweeted))
+        .collect();
+
+    Ok(HttpResponse::Ok()
+        .insert_header(("X-Timeline-Snapshot", state.cursors.encode(&scope, snapshot)))
+        .json(ApiResponse {
+            success: true,
+            data: Some(tweet_responses),
+            message: None,
+            next_cursor,
+        }))
+}
+
+// The user's tweets, and those they accepted as co-author.
+async fn get_user_tweets(
+    state: web::Data<AppState>,
+    tweets: web::Data<dyn TweetRepo>,
+    viewer: Option<AuthenticatedUser>,
+    username: web::Path<String>,
+    page: web::Query<PageQuery>,
+) -> ApiResult<HttpResponse> {
+    let limit = page.limit();
+    let scope = format!("tweets:{}", username);
+    let (cursor, since) = tweet_page_bounds(tweets.as_ref(), &page, &state.cursors, &scope).await?;
+
+    let page = TweetPage {
+        before: cursor,
+        since,
+        limit: limit + 1,
+    };
+    let viewer_id = viewer.map(|AuthenticatedUser(id)| id);
+    let mut page = tweets.by_username(&username, viewer_id, page).await?;
+
+    let next_cursor = pagination::next_cursor(&mut page, limit, &state.cursors, &scope, |t| Cursor {
+        created_at: t.created_at,
+        id: t.id,
+    });
+    state.analytics.count_impressions(page.iter().map(|t| t.id));
+    let ids: Vec<Uuid> = page.iter().map(|t| t.id).collect();
+    let (liked, retweeted) = engagement(tweets.as_ref(), viewer_id, &ids).await?;
+    let tweet_responses: Vec<TweetResponse> = page
+        .into_iter()
+        .map(|tweet| {
+            let (is_liked, is_retweeted) = (liked.contains(&tweet.id), retweeted.contains(&tweet.id));
+            tweet.into_response(is_liked, is_retweeted)
+        })
+        .collect();
+
+    Ok(HttpResponse::Ok().json(ApiResponse {
+        success: true,
+        data: Some(tweet_responses),
+        message: None,
+        next_cursor,
+    }))
+}
+
+async fn get_tweet(
+    state: web::Data<AppState>,
+    tweets: web::Data<dyn TweetRepo>,
+    viewer: Option<AuthenticatedUser>,
+    tweet_id: web::Path<Uuid>,
+    query: web::Query<TweetDetailQuery>,
+) -> ApiResult<HttpResponse> {
+    let tweet_id = tweet_id.into_inner();
+    let with_context = query.context.unwrap_or(false);
+
+    // The tweet itself is depth 0, its parent depth 1 and so on. Reads through
+    // to the archive, so old permalinks (and archived ancestors) still resolve.
+    let viewer_id = viewer.map(|AuthenticatedUser(id)| id);
+    let depth = if with_context { pagination::MAX_PARENT_CHAIN } else { 0 };
+    let mut chain = tweets.thread(tweet_id, depth, viewer_id).await?;
+
+    let tweet = chain.pop().ok_or_else(|| ApiError::NotFound("Tweet not found".to_string()))?;
+    state.analytics.count_impressions([tweet.id]);
+
+    let ids: Vec<Uuid> = chain.iter().map(|t| t.id).chain(std::iter::once(tweet.id)).collect();
+    let (liked, retweeted) = engagement(tweets.as_ref(), viewer_id, &ids).await?;
+
+    let respond = |t: TweetWithUser| {
+        let (is_liked, is_retweeted) = (liked.contains(&t.id), retweeted.contains(&t.id));
+        t.into_response(is_liked, is_retweeted)
+    };
+
+    Ok(HttpResponse::Ok().json(ApiResponse {
+        success: true,
+        data: Some(TweetDetailResponse {
+            short_url: format!("{}/t/{}", state.base_url, short_links::encode(tweet.id)),
+            card_url: format!("{}/api/tweets/{}/card.png", state.base_url, tweet.id),
+            tweet: respond(tweet),
+            ancestors: with_context.then(|| chain.into_iter().map(respond).collect()),
+        }),
+        message: None,
+        next_cursor: None,
+    }))
+}
+
+async fn get_replies(
+    state: web::Data<AppState>,
+    tweets: web::Data<dyn TweetRepo>,
+    social: web::Data<dyn SocialRepo>,
+    viewer: Option<AuthenticatedUser>,
+    tweet_id: web::Path<Uuid>,
+    query: web::Query<RepliesQuery>,
+) -> ApiResult<HttpResponse> {
+    let tweet_id = tweet_id.into_inner();
+    let sort = query.sort.unwrap_or_default();
+    let depth = query.depth.unwrap_or(pagination::DEFAULT_REPLY_DEPTH).clamp(1, pagination::MAX_REPLY_DEPTH);
+    let limit = query.limit.unwrap_or(pagination::DEFAULT_REPLIES_PER_NODE).clamp(1, pagination::MAX_REPLIES_PER_NODE);
+    // `parent` expands a subtree further down the same conversation
+    let root_id = query.parent.unwrap_or(tweet_id);
+    let scope = format!("replies:{}:{:?}", root_id, sort);
+    let offset = query
+        .cursor
+        .as_deref()
+        .map(|raw| pagination::decode_offset(&state.cursors, &scope, raw))
+        .transpose()
+        .map_err(ApiError::BadRequest)?
+        .unwrap_or(0);
+
+    let viewer_id = viewer.map(|AuthenticatedUser(id)| id);
+    // Reads through to the archive, like the tweet's own page
+    let author_id = tweets
+        .thread(tweet_id, 0, viewer_id)
+        .await?
+        .pop()
+        .map(|tweet| tweet.user_id)
+        .ok_or_else(|| ApiError::NotFound("Tweet not found".to_string()))?;
+
+    // Authentication is optional here; a signed-in viewer gets replies from
+    // people they follow boosted
+    let followed = match viewer_id {
+        Some(viewer_id) => social.following(viewer_id).await?,
+        None => HashSet::new(),
+    };
+
+    if root_id != tweet_id && !tweets.is_reply_to(root_id, tweet_id).await? {
+        return Err(ApiError::NotFound("Reply not found in this conversation".to_string()));
+    }
+
+    // Walk the reply tree below the root, `depth` levels deep
+    let tweets = tweets.replies(root_id, depth as i32, viewer_id).await?;
+
+    let mut children: HashMap<Uuid, Vec<TweetResponse>> = HashMap::new();
+    for tweet in tweets {
+        if let Some(parent_tweet_id) = tweet.parent_tweet_id {
+            children.entry(parent_tweet_id).or_default().push(tweet.into_response(false, false));
+        }
+    }
+
+    let now = Utc::now();
+    for siblings in children.values_mut() {
+        sort_replies(siblings, sort, author_id, &followed, &state.ranking, now);
+    }
+
+    let total = children.get(&root_id).map_or(0, |siblings| siblings.len());
+    let replies = build_reply_tree(root_id, &mut children, offset, limit);
+    let next_cursor = (total > offset + replies.len()).then(|| state.cursors.encode(&scope, offset + replies.len()));
+
+    Ok(HttpResponse::Ok().json(ApiResponse {
+        success: true,
+        data: Some(replies),
+        message: None,
+        next_cursor,
+    }))
+}
+
+// Replies from the tweet author come first, then replies from accounts the
+// viewer follows, then everyone else; `sort` orders replies within each group.
+fn sort_replies(
+    replies: &mut [TweetResponse],
+    sort: ReplySort,
+    author_id: Uuid,
+    followed: &HashSet<Uuid>,
+    weights: &ranking::RankingWeights,
+    now: DateTime<Utc>,
+) {
+    let boost = |tweet: &TweetResponse| {
+        if tweet.user.id == author_id {
+            0
+        } else if followed.contains(&tweet.user.id) {
+            1
+        } else {
+            2
+        }
+    };
+    let score = |t: &TweetResponse| weights.score(t.likes_count, t.retweets_count, t.replies_count, t.created_at, now);
+
+    replies.sort_by(|a, b| {
+        boost(a).cmp(&boost(b)).then_with(|| match sort {
+            ReplySort::Top => score(b).total_cmp(&score(a)),
+            ReplySort::Latest => b.created_at.cmp(&a.created_at),
+            ReplySort::Oldest => a.created_at.cmp(&b.created_at),
+        })
+    });
+}
+
+// Builds the first `limit` replies under `parent_id`, each expanded with its
+// own first `limit` replies. `has_more` marks replies whose remaining children
+// were cut off here or lie below the loaded depth.
+fn build_reply_tree(
+    parent_id: Uuid,
+    children: &mut HashMap<Uuid, Vec<TweetResponse>>,
+    offset: usize,
+    limit: usize,
+) -> Vec<ReplyNode> {
+    children
+        .remove(&parent_id)
+        .unwrap_or_default()
+        .into_iter()
+        .skip(offset)
+        .take(limit)
+        .map(|tweet| {
+            let loaded = children.get(&tweet.id).map(|replies| replies.len());
+            let replies = build_reply_tree(tweet.id, children, 0, limit);
+            let has_more = match loaded {
+                Some(count) => count > replies.len(),
+                None => tweet.replies_count > 0,
+            };
+            ReplyNode { tweet, replies, has_more }
+        })
+        .collect()
+}
+
+// Soft delete: the tweet disappears from every read, but the row stays so
+// replies keep their place in the thread. Admins purge deleted tweets for good.
+async fn delete_tweet(state: web::Data<AppState>, AuthenticatedUser(user_id): AuthenticatedUser, tweet_id: web::Path<Uuid>) -> ApiResult<HttpResponse> {
+    soft_delete_tweet(&state.db, Some(user_id), tweet_id.into_inner()).await?;
+
+    Ok(HttpResponse::Ok().json(ApiResponse {
+        success: true,
+        data: Some("Tweet deleted successfully"),
+        message: None,
+        next_cursor: None,
+    }))
+}
+
+// `author` limits it to the author's own tweets; moderators pass None.
+pub async fn soft_delete_tweet(db: &PgPool, author: Option<Uuid>, tweet_id: Uuid) -> ApiResult<()> {
+    let mut tx = db.begin().await?;
+    mark_tweet_deleted(&mut tx, author, tweet_id).await?;
+    tx.commit().await?;
+
+    Ok(())
+}
+
+pub async fn mark_tweet_deleted(conn: &mut sqlx::PgConnection, author: Option<Uuid>, tweet_id: Uuid) -> ApiResult<()> {
+    let deleted = sqlx::query(
+        "UPDATE tweets SET deleted_at = NOW()
+         WHERE id = $1 AND ($2::uuid IS NULL OR user_id = $2) AND deleted_at IS NULL"
+    )
+    .bind(tweet_id)
+    .bind(author)
+    .execute(&mut *conn)
+    .await?;
+
+    if deleted.rows_affected() == 0 {
+        return Err(ApiError::NotFound("Tweet not found or unauthorized".to_string()));
+    }
+
+    sqlx::query("DELETE FROM notifications WHERE tweet_id = $1")
+        .bind(tweet_id)
+        .execute(&mut *conn)
+        .await?;
+
+    Ok(())
+}
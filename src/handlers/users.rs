@@ -0,0 +1,259 @@
+use actix_web::{web, HttpResponse};
+use chrono::{DateTime, Utc};
+
+use super::auth::revoke_all_sessions;
+use crate::auth::AuthenticatedUser;
+use crate::error::{ApiError, ApiResult};
+use crate::models::*;
+use crate::repo::{TweetRepo, UserRepo};
+use crate::{auth, collections, AppState};
+
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.route("/api/users/{username}", web::get().to(get_user_by_username))
+        .route("/api/users/profile", web::put().to(update_profile))
+        .route("/api/users/me", web::delete().to(delete_account))
+        .route("/api/users/me/deactivate", web::post().to(deactivate_account))
+        .route("/api/users/me/automation", web::put().to(update_automation))
+        .route("/api/users/me/pinned_tweet", web::put().to(set_pinned_tweet))
+        .route("/api/users/me/data.json", web::get().to(export_data));
+}
+
+async fn get_user_by_username(
+    state: web::Data<AppState>,
+    users: web::Data<dyn UserRepo>,
+    tweets: web::Data<dyn TweetRepo>,
+    viewer: Option<AuthenticatedUser>,
+    username: web::Path<String>,
+) -> ApiResult<HttpResponse> {
+    let user = users
+        .find_by_username(&username)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("User not found".to_string()))?;
+    let featured_collections = collections::featured(&state.db, user.id).await?;
+
+    let pinned_tweet = match user.pinned_tweet_id {
+        Some(tweet_id) => tweets
+            .find_visible(tweet_id, viewer.map(|AuthenticatedUser(id)| id))
+            .await?
+            .map(|tweet| tweet.into_response(false, false)),
+        None => None,
+    };
+
+    Ok(HttpResponse::Ok().json(ApiResponse {
+        success: true,
+        data: Some(ProfileResponse {
+            profile: PublicUserResponse::from(user),
+            pinned_tweet,
+            featured_collections,
+        }),
+        message: None,
+        next_cursor: None,
+    }))
+}
+
+async fn update_profile(
+    state: web::Data<AppState>,
+    users: web::Data<dyn UserRepo>,
+    AuthenticatedUser(user_id): AuthenticatedUser,
+    update: web::Json<UpdateProfileRequest>,
+) -> ApiResult<HttpResponse> {
+    if let Some(bio) = &update.bio {
+        state.instance.check_bio(bio)?;
+    }
+
+    let user = users.update_profile(user_id, &update).await?;
+
+    Ok(HttpResponse::Ok().json(ApiResponse {
+        success: true,
+        data: Some(PrivateUserResponse::from(user)),
+        message: Some("Profile updated successfully".to_string()),
+        next_cursor: None,
+    }))
+}
+
+// Pins one of the user's own tweets to their profile, replacing any pinned
+// before; `tweet_id: null` unpins.
+async fn set_pinned_tweet(
+    users: web::Data<dyn UserRepo>,
+    tweets: web::Data<dyn TweetRepo>,
+    AuthenticatedUser(user_id): AuthenticatedUser,
+    req: web::Json<PinTweetRequest>,
+) -> ApiResult<HttpResponse> {
+    if let Some(tweet_id) = req.tweet_id {
+        if !tweets.is_author(tweet_id, user_id).await? {
+            return Err(ApiError::NotFound("Tweet not found or unauthorized".to_string()));
+        }
+    }
+
+    let user = users.set_pinned_tweet(user_id, req.tweet_id).await?;
+
+    Ok(HttpResponse::Ok().json(ApiResponse {
+        success: true,
+        data: Some(PrivateUserResponse::from(user)),
+        message: Some(if req.tweet_id.is_some() { "Tweet pinned" } else { "Tweet unpinned" }.to_string()),
+        next_cursor: None,
+    }))
+}
+
+// Marks the account as automated (or not), optionally naming the account
+// that runs it. Bots get the stricter bot rate limits from their next access
+// token on.
+async fn update_automation(
+    users: web::Data<dyn UserRepo>,
+    AuthenticatedUser(user_id): AuthenticatedUser,
+    req: web::Json<AutomationRequest>,
+) -> ApiResult<HttpResponse> {
+    let req = req.into_inner();
+
+    let owner = match (req.is_bot, req.owner.as_deref()) {
+        (true, Some(owner)) => {
+            let owner = users
+                .find_by_username(owner.trim_start_matches('@'))
+                .await?
+                .ok_or_else(|| ApiError::NotFound("Owner account not found".to_string()))?;
+
+            if owner.id == user_id {
+                return Err(ApiError::BadRequest("A bot can't be its own owner".to_string()));
+            }
+            if owner.is_bot {
+                return Err(ApiError::BadRequest("The owner must be a person's account, not another bot".to_string()));
+            }
+            Some(owner)
+        }
+        (false, Some(_)) => return Err(ApiError::BadRequest("Only bot accounts have an owner".to_string())),
+        (_, None) => None,
+    };
+
+    let user = users
+        .set_automation(user_id, req.is_bot, owner.as_ref().map(|owner| (owner.id, owner.username.as_str())))
+        .await?;
+
+    Ok(HttpResponse::Ok().json(ApiResponse {
+        success: true,
+        data: Some(PrivateUserResponse::from(user)),
+        message: Some("Automation settings updated".to_string()),
+        next_cursor: None,
+    }))
+}
+
+// Permanently deletes the account and everything that hangs off it (see
+// `UserRepo::delete`). Access tokens already handed out stop working as soon
+// as they're used against the missing account.
+async fn delete_account(
+    users: web::Data<dyn UserRepo>,
+    AuthenticatedUser(user_id): AuthenticatedUser,
+    req: web::Json<DeleteAccountRequest>,
+) -> ApiResult<HttpResponse> {
+    let password_hash = users
+        .password_hash(user_id)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("User not found".to_string()))?;
+
+    if !auth::verify_password(&req.password, &password_hash).unwrap_or(false) {
+        return Err(ApiError::Forbidden("Incorrect password".to_string()));
+    }
+
+    users.delete(user_id).await?;
+
+    log::info!("account {} deleted", user_id);
+
+    Ok(HttpResponse::Ok().json(ApiResponse::<()> {
+        success: true,
+        data: None,
+        message: Some("Account deleted".to_string()),
+        next_cursor: None,
+    }))
+}
+
+// Hides the profile and tweets and ends every session. Signing in again
+// reactivates the account.
+async fn deactivate_account(state: web::Data<AppState>, AuthenticatedUser(user_id): AuthenticatedUser) -> ApiResult<HttpResponse> {
+    let mut tx = state.db.begin().await?;
+
+    sqlx::query("UPDATE users SET deactivated_at = NOW() WHERE id = $1 AND deactivated_at IS NULL")
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await?;
+
+    revoke_all_sessions(&mut tx, user_id).await?;
+
+    tx.commit().await?;
+
+    Ok(HttpResponse::Ok().json(ApiResponse::<()> {
+        success: true,
+        data: None,
+        message: Some("Account deactivated, sign in again to reactivate it".to_string()),
+        next_cursor: None,
+    }))
+}
+
+const DATA_EXPORT_TWEETS: i64 = 200;
+const DATA_EXPORT_INTERVAL: chrono::Duration = chrono::Duration::hours(1);
+
+// A quick, synchronous snapshot of the account: profile, settings, who it
+// follows and its most recent tweets. Cheap enough to serve inline, but still
+// limited to once an hour per account.
+async fn export_data(state: web::Data<AppState>, AuthenticatedUser(user_id): AuthenticatedUser) -> ApiResult<HttpResponse> {
+    let user = sqlx::query_as::<_, User>(
+        "UPDATE users SET data_exported_at = NOW()
+         WHERE id = $1 AND (data_exported_at IS NULL OR data_exported_at <= $2)
+         RETURNING *"
+    )
+    .bind(user_id)
+    .bind(Utc::now() - DATA_EXPORT_INTERVAL)
+    .fetch_optional(&state.db)
+    .await?;
+
+    let Some(user) = user else {
+        let last = sqlx::query_scalar::<_, Option<DateTime<Utc>>>("SELECT data_exported_at FROM users WHERE id = $1")
+            .bind(user_id)
+            .fetch_optional(&state.db)
+            .await?
+            .ok_or_else(|| ApiError::NotFound("User not found".to_string()))?;
+        let next = last.map(|at| at + DATA_EXPORT_INTERVAL).unwrap_or_else(Utc::now);
+        return Err(ApiError::TooManyRequests(format!(
+            "Data can be downloaded once an hour, try again after {}",
+            next.to_rfc3339()
+        )));
+    };
+
+    let aliases = sqlx::query_scalar::<_, String>(
+        "SELECT u.username FROM account_aliases a
+         JOIN users u ON u.id = a.alias_id
+         WHERE a.user_id = $1
+         ORDER BY a.created_at"
+    )
+    .bind(user_id)
+    .fetch_all(&state.db)
+    .await?;
+
+    let following = sqlx::query_as::<_, ExportedFollow>(
+        "SELECT u.username, f.notify, f.created_at FROM follows f
+         JOIN users u ON u.id = f.following_id
+         WHERE f.follower_id = $1
+         ORDER BY f.created_at, u.username"
+    )
+    .bind(user_id)
+    .fetch_all(&state.db)
+    .await?;
+
+    let tweets = sqlx::query_as::<_, Tweet>(
+        "SELECT * FROM tweets WHERE user_id = $1 AND deleted_at IS NULL ORDER BY created_at DESC, id DESC LIMIT $2"
+    )
+    .bind(user_id)
+    .bind(DATA_EXPORT_TWEETS)
+    .fetch_all(&state.db)
+    .await?;
+
+    let export = DataExport {
+        exported_at: Utc::now(),
+        profile: PrivateUserResponse::from(user),
+        aliases,
+        following,
+        tweets,
+    };
+
+    Ok(HttpResponse::Ok()
+        .insert_header(("Content-Disposition", "attachment; filename=\"data.json\""))
+        .json(export))
+}
@@ -0,0 +1,73 @@
+use sqlx::PgConnection;
+use uuid::Uuid;
+
+pub const MAX_TAG_LENGTH: usize = 100;
+
+// Pulls `#tags` out of tweet text. A tag starts at a `#` that isn't glued to
+// a preceding word character, runs over letters, digits and underscores, and
+// must contain at least one non-digit (so "#1" isn't a tag). Tags are
+// lowercased and deduplicated in order of appearance.
+pub fn extract(content: &str) -> Vec<String> {
+    let mut tags: Vec<String> = Vec::new();
+    let mut prev: Option<char> = None;
+    let mut chars = content.char_indices().peekable();
+
+    while let Some((i, c)) = chars.next() {
+        let starts_tag = c == '#' && !prev.is_some_and(is_tag_char);
+        prev = Some(c);
+        if !starts_tag {
+            continue;
+        }
+
+        let start = i + c.len_utf8();
+        let mut end = start;
+        while let Some(&(j, next)) = chars.peek() {
+            if !is_tag_char(next) {
+                break;
+            }
+            end = j + next.len_utf8();
+            prev = Some(next);
+            chars.next();
+        }
+
+        let tag = content[start..end].to_lowercase();
+        if !tag.is_empty()
+            && tag.chars().count() <= MAX_TAG_LENGTH
+            && !tag.chars().all(|c| c.is_ascii_digit())
+            && !tags.contains(&tag)
+        {
+            tags.push(tag);
+        }
+    }
+
+    tags
+}
+
+fn is_tag_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+// Links a freshly inserted tweet to its hashtags, creating any tags seen for
+// the first time.
+pub async fn attach(conn: &mut PgConnection, tweet_id: Uuid, content: &str) -> Result<(), sqlx::Error> {
+    let tags = extract(content);
+    if tags.is_empty() {
+        return Ok(());
+    }
+
+    sqlx::query("INSERT INTO hashtags (tag) SELECT unnest($1::text[]) ON CONFLICT (tag) DO NOTHING")
+        .bind(&tags)
+        .execute(&mut *conn)
+        .await?;
+
+    sqlx::query(
+        "INSERT INTO tweet_hashtags (tweet_id, hashtag_id)
+         SELECT $1, id FROM hashtags WHERE tag = ANY($2)"
+    )
+    .bind(tweet_id)
+    .bind(&tags)
+    .execute(&mut *conn)
+    .await?;
+
+    Ok(())
+}
@@ -0,0 +1,138 @@
+use actix_web::{web, HttpResponse};
+use serde::Serialize;
+use sqlx::PgPool;
+use std::time::{Duration, Instant};
+
+use crate::db::MIGRATOR;
+use crate::models::ApiResponse;
+use crate::AppState;
+
+const CHECK_TIMEOUT: Duration = Duration::from_secs(2);
+
+// Probes for orchestrators. Liveness only says the process is serving
+// requests, so a database outage doesn't get healthy app servers restarted;
+// readiness checks the dependencies and answers 503 while any of them is
+// down, taking the instance out of rotation until it recovers.
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.route("/api/health", web::get().to(live))
+        .route("/api/health/live", web::get().to(live))
+        .route("/api/health/ready", web::get().to(ready));
+}
+
+#[derive(Debug, Serialize)]
+pub struct Readiness {
+    pub status: &'static str,
+    pub database: DependencyStatus,
+    pub migrations: MigrationStatus,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DependencyStatus {
+    pub ok: bool,
+    pub latency_ms: u128,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct MigrationStatus {
+    pub ok: bool,
+    // Latest migration applied to the database
+    pub version: Option<i64>,
+    // Latest migration this build ships
+    pub expected: Option<i64>,
+}
+
+async fn live() -> HttpResponse {
+    HttpResponse::Ok().json(ApiResponse {
+        success: true,
+        data: Some("Twitter API is running with PostgreSQL"),
+        message: None,
+        next_cursor: None,
+    })
+}
+
+async fn ready(state: web::Data<AppState>) -> HttpResponse {
+    let database = check_database(&state.db).await;
+    let migrations = check_migrations(&state.db, database.ok).await;
+    let ok = database.ok && migrations.ok;
+
+    let body = ApiResponse {
+        success: ok,
+        data: Some(Readiness {
+            status: if ok { "ready" } else { "degraded" },
+            database,
+            migrations,
+        }),
+        message: None,
+        next_cursor: None,
+    };
+    if ok {
+        HttpResponse::Ok().json(body)
+    } else {
+        HttpResponse::ServiceUnavailable().json(body)
+    }
+}
+
+async fn check_database(db: &PgPool) -> DependencyStatus {
+    let started = Instant::now();
+    let result = tokio::time::timeout(CHECK_TIMEOUT, sqlx::query("SELECT 1").execute(db)).await;
+    let error = match result {
+        Ok(Ok(_)) => None,
+        Ok(Err(e)) => Some(e.to_string()),
+        Err(_) => Some(format!("no response within {}s", CHECK_TIMEOUT.as_secs())),
+    };
+    DependencyStatus {
+        ok: error.is_none(),
+        latency_ms: started.elapsed().as_millis(),
+        error,
+    }
+}
+
+// Behind while a deploy's migrations are still running elsewhere; ahead is
+// fine, since migrations are additive and an older build keeps working
+async fn check_migrations(db: &PgPool, reachable: bool) -> MigrationStatus {
+    let expected = MIGRATOR.iter().map(|m| m.version).max();
+    let version = if reachable {
+        sqlx::query_scalar::<_, Option<i64>>("SELECT MAX(version) FROM _sqlx_migrations WHERE success")
+            .fetch_one(db)
+            .await
+            .ok()
+            .flatten()
+    } else {
+        None
+    };
+
+    MigrationStatus {
+        ok: is_schema_current(version, expected),
+        version,
+        expected,
+    }
+}
+
+fn is_schema_current(version: Option<i64>, expected: Option<i64>) -> bool {
+    match (version, expected) {
+        (_, None) => true,
+        (Some(version), Some(expected)) => version >= expected,
+        (None, Some(_)) => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn schema_is_current_at_or_past_the_latest_migration() {
+        assert!(is_schema_current(Some(45), Some(45)));
+        assert!(is_schema_current(Some(46), Some(45)));
+        assert!(!is_schema_current(Some(44), Some(45)));
+        assert!(!is_schema_current(None, Some(45)));
+        assert!(is_schema_current(None, None));
+    }
+
+    #[test]
+    fn build_ships_migrations() {
+        assert!(MIGRATOR.iter().map(|m| m.version).max().is_some());
+    }
+}
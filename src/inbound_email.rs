@@ -0,0 +1,487 @@
+use actix_multipart::Multipart;
+use actix_web::{web, HttpRequest, HttpResponse};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use futures_util::StreamExt;
+use serde_json::json;
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+use crate::auth::{self, AuthenticatedUser};
+use crate::config;
+use crate::error::{ApiError, ApiResult};
+use crate::models::{ApiResponse, NewTweet, TweetVisibility};
+use crate::handlers::tweets::{held_response, submit_tweet, Submitted};
+use crate::{secrets, AppState};
+
+// Text fields larger than this are cut off; a tweet is far shorter anyway
+const MAX_FIELD_BYTES: usize = 256 * 1024;
+
+// Post-by-email. Each user can create a secret address `<token>@<domain>`;
+// mail sent to it becomes a tweet from their account, with the first image
+// attachment going through the media pipeline. The address is the only
+// credential, so it's shown once and can be rotated or removed.
+//
+// Mail arrives through the provider's inbound webhook, pointed at
+// /api/inbound/email/<INBOUND_EMAIL_KEY>. Any provider that posts
+// multipart/form-data, urlencoded forms or JSON with the usual field names
+// works (Mailgun, SendGrid and Postmark do); see `ParsedEmail::from_fields`.
+// Redelivered messages are recognised by Message-ID and posted once.
+// Disabled unless INBOUND_EMAIL_DOMAIN and INBOUND_EMAIL_KEY are set.
+#[derive(Debug, Clone)]
+pub struct InboundEmail {
+    domain: Option<String>,
+    key_hash: Option<String>,
+}
+
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.route("/api/inbound/email/{key}", web::post().to(receive_email))
+        .route("/api/users/me/post_email", web::post().to(create_address))
+        .route("/api/users/me/post_email", web::delete().to(remove_address));
+}
+
+#[derive(Debug, Default, PartialEq)]
+pub struct ParsedEmail {
+    pub message_id: Option<String>,
+    pub recipients: Vec<String>,
+    pub subject: String,
+    pub text: String,
+    pub attachments: Vec<Attachment>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Attachment {
+    pub content_type: String,
+    pub bytes: Vec<u8>,
+}
+
+impl InboundEmail {
+    pub fn from_env() -> Self {
+        let enabled = |v: &String| !v.trim().is_empty();
+        InboundEmail {
+            domain: config::var("INBOUND_EMAIL_DOMAIN").filter(enabled).map(|d| d.trim().to_lowercase()),
+            key_hash: secrets::var("INBOUND_EMAIL_KEY").filter(enabled).map(|key| auth::hash_token(&key)),
+        }
+    }
+
+    fn domain(&self) -> ApiResult<&str> {
+        match (&self.domain, &self.key_hash) {
+            (Some(domain), Some(_)) => Ok(domain),
+            _ => Err(ApiError::NotFound("Post by email is not enabled".to_string())),
+        }
+    }
+
+    // Token of the first recipient on our domain
+    fn token_for(&self, email: &ParsedEmail) -> Option<String> {
+        let domain = self.domain.as_deref()?;
+        email.recipients.iter().find_map(|address| {
+            let (local, host) = address.rsplit_once('@')?;
+            (host == domain && !local.is_empty()).then(|| local.to_string())
+        })
+    }
+}
+
+impl ParsedEmail {
+    // Maps a provider's fields onto one shape. Names are matched
+    // case-insensitively; where several carry the same thing the first in
+    // each list wins (stripped replies before the full body, the envelope
+    // recipient before the To header).
+    pub fn from_fields(fields: &[(String, String)], attachments: Vec<Attachment>) -> Self {
+        let get = |names: &[&str]| {
+            names.iter().find_map(|name| {
+                fields
+                    .iter()
+                    .find(|(key, value)| key.eq_ignore_ascii_case(name) && !value.trim().is_empty())
+                    .map(|(_, value)| value.as_str())
+            })
+        };
+
+        let message_id = get(&["message-id", "messageid"])
+            .map(str::to_string)
+            .or_else(|| get(&["headers"]).and_then(header_message_id))
+            .map(|id| id.trim().trim_start_matches('<').trim_end_matches('>').to_string());
+        let recipients = get(&["recipient", "originalrecipient", "to"]).map(parse_addresses).unwrap_or_default();
+
+        ParsedEmail {
+            message_id,
+            recipients,
+            subject: get(&["subject"]).unwrap_or_default().trim().to_string(),
+            text: strip_signature(get(&["stripped-text", "strippedtextreply", "body-plain", "text", "textbody"]).unwrap_or_default()),
+            attachments,
+        }
+    }
+
+    // Postmark-style JSON: top-level strings plus an `Attachments` array of
+    // base64 `Content` with a `ContentType`
+    pub fn from_json(body: &serde_json::Value) -> Self {
+        let Some(object) = body.as_object() else {
+            return ParsedEmail::default();
+        };
+
+        let fields: Vec<(String, String)> = object
+            .iter()
+            .filter_map(|(key, value)| value.as_str().map(|v| (key.clone(), v.to_string())))
+            .collect();
+        let attachments = object
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case("attachments"))
+            .and_then(|(_, value)| value.as_array())
+            .map(|items| {
+                items
+                    .iter()
+                    .filter_map(|item| {
+                        let content = item.get("Content")?.as_str()?;
+                        Some(Attachment {
+                            content_type: item.get("ContentType").and_then(|v| v.as_str()).unwrap_or_default().to_string(),
+                            bytes: STANDARD.decode(content).ok()?,
+                        })
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        ParsedEmail::from_fields(&fields, attachments)
+    }
+
+    // Text if there is any, else the subject
+    fn content(&self) -> &str {
+        if self.text.is_empty() {
+            &self.subject
+        } else {
+            &self.text
+        }
+    }
+
+    // Message-ID, or a digest of the message for providers that don't pass it
+    fn dedup_key(&self) -> String {
+        match &self.message_id {
+            Some(id) => id.clone(),
+            None => {
+                let mut digest = Sha256::new();
+                digest.update(self.recipients.join(",").as_bytes());
+                digest.update([0]);
+                digest.update(self.subject.as_bytes());
+                digest.update([0]);
+                digest.update(self.text.as_bytes());
+                format!("sha256:{}", hex::encode(digest.finalize()))
+            }
+        }
+    }
+}
+
+// `Name <a@example.com>, b@example.com` -> bare, lowercased addresses
+fn parse_addresses(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .filter_map(|part| {
+            let part = part.trim();
+            let address = match (part.rfind('<'), part.rfind('>')) {
+                (Some(start), Some(end)) if start < end => &part[start + 1..end],
+                _ => part,
+            };
+            let address = address.trim().to_lowercase();
+            address.contains('@').then_some(address)
+        })
+        .collect()
+}
+
+// SendGrid passes the raw header block instead of a Message-ID field
+fn header_message_id(headers: &str) -> Option<String> {
+    headers.lines().find_map(|line| {
+        let (name, value) = line.split_once(':')?;
+        name.trim().eq_ignore_ascii_case("message-id").then(|| value.trim().to_string())
+    })
+}
+
+// Drops everything from the conventional `-- ` signature delimiter on
+fn strip_signature(text: &str) -> String {
+    let body = text
+        .lines()
+        .take_while(|line| line.trim_end() != "--")
+        .collect::<Vec<_>>()
+        .join("\n");
+    body.trim().to_string()
+}
+
+async fn read_multipart(mut payload: Multipart, max_attachment_bytes: usize) -> ApiResult<ParsedEmail> {
+    let invalid = |e: actix_multipart::MultipartError| ApiError::BadRequest(format!("Invalid multipart body: {}", e));
+    let mut fields = Vec::new();
+    let mut attachments = Vec::new();
+
+    while let Some(field) = payload.next().await {
+        let mut field = field.map_err(invalid)?;
+        let name = field.name().unwrap_or_default().to_string();
+        let is_file = field.content_disposition().and_then(|cd| cd.get_filename()).is_some();
+        let content_type = field.content_type().map(|mime| mime.essence_str().to_string()).unwrap_or_default();
+        let limit = if is_file { max_attachment_bytes } else { MAX_FIELD_BYTES };
+
+        let mut bytes = Vec::new();
+        let mut oversized = false;
+        while let Some(chunk) = field.next().await {
+            let chunk = chunk.map_err(invalid)?;
+            if bytes.len() + chunk.len() > limit {
+                oversized = true;
+                continue;
+            }
+            bytes.extend_from_slice(&chunk);
+        }
+
+        if is_file {
+            // Oversized attachments are dropped rather than failing the mail
+            if !oversized {
+                attachments.push(Attachment { content_type, bytes });
+            }
+        } else {
+            fields.push((name, String::from_utf8_lossy(&bytes).into_owned()));
+        }
+    }
+
+    Ok(ParsedEmail::from_fields(&fields, attachments))
+}
+
+async fn read_email(req: &HttpRequest, mut payload: web::Payload, max_attachment_bytes: usize) -> ApiResult<ParsedEmail> {
+    let content_type = req
+        .headers()
+        .get(actix_web::http::header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or_default()
+        .to_ascii_lowercase();
+
+    if content_type.starts_with("multipart/form-data") {
+        return read_multipart(Multipart::new(req.headers(), payload), max_attachment_bytes).await;
+    }
+
+    // JSON bodies carry attachments inline, base64 encoded
+    let max_body = MAX_FIELD_BYTES + max_attachment_bytes * 4 / 3 + 1024;
+    let mut body = web::BytesMut::new();
+    while let Some(chunk) = payload.next().await {
+        let chunk = chunk.map_err(|e| ApiError::BadRequest(format!("Invalid body: {}", e)))?;
+        if body.len() + chunk.len() > max_body {
+            return Err(ApiError::BadRequest("Message is too large".to_string()));
+        }
+        body.extend_from_slice(&chunk);
+    }
+
+    if content_type.starts_with("application/json") {
+        let value: serde_json::Value = serde_json::from_slice(&body).map_err(|e| ApiError::BadRequest(format!("Invalid JSON: {}", e)))?;
+        Ok(ParsedEmail::from_json(&value))
+    } else if content_type.starts_with("application/x-www-form-urlencoded") {
+        let fields: Vec<(String, String)> = serde_urlencoded::from_bytes(&body).map_err(|e| ApiError::BadRequest(format!("Invalid form body: {}", e)))?;
+        Ok(ParsedEmail::from_fields(&fields, Vec::new()))
+    } else {
+        Err(ApiError::BadRequest("Unsupported content type".to_string()))
+    }
+}
+
+async fn receive_email(
+    state: web::Data<AppState>,
+    req: HttpRequest,
+    key: web::Path<String>,
+    payload: web::Payload,
+) -> ApiResult<HttpResponse> {
+    state.inbound_email.domain()?;
+    if state.inbound_email.key_hash.as_deref() != Some(auth::hash_token(&key).as_str()) {
+        return Err(ApiError::NotFound("Post by email is not enabled".to_string()));
+    }
+
+    let email = read_email(&req, payload, state.media.max_bytes).await?;
+    let token = state
+        .inbound_email
+        .token_for(&email)
+        .ok_or_else(|| ApiError::NotFound("No post-by-email address among the recipients".to_string()))?;
+
+    let (user_id, verified_email) = sqlx::query_as::<_, (Uuid, bool)>(
+        "SELECT id, verified_email FROM users WHERE post_email_token_hash = $1 AND deactivated_at IS NULL"
+    )
+    .bind(auth::hash_token(&token))
+    .fetch_optional(&state.db)
+    .await?
+    .ok_or_else(|| ApiError::NotFound("Unknown post-by-email address".to_string()))?;
+
+    if !verified_email {
+        return Err(ApiError::Forbidden("Verify your email address to post by email".to_string()));
+    }
+
+    let dedup_key = email.dedup_key();
+    let claimed = sqlx::query("INSERT INTO inbound_emails (message_id, user_id) VALUES ($1, $2) ON CONFLICT DO NOTHING")
+        .bind(&dedup_key)
+        .bind(user_id)
+        .execute(&state.db)
+        .await?;
+    if claimed.rows_affected() == 0 {
+        return Ok(HttpResponse::Ok().json(ApiResponse {
+            success: true,
+            data: None::<()>,
+            message: Some("Message already processed".to_string()),
+            next_cursor: None,
+        }));
+    }
+
+    let posted = post_email(&state, user_id, email).await;
+
+    // A failed message may be redelivered and should get another go then
+    if posted.is_err() {
+        sqlx::query("DELETE FROM inbound_emails WHERE message_id = $1")
+            .bind(&dedup_key)
+            .execute(&state.db)
+            .await?;
+    }
+
+    match posted? {
+        Submitted::Published(tweet) => {
+            sqlx::query("UPDATE inbound_emails SET tweet_id = $1 WHERE message_id = $2")
+                .bind(tweet.id)
+                .bind(&dedup_key)
+                .execute(&state.db)
+                .await?;
+
+            Ok(HttpResponse::Created().json(ApiResponse {
+                success: true,
+                data: Some(json!({ "tweet_id": tweet.id })),
+                message: Some("Tweet created successfully".to_string()),
+                next_cursor: None,
+            }))
+        }
+        Submitted::Held(held) => Ok(held_response(held)),
+    }
+}
+
+async fn post_email(state: &AppState, user_id: Uuid, email: ParsedEmail) -> ApiResult<Submitted> {
+    let content = email.content().to_string();
+    if content.is_empty() {
+        return Err(ApiError::BadRequest("Message has no text".to_string()));
+    }
+
+    let image = email.attachments.into_iter().find(|a| a.content_type.starts_with("image/"));
+    let image_url = match image {
+        Some(image) => Some(crate::handlers::media::store_image(state, user_id, image.bytes).await?.url),
+        None => None,
+    };
+
+    let new_tweet = NewTweet {
+        id: None,
+        content,
+        image_url,
+        parent_tweet_id: None,
+        quoted_tweet_id: None,
+        visibility: TweetVisibility::Public,
+    };
+    submit_tweet(state, user_id, new_tweet).await
+}
+
+// Creates or rotates the caller's address; the previous one stops working.
+async fn create_address(state: web::Data<AppState>, AuthenticatedUser(user_id): AuthenticatedUser) -> ApiResult<HttpResponse> {
+    let domain = state.inbound_email.domain()?;
+
+    let verified_email = sqlx::query_scalar::<_, bool>("SELECT verified_email FROM users WHERE id = $1")
+        .bind(user_id)
+        .fetch_one(&state.db)
+        .await?;
+    if !verified_email {
+        return Err(ApiError::Forbidden("Verify your email address to post by email".to_string()));
+    }
+
+    let token = auth::generate_token();
+    sqlx::query("UPDATE users SET post_email_token_hash = $1 WHERE id = $2")
+        .bind(auth::hash_token(&token))
+        .bind(user_id)
+        .execute(&state.db)
+        .await?;
+
+    Ok(HttpResponse::Created().json(ApiResponse {
+        success: true,
+        data: Some(json!({ "address": format!("{}@{}", token, domain) })),
+        message: Some("Keep this address private: anyone who knows it can post as you".to_string()),
+        next_cursor: None,
+    }))
+}
+
+async fn remove_address(state: web::Data<AppState>, AuthenticatedUser(user_id): AuthenticatedUser) -> ApiResult<HttpResponse> {
+    let result = sqlx::query("UPDATE users SET post_email_token_hash = NULL WHERE id = $1 AND post_email_token_hash IS NOT NULL")
+        .bind(user_id)
+        .execute(&state.db)
+        .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(ApiError::NotFound("No post-by-email address".to_string()));
+    }
+
+    Ok(HttpResponse::Ok().json(ApiResponse {
+        success: true,
+        data: Some("Post-by-email address removed"),
+        message: None,
+        next_cursor: None,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fields(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+        pairs.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect()
+    }
+
+    #[test]
+    fn parses_mailgun_fields() {
+        let email = ParsedEmail::from_fields(
+            &fields(&[
+                ("recipient", "abc123@post.example.com"),
+                ("subject", "Hello"),
+                ("body-plain", "Full body\n> quoted"),
+                ("stripped-text", "Just this\n-- \nErin"),
+                ("Message-Id", "<m1@mail.example.com>"),
+            ]),
+            Vec::new(),
+        );
+        assert_eq!(email.recipients, vec!["abc123@post.example.com"]);
+        assert_eq!(email.text, "Just this");
+        assert_eq!(email.message_id.as_deref(), Some("m1@mail.example.com"));
+    }
+
+    #[test]
+    fn parses_sendgrid_fields() {
+        let email = ParsedEmail::from_fields(
+            &fields(&[
+                ("to", "Posts <ABC123@Post.Example.com>, other@example.com"),
+                ("text", "From SendGrid"),
+                ("headers", "Subject: x\nMessage-ID: <m2@example.com>\nFrom: a@example.com"),
+            ]),
+            Vec::new(),
+        );
+        assert_eq!(email.recipients, vec!["abc123@post.example.com", "other@example.com"]);
+        assert_eq!(email.message_id.as_deref(), Some("m2@example.com"));
+        assert_eq!(email.content(), "From SendGrid");
+    }
+
+    #[test]
+    fn parses_postmark_json() {
+        let email = ParsedEmail::from_json(&json!({
+            "To": "abc123@post.example.com",
+            "Subject": "Only a subject",
+            "TextBody": "",
+            "MessageID": "m3",
+            "Attachments": [{ "Name": "a.png", "Content": STANDARD.encode(b"png"), "ContentType": "image/png" }]
+        }));
+        assert_eq!(email.content(), "Only a subject");
+        assert_eq!(email.attachments, vec![Attachment { content_type: "image/png".to_string(), bytes: b"png".to_vec() }]);
+    }
+
+    #[test]
+    fn finds_token_on_configured_domain() {
+        let inbound = InboundEmail { domain: Some("post.example.com".to_string()), key_hash: Some(String::new()) };
+        let email = ParsedEmail {
+            recipients: vec!["someone@example.com".to_string(), "abc123@post.example.com".to_string()],
+            ..Default::default()
+        };
+        assert_eq!(inbound.token_for(&email).as_deref(), Some("abc123"));
+    }
+
+    #[test]
+    fn dedup_key_falls_back_to_digest() {
+        let a = ParsedEmail { text: "hi".to_string(), ..Default::default() };
+        let b = ParsedEmail { text: "hello".to_string(), ..Default::default() };
+        assert!(a.dedup_key().starts_with("sha256:"));
+        assert_ne!(a.dedup_key(), b.dedup_key());
+    }
+}
@@ -0,0 +1,198 @@
+use serde::Serialize;
+use sqlx::{FromRow, PgPool};
+
+// An index the hot read paths depend on. It counts as present when any index
+// on `table` with the same access method starts with `columns` (so a unique
+// constraint on (a, b) covers an expected index on (a)). Columns are spelled
+// the way Postgres prints them, casts included.
+struct ExpectedIndex {
+    name: &'static str,
+    table: &'static str,
+    method: &'static str,
+    columns: &'static [&'static str],
+    definition: &'static str,
+}
+
+const EXPECTED_INDEXES: &[ExpectedIndex] = &[
+    ExpectedIndex {
+        name: "idx_tweets_user_id_created_at",
+        table: "tweets",
+        method: "btree",
+        columns: &["user_id", "created_at"],
+        definition: "tweets (user_id, created_at DESC)",
+    },
+    ExpectedIndex {
+        name: "idx_tweets_parent_tweet_id",
+        table: "tweets",
+        method: "btree",
+        columns: &["parent_tweet_id"],
+        definition: "tweets (parent_tweet_id)",
+    },
+    ExpectedIndex {
+        name: "idx_tweets_content_fts",
+        table: "tweets",
+        method: "gin",
+        columns: &["to_tsvector('english'::regconfig, content)"],
+        definition: "tweets USING GIN (to_tsvector('english', content))",
+    },
+    ExpectedIndex {
+        name: "idx_likes_user_id_tweet_id",
+        table: "likes",
+        method: "btree",
+        columns: &["user_id", "tweet_id"],
+        definition: "likes (user_id, tweet_id)",
+    },
+    ExpectedIndex {
+        name: "idx_likes_tweet_id",
+        table: "likes",
+        method: "btree",
+        columns: &["tweet_id"],
+        definition: "likes (tweet_id)",
+    },
+    ExpectedIndex {
+        name: "idx_follows_follower_id_following_id",
+        table: "follows",
+        method: "btree",
+        columns: &["follower_id", "following_id"],
+        definition: "follows (follower_id, following_id)",
+    },
+    ExpectedIndex {
+        name: "idx_follows_following_id",
+        table: "follows",
+        method: "btree",
+        columns: &["following_id"],
+        definition: "follows (following_id)",
+    },
+    ExpectedIndex {
+        name: "idx_users_username_lower",
+        table: "users",
+        method: "btree",
+        columns: &["lower(username::text)"],
+        definition: "users (lower(username))",
+    },
+    ExpectedIndex {
+        name: "idx_users_username_trgm",
+        table: "users",
+        method: "gin",
+        columns: &["lower(username::text)"],
+        definition: "users USING GIN (lower(username) gin_trgm_ops)",
+    },
+    ExpectedIndex {
+        name: "idx_users_display_name_trgm",
+        table: "users",
+        method: "gin",
+        columns: &["lower(display_name::text)"],
+        definition: "users USING GIN (lower(display_name) gin_trgm_ops)",
+    },
+    ExpectedIndex {
+        name: "idx_tweet_hashtags_hashtag_id",
+        table: "tweet_hashtags",
+        method: "btree",
+        columns: &["hashtag_id"],
+        definition: "tweet_hashtags (hashtag_id)",
+    },
+    ExpectedIndex {
+        name: "idx_mentions_user_id_created_at",
+        table: "mentions",
+        method: "btree",
+        columns: &["user_id", "created_at"],
+        definition: "mentions (user_id, created_at DESC)",
+    },
+    ExpectedIndex {
+        name: "idx_notifications_user_id_created_at",
+        table: "notifications",
+        method: "btree",
+        columns: &["user_id", "created_at"],
+        definition: "notifications (user_id, created_at DESC)",
+    },
+];
+
+#[derive(Debug, Serialize)]
+pub struct IndexStatus {
+    pub name: &'static str,
+    pub table: &'static str,
+    pub definition: &'static str,
+    pub present: bool,
+}
+
+#[derive(FromRow)]
+struct ExistingIndex {
+    table_name: String,
+    method: String,
+    columns: Vec<String>,
+}
+
+// Compares the expected indexes with what the current schema actually has.
+pub async fn audit(db: &PgPool) -> Result<Vec<IndexStatus>, sqlx::Error> {
+    let existing = sqlx::query_as::<_, ExistingIndex>(
+        "SELECT c.relname::text AS table_name, am.amname::text AS method,
+                ARRAY(SELECT pg_get_indexdef(i.indexrelid, k + 1, true)
+                      FROM generate_subscripts(i.indkey, 1) k
+                      ORDER BY k) AS columns
+         FROM pg_index i
+         JOIN pg_class c ON c.oid = i.indrelid
+         JOIN pg_class ic ON ic.oid = i.indexrelid
+         JOIN pg_am am ON am.oid = ic.relam
+         JOIN pg_namespace n ON n.oid = c.relnamespace
+         WHERE n.nspname = current_schema() AND i.indisvalid"
+    )
+    .fetch_all(db)
+    .await?;
+
+    Ok(EXPECTED_INDEXES
+        .iter()
+        .map(|expected| IndexStatus {
+            name: expected.name,
+            table: expected.table,
+            definition: expected.definition,
+            present: existing.iter().any(|index| {
+                index.table_name == expected.table
+                    && index.method == expected.method
+                    && index.columns.len() >= expected.columns.len()
+                    && index.columns.iter().zip(expected.columns).all(|(column, expected)| column == expected)
+            }),
+        })
+        .collect())
+}
+
+// Builds every missing index. CONCURRENTLY keeps the tables writable, which
+// also means this must not run inside a transaction.
+pub async fn create_missing(db: &PgPool) -> Result<Vec<&'static str>, sqlx::Error> {
+    let mut created = Vec::new();
+
+    for status in audit(db).await?.into_iter().filter(|status| !status.present) {
+        sqlx::query(&format!("CREATE INDEX CONCURRENTLY IF NOT EXISTS {} ON {}", status.name, status.definition))
+            .execute(db)
+            .await?;
+        log::info!("Created missing index {} on {}", status.name, status.definition);
+        created.push(status.name);
+    }
+
+    Ok(created)
+}
+
+// Startup check: warns about every missing index, or builds them when
+// INDEX_AUDIT_CREATE_MISSING=true.
+pub async fn check_on_startup(db: &PgPool, create: bool) -> Result<(), sqlx::Error> {
+    if create {
+        create_missing(db).await?;
+        return Ok(());
+    }
+
+    let missing: Vec<IndexStatus> = audit(db).await?.into_iter().filter(|status| !status.present).collect();
+    for status in &missing {
+        log::warn!(
+            "MISSING INDEX {} on {}: hot-path queries will fall back to sequential scans",
+            status.name,
+            status.definition
+        );
+    }
+    if !missing.is_empty() {
+        log::warn!(
+            "{} expected index(es) missing; set INDEX_AUDIT_CREATE_MISSING=true or POST /api/admin/indexes to build them",
+            missing.len()
+        );
+    }
+
+    Ok(())
+}
@@ -0,0 +1,84 @@
+use serde::Serialize;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::config;
+use crate::error::ApiError;
+
+// Per-instance limits. Defaults match the classic Twitter numbers; self-hosters
+// can raise them (e.g. for long-form instances) through the environment.
+#[derive(Debug, Clone, Serialize)]
+pub struct InstanceSettings {
+    pub name: String,
+    pub version: &'static str,
+    pub max_tweet_length: usize,
+    pub max_bio_length: usize,
+    pub max_media_per_tweet: usize,
+    // Accounts must confirm their email address before posting
+    pub require_verified_email: bool,
+}
+
+impl InstanceSettings {
+    pub fn from_env() -> Result<Self, String> {
+        let settings = InstanceSettings {
+            name: config::var("INSTANCE_NAME").unwrap_or_else(|| "Twitter Clone".to_string()),
+            version: env!("CARGO_PKG_VERSION"),
+            max_tweet_length: config::parse_var("MAX_TWEET_LENGTH", 280)?,
+            max_bio_length: config::parse_var("MAX_BIO_LENGTH", 160)?,
+            max_media_per_tweet: config::parse_var("MAX_MEDIA_PER_TWEET", 4)?,
+            require_verified_email: config::flag("REQUIRE_VERIFIED_EMAIL", false)?,
+        };
+
+        if !(1..=100_000).contains(&settings.max_tweet_length) {
+            return Err("MAX_TWEET_LENGTH must be between 1 and 100000".to_string());
+        }
+        if settings.max_bio_length > 10_000 {
+            return Err("MAX_BIO_LENGTH must be at most 10000".to_string());
+        }
+        if settings.max_media_per_tweet > 20 {
+            return Err("MAX_MEDIA_PER_TWEET must be at most 20".to_string());
+        }
+
+        Ok(settings)
+    }
+
+    pub fn check_tweet(&self, content: &str, image_url: Option<&str>) -> Result<(), ApiError> {
+        if content.chars().count() > self.max_tweet_length {
+            return Err(ApiError::BadRequest(format!(
+                "Tweet must be at most {} characters",
+                self.max_tweet_length
+            )));
+        }
+        if image_url.is_some() && self.max_media_per_tweet == 0 {
+            return Err(ApiError::BadRequest("Media is disabled on this instance".to_string()));
+        }
+        Ok(())
+    }
+
+    pub async fn ensure_verified(&self, db: &PgPool, user_id: Uuid) -> Result<(), ApiError> {
+        if !self.require_verified_email {
+            return Ok(());
+        }
+
+        let verified = sqlx::query_scalar::<_, bool>("SELECT verified_email FROM users WHERE id = $1")
+            .bind(user_id)
+            .fetch_optional(db)
+            .await?
+            .unwrap_or(false);
+
+        if !verified {
+            return Err(ApiError::Forbidden("Verify your email address before posting".to_string()));
+        }
+        Ok(())
+    }
+
+    pub fn check_bio(&self, bio: &str) -> Result<(), ApiError> {
+        if bio.chars().count() > self.max_bio_length {
+            return Err(ApiError::BadRequest(format!(
+                "Bio must be at most {} characters",
+                self.max_bio_length
+            )));
+        }
+        Ok(())
+    }
+}
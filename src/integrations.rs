@@ -0,0 +1,417 @@
+use actix_web::{web, HttpResponse};
+use chrono::{DateTime, Utc};
+use reqwest::{Method, Url};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sqlx::{FromRow, PgPool};
+use uuid::Uuid;
+
+use crate::auth::AuthenticatedUser;
+use crate::config;
+use crate::crypto::{self, Pii};
+use crate::error::{ApiError, ApiResult};
+use crate::http_client::{HttpClient, HttpError};
+use crate::models::ApiResponse;
+use crate::{short_links, AppState};
+
+const TELEGRAM_API: &str = "https://api.telegram.org";
+const DISCORD_HOSTS: [&str; 4] = ["discord.com", "discordapp.com", "ptb.discord.com", "canary.discord.com"];
+
+// Cross-posting of a user's new public tweets to a Telegram chat (through a
+// bot the user created and added to it) or a Discord channel webhook.
+// Replies and followers-only or circle tweets are never cross-posted. Like
+// triggers, the scheduler queues posts into an outbox and delivers them with
+// retries; the latest failure is kept on the integration so the settings
+// page can show why posts stopped arriving.
+//
+// Credentials are sealed with the personal-data cipher and never returned.
+#[derive(Debug, Clone)]
+pub struct CrossPosting {
+    max_per_user: i64,
+    max_attempts: i32,
+    batch_size: i64,
+}
+
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.route("/api/integrations", web::get().to(get_integrations))
+        .route("/api/integrations", web::post().to(create_integration))
+        .route("/api/integrations/{id}", web::put().to(update_integration))
+        .route("/api/integrations/{id}", web::delete().to(delete_integration));
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Provider {
+    Telegram,
+    Discord,
+}
+
+impl Provider {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Provider::Telegram => "telegram",
+            Provider::Discord => "discord",
+        }
+    }
+}
+
+#[derive(Debug, Serialize, FromRow)]
+pub struct Integration {
+    pub id: Uuid,
+    pub provider: String,
+    pub target: Option<String>,
+    pub enabled: bool,
+    pub last_posted_at: Option<DateTime<Utc>>,
+    pub last_error: Option<String>,
+    pub last_error_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateIntegrationRequest {
+    pub provider: Provider,
+    // Telegram
+    pub bot_token: Option<String>,
+    pub chat_id: Option<String>,
+    // Discord
+    pub webhook_url: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateIntegrationRequest {
+    pub enabled: bool,
+}
+
+#[derive(Debug, FromRow)]
+struct DuePost {
+    id: Uuid,
+    integration_id: Uuid,
+    attempts: i32,
+    provider: String,
+    credentials: Pii,
+    target: Option<String>,
+    tweet_id: Uuid,
+    content: String,
+    image_url: Option<String>,
+}
+
+const INTEGRATION_COLUMNS: &str = "id, provider, target, enabled, last_posted_at, last_error, last_error_at, created_at";
+
+impl CrossPosting {
+    pub fn from_env() -> Result<Self, String> {
+        Ok(CrossPosting {
+            max_per_user: config::parse_var("INTEGRATION_MAX_PER_USER", 5)?,
+            max_attempts: config::parse_var("INTEGRATION_MAX_ATTEMPTS", 5)?,
+            batch_size: 100,
+        })
+    }
+
+    // Run by the scheduler.
+    pub async fn tick(&self, db: &PgPool, http: &HttpClient, base_url: &str) -> Result<(), sqlx::Error> {
+        // Tweets older than a day are left alone, so a long outage doesn't
+        // end in a flood of stale cross-posts
+        sqlx::query(
+            "INSERT INTO integration_posts (integration_id, tweet_id)
+             SELECT i.id, t.id
+             FROM integrations i
+             INNER JOIN tweets t ON t.user_id = i.user_id
+             WHERE i.enabled
+               AND t.created_at > i.armed_at AND t.created_at > NOW() - INTERVAL '1 day'
+               AND t.visibility = 'public' AND t.parent_tweet_id IS NULL AND t.deleted_at IS NULL
+             ON CONFLICT (integration_id, tweet_id) DO NOTHING"
+        )
+        .execute(db)
+        .await?;
+
+        // Leases the batch so another instance's tick doesn't send it too
+        let due = sqlx::query_as::<_, DuePost>(
+            "WITH claimed AS (
+                 UPDATE integration_posts SET next_attempt_at = NOW() + INTERVAL '5 minutes'
+                 WHERE id IN (
+                     SELECT id FROM integration_posts
+                     WHERE status = 'pending' AND next_attempt_at <= NOW()
+                     ORDER BY next_attempt_at
+                     LIMIT $1
+                     FOR UPDATE SKIP LOCKED
+                 )
+                 RETURNING id, integration_id, tweet_id, attempts
+             )
+             SELECT c.id, c.integration_id, c.attempts, i.provider, i.credentials, i.target,
+                    t.id AS tweet_id, t.content, t.image_url
+             FROM claimed c
+             INNER JOIN integrations i ON i.id = c.integration_id
+             INNER JOIN tweets t ON t.id = c.tweet_id
+             WHERE i.enabled AND t.deleted_at IS NULL"
+        )
+        .bind(self.batch_size)
+        .fetch_all(db)
+        .await?;
+
+        for post in due {
+            let attempts = post.attempts + 1;
+            let permalink = format!("{}/t/{}", base_url, short_links::encode(post.tweet_id));
+
+            match send(http, &post, &permalink).await {
+                Ok(()) => {
+                    sqlx::query("UPDATE integration_posts SET status = 'posted', attempts = $2, last_error = NULL WHERE id = $1")
+                        .bind(post.id)
+                        .bind(attempts)
+                        .execute(db)
+                        .await?;
+                    sqlx::query("UPDATE integrations SET last_posted_at = NOW() WHERE id = $1")
+                        .bind(post.integration_id)
+                        .execute(db)
+                        .await?;
+                }
+                Err(error) => {
+                    let status = if attempts >= self.max_attempts { "failed" } else { "pending" };
+                    sqlx::query(
+                        "UPDATE integration_posts
+                         SET status = $2, attempts = $3, last_error = $4,
+                             next_attempt_at = NOW() + make_interval(mins => power(2, $3)::int)
+                         WHERE id = $1"
+                    )
+                    .bind(post.id)
+                    .bind(status)
+                    .bind(attempts)
+                    .bind(&error)
+                    .execute(db)
+                    .await?;
+                    sqlx::query("UPDATE integrations SET last_error = $2, last_error_at = NOW() WHERE id = $1")
+                        .bind(post.integration_id)
+                        .bind(&error)
+                        .execute(db)
+                        .await?;
+                }
+            }
+        }
+
+        // Posts left behind by a disabled integration or a deleted tweet
+        sqlx::query(
+            "UPDATE integration_posts p SET status = 'skipped'
+             FROM integrations i, tweets t
+             WHERE i.id = p.integration_id AND t.id = p.tweet_id AND p.status = 'pending'
+               AND (NOT i.enabled OR t.deleted_at IS NOT NULL)"
+        )
+        .execute(db)
+        .await?;
+
+        Ok(())
+    }
+}
+
+async fn send(http: &HttpClient, post: &DuePost, permalink: &str) -> Result<(), String> {
+    let text = format!("{}\n\n{}", post.content, permalink);
+    let (url, body) = match post.provider.as_str() {
+        "telegram" => {
+            let chat_id = post.target.as_deref().unwrap_or_default();
+            match &post.image_url {
+                Some(photo) => (
+                    format!("{}/bot{}/sendPhoto", TELEGRAM_API, post.credentials.0),
+                    json!({ "chat_id": chat_id, "photo": photo, "caption": text }),
+                ),
+                None => (
+                    format!("{}/bot{}/sendMessage", TELEGRAM_API, post.credentials.0),
+                    json!({ "chat_id": chat_id, "text": text }),
+                ),
+            }
+        }
+        "discord" => {
+            let embeds: Vec<_> = post.image_url.iter().map(|url| json!({ "image": { "url": url } })).collect();
+            (post.credentials.0.clone(), json!({ "content": text, "embeds": embeds }))
+        }
+        other => return Err(format!("unknown provider {}", other)),
+    };
+
+    let url = Url::parse(&url).map_err(|_| "invalid endpoint URL".to_string())?;
+    let response = http
+        .public(Method::POST, url)
+        .map_err(|e| e.to_string())?
+        .header("Content-Type", "application/json")
+        .body(body.to_string().into_bytes())
+        .send()
+        .await
+        // The request URL holds the credentials; keep them out of the error
+        .map_err(|e| match e {
+            HttpError::Request(e) => e.without_url().to_string(),
+            e => e.to_string(),
+        })?;
+
+    if response.status().is_success() {
+        return Ok(());
+    }
+
+    // Both APIs explain failures in a `description`/`message` field
+    let status = response.status();
+    let detail = response
+        .bytes()
+        .await
+        .ok()
+        .and_then(|bytes| serde_json::from_slice::<serde_json::Value>(&bytes).ok())
+        .and_then(|body| body.get("description").or_else(|| body.get("message")).and_then(|v| v.as_str()).map(str::to_string));
+    Err(match detail {
+        Some(detail) => format!("{} responded with {}: {}", post.provider, status, detail),
+        None => format!("{} responded with {}", post.provider, status),
+    })
+}
+
+fn is_telegram_token(token: &str) -> bool {
+    match token.split_once(':') {
+        Some((bot_id, secret)) => {
+            !bot_id.is_empty()
+                && bot_id.chars().all(|c| c.is_ascii_digit())
+                && !secret.is_empty()
+                && secret.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+        }
+        None => false,
+    }
+}
+
+fn is_discord_webhook(raw: &str) -> bool {
+    Url::parse(raw).is_ok_and(|url| {
+        url.scheme() == "https"
+            && url.host_str().is_some_and(|host| DISCORD_HOSTS.contains(&host))
+            && url.path().starts_with("/api/webhooks/")
+    })
+}
+
+async fn get_integrations(state: web::Data<AppState>, AuthenticatedUser(user_id): AuthenticatedUser) -> ApiResult<HttpResponse> {
+    let integrations = sqlx::query_as::<_, Integration>(&format!(
+        "SELECT {} FROM integrations WHERE user_id = $1 ORDER BY created_at",
+        INTEGRATION_COLUMNS
+    ))
+    .bind(user_id)
+    .fetch_all(&state.db)
+    .await?;
+
+    Ok(HttpResponse::Ok().json(ApiResponse {
+        success: true,
+        data: Some(integrations),
+        message: None,
+        next_cursor: None,
+    }))
+}
+
+async fn create_integration(
+    state: web::Data<AppState>,
+    AuthenticatedUser(user_id): AuthenticatedUser,
+    req: web::Json<CreateIntegrationRequest>,
+) -> ApiResult<HttpResponse> {
+    let req = req.into_inner();
+    let (credentials, target) = match req.provider {
+        Provider::Telegram => {
+            let token = req.bot_token.map(|t| t.trim().to_string()).filter(|t| is_telegram_token(t));
+            let chat_id = req.chat_id.map(|c| c.trim().to_string()).filter(|c| !c.is_empty());
+            match (token, chat_id) {
+                (Some(token), Some(chat_id)) => (token, Some(chat_id)),
+                _ => return Err(ApiError::BadRequest("Telegram needs a bot_token and a chat_id".to_string())),
+            }
+        }
+        Provider::Discord => match req.webhook_url.map(|u| u.trim().to_string()) {
+            Some(url) if is_discord_webhook(&url) => (url, None),
+            _ => return Err(ApiError::BadRequest("Discord needs a channel webhook_url".to_string())),
+        },
+    };
+
+    let count = sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM integrations WHERE user_id = $1")
+        .bind(user_id)
+        .fetch_one(&state.db)
+        .await?;
+    if count >= state.cross_posting.max_per_user {
+        return Err(ApiError::Forbidden(format!("You can have at most {} integrations", state.cross_posting.max_per_user)));
+    }
+
+    let integration = sqlx::query_as::<_, Integration>(&format!(
+        "INSERT INTO integrations (user_id, provider, credentials, target) VALUES ($1, $2, $3, $4) RETURNING {}",
+        INTEGRATION_COLUMNS
+    ))
+    .bind(user_id)
+    .bind(req.provider.as_str())
+    .bind(crypto::seal(&credentials))
+    .bind(target)
+    .fetch_one(&state.db)
+    .await?;
+
+    Ok(HttpResponse::Created().json(ApiResponse {
+        success: true,
+        data: Some(integration),
+        message: None,
+        next_cursor: None,
+    }))
+}
+
+// Re-enabling only cross-posts tweets from then on, and clears the last error.
+async fn update_integration(
+    state: web::Data<AppState>,
+    AuthenticatedUser(user_id): AuthenticatedUser,
+    integration_id: web::Path<Uuid>,
+    req: web::Json<UpdateIntegrationRequest>,
+) -> ApiResult<HttpResponse> {
+    let integration = sqlx::query_as::<_, Integration>(&format!(
+        "UPDATE integrations
+         SET armed_at = CASE WHEN $3 AND NOT enabled THEN NOW() ELSE armed_at END,
+             last_error = CASE WHEN $3 AND NOT enabled THEN NULL ELSE last_error END,
+             last_error_at = CASE WHEN $3 AND NOT enabled THEN NULL ELSE last_error_at END,
+             enabled = $3
+         WHERE id = $1 AND user_id = $2
+         RETURNING {}",
+        INTEGRATION_COLUMNS
+    ))
+    .bind(integration_id.into_inner())
+    .bind(user_id)
+    .bind(req.enabled)
+    .fetch_optional(&state.db)
+    .await?
+    .ok_or_else(|| ApiError::NotFound("Integration not found".to_string()))?;
+
+    Ok(HttpResponse::Ok().json(ApiResponse {
+        success: true,
+        data: Some(integration),
+        message: None,
+        next_cursor: None,
+    }))
+}
+
+async fn delete_integration(
+    state: web::Data<AppState>,
+    AuthenticatedUser(user_id): AuthenticatedUser,
+    integration_id: web::Path<Uuid>,
+) -> ApiResult<HttpResponse> {
+    let result = sqlx::query("DELETE FROM integrations WHERE id = $1 AND user_id = $2")
+        .bind(integration_id.into_inner())
+        .bind(user_id)
+        .execute(&state.db)
+        .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(ApiError::NotFound("Integration not found".to_string()));
+    }
+
+    Ok(HttpResponse::Ok().json(ApiResponse {
+        success: true,
+        data: Some("Integration deleted successfully"),
+        message: None,
+        next_cursor: None,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn validates_telegram_tokens() {
+        assert!(is_telegram_token("123456:ABC-def_ghi"));
+        assert!(!is_telegram_token("123456"));
+        assert!(!is_telegram_token("abc:def"));
+        assert!(!is_telegram_token("123456:has/slash"));
+    }
+
+    #[test]
+    fn only_accepts_discord_webhooks() {
+        assert!(is_discord_webhook("https://discord.com/api/webhooks/1/abc"));
+        assert!(!is_discord_webhook("http://discord.com/api/webhooks/1/abc"));
+        assert!(!is_discord_webhook("https://discord.com.evil.example/api/webhooks/1/abc"));
+        assert!(!is_discord_webhook("https://discord.com/channels/1"));
+    }
+}
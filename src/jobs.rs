@@ -0,0 +1,263 @@
+use actix_web::web;
+use futures_util::future::LocalBoxFuture;
+use futures_util::FutureExt;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use sqlx::types::Json;
+use sqlx::{FromRow, PgConnection, PgPool};
+use std::any::Any;
+use std::collections::HashMap;
+use std::panic::AssertUnwindSafe;
+use std::sync::Arc;
+use std::time::Duration;
+use uuid::Uuid;
+
+use crate::AppState;
+use crate::config;
+
+// Running jobs refresh their lock this often. A lock older than LOCK_TIMEOUT
+// belongs to a worker that died, and the job is claimed again.
+const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(60);
+const LOCK_TIMEOUT: Duration = Duration::from_secs(5 * 60);
+
+const BASE_RETRY_DELAY: Duration = Duration::from_secs(30);
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(60 * 60);
+
+pub type JobResult = Result<(), Box<dyn std::error::Error + Send + Sync>>;
+
+// Work that outlives a request. Handlers are registered by kind at startup and
+// `enqueue` stores a JSON payload for them in the `jobs` table, which a pool
+// of workers drains. A job that returns an error is retried with exponential
+// backoff until it runs out of attempts, then `failed` gets a chance to tidy
+// up. Jobs can run more than once (a retry, or a worker dying mid-run), so
+// handlers must be safe to resume.
+pub trait JobHandler: Send + Sync {
+    fn kind(&self) -> &'static str;
+
+    fn run<'a>(&'a self, state: &'a AppState, job: &'a Job) -> LocalBoxFuture<'a, JobResult>;
+
+    // Called once the job has failed for good
+    fn failed<'a>(&'a self, _state: &'a AppState, _job: &'a Job, _error: &'a str) -> LocalBoxFuture<'a, JobResult> {
+        Box::pin(async { Ok(()) })
+    }
+}
+
+#[derive(Debug, FromRow)]
+pub struct Job {
+    pub id: Uuid,
+    pub kind: String,
+    pub payload: Json<serde_json::Value>,
+    pub attempts: i32,
+    pub max_attempts: i32,
+}
+
+impl Job {
+    pub fn payload<T: DeserializeOwned>(&self) -> Result<T, serde_json::Error> {
+        T::deserialize(&self.payload.0)
+    }
+}
+
+#[derive(Clone)]
+pub struct Jobs {
+    handlers: HashMap<&'static str, Arc<dyn JobHandler>>,
+    workers: usize,
+    poll_interval: Duration,
+    retention_days: i32,
+}
+
+impl Jobs {
+    pub fn from_env() -> Result<Self, String> {
+        Ok(Jobs {
+            handlers: HashMap::new(),
+            workers: config::parse_var("JOB_WORKERS", 4)?,
+            poll_interval: Duration::from_secs(config::positive_var("JOB_POLL_INTERVAL_SECS", 2)?),
+            retention_days: config::parse_var("JOB_RETENTION_DAYS", 7)?,
+        })
+    }
+
+    pub fn register(mut self, handler: impl JobHandler + 'static) -> Self {
+        let kind = handler.kind();
+        if self.handlers.insert(kind, Arc::new(handler)).is_some() {
+            panic!("job handler {} registered twice", kind);
+        }
+        self
+    }
+
+    // Starts the worker pool. Each worker claims one due job at a time and
+    // only sleeps when the queue is empty.
+    pub fn spawn_workers(state: web::Data<AppState>) {
+        for _ in 0..state.jobs.workers {
+            let state = state.clone();
+            actix_web::rt::spawn(async move {
+                loop {
+                    match state.jobs.run_next(&state).await {
+                        Ok(true) => continue,
+                        Ok(false) => {}
+                        Err(e) => log::error!("job worker failed: {}", e),
+                    }
+                    actix_web::rt::time::sleep(state.jobs.poll_interval).await;
+                }
+            });
+        }
+    }
+
+    // Run by the scheduler. Finished jobs are only kept for inspection.
+    pub async fn prune(&self, db: &PgPool) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            "DELETE FROM jobs WHERE status IN ('completed', 'failed') AND finished_at < NOW() - make_interval(days => $1)"
+        )
+        .bind(self.retention_days)
+        .execute(db)
+        .await?;
+        Ok(())
+    }
+
+    // Returns whether a job was run
+    async fn run_next(&self, state: &AppState) -> Result<bool, sqlx::Error> {
+        let job = sqlx::query_as::<_, Job>(
+            "UPDATE jobs SET status = 'running', attempts = attempts + 1, locked_at = NOW()
+             WHERE id = (
+                 SELECT id FROM jobs
+                 WHERE (status = 'pending' AND run_at <= NOW())
+                    OR (status = 'running' AND locked_at < NOW() - make_interval(secs => $1))
+                 ORDER BY run_at
+                 LIMIT 1
+                 FOR UPDATE SKIP LOCKED
+             )
+             RETURNING id, kind, payload, attempts, max_attempts"
+        )
+        .bind(LOCK_TIMEOUT.as_secs_f64())
+        .fetch_optional(&state.db)
+        .await?;
+
+        let Some(job) = job else {
+            return Ok(false);
+        };
+
+        let result = match self.handlers.get(job.kind.as_str()) {
+            Some(handler) => self.run_locked(state, handler.as_ref(), &job).await,
+            None => Err(format!("no handler registered for job kind {}", job.kind)),
+        };
+
+        match result {
+            Ok(()) => {
+                sqlx::query(
+                    "UPDATE jobs SET status = 'completed', locked_at = NULL, last_error = NULL, finished_at = NOW() WHERE id = $1"
+                )
+                .bind(job.id)
+                .execute(&state.db)
+                .await?;
+            }
+            Err(error) if job.attempts < job.max_attempts => {
+                log::warn!("job {} ({}) attempt {} failed: {}", job.id, job.kind, job.attempts, error);
+                sqlx::query(
+                    "UPDATE jobs SET status = 'pending', locked_at = NULL, last_error = $2,
+                         run_at = NOW() + make_interval(secs => $3)
+                     WHERE id = $1"
+                )
+                .bind(job.id)
+                .bind(&error)
+                .bind(retry_delay(job.attempts).as_secs_f64())
+                .execute(&state.db)
+                .await?;
+            }
+            Err(error) => {
+                log::error!("job {} ({}) failed after {} attempts: {}", job.id, job.kind, job.attempts, error);
+                sqlx::query(
+                    "UPDATE jobs SET status = 'failed', locked_at = NULL, last_error = $2, finished_at = NOW() WHERE id = $1"
+                )
+                .bind(job.id)
+                .bind(&error)
+                .execute(&state.db)
+                .await?;
+
+                if let Some(handler) = self.handlers.get(job.kind.as_str()) {
+                    if let Err(e) = handler.failed(state, &job, &error).await {
+                        log::error!("job {} ({}) failure hook failed: {}", job.id, job.kind, e);
+                    }
+                }
+            }
+        }
+
+        Ok(true)
+    }
+
+    // Runs the handler while keeping the job's lock fresh. A panic is treated
+    // like any other error so it doesn't take the worker down with it.
+    async fn run_locked(&self, state: &AppState, handler: &dyn JobHandler, job: &Job) -> Result<(), String> {
+        let mut run = AssertUnwindSafe(handler.run(state, job)).catch_unwind();
+        let mut heartbeat = actix_web::rt::time::interval(HEARTBEAT_INTERVAL);
+        heartbeat.tick().await;
+
+        loop {
+            tokio::select! {
+                result = &mut run => {
+                    return match result {
+                        Ok(Ok(())) => Ok(()),
+                        Ok(Err(e)) => Err(e.to_string()),
+                        Err(panic) => Err(format!("panicked: {}", panic_message(&*panic))),
+                    };
+                }
+                _ = heartbeat.tick() => {
+                    if let Err(e) = sqlx::query("UPDATE jobs SET locked_at = NOW() WHERE id = $1")
+                        .bind(job.id)
+                        .execute(&state.db)
+                        .await
+                    {
+                        log::warn!("failed to refresh lock on job {}: {}", job.id, e);
+                    }
+                }
+            }
+        }
+    }
+}
+
+// Queues a job. Takes a connection so callers can enqueue in the same
+// transaction as the rows the job works on.
+pub async fn enqueue(conn: &mut PgConnection, kind: &str, payload: &(impl Serialize + Sync)) -> Result<Uuid, sqlx::Error> {
+    sqlx::query_scalar::<_, Uuid>("INSERT INTO jobs (kind, payload) VALUES ($1, $2) RETURNING id")
+        .bind(kind)
+        .bind(Json(payload))
+        .fetch_one(conn)
+        .await
+}
+
+// 30s after the first failure, doubling up to an hour
+fn retry_delay(attempts: i32) -> Duration {
+    let exponent = attempts.saturating_sub(1).clamp(0, 16) as u32;
+    BASE_RETRY_DELAY.saturating_mul(1 << exponent).min(MAX_RETRY_DELAY)
+}
+
+fn panic_message(panic: &(dyn Any + Send)) -> &str {
+    panic
+        .downcast_ref::<&str>()
+        .copied()
+        .or_else(|| panic.downcast_ref::<String>().map(String::as_str))
+        .unwrap_or("unknown panic")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn retry_delay_doubles_up_to_an_hour() {
+        assert_eq!(retry_delay(1), Duration::from_secs(30));
+        assert_eq!(retry_delay(2), Duration::from_secs(60));
+        assert_eq!(retry_delay(4), Duration::from_secs(240));
+        assert_eq!(retry_delay(8), MAX_RETRY_DELAY);
+        assert_eq!(retry_delay(i32::MAX), MAX_RETRY_DELAY);
+        assert_eq!(retry_delay(0), BASE_RETRY_DELAY);
+    }
+
+    #[test]
+    fn panic_message_reads_str_and_string_payloads() {
+        let static_str: Box<dyn Any + Send> = Box::new("boom");
+        let owned: Box<dyn Any + Send> = Box::new(String::from("bang"));
+        let other: Box<dyn Any + Send> = Box::new(42);
+
+        assert_eq!(panic_message(&*static_str), "boom");
+        assert_eq!(panic_message(&*owned), "bang");
+        assert_eq!(panic_message(&*other), "unknown panic");
+    }
+}
@@ -0,0 +1,399 @@
+mod abuse;
+mod admin;
+mod analytics;
+mod archive;
+mod auth;
+mod backups;
+mod bitmap_font;
+mod bots;
+mod coauthors;
+mod collections;
+mod config;
+mod crypto;
+mod db;
+mod deprecations;
+mod drafts;
+mod error;
+mod events;
+mod experiments;
+mod handlers;
+mod hashtags;
+mod health;
+mod http_client;
+mod inbound_email;
+mod index_audit;
+mod instance;
+mod integrations;
+mod jobs;
+mod lists;
+mod locations;
+mod mailer;
+mod maintenance;
+mod mastodon_import;
+mod media;
+mod media_proxy;
+mod mentions;
+mod notifications;
+mod models;
+mod pagination;
+mod probation;
+mod query_plans;
+mod ranking;
+mod rate_limit;
+mod realtime;
+mod recaps;
+mod reports;
+mod repo;
+mod request_log;
+mod response_meta;
+mod scheduler;
+mod secrets;
+mod share_cards;
+mod short_links;
+mod storage;
+mod stories;
+mod trends;
+mod triggers;
+mod twitter_archive;
+mod twitter_v2;
+mod unread;
+mod unzip;
+
+use actix_cors::Cors;
+use actix_files as fs;
+use actix_web::body::MessageBody;
+use actix_web::dev::{ServiceFactory, ServiceRequest, ServiceResponse};
+use actix_web::{middleware, web, App, HttpServer};
+use sqlx::PgPool;
+use std::sync::{Arc, Once};
+
+pub use request_log::init_logger;
+
+// ============ APP STATE ============
+
+#[derive(Clone)]
+pub struct AppState {
+    db: PgPool,
+    jwt_keys: auth::JwtKeys,
+    ranking: ranking::RankingWeights,
+    // Public origin used to build absolute links (e.g. profile URLs)
+    base_url: String,
+    media_proxy: media_proxy::MediaProxy,
+    instance: instance::InstanceSettings,
+    maintenance: maintenance::Maintenance,
+    abuse: abuse::AbuseLimits,
+    realtime: realtime::Hub,
+    probation: probation::ProbationPolicy,
+    tokens: auth::TokenLifetimes,
+    cursors: pagination::CursorCodec,
+    archive: archive::ArchivePolicy,
+    media: media::MediaUploads,
+    http: http_client::HttpClient,
+    rate_limits: rate_limit::RateLimiter,
+    mailer: Arc<dyn mailer::Mailer>,
+    analytics: analytics::Analytics,
+    bots: bots::BotRules,
+    triggers: triggers::Triggers,
+    inbound_email: inbound_email::InboundEmail,
+    cross_posting: integrations::CrossPosting,
+    jobs: jobs::Jobs,
+    twitter_archive: twitter_archive::TwitterArchive,
+    backups: backups::Backups,
+    trends: trends::Trends,
+    share_cards: share_cards::ShareCards,
+    experiments: experiments::Experiments,
+    deprecations: deprecations::Deprecations,
+    unread: unread::UnreadCounts,
+    repos: repo::Repos,
+}
+
+// ============ SETUP ============
+
+static FIELD_CIPHER: Once = Once::new();
+
+// A configured app: its state, plus where to listen and which origins to
+// allow. Background tasks aren't running yet; `run` starts them.
+#[derive(Clone)]
+pub struct Setup {
+    pub state: web::Data<AppState>,
+    server: config::ServerConfig,
+    cors: config::CorsConfig,
+}
+
+// Loads the configuration, connects to the database and migrates it, and
+// builds the shared state.
+pub async fn setup() -> Result<Setup, String> {
+    let config = config::Config::load().await.map_err(|e| e.to_string())?;
+    let config::Config {
+        server,
+        database,
+        jwt_keys,
+        cors,
+        admin_usernames,
+        http,
+        rate_limits,
+        media,
+        mailer,
+        instance,
+        analytics,
+        pii,
+        cursors,
+        tokens,
+        ranking,
+        media_proxy,
+        maintenance,
+        abuse,
+        probation,
+        archive,
+        bots,
+        triggers,
+        inbound_email,
+        cross_posting,
+        jobs,
+        twitter_archive,
+        backups,
+        trends,
+        share_cards,
+        experiments,
+        unread,
+        create_missing_indexes,
+    } = config;
+
+    // Create database pool
+    let pool = db::create_pool(&database.url, database.password.as_deref())
+        .await
+        .map_err(|e| format!("Failed to create database pool: {}", e))?;
+
+    // Run migrations
+    db::MIGRATOR
+        .run(&pool)
+        .await
+        .map_err(|e| format!("Failed to run migrations: {}", e))?;
+
+    // Seal personal data left in the clear or under a retired key. The cipher
+    // is set once per process; tests set up the app more than once
+    FIELD_CIPHER.call_once(|| crypto::init(pii));
+    let resealed = crypto::backfill(&pool)
+        .await
+        .map_err(|e| format!("Failed to encrypt personal data: {}", e))?;
+    if resealed > 0 {
+        log::info!("re-sealed the email of {} users", resealed);
+    }
+
+    // Grant admin to the operator accounts listed in ADMIN_USERNAMES
+    sqlx::query("UPDATE users SET role = 'admin' WHERE username = ANY($1)")
+        .bind(&admin_usernames)
+        .execute(&pool)
+        .await
+        .map_err(|e| format!("Failed to grant admin accounts: {}", e))?;
+
+    if let Err(e) = index_audit::check_on_startup(&pool, create_missing_indexes).await {
+        log::error!("Failed to audit indexes: {}", e);
+    }
+
+    let app_state = web::Data::new(AppState {
+        repos: repo::Repos::postgres(pool.clone()),
+        db: pool,
+        cursors,
+        media,
+        jwt_keys,
+        ranking,
+        base_url: server.base_url.clone(),
+        media_proxy,
+        mailer: mailer.into(),
+        instance,
+        maintenance,
+        abuse,
+        realtime: realtime::Hub::default(),
+        probation,
+        tokens,
+        archive,
+        http,
+        rate_limits,
+        analytics,
+        bots,
+        triggers,
+        inbound_email,
+        cross_posting,
+        jobs: jobs
+            .register(handlers::social::FollowImportJob)
+            .register(twitter_archive::ImportJob)
+            .register(mastodon_import::MastodonImportJob),
+        twitter_archive,
+        backups,
+        trends,
+        share_cards,
+        experiments,
+        deprecations: deprecations::Deprecations::default(),
+        unread,
+    });
+
+    Ok(Setup {
+        state: app_state,
+        server,
+        cors,
+    })
+}
+
+impl Setup {
+    // The app factory, shared by the server and the integration tests
+    pub fn app(
+        &self,
+    ) -> App<
+        impl ServiceFactory<
+            ServiceRequest,
+            Config = (),
+            Response = ServiceResponse<impl MessageBody>,
+            Error = actix_web::Error,
+            InitError = (),
+        >,
+    > {
+        let cors = match &self.cors.allowed_origins {
+            Some(origins) => origins.iter().fold(Cors::default(), |cors, origin| cors.allowed_origin(origin)),
+            None => Cors::default().allow_any_origin(),
+        };
+        let cors = cors
+            .allow_any_method()
+            .allow_any_header()
+            .expose_headers(vec!["X-Timeline-Snapshot", "Retry-After", "X-Request-Id"])
+            .max_age(3600);
+
+        App::new()
+            .wrap(middleware::from_fn(admin::suspension_guard))
+            .wrap(middleware::from_fn(rate_limit::throttle))
+            .wrap(middleware::from_fn(response_meta::attach))
+            .wrap(middleware::from_fn(maintenance::read_only_guard))
+            .wrap(middleware::from_fn(analytics::record))
+            .wrap(middleware::from_fn(deprecations::annotate))
+            .wrap(cors)
+            .wrap(middleware::from_fn(request_log::log_request))
+            .app_data(self.state.clone())
+            .app_data(web::Data::from(self.state.repos.users.clone()))
+            .app_data(web::Data::from(self.state.repos.tweets.clone()))
+            .app_data(web::Data::from(self.state.repos.social.clone()))
+            .app_data(web::Data::from(self.state.repos.sessions.clone()))
+            // Uploaded media, when stored on this server's disk
+            .configure(|cfg| {
+                if let Some(dir) = self.state.media.storage.local_dir() {
+                    cfg.service(fs::Files::new("/media", dir));
+                }
+            })
+            // API routes
+            // Liveness and readiness probes
+            .configure(health::configure)
+            // Instance settings and public stats
+            .configure(handlers::instance::configure)
+            // Live timeline and notifications
+            .configure(handlers::realtime::configure)
+            // Registration, sessions and account credentials
+            .configure(handlers::auth::configure)
+            // Profiles and account settings
+            .configure(handlers::users::configure)
+            // Notifications
+            .configure(handlers::notifications::configure)
+            // Badge counts for polling clients
+            .configure(unread::configure)
+            // Announcements, and their management by admins
+            .configure(handlers::announcements::configure)
+            // Abuse events and held tweets for moderators
+            .configure(handlers::moderation::configure)
+            // Index audit, outbound HTTP and usage stats, purges and maintenance windows
+            .configure(handlers::operations::configure)
+            .configure(handlers::search::configure)
+            // Media uploads and the media proxy
+            .configure(handlers::media::configure)
+            // Ahead of /api/tweets/{id}, which would take "nearby" for an id
+            .route("/api/tweets/nearby", web::get().to(locations::get_nearby))
+            // Short links, impressions and tweet analytics
+            .configure(handlers::analytics::configure)
+            // Tweets, timelines and replies
+            .configure(handlers::tweets::configure)
+            // Likes, retweets, quotes, follows, blocks and mutes, and follow
+            // import/export
+            .configure(handlers::social::configure)
+            .configure(handlers::mentions::configure)
+            .configure(handlers::hashtags::configure)
+            .configure(handlers::circles::configure)
+            // Account aliases and moves
+            .configure(handlers::migration::configure)
+            // Twitter API v2 compatibility
+            .configure(twitter_v2::configure)
+            // Bot automation rules
+            .configure(bots::configure)
+            // Outbound webhook triggers
+            .configure(triggers::configure)
+            // Post by email
+            .configure(inbound_email::configure)
+            // Telegram/Discord cross-posting
+            .configure(integrations::configure)
+            // Twitter archive import
+            .configure(twitter_archive::configure)
+            // Mastodon follows/blocks/mutes import
+            .configure(mastodon_import::configure)
+            // Backups to a user-owned bucket
+            .configure(backups::configure)
+            // Stories
+            .configure(stories::configure)
+            // Tweet collections
+            .configure(collections::configure)
+            // Co-authored tweets
+            .configure(coauthors::configure)
+            // Tweet locations and places
+            .configure(locations::configure)
+            // Event pages
+            .configure(events::configure)
+            // Trending topics by region
+            .configure(trends::configure)
+            // Weekly recaps
+            .configure(recaps::configure)
+            // Share card images for link previews
+            .configure(share_cards::configure)
+            // Lists and their timelines
+            .configure(lists::configure)
+            // Drafts (also autosaved over /api/ws)
+            .configure(drafts::configure)
+            // User management and platform stats for staff
+            .configure(admin::configure)
+            // Deprecated routes and who still calls them
+            .configure(deprecations::configure)
+            // Reports and the moderation queue
+            .configure(reports::configure)
+            // Query plans (debug builds only)
+            .configure(query_plans::configure)
+            // Static frontend; last, since it matches every path
+            .service(fs::Files::new("/", "./static").index_file("index.html"))
+    }
+}
+
+// ============ MAIN ============
+
+pub async fn run() -> std::io::Result<()> {
+    let setup = match setup().await {
+        Ok(setup) => setup,
+        Err(e) => {
+            eprint!("{}", e);
+            std::process::exit(1);
+        }
+    };
+    let app_state = setup.state.clone();
+
+    app_state
+        .maintenance
+        .tick(&app_state.db)
+        .await
+        .expect("Failed to check maintenance windows");
+    scheduler::spawn(app_state.clone());
+    jobs::Jobs::spawn_workers(app_state.clone());
+    app_state.realtime.spawn_listener(app_state.db.clone());
+    app_state.jwt_keys.reload_on_hangup(app_state.http.clone());
+
+    let (host, port) = (setup.server.host.clone(), setup.server.port);
+    println!("🚀 Twitter API Server starting at http://{}:{}", host, port);
+    println!("📋 Database: Connected to PostgreSQL");
+    println!("🔐 Authentication: JWT enabled");
+
+    HttpServer::new(move || setup.app())
+        .bind((host.as_str(), port))?
+        .run()
+        .await
+}
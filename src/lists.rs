@@ -0,0 +1,507 @@
+use actix_web::{web, HttpResponse};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, PgPool};
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::auth::AuthenticatedUser;
+use crate::error::{ApiError, ApiResult};
+use crate::models::{ApiResponse, PublicUserResponse, TweetResponse, TweetWithViewerState, User};
+use crate::pagination::{self, Cursor, PageQuery};
+use crate::handlers::tweets::tweet_page_bounds;
+use crate::handlers::social::is_blocked;
+use crate::AppState;
+
+const MAX_LISTS_PER_USER: i64 = 100;
+const MAX_MEMBERS_PER_LIST: i64 = 5000;
+
+// Lists: named groups of accounts a user puts together, each with its own
+// timeline of the members' tweets. Members don't need to be followed and
+// aren't told they were added. Public lists can be viewed by anyone; private
+// lists only by their owner and answer 404 to everyone else. The timeline
+// shows each viewer only the tweets they could see anyway.
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.route("/api/lists", web::get().to(get_own_lists))
+        .route("/api/lists", web::post().to(create_list))
+        .route("/api/lists/{id}", web::get().to(get_list))
+        .route("/api/lists/{id}", web::put().to(update_list))
+        .route("/api/lists/{id}", web::delete().to(delete_list))
+        .route("/api/lists/{id}/tweets", web::get().to(get_list_tweets))
+        .route("/api/lists/{id}/members", web::get().to(get_members))
+        .route("/api/lists/{id}/members", web::post().to(add_member))
+        .route("/api/lists/{id}/members/{username}", web::delete().to(remove_member))
+        .route("/api/users/{username}/lists", web::get().to(get_user_lists));
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ListVisibility {
+    #[default]
+    Public,
+    Private,
+}
+
+impl ListVisibility {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ListVisibility::Public => "public",
+            ListVisibility::Private => "private",
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct CreateListRequest {
+    #[validate(length(min = 1, max = 50))]
+    pub name: String,
+    #[serde(default)]
+    #[validate(length(max = 500))]
+    pub description: String,
+    #[serde(default)]
+    pub visibility: ListVisibility,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct UpdateListRequest {
+    #[validate(length(min = 1, max = 50))]
+    pub name: Option<String>,
+    #[validate(length(max = 500))]
+    pub description: Option<String>,
+    pub visibility: Option<ListVisibility>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AddMemberRequest {
+    pub username: String,
+}
+
+#[derive(Debug, Serialize, FromRow)]
+pub struct ListSummary {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub owner: String,
+    pub name: String,
+    pub description: String,
+    pub visibility: String,
+    pub members_count: i64,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ListMemberResponse {
+    pub user: PublicUserResponse,
+    pub added_at: DateTime<Utc>,
+}
+
+#[derive(FromRow)]
+struct MemberRow {
+    #[sqlx(flatten)]
+    user: User,
+    added_at: DateTime<Utc>,
+}
+
+const SUMMARY_COLUMNS: &str = "l.id, l.user_id, o.username AS owner, l.name, l.description, l.visibility,
+    (SELECT COUNT(*) FROM list_members m WHERE m.list_id = l.id) AS members_count,
+    l.created_at, l.updated_at";
+
+// Tweets by the list's members that the viewer ($1, NULL when signed out)
+// may see, leaving out accounts they blocked or muted and muted keywords.
+const LIST_TIMELINE_QUERY: &str = "SELECT t.id, t.user_id, t.parent_tweet_id, t.quoted_tweet_id, t.content, t.image_url,
+        t.likes_count, t.retweets_count, t.replies_count, t.visibility, t.created_at,
+        u.username as user_username, u.display_name as user_display_name,
+        u.bio as user_bio,
+        u.profile_image as user_profile_image, u.banner_image as user_banner_image,
+        u.followers_count as user_followers_count, u.following_count as user_following_count,
+        u.verified as user_verified, u.is_bot as user_is_bot, u.created_at as user_created_at,
+        EXISTS(SELECT 1 FROM likes l WHERE l.user_id = $1 AND l.tweet_id = t.id) as is_liked,
+        EXISTS(SELECT 1 FROM retweets r WHERE r.user_id = $1 AND r.tweet_id = t.id) as is_retweeted
+     FROM tweets t
+     INNER JOIN users u ON t.user_id = u.id
+     WHERE t.user_id IN (SELECT m.user_id FROM list_members m WHERE m.list_id = $2)
+       AND u.deactivated_at IS NULL AND t.deleted_at IS NULL
+       AND tweet_visible_to(t.user_id, t.visibility, $1)
+       AND NOT EXISTS (SELECT 1 FROM blocks b WHERE b.blocker_id = $1 AND b.blocked_id = t.user_id)
+       AND NOT EXISTS (SELECT 1 FROM mutes m WHERE m.muter_id = $1 AND m.muted_id = t.user_id)
+       AND NOT EXISTS (
+           SELECT 1 FROM muted_keywords k WHERE k.user_id = $1 AND strpos(lower(t.content), k.keyword) > 0
+       )
+       AND ($3::timestamptz IS NULL OR (t.created_at, t.id) < ($3, $4))
+       AND ($6::timestamptz IS NULL OR (t.created_at, t.id) > ($6, $7))
+     ORDER BY t.created_at DESC, t.id DESC
+     LIMIT $5";
+
+// The list, if the viewer may see it.
+async fn fetch_visible(db: &PgPool, list_id: Uuid, viewer_id: Option<Uuid>) -> ApiResult<ListSummary> {
+    sqlx::query_as::<_, ListSummary>(&format!(
+        "SELECT {} FROM lists l
+         INNER JOIN users o ON o.id = l.user_id
+         WHERE l.id = $1 AND o.deactivated_at IS NULL
+           AND (l.visibility = 'public' OR l.user_id = $2)",
+        SUMMARY_COLUMNS
+    ))
+    .bind(list_id)
+    .bind(viewer_id)
+    .fetch_optional(db)
+    .await?
+    .ok_or_else(|| ApiError::NotFound("List not found".to_string()))
+}
+
+// Locks the list for a change by its owner.
+async fn lock_owned(conn: &mut sqlx::PgConnection, list_id: Uuid, user_id: Uuid) -> ApiResult<()> {
+    sqlx::query_scalar::<_, Uuid>("SELECT id FROM lists WHERE id = $1 AND user_id = $2 FOR UPDATE")
+        .bind(list_id)
+        .bind(user_id)
+        .fetch_optional(conn)
+        .await?
+        .map(|_| ())
+        .ok_or_else(|| ApiError::NotFound("List not found".to_string()))
+}
+
+async fn touch(conn: &mut sqlx::PgConnection, list_id: Uuid) -> Result<(), sqlx::Error> {
+    sqlx::query("UPDATE lists SET updated_at = NOW() WHERE id = $1")
+        .bind(list_id)
+        .execute(conn)
+        .await?;
+    Ok(())
+}
+
+async fn create_list(
+    state: web::Data<AppState>,
+    AuthenticatedUser(user_id): AuthenticatedUser,
+    req: web::Json<CreateListRequest>,
+) -> ApiResult<HttpResponse> {
+    req.validate()?;
+
+    let count = sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM lists WHERE user_id = $1")
+        .bind(user_id)
+        .fetch_one(&state.db)
+        .await?;
+    if count >= MAX_LISTS_PER_USER {
+        return Err(ApiError::Forbidden(format!("You can have at most {} lists", MAX_LISTS_PER_USER)));
+    }
+
+    let list_id = sqlx::query_scalar::<_, Uuid>(
+        "INSERT INTO lists (user_id, name, description, visibility) VALUES ($1, $2, $3, $4) RETURNING id"
+    )
+    .bind(user_id)
+    .bind(req.name.trim())
+    .bind(req.description.trim())
+    .bind(req.visibility.as_str())
+    .fetch_one(&state.db)
+    .await?;
+
+    Ok(HttpResponse::Created().json(ApiResponse {
+        success: true,
+        data: Some(fetch_visible(&state.db, list_id, Some(user_id)).await?),
+        message: None,
+        next_cursor: None,
+    }))
+}
+
+async fn get_own_lists(state: web::Data<AppState>, AuthenticatedUser(user_id): AuthenticatedUser) -> ApiResult<HttpResponse> {
+    let lists = sqlx::query_as::<_, ListSummary>(&format!(
+        "SELECT {} FROM lists l
+         INNER JOIN users o ON o.id = l.user_id
+         WHERE l.user_id = $1
+         ORDER BY l.updated_at DESC",
+        SUMMARY_COLUMNS
+    ))
+    .bind(user_id)
+    .fetch_all(&state.db)
+    .await?;
+
+    Ok(HttpResponse::Ok().json(ApiResponse {
+        success: true,
+        data: Some(lists),
+        message: None,
+        next_cursor: None,
+    }))
+}
+
+// Public lists, and private ones too when the owner is looking.
+async fn get_user_lists(
+    state: web::Data<AppState>,
+    viewer: Option<AuthenticatedUser>,
+    username: web::Path<String>,
+) -> ApiResult<HttpResponse> {
+    let lists = sqlx::query_as::<_, ListSummary>(&format!(
+        "SELECT {} FROM lists l
+         INNER JOIN users o ON o.id = l.user_id
+         WHERE o.username = $1 AND o.deactivated_at IS NULL
+           AND (l.visibility = 'public' OR l.user_id = $2)
+         ORDER BY l.updated_at DESC",
+        SUMMARY_COLUMNS
+    ))
+    .bind(username.as_str())
+    .bind(viewer.map(|AuthenticatedUser(id)| id))
+    .fetch_all(&state.db)
+    .await?;
+
+    Ok(HttpResponse::Ok().json(ApiResponse {
+        success: true,
+        data: Some(lists),
+        message: None,
+        next_cursor: None,
+    }))
+}
+
+async fn get_list(
+    state: web::Data<AppState>,
+    viewer: Option<AuthenticatedUser>,
+    list_id: web::Path<Uuid>,
+) -> ApiResult<HttpResponse> {
+    let list = fetch_visible(&state.db, list_id.into_inner(), viewer.map(|AuthenticatedUser(id)| id)).await?;
+
+    Ok(HttpResponse::Ok().json(ApiResponse {
+        success: true,
+        data: Some(list),
+        message: None,
+        next_cursor: None,
+    }))
+}
+
+async fn update_list(
+    state: web::Data<AppState>,
+    AuthenticatedUser(user_id): AuthenticatedUser,
+    list_id: web::Path<Uuid>,
+    req: web::Json<UpdateListRequest>,
+) -> ApiResult<HttpResponse> {
+    req.validate()?;
+    let list_id = list_id.into_inner();
+
+    let result = sqlx::query(
+        "UPDATE lists
+         SET name = COALESCE($3, name),
+             description = COALESCE($4, description),
+             visibility = COALESCE($5, visibility),
+             updated_at = NOW()
+         WHERE id = $1 AND user_id = $2"
+    )
+    .bind(list_id)
+    .bind(user_id)
+    .bind(req.name.as_deref().map(str::trim))
+    .bind(req.description.as_deref().map(str::trim))
+    .bind(req.visibility.map(|v| v.as_str()))
+    .execute(&state.db)
+    .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(ApiError::NotFound("List not found".to_string()));
+    }
+
+    Ok(HttpResponse::Ok().json(ApiResponse {
+        success: true,
+        data: Some(fetch_visible(&state.db, list_id, Some(user_id)).await?),
+        message: None,
+        next_cursor: None,
+    }))
+}
+
+async fn delete_list(
+    state: web::Data<AppState>,
+    AuthenticatedUser(user_id): AuthenticatedUser,
+    list_id: web::Path<Uuid>,
+) -> ApiResult<HttpResponse> {
+    let result = sqlx::query("DELETE FROM lists WHERE id = $1 AND user_id = $2")
+        .bind(list_id.into_inner())
+        .bind(user_id)
+        .execute(&state.db)
+        .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(ApiError::NotFound("List not found".to_string()));
+    }
+
+    Ok(HttpResponse::Ok().json(ApiResponse {
+        success: true,
+        data: Some("List deleted successfully"),
+        message: None,
+        next_cursor: None,
+    }))
+}
+
+async fn get_list_tweets(
+    state: web::Data<AppState>,
+    viewer: Option<AuthenticatedUser>,
+    list_id: web::Path<Uuid>,
+    page: web::Query<PageQuery>,
+) -> ApiResult<HttpResponse> {
+    let viewer_id = viewer.map(|AuthenticatedUser(id)| id);
+    let list = fetch_visible(&state.db, list_id.into_inner(), viewer_id).await?;
+
+    let limit = page.limit();
+    let scope = format!("list:{}", list.id);
+    let (cursor, since) = tweet_page_bounds(state.repos.tweets.as_ref(), &page, &state.cursors, &scope).await?;
+
+    let mut tweets = sqlx::query_as::<_, TweetWithViewerState>(LIST_TIMELINE_QUERY)
+        .bind(viewer_id)
+        .bind(list.id)
+        .bind(cursor.map(|c| c.created_at))
+        .bind(cursor.map(|c| c.id))
+        .bind(limit + 1)
+        .bind(since.map(|c| c.created_at))
+        .bind(since.map(|c| c.id))
+        .fetch_all(&state.db)
+        .await?;
+
+    let next_cursor = pagination::next_cursor(&mut tweets, limit, &state.cursors, &scope, |t| Cursor {
+        created_at: t.tweet.created_at,
+        id: t.tweet.id,
+    });
+    state.analytics.count_impressions(tweets.iter().map(|t| t.tweet.id));
+    let tweet_responses: Vec<TweetResponse> = tweets
+        .into_iter()
+        .map(|row| row.tweet.into_response(row.is_liked, row.is_retweeted))
+        .collect();
+
+    Ok(HttpResponse::Ok().json(ApiResponse {
+        success: true,
+        data: Some(tweet_responses),
+        message: None,
+        next_cursor,
+    }))
+}
+
+// Most recently added first.
+async fn get_members(
+    state: web::Data<AppState>,
+    viewer: Option<AuthenticatedUser>,
+    list_id: web::Path<Uuid>,
+    page: web::Query<PageQuery>,
+) -> ApiResult<HttpResponse> {
+    let list = fetch_visible(&state.db, list_id.into_inner(), viewer.map(|AuthenticatedUser(id)| id)).await?;
+
+    let limit = page.limit();
+    let scope = format!("list_members:{}", list.id);
+    let cursor = page.cursor(&state.cursors, &scope).map_err(ApiError::BadRequest)?;
+
+    let mut members = sqlx::query_as::<_, MemberRow>(
+        "SELECT u.*, m.added_at FROM list_members m
+         INNER JOIN users u ON u.id = m.user_id
+         WHERE m.list_id = $1 AND u.deactivated_at IS NULL
+           AND ($2::timestamptz IS NULL OR (m.added_at, m.user_id) < ($2, $3))
+         ORDER BY m.added_at DESC, m.user_id DESC
+         LIMIT $4"
+    )
+    .bind(list.id)
+    .bind(cursor.map(|c| c.created_at))
+    .bind(cursor.map(|c| c.id))
+    .bind(limit + 1)
+    .fetch_all(&state.db)
+    .await?;
+
+    let next_cursor = pagination::next_cursor(&mut members, limit, &state.cursors, &scope, |m| Cursor {
+        created_at: m.added_at,
+        id: m.user.id,
+    });
+    let responses: Vec<ListMemberResponse> = members
+        .into_iter()
+        .map(|m| ListMemberResponse {
+            user: m.user.into(),
+            added_at: m.added_at,
+        })
+        .collect();
+
+    Ok(HttpResponse::Ok().json(ApiResponse {
+        success: true,
+        data: Some(responses),
+        message: None,
+        next_cursor,
+    }))
+}
+
+// Accounts that blocked the owner can't be added.
+async fn add_member(
+    state: web::Data<AppState>,
+    AuthenticatedUser(user_id): AuthenticatedUser,
+    list_id: web::Path<Uuid>,
+    req: web::Json<AddMemberRequest>,
+) -> ApiResult<HttpResponse> {
+    let list_id = list_id.into_inner();
+    let mut tx = state.db.begin().await?;
+    lock_owned(&mut tx, list_id, user_id).await?;
+
+    let member_id = sqlx::query_scalar::<_, Uuid>("SELECT id FROM users WHERE username = $1 AND deactivated_at IS NULL")
+        .bind(req.username.trim().trim_start_matches('@'))
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("User not found".to_string()))?;
+    if is_blocked(&mut tx, member_id, user_id).await? {
+        return Err(ApiError::Forbidden("You can't add this user to lists".to_string()));
+    }
+
+    let count = sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM list_members WHERE list_id = $1")
+        .bind(list_id)
+        .fetch_one(&mut *tx)
+        .await?;
+    if count >= MAX_MEMBERS_PER_LIST {
+        return Err(ApiError::Forbidden(format!("A list can have at most {} members", MAX_MEMBERS_PER_LIST)));
+    }
+
+    let result = sqlx::query("INSERT INTO list_members (list_id, user_id) VALUES ($1, $2) ON CONFLICT DO NOTHING")
+        .bind(list_id)
+        .bind(member_id)
+        .execute(&mut *tx)
+        .await?;
+    if result.rows_affected() == 0 {
+        return Err(ApiError::Conflict("User is already on this list".to_string()));
+    }
+    touch(&mut tx, list_id).await?;
+
+    tx.commit().await?;
+
+    Ok(HttpResponse::Created().json(ApiResponse {
+        success: true,
+        data: Some(fetch_visible(&state.db, list_id, Some(user_id)).await?),
+        message: None,
+        next_cursor: None,
+    }))
+}
+
+async fn remove_member(
+    state: web::Data<AppState>,
+    AuthenticatedUser(user_id): AuthenticatedUser,
+    path: web::Path<(Uuid, String)>,
+) -> ApiResult<HttpResponse> {
+    let (list_id, username) = path.into_inner();
+    let mut tx = state.db.begin().await?;
+    lock_owned(&mut tx, list_id, user_id).await?;
+
+    let result = sqlx::query(
+        "DELETE FROM list_members m USING users u
+         WHERE m.list_id = $1 AND u.id = m.user_id AND u.username = $2"
+    )
+    .bind(list_id)
+    .bind(&username)
+    .execute(&mut *tx)
+    .await?;
+    if result.rows_affected() == 0 {
+        return Err(ApiError::NotFound("User is not on this list".to_string()));
+    }
+    touch(&mut tx, list_id).await?;
+
+    tx.commit().await?;
+
+    Ok(HttpResponse::Ok().json(ApiResponse {
+        success: true,
+        data: Some(fetch_visible(&state.db, list_id, Some(user_id)).await?),
+        message: None,
+        next_cursor: None,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn visibility_defaults_to_public() {
+        let req: CreateListRequest = serde_json::from_str(r#"{"name": "Friends"}"#).unwrap();
+        assert_eq!(req.visibility, ListVisibility::Public);
+        let req: CreateListRequest = serde_json::from_str(r#"{"name": "Friends", "visibility": "private"}"#).unwrap();
+        assert_eq!(req.visibility.as_str(), "private");
+        assert!(serde_json::from_str::<CreateListRequest>(r#"{"name": "Friends", "visibility": "secret"}"#).is_err());
+    }
+}
@@ -0,0 +1,386 @@
+use actix_web::{web, HttpResponse};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::auth::{AdminUser, AuthenticatedUser};
+use crate::error::{ApiError, ApiResult};
+use crate::models::{ApiResponse, TweetResponse, TweetWithUser};
+use crate::pagination::{self, Cursor, PageQuery};
+use crate::handlers::search::like_prefix;
+use crate::AppState;
+
+const DEFAULT_RADIUS_KM: f64 = 10.0;
+const MAX_RADIUS_KM: f64 = 100.0;
+const MAX_PLACE_RESULTS: i64 = 20;
+
+// Location tags: an author can pin a tweet to coordinates or to one of the
+// instance's named places (curated by admins). Tags are only shown, and only
+// found by the nearby feed, while the author has `share_location` turned on
+// in their profile settings; turning it off hides existing tags too.
+//
+// `GET /api/tweets/nearby` is registered in main, ahead of `/api/tweets/{id}`.
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.route("/api/tweets/{id}/location", web::get().to(get_location))
+        .route("/api/tweets/{id}/location", web::put().to(set_location))
+        .route("/api/tweets/{id}/location", web::delete().to(remove_location))
+        .route("/api/places", web::get().to(search_places))
+        .route("/api/admin/places", web::post().to(create_place));
+}
+
+// Either coordinates or a place
+#[derive(Debug, Deserialize)]
+pub struct SetLocationRequest {
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+    pub place_id: Option<Uuid>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct NearbyQuery {
+    pub lat: f64,
+    pub lon: f64,
+    // Kilometres
+    pub radius: Option<f64>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PlaceSearchQuery {
+    pub q: String,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct CreatePlaceRequest {
+    #[validate(length(min = 1, max = 100))]
+    pub name: String,
+    // ISO 3166-1 alpha-2
+    #[validate(length(equal = 2))]
+    pub country_code: Option<String>,
+    pub latitude: f64,
+    pub longitude: f64,
+}
+
+#[derive(Debug, Serialize, FromRow)]
+pub struct Place {
+    pub id: Uuid,
+    pub name: String,
+    pub country_code: Option<String>,
+    pub latitude: f64,
+    pub longitude: f64,
+}
+
+#[derive(Debug, Serialize, FromRow)]
+pub struct TweetLocation {
+    pub latitude: f64,
+    pub longitude: f64,
+    pub place_id: Option<Uuid>,
+    pub place_name: Option<String>,
+    pub tagged_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct NearbyTweet {
+    #[serde(flatten)]
+    pub tweet: TweetResponse,
+    pub location: TweetLocation,
+    pub distance_km: f64,
+}
+
+#[derive(FromRow)]
+struct NearbyRow {
+    #[sqlx(flatten)]
+    tweet: TweetWithUser,
+    #[sqlx(flatten)]
+    location: TweetLocation,
+    distance_m: f64,
+}
+
+fn valid_coordinates(latitude: f64, longitude: f64) -> bool {
+    (-90.0..=90.0).contains(&latitude) && (-180.0..=180.0).contains(&longitude)
+}
+
+fn radius_meters(radius_km: Option<f64>) -> Result<f64, String> {
+    match radius_km.unwrap_or(DEFAULT_RADIUS_KM) {
+        radius if radius > 0.0 && radius <= MAX_RADIUS_KM => Ok(radius * 1000.0),
+        _ => Err(format!("radius must be more than 0 and at most {} km", MAX_RADIUS_KM)),
+    }
+}
+
+async fn fetch_location(db: &sqlx::PgPool, tweet_id: Uuid) -> Result<Option<TweetLocation>, sqlx::Error> {
+    sqlx::query_as::<_, TweetLocation>(
+        "SELECT l.latitude, l.longitude, l.place_id, p.name AS place_name, l.created_at AS tagged_at
+         FROM tweet_locations l
+         LEFT JOIN places p ON p.id = l.place_id
+         WHERE l.tweet_id = $1"
+    )
+    .bind(tweet_id)
+    .fetch_optional(db)
+    .await
+}
+
+async fn get_location(
+    state: web::Data<AppState>,
+    viewer: Option<AuthenticatedUser>,
+    tweet_id: web::Path<Uuid>,
+) -> ApiResult<HttpResponse> {
+    let tweet_id = tweet_id.into_inner();
+    let shown = sqlx::query_scalar::<_, bool>(
+        "SELECT EXISTS(
+             SELECT 1 FROM tweets t INNER JOIN users u ON u.id = t.user_id
+             WHERE t.id = $1 AND t.deleted_at IS NULL AND u.deactivated_at IS NULL AND u.share_location
+               AND tweet_visible_to(t.user_id, t.visibility, $2)
+         )"
+    )
+    .bind(tweet_id)
+    .bind(viewer.map(|AuthenticatedUser(id)| id))
+    .fetch_one(&state.db)
+    .await?;
+
+    let location = match shown {
+        true => fetch_location(&state.db, tweet_id).await?,
+        false => None,
+    }
+    .ok_or_else(|| ApiError::NotFound("Tweet has no location".to_string()))?;
+
+    Ok(HttpResponse::Ok().json(ApiResponse {
+        success: true,
+        data: Some(location),
+        message: None,
+        next_cursor: None,
+    }))
+}
+
+async fn set_location(
+    state: web::Data<AppState>,
+    AuthenticatedUser(user_id): AuthenticatedUser,
+    tweet_id: web::Path<Uuid>,
+    req: web::Json<SetLocationRequest>,
+) -> ApiResult<HttpResponse> {
+    let tweet_id = tweet_id.into_inner();
+
+    let share_location = sqlx::query_scalar::<_, bool>(
+        "SELECT u.share_location FROM tweets t INNER JOIN users u ON u.id = t.user_id
+         WHERE t.id = $1 AND t.user_id = $2 AND t.deleted_at IS NULL"
+    )
+    .bind(tweet_id)
+    .bind(user_id)
+    .fetch_optional(&state.db)
+    .await?
+    .ok_or_else(|| ApiError::NotFound("Tweet not found".to_string()))?;
+    if !share_location {
+        return Err(ApiError::BadRequest("Turn on location sharing in your profile first".to_string()));
+    }
+
+    let (latitude, longitude) = match (req.latitude, req.longitude, req.place_id) {
+        (Some(latitude), Some(longitude), None) if valid_coordinates(latitude, longitude) => (latitude, longitude),
+        (Some(_), Some(_), None) => return Err(ApiError::BadRequest("Coordinates are out of range".to_string())),
+        (None, None, Some(place_id)) => {
+            sqlx::query_as::<_, (f64, f64)>("SELECT latitude, longitude FROM places WHERE id = $1")
+                .bind(place_id)
+                .fetch_optional(&state.db)
+                .await?
+                .ok_or_else(|| ApiError::NotFound("Place not found".to_string()))?
+        }
+        _ => return Err(ApiError::BadRequest("Send either latitude and longitude, or place_id".to_string())),
+    };
+
+    sqlx::query(
+        "INSERT INTO tweet_locations (tweet_id, place_id, latitude, longitude)
+         VALUES ($1, $2, $3, $4)
+         ON CONFLICT (tweet_id) DO UPDATE
+         SET place_id = EXCLUDED.place_id, latitude = EXCLUDED.latitude, longitude = EXCLUDED.longitude, created_at = NOW()"
+    )
+    .bind(tweet_id)
+    .bind(req.place_id)
+    .bind(latitude)
+    .bind(longitude)
+    .execute(&state.db)
+    .await?;
+
+    let location = fetch_location(&state.db, tweet_id)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("Tweet not found".to_string()))?;
+
+    Ok(HttpResponse::Ok().json(ApiResponse {
+        success: true,
+        data: Some(location),
+        message: None,
+        next_cursor: None,
+    }))
+}
+
+async fn remove_location(
+    state: web::Data<AppState>,
+    AuthenticatedUser(user_id): AuthenticatedUser,
+    tweet_id: web::Path<Uuid>,
+) -> ApiResult<HttpResponse> {
+    let result = sqlx::query(
+        "DELETE FROM tweet_locations l USING tweets t
+         WHERE l.tweet_id = $1 AND t.id = l.tweet_id AND t.user_id = $2"
+    )
+    .bind(tweet_id.into_inner())
+    .bind(user_id)
+    .execute(&state.db)
+    .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(ApiError::NotFound("Tweet has no location".to_string()));
+    }
+
+    Ok(HttpResponse::Ok().json(ApiResponse::<()> {
+        success: true,
+        data: None,
+        message: Some("Location removed".to_string()),
+        next_cursor: None,
+    }))
+}
+
+// Tagged tweets within `radius` km of a point, newest first.
+pub async fn get_nearby(
+    state: web::Data<AppState>,
+    viewer: Option<AuthenticatedUser>,
+    query: web::Query<NearbyQuery>,
+    page: web::Query<PageQuery>,
+) -> ApiResult<HttpResponse> {
+    if !valid_coordinates(query.lat, query.lon) {
+        return Err(ApiError::BadRequest("Coordinates are out of range".to_string()));
+    }
+    let radius = radius_meters(query.radius).map_err(ApiError::BadRequest)?;
+    let limit = page.limit();
+    let scope = format!("nearby:{}:{}:{}", query.lat, query.lon, radius);
+    let cursor = page.cursor(&state.cursors, &scope).map_err(ApiError::BadRequest)?;
+
+    // The earth_box test uses the GiST index; the distance check trims its corners
+    let mut rows = sqlx::query_as::<_, NearbyRow>(
+        "SELECT t.id, t.user_id, t.parent_tweet_id, t.quoted_tweet_id, t.content, t.image_url,
+                t.likes_count, t.retweets_count, t.replies_count, t.visibility, t.created_at,
+                u.username as user_username, u.display_name as user_display_name,
+                u.bio as user_bio,
+                u.profile_image as user_profile_image, u.banner_image as user_banner_image,
+                u.followers_count as user_followers_count, u.following_count as user_following_count,
+                u.verified as user_verified, u.is_bot as user_is_bot, u.created_at as user_created_at,
+                l.latitude, l.longitude, l.place_id, p.name AS place_name, l.created_at AS tagged_at,
+                earth_distance(ll_to_earth($1, $2), ll_to_earth(l.latitude, l.longitude)) AS distance_m
+         FROM tweet_locations l
+         INNER JOIN tweets t ON t.id = l.tweet_id
+         INNER JOIN users u ON u.id = t.user_id
+         LEFT JOIN places p ON p.id = l.place_id
+         WHERE earth_box(ll_to_earth($1, $2), $3) @> ll_to_earth(l.latitude, l.longitude)
+           AND earth_distance(ll_to_earth($1, $2), ll_to_earth(l.latitude, l.longitude)) <= $3
+           AND u.share_location AND u.deactivated_at IS NULL AND t.deleted_at IS NULL
+           AND tweet_visible_to(t.user_id, t.visibility, $4)
+           AND NOT EXISTS (
+               SELECT 1 FROM blocks b
+               WHERE (b.blocker_id = $4 AND b.blocked_id = t.user_id) OR (b.blocker_id = t.user_id AND b.blocked_id = $4)
+           )
+           AND NOT EXISTS (SELECT 1 FROM mutes m WHERE m.muter_id = $4 AND m.muted_id = t.user_id)
+           AND ($5::timestamptz IS NULL OR (t.created_at, t.id) < ($5, $6))
+         ORDER BY t.created_at DESC, t.id DESC
+         LIMIT $7"
+    )
+    .bind(query.lat)
+    .bind(query.lon)
+    .bind(radius)
+    .bind(viewer.map(|AuthenticatedUser(id)| id))
+    .bind(cursor.map(|c| c.created_at))
+    .bind(cursor.map(|c| c.id))
+    .bind(limit + 1)
+    .fetch_all(&state.db)
+    .await?;
+
+    let next_cursor = pagination::next_cursor(&mut rows, limit, &state.cursors, &scope, |r| Cursor {
+        created_at: r.tweet.created_at,
+        id: r.tweet.id,
+    });
+    let tweets: Vec<NearbyTweet> = rows
+        .into_iter()
+        .map(|row| NearbyTweet {
+            tweet: row.tweet.into_response(false, false),
+            location: row.location,
+            distance_km: (row.distance_m / 100.0).round() / 10.0,
+        })
+        .collect();
+
+    Ok(HttpResponse::Ok().json(ApiResponse {
+        success: true,
+        data: Some(tweets),
+        message: None,
+        next_cursor,
+    }))
+}
+
+async fn search_places(state: web::Data<AppState>, query: web::Query<PlaceSearchQuery>) -> ApiResult<HttpResponse> {
+    let q = query.q.trim().to_lowercase();
+    if q.is_empty() {
+        return Err(ApiError::BadRequest("q must not be empty".to_string()));
+    }
+
+    let places = sqlx::query_as::<_, Place>(
+        "SELECT id, name, country_code, latitude, longitude FROM places
+         WHERE lower(name) LIKE $1
+         ORDER BY lower(name), id
+         LIMIT $2"
+    )
+    .bind(like_prefix(&q))
+    .bind(MAX_PLACE_RESULTS)
+    .fetch_all(&state.db)
+    .await?;
+
+    Ok(HttpResponse::Ok().json(ApiResponse {
+        success: true,
+        data: Some(places),
+        message: None,
+        next_cursor: None,
+    }))
+}
+
+async fn create_place(state: web::Data<AppState>, _admin: AdminUser, req: web::Json<CreatePlaceRequest>) -> ApiResult<HttpResponse> {
+    req.validate()?;
+    if !valid_coordinates(req.latitude, req.longitude) {
+        return Err(ApiError::BadRequest("Coordinates are out of range".to_string()));
+    }
+
+    let place = sqlx::query_as::<_, Place>(
+        "INSERT INTO places (name, country_code, latitude, longitude)
+         VALUES ($1, $2, $3, $4)
+         RETURNING id, name, country_code, latitude, longitude"
+    )
+    .bind(req.name.trim())
+    .bind(req.country_code.as_deref().map(str::to_uppercase))
+    .bind(req.latitude)
+    .bind(req.longitude)
+    .fetch_one(&state.db)
+    .await?;
+
+    Ok(HttpResponse::Created().json(ApiResponse {
+        success: true,
+        data: Some(place),
+        message: None,
+        next_cursor: None,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn checks_coordinate_ranges() {
+        assert!(valid_coordinates(51.5, -0.12));
+        assert!(valid_coordinates(-90.0, 180.0));
+        assert!(!valid_coordinates(90.5, 0.0));
+        assert!(!valid_coordinates(0.0, -181.0));
+        assert!(!valid_coordinates(f64::NAN, 0.0));
+    }
+
+    #[test]
+    fn radius_defaults_and_is_capped() {
+        assert_eq!(radius_meters(None), Ok(10_000.0));
+        assert_eq!(radius_meters(Some(2.5)), Ok(2_500.0));
+        assert!(radius_meters(Some(0.0)).is_err());
+        assert!(radius_meters(Some(500.0)).is_err());
+        assert!(radius_meters(Some(f64::NAN)).is_err());
+    }
+}
@@ -0,0 +1,164 @@
+use lettre::message::header::ContentType;
+use lettre::message::Mailbox;
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+use reqwest::{Method, Url};
+use serde_json::json;
+use std::future::Future;
+use std::pin::Pin;
+
+use crate::config;
+use crate::http_client::HttpClient;
+use crate::secrets;
+
+// Outgoing email. Chosen with MAIL_TRANSPORT: `log` (default) only writes
+// the message to the log, which is enough for development; `smtp` delivers
+// through an SMTP server; `http` posts it as JSON to a transactional mail
+// relay.
+pub trait Mailer: Send + Sync {
+    fn send<'a>(
+        &'a self,
+        to: &'a str,
+        subject: &'a str,
+        text: &'a str,
+    ) -> Pin<Box<dyn Future<Output = Result<(), String>> + 'a>>;
+}
+
+pub fn from_env(http: HttpClient) -> Result<Box<dyn Mailer>, String> {
+    match config::var("MAIL_TRANSPORT").as_deref() {
+        None | Some("log") => {
+            log::warn!("MAIL_TRANSPORT=log: outgoing email is only logged, not delivered");
+            Ok(Box::new(LogMailer))
+        }
+        Some("smtp") => Ok(Box::new(SmtpMailer::from_env()?)),
+        Some("http") => Ok(Box::new(HttpMailer::from_env(http)?)),
+        Some(other) => Err(format!("Unknown MAIL_TRANSPORT: {}", other)),
+    }
+}
+
+pub struct LogMailer;
+
+impl Mailer for LogMailer {
+    fn send<'a>(
+        &'a self,
+        to: &'a str,
+        subject: &'a str,
+        text: &'a str,
+    ) -> Pin<Box<dyn Future<Output = Result<(), String>> + 'a>> {
+        Box::pin(async move {
+            log::info!("email to {}: {}\n{}", to, subject, text);
+            Ok(())
+        })
+    }
+}
+
+// SMTP_HOST and MAIL_FROM are required. SMTP_TLS is `starttls` (default,
+// port 587), `tls` (implicit TLS, port 465) or `none` (local relays only);
+// SMTP_USERNAME/SMTP_PASSWORD are sent when both are set.
+pub struct SmtpMailer {
+    transport: AsyncSmtpTransport<Tokio1Executor>,
+    from: Mailbox,
+}
+
+impl SmtpMailer {
+    pub fn from_env() -> Result<Self, String> {
+        let host = config::var("SMTP_HOST").ok_or("SMTP_HOST must be set when MAIL_TRANSPORT=smtp")?;
+        let from = mail_from()?;
+
+        let (builder, default_port) = match config::var("SMTP_TLS").as_deref() {
+            None | Some("starttls") => (AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&host), 587),
+            Some("tls") => (AsyncSmtpTransport::<Tokio1Executor>::relay(&host), 465),
+            Some("none") => (Ok(AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(&host)), 25),
+            Some(other) => return Err(format!("Unknown SMTP_TLS: {}", other)),
+        };
+        let port = config::parse_var("SMTP_PORT", default_port)?;
+
+        let mut builder = builder.map_err(|e| format!("Invalid SMTP settings: {}", e))?.port(port);
+        if let (Some(username), Some(password)) = (config::var("SMTP_USERNAME"), secrets::var("SMTP_PASSWORD")) {
+            builder = builder.credentials(Credentials::new(username, password));
+        }
+
+        Ok(SmtpMailer { transport: builder.build(), from })
+    }
+}
+
+impl Mailer for SmtpMailer {
+    fn send<'a>(
+        &'a self,
+        to: &'a str,
+        subject: &'a str,
+        text: &'a str,
+    ) -> Pin<Box<dyn Future<Output = Result<(), String>> + 'a>> {
+        Box::pin(async move {
+            let to: Mailbox = to.parse().map_err(|e| format!("Invalid recipient: {}", e))?;
+            let message = Message::builder()
+                .from(self.from.clone())
+                .to(to)
+                .subject(subject)
+                .header(ContentType::TEXT_PLAIN)
+                .body(text.to_string())
+                .map_err(|e| format!("Failed to build email: {}", e))?;
+
+            self.transport
+                .send(message)
+                .await
+                .map(|_| ())
+                .map_err(|e| format!("SMTP delivery failed: {}", e))
+        })
+    }
+}
+
+// Posts `from`, `to`, `subject` and `text` as JSON to MAIL_HTTP_URL,
+// authenticated with the MAIL_HTTP_TOKEN bearer token when set.
+pub struct HttpMailer {
+    url: Url,
+    token: Option<String>,
+    from: String,
+    http: HttpClient,
+}
+
+impl HttpMailer {
+    pub fn from_env(http: HttpClient) -> Result<Self, String> {
+        let url = config::var("MAIL_HTTP_URL").ok_or("MAIL_HTTP_URL must be set when MAIL_TRANSPORT=http")?;
+        Ok(HttpMailer {
+            url: Url::parse(&url).map_err(|e| format!("Invalid MAIL_HTTP_URL: {}", e))?,
+            token: secrets::var("MAIL_HTTP_TOKEN"),
+            from: mail_from()?.to_string(),
+            http,
+        })
+    }
+}
+
+impl Mailer for HttpMailer {
+    fn send<'a>(
+        &'a self,
+        to: &'a str,
+        subject: &'a str,
+        text: &'a str,
+    ) -> Pin<Box<dyn Future<Output = Result<(), String>> + 'a>> {
+        Box::pin(async move {
+            let body = json!({ "from": self.from, "to": to, "subject": subject, "text": text }).to_string();
+            let mut request = self
+                .http
+                .trusted(Method::POST, self.url.clone())
+                .header("Content-Type", "application/json")
+                .body(body.into_bytes());
+            if let Some(token) = &self.token {
+                request = request.header("Authorization", &format!("Bearer {}", token));
+            }
+
+            let response = request.send().await.map_err(|e| format!("Mail relay request failed: {}", e))?;
+            if !response.status().is_success() {
+                return Err(format!("Mail relay returned {}", response.status()));
+            }
+            Ok(())
+        })
+    }
+}
+
+fn mail_from() -> Result<Mailbox, String> {
+    config::var("MAIL_FROM")
+        .ok_or_else(|| "MAIL_FROM must be set to deliver email".to_string())?
+        .parse()
+        .map_err(|e| format!("Invalid MAIL_FROM: {}", e))
+}
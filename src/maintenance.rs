@@ -0,0 +1,88 @@
+use actix_web::body::{EitherBody, MessageBody};
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::http::Method;
+use actix_web::middleware::Next;
+use actix_web::{web, Error};
+use sqlx::PgPool;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use crate::config;
+use crate::error::ApiError;
+use crate::AppState;
+
+// Scheduled maintenance. While a window is active the instance is read-only:
+// every write is rejected with 503 except admin routes (so operators can
+// end maintenance early) and the session endpoints (login, refresh, logout).
+#[derive(Debug, Clone)]
+pub struct Maintenance {
+    read_only: Arc<AtomicBool>,
+    notice_hours: i32,
+}
+
+impl Maintenance {
+    pub fn from_env() -> Result<Self, String> {
+        Ok(Maintenance {
+            read_only: Arc::new(AtomicBool::new(false)),
+            notice_hours: config::parse_var("MAINTENANCE_NOTICE_HOURS", 24)?,
+        })
+    }
+
+    pub fn is_read_only(&self) -> bool {
+        self.read_only.load(Ordering::Relaxed)
+    }
+
+    // Run by the scheduler: flips read-only mode on/off to match the current
+    // window and sends the advance notice for windows starting soon.
+    pub async fn tick(&self, db: &PgPool) -> Result<(), sqlx::Error> {
+        let active = sqlx::query_scalar::<_, bool>(
+            "SELECT EXISTS(SELECT 1 FROM maintenance_windows WHERE starts_at <= NOW() AND ends_at > NOW())"
+        )
+        .fetch_one(db)
+        .await?;
+
+        if self.read_only.swap(active, Ordering::Relaxed) != active {
+            log::info!("maintenance read-only mode {}", if active { "enabled" } else { "disabled" });
+        }
+
+        // Claiming the window and fanning out happen in one statement so
+        // every user is notified exactly once
+        sqlx::query(
+            "WITH due AS (
+                 UPDATE maintenance_windows SET notified_at = NOW()
+                 WHERE notified_at IS NULL
+                   AND created_by IS NOT NULL
+                   AND starts_at <= NOW() + make_interval(hours => $1)
+                   AND ends_at > NOW()
+                 RETURNING created_by
+             )
+             INSERT INTO notifications (user_id, actor_id, kind)
+             SELECT u.id, due.created_by, 'maintenance' FROM due JOIN users u ON u.id <> due.created_by"
+        )
+        .bind(self.notice_hours)
+        .execute(db)
+        .await?;
+
+        Ok(())
+    }
+}
+
+pub async fn read_only_guard<B: MessageBody + 'static>(
+    req: ServiceRequest,
+    next: Next<B>,
+) -> Result<ServiceResponse<EitherBody<B>>, Error> {
+    let read_only = req
+        .app_data::<web::Data<AppState>>()
+        .is_some_and(|state| state.maintenance.is_read_only());
+
+    let is_write = !matches!(*req.method(), Method::GET | Method::HEAD | Method::OPTIONS);
+    let exempt = req.path().starts_with("/api/admin/")
+        || matches!(req.path(), "/api/auth/login" | "/api/auth/refresh" | "/api/auth/logout");
+
+    if read_only && is_write && !exempt {
+        let error = ApiError::ServiceUnavailable("The instance is read-only during scheduled maintenance".to_string());
+        return Ok(req.error_response(error).map_into_right_body());
+    }
+
+    next.call(req).await.map(ServiceResponse::map_into_left_body)
+}
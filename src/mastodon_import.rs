@@ -0,0 +1,401 @@
+use actix_web::{web, HttpResponse};
+use chrono::{DateTime, Utc};
+use futures_util::future::LocalBoxFuture;
+use reqwest::Url;
+use serde::{Deserialize, Serialize};
+use sqlx::types::Json;
+use sqlx::{FromRow, PgPool};
+use uuid::Uuid;
+
+use crate::auth::AuthenticatedUser;
+use crate::error::{ApiError, ApiResult};
+use crate::jobs::{self, Job, JobHandler, JobResult};
+use crate::models::{ApiResponse, FollowImportRowStatus};
+use crate::handlers::social;
+use crate::AppState;
+
+// Applies the account lists from a Mastodon export to the caller's account:
+// following_accounts.csv, blocked_accounts.csv and muted_accounts.csv, one
+// upload per list. Each runs as a background job that appends a result per
+// row, so the status endpoint shows progress and what didn't apply. Accounts
+// on other servers are reported as `remote`; only local accounts (no domain,
+// or this server's) can be followed, blocked or muted here.
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.route("/api/import/mastodon/{kind}", web::post().to(import_list))
+        .route("/api/import/mastodon/{id}", web::get().to(get_import));
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ImportKind {
+    Following,
+    Blocks,
+    Mutes,
+}
+
+impl ImportKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ImportKind::Following => "following",
+            ImportKind::Blocks => "blocks",
+            ImportKind::Mutes => "mutes",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Account {
+    Local(String),
+    Remote,
+    Invalid,
+}
+
+// A CSV row as uploaded. `notify` is the following list's "Notify on new
+// posts" column.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ImportRow {
+    pub row: usize,
+    pub input: String,
+    pub account: Account,
+    #[serde(default)]
+    pub notify: bool,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AccountImportRowStatus {
+    Applied,
+    AlreadyApplied,
+    NotFound,
+    Remote,
+    Invalid,
+    SelfTarget,
+    Blocked,
+    Error,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AccountImportRow {
+    pub row: usize,
+    pub input: String,
+    pub username: Option<String>,
+    pub status: AccountImportRowStatus,
+}
+
+#[derive(Debug, Serialize, FromRow)]
+pub struct AccountImport {
+    pub id: Uuid,
+    pub kind: String,
+    pub status: String,
+    pub total_rows: i32,
+    pub processed_rows: i32,
+    pub results: Json<Vec<AccountImportRow>>,
+    pub created_at: DateTime<Utc>,
+    pub completed_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct ImportPayload {
+    import_id: Uuid,
+    user_id: Uuid,
+    kind: ImportKind,
+    rows: Vec<ImportRow>,
+}
+
+// Mastodon writes `user@domain` addresses, with a header row on the lists
+// that have more than one column.
+pub fn parse_accounts(body: &str, local_domain: Option<&str>) -> Vec<ImportRow> {
+    body.lines()
+        .enumerate()
+        .filter_map(|(i, line)| {
+            let input = social::csv_first_field(line);
+            if input.is_empty() || (i == 0 && input.eq_ignore_ascii_case("account address")) {
+                return None;
+            }
+            let notify = line.split(',').nth(2).is_some_and(|v| v.trim().eq_ignore_ascii_case("true"));
+            Some(ImportRow {
+                row: i + 1,
+                account: parse_address(&input, local_domain),
+                input,
+                notify,
+            })
+        })
+        .collect()
+}
+
+fn parse_address(input: &str, local_domain: Option<&str>) -> Account {
+    let address = input.trim_start_matches('@');
+    let (username, domain) = match address.split_once('@') {
+        Some((username, domain)) => (username, Some(domain)),
+        None => (address, None),
+    };
+
+    if let Some(domain) = domain {
+        if !local_domain.is_some_and(|local| local.eq_ignore_ascii_case(domain)) {
+            return Account::Remote;
+        }
+    }
+    if username.contains(['/', '@']) {
+        return Account::Invalid;
+    }
+
+    match social::username_from_import(username) {
+        Some(username) => Account::Local(username),
+        None => Account::Invalid,
+    }
+}
+
+async fn import_list(
+    state: web::Data<AppState>,
+    AuthenticatedUser(user_id): AuthenticatedUser,
+    kind: web::Path<ImportKind>,
+    body: String,
+) -> ApiResult<HttpResponse> {
+    let kind = kind.into_inner();
+    let local_domain = Url::parse(&state.base_url).ok().and_then(|url| url.host_str().map(str::to_string));
+    let rows = parse_accounts(&body, local_domain.as_deref());
+
+    if rows.is_empty() {
+        return Err(ApiError::BadRequest("No accounts found in import".to_string()));
+    }
+    if rows.len() > social::MAX_FOLLOW_IMPORT_ROWS {
+        return Err(ApiError::BadRequest(format!(
+            "Imports are limited to {} rows",
+            social::MAX_FOLLOW_IMPORT_ROWS
+        )));
+    }
+
+    let running = sqlx::query_scalar::<_, bool>(
+        "SELECT EXISTS(SELECT 1 FROM account_imports WHERE user_id = $1 AND status IN ('pending', 'running'))"
+    )
+    .bind(user_id)
+    .fetch_one(&state.db)
+    .await?;
+
+    if running {
+        return Err(ApiError::Conflict("An import is already in progress".to_string()));
+    }
+
+    let mut tx = state.db.begin().await?;
+
+    let import = sqlx::query_as::<_, AccountImport>(
+        "INSERT INTO account_imports (user_id, kind, total_rows) VALUES ($1, $2, $3) RETURNING *"
+    )
+    .bind(user_id)
+    .bind(kind.as_str())
+    .bind(rows.len() as i32)
+    .fetch_one(&mut *tx)
+    .await?;
+
+    jobs::enqueue(
+        &mut tx,
+        MastodonImportJob::KIND,
+        &ImportPayload { import_id: import.id, user_id, kind, rows },
+    )
+    .await?;
+
+    tx.commit().await?;
+
+    Ok(HttpResponse::Accepted().json(ApiResponse {
+        success: true,
+        data: Some(import),
+        message: Some("Import started".to_string()),
+        next_cursor: None,
+    }))
+}
+
+async fn get_import(
+    state: web::Data<AppState>,
+    AuthenticatedUser(user_id): AuthenticatedUser,
+    import_id: web::Path<Uuid>,
+) -> ApiResult<HttpResponse> {
+    let import = sqlx::query_as::<_, AccountImport>("SELECT * FROM account_imports WHERE id = $1 AND user_id = $2")
+        .bind(import_id.into_inner())
+        .bind(user_id)
+        .fetch_optional(&state.db)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("Import not found".to_string()))?;
+
+    Ok(HttpResponse::Ok().json(ApiResponse {
+        success: true,
+        data: Some(import),
+        message: None,
+        next_cursor: None,
+    }))
+}
+
+pub struct MastodonImportJob;
+
+impl MastodonImportJob {
+    pub const KIND: &'static str = "mastodon_import";
+}
+
+impl JobHandler for MastodonImportJob {
+    fn kind(&self) -> &'static str {
+        Self::KIND
+    }
+
+    fn run<'a>(&'a self, state: &'a AppState, job: &'a Job) -> LocalBoxFuture<'a, JobResult> {
+        Box::pin(async move {
+            let payload: ImportPayload = job.payload()?;
+            run_import(&state.db, payload).await?;
+            Ok(())
+        })
+    }
+
+    fn failed<'a>(&'a self, state: &'a AppState, job: &'a Job, _error: &'a str) -> LocalBoxFuture<'a, JobResult> {
+        Box::pin(async move {
+            let payload: ImportPayload = job.payload()?;
+            sqlx::query("UPDATE account_imports SET status = 'failed', completed_at = NOW() WHERE id = $1")
+                .bind(payload.import_id)
+                .execute(&state.db)
+                .await?;
+            Ok(())
+        })
+    }
+}
+
+// Same shape as the follow import: one result appended per row, and a
+// retried job picks up after the last recorded row.
+async fn run_import(db: &PgPool, payload: ImportPayload) -> Result<(), sqlx::Error> {
+    let processed = sqlx::query_scalar::<_, i32>(
+        "UPDATE account_imports SET status = 'running' WHERE id = $1 RETURNING processed_rows"
+    )
+    .bind(payload.import_id)
+    .fetch_optional(db)
+    .await?;
+
+    // The import was deleted along with its user
+    let Some(processed) = processed else {
+        return Ok(());
+    };
+
+    for row in payload.rows.into_iter().skip(processed as usize) {
+        let (username, status) = match row.account {
+            Account::Local(username) => {
+                let status = apply_row(db, payload.user_id, payload.kind, &username, row.notify).await;
+                (Some(username), status)
+            }
+            Account::Remote => (None, AccountImportRowStatus::Remote),
+            Account::Invalid => (None, AccountImportRowStatus::Invalid),
+        };
+
+        sqlx::query(
+            "UPDATE account_imports
+             SET processed_rows = processed_rows + 1, results = results || $2
+             WHERE id = $1"
+        )
+        .bind(payload.import_id)
+        .bind(Json(vec![AccountImportRow { row: row.row, input: row.input, username, status }]))
+        .execute(db)
+        .await?;
+
+        if payload.kind == ImportKind::Following && matches!(status, AccountImportRowStatus::Applied) {
+            actix_web::rt::time::sleep(social::FOLLOW_IMPORT_DELAY).await;
+        }
+    }
+
+    sqlx::query("UPDATE account_imports SET status = 'completed', completed_at = NOW() WHERE id = $1")
+        .bind(payload.import_id)
+        .execute(db)
+        .await?;
+
+    Ok(())
+}
+
+async fn apply_row(db: &PgPool, user_id: Uuid, kind: ImportKind, username: &str, notify: bool) -> AccountImportRowStatus {
+    if kind == ImportKind::Following {
+        let status = match social::follow_import_row(db, user_id, username).await {
+            FollowImportRowStatus::Followed => AccountImportRowStatus::Applied,
+            FollowImportRowStatus::AlreadyFollowing => AccountImportRowStatus::AlreadyApplied,
+            FollowImportRowStatus::NotFound => AccountImportRowStatus::NotFound,
+            FollowImportRowStatus::Invalid => AccountImportRowStatus::Invalid,
+            FollowImportRowStatus::SelfFollow => AccountImportRowStatus::SelfTarget,
+            FollowImportRowStatus::Blocked => AccountImportRowStatus::Blocked,
+            FollowImportRowStatus::Error => AccountImportRowStatus::Error,
+        };
+        if notify && matches!(status, AccountImportRowStatus::Applied | AccountImportRowStatus::AlreadyApplied) {
+            let result = sqlx::query(
+                "UPDATE follows SET notify = 'all'
+                 WHERE follower_id = $1 AND following_id = (SELECT id FROM users WHERE username = $2)"
+            )
+            .bind(user_id)
+            .bind(username)
+            .execute(db)
+            .await;
+            if result.is_err() {
+                return AccountImportRowStatus::Error;
+            }
+        }
+        return status;
+    }
+
+    let target_id = match sqlx::query_scalar::<_, Uuid>("SELECT id FROM users WHERE username = $1")
+        .bind(username)
+        .fetch_optional(db)
+        .await
+    {
+        Ok(Some(id)) => id,
+        Ok(None) => return AccountImportRowStatus::NotFound,
+        Err(_) => return AccountImportRowStatus::Error,
+    };
+
+    if target_id == user_id {
+        return AccountImportRowStatus::SelfTarget;
+    }
+
+    let applied = match kind {
+        ImportKind::Blocks => social::insert_block(db, user_id, target_id).await,
+        _ => sqlx::query("INSERT INTO mutes (muter_id, muted_id) VALUES ($1, $2) ON CONFLICT DO NOTHING")
+            .bind(user_id)
+            .bind(target_id)
+            .execute(db)
+            .await
+            .map(|result| result.rows_affected() > 0),
+    };
+
+    match applied {
+        Ok(true) => AccountImportRowStatus::Applied,
+        Ok(false) => AccountImportRowStatus::AlreadyApplied,
+        Err(_) => AccountImportRowStatus::Error,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_following_export() {
+        let csv = "Account address,Show boosts,Notify on new posts,Languages\n\
+                   alice@social.example,true,true,\n\
+                   bob@mastodon.social,true,false,\n\
+                   \n\
+                   carol,true,false,\n";
+        let rows = parse_accounts(csv, Some("social.example"));
+
+        assert_eq!(
+            rows,
+            vec![
+                ImportRow { row: 2, input: "alice@social.example".into(), account: Account::Local("alice".into()), notify: true },
+                ImportRow { row: 3, input: "bob@mastodon.social".into(), account: Account::Remote, notify: false },
+                ImportRow { row: 5, input: "carol".into(), account: Account::Local("carol".into()), notify: false },
+            ]
+        );
+    }
+
+    #[test]
+    fn parses_single_column_lists() {
+        let rows = parse_accounts("@dave@Social.Example\nx\nbad/name\n", Some("social.example"));
+        let accounts: Vec<_> = rows.into_iter().map(|row| row.account).collect();
+
+        assert_eq!(accounts, vec![Account::Local("dave".into()), Account::Invalid, Account::Invalid]);
+    }
+
+    #[test]
+    fn treats_every_domain_as_remote_without_a_local_one() {
+        assert_eq!(parse_address("alice@social.example", None), Account::Remote);
+        assert_eq!(parse_address("alice", None), Account::Local("alice".into()));
+    }
+}
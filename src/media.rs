@@ -0,0 +1,73 @@
+use image::{ImageFormat, ImageReader, Limits};
+use std::io::Cursor;
+use std::sync::Arc;
+
+use crate::config;
+use crate::error::ApiError;
+use crate::http_client::HttpClient;
+use crate::storage::{self, MediaStorage};
+
+const THUMBNAIL_SIZE: u32 = 400;
+const MAX_DIMENSION: u32 = 8192;
+
+// Image uploads: checks what the bytes actually are (the declared content
+// type is only a hint), bounds the decoded size, and renders a thumbnail.
+#[derive(Clone)]
+pub struct MediaUploads {
+    pub storage: Arc<dyn MediaStorage>,
+    pub max_bytes: usize,
+}
+
+pub struct ProcessedImage {
+    pub content_type: &'static str,
+    pub extension: &'static str,
+    pub width: u32,
+    pub height: u32,
+    pub thumbnail: Vec<u8>,
+}
+
+impl MediaUploads {
+    pub fn from_env(base_url: &str, http: HttpClient) -> Result<Self, String> {
+        Ok(MediaUploads {
+            storage: storage::from_env(base_url, http)?.into(),
+            max_bytes: config::parse_var("MEDIA_UPLOAD_MAX_BYTES", 5 * 1024 * 1024)?,
+        })
+    }
+}
+
+// CPU-bound; run it off the async workers.
+pub fn process_image(bytes: &[u8]) -> Result<ProcessedImage, ApiError> {
+    let format = image::guess_format(bytes).map_err(|_| ApiError::BadRequest("Unsupported image format".to_string()))?;
+    let (content_type, extension) = match format {
+        ImageFormat::Png => ("image/png", "png"),
+        ImageFormat::Jpeg => ("image/jpeg", "jpg"),
+        ImageFormat::Gif => ("image/gif", "gif"),
+        ImageFormat::WebP => ("image/webp", "webp"),
+        _ => return Err(ApiError::BadRequest("Only PNG, JPEG, GIF and WebP images are supported".to_string())),
+    };
+
+    let mut limits = Limits::default();
+    limits.max_image_width = Some(MAX_DIMENSION);
+    limits.max_image_height = Some(MAX_DIMENSION);
+
+    let mut reader = ImageReader::with_format(Cursor::new(bytes), format);
+    reader.limits(limits);
+    let image = reader
+        .decode()
+        .map_err(|_| ApiError::BadRequest("Image is corrupt or too large".to_string()))?;
+
+    let mut thumbnail = Vec::new();
+    image
+        .thumbnail(THUMBNAIL_SIZE, THUMBNAIL_SIZE)
+        .to_rgb8()
+        .write_to(&mut Cursor::new(&mut thumbnail), ImageFormat::Jpeg)
+        .map_err(|e| ApiError::Internal(format!("Failed to encode thumbnail: {}", e)))?;
+
+    Ok(ProcessedImage {
+        content_type,
+        extension,
+        width: image.width(),
+        height: image.height(),
+        thumbnail,
+    })
+}
@@ -0,0 +1,166 @@
+use reqwest::{Method, Url};
+use sha2::{Digest, Sha256};
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime};
+
+use crate::config;
+use crate::error::ApiError;
+use crate::http_client::{HttpClient, HttpError};
+
+const MAX_REDIRECTS: usize = 3;
+const FETCH_TIMEOUT: Duration = Duration::from_secs(10);
+
+// Fetches remote media on behalf of clients so they never hotlink other
+// servers. Responses are cached on disk keyed by the SHA-256 of the URL.
+//
+// Fetches go through the client's public pool, so every hop (including
+// redirects) is refused unless its host is publicly routable.
+#[derive(Clone)]
+pub struct MediaProxy {
+    http: HttpClient,
+    pub cache_dir: PathBuf,
+    pub max_bytes: u64,
+    pub cache_ttl: Duration,
+}
+
+pub struct Media {
+    pub content_type: String,
+    pub body: Vec<u8>,
+}
+
+impl MediaProxy {
+    pub fn from_env(http: HttpClient) -> Result<Self, String> {
+        Ok(MediaProxy {
+            http,
+            cache_dir: config::var("MEDIA_PROXY_CACHE_DIR")
+                .unwrap_or_else(|| "./media_cache".to_string())
+                .into(),
+            max_bytes: config::parse_var("MEDIA_PROXY_MAX_BYTES", 10 * 1024 * 1024)?,
+            cache_ttl: Duration::from_secs(config::parse_var("MEDIA_PROXY_CACHE_TTL_SECS", 7 * 24 * 3600)?),
+        })
+    }
+
+    pub async fn get(&self, url: &str) -> Result<Media, ApiError> {
+        let url = Url::parse(url).map_err(|_| ApiError::BadRequest("Invalid media URL".to_string()))?;
+        let key = hex::encode(Sha256::digest(url.as_str().as_bytes()));
+
+        if let Some(media) = self.read_cache(&key).await {
+            return Ok(media);
+        }
+
+        let media = self.fetch(url).await?;
+        self.write_cache(&key, &media).await;
+
+        Ok(media)
+    }
+
+    async fn fetch(&self, mut url: Url) -> Result<Media, ApiError> {
+        for _ in 0..=MAX_REDIRECTS {
+            let mut response = self
+                .http
+                .public(Method::GET, url.clone())
+                .map_err(fetch_error)?
+                .timeout(FETCH_TIMEOUT)
+                .send()
+                .await
+                .map_err(fetch_error)?;
+
+            if response.status().is_redirection() {
+                let location = response
+                    .headers()
+                    .get(reqwest::header::LOCATION)
+                    .and_then(|v| v.to_str().ok())
+                    .and_then(|v| url.join(v).ok())
+                    .ok_or_else(|| ApiError::BadGateway("Invalid redirect from remote server".to_string()))?;
+                url = location;
+                continue;
+            }
+
+            if !response.status().is_success() {
+                return Err(ApiError::BadGateway(format!(
+                    "Remote server responded with {}",
+                    response.status()
+                )));
+            }
+
+            let content_type = response
+                .headers()
+                .get(reqwest::header::CONTENT_TYPE)
+                .and_then(|v| v.to_str().ok())
+                .unwrap_or_default()
+                .to_string();
+            if !is_media_type(&content_type) {
+                return Err(ApiError::BadGateway("Remote content is not media".to_string()));
+            }
+
+            if response.content_length().is_some_and(|len| len > self.max_bytes) {
+                return Err(ApiError::BadGateway("Remote media is too large".to_string()));
+            }
+
+            // Content-Length can lie or be missing, so enforce the cap while reading
+            let mut body = Vec::new();
+            while let Some(chunk) = response
+                .chunk()
+                .await
+                .map_err(|_| ApiError::BadGateway("Failed to fetch remote media".to_string()))?
+            {
+                if (body.len() + chunk.len()) as u64 > self.max_bytes {
+                    return Err(ApiError::BadGateway("Remote media is too large".to_string()));
+                }
+                body.extend_from_slice(&chunk);
+            }
+
+            return Ok(Media { content_type, body });
+        }
+
+        Err(ApiError::BadGateway("Too many redirects".to_string()))
+    }
+
+    async fn read_cache(&self, key: &str) -> Option<Media> {
+        let path = self.cache_dir.join(key);
+        let modified = tokio::fs::metadata(&path).await.ok()?.modified().ok()?;
+        if SystemTime::now().duration_since(modified).unwrap_or_default() > self.cache_ttl {
+            return None;
+        }
+
+        let content_type = tokio::fs::read_to_string(path.with_extension("type")).await.ok()?;
+        let body = tokio::fs::read(&path).await.ok()?;
+
+        Some(Media { content_type, body })
+    }
+
+    // Best effort: a failed write only means the next request fetches again.
+    // Files are written under a temporary name and renamed into place so
+    // concurrent readers never see a partial body.
+    async fn write_cache(&self, key: &str, media: &Media) {
+        let path = self.cache_dir.join(key);
+        let tmp = self.cache_dir.join(format!("{}.tmp", key));
+
+        let result = async {
+            tokio::fs::create_dir_all(&self.cache_dir).await?;
+            tokio::fs::write(path.with_extension("type"), &media.content_type).await?;
+            tokio::fs::write(&tmp, &media.body).await?;
+            tokio::fs::rename(&tmp, &path).await
+        }
+        .await;
+
+        if let Err(e) = result {
+            log::warn!("failed to cache proxied media {}: {}", key, e);
+        }
+    }
+}
+
+fn fetch_error(e: HttpError) -> ApiError {
+    match e {
+        HttpError::Blocked => ApiError::BadRequest("Media URL is not allowed".to_string()),
+        HttpError::Request(_) => ApiError::BadGateway("Failed to fetch remote media".to_string()),
+    }
+}
+
+fn is_media_type(content_type: &str) -> bool {
+    let mime = content_type.split(';').next().unwrap_or_default().trim();
+    // SVG can carry script, so it's never proxied
+    (mime.starts_with("image/") && mime != "image/svg+xml")
+        || mime.starts_with("video/")
+        || mime.starts_with("audio/")
+}
@@ -0,0 +1,79 @@
+use sqlx::PgConnection;
+use uuid::Uuid;
+
+// Pulls `@username` handles out of tweet text. A handle starts at an `@` that
+// isn't glued to a preceding word character (so emails don't match) and runs
+// over ASCII letters, digits and underscores. Handles are lowercased and
+// deduplicated in order of appearance.
+pub fn extract(content: &str) -> Vec<String> {
+    let mut handles: Vec<String> = Vec::new();
+    let mut prev: Option<char> = None;
+    let mut chars = content.char_indices().peekable();
+
+    while let Some((i, c)) = chars.next() {
+        let starts_handle = c == '@' && !prev.is_some_and(is_handle_char);
+        prev = Some(c);
+        if !starts_handle {
+            continue;
+        }
+
+        let start = i + 1;
+        let mut end = start;
+        while let Some(&(j, next)) = chars.peek() {
+            if !is_handle_char(next) {
+                break;
+            }
+            end = j + 1;
+            prev = Some(next);
+            chars.next();
+        }
+
+        let handle = content[start..end].to_lowercase();
+        if !handle.is_empty() && !handles.contains(&handle) {
+            handles.push(handle);
+        }
+    }
+
+    handles
+}
+
+fn is_handle_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_'
+}
+
+// Records the users mentioned by a freshly inserted tweet and, when `notify`
+// is set, notifies them. Handles that don't match an account are ignored,
+// and authors aren't notified about mentioning themselves or, for tweets not
+// visible to the mentioned account, at all.
+pub async fn attach(conn: &mut PgConnection, tweet_id: Uuid, author_id: Uuid, content: &str, notify: bool) -> Result<(), sqlx::Error> {
+    let handles = extract(content);
+    if handles.is_empty() {
+        return Ok(());
+    }
+
+    sqlx::query(
+        "INSERT INTO mentions (tweet_id, user_id)
+         SELECT $1, id FROM users WHERE lower(username) = ANY($2)"
+    )
+    .bind(tweet_id)
+    .bind(&handles)
+    .execute(&mut *conn)
+    .await?;
+
+    if !notify {
+        return Ok(());
+    }
+
+    sqlx::query(
+        "INSERT INTO notifications (user_id, actor_id, kind, tweet_id)
+         SELECT m.user_id, $2, 'mention', $1 FROM mentions m
+         INNER JOIN tweets t ON t.id = m.tweet_id
+         WHERE m.tweet_id = $1 AND m.user_id <> $2 AND tweet_visible_to(t.user_id, t.visibility, m.user_id)"
+    )
+    .bind(tweet_id)
+    .bind(author_id)
+    .execute(&mut *conn)
+    .await?;
+
+    Ok(())
+}
@@ -1,8 +1,13 @@
 use serde::{Deserialize, Serialize};
-use sqlx::FromRow;
+use sqlx::{types::Json, FromRow};
 use chrono::{DateTime, Utc};
 use uuid::Uuid;
-use validator::Validate;
+use validator::{Validate, ValidationError};
+
+use crate::analytics::{ExportRange, Interval};
+use crate::crypto::Pii;
+use crate::instance::InstanceSettings;
+use crate::response_meta;
 
 // ============ DATABASE MODELS ============
 
@@ -10,7 +15,7 @@ use validator::Validate;
 pub struct User {
     pub id: Uuid,
     pub username: String,
-    pub email: String,
+    pub email: Pii,
     pub password_hash: String,
     pub display_name: String,
     pub bio: Option<String>,
@@ -19,6 +24,17 @@ pub struct User {
     pub followers_count: i32,
     pub following_count: i32,
     pub verified: bool,
+    pub moved_to: Option<String>,
+    pub deactivated_at: Option<DateTime<Utc>>,
+    pub verified_email: bool,
+    pub is_bot: bool,
+    pub automated_by: Option<String>,
+    pub role: String,
+    pub suspended_at: Option<DateTime<Utc>>,
+    pub suspended_until: Option<DateTime<Utc>>,
+    pub suspension_reason: Option<String>,
+    pub share_location: bool,
+    pub pinned_tweet_id: Option<Uuid>,
     pub created_at: DateTime<Utc>,
 }
 
@@ -26,14 +42,18 @@ pub struct User {
 pub struct Tweet {
     pub id: Uuid,
     pub user_id: Uuid,
+    pub parent_tweet_id: Option<Uuid>,
+    pub quoted_tweet_id: Option<Uuid>,
     pub content: String,
     pub image_url: Option<String>,
     pub likes_count: i32,
     pub retweets_count: i32,
     pub replies_count: i32,
+    pub visibility: String,
     pub created_at: DateTime<Utc>,
 }
 
+#[allow(dead_code)]
 #[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
 pub struct Like {
     pub id: Uuid,
@@ -42,11 +62,100 @@ pub struct Like {
     pub created_at: DateTime<Utc>,
 }
 
+#[allow(dead_code)]
 #[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
 pub struct Follow {
     pub id: Uuid,
     pub follower_id: Uuid,
     pub following_id: Uuid,
+    pub notify: String,
+    pub created_at: DateTime<Utc>,
+}
+
+// A tweet's counters and how the caller relates to it, as left by a like,
+// unlike, retweet or unretweet, so clients can update without refetching
+#[derive(Debug, Serialize, FromRow)]
+pub struct TweetState {
+    pub tweet_id: Uuid,
+    pub liked: bool,
+    pub retweeted: bool,
+    pub likes_count: i32,
+    pub retweets_count: i32,
+    pub replies_count: i32,
+}
+
+// How the caller relates to an account, as left by a follow, unfollow or
+// notification change: `followers_count` is the account's, `following_count`
+// the caller's. `notify` is None unless the caller follows the account.
+#[derive(Debug, Serialize, FromRow)]
+pub struct FollowState {
+    pub username: String,
+    pub following: bool,
+    pub followed_by: bool,
+    pub notify: Option<String>,
+    pub followers_count: i32,
+    pub following_count: i32,
+}
+
+// A tweet waiting for moderator review (see probation)
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct HeldTweet {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub content: String,
+    pub image_url: Option<String>,
+    pub parent_tweet_id: Option<Uuid>,
+    pub quoted_tweet_id: Option<Uuid>,
+    pub visibility: String,
+    pub reason: String,
+    // Id the tweet gets when approved, if the client chose one
+    pub tweet_id: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+}
+
+// One rotation of a login session's refresh token (only the hash is kept)
+#[derive(Debug, Clone, FromRow)]
+pub struct RefreshToken {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub family_id: Uuid,
+    pub expires_at: DateTime<Utc>,
+    pub used_at: Option<DateTime<Utc>>,
+    pub revoked_at: Option<DateTime<Utc>>,
+}
+
+// An uploaded image
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct Media {
+    pub id: Uuid,
+    pub content_type: String,
+    pub size_bytes: i32,
+    pub width: i32,
+    pub height: i32,
+    pub url: String,
+    pub thumbnail_url: String,
+    pub created_at: DateTime<Utc>,
+}
+
+// Content of a tweet about to be published
+#[derive(Debug, Clone)]
+pub struct NewTweet {
+    // Client-chosen; generated when None
+    pub id: Option<Uuid>,
+    pub content: String,
+    pub image_url: Option<String>,
+    pub parent_tweet_id: Option<Uuid>,
+    pub quoted_tweet_id: Option<Uuid>,
+    pub visibility: TweetVisibility,
+}
+
+#[derive(Debug, Clone, FromRow)]
+pub struct Notification {
+    pub id: Uuid,
+    pub actor_id: Uuid,
+    pub kind: String,
+    pub tweet_id: Option<Uuid>,
+    pub read_at: Option<DateTime<Utc>>,
     pub created_at: DateTime<Utc>,
 }
 
@@ -56,25 +165,111 @@ pub struct TweetWithUser {
     // Tweet fields
     pub id: Uuid,
     pub user_id: Uuid,
+    pub parent_tweet_id: Option<Uuid>,
+    pub quoted_tweet_id: Option<Uuid>,
     pub content: String,
     pub image_url: Option<String>,
     pub likes_count: i32,
     pub retweets_count: i32,
     pub replies_count: i32,
+    pub visibility: String,
     pub created_at: DateTime<Utc>,
     // User fields
     pub user_username: String,
     pub user_display_name: String,
-    pub user_email: String,
     pub user_bio: Option<String>,
     pub user_profile_image: Option<String>,
     pub user_banner_image: Option<String>,
     pub user_followers_count: i32,
     pub user_following_count: i32,
     pub user_verified: bool,
+    pub user_is_bot: bool,
     pub user_created_at: DateTime<Utc>,
 }
 
+impl TweetWithUser {
+    pub fn into_response(self, is_liked: bool, is_retweeted: bool) -> TweetResponse {
+        TweetResponse {
+            id: self.id,
+            parent_tweet_id: self.parent_tweet_id,
+            quoted_tweet_id: self.quoted_tweet_id,
+            content: self.content,
+            image_url: self.image_url,
+            likes_count: self.likes_count,
+            retweets_count: self.retweets_count,
+            replies_count: self.replies_count,
+            visibility: self.visibility,
+            created_at: self.created_at,
+            user: PublicUserResponse {
+                id: self.user_id,
+                username: self.user_username,
+                display_name: self.user_display_name,
+                bio: self.user_bio,
+                profile_image: self.user_profile_image,
+                banner_image: self.user_banner_image,
+                followers_count: self.user_followers_count,
+                following_count: self.user_following_count,
+                verified: self.user_verified,
+                is_bot: self.user_is_bot,
+                automated_by: None,
+                moved_to: None,
+                created_at: self.user_created_at,
+            },
+            is_liked,
+            is_retweeted,
+        }
+    }
+}
+
+// A timeline row: the tweet plus the viewer's like/retweet state, computed in
+// the same query
+#[derive(Debug, FromRow)]
+pub struct TweetWithViewerState {
+    #[sqlx(flatten)]
+    pub tweet: TweetWithUser,
+    pub is_liked: bool,
+    pub is_retweeted: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FollowImportRow {
+    pub row: usize,
+    pub input: String,
+    pub username: Option<String>,
+    pub status: FollowImportRowStatus,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FollowImportRowStatus {
+    Followed,
+    AlreadyFollowing,
+    NotFound,
+    Invalid,
+    SelfFollow,
+    Blocked,
+    Error,
+}
+
+#[derive(Debug, Serialize, FromRow)]
+pub struct FollowImport {
+    pub id: Uuid,
+    pub status: String,
+    pub total_rows: i32,
+    pub processed_rows: i32,
+    pub results: Json<Vec<FollowImportRow>>,
+    pub created_at: DateTime<Utc>,
+    pub completed_at: Option<DateTime<Utc>>,
+}
+
+// Job payload: each row is (row number, raw input, resolved username)
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FollowImportPayload {
+    pub import_id: Uuid,
+    pub user_id: Uuid,
+    pub rows: Vec<(usize, String, Option<String>)>,
+}
+
 // ============ REQUEST MODELS ============
 
 #[derive(Debug, Deserialize, Validate)]
@@ -96,11 +291,271 @@ pub struct LoginRequest {
     pub password: String,
 }
 
+#[derive(Debug, Deserialize)]
+pub struct RefreshTokenRequest {
+    pub refresh_token: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DeleteAccountRequest {
+    pub password: String,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct ChangePasswordRequest {
+    pub current_password: String,
+    #[validate(length(min = 6))]
+    pub new_password: String,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct ChangeEmailRequest {
+    #[validate(email)]
+    pub new_email: String,
+    pub password: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ConfirmEmailRequest {
+    pub token: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct VerifyEmailQuery {
+    pub token: String,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct ForgotPasswordRequest {
+    #[validate(email)]
+    pub email: String,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct ResetPasswordRequest {
+    pub token: String,
+    #[validate(length(min = 6))]
+    pub new_password: String,
+}
+
 #[derive(Debug, Deserialize, Validate)]
 pub struct CreateTweetRequest {
-    #[validate(length(min = 1, max = 280))]
+    // Optional id chosen by the client, so it can show the tweet before the
+    // server answers. Retrying with the same id and content returns the tweet
+    // already made instead of posting it twice.
+    #[validate(custom = "random_uuid")]
+    pub id: Option<Uuid>,
+    // Upper bound is the instance's max_tweet_length
+    #[validate(length(min = 1))]
     pub content: String,
     pub image_url: Option<String>,
+    // An upload from /api/media/upload; alternative to `image_url`
+    pub media_id: Option<Uuid>,
+    pub parent_tweet_id: Option<Uuid>,
+    #[serde(default)]
+    pub visibility: TweetVisibility,
+}
+
+// Client-chosen ids must be random (version 4) UUIDs, so they can't be
+// picked to collide with someone else's.
+fn random_uuid(id: &Uuid) -> Result<(), ValidationError> {
+    if id.get_version() == Some(uuid::Version::Random) && id.get_variant() == uuid::Variant::RFC4122 {
+        Ok(())
+    } else {
+        Err(ValidationError::new("random_uuid"))
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TweetVisibility {
+    #[default]
+    Public,
+    FollowersOnly,
+    Circle,
+}
+
+impl TweetVisibility {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TweetVisibility::Public => "public",
+            TweetVisibility::FollowersOnly => "followers_only",
+            TweetVisibility::Circle => "circle",
+        }
+    }
+}
+
+impl std::str::FromStr for TweetVisibility {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "public" => Ok(TweetVisibility::Public),
+            "followers_only" => Ok(TweetVisibility::FollowersOnly),
+            "circle" => Ok(TweetVisibility::Circle),
+            _ => Err(format!("Unknown tweet visibility: {}", s)),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct QuoteTweetRequest {
+    // Upper bound is the instance's max_tweet_length
+    #[validate(length(min = 1))]
+    pub content: String,
+    pub image_url: Option<String>,
+    pub media_id: Option<Uuid>,
+    #[serde(default)]
+    pub visibility: TweetVisibility,
+}
+
+#[derive(Debug, Default, Clone, Copy, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ReplySort {
+    #[default]
+    Top,
+    Latest,
+    Oldest,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TweetDetailQuery {
+    // Include the chain of tweets this one replies to
+    pub context: Option<bool>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RepliesQuery {
+    pub sort: Option<ReplySort>,
+    pub parent: Option<Uuid>,
+    pub cursor: Option<String>,
+    pub limit: Option<usize>,
+    pub depth: Option<usize>,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NotifyMode {
+    All,
+    RepliesOff,
+    Off,
+}
+
+impl NotifyMode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            NotifyMode::All => "all",
+            NotifyMode::RepliesOff => "replies_off",
+            NotifyMode::Off => "off",
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct NotifyRequest {
+    pub mode: NotifyMode,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct MuteKeywordRequest {
+    #[validate(length(min = 1, max = 100))]
+    pub keyword: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AliasRequest {
+    pub username: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct MoveAccountRequest {
+    // Local username, remote handle (user@domain) or profile URL
+    pub target: String,
+    #[serde(default)]
+    pub migrate_followers: bool,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UsageQuery {
+    pub days: Option<i32>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TweetAnalyticsQuery {
+    pub days: Option<i32>,
+    #[serde(default)]
+    pub interval: Interval,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AnalyticsExportQuery {
+    #[serde(default)]
+    pub range: ExportRange,
+}
+
+// A batch of what a client saw: tweets that were on screen, and tweets whose
+// author's profile was opened from them
+#[derive(Debug, Deserialize)]
+pub struct TweetViewsRequest {
+    #[serde(default)]
+    pub tweet_ids: Vec<Uuid>,
+    #[serde(default)]
+    pub profile_clicks: Vec<Uuid>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TrendingQuery {
+    pub hours: Option<i32>,
+    pub limit: Option<i64>,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct CreateAnnouncementRequest {
+    #[validate(length(min = 1, max = 200))]
+    pub title: String,
+    #[validate(length(min = 1, max = 5000))]
+    pub body: String,
+    #[serde(default)]
+    pub is_banner: bool,
+    pub starts_at: Option<DateTime<Utc>>,
+    pub ends_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct CreateMaintenanceWindowRequest {
+    #[validate(length(min = 1, max = 1000))]
+    pub reason: String,
+    pub starts_at: DateTime<Utc>,
+    pub ends_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct MarkReadRequest {
+    pub ids: Option<Vec<Uuid>>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SearchQuery {
+    pub q: String,
+    pub limit: Option<i64>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ExplainQuery {
+    pub route: String,
+    pub q: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct WsQuery {
+    pub token: Option<String>,
+    // Label for drafts autosaved on this connection, e.g. `laptop`
+    pub device: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ProxyMediaQuery {
+    pub url: String,
 }
 
 #[derive(Debug, Deserialize)]
@@ -109,28 +564,107 @@ pub struct UpdateProfileRequest {
     pub bio: Option<String>,
     pub profile_image: Option<String>,
     pub banner_image: Option<String>,
+    pub share_location: Option<bool>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AutomationRequest {
+    pub is_bot: bool,
+    // Username of the account running the bot
+    pub owner: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PinTweetRequest {
+    // None unpins
+    pub tweet_id: Option<Uuid>,
+}
+
+#[derive(Debug, Serialize, FromRow)]
+pub struct CollectionSummary {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub title: String,
+    pub description: String,
+    pub featured: bool,
+    pub tweets_count: i64,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
 }
 
 // ============ RESPONSE MODELS ============
 
-#[derive(Debug, Serialize)]
+// Serialized with a `meta` block when the request has one (see
+// `response_meta`), so handlers don't have to thread it through.
+#[derive(Debug)]
 pub struct ApiResponse<T> {
     pub success: bool,
     pub data: Option<T>,
     pub message: Option<String>,
+    pub next_cursor: Option<String>,
+}
+
+impl<T: Serialize> Serialize for ApiResponse<T> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+
+        let meta = response_meta::current();
+        let mut response = serializer.serialize_struct("ApiResponse", 5)?;
+        response.serialize_field("success", &self.success)?;
+        response.serialize_field("data", &self.data)?;
+        response.serialize_field("message", &self.message)?;
+        match &self.next_cursor {
+            Some(cursor) => response.serialize_field("next_cursor", cursor)?,
+            None => response.skip_field("next_cursor")?,
+        }
+        match &meta {
+            Some(meta) => response.serialize_field("meta", meta)?,
+            None => response.skip_field("meta")?,
+        }
+        response.end()
+    }
+}
+
+// GET /api/users/me/data.json: everything worth a quick look, in one file
+#[derive(Debug, Serialize)]
+pub struct DataExport {
+    pub exported_at: DateTime<Utc>,
+    pub profile: PrivateUserResponse,
+    pub aliases: Vec<String>,
+    pub following: Vec<ExportedFollow>,
+    pub tweets: Vec<Tweet>,
+}
+
+#[derive(Debug, Serialize, FromRow)]
+pub struct MutedKeyword {
+    pub id: Uuid,
+    pub keyword: String,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, FromRow)]
+pub struct ExportedFollow {
+    pub username: String,
+    pub notify: String,
+    pub created_at: DateTime<Utc>,
 }
 
 #[derive(Debug, Serialize)]
 pub struct AuthResponse {
+    // Short-lived access token for the Authorization header
     pub token: String,
-    pub user: UserResponse,
+    // Seconds until `token` expires
+    pub expires_in: i64,
+    pub refresh_token: String,
+    pub user: PrivateUserResponse,
 }
 
+// Profile as anyone may see it. Embedded in tweets, notifications and search
+// results, so it must never carry private account data.
 #[derive(Debug, Serialize, Clone)]
-pub struct UserResponse {
+pub struct PublicUserResponse {
     pub id: Uuid,
     pub username: String,
-    pub email: String,
     pub display_name: String,
     pub bio: Option<String>,
     pub profile_image: Option<String>,
@@ -138,15 +672,20 @@ pub struct UserResponse {
     pub followers_count: i32,
     pub following_count: i32,
     pub verified: bool,
+    pub is_bot: bool,
+    // Owner of a bot account ("automated by @owner")
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub automated_by: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub moved_to: Option<String>,
     pub created_at: DateTime<Utc>,
 }
 
-impl From<User> for UserResponse {
+impl From<User> for PublicUserResponse {
     fn from(user: User) -> Self {
-        UserResponse {
+        PublicUserResponse {
             id: user.id,
             username: user.username,
-            email: user.email,
             display_name: user.display_name,
             bio: user.bio,
             profile_image: user.profile_image,
@@ -154,20 +693,172 @@ impl From<User> for UserResponse {
             followers_count: user.followers_count,
             following_count: user.following_count,
             verified: user.verified,
+            is_bot: user.is_bot,
+            automated_by: user.automated_by,
+            moved_to: user.moved_to,
             created_at: user.created_at,
         }
     }
 }
 
+// A profile as shown on its page, with its pinned tweet (when the viewer can
+// see it) and the collections its owner features.
+#[derive(Debug, Serialize)]
+pub struct ProfileResponse {
+    #[serde(flatten)]
+    pub profile: PublicUserResponse,
+    pub pinned_tweet: Option<TweetResponse>,
+    pub featured_collections: Vec<CollectionSummary>,
+}
+
+// The signed-in user's own account: the public profile plus private fields.
+// Only returned to the account owner (auth and /me endpoints).
+#[derive(Debug, Serialize, Clone)]
+pub struct PrivateUserResponse {
+    #[serde(flatten)]
+    pub profile: PublicUserResponse,
+    pub email: String,
+    pub verified_email: bool,
+    pub role: String,
+    // Whether location tags on the account's tweets are shown
+    pub share_location: bool,
+    pub pinned_tweet_id: Option<Uuid>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub suspension: Option<Suspension>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct Suspension {
+    pub since: DateTime<Utc>,
+    // None while indefinite
+    pub until: Option<DateTime<Utc>>,
+    pub reason: Option<String>,
+}
+
+impl User {
+    // In force: set and not yet past `suspended_until`, which the scheduler
+    // may not have lifted yet
+    pub fn suspension(&self) -> Option<Suspension> {
+        let since = self.suspended_at?;
+        if self.suspended_until.is_some_and(|until| until <= Utc::now()) {
+            return None;
+        }
+        Some(Suspension {
+            since,
+            until: self.suspended_until,
+            reason: self.suspension_reason.clone(),
+        })
+    }
+}
+
+impl From<User> for PrivateUserResponse {
+    fn from(user: User) -> Self {
+        PrivateUserResponse {
+            email: user.email.0.clone(),
+            verified_email: user.verified_email,
+            role: user.role.clone(),
+            share_location: user.share_location,
+            pinned_tweet_id: user.pinned_tweet_id,
+            suspension: user.suspension(),
+            profile: user.into(),
+        }
+    }
+}
+
 #[derive(Debug, Serialize)]
 pub struct TweetResponse {
     pub id: Uuid,
+    pub parent_tweet_id: Option<Uuid>,
+    pub quoted_tweet_id: Option<Uuid>,
     pub content: String,
     pub image_url: Option<String>,
     pub likes_count: i32,
     pub retweets_count: i32,
     pub replies_count: i32,
+    pub visibility: String,
     pub created_at: DateTime<Utc>,
-    pub user: UserResponse,
+    pub user: PublicUserResponse,
     pub is_liked: bool,
+    pub is_retweeted: bool,
+}
+
+// A tweet permalink: the tweet plus, on request, its parent chain ordered
+// from the conversation root down to the direct parent
+#[derive(Debug, Serialize)]
+pub struct TweetDetailResponse {
+    pub tweet: TweetResponse,
+    // Share link, see `redirect_short_link`
+    pub short_url: String,
+    // Share card image, see `share_cards`
+    pub card_url: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ancestors: Option<Vec<TweetResponse>>,
+}
+
+#[derive(Debug, Serialize, FromRow)]
+pub struct TrendingHashtag {
+    pub tag: String,
+    pub tweet_count: i64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct NotificationResponse {
+    pub id: Uuid,
+    pub kind: String,
+    pub actor: PublicUserResponse,
+    pub tweet_id: Option<Uuid>,
+    pub read: bool,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct UnreadCountResponse {
+    pub unread: i64,
+}
+
+#[derive(Debug, Serialize, FromRow)]
+pub struct Announcement {
+    pub id: Uuid,
+    pub title: String,
+    pub body: String,
+    pub is_banner: bool,
+    pub starts_at: DateTime<Utc>,
+    pub ends_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub dismissed: bool,
+}
+
+#[derive(Debug, Serialize, FromRow)]
+pub struct AbuseEvent {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub username: String,
+    pub kind: String,
+    pub tweet_id: Option<Uuid>,
+    pub details: String,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, FromRow)]
+pub struct MaintenanceWindow {
+    pub id: Uuid,
+    pub reason: String,
+    pub starts_at: DateTime<Utc>,
+    pub ends_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct InstanceResponse<'a> {
+    #[serde(flatten)]
+    pub settings: &'a InstanceSettings,
+    pub read_only: bool,
+    pub maintenance: Vec<MaintenanceWindow>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ReplyNode {
+    #[serde(flatten)]
+    pub tweet: TweetResponse,
+    pub replies: Vec<ReplyNode>,
+    pub has_more: bool,
 }
@@ -0,0 +1,85 @@
+use sqlx::PgConnection;
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Copy)]
+pub enum Kind {
+    Like,
+    Reply,
+    Retweet,
+    Quote,
+    Follow,
+    CoauthorRequest,
+    CoauthorAccept,
+}
+
+impl Kind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Kind::Like => "like",
+            Kind::Reply => "reply",
+            Kind::Retweet => "retweet",
+            Kind::Quote => "quote",
+            Kind::Follow => "follow",
+            Kind::CoauthorRequest => "coauthor_request",
+            Kind::CoauthorAccept => "coauthor_accept",
+        }
+    }
+}
+
+// Notifies `user_id` that `actor_id` did something. Acting on your own
+// content never notifies you.
+pub async fn notify(
+    conn: &mut PgConnection,
+    user_id: Uuid,
+    actor_id: Uuid,
+    kind: Kind,
+    tweet_id: Option<Uuid>,
+) -> Result<(), sqlx::Error> {
+    if user_id == actor_id {
+        return Ok(());
+    }
+
+    sqlx::query("INSERT INTO notifications (user_id, actor_id, kind, tweet_id) VALUES ($1, $2, $3, $4)")
+        .bind(user_id)
+        .bind(actor_id)
+        .bind(kind.as_str())
+        .bind(tweet_id)
+        .execute(conn)
+        .await?;
+
+    Ok(())
+}
+
+// Notifies the author of `tweet_id`.
+pub async fn notify_author(conn: &mut PgConnection, tweet_id: Uuid, actor_id: Uuid, kind: Kind) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "INSERT INTO notifications (user_id, actor_id, kind, tweet_id)
+         SELECT user_id, $1, $2, id FROM tweets WHERE id = $3 AND user_id <> $1"
+    )
+    .bind(actor_id)
+    .bind(kind.as_str())
+    .bind(tweet_id)
+    .execute(conn)
+    .await?;
+
+    Ok(())
+}
+
+// Withdraws a still-unread notification when the action is undone (unlike,
+// unretweet, unfollow), so toggling doesn't pile up badges.
+pub async fn retract(conn: &mut PgConnection, actor_id: Uuid, kind: Kind, tweet_id: Option<Uuid>, user_id: Option<Uuid>) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "DELETE FROM notifications
+         WHERE actor_id = $1 AND kind = $2 AND read_at IS NULL
+           AND ($3::uuid IS NULL OR tweet_id = $3)
+           AND ($4::uuid IS NULL OR user_id = $4)"
+    )
+    .bind(actor_id)
+    .bind(kind.as_str())
+    .bind(tweet_id)
+    .bind(user_id)
+    .execute(conn)
+    .await?;
+
+    Ok(())
+}
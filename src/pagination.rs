@@ -0,0 +1,181 @@
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use chrono::{DateTime, Duration, Utc};
+use hmac::{Hmac, Mac};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use uuid::Uuid;
+
+use crate::config;
+use crate::secrets;
+
+pub const DEFAULT_PAGE_SIZE: i64 = 20;
+pub const MAX_PAGE_SIZE: i64 = 100;
+
+// Reply trees are expanded a few levels deep with a handful of children per
+// reply; clients expand further subtrees on demand.
+pub const DEFAULT_REPLY_DEPTH: usize = 3;
+pub const MAX_REPLY_DEPTH: usize = 10;
+pub const DEFAULT_REPLIES_PER_NODE: usize = 10;
+pub const MAX_REPLIES_PER_NODE: usize = 50;
+
+// Parent chain shown above a tweet's permalink. Deeper conversations are
+// walked further from the oldest ancestor's `parent_tweet_id`.
+pub const MAX_PARENT_CHAIN: i32 = 50;
+
+#[derive(Debug, Deserialize)]
+pub struct PageQuery {
+    pub cursor: Option<String>,
+    pub limit: Option<i64>,
+    // Only honoured by feeds that support snapshots (see `Snapshot`)
+    pub snapshot: Option<String>,
+    // Twitter v1.1 style bounds, honoured by the tweet timelines: only tweets
+    // newer than `since_id`, and none newer than `max_id` (inclusive). Both
+    // are tweet ids, placed by the tweet's (created_at, id) position.
+    pub since_id: Option<Uuid>,
+    pub max_id: Option<Uuid>,
+}
+
+impl PageQuery {
+    pub fn limit(&self) -> i64 {
+        self.limit.unwrap_or(DEFAULT_PAGE_SIZE).clamp(1, MAX_PAGE_SIZE)
+    }
+
+    pub fn cursor(&self, codec: &CursorCodec, scope: &str) -> Result<Option<Cursor>, String> {
+        self.cursor.as_deref().map(|raw| codec.decode(scope, raw)).transpose()
+    }
+
+    pub fn snapshot(&self, codec: &CursorCodec, scope: &str) -> Result<Option<Snapshot>, String> {
+        self.snapshot
+            .as_deref()
+            .map(|raw| codec.decode(scope, raw).map_err(|_| "Invalid snapshot".to_string()))
+            .transpose()
+    }
+}
+
+// Upper bound on created_at captured when a client loads the first page of a
+// fast-moving feed. Passing it back with every following page pins the scroll
+// session to that moment, so tweets that show up mid-scroll (including ones
+// committed late with an earlier timestamp) can't shift later pages.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub max_created_at: DateTime<Utc>,
+}
+
+// Position of the last item on a page. Lists are ordered by
+// (created_at DESC, id DESC), so the next page is everything strictly
+// before this pair and the id breaks ties between identical timestamps.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Cursor {
+    pub created_at: DateTime<Utc>,
+    pub id: Uuid,
+}
+
+impl Cursor {
+    // The exclusive bound that keeps this position itself in the page, for
+    // inclusive `max_id`: the next id up in Postgres' uuid order, rolling over
+    // to the next microsecond.
+    pub fn just_after(self) -> Cursor {
+        match self.id.as_u128().checked_add(1) {
+            Some(next) => Cursor { created_at: self.created_at, id: Uuid::from_u128(next) },
+            None => Cursor { created_at: self.created_at + Duration::microseconds(1), id: Uuid::nil() },
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct SignedCursor<P> {
+    position: P,
+    // The list the cursor was issued for, including its filters (e.g.
+    // "hashtag:rust"), so a cursor can't be replayed against another list
+    scope: String,
+    issued_at: i64,
+}
+
+// Cursors handed to clients are opaque: the position, scope and issue time are
+// serialized, base64url encoded and signed with HMAC-SHA256 as
+// `<payload>.<signature>`. Forged, edited or expired cursors are rejected.
+#[derive(Clone)]
+pub struct CursorCodec {
+    key: Vec<u8>,
+    ttl: Duration,
+}
+
+impl CursorCodec {
+    // CURSOR_SECRET defaults to the JWT secret; either way the key is
+    // domain-separated so a cursor signature is never a valid token signature.
+    pub fn from_env(jwt_secret: &str) -> Result<Self, String> {
+        let secret = secrets::var("CURSOR_SECRET").unwrap_or_else(|| jwt_secret.to_string());
+        let ttl_minutes = config::positive_var("CURSOR_TTL_MINUTES", 24 * 60)?;
+
+        Ok(CursorCodec {
+            key: format!("pagination-cursor:{}", secret).into_bytes(),
+            ttl: Duration::minutes(ttl_minutes),
+        })
+    }
+
+    pub fn encode<P: Serialize>(&self, scope: &str, position: P) -> String {
+        let payload = serde_json::to_vec(&SignedCursor {
+            position,
+            scope: scope.to_string(),
+            issued_at: Utc::now().timestamp(),
+        })
+        .expect("cursor payload serializes");
+        let payload = URL_SAFE_NO_PAD.encode(payload);
+        let signature = URL_SAFE_NO_PAD.encode(self.mac(payload.as_bytes()).finalize().into_bytes());
+
+        format!("{}.{}", payload, signature)
+    }
+
+    pub fn decode<P: DeserializeOwned>(&self, scope: &str, raw: &str) -> Result<P, String> {
+        let (payload, signature) = raw.split_once('.').ok_or("Invalid cursor")?;
+        let signature = URL_SAFE_NO_PAD.decode(signature).map_err(|_| "Invalid cursor")?;
+
+        // Constant-time comparison
+        self.mac(payload.as_bytes())
+            .verify_slice(&signature)
+            .map_err(|_| "Invalid cursor")?;
+
+        let payload = URL_SAFE_NO_PAD.decode(payload).map_err(|_| "Invalid cursor")?;
+        let cursor: SignedCursor<P> = serde_json::from_slice(&payload).map_err(|_| "Invalid cursor")?;
+
+        if cursor.scope != scope {
+            return Err("Cursor belongs to a different list".to_string());
+        }
+        if Utc::now().timestamp() - cursor.issued_at > self.ttl.num_seconds() {
+            return Err("Cursor has expired".to_string());
+        }
+
+        Ok(cursor.position)
+    }
+
+    fn mac(&self, payload: &[u8]) -> Hmac<Sha256> {
+        let mut mac = Hmac::<Sha256>::new_from_slice(&self.key).expect("HMAC accepts any key length");
+        mac.update(payload);
+        mac
+    }
+}
+
+// Trims a page fetched with `limit + 1` rows back to `limit` and returns the
+// cursor for the following page, if there is one.
+pub fn next_cursor<T>(
+    items: &mut Vec<T>,
+    limit: i64,
+    codec: &CursorCodec,
+    scope: &str,
+    key: impl Fn(&T) -> Cursor,
+) -> Option<String> {
+    if items.len() as i64 <= limit {
+        return None;
+    }
+
+    items.truncate(limit as usize);
+    items.last().map(|item| codec.encode(scope, key(item)))
+}
+
+// Ranked lists (e.g. replies ordered by score) have no stable keyset, so their
+// cursor position is an offset into the ranked list.
+pub fn decode_offset(codec: &CursorCodec, scope: &str, raw: &str) -> Result<usize, String> {
+    codec.decode(scope, raw)
+}
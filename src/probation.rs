@@ -0,0 +1,64 @@
+use chrono::{DateTime, Duration, Utc};
+
+use crate::config;
+use crate::error::ApiError;
+use crate::mentions;
+
+// Restrictions for brand-new accounts, consulted by the posting handlers.
+// Accounts younger than `account_age_hours` may only mention a few people
+// per tweet, and their tweets containing links are held for moderator review
+// instead of being published. An age of 0 turns probation off.
+#[derive(Debug, Clone)]
+pub struct ProbationPolicy {
+    pub account_age_hours: i64,
+    pub max_mentions: usize,
+    pub hold_links: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Verdict {
+    Publish,
+    Hold(&'static str),
+}
+
+impl ProbationPolicy {
+    pub fn from_env() -> Result<Self, String> {
+        Ok(ProbationPolicy {
+            account_age_hours: config::parse_var("PROBATION_HOURS", 72)?,
+            max_mentions: config::parse_var("PROBATION_MAX_MENTIONS", 3)?,
+            hold_links: config::flag("PROBATION_HOLD_LINKS", true)?,
+        })
+    }
+
+    pub fn on_probation(&self, account_created_at: DateTime<Utc>, now: DateTime<Utc>) -> bool {
+        now - account_created_at < Duration::hours(self.account_age_hours)
+    }
+
+    // Decides what happens to a tweet from an account created at
+    // `account_created_at`. Hard limits are errors; soft ones hold the tweet.
+    pub fn review_tweet(&self, account_created_at: DateTime<Utc>, content: &str) -> Result<Verdict, ApiError> {
+        if !self.on_probation(account_created_at, Utc::now()) {
+            return Ok(Verdict::Publish);
+        }
+
+        if mentions::extract(content).len() > self.max_mentions {
+            return Err(ApiError::Forbidden(format!(
+                "New accounts can mention at most {} users per tweet",
+                self.max_mentions
+            )));
+        }
+
+        if self.hold_links && contains_link(content) {
+            return Ok(Verdict::Hold("link from new account"));
+        }
+
+        Ok(Verdict::Publish)
+    }
+}
+
+fn contains_link(content: &str) -> bool {
+    content.split_whitespace().any(|word| {
+        let word = word.trim_start_matches(|c: char| !c.is_alphanumeric()).to_lowercase();
+        word.starts_with("http://") || word.starts_with("https://") || word.starts_with("www.")
+    })
+}
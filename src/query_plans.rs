@@ -0,0 +1,73 @@
+use actix_web::{web, HttpResponse};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::auth::AdminUser;
+use crate::error::{ApiError, ApiResult};
+use crate::models::{ApiResponse, ExplainQuery};
+use crate::pagination::DEFAULT_PAGE_SIZE;
+use crate::handlers::search;
+use crate::repo::postgres::TIMELINE_QUERY;
+use crate::AppState;
+
+// The plan endpoint only exists in debug builds: EXPLAIN ANALYZE executes the
+// query, and plans leak table sizes.
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    if cfg!(debug_assertions) {
+        cfg.route("/api/debug/explain", web::get().to(explain));
+    }
+}
+
+// Runs `EXPLAIN (ANALYZE, BUFFERS)` for a hot-path query with the same SQL
+// and parameters its handler uses, as the calling admin.
+async fn explain(
+    state: web::Data<AppState>,
+    AdminUser(admin_id): AdminUser,
+    query: web::Query<ExplainQuery>,
+) -> ApiResult<HttpResponse> {
+    let plan = match query.route.as_str() {
+        "timeline" => timeline_plan(&state.db, admin_id).await?,
+        "search" => {
+            let q = query.q.as_deref().unwrap_or_default().trim().trim_start_matches('@').to_lowercase();
+            if q.is_empty() {
+                return Err(ApiError::BadRequest("Search query is required".to_string()));
+            }
+            search_plan(&state.db, admin_id, &q).await?
+        }
+        _ => return Err(ApiError::BadRequest("route must be one of: timeline, search".to_string())),
+    };
+
+    Ok(HttpResponse::Ok().json(ApiResponse {
+        success: true,
+        data: Some(plan),
+        message: None,
+        next_cursor: None,
+    }))
+}
+
+async fn timeline_plan(db: &PgPool, viewer: Uuid) -> Result<serde_json::Value, sqlx::Error> {
+    sqlx::query_scalar::<_, serde_json::Value>(&explain_sql(TIMELINE_QUERY))
+        .bind(viewer)
+        .bind(None::<chrono::DateTime<chrono::Utc>>)
+        .bind(None::<Uuid>)
+        .bind(DEFAULT_PAGE_SIZE + 1)
+        .bind(None::<chrono::DateTime<chrono::Utc>>)
+        .bind(None::<chrono::DateTime<chrono::Utc>>)
+        .bind(None::<Uuid>)
+        .fetch_one(db)
+        .await
+}
+
+async fn search_plan(db: &PgPool, viewer: Uuid, q: &str) -> Result<serde_json::Value, sqlx::Error> {
+    sqlx::query_scalar::<_, serde_json::Value>(&explain_sql(search::USER_SEARCH_QUERY))
+        .bind(q)
+        .bind(search::like_prefix(q))
+        .bind(Some(viewer))
+        .bind(10_i64)
+        .fetch_one(db)
+        .await
+}
+
+fn explain_sql(query: &str) -> String {
+    format!("EXPLAIN (ANALYZE, BUFFERS, FORMAT JSON) {}", query)
+}
@@ -0,0 +1,128 @@
+use chrono::{DateTime, Utc};
+
+use crate::config;
+
+// Engagement scoring shared by every feature that orders tweets by "best"
+// rather than by time. Raw engagement is weighted per signal and then
+// decayed exponentially with age, so a tweet loses half of its score every
+// `half_life_hours`.
+#[derive(Debug, Clone)]
+pub struct RankingWeights {
+    pub like: f64,
+    pub retweet: f64,
+    pub reply: f64,
+    pub half_life_hours: f64,
+}
+
+impl Default for RankingWeights {
+    fn default() -> Self {
+        RankingWeights {
+            like: 1.0,
+            retweet: 2.0,
+            reply: 1.5,
+            half_life_hours: 24.0,
+        }
+    }
+}
+
+impl RankingWeights {
+    pub fn from_env() -> Result<Self, String> {
+        let defaults = RankingWeights::default();
+
+        Ok(RankingWeights {
+            like: weight("RANKING_LIKE_WEIGHT", defaults.like)?,
+            retweet: weight("RANKING_RETWEET_WEIGHT", defaults.retweet)?,
+            reply: weight("RANKING_REPLY_WEIGHT", defaults.reply)?,
+            half_life_hours: weight("RANKING_HALF_LIFE_HOURS", defaults.half_life_hours)?,
+        })
+    }
+
+    // Multiplier in (0, 1] for a tweet created at `created_at`. Timestamps in
+    // the future (clock skew) are treated as brand new.
+    pub fn decay(&self, created_at: DateTime<Utc>, now: DateTime<Utc>) -> f64 {
+        let age_hours = (now - created_at).num_seconds().max(0) as f64 / 3600.0;
+        0.5_f64.powf(age_hours / self.half_life_hours)
+    }
+
+    pub fn score(
+        &self,
+        likes: i32,
+        retweets: i32,
+        replies: i32,
+        created_at: DateTime<Utc>,
+        now: DateTime<Utc>,
+    ) -> f64 {
+        // +1 so fresh tweets without engagement still order by recency
+        let engagement = 1.0
+            + self.like * likes.max(0) as f64
+            + self.retweet * retweets.max(0) as f64
+            + self.reply * replies.max(0) as f64;
+
+        engagement * self.decay(created_at, now)
+    }
+}
+
+fn weight(key: &str, default: f64) -> Result<f64, String> {
+    let value: f64 = config::parse_var(key, default)?;
+    if !value.is_finite() || value < 0.0 {
+        return Err(format!("{} must be a non-negative number", key));
+    }
+    Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    fn weights() -> RankingWeights {
+        RankingWeights {
+            like: 1.0,
+            retweet: 2.0,
+            reply: 1.5,
+            half_life_hours: 10.0,
+        }
+    }
+
+    #[test]
+    fn decay_is_one_for_new_tweets() {
+        let now = Utc::now();
+        assert_eq!(weights().decay(now, now), 1.0);
+    }
+
+    #[test]
+    fn decay_halves_every_half_life() {
+        let now = Utc::now();
+        let w = weights();
+        assert!((w.decay(now - Duration::hours(10), now) - 0.5).abs() < 1e-9);
+        assert!((w.decay(now - Duration::hours(20), now) - 0.25).abs() < 1e-9);
+        assert!((w.decay(now - Duration::hours(5), now) - 0.5_f64.sqrt()).abs() < 1e-9);
+    }
+
+    #[test]
+    fn decay_clamps_future_timestamps() {
+        let now = Utc::now();
+        assert_eq!(weights().decay(now + Duration::hours(3), now), 1.0);
+    }
+
+    #[test]
+    fn score_weights_each_signal() {
+        let now = Utc::now();
+        let w = weights();
+        assert_eq!(w.score(0, 0, 0, now, now), 1.0);
+        assert_eq!(w.score(2, 0, 0, now, now), 3.0);
+        assert_eq!(w.score(0, 2, 0, now, now), 5.0);
+        assert_eq!(w.score(0, 0, 2, now, now), 4.0);
+        assert_eq!(w.score(-5, 0, 0, now, now), 1.0);
+    }
+
+    #[test]
+    fn older_tweets_need_more_engagement() {
+        let now = Utc::now();
+        let w = weights();
+        let fresh = w.score(3, 0, 0, now, now);
+        let stale = w.score(3, 0, 0, now - Duration::hours(10), now);
+        assert_eq!(stale, fresh / 2.0);
+        assert!(w.score(8, 0, 0, now - Duration::hours(10), now) > fresh);
+    }
+}
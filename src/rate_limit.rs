@@ -0,0 +1,272 @@
+use actix_web::body::{EitherBody, MessageBody};
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::http::header::{HeaderValue, RETRY_AFTER};
+use actix_web::http::Method;
+use actix_web::middleware::Next;
+use actix_web::{web, Error};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::auth;
+use crate::config;
+use crate::error::ApiError;
+use crate::response_meta::{self, RateLimitMeta};
+use crate::AppState;
+
+// In-memory token buckets for the endpoints worth abusing: the auth routes
+// (keyed by client IP, since there may be no account yet) and the write routes
+// for tweets, likes, retweets, follows, blocks, reports and reported views
+// (keyed by the signed-in user, or by IP when the request carries no valid
+// token). Bot accounts have a separate, stricter write budget. Limits are per
+// process, so with several instances each one enforces its own budget.
+// Limited responses report what's left in their `meta`, with a warning once
+// it runs low.
+#[derive(Clone)]
+pub struct RateLimiter {
+    auth: Option<Limit>,
+    write: Option<Limit>,
+    bot_write: Option<Limit>,
+    // Client IPs come from X-Forwarded-For / Forwarded only behind a proxy
+    // we trust to set them; otherwise anyone could pick their own bucket
+    trust_proxy: bool,
+    buckets: Arc<Mutex<HashMap<(Scope, String), Bucket>>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Scope {
+    Auth,
+    Write,
+    BotWrite,
+}
+
+// `per_minute` is the sustained rate, `burst` how many requests may arrive
+// back to back after a quiet spell.
+#[derive(Debug, Clone, Copy)]
+struct Limit {
+    per_minute: u32,
+    burst: u32,
+}
+
+impl Limit {
+    fn from_env(prefix: &str, per_minute: u32, burst: u32) -> Result<Option<Self>, String> {
+        let per_minute = config::parse_var(&format!("{}_PER_MINUTE", prefix), per_minute)?;
+        let burst = config::parse_var(&format!("{}_BURST", prefix), burst)?;
+        // A rate of 0 turns the limit off
+        Ok((per_minute > 0).then(|| Limit { per_minute, burst: burst.max(1) }))
+    }
+
+    fn refill_per_sec(&self) -> f64 {
+        self.per_minute as f64 / 60.0
+    }
+
+    // How long an untouched bucket takes to fill back up; after that it's
+    // indistinguishable from a fresh one and can be dropped
+    fn full_after(&self) -> Duration {
+        Duration::from_secs_f64(self.burst as f64 / self.refill_per_sec())
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+impl Bucket {
+    fn full(limit: &Limit, now: Instant) -> Self {
+        Bucket { tokens: limit.burst as f64, updated: now }
+    }
+
+    // Takes one token and returns how many whole ones are left, or returns
+    // how long until one is available.
+    fn take(&mut self, limit: &Limit, now: Instant) -> Result<u32, Duration> {
+        let elapsed = now.saturating_duration_since(self.updated).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * limit.refill_per_sec()).min(limit.burst as f64);
+        self.updated = now;
+
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            Ok(self.tokens as u32)
+        } else {
+            Err(Duration::from_secs_f64((1.0 - self.tokens) / limit.refill_per_sec()))
+        }
+    }
+}
+
+impl RateLimiter {
+    pub fn from_env() -> Result<Self, String> {
+        Ok(RateLimiter {
+            auth: Limit::from_env("RATE_LIMIT_AUTH", 10, 5)?,
+            write: Limit::from_env("RATE_LIMIT_WRITE", 60, 20)?,
+            bot_write: Limit::from_env("RATE_LIMIT_BOT_WRITE", 20, 5)?,
+            trust_proxy: config::flag("RATE_LIMIT_TRUST_PROXY", false)?,
+            buckets: Arc::new(Mutex::new(HashMap::new())),
+        })
+    }
+
+    fn limit(&self, scope: Scope) -> Option<&Limit> {
+        match scope {
+            Scope::Auth => self.auth.as_ref(),
+            Scope::Write => self.write.as_ref(),
+            Scope::BotWrite => self.bot_write.as_ref(),
+        }
+    }
+
+    fn check(&self, scope: Scope, key: String, now: Instant) -> Result<Option<RateLimitMeta>, Duration> {
+        let Some(limit) = self.limit(scope) else {
+            return Ok(None);
+        };
+
+        let mut buckets = self.buckets.lock().unwrap();
+        buckets
+            .entry((scope, key))
+            .or_insert_with(|| Bucket::full(limit, now))
+            .take(limit, now)
+            .map(|remaining| Some(RateLimitMeta { limit: limit.burst, remaining }))
+    }
+
+    // Run by the scheduler: forgets buckets that have refilled completely.
+    pub fn prune(&self) {
+        let now = Instant::now();
+        let mut buckets = self.buckets.lock().unwrap();
+        buckets.retain(|(scope, _), bucket| {
+            self.limit(*scope)
+                .is_some_and(|limit| now.saturating_duration_since(bucket.updated) < limit.full_after())
+        });
+    }
+}
+
+fn classify(method: &Method, path: &str) -> Option<Scope> {
+    let segments: Vec<&str> = path.trim_matches('/').split('/').collect();
+
+    match (method, segments.as_slice()) {
+        (&Method::POST, ["api", "auth", "register" | "login" | "refresh" | "forgot-password" | "reset-password"]) => Some(Scope::Auth),
+        (&Method::PUT, ["api", "auth", "password" | "email"]) => Some(Scope::Auth),
+        (&Method::POST, ["api", "auth", "email", "confirm"] | ["api", "auth", "verify", "resend"]) => Some(Scope::Auth),
+        (&Method::POST, ["api", "tweets"] | ["2", "tweets"]) => Some(Scope::Write),
+        (&Method::POST, ["api", "tweets", "impressions"]) => Some(Scope::Write),
+        (&Method::POST, ["api", "tweets", _, "like" | "retweet" | "quote" | "report"]) => Some(Scope::Write),
+        (&Method::DELETE, ["api", "tweets", _, "unlike" | "unretweet"]) => Some(Scope::Write),
+        (&Method::POST, ["api", "users", _, "follow" | "block" | "report"]) => Some(Scope::Write),
+        (&Method::DELETE, ["api", "users", _, "unfollow" | "unblock"]) => Some(Scope::Write),
+        _ => None,
+    }
+}
+
+pub async fn throttle<B: MessageBody + 'static>(
+    req: ServiceRequest,
+    next: Next<B>,
+) -> Result<ServiceResponse<EitherBody<B>>, Error> {
+    let Some(state) = req.app_data::<web::Data<AppState>>().cloned() else {
+        return next.call(req).await.map(ServiceResponse::map_into_left_body);
+    };
+    let Some(scope) = classify(req.method(), req.path()) else {
+        return next.call(req).await.map(ServiceResponse::map_into_left_body);
+    };
+
+    let limiter = &state.rate_limits;
+    let claims = match scope {
+        Scope::Auth => None,
+        Scope::Write | Scope::BotWrite => auth::request_claims(req.request()).ok(),
+    };
+    let scope = match &claims {
+        Some(claims) if claims.bot && scope == Scope::Write => Scope::BotWrite,
+        _ => scope,
+    };
+    let key = match claims {
+        Some(claims) => format!("user:{}", claims.sub),
+        None => {
+            let conn = req.connection_info();
+            let ip = if limiter.trust_proxy { conn.realip_remote_addr() } else { conn.peer_addr() };
+            format!("ip:{}", ip.unwrap_or("unknown"))
+        }
+    };
+
+    match limiter.check(scope, key, Instant::now()) {
+        Ok(Some(status)) => response_meta::update(|meta| {
+            if running_low(status) {
+                meta.warnings.push(format!(
+                    "{} of {} requests left before this route is rate limited",
+                    status.remaining, status.limit
+                ));
+            }
+            meta.rate_limit = Some(status);
+        }),
+        Ok(None) => {}
+        Err(wait) => {
+            if let Some(limit) = limiter.limit(scope) {
+                response_meta::update(|meta| meta.rate_limit = Some(RateLimitMeta { limit: limit.burst, remaining: 0 }));
+            }
+            let retry_after = wait.as_secs_f64().ceil().max(1.0) as u64;
+            let error = ApiError::TooManyRequests(format!("Too many requests, try again in {} seconds", retry_after));
+            let mut res = req.error_response(error);
+            res.headers_mut().insert(RETRY_AFTER, HeaderValue::from(retry_after));
+            return Ok(res.map_into_right_body());
+        }
+    }
+
+    next.call(req).await.map(ServiceResponse::map_into_left_body)
+}
+
+// A quarter of the burst or less is worth a warning
+fn running_low(status: RateLimitMeta) -> bool {
+    status.remaining * 4 <= status.limit
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limit() -> Limit {
+        Limit { per_minute: 60, burst: 3 }
+    }
+
+    #[test]
+    fn bucket_allows_burst_then_refuses() {
+        let now = Instant::now();
+        let mut bucket = Bucket::full(&limit(), now);
+        for _ in 0..3 {
+            assert!(bucket.take(&limit(), now).is_ok());
+        }
+        assert_eq!(bucket.take(&limit(), now), Err(Duration::from_secs(1)));
+    }
+
+    #[test]
+    fn reports_remaining_and_warns_when_low() {
+        let now = Instant::now();
+        let mut bucket = Bucket::full(&limit(), now);
+        assert_eq!(bucket.take(&limit(), now), Ok(2));
+        assert!(!running_low(RateLimitMeta { limit: 20, remaining: 6 }));
+        assert!(running_low(RateLimitMeta { limit: 20, remaining: 5 }));
+        assert!(running_low(RateLimitMeta { limit: 1, remaining: 0 }));
+    }
+
+    #[test]
+    fn bucket_refills_over_time_up_to_burst() {
+        let now = Instant::now();
+        let mut bucket = Bucket { tokens: 0.0, updated: now };
+        assert!(bucket.take(&limit(), now + Duration::from_millis(1500)).is_ok());
+        assert!(bucket.take(&limit(), now + Duration::from_millis(1500)).is_err());
+
+        let later = now + Duration::from_secs(600);
+        for _ in 0..3 {
+            assert!(bucket.take(&limit(), later).is_ok());
+        }
+        assert!(bucket.take(&limit(), later).is_err());
+    }
+
+    #[test]
+    fn classifies_limited_routes() {
+        assert_eq!(classify(&Method::POST, "/api/auth/login"), Some(Scope::Auth));
+        assert_eq!(classify(&Method::POST, "/api/auth/register"), Some(Scope::Auth));
+        assert_eq!(classify(&Method::POST, "/api/tweets"), Some(Scope::Write));
+        assert_eq!(classify(&Method::POST, "/api/tweets/abc/like"), Some(Scope::Write));
+        assert_eq!(classify(&Method::POST, "/api/tweets/impressions"), Some(Scope::Write));
+        assert_eq!(classify(&Method::DELETE, "/api/users/bob/unfollow"), Some(Scope::Write));
+        assert_eq!(classify(&Method::POST, "/api/users/bob/report"), Some(Scope::Write));
+        assert_eq!(classify(&Method::GET, "/api/tweets/abc"), None);
+        assert_eq!(classify(&Method::GET, "/api/auth/me"), None);
+        assert_eq!(classify(&Method::POST, "/api/auth/logout"), None);
+    }
+}
@@ -0,0 +1,491 @@
+use actix_ws::{Message, MessageStream, Session};
+use serde::Deserialize;
+use serde_json::json;
+use sqlx::postgres::PgListener;
+use sqlx::PgPool;
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::broadcast;
+use uuid::Uuid;
+
+use crate::drafts::{self, SaveOutcome};
+use crate::error::ApiError;
+use crate::models::{Notification, NotificationResponse, TweetWithUser, User};
+
+const CHANNEL: &str = "realtime";
+const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(30);
+const CLIENT_TIMEOUT: Duration = Duration::from_secs(75);
+const RECONNECT_DELAY: Duration = Duration::from_secs(5);
+// Drafts a session holds between autosaves
+const MAX_PENDING_DRAFTS: usize = 10;
+
+// Real-time push. Database triggers publish inserts on the `realtime`
+// channel; a single listener turns them into ready-to-send JSON messages and
+// fans them out to every WebSocket session, which forwards what's relevant to
+// its user. Because events come from Postgres, writes made by any server
+// instance reach every connected client.
+//
+// Clients can also send drafts on the socket: `{"type": "draft", "id",
+// "base_version", "content"}`. The latest text per draft is written every
+// few seconds and when the socket closes. A saved draft comes back to all
+// the user's sessions as a `draft` message; a conflict with another device
+// is answered with `draft_conflict` and the stored draft (see `drafts`).
+#[derive(Clone)]
+pub struct Hub {
+    sender: broadcast::Sender<Event>,
+}
+
+#[derive(Debug, Clone)]
+pub enum Event {
+    // `circle` is set for circle tweets: only those accounts (and the author)
+    // may receive it. `text` is the content lower-cased, for muted keywords.
+    Tweet { author_id: Uuid, circle: Option<Arc<HashSet<Uuid>>>, text: Arc<String>, message: Arc<String> },
+    // `text` as for tweets, when the notification is about one
+    Notification { user_id: Uuid, actor_id: Uuid, text: Option<Arc<String>>, message: Arc<String> },
+    Follow { follower_id: Uuid, following_id: Uuid, active: bool },
+    Mute { muter_id: Uuid, muted_id: Uuid, active: bool },
+    MutedKeyword { user_id: Uuid, keyword: Arc<String>, active: bool },
+    Draft { user_id: Uuid, message: Arc<String> },
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum DbEvent {
+    Tweet { id: Uuid, user_id: Uuid },
+    Notification { id: Uuid, user_id: Uuid },
+    Follow { follower_id: Uuid, following_id: Uuid },
+    Unfollow { follower_id: Uuid, following_id: Uuid },
+    Mute { muter_id: Uuid, muted_id: Uuid },
+    Unmute { muter_id: Uuid, muted_id: Uuid },
+    MutedKeyword { user_id: Uuid, keyword: String },
+    UnmutedKeyword { user_id: Uuid, keyword: String },
+    Draft { id: Uuid, user_id: Uuid },
+    DraftDeleted { id: Uuid, user_id: Uuid },
+}
+
+// Messages clients send on the socket
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum ClientMessage {
+    Draft { id: Uuid, base_version: i64, content: String },
+}
+
+struct PendingDraft {
+    base_version: i64,
+    content: String,
+}
+
+impl Default for Hub {
+    fn default() -> Self {
+        let (sender, _) = broadcast::channel(1024);
+        Hub { sender }
+    }
+}
+
+impl Hub {
+    pub fn subscribe(&self) -> broadcast::Receiver<Event> {
+        self.sender.subscribe()
+    }
+
+    // Listens for database events for the lifetime of the server,
+    // reconnecting if the listener connection drops. Runs as a plain Tokio
+    // task rather than on the actix system's local set: local tasks are
+    // dropped after the runtime has shut down, and dropping a live PgListener
+    // outside a runtime panics and aborts shutdown.
+    pub fn spawn_listener(&self, db: PgPool) {
+        let hub = self.clone();
+        tokio::spawn(async move {
+            loop {
+                if let Err(e) = hub.listen(&db).await {
+                    log::error!("realtime listener failed: {}", e);
+                }
+                actix_web::rt::time::sleep(RECONNECT_DELAY).await;
+            }
+        });
+    }
+
+    async fn listen(&self, db: &PgPool) -> Result<(), sqlx::Error> {
+        let mut listener = PgListener::connect_with(db).await?;
+        listener.listen(CHANNEL).await?;
+
+        loop {
+            let notification = listener.recv().await?;
+
+            // No subscribers means nobody to enrich events for
+            if self.sender.receiver_count() == 0 {
+                continue;
+            }
+
+            let event = match serde_json::from_str::<DbEvent>(notification.payload()) {
+                Ok(event) => event,
+                Err(e) => {
+                    log::warn!("unexpected realtime payload: {}", e);
+                    continue;
+                }
+            };
+
+            if let Some(event) = enrich(db, event).await? {
+                let _ = self.sender.send(event);
+            }
+        }
+    }
+}
+
+async fn enrich(db: &PgPool, event: DbEvent) -> Result<Option<Event>, sqlx::Error> {
+    match event {
+        DbEvent::Tweet { id, user_id } => {
+            let tweet = sqlx::query_as::<_, TweetWithUser>(
+                "SELECT t.id, t.user_id, t.parent_tweet_id, t.quoted_tweet_id, t.content, t.image_url, t.likes_count, t.retweets_count, 
+                        t.replies_count, t.visibility, t.created_at,
+                        u.username as user_username, u.display_name as user_display_name, 
+                        u.bio as user_bio, 
+                        u.profile_image as user_profile_image, u.banner_image as user_banner_image,
+                        u.followers_count as user_followers_count, u.following_count as user_following_count,
+                        u.verified as user_verified, u.is_bot as user_is_bot, u.created_at as user_created_at
+                 FROM tweets t
+                 INNER JOIN users u ON t.user_id = u.id
+                 WHERE t.id = $1 AND u.deactivated_at IS NULL AND t.deleted_at IS NULL"
+            )
+            .bind(id)
+            .fetch_optional(db)
+            .await?;
+
+            let Some(tweet) = tweet else {
+                return Ok(None);
+            };
+
+            // Followers-only tweets need no extra filtering: sessions only
+            // forward tweets from accounts their user follows
+            let circle = if tweet.visibility == "circle" {
+                let members = sqlx::query_scalar::<_, Uuid>("SELECT member_id FROM circle_members WHERE owner_id = $1")
+                    .bind(user_id)
+                    .fetch_all(db)
+                    .await?;
+                Some(Arc::new(members.into_iter().chain(std::iter::once(user_id)).collect()))
+            } else {
+                None
+            };
+
+            Ok(Some(Event::Tweet {
+                author_id: user_id,
+                circle,
+                text: Arc::new(tweet.content.to_lowercase()),
+                message: Arc::new(json!({ "type": "tweet", "data": tweet.into_response(false, false) }).to_string()),
+            }))
+        }
+        DbEvent::Notification { id, user_id } => {
+            let Some(notification) = sqlx::query_as::<_, Notification>(
+                "SELECT id, actor_id, kind, tweet_id, read_at, created_at FROM notifications WHERE id = $1"
+            )
+            .bind(id)
+            .fetch_optional(db)
+            .await?
+            else {
+                return Ok(None);
+            };
+
+            let Some(actor) = sqlx::query_as::<_, User>("SELECT * FROM users WHERE id = $1")
+                .bind(notification.actor_id)
+                .fetch_optional(db)
+                .await?
+            else {
+                return Ok(None);
+            };
+
+            let text = match notification.tweet_id {
+                Some(tweet_id) => sqlx::query_scalar::<_, String>("SELECT content FROM tweets WHERE id = $1")
+                    .bind(tweet_id)
+                    .fetch_optional(db)
+                    .await?
+                    .map(|content| Arc::new(content.to_lowercase())),
+                None => None,
+            };
+
+            let data = NotificationResponse {
+                id: notification.id,
+                kind: notification.kind,
+                actor: actor.into(),
+                tweet_id: notification.tweet_id,
+                read: notification.read_at.is_some(),
+                created_at: notification.created_at,
+            };
+
+            Ok(Some(Event::Notification {
+                user_id,
+                actor_id: notification.actor_id,
+                text,
+                message: Arc::new(json!({ "type": "notification", "data": data }).to_string()),
+            }))
+        }
+        DbEvent::Follow { follower_id, following_id } => Ok(Some(Event::Follow { follower_id, following_id, active: true })),
+        DbEvent::Unfollow { follower_id, following_id } => Ok(Some(Event::Follow { follower_id, following_id, active: false })),
+        DbEvent::Mute { muter_id, muted_id } => Ok(Some(Event::Mute { muter_id, muted_id, active: true })),
+        DbEvent::Unmute { muter_id, muted_id } => Ok(Some(Event::Mute { muter_id, muted_id, active: false })),
+        DbEvent::MutedKeyword { user_id, keyword } => Ok(Some(Event::MutedKeyword { user_id, keyword: Arc::new(keyword), active: true })),
+        DbEvent::UnmutedKeyword { user_id, keyword } => Ok(Some(Event::MutedKeyword { user_id, keyword: Arc::new(keyword), active: false })),
+        DbEvent::Draft { id, user_id } => {
+            let Some(draft) = drafts::fetch(db, user_id, id).await? else {
+                return Ok(None);
+            };
+            Ok(Some(Event::Draft {
+                user_id,
+                message: Arc::new(json!({ "type": "draft", "data": draft }).to_string()),
+            }))
+        }
+        DbEvent::DraftDeleted { id, user_id } => Ok(Some(Event::Draft {
+            user_id,
+            message: Arc::new(json!({ "type": "draft_deleted", "data": { "id": id } }).to_string()),
+        })),
+    }
+}
+
+// The version to save a draft on top of. While the client hasn't yet heard
+// back about this session's own saves it keeps sending an older base; any
+// base from the run of versions this session wrote (`own`: the base it
+// started from and its latest save) means the client's text follows on from
+// them, so it's saved on the latest. Another device's save breaks the run
+// with a conflict.
+fn rebase(own: Option<(i64, i64)>, base_version: i64) -> i64 {
+    match own {
+        Some((first, latest)) if (first..=latest).contains(&base_version) => latest,
+        _ => base_version,
+    }
+}
+
+// Writes the pending drafts. Failed writes are kept for the next round unless
+// newer text arrived meanwhile; a conflict or a rejected draft is reported to
+// the client. Returns false once the client is gone.
+async fn save_drafts(
+    db: &PgPool,
+    session: &mut Session,
+    user_id: Uuid,
+    device: Option<&str>,
+    pending: &mut HashMap<Uuid, PendingDraft>,
+    own: &mut HashMap<Uuid, (i64, i64)>,
+) -> bool {
+    for (id, draft) in std::mem::take(pending) {
+        let run = own.get(&id).copied();
+        let base_version = rebase(run, draft.base_version);
+        let reply = match drafts::save(db, user_id, id, base_version, &draft.content, device).await {
+            Ok(SaveOutcome::Saved(saved)) => {
+                let first = match run {
+                    Some((first, latest)) if (first..=latest).contains(&draft.base_version) => first,
+                    _ => draft.base_version,
+                };
+                own.insert(id, (first, saved.version));
+                continue;
+            }
+            Ok(SaveOutcome::Conflict(current)) => {
+                own.remove(&id);
+                json!({ "type": "draft_conflict", "data": current })
+            }
+            Err(ApiError::Db(e)) => {
+                log::warn!("autosave of draft {} failed: {}", id, e);
+                pending.entry(id).or_insert(draft);
+                continue;
+            }
+            Err(e) => json!({ "type": "draft_error", "data": { "id": id, "message": e.to_string() } }),
+        };
+        if session.text(reply.to_string()).await.is_err() {
+            return false;
+        }
+    }
+    true
+}
+
+// Accounts and keywords the user muted; what they hide from the timeline
+// and the notification list isn't pushed either
+#[derive(Default)]
+struct Muted {
+    accounts: HashSet<Uuid>,
+    keywords: HashSet<Arc<String>>,
+}
+
+impl Muted {
+    async fn load(db: &PgPool, user_id: Uuid) -> Result<Self, sqlx::Error> {
+        let accounts = sqlx::query_scalar::<_, Uuid>("SELECT muted_id FROM mutes WHERE muter_id = $1")
+            .bind(user_id)
+            .fetch_all(db)
+            .await?;
+        let keywords = sqlx::query_scalar::<_, String>("SELECT keyword FROM muted_keywords WHERE user_id = $1")
+            .bind(user_id)
+            .fetch_all(db)
+            .await?;
+        Ok(Muted {
+            accounts: accounts.into_iter().collect(),
+            keywords: keywords.into_iter().map(Arc::new).collect(),
+        })
+    }
+
+    // `text` is lower-cased; keywords are stored that way
+    fn hides(&self, account_id: Uuid, text: Option<&str>) -> bool {
+        self.accounts.contains(&account_id)
+            || text.is_some_and(|text| self.keywords.iter().any(|keyword| text.contains(keyword.as_str())))
+    }
+}
+
+// Drives one WebSocket connection: forwards new tweets from accounts the user
+// follows (and their own) plus their notifications and drafts, leaving out
+// what the user muted, autosaves the
+// drafts the client sends, and pings the client so dead connections are
+// dropped. `device` labels this session's draft saves.
+pub async fn run_session(
+    db: PgPool,
+    mut events: broadcast::Receiver<Event>,
+    mut session: Session,
+    mut stream: MessageStream,
+    user_id: Uuid,
+    device: Option<String>,
+) {
+    let mut following: HashSet<Uuid> = sqlx::query_scalar::<_, Uuid>("SELECT following_id FROM follows WHERE follower_id = $1")
+        .bind(user_id)
+        .fetch_all(&db)
+        .await
+        .unwrap_or_default()
+        .into_iter()
+        .collect();
+    following.insert(user_id);
+    let mut muted = Muted::load(&db, user_id).await.unwrap_or_default();
+
+    let mut heartbeat = actix_web::rt::time::interval(HEARTBEAT_INTERVAL);
+    let mut autosave = actix_web::rt::time::interval(drafts::AUTOSAVE_INTERVAL);
+    let mut last_seen = Instant::now();
+    let mut pending: HashMap<Uuid, PendingDraft> = HashMap::new();
+    let mut own_saves: HashMap<Uuid, (i64, i64)> = HashMap::new();
+
+    loop {
+        tokio::select! {
+            _ = heartbeat.tick() => {
+                if last_seen.elapsed() > CLIENT_TIMEOUT || session.ping(b"").await.is_err() {
+                    break;
+                }
+            }
+            _ = autosave.tick(), if !pending.is_empty() => {
+                if !save_drafts(&db, &mut session, user_id, device.as_deref(), &mut pending, &mut own_saves).await {
+                    break;
+                }
+            }
+            message = stream.recv() => {
+                last_seen = Instant::now();
+                match message {
+                    Some(Ok(Message::Ping(bytes))) => {
+                        if session.pong(&bytes).await.is_err() {
+                            break;
+                        }
+                    }
+                    Some(Ok(Message::Text(text))) => {
+                        let Ok(ClientMessage::Draft { id, base_version, content }) = serde_json::from_str(&text) else {
+                            continue;
+                        };
+                        let rejected = match drafts::check_content(&content) {
+                            Err(e) => Some(e.to_string()),
+                            Ok(()) if pending.len() >= MAX_PENDING_DRAFTS && !pending.contains_key(&id) => {
+                                Some("Too many drafts at once".to_string())
+                            }
+                            Ok(()) => None,
+                        };
+                        match rejected {
+                            Some(message) => {
+                                let reply = json!({ "type": "draft_error", "data": { "id": id, "message": message } });
+                                if session.text(reply.to_string()).await.is_err() {
+                                    break;
+                                }
+                            }
+                            None => {
+                                pending.insert(id, PendingDraft { base_version, content });
+                            }
+                        }
+                    }
+                    Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                    Some(Ok(_)) => {}
+                }
+            }
+            event = events.recv() => {
+                let message = match event {
+                    Ok(Event::Tweet { author_id, circle, text, message })
+                        if following.contains(&author_id)
+                            && circle.as_ref().is_none_or(|members| members.contains(&user_id))
+                            && !muted.hides(author_id, Some(&text)) =>
+                    {
+                        message
+                    }
+                    Ok(Event::Notification { user_id: recipient, actor_id, text, message })
+                        if recipient == user_id && !muted.hides(actor_id, text.as_deref().map(String::as_str)) =>
+                    {
+                        message
+                    }
+                    Ok(Event::Draft { user_id: owner, message }) if owner == user_id => message,
+                    Ok(Event::Follow { follower_id, following_id, active }) if follower_id == user_id => {
+                        if active {
+                            following.insert(following_id);
+                        } else if following_id != user_id {
+                            following.remove(&following_id);
+                        }
+                        continue;
+                    }
+                    Ok(Event::Mute { muter_id, muted_id, active }) if muter_id == user_id => {
+                        if active {
+                            muted.accounts.insert(muted_id);
+                        } else {
+                            muted.accounts.remove(&muted_id);
+                        }
+                        continue;
+                    }
+                    Ok(Event::MutedKeyword { user_id: owner, keyword, active }) if owner == user_id => {
+                        if active {
+                            muted.keywords.insert(keyword);
+                        } else {
+                            muted.keywords.remove(&keyword);
+                        }
+                        continue;
+                    }
+                    Ok(_) | Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                    Err(broadcast::error::RecvError::Closed) => break,
+                };
+
+                if session.text(message.as_str().to_owned()).await.is_err() {
+                    break;
+                }
+            }
+        }
+    }
+
+    // Whatever the client sent last is kept even if it went away mid-edit
+    if !pending.is_empty() {
+        save_drafts(&db, &mut session, user_id, device.as_deref(), &mut pending, &mut own_saves).await;
+    }
+
+    let _ = session.close(None).await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hides_muted_accounts_and_keywords() {
+        let (muted_account, other) = (Uuid::new_v4(), Uuid::new_v4());
+        let muted = Muted {
+            accounts: HashSet::from([muted_account]),
+            keywords: HashSet::from([Arc::new("spoiler".to_string())]),
+        };
+        assert!(muted.hides(muted_account, None));
+        assert!(muted.hides(other, Some("big spoilers ahead")));
+        assert!(!muted.hides(other, Some("nothing to see")));
+        assert!(!muted.hides(other, None));
+    }
+
+    #[test]
+    fn rebases_onto_the_sessions_own_saves() {
+        // Nothing saved yet: the client's base is used as is
+        assert_eq!(rebase(None, 3), 3);
+        // Saved 3 -> 4 -> 5; the client may still be sending 3 or 4
+        assert_eq!(rebase(Some((3, 5)), 3), 5);
+        assert_eq!(rebase(Some((3, 5)), 4), 5);
+        assert_eq!(rebase(Some((3, 5)), 5), 5);
+        // A base from outside the run isn't ours to move
+        assert_eq!(rebase(Some((3, 5)), 2), 2);
+        assert_eq!(rebase(Some((3, 5)), 7), 7);
+    }
+}
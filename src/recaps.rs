@@ -0,0 +1,183 @@
+use actix_web::{web, HttpResponse};
+use chrono::{DateTime, Datelike, Duration, NaiveDate, Utc, Weekday};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+use crate::auth::AuthenticatedUser;
+use crate::error::{ApiError, ApiResult};
+use crate::models::{ApiResponse, TweetResponse, TweetWithUser};
+use crate::AppState;
+
+// Weekly recaps: a summary of one ISO week (Monday to Monday, UTC) for the
+// current user, small enough to render as a shareable card. Impressions come
+// from the hourly analytics rollups, so the hour in progress isn't counted
+// yet. Without `week` the last full week is returned.
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.route("/api/users/me/recap", web::get().to(get_recap));
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RecapQuery {
+    // ISO week, e.g. `2024-W19`
+    pub week: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RecapResponse {
+    pub week: String,
+    pub starts_at: DateTime<Utc>,
+    pub ends_at: DateTime<Utc>,
+    pub tweets_count: i64,
+    pub impressions: i64,
+    pub new_followers: i64,
+    pub top_tweet: Option<TopTweet>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TopTweet {
+    pub tweet: TweetResponse,
+    pub impressions: i64,
+}
+
+#[derive(FromRow)]
+struct TopTweetRow {
+    #[sqlx(flatten)]
+    tweet: TweetWithUser,
+    impressions: i64,
+}
+
+// `2024-W19` to the Monday starting that week.
+fn parse_week(week: &str) -> Option<NaiveDate> {
+    let (year, week) = week.trim().split_once("-W")?;
+    NaiveDate::from_isoywd_opt(year.parse().ok()?, week.parse().ok()?, Weekday::Mon)
+}
+
+fn format_week(monday: NaiveDate) -> String {
+    let week = monday.iso_week();
+    format!("{}-W{:02}", week.year(), week.week())
+}
+
+// Monday of the last full week before `today`.
+fn last_full_week(today: NaiveDate) -> NaiveDate {
+    today - Duration::days(today.weekday().num_days_from_monday() as i64 + 7)
+}
+
+async fn get_recap(
+    state: web::Data<AppState>,
+    AuthenticatedUser(user_id): AuthenticatedUser,
+    query: web::Query<RecapQuery>,
+) -> ApiResult<HttpResponse> {
+    let today = Utc::now().date_naive();
+    let monday = match &query.week {
+        Some(week) => parse_week(week).ok_or_else(|| ApiError::BadRequest("week must look like 2024-W19".to_string()))?,
+        None => last_full_week(today),
+    };
+    if monday > today {
+        return Err(ApiError::BadRequest("That week hasn't started yet".to_string()));
+    }
+
+    let starts_at = monday.and_hms_opt(0, 0, 0).unwrap().and_utc();
+    let ends_at = starts_at + Duration::weeks(1);
+
+    let tweets_count = sqlx::query_scalar::<_, i64>(
+        "SELECT COUNT(*) FROM tweets
+         WHERE user_id = $1 AND deleted_at IS NULL AND created_at >= $2 AND created_at < $3"
+    )
+    .bind(user_id)
+    .bind(starts_at)
+    .bind(ends_at)
+    .fetch_one(&state.db)
+    .await?;
+
+    // Every tweet of the user's that was seen during the week, however old
+    let impressions = sqlx::query_scalar::<_, i64>(
+        "SELECT COALESCE(SUM(i.impressions), 0)::bigint
+         FROM tweet_impressions i
+         INNER JOIN tweets t ON t.id = i.tweet_id
+         WHERE t.user_id = $1 AND t.deleted_at IS NULL AND i.bucket >= $2 AND i.bucket < $3"
+    )
+    .bind(user_id)
+    .bind(starts_at)
+    .bind(ends_at)
+    .fetch_one(&state.db)
+    .await?;
+
+    let new_followers = sqlx::query_scalar::<_, i64>(
+        "SELECT COUNT(*) FROM follows f
+         INNER JOIN users u ON u.id = f.follower_id
+         WHERE f.following_id = $1 AND f.created_at >= $2 AND f.created_at < $3 AND u.deactivated_at IS NULL"
+    )
+    .bind(user_id)
+    .bind(starts_at)
+    .bind(ends_at)
+    .fetch_one(&state.db)
+    .await?;
+
+    // The public tweet posted that week with the most impressions, likes and
+    // retweets breaking ties. Restricted tweets stay off the card, since it's
+    // meant to be shared.
+    let top_tweet = sqlx::query_as::<_, TopTweetRow>(
+        "SELECT t.id, t.user_id, t.parent_tweet_id, t.quoted_tweet_id, t.content, t.image_url,
+                t.likes_count, t.retweets_count, t.replies_count, t.visibility, t.created_at,
+                u.username as user_username, u.display_name as user_display_name,
+                u.bio as user_bio,
+                u.profile_image as user_profile_image, u.banner_image as user_banner_image,
+                u.followers_count as user_followers_count, u.following_count as user_following_count,
+                u.verified as user_verified, u.is_bot as user_is_bot, u.created_at as user_created_at,
+                COALESCE((
+                    SELECT SUM(i.impressions) FROM tweet_impressions i
+                    WHERE i.tweet_id = t.id AND i.bucket >= $2 AND i.bucket < $3
+                ), 0)::bigint AS impressions
+         FROM tweets t
+         INNER JOIN users u ON u.id = t.user_id
+         WHERE t.user_id = $1 AND t.deleted_at IS NULL AND t.visibility = 'public'
+           AND t.created_at >= $2 AND t.created_at < $3
+         ORDER BY impressions DESC, t.likes_count + t.retweets_count DESC, t.created_at DESC
+         LIMIT 1"
+    )
+    .bind(user_id)
+    .bind(starts_at)
+    .bind(ends_at)
+    .fetch_optional(&state.db)
+    .await?;
+
+    Ok(HttpResponse::Ok().json(ApiResponse {
+        success: true,
+        data: Some(RecapResponse {
+            week: format_week(monday),
+            starts_at,
+            ends_at,
+            tweets_count,
+            impressions,
+            new_followers,
+            top_tweet: top_tweet.map(|row| TopTweet {
+                tweet: row.tweet.into_response(false, false),
+                impressions: row.impressions,
+            }),
+        }),
+        message: None,
+        next_cursor: None,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_iso_weeks() {
+        assert_eq!(parse_week("2024-W19"), NaiveDate::from_ymd_opt(2024, 5, 6));
+        // ISO week 1 of 2025 starts in December 2024
+        assert_eq!(parse_week("2025-W01"), NaiveDate::from_ymd_opt(2024, 12, 30));
+        assert_eq!(parse_week("2024-W54"), None);
+        assert_eq!(parse_week("2024-05-06"), None);
+        assert_eq!(format_week(NaiveDate::from_ymd_opt(2024, 12, 30).unwrap()), "2025-W01");
+    }
+
+    #[test]
+    fn defaults_to_the_last_full_week() {
+        let monday = NaiveDate::from_ymd_opt(2024, 5, 6).unwrap();
+        assert_eq!(last_full_week(monday), NaiveDate::from_ymd_opt(2024, 4, 29).unwrap());
+        assert_eq!(last_full_week(monday + Duration::days(6)), NaiveDate::from_ymd_opt(2024, 4, 29).unwrap());
+    }
+}
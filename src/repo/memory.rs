@@ -0,0 +1,257 @@
+use chrono::Utc;
+use std::collections::{HashMap, HashSet};
+use std::sync::Mutex;
+use uuid::Uuid;
+
+use super::{Outcome, RepoFuture, SocialRepo, UserRepo};
+use crate::crypto::Pii;
+use crate::models::{FollowState, RegisterRequest, TweetState, UpdateProfileRequest, User};
+
+// Users, likes, retweets, follows and blocks kept in memory, for handler
+// tests. Every tweet exists and everyone can see it; its counters only count
+// what was done here.
+#[derive(Default)]
+pub struct MemoryRepo {
+    store: Mutex<Store>,
+}
+
+#[derive(Default)]
+struct Store {
+    users: Vec<User>,
+    // Blind index of each user's email
+    email_hashes: HashMap<Uuid, String>,
+    likes: HashSet<(Uuid, Uuid)>,
+    retweets: HashSet<(Uuid, Uuid)>,
+    // (follower, following) to the notification mode
+    follows: HashMap<(Uuid, Uuid), String>,
+    blocks: HashSet<(Uuid, Uuid)>,
+}
+
+impl MemoryRepo {
+    pub fn add_user(&self, username: &str) -> Uuid {
+        let email = format!("{}@example.com", username);
+        self.store.lock().unwrap().insert_user(username, &email, &email, "", username).id
+    }
+
+    pub fn block(&self, blocker_id: Uuid, blocked_id: Uuid) {
+        self.store.lock().unwrap().blocks.insert((blocker_id, blocked_id));
+    }
+
+    // Applies `change` to the user and returns them, like an UPDATE ... RETURNING
+    fn update_user(&self, user_id: Uuid, change: impl FnOnce(&mut User)) -> RepoFuture<'_, User> {
+        let user = self.store.lock().unwrap().users.iter_mut().find(|user| user.id == user_id).map(|user| {
+            change(user);
+            user.clone()
+        });
+        Box::pin(async move { user.ok_or(sqlx::Error::RowNotFound) })
+    }
+
+    fn tweet_outcome(&self, changed: bool, user_id: Uuid, tweet_id: Uuid) -> RepoFuture<'_, Option<Outcome<TweetState>>> {
+        let store = self.store.lock().unwrap();
+        let state = TweetState {
+            tweet_id,
+            liked: store.likes.contains(&(user_id, tweet_id)),
+            retweeted: store.retweets.contains(&(user_id, tweet_id)),
+            likes_count: store.likes.iter().filter(|(_, t)| *t == tweet_id).count() as i32,
+            retweets_count: store.retweets.iter().filter(|(_, t)| *t == tweet_id).count() as i32,
+            replies_count: 0,
+        };
+        Box::pin(async move { Ok(Some(Outcome { changed, state })) })
+    }
+}
+
+impl Store {
+    fn insert_user(&mut self, username: &str, email: &str, email_hash: &str, password_hash: &str, display_name: &str) -> User {
+        let user = User {
+            id: Uuid::new_v4(),
+            username: username.to_string(),
+            email: Pii(email.to_string()),
+            password_hash: password_hash.to_string(),
+            display_name: display_name.to_string(),
+            bio: None,
+            profile_image: None,
+            banner_image: None,
+            followers_count: 0,
+            following_count: 0,
+            verified: false,
+            moved_to: None,
+            deactivated_at: None,
+            verified_email: true,
+            is_bot: false,
+            automated_by: None,
+            role: "user".to_string(),
+            suspended_at: None,
+            suspended_until: None,
+            suspension_reason: None,
+            share_location: false,
+            pinned_tweet_id: None,
+            created_at: Utc::now(),
+        };
+        self.users.push(user.clone());
+        self.email_hashes.insert(user.id, email_hash.to_string());
+        user
+    }
+
+    fn user(&self, user_id: Uuid) -> Option<&User> {
+        self.users.iter().find(|user| user.id == user_id)
+    }
+
+    fn follow_state(&self, follower_id: Uuid, following_id: Uuid) -> Option<FollowState> {
+        let count = |matches: &dyn Fn(&(Uuid, Uuid)) -> bool| self.follows.keys().filter(|edge| matches(edge)).count() as i32;
+        Some(FollowState {
+            username: self.user(following_id)?.username.clone(),
+            following: self.follows.contains_key(&(follower_id, following_id)),
+            followed_by: self.follows.contains_key(&(following_id, follower_id)),
+            notify: self.follows.get(&(follower_id, following_id)).cloned(),
+            followers_count: count(&|(_, following)| *following == following_id),
+            following_count: count(&|(follower, _)| *follower == follower_id),
+        })
+    }
+}
+
+impl UserRepo for MemoryRepo {
+    fn create<'a>(&'a self, account: &'a RegisterRequest, email_hash: &'a str, password_hash: &'a str) -> RepoFuture<'a, Option<User>> {
+        let mut store = self.store.lock().unwrap();
+        let taken = store.users.iter().any(|user| user.username == account.username) || store.email_hashes.values().any(|hash| hash == email_hash);
+        let user = (!taken).then(|| store.insert_user(&account.username, &account.email, email_hash, password_hash, &account.display_name));
+        Box::pin(async move { Ok(user) })
+    }
+
+    fn find_by_id(&self, user_id: Uuid) -> RepoFuture<'_, Option<User>> {
+        let user = self.store.lock().unwrap().user(user_id).cloned();
+        Box::pin(async move { Ok(user) })
+    }
+
+    fn find_by_email_hash<'a>(&'a self, email_hash: &'a str) -> RepoFuture<'a, Option<User>> {
+        let store = self.store.lock().unwrap();
+        let user = store
+            .email_hashes
+            .iter()
+            .find(|(_, hash)| *hash == email_hash)
+            .and_then(|(id, _)| store.user(*id))
+            .cloned();
+        Box::pin(async move { Ok(user) })
+    }
+
+    fn find_by_username<'a>(&'a self, username: &'a str) -> RepoFuture<'a, Option<User>> {
+        let store = self.store.lock().unwrap();
+        let user = store
+            .users
+            .iter()
+            .find(|user| user.username == username && user.deactivated_at.is_none())
+            .cloned();
+        Box::pin(async move { Ok(user) })
+    }
+
+    fn id_by_username<'a>(&'a self, username: &'a str) -> RepoFuture<'a, Option<Uuid>> {
+        let id = self.store.lock().unwrap().users.iter().find(|user| user.username == username).map(|user| user.id);
+        Box::pin(async move { Ok(id) })
+    }
+
+    fn update_profile<'a>(&'a self, user_id: Uuid, update: &'a UpdateProfileRequest) -> RepoFuture<'a, User> {
+        self.update_user(user_id, |user| {
+            if let Some(display_name) = &update.display_name {
+                user.display_name = display_name.clone();
+            }
+            user.bio = update.bio.clone().or(user.bio.take());
+            user.profile_image = update.profile_image.clone().or(user.profile_image.take());
+            user.banner_image = update.banner_image.clone().or(user.banner_image.take());
+            user.share_location = update.share_location.unwrap_or(user.share_location);
+        })
+    }
+
+    fn set_pinned_tweet(&self, user_id: Uuid, tweet_id: Option<Uuid>) -> RepoFuture<'_, User> {
+        self.update_user(user_id, |user| user.pinned_tweet_id = tweet_id)
+    }
+
+    fn set_automation<'a>(&'a self, user_id: Uuid, is_bot: bool, owner: Option<(Uuid, &'a str)>) -> RepoFuture<'a, User> {
+        self.update_user(user_id, |user| {
+            user.is_bot = is_bot;
+            user.automated_by = owner.map(|(_, username)| username.to_string());
+        })
+    }
+
+    fn password_hash(&self, user_id: Uuid) -> RepoFuture<'_, Option<String>> {
+        let hash = self.store.lock().unwrap().user(user_id).map(|user| user.password_hash.clone());
+        Box::pin(async move { Ok(hash) })
+    }
+
+    fn reactivate(&self, user_id: Uuid) -> RepoFuture<'_, ()> {
+        let user = self.update_user(user_id, |user| user.deactivated_at = None);
+        Box::pin(async move { user.await.map(|_| ()) })
+    }
+
+    fn delete(&self, user_id: Uuid) -> RepoFuture<'_, ()> {
+        let mut store = self.store.lock().unwrap();
+        store.users.retain(|user| user.id != user_id);
+        store.email_hashes.remove(&user_id);
+        store.likes.retain(|(user, _)| *user != user_id);
+        store.retweets.retain(|(user, _)| *user != user_id);
+        store.follows.retain(|(follower, following), _| *follower != user_id && *following != user_id);
+        store.blocks.retain(|(blocker, blocked)| *blocker != user_id && *blocked != user_id);
+        Box::pin(async move { Ok(()) })
+    }
+}
+
+impl SocialRepo for MemoryRepo {
+    fn like(&self, user_id: Uuid, tweet_id: Uuid) -> RepoFuture<'_, Option<Outcome<TweetState>>> {
+        let changed = self.store.lock().unwrap().likes.insert((user_id, tweet_id));
+        self.tweet_outcome(changed, user_id, tweet_id)
+    }
+
+    fn unlike(&self, user_id: Uuid, tweet_id: Uuid) -> RepoFuture<'_, Option<Outcome<TweetState>>> {
+        let changed = self.store.lock().unwrap().likes.remove(&(user_id, tweet_id));
+        self.tweet_outcome(changed, user_id, tweet_id)
+    }
+
+    fn retweet(&self, user_id: Uuid, tweet_id: Uuid) -> RepoFuture<'_, Option<Outcome<TweetState>>> {
+        let changed = self.store.lock().unwrap().retweets.insert((user_id, tweet_id));
+        self.tweet_outcome(changed, user_id, tweet_id)
+    }
+
+    fn unretweet(&self, user_id: Uuid, tweet_id: Uuid) -> RepoFuture<'_, Option<Outcome<TweetState>>> {
+        let changed = self.store.lock().unwrap().retweets.remove(&(user_id, tweet_id));
+        self.tweet_outcome(changed, user_id, tweet_id)
+    }
+
+    fn blocks(&self, blocker_id: Uuid, blocked_id: Uuid) -> RepoFuture<'_, bool> {
+        let blocks = self.store.lock().unwrap().blocks.contains(&(blocker_id, blocked_id));
+        Box::pin(async move { Ok(blocks) })
+    }
+
+    fn follow(&self, follower_id: Uuid, following_id: Uuid) -> RepoFuture<'_, Outcome<FollowState>> {
+        let mut store = self.store.lock().unwrap();
+        let blocked = store.blocks.contains(&(follower_id, following_id)) || store.blocks.contains(&(following_id, follower_id));
+        let changed = !blocked && !store.follows.contains_key(&(follower_id, following_id));
+        if changed {
+            store.follows.insert((follower_id, following_id), "off".to_string());
+        }
+        let state = store.follow_state(follower_id, following_id).ok_or(sqlx::Error::RowNotFound);
+        Box::pin(async move { Ok(Outcome { changed, state: state? }) })
+    }
+
+    fn unfollow(&self, follower_id: Uuid, following_id: Uuid) -> RepoFuture<'_, Outcome<FollowState>> {
+        let mut store = self.store.lock().unwrap();
+        let changed = store.follows.remove(&(follower_id, following_id)).is_some();
+        let state = store.follow_state(follower_id, following_id).ok_or(sqlx::Error::RowNotFound);
+        Box::pin(async move { Ok(Outcome { changed, state: state? }) })
+    }
+
+    fn set_follow_notifications<'a>(&'a self, follower_id: Uuid, following_id: Uuid, mode: &'a str) -> RepoFuture<'a, Option<FollowState>> {
+        let mut store = self.store.lock().unwrap();
+        let state = match store.follows.get_mut(&(follower_id, following_id)) {
+            Some(notify) => {
+                *notify = mode.to_string();
+                store.follow_state(follower_id, following_id)
+            }
+            None => None,
+        };
+        Box::pin(async move { Ok(state) })
+    }
+
+    fn following(&self, user_id: Uuid) -> RepoFuture<'_, HashSet<Uuid>> {
+        let store = self.store.lock().unwrap();
+        let ids = store.follows.keys().filter(|(follower, _)| *follower == user_id).map(|(_, following)| *following).collect();
+        Box::pin(async move { Ok(ids) })
+    }
+}
@@ -0,0 +1,176 @@
+use chrono::{DateTime, Utc};
+use futures_util::future::LocalBoxFuture;
+use sqlx::PgPool;
+use std::collections::HashSet;
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::abuse::AbuseLimits;
+use crate::models::{
+    FollowState, NewTweet, RegisterRequest, Tweet, TweetState, TweetWithUser, TweetWithViewerState, UpdateProfileRequest, User,
+};
+use crate::pagination::Cursor;
+
+#[cfg(test)]
+pub mod memory;
+pub mod postgres;
+
+// Storage for registration and sign-in, sessions, profiles, posting, tweet
+// pages, timelines and replies, and likes, retweets and follows. Handlers take
+// the repo they need as `web::Data<dyn UserRepo>` and so on, registered from
+// `AppState::repos`; tests can pass `memory::MemoryRepo` instead. Helpers
+// shared with other modules (`publish_tweet`, `tweet_page_bounds`) use
+// `AppState::repos` directly. The emailed-code flows, held tweets, blocks and
+// mutes, and the handlers of the smaller areas still query the database
+// directly.
+// Errors are storage failures only: a missing row or a refused change is in
+// the return value.
+pub type RepoFuture<'a, T> = LocalBoxFuture<'a, Result<T, sqlx::Error>>;
+
+#[derive(Clone)]
+pub struct Repos {
+    pub users: Arc<dyn UserRepo>,
+    pub tweets: Arc<dyn TweetRepo>,
+    pub social: Arc<dyn SocialRepo>,
+    pub sessions: Arc<dyn SessionRepo>,
+}
+
+impl Repos {
+    pub fn postgres(db: PgPool) -> Self {
+        Repos {
+            users: Arc::new(postgres::PgUserRepo::new(db.clone())),
+            tweets: Arc::new(postgres::PgTweetRepo::new(db.clone())),
+            social: Arc::new(postgres::PgSocialRepo::new(db.clone())),
+            sessions: Arc::new(postgres::PgSessionRepo::new(db)),
+        }
+    }
+}
+
+// Where something stands after a call that may or may not have changed it
+#[derive(Debug)]
+pub struct Outcome<T> {
+    pub changed: bool,
+    pub state: T,
+}
+
+// A page of tweets, newest first: those before `before` and after `since`
+#[derive(Debug, Clone, Copy)]
+pub struct TweetPage {
+    pub before: Option<Cursor>,
+    pub since: Option<Cursor>,
+    pub limit: i64,
+}
+
+// A tweet `TweetRepo::insert` posted; `flagged` if the abuse heuristics
+// caught it, in which case it notified nobody
+#[derive(Debug)]
+pub struct Inserted {
+    pub tweet: Tweet,
+    pub flagged: bool,
+}
+
+// Why `TweetRepo::insert` posted nothing
+#[derive(Debug)]
+pub enum InsertRefused {
+    ParentNotFound,
+    // The parent's author blocks the replier
+    ReplyBlocked,
+    QuotedNotFound,
+    IdTaken,
+}
+
+// What became of a presented refresh token
+#[derive(Debug)]
+pub enum Rotation {
+    Unknown,
+    Revoked,
+    Expired,
+    // It had been used already, so the whole family was revoked
+    Reused { user_id: Uuid, family_id: Uuid },
+    // Marked used, with its successor stored in the same family
+    Rotated(Box<User>),
+}
+
+pub trait UserRepo: Send + Sync {
+    // None if the email or the username is taken. `email_hash` is the
+    // email's blind index.
+    fn create<'a>(&'a self, account: &'a RegisterRequest, email_hash: &'a str, password_hash: &'a str) -> RepoFuture<'a, Option<User>>;
+    fn find_by_id(&self, user_id: Uuid) -> RepoFuture<'_, Option<User>>;
+    // Finds deactivated accounts too
+    fn find_by_email_hash<'a>(&'a self, email_hash: &'a str) -> RepoFuture<'a, Option<User>>;
+    // Accounts that are deactivated aren't found
+    fn find_by_username<'a>(&'a self, username: &'a str) -> RepoFuture<'a, Option<User>>;
+    // Finds deactivated accounts too
+    fn id_by_username<'a>(&'a self, username: &'a str) -> RepoFuture<'a, Option<Uuid>>;
+    fn update_profile<'a>(&'a self, user_id: Uuid, update: &'a UpdateProfileRequest) -> RepoFuture<'a, User>;
+    fn set_pinned_tweet(&self, user_id: Uuid, tweet_id: Option<Uuid>) -> RepoFuture<'_, User>;
+    // `owner` is the id and username of the account running the bot
+    fn set_automation<'a>(&'a self, user_id: Uuid, is_bot: bool, owner: Option<(Uuid, &'a str)>) -> RepoFuture<'a, User>;
+    fn password_hash(&self, user_id: Uuid) -> RepoFuture<'_, Option<String>>;
+    fn reactivate(&self, user_id: Uuid) -> RepoFuture<'_, ()>;
+    // Tweets, likes, follows, sessions and the rest go with the account, and
+    // the counts they fed on other accounts and tweets are corrected.
+    fn delete(&self, user_id: Uuid) -> RepoFuture<'_, ()>;
+}
+
+pub trait TweetRepo: Send + Sync {
+    // The tweet with its author, if it isn't deleted and the viewer may see it
+    fn find_visible(&self, tweet_id: Uuid, viewer_id: Option<Uuid>) -> RepoFuture<'_, Option<TweetWithUser>>;
+    // The tweet and up to `depth` of its ancestors, oldest first, so the tweet
+    // is last; empty if the viewer can't see it. Reads through to the archive.
+    fn thread(&self, tweet_id: Uuid, depth: i32, viewer_id: Option<Uuid>) -> RepoFuture<'_, Vec<TweetWithUser>>;
+    // Tweets by the account and those it co-authored, newest first
+    fn by_username<'a>(&'a self, username: &'a str, viewer_id: Option<Uuid>, page: TweetPage) -> RepoFuture<'a, Vec<TweetWithUser>>;
+    // Whether `user_id` wrote the tweet and it isn't deleted
+    fn is_author(&self, tweet_id: Uuid, user_id: Uuid) -> RepoFuture<'_, bool>;
+    // Of `tweet_ids`, those the user liked and those they retweeted
+    fn engagement<'a>(&'a self, user_id: Uuid, tweet_ids: &'a [Uuid]) -> RepoFuture<'a, (HashSet<Uuid>, HashSet<Uuid>)>;
+    // Where the tweet sits in timelines; reads through to the archive
+    fn position(&self, tweet_id: Uuid) -> RepoFuture<'_, Option<Cursor>>;
+    // Posts the tweet with its hashtags and mentions, runs the abuse
+    // heuristics and notifies the parent and quoted authors, all in one
+    // transaction.
+    fn insert<'a>(&'a self, user_id: Uuid, new_tweet: &'a NewTweet, abuse: &'a AbuseLimits) -> RepoFuture<'a, Result<Inserted, InsertRefused>>;
+    // Notifies the followers who turned on the bell for the author
+    fn notify_subscribers<'a>(&'a self, tweet: &'a Tweet) -> RepoFuture<'a, ()>;
+    // The user's own tweet with this id and content, for replaying a create
+    fn find_posted<'a>(&'a self, tweet_id: Uuid, user_id: Uuid, content: &'a str) -> RepoFuture<'a, Option<Tweet>>;
+    // Whether any tweet, archived tweet or held tweet has the id
+    fn id_taken(&self, tweet_id: Uuid) -> RepoFuture<'_, bool>;
+    // Home timeline: the viewer's tweets and those of the accounts they
+    // follow, less blocked and muted ones, with the viewer's like/retweet
+    // state. `snapshot` leaves out tweets newer than it.
+    fn timeline(&self, viewer_id: Uuid, page: TweetPage, snapshot: Option<DateTime<Utc>>) -> RepoFuture<'_, Vec<TweetWithViewerState>>;
+    // The replies below `tweet_id`, up to `depth` levels down, oldest first
+    fn replies(&self, tweet_id: Uuid, depth: i32, viewer_id: Option<Uuid>) -> RepoFuture<'_, Vec<TweetWithUser>>;
+    // Whether `ancestor_id` is somewhere above `tweet_id` in its thread
+    fn is_reply_to(&self, tweet_id: Uuid, ancestor_id: Uuid) -> RepoFuture<'_, bool>;
+}
+
+pub trait SocialRepo: Send + Sync {
+    // Like and retweet are None if there's no such tweet or the user can't
+    // see it; unlike and unretweet if there's no such tweet.
+    fn like(&self, user_id: Uuid, tweet_id: Uuid) -> RepoFuture<'_, Option<Outcome<TweetState>>>;
+    fn unlike(&self, user_id: Uuid, tweet_id: Uuid) -> RepoFuture<'_, Option<Outcome<TweetState>>>;
+    fn retweet(&self, user_id: Uuid, tweet_id: Uuid) -> RepoFuture<'_, Option<Outcome<TweetState>>>;
+    fn unretweet(&self, user_id: Uuid, tweet_id: Uuid) -> RepoFuture<'_, Option<Outcome<TweetState>>>;
+    fn blocks(&self, blocker_id: Uuid, blocked_id: Uuid) -> RepoFuture<'_, bool>;
+    // Doesn't follow across a block in either direction
+    fn follow(&self, follower_id: Uuid, following_id: Uuid) -> RepoFuture<'_, Outcome<FollowState>>;
+    fn unfollow(&self, follower_id: Uuid, following_id: Uuid) -> RepoFuture<'_, Outcome<FollowState>>;
+    // None if the follower doesn't follow the account
+    fn set_follow_notifications<'a>(&'a self, follower_id: Uuid, following_id: Uuid, mode: &'a str) -> RepoFuture<'a, Option<FollowState>>;
+    // The accounts the user follows
+    fn following(&self, user_id: Uuid) -> RepoFuture<'_, HashSet<Uuid>>;
+}
+
+// Login sessions: each is a family of refresh tokens, rotated on every use.
+// Only token hashes are stored.
+pub trait SessionRepo: Send + Sync {
+    // Stores a refresh token in `family_id`; a new family starts a session
+    fn insert_refresh_token<'a>(&'a self, user_id: Uuid, family_id: Uuid, token_hash: &'a str, expires_at: DateTime<Utc>) -> RepoFuture<'a, ()>;
+    // Marks the token used and stores `next_hash` in its family in its place
+    fn rotate<'a>(&'a self, token_hash: &'a str, next_hash: &'a str, expires_at: DateTime<Utc>) -> RepoFuture<'a, Rotation>;
+    // Revokes the family the token belongs to, if there is one
+    fn end_session<'a>(&'a self, token_hash: &'a str) -> RepoFuture<'a, ()>;
+}
@@ -0,0 +1,907 @@
+use chrono::{DateTime, Utc};
+use sqlx::{PgConnection, PgPool};
+use std::collections::HashSet;
+use uuid::Uuid;
+
+use super::{InsertRefused, Inserted, Outcome, RepoFuture, Rotation, SessionRepo, SocialRepo, TweetPage, TweetRepo, UserRepo};
+use crate::abuse::AbuseLimits;
+use crate::models::{
+    FollowState, NewTweet, RefreshToken, RegisterRequest, Tweet, TweetState, TweetWithUser, TweetWithViewerState, UpdateProfileRequest, User,
+};
+use crate::pagination::Cursor;
+use crate::{crypto, db, hashtags, mentions, notifications};
+
+// Columns for `TweetWithUser`, from tweets `t` joined with their authors `u`
+const TWEET_WITH_USER_COLUMNS: &str = "t.id, t.user_id, t.parent_tweet_id, t.quoted_tweet_id, t.content, t.image_url, t.likes_count, t.retweets_count,
+        t.replies_count, t.visibility, t.created_at,
+        u.username as user_username, u.display_name as user_display_name,
+        u.bio as user_bio,
+        u.profile_image as user_profile_image, u.banner_image as user_banner_image,
+        u.followers_count as user_followers_count, u.following_count as user_following_count,
+        u.verified as user_verified, u.is_bot as user_is_bot, u.created_at as user_created_at";
+
+pub struct PgUserRepo {
+    db: PgPool,
+}
+
+impl PgUserRepo {
+    pub fn new(db: PgPool) -> Self {
+        PgUserRepo { db }
+    }
+}
+
+impl UserRepo for PgUserRepo {
+    fn create<'a>(&'a self, account: &'a RegisterRequest, email_hash: &'a str, password_hash: &'a str) -> RepoFuture<'a, Option<User>> {
+        Box::pin(async move {
+            sqlx::query_as::<_, User>(
+                "INSERT INTO users (username, email, email_hash, password_hash, display_name)
+                 VALUES ($1, $2, $3, $4, $5)
+                 ON CONFLICT DO NOTHING
+                 RETURNING *"
+            )
+            .bind(&account.username)
+            .bind(crypto::seal(&account.email))
+            .bind(email_hash)
+            .bind(password_hash)
+            .bind(&account.display_name)
+            .fetch_optional(&self.db)
+            .await
+        })
+    }
+
+    fn find_by_id(&self, user_id: Uuid) -> RepoFuture<'_, Option<User>> {
+        Box::pin(async move {
+            sqlx::query_as::<_, User>("SELECT * FROM users WHERE id = $1")
+                .bind(user_id)
+                .fetch_optional(&self.db)
+                .await
+        })
+    }
+
+    fn find_by_email_hash<'a>(&'a self, email_hash: &'a str) -> RepoFuture<'a, Option<User>> {
+        Box::pin(async move {
+            sqlx::query_as::<_, User>("SELECT * FROM users WHERE email_hash = $1")
+                .bind(email_hash)
+                .fetch_optional(&self.db)
+                .await
+        })
+    }
+
+    fn find_by_username<'a>(&'a self, username: &'a str) -> RepoFuture<'a, Option<User>> {
+        Box::pin(async move {
+            sqlx::query_as::<_, User>("SELECT * FROM users WHERE username = $1 AND deactivated_at IS NULL")
+                .bind(username)
+                .fetch_optional(&self.db)
+                .await
+        })
+    }
+
+    fn id_by_username<'a>(&'a self, username: &'a str) -> RepoFuture<'a, Option<Uuid>> {
+        Box::pin(async move {
+            sqlx::query_scalar::<_, Uuid>("SELECT id FROM users WHERE username = $1")
+                .bind(username)
+                .fetch_optional(&self.db)
+                .await
+        })
+    }
+
+    fn update_profile<'a>(&'a self, user_id: Uuid, update: &'a UpdateProfileRequest) -> RepoFuture<'a, User> {
+        Box::pin(async move {
+            sqlx::query_as::<_, User>(
+                "UPDATE users
+                 SET display_name = COALESCE($1, display_name),
+                     bio = COALESCE($2, bio),
+                     profile_image = COALESCE($3, profile_image),
+                     banner_image = COALESCE($4, banner_image),
+                     share_location = COALESCE($5, share_location)
+                 WHERE id = $6
+                 RETURNING *"
+            )
+            .bind(&update.display_name)
+            .bind(&update.bio)
+            .bind(&update.profile_image)
+            .bind(&update.banner_image)
+            .bind(update.share_location)
+            .bind(user_id)
+            .fetch_one(&self.db)
+            .await
+        })
+    }
+
+    fn set_pinned_tweet(&self, user_id: Uuid, tweet_id: Option<Uuid>) -> RepoFuture<'_, User> {
+        Box::pin(async move {
+            sqlx::query_as::<_, User>("UPDATE users SET pinned_tweet_id = $1 WHERE id = $2 RETURNING *")
+                .bind(tweet_id)
+                .bind(user_id)
+                .fetch_one(&self.db)
+                .await
+        })
+    }
+
+    fn set_automation<'a>(&'a self, user_id: Uuid, is_bot: bool, owner: Option<(Uuid, &'a str)>) -> RepoFuture<'a, User> {
+        Box::pin(async move {
+            sqlx::query_as::<_, User>(
+                "UPDATE users SET is_bot = $1, bot_owner_id = $2, automated_by = $3
+                 WHERE id = $4
+                 RETURNING *"
+            )
+            .bind(is_bot)
+            .bind(owner.map(|(id, _)| id))
+            .bind(owner.map(|(_, username)| username))
+            .bind(user_id)
+            .fetch_one(&self.db)
+            .await
+        })
+    }
+
+    fn password_hash(&self, user_id: Uuid) -> RepoFuture<'_, Option<String>> {
+        Box::pin(async move {
+            sqlx::query_scalar::<_, String>("SELECT password_hash FROM users WHERE id = $1")
+                .bind(user_id)
+                .fetch_optional(&self.db)
+                .await
+        })
+    }
+
+    fn reactivate(&self, user_id: Uuid) -> RepoFuture<'_, ()> {
+        Box::pin(async move {
+            sqlx::query("UPDATE users SET deactivated_at = NULL WHERE id = $1")
+                .bind(user_id)
+                .execute(&self.db)
+                .await?;
+            Ok(())
+        })
+    }
+
+    fn delete(&self, user_id: Uuid) -> RepoFuture<'_, ()> {
+        Box::pin(async move {
+            sqlx::query("DELETE FROM users WHERE id = $1")
+                .bind(user_id)
+                .execute(&self.db)
+                .await?;
+            Ok(())
+        })
+    }
+}
+
+// Home timeline: tweets from followed users + own tweets with the viewer's
+// like/retweet state, one keyset page.
+// $1 viewer, $2/$3 cursor, $4 limit, $5 snapshot, $6/$7 since_id. Shared
+// with the debug query plan endpoint.
+pub const TIMELINE_QUERY: &str = "SELECT t.id, t.user_id, t.parent_tweet_id, t.quoted_tweet_id, t.content, t.image_url, t.likes_count, t.retweets_count, 
+                t.replies_count, t.visibility, t.created_at,
+                u.username as user_username, u.display_name as user_display_name, 
+                u.bio as user_bio, 
+                u.profile_image as user_profile_image, u.banner_image as user_banner_image,
+                u.followers_count as user_followers_count, u.following_count as user_following_count,
+                u.verified as user_verified, u.is_bot as user_is_bot, u.created_at as user_created_at,
+                EXISTS(SELECT 1 FROM likes l WHERE l.user_id = $1 AND l.tweet_id = t.id) as is_liked,
+                EXISTS(SELECT 1 FROM retweets r WHERE r.user_id = $1 AND r.tweet_id = t.id) as is_retweeted
+         FROM tweets t
+         INNER JOIN users u ON t.user_id = u.id
+         WHERE u.deactivated_at IS NULL AND t.deleted_at IS NULL
+         AND tweet_visible_to(t.user_id, t.visibility, $1)
+         AND NOT EXISTS (SELECT 1 FROM blocks b WHERE b.blocker_id = $1 AND b.blocked_id = t.user_id)
+         AND NOT EXISTS (SELECT 1 FROM mutes m WHERE m.muter_id = $1 AND m.muted_id = t.user_id)
+         AND NOT EXISTS (
+             SELECT 1 FROM muted_keywords k WHERE k.user_id = $1 AND strpos(lower(t.content), k.keyword) > 0
+         )
+         AND t.user_id IN (
+             SELECT following_id FROM follows WHERE follower_id = $1
+             UNION
+             SELECT $1
+         )
+         AND ($2::timestamptz IS NULL OR (t.created_at, t.id) < ($2, $3))
+         AND ($5::timestamptz IS NULL OR t.created_at <= $5)
+         AND ($6::timestamptz IS NULL OR (t.created_at, t.id) > ($6, $7))
+         ORDER BY t.created_at DESC, t.id DESC
+         LIMIT $4";
+
+// See `tweet_visible_to` (migration 037) for the rules.
+pub async fn can_view_tweet(conn: &mut PgConnection, tweet_id: Uuid, viewer_id: Uuid) -> Result<bool, sqlx::Error> {
+    sqlx::query_scalar::<_, bool>("SELECT EXISTS(SELECT 1 FROM tweets WHERE id = $1 AND tweet_visible_to(user_id, visibility, $2))")
+        .bind(tweet_id)
+        .bind(viewer_id)
+        .fetch_one(conn)
+        .await
+}
+
+// The author of a tweet the user may see and that isn't deleted
+async fn visible_author(conn: &mut PgConnection, tweet_id: Uuid, user_id: Uuid) -> Result<Option<Uuid>, sqlx::Error> {
+    sqlx::query_scalar::<_, Uuid>(
+        "SELECT user_id FROM tweets WHERE id = $1 AND deleted_at IS NULL AND tweet_visible_to(user_id, visibility, $2)"
+    )
+    .bind(tweet_id)
+    .bind(user_id)
+    .fetch_optional(conn)
+    .await
+}
+
+// The body of `TweetRepo::insert`, run in the caller's transaction
+async fn insert_tweet(
+    conn: &mut PgConnection,
+    user_id: Uuid,
+    new_tweet: &NewTweet,
+    abuse: &AbuseLimits,
+) -> Result<Result<Inserted, InsertRefused>, sqlx::Error> {
+    let signal = abuse.check(conn, user_id, &new_tweet.content, new_tweet.parent_tweet_id).await?;
+
+    let parent_author_id = match new_tweet.parent_tweet_id {
+        Some(parent_tweet_id) => match visible_author(conn, parent_tweet_id, user_id).await? {
+            Some(author_id) => Some(author_id),
+            None => return Ok(Err(InsertRefused::ParentNotFound)),
+        },
+        None => None,
+    };
+
+    if let Some(parent_author_id) = parent_author_id {
+        let blocked = sqlx::query_scalar::<_, bool>("SELECT EXISTS(SELECT 1 FROM blocks WHERE blocker_id = $1 AND blocked_id = $2)")
+            .bind(parent_author_id)
+            .bind(user_id)
+            .fetch_one(&mut *conn)
+            .await?;
+        if blocked {
+            return Ok(Err(InsertRefused::ReplyBlocked));
+        }
+    }
+
+    let quoted_author_id = match new_tweet.quoted_tweet_id {
+        Some(quoted_tweet_id) => match visible_author(conn, quoted_tweet_id, user_id).await? {
+            Some(author_id) => Some(author_id),
+            None => return Ok(Err(InsertRefused::QuotedNotFound)),
+        },
+        None => None,
+    };
+
+    let tweet = sqlx::query_as::<_, Tweet>(
+        "INSERT INTO tweets (id, user_id, content, image_url, parent_tweet_id, quoted_tweet_id, visibility)
+         VALUES (COALESCE($7, gen_random_uuid()), $1, $2, $3, $4, $5, $6)
+         ON CONFLICT (id) DO NOTHING
+         RETURNING *"
+    )
+    .bind(user_id)
+    .bind(&new_tweet.content)
+    .bind(&new_tweet.image_url)
+    .bind(new_tweet.parent_tweet_id)
+    .bind(new_tweet.quoted_tweet_id)
+    .bind(new_tweet.visibility.as_str())
+    .bind(new_tweet.id)
+    .fetch_optional(&mut *conn)
+    .await?;
+    let Some(tweet) = tweet else {
+        return Ok(Err(InsertRefused::IdTaken));
+    };
+
+    hashtags::attach(conn, tweet.id, &tweet.content).await?;
+    mentions::attach(conn, tweet.id, user_id, &tweet.content, signal.is_none()).await?;
+
+    // Tweets flagged as spam are published but notify nobody
+    if let Some(signal) = signal {
+        abuse.record(conn, user_id, tweet.id, &tweet.content, signal).await?;
+    } else {
+        if let Some(parent_author_id) = parent_author_id {
+            if can_view_tweet(conn, tweet.id, parent_author_id).await? {
+                notifications::notify(conn, parent_author_id, user_id, notifications::Kind::Reply, Some(tweet.id)).await?;
+            }
+        }
+        if let Some(quoted_author_id) = quoted_author_id {
+            if can_view_tweet(conn, tweet.id, quoted_author_id).await? {
+                notifications::notify(conn, quoted_author_id, user_id, notifications::Kind::Quote, Some(tweet.id)).await?;
+            }
+        }
+    }
+
+    Ok(Ok(Inserted {
+        tweet,
+        flagged: signal.is_some(),
+    }))
+}
+
+pub struct PgTweetRepo {
+    db: PgPool,
+}
+
+impl PgTweetRepo {
+    pub fn new(db: PgPool) -> Self {
+        PgTweetRepo { db }
+    }
+}
+
+impl TweetRepo for PgTweetRepo {
+    fn find_visible(&self, tweet_id: Uuid, viewer_id: Option<Uuid>) -> RepoFuture<'_, Option<TweetWithUser>> {
+        Box::pin(async move {
+            sqlx::query_as::<_, TweetWithUser>(&format!(
+                "SELECT {}
+                 FROM tweets t
+                 INNER JOIN users u ON t.user_id = u.id
+                 WHERE t.id = $1 AND t.deleted_at IS NULL AND tweet_visible_to(t.user_id, t.visibility, $2)",
+                TWEET_WITH_USER_COLUMNS
+            ))
+            .bind(tweet_id)
+            .bind(viewer_id)
+            .fetch_optional(&self.db)
+            .await
+        })
+    }
+
+    // Deleted ancestors, and those the viewer isn't allowed to see, keep the
+    // chain connected but aren't returned.
+    fn thread(&self, tweet_id: Uuid, depth: i32, viewer_id: Option<Uuid>) -> RepoFuture<'_, Vec<TweetWithUser>> {
+        Box::pin(async move {
+            sqlx::query_as::<_, TweetWithUser>(&format!(
+                "WITH RECURSIVE all_tweets AS (
+                     SELECT id, user_id, parent_tweet_id, quoted_tweet_id, content, image_url,
+                            likes_count, retweets_count, replies_count, visibility, created_at, deleted_at
+                     FROM tweets
+                     UNION ALL
+                     SELECT id, user_id, parent_tweet_id, quoted_tweet_id, content, image_url,
+                            likes_count, retweets_count, replies_count, visibility, created_at, NULL
+                     FROM archived_tweets
+                 ),
+                 chain AS (
+                     SELECT t.id, t.parent_tweet_id, 0 AS depth FROM all_tweets t
+                     INNER JOIN users u ON t.user_id = u.id
+                     WHERE t.id = $1 AND u.deactivated_at IS NULL AND t.deleted_at IS NULL
+                       AND tweet_visible_to(t.user_id, t.visibility, $3)
+                     UNION ALL
+                     SELECT t.id, t.parent_tweet_id, c.depth + 1 FROM all_tweets t INNER JOIN chain c ON t.id = c.parent_tweet_id
+                     WHERE c.depth < $2
+                 )
+                 SELECT {}
+                 FROM chain c
+                 INNER JOIN all_tweets t ON t.id = c.id
+                 INNER JOIN users u ON t.user_id = u.id
+                 WHERE u.deactivated_at IS NULL AND t.deleted_at IS NULL
+                   AND tweet_visible_to(t.user_id, t.visibility, $3)
+                 ORDER BY c.depth DESC",
+                TWEET_WITH_USER_COLUMNS
+            ))
+            .bind(tweet_id)
+            .bind(depth)
+            .bind(viewer_id)
+            .fetch_all(&self.db)
+            .await
+        })
+    }
+
+    fn by_username<'a>(&'a self, username: &'a str, viewer_id: Option<Uuid>, page: TweetPage) -> RepoFuture<'a, Vec<TweetWithUser>> {
+        Box::pin(async move {
+            sqlx::query_as::<_, TweetWithUser>(&format!(
+                "SELECT {}
+                 FROM tweets t
+                 INNER JOIN users u ON t.user_id = u.id
+                 WHERE u.deactivated_at IS NULL AND t.deleted_at IS NULL
+                   AND (u.username = $1 OR t.id IN (
+                       SELECT c.tweet_id FROM tweet_coauthors c
+                       INNER JOIN users cu ON cu.id = c.user_id
+                       WHERE cu.username = $1 AND cu.deactivated_at IS NULL AND c.accepted_at IS NOT NULL
+                   ))
+                   AND tweet_visible_to(t.user_id, t.visibility, $7)
+                   AND ($2::timestamptz IS NULL OR (t.created_at, t.id) < ($2, $3))
+                   AND ($5::timestamptz IS NULL OR (t.created_at, t.id) > ($5, $6))
+                 ORDER BY t.created_at DESC, t.id DESC
+                 LIMIT $4",
+                TWEET_WITH_USER_COLUMNS
+            ))
+            .bind(username)
+            .bind(page.before.map(|c| c.created_at))
+            .bind(page.before.map(|c| c.id))
+            .bind(page.limit)
+            .bind(page.since.map(|c| c.created_at))
+            .bind(page.since.map(|c| c.id))
+            .bind(viewer_id)
+            .fetch_all(&self.db)
+            .await
+        })
+    }
+
+    fn is_author(&self, tweet_id: Uuid, user_id: Uuid) -> RepoFuture<'_, bool> {
+        Box::pin(async move {
+            sqlx::query_scalar::<_, bool>(
+                "SELECT EXISTS(SELECT 1 FROM tweets WHERE id = $1 AND user_id = $2 AND deleted_at IS NULL)"
+            )
+            .bind(tweet_id)
+            .bind(user_id)
+            .fetch_one(&self.db)
+            .await
+        })
+    }
+
+    fn engagement<'a>(&'a self, user_id: Uuid, tweet_ids: &'a [Uuid]) -> RepoFuture<'a, (HashSet<Uuid>, HashSet<Uuid>)> {
+        Box::pin(async move {
+            let liked = sqlx::query_scalar::<_, Uuid>("SELECT tweet_id FROM likes WHERE user_id = $1 AND tweet_id = ANY($2)")
+                .bind(user_id)
+                .bind(tweet_ids)
+                .fetch_all(&self.db)
+                .await?;
+            let retweeted = sqlx::query_scalar::<_, Uuid>("SELECT tweet_id FROM retweets WHERE user_id = $1 AND tweet_id = ANY($2)")
+                .bind(user_id)
+                .bind(tweet_ids)
+                .fetch_all(&self.db)
+                .await?;
+            Ok((liked.into_iter().collect(), retweeted.into_iter().collect()))
+        })
+    }
+
+    fn position(&self, tweet_id: Uuid) -> RepoFuture<'_, Option<Cursor>> {
+        Box::pin(async move {
+            let row = sqlx::query_as::<_, (DateTime<Utc>, Uuid)>(
+                "SELECT created_at, id FROM tweets WHERE id = $1
+                 UNION ALL
+                 SELECT created_at, id FROM archived_tweets WHERE id = $1"
+            )
+            .bind(tweet_id)
+            .fetch_optional(&self.db)
+            .await?;
+            Ok(row.map(|(created_at, id)| Cursor { created_at, id }))
+        })
+    }
+
+    // A refused insert has written nothing yet, so the transaction is just
+    // dropped.
+    fn insert<'a>(&'a self, user_id: Uuid, new_tweet: &'a NewTweet, abuse: &'a AbuseLimits) -> RepoFuture<'a, Result<Inserted, InsertRefused>> {
+        Box::pin(async move {
+            let mut tx = self.db.begin().await?;
+            let inserted = insert_tweet(&mut tx, user_id, new_tweet, abuse).await?;
+            if inserted.is_ok() {
+                tx.commit().await?;
+            }
+            Ok(inserted)
+        })
+    }
+
+    fn notify_subscribers<'a>(&'a self, tweet: &'a Tweet) -> RepoFuture<'a, ()> {
+        Box::pin(async move {
+            sqlx::query(
+                "INSERT INTO notifications (user_id, actor_id, kind, tweet_id)
+                 SELECT follower_id, $1, 'tweet', $2 FROM follows
+                 WHERE following_id = $1
+                   AND (notify = 'all' OR (notify = 'replies_off' AND $3::uuid IS NULL))
+                   AND tweet_visible_to($1, $4, follower_id)"
+            )
+            .bind(tweet.user_id)
+            .bind(tweet.id)
+            .bind(tweet.parent_tweet_id)
+            .bind(&tweet.visibility)
+            .execute(&self.db)
+            .await?;
+            Ok(())
+        })
+    }
+
+    fn find_posted<'a>(&'a self, tweet_id: Uuid, user_id: Uuid, content: &'a str) -> RepoFuture<'a, Option<Tweet>> {
+        Box::pin(async move {
+            sqlx::query_as::<_, Tweet>(
+                "SELECT * FROM tweets WHERE id = $1 AND user_id = $2 AND content = $3 AND deleted_at IS NULL"
+            )
+            .bind(tweet_id)
+            .bind(user_id)
+            .bind(content)
+            .fetch_optional(&self.db)
+            .await
+        })
+    }
+
+    fn id_taken(&self, tweet_id: Uuid) -> RepoFuture<'_, bool> {
+        Box::pin(async move {
+            sqlx::query_scalar::<_, bool>(
+                "SELECT EXISTS(SELECT 1 FROM tweets WHERE id = $1)
+                     OR EXISTS(SELECT 1 FROM archived_tweets WHERE id = $1)
+                     OR EXISTS(SELECT 1 FROM held_tweets WHERE tweet_id = $1)"
+            )
+            .bind(tweet_id)
+            .fetch_one(&self.db)
+            .await
+        })
+    }
+
+    fn timeline(&self, viewer_id: Uuid, page: TweetPage, snapshot: Option<DateTime<Utc>>) -> RepoFuture<'_, Vec<TweetWithViewerState>> {
+        Box::pin(async move {
+            sqlx::query_as::<_, TweetWithViewerState>(TIMELINE_QUERY)
+                .bind(viewer_id)
+                .bind(page.before.map(|c| c.created_at))
+                .bind(page.before.map(|c| c.id))
+                .bind(page.limit)
+                .bind(snapshot)
+                .bind(page.since.map(|c| c.created_at))
+                .bind(page.since.map(|c| c.id))
+                .fetch_all(&self.db)
+                .await
+        })
+    }
+
+    fn replies(&self, tweet_id: Uuid, depth: i32, viewer_id: Option<Uuid>) -> RepoFuture<'_, Vec<TweetWithUser>> {
+        Box::pin(async move {
+            sqlx::query_as::<_, TweetWithUser>(&format!(
+                "WITH RECURSIVE thread AS (
+                     SELECT id, 1 AS depth FROM tweets WHERE parent_tweet_id = $1
+                     UNION ALL
+                     SELECT t.id, th.depth + 1 FROM tweets t INNER JOIN thread th ON t.parent_tweet_id = th.id
+                     WHERE th.depth < $2
+                 )
+                 SELECT {}
+                 FROM tweets t
+                 INNER JOIN users u ON t.user_id = u.id
+                 WHERE t.id IN (SELECT id FROM thread) AND u.deactivated_at IS NULL AND t.deleted_at IS NULL
+                   AND tweet_visible_to(t.user_id, t.visibility, $3)
+                 ORDER BY t.created_at ASC",
+                TWEET_WITH_USER_COLUMNS
+            ))
+            .bind(tweet_id)
+            .bind(depth)
+            .bind(viewer_id)
+            .fetch_all(&self.db)
+            .await
+        })
+    }
+
+    fn is_reply_to(&self, tweet_id: Uuid, ancestor_id: Uuid) -> RepoFuture<'_, bool> {
+        Box::pin(async move {
+            sqlx::query_scalar::<_, bool>(
+                "WITH RECURSIVE ancestors AS (
+                     SELECT id, parent_tweet_id FROM tweets WHERE id = $1
+                     UNION ALL
+                     SELECT t.id, t.parent_tweet_id FROM tweets t INNER JOIN ancestors a ON t.id = a.parent_tweet_id
+                 )
+                 SELECT EXISTS(SELECT 1 FROM ancestors WHERE parent_tweet_id = $2)"
+            )
+            .bind(tweet_id)
+            .bind(ancestor_id)
+            .fetch_one(&self.db)
+            .await
+        })
+    }
+}
+
+pub struct PgSocialRepo {
+    db: PgPool,
+}
+
+impl PgSocialRepo {
+    pub fn new(db: PgPool) -> Self {
+        PgSocialRepo { db }
+    }
+}
+
+async fn can_see_tweet(conn: &mut PgConnection, user_id: Uuid, tweet_id: Uuid) -> Result<bool, sqlx::Error> {
+    sqlx::query_scalar::<_, bool>(
+        "SELECT EXISTS(SELECT 1 FROM tweets WHERE id = $1 AND deleted_at IS NULL AND tweet_visible_to(user_id, visibility, $2))"
+    )
+    .bind(tweet_id)
+    .bind(user_id)
+    .fetch_one(conn)
+    .await
+}
+
+async fn tweet_state(conn: &mut PgConnection, user_id: Uuid, tweet_id: Uuid) -> Result<Option<TweetState>, sqlx::Error> {
+    sqlx::query_as::<_, TweetState>(
+        "SELECT t.id AS tweet_id,
+                EXISTS(SELECT 1 FROM likes l WHERE l.tweet_id = t.id AND l.user_id = $2) AS liked,
+                EXISTS(SELECT 1 FROM retweets r WHERE r.tweet_id = t.id AND r.user_id = $2) AS retweeted,
+                t.likes_count, t.retweets_count, t.replies_count
+         FROM tweets t
+         WHERE t.id = $1"
+    )
+    .bind(tweet_id)
+    .bind(user_id)
+    .fetch_optional(conn)
+    .await
+}
+
+async fn follow_state(conn: &mut PgConnection, follower_id: Uuid, following_id: Uuid) -> Result<FollowState, sqlx::Error> {
+    sqlx::query_as::<_, FollowState>(
+        "SELECT u.username,
+                f.follower_id IS NOT NULL AS following,
+                EXISTS(SELECT 1 FROM follows b WHERE b.follower_id = u.id AND b.following_id = $1) AS followed_by,
+                f.notify,
+                u.followers_count,
+                (SELECT following_count FROM users WHERE id = $1) AS following_count
+         FROM users u
+         LEFT JOIN follows f ON f.follower_id = $1 AND f.following_id = u.id
+         WHERE u.id = $2"
+    )
+    .bind(follower_id)
+    .bind(following_id)
+    .fetch_one(conn)
+    .await
+}
+
+// Inserts the follow edge; the counters follow from the row (migration 044).
+// Returns false when the edge already exists, or when either account blocks
+// the other.
+pub async fn insert_follow(conn: &mut PgConnection, follower_id: Uuid, following_id: Uuid) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query(
+        "INSERT INTO follows (follower_id, following_id)
+         SELECT $1, $2
+         WHERE NOT EXISTS (
+             SELECT 1 FROM blocks
+             WHERE (blocker_id = $1 AND blocked_id = $2) OR (blocker_id = $2 AND blocked_id = $1)
+         )
+         ON CONFLICT DO NOTHING"
+    )
+    .bind(follower_id)
+    .bind(following_id)
+    .execute(conn)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+// Removes the follow edge. Returns false when there was nothing to remove.
+pub async fn delete_follow(conn: &mut PgConnection, follower_id: Uuid, following_id: Uuid) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query("DELETE FROM follows WHERE follower_id = $1 AND following_id = $2")
+        .bind(follower_id)
+        .bind(following_id)
+        .execute(conn)
+        .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+impl SocialRepo for PgSocialRepo {
+    // The counters follow from the rows (migration 044), so the state read
+    // back in the same transaction has them up to date.
+    fn like(&self, user_id: Uuid, tweet_id: Uuid) -> RepoFuture<'_, Option<Outcome<TweetState>>> {
+        Box::pin(db::with_tx(&self.db, move |conn| {
+            Box::pin(async move {
+                if !can_see_tweet(conn, user_id, tweet_id).await? {
+                    return Ok(None);
+                }
+
+                let changed = sqlx::query("INSERT INTO likes (user_id, tweet_id) VALUES ($1, $2) ON CONFLICT (user_id, tweet_id) DO NOTHING")
+                    .bind(user_id)
+                    .bind(tweet_id)
+                    .execute(&mut *conn)
+                    .await?
+                    .rows_affected()
+                    > 0;
+
+                if changed {
+                    notifications::notify_author(conn, tweet_id, user_id, notifications::Kind::Like).await?;
+                }
+                Ok(tweet_state(conn, user_id, tweet_id).await?.map(|state| Outcome { changed, state }))
+            })
+        }))
+    }
+
+    fn unlike(&self, user_id: Uuid, tweet_id: Uuid) -> RepoFuture<'_, Option<Outcome<TweetState>>> {
+        Box::pin(db::with_tx(&self.db, move |conn| {
+            Box::pin(async move {
+                let changed = sqlx::query("DELETE FROM likes WHERE user_id = $1 AND tweet_id = $2")
+                    .bind(user_id)
+                    .bind(tweet_id)
+                    .execute(&mut *conn)
+                    .await?
+                    .rows_affected()
+                    > 0;
+
+                if changed {
+                    notifications::retract(conn, user_id, notifications::Kind::Like, Some(tweet_id), None).await?;
+                }
+                Ok(tweet_state(conn, user_id, tweet_id).await?.map(|state| Outcome { changed, state }))
+            })
+        }))
+    }
+
+    fn retweet(&self, user_id: Uuid, tweet_id: Uuid) -> RepoFuture<'_, Option<Outcome<TweetState>>> {
+        Box::pin(db::with_tx(&self.db, move |conn| {
+            Box::pin(async move {
+                if !can_see_tweet(conn, user_id, tweet_id).await? {
+                    return Ok(None);
+                }
+
+                let changed = sqlx::query("INSERT INTO retweets (user_id, tweet_id) VALUES ($1, $2) ON CONFLICT DO NOTHING")
+                    .bind(user_id)
+                    .bind(tweet_id)
+                    .execute(&mut *conn)
+                    .await?
+                    .rows_affected()
+                    > 0;
+
+                if changed {
+                    notifications::notify_author(conn, tweet_id, user_id, notifications::Kind::Retweet).await?;
+                }
+                Ok(tweet_state(conn, user_id, tweet_id).await?.map(|state| Outcome { changed, state }))
+            })
+        }))
+    }
+
+    fn unretweet(&self, user_id: Uuid, tweet_id: Uuid) -> RepoFuture<'_, Option<Outcome<TweetState>>> {
+        Box::pin(db::with_tx(&self.db, move |conn| {
+            Box::pin(async move {
+                let changed = sqlx::query("DELETE FROM retweets WHERE user_id = $1 AND tweet_id = $2")
+                    .bind(user_id)
+                    .bind(tweet_id)
+                    .execute(&mut *conn)
+                    .await?
+                    .rows_affected()
+                    > 0;
+
+                if changed {
+                    notifications::retract(conn, user_id, notifications::Kind::Retweet, Some(tweet_id), None).await?;
+                }
+                Ok(tweet_state(conn, user_id, tweet_id).await?.map(|state| Outcome { changed, state }))
+            })
+        }))
+    }
+
+    fn blocks(&self, blocker_id: Uuid, blocked_id: Uuid) -> RepoFuture<'_, bool> {
+        Box::pin(async move {
+            sqlx::query_scalar::<_, bool>("SELECT EXISTS(SELECT 1 FROM blocks WHERE blocker_id = $1 AND blocked_id = $2)")
+                .bind(blocker_id)
+                .bind(blocked_id)
+                .fetch_one(&self.db)
+                .await
+        })
+    }
+
+    fn follow(&self, follower_id: Uuid, following_id: Uuid) -> RepoFuture<'_, Outcome<FollowState>> {
+        Box::pin(db::with_tx(&self.db, move |conn| {
+            Box::pin(async move {
+                let changed = insert_follow(conn, follower_id, following_id).await?;
+                if changed {
+                    notifications::notify(conn, following_id, follower_id, notifications::Kind::Follow, None).await?;
+                }
+                let state = follow_state(conn, follower_id, following_id).await?;
+                Ok(Outcome { changed, state })
+            })
+        }))
+    }
+
+    fn unfollow(&self, follower_id: Uuid, following_id: Uuid) -> RepoFuture<'_, Outcome<FollowState>> {
+        Box::pin(db::with_tx(&self.db, move |conn| {
+            Box::pin(async move {
+                let changed = delete_follow(conn, follower_id, following_id).await?;
+                if changed {
+                    notifications::retract(conn, follower_id, notifications::Kind::Follow, None, Some(following_id)).await?;
+                }
+                let state = follow_state(conn, follower_id, following_id).await?;
+                Ok(Outcome { changed, state })
+            })
+        }))
+    }
+
+    fn set_follow_notifications<'a>(&'a self, follower_id: Uuid, following_id: Uuid, mode: &'a str) -> RepoFuture<'a, Option<FollowState>> {
+        let mode = mode.to_string();
+        Box::pin(db::with_tx(&self.db, move |conn| {
+            Box::pin(async move {
+                let updated = sqlx::query("UPDATE follows SET notify = $1 WHERE follower_id = $2 AND following_id = $3")
+                    .bind(mode)
+                    .bind(follower_id)
+                    .bind(following_id)
+                    .execute(&mut *conn)
+                    .await?
+                    .rows_affected()
+                    > 0;
+
+                if !updated {
+                    return Ok(None);
+                }
+                Ok(Some(follow_state(conn, follower_id, following_id).await?))
+            })
+        }))
+    }
+
+    fn following(&self, user_id: Uuid) -> RepoFuture<'_, HashSet<Uuid>> {
+        Box::pin(async move {
+            let ids = sqlx::query_scalar::<_, Uuid>("SELECT following_id FROM follows WHERE follower_id = $1")
+                .bind(user_id)
+                .fetch_all(&self.db)
+                .await?;
+            Ok(ids.into_iter().collect())
+        })
+    }
+}
+
+pub struct PgSessionRepo {
+    db: PgPool,
+}
+
+impl PgSessionRepo {
+    pub fn new(db: PgPool) -> Self {
+        PgSessionRepo { db }
+    }
+}
+
+// For callers that store the token in a transaction of their own
+pub async fn insert_refresh_token(
+    conn: &mut PgConnection,
+    user_id: Uuid,
+    family_id: Uuid,
+    token_hash: &str,
+    expires_at: DateTime<Utc>,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "INSERT INTO refresh_tokens (user_id, family_id, token_hash, expires_at)
+         VALUES ($1, $2, $3, $4)"
+    )
+    .bind(user_id)
+    .bind(family_id)
+    .bind(token_hash)
+    .bind(expires_at)
+    .execute(conn)
+    .await?;
+    Ok(())
+}
+
+async fn revoke_token_family(conn: &mut PgConnection, family_id: Uuid) -> Result<(), sqlx::Error> {
+    sqlx::query("UPDATE refresh_tokens SET revoked_at = NOW() WHERE family_id = $1 AND revoked_at IS NULL")
+        .bind(family_id)
+        .execute(conn)
+        .await?;
+    Ok(())
+}
+
+impl SessionRepo for PgSessionRepo {
+    fn insert_refresh_token<'a>(&'a self, user_id: Uuid, family_id: Uuid, token_hash: &'a str, expires_at: DateTime<Utc>) -> RepoFuture<'a, ()> {
+        Box::pin(async move { insert_refresh_token(&mut *self.db.acquire().await?, user_id, family_id, token_hash, expires_at).await })
+    }
+
+    // The row stays locked until the rotation commits, so a token presented
+    // twice at once is only exchanged once.
+    fn rotate<'a>(&'a self, token_hash: &'a str, next_hash: &'a str, expires_at: DateTime<Utc>) -> RepoFuture<'a, Rotation> {
+        Box::pin(async move {
+            let mut tx = self.db.begin().await?;
+
+            let token = sqlx::query_as::<_, RefreshToken>(
+                "SELECT id, user_id, family_id, expires_at, used_at, revoked_at
+                 FROM refresh_tokens
+                 WHERE token_hash = $1
+                 FOR UPDATE"
+            )
+            .bind(token_hash)
+            .fetch_optional(&mut *tx)
+            .await?;
+
+            let Some(token) = token else {
+                return Ok(Rotation::Unknown);
+            };
+            if token.revoked_at.is_some() {
+                return Ok(Rotation::Revoked);
+            }
+            if token.used_at.is_some() {
+                revoke_token_family(&mut tx, token.family_id).await?;
+                tx.commit().await?;
+                return Ok(Rotation::Reused {
+                    user_id: token.user_id,
+                    family_id: token.family_id,
+                });
+            }
+            if token.expires_at <= Utc::now() {
+                return Ok(Rotation::Expired);
+            }
+
+            sqlx::query("UPDATE refresh_tokens SET used_at = NOW() WHERE id = $1")
+                .bind(token.id)
+                .execute(&mut *tx)
+                .await?;
+
+            let user = sqlx::query_as::<_, User>("SELECT * FROM users WHERE id = $1")
+                .bind(token.user_id)
+                .fetch_one(&mut *tx)
+                .await?;
+
+            insert_refresh_token(&mut tx, user.id, token.family_id, next_hash, expires_at).await?;
+            tx.commit().await?;
+
+            Ok(Rotation::Rotated(Box::new(user)))
+        })
+    }
+
+    fn end_session<'a>(&'a self, token_hash: &'a str) -> RepoFuture<'a, ()> {
+        Box::pin(async move {
+            let mut tx = self.db.begin().await?;
+
+            let family_id = sqlx::query_scalar::<_, Uuid>("SELECT family_id FROM refresh_tokens WHERE token_hash = $1")
+                .bind(token_hash)
+                .fetch_optional(&mut *tx)
+                .await?;
+
+            if let Some(family_id) = family_id {
+                revoke_token_family(&mut tx, family_id).await?;
+            }
+            tx.commit().await?;
+            Ok(())
+        })
+    }
+}
@@ -0,0 +1,400 @@
+use actix_web::{web, HttpResponse};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::auth::{AuthenticatedUser, ModeratorUser};
+use crate::error::{ApiError, ApiResult};
+use crate::models::ApiResponse;
+use crate::pagination::{self, Cursor, PageQuery};
+use crate::handlers::tweets::mark_tweet_deleted;
+use crate::repo::postgres::can_view_tweet;
+use crate::{admin, AppState};
+
+// Reports: any user can flag a tweet or an account with a reason, and
+// moderators work through the open ones oldest first. Resolving a report can
+// hide the tweet or suspend the account, and closes every other open report
+// about the same target along with it. Dismissing closes just the one.
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.route("/api/tweets/{id}/report", web::post().to(report_tweet))
+        .route("/api/users/{username}/report", web::post().to(report_user))
+        .route("/api/admin/reports", web::get().to(list_reports))
+        .route("/api/admin/reports/{id}", web::get().to(get_report))
+        .route("/api/admin/reports/{id}/resolve", web::post().to(resolve_report))
+        .route("/api/admin/reports/{id}/dismiss", web::post().to(dismiss_report));
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReportReason {
+    Spam,
+    Harassment,
+    Hate,
+    Violence,
+    SelfHarm,
+    SexualContent,
+    Impersonation,
+    Other,
+}
+
+impl ReportReason {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ReportReason::Spam => "spam",
+            ReportReason::Harassment => "harassment",
+            ReportReason::Hate => "hate",
+            ReportReason::Violence => "violence",
+            ReportReason::SelfHarm => "self_harm",
+            ReportReason::SexualContent => "sexual_content",
+            ReportReason::Impersonation => "impersonation",
+            ReportReason::Other => "other",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReportAction {
+    // Resolved without acting on the target
+    None,
+    HideTweet,
+    SuspendUser,
+}
+
+impl ReportAction {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ReportAction::None => "none",
+            ReportAction::HideTweet => "hide_tweet",
+            ReportAction::SuspendUser => "suspend_user",
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct CreateReportRequest {
+    pub reason: ReportReason,
+    #[serde(default)]
+    #[validate(length(max = 1000))]
+    pub details: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ReportFilter {
+    // open (the default), resolved or dismissed
+    pub status: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ResolveReportRequest {
+    pub action: ReportAction,
+    // Recorded as the suspension reason
+    pub note: Option<String>,
+}
+
+#[derive(Debug, Serialize, FromRow)]
+pub struct Report {
+    pub id: Uuid,
+    pub reporter_id: Uuid,
+    pub reporter_username: String,
+    pub user_id: Uuid,
+    pub username: String,
+    pub tweet_id: Option<Uuid>,
+    // The reported tweet as it stands, deleted or not
+    pub tweet_content: Option<String>,
+    pub reason: String,
+    pub details: String,
+    pub status: String,
+    pub action: Option<String>,
+    pub resolved_by: Option<Uuid>,
+    pub resolved_at: Option<DateTime<Utc>>,
+    // Open reports about the same target, this one included
+    pub open_reports: i64,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(FromRow)]
+struct ReportTarget {
+    user_id: Uuid,
+    tweet_id: Option<Uuid>,
+    status: String,
+}
+
+const REPORT_QUERY: &str = "SELECT r.id, r.reporter_id, ru.username AS reporter_username, r.user_id, u.username,
+        r.tweet_id, t.content AS tweet_content, r.reason, r.details, r.status, r.action,
+        r.resolved_by, r.resolved_at,
+        (SELECT COUNT(*) FROM reports o
+         WHERE o.status = 'open' AND o.user_id = r.user_id AND o.tweet_id IS NOT DISTINCT FROM r.tweet_id) AS open_reports,
+        r.created_at
+     FROM reports r
+     INNER JOIN users ru ON ru.id = r.reporter_id
+     INNER JOIN users u ON u.id = r.user_id
+     LEFT JOIN tweets t ON t.id = r.tweet_id";
+
+fn parse_status(status: Option<&str>) -> Result<&str, String> {
+    match status.unwrap_or("open") {
+        status @ ("open" | "resolved" | "dismissed") => Ok(status),
+        other => Err(format!("Unknown report status: {}", other)),
+    }
+}
+
+async fn insert_report(
+    conn: &mut sqlx::PgConnection,
+    reporter_id: Uuid,
+    user_id: Uuid,
+    tweet_id: Option<Uuid>,
+    req: &CreateReportRequest,
+) -> Result<(), sqlx::Error> {
+    // A second report while the first is still open is a no-op
+    sqlx::query(
+        "INSERT INTO reports (reporter_id, user_id, tweet_id, reason, details)
+         VALUES ($1, $2, $3, $4, $5)
+         ON CONFLICT DO NOTHING"
+    )
+    .bind(reporter_id)
+    .bind(user_id)
+    .bind(tweet_id)
+    .bind(req.reason.as_str())
+    .bind(req.details.trim())
+    .execute(conn)
+    .await?;
+    Ok(())
+}
+
+fn report_received() -> HttpResponse {
+    HttpResponse::Created().json(ApiResponse::<()> {
+        success: true,
+        data: None,
+        message: Some("Report received".to_string()),
+        next_cursor: None,
+    })
+}
+
+async fn report_tweet(
+    state: web::Data<AppState>,
+    AuthenticatedUser(user_id): AuthenticatedUser,
+    tweet_id: web::Path<Uuid>,
+    req: web::Json<CreateReportRequest>,
+) -> ApiResult<HttpResponse> {
+    req.validate()?;
+    let tweet_id = tweet_id.into_inner();
+    let mut conn = state.db.acquire().await?;
+
+    let author_id = sqlx::query_scalar::<_, Uuid>("SELECT user_id FROM tweets WHERE id = $1 AND deleted_at IS NULL")
+        .bind(tweet_id)
+        .fetch_optional(&mut *conn)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("Tweet not found".to_string()))?;
+    if !can_view_tweet(&mut conn, tweet_id, user_id).await? {
+        return Err(ApiError::NotFound("Tweet not found".to_string()));
+    }
+    if author_id == user_id {
+        return Err(ApiError::BadRequest("You can't report your own tweet".to_string()));
+    }
+
+    insert_report(&mut conn, user_id, author_id, Some(tweet_id), &req).await?;
+    Ok(report_received())
+}
+
+async fn report_user(
+    state: web::Data<AppState>,
+    AuthenticatedUser(user_id): AuthenticatedUser,
+    username: web::Path<String>,
+    req: web::Json<CreateReportRequest>,
+) -> ApiResult<HttpResponse> {
+    req.validate()?;
+    let mut conn = state.db.acquire().await?;
+
+    let reported_id = sqlx::query_scalar::<_, Uuid>("SELECT id FROM users WHERE username = $1 AND deactivated_at IS NULL")
+        .bind(username.into_inner())
+        .fetch_optional(&mut *conn)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("User not found".to_string()))?;
+    if reported_id == user_id {
+        return Err(ApiError::BadRequest("You can't report yourself".to_string()));
+    }
+
+    insert_report(&mut conn, user_id, reported_id, None, &req).await?;
+    Ok(report_received())
+}
+
+// Open reports oldest first, the order they should be handled in; closed
+// ones most recently filed first.
+async fn list_reports(
+    state: web::Data<AppState>,
+    _moderator: ModeratorUser,
+    filter: web::Query<ReportFilter>,
+    page: web::Query<PageQuery>,
+) -> ApiResult<HttpResponse> {
+    let status = parse_status(filter.status.as_deref()).map_err(ApiError::BadRequest)?;
+    let limit = page.limit();
+    let scope = format!("reports:{}", status);
+    let cursor = page.cursor(&state.cursors, &scope).map_err(ApiError::BadRequest)?;
+    let (order, after) = if status == "open" { ("ASC", ">") } else { ("DESC", "<") };
+
+    let mut reports = sqlx::query_as::<_, Report>(&format!(
+        "{}
+         WHERE r.status = $1
+           AND ($2::timestamptz IS NULL OR (r.created_at, r.id) {} ($2, $3))
+         ORDER BY r.created_at {}, r.id {}
+         LIMIT $4",
+        REPORT_QUERY, after, order, order
+    ))
+    .bind(status)
+    .bind(cursor.map(|c| c.created_at))
+    .bind(cursor.map(|c| c.id))
+    .bind(limit + 1)
+    .fetch_all(&state.db)
+    .await?;
+
+    let next_cursor = pagination::next_cursor(&mut reports, limit, &state.cursors, &scope, |r| Cursor {
+        created_at: r.created_at,
+        id: r.id,
+    });
+
+    Ok(HttpResponse::Ok().json(ApiResponse {
+        success: true,
+        data: Some(reports),
+        message: None,
+        next_cursor,
+    }))
+}
+
+async fn fetch_report(db: &sqlx::PgPool, report_id: Uuid) -> ApiResult<Report> {
+    sqlx::query_as::<_, Report>(&format!("{} WHERE r.id = $1", REPORT_QUERY))
+        .bind(report_id)
+        .fetch_optional(db)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("Report not found".to_string()))
+}
+
+async fn get_report(state: web::Data<AppState>, _moderator: ModeratorUser, report_id: web::Path<Uuid>) -> ApiResult<HttpResponse> {
+    let report = fetch_report(&state.db, report_id.into_inner()).await?;
+
+    Ok(HttpResponse::Ok().json(ApiResponse {
+        success: true,
+        data: Some(report),
+        message: None,
+        next_cursor: None,
+    }))
+}
+
+async fn lock_open_report(conn: &mut sqlx::PgConnection, report_id: Uuid) -> ApiResult<ReportTarget> {
+    let report = sqlx::query_as::<_, ReportTarget>("SELECT user_id, tweet_id, status FROM reports WHERE id = $1 FOR UPDATE")
+        .bind(report_id)
+        .fetch_optional(conn)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("Report not found".to_string()))?;
+
+    if report.status != "open" {
+        return Err(ApiError::Conflict("Report is already closed".to_string()));
+    }
+    Ok(report)
+}
+
+async fn resolve_report(
+    state: web::Data<AppState>,
+    ModeratorUser(moderator_id): ModeratorUser,
+    report_id: web::Path<Uuid>,
+    req: web::Json<ResolveReportRequest>,
+) -> ApiResult<HttpResponse> {
+    let report_id = report_id.into_inner();
+    let mut tx = state.db.begin().await?;
+    let report = lock_open_report(&mut tx, report_id).await?;
+
+    match req.action {
+        ReportAction::None => {}
+        ReportAction::HideTweet => {
+            let tweet_id = report
+                .tweet_id
+                .ok_or_else(|| ApiError::BadRequest("Only tweet reports can hide a tweet".to_string()))?;
+            // Already gone when its author deleted it first
+            let live = sqlx::query_scalar::<_, bool>("SELECT EXISTS(SELECT 1 FROM tweets WHERE id = $1 AND deleted_at IS NULL)")
+                .bind(tweet_id)
+                .fetch_one(&mut *tx)
+                .await?;
+            if live {
+                mark_tweet_deleted(&mut tx, None, tweet_id).await?;
+                log::info!("{} hid tweet {} after report {}", moderator_id, tweet_id, report_id);
+            }
+        }
+        ReportAction::SuspendUser => {
+            let note = req.note.as_deref().filter(|n| !n.trim().is_empty());
+            admin::suspend(&mut tx, moderator_id, report.user_id, note.or(Some("Reported")), None).await?;
+        }
+    }
+
+    // Close this report and the duplicates it settles: every report about a
+    // suspended account, otherwise those about the same tweet or account
+    let suspended = req.action == ReportAction::SuspendUser;
+    sqlx::query(
+        "UPDATE reports SET status = 'resolved', action = $4, resolved_by = $5, resolved_at = NOW()
+         WHERE status = 'open' AND user_id = $1
+           AND (id = $2 OR $3 OR tweet_id IS NOT DISTINCT FROM $6)"
+    )
+    .bind(report.user_id)
+    .bind(report_id)
+    .bind(suspended)
+    .bind(req.action.as_str())
+    .bind(moderator_id)
+    .bind(report.tweet_id)
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+
+    let report = fetch_report(&state.db, report_id).await?;
+    Ok(HttpResponse::Ok().json(ApiResponse {
+        success: true,
+        data: Some(report),
+        message: None,
+        next_cursor: None,
+    }))
+}
+
+async fn dismiss_report(
+    state: web::Data<AppState>,
+    ModeratorUser(moderator_id): ModeratorUser,
+    report_id: web::Path<Uuid>,
+) -> ApiResult<HttpResponse> {
+    let report_id = report_id.into_inner();
+    let mut tx = state.db.begin().await?;
+    lock_open_report(&mut tx, report_id).await?;
+
+    sqlx::query("UPDATE reports SET status = 'dismissed', resolved_by = $2, resolved_at = NOW() WHERE id = $1")
+        .bind(report_id)
+        .bind(moderator_id)
+        .execute(&mut *tx)
+        .await?;
+
+    tx.commit().await?;
+
+    let report = fetch_report(&state.db, report_id).await?;
+    Ok(HttpResponse::Ok().json(ApiResponse {
+        success: true,
+        data: Some(report),
+        message: None,
+        next_cursor: None,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn defaults_to_the_open_queue() {
+        assert_eq!(parse_status(None), Ok("open"));
+        assert_eq!(parse_status(Some("dismissed")), Ok("dismissed"));
+        assert!(parse_status(Some("closed")).is_err());
+    }
+
+    #[test]
+    fn reasons_match_the_schema() {
+        let reason: ReportReason = serde_json::from_str("\"self_harm\"").unwrap();
+        assert_eq!(reason.as_str(), "self_harm");
+        assert!(serde_json::from_str::<ReportReason>("\"rude\"").is_err());
+    }
+}
@@ -0,0 +1,160 @@
+use actix_web::body::MessageBody;
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::http::header::{HeaderName, HeaderValue};
+use actix_web::error::InternalError;
+use actix_web::middleware::Next;
+use actix_web::Error;
+use std::io::Write;
+use std::time::Instant;
+use tracing::field::Empty;
+use tracing::Instrument;
+use uuid::Uuid;
+
+use crate::auth;
+
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+const MAX_REQUEST_ID_LENGTH: usize = 64;
+
+const SPAN_TARGET: &str = "request_span";
+
+tokio::task_local! {
+    static REQUEST_ID: String;
+}
+
+// ID of the request being handled, for error bodies and log lines
+pub fn current() -> Option<String> {
+    REQUEST_ID.try_with(|id| id.clone()).ok()
+}
+
+// env_logger with the current request's ID on every line logged while a
+// request is handled, so handler logs can be tied to the request line. With no
+// tracing subscriber installed, tracing echoes spans to `log` (other crates
+// turn that on); the request span would only repeat the request line.
+pub fn init_logger() {
+    env_logger::Builder::from_default_env()
+        .filter_module(SPAN_TARGET, log::LevelFilter::Off)
+        .format(|buf, record| {
+            let request_id = current().map(|id| format!(" request_id={}", id)).unwrap_or_default();
+            writeln!(buf, "[{} {:<5} {}{}] {}", buf.timestamp(), record.level(), record.target(), request_id, record.args())
+        })
+        .init();
+}
+
+// Gives every request an ID, taken from an incoming X-Request-Id (so an ID a
+// proxy assigned carries through) or generated, returns it in the response
+// header and in error bodies, and logs one line per request:
+//
+//   request_id=... method=GET route=/api/tweets/{id} status=200 latency_ms=12 user_id=...
+//
+// The handler runs inside a `request` tracing span carrying the same fields,
+// for deployments that install a tracing subscriber.
+//
+// Routes are logged as their pattern, so paths with usernames or IDs don't
+// end up in the log. Server errors log at `warn`, everything else at `info`.
+pub async fn log_request<B: MessageBody + 'static>(req: ServiceRequest, next: Next<B>) -> Result<ServiceResponse<B>, Error> {
+    let id = req
+        .headers()
+        .get(REQUEST_ID_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .filter(|value| is_valid_request_id(value))
+        .map(str::to_string)
+        .unwrap_or_else(|| Uuid::new_v4().to_string());
+
+    let started = Instant::now();
+    let method = req.method().to_string();
+    let route = req.match_pattern().unwrap_or_else(|| "unmatched".to_string());
+    let user_id = auth::request_claims(req.request()).ok().map(|claims| claims.sub);
+
+    let span = tracing::info_span!(
+        target: SPAN_TARGET,
+        "request",
+        request_id = %id,
+        method = %method,
+        route = %route,
+        user_id = user_id.as_deref().unwrap_or("-"),
+        status = Empty,
+        latency_ms = Empty,
+    );
+
+    // Errors from inner middleware are rendered here rather than further
+    // out, so their bodies still carry the ID. The request can't be held on
+    // to for that: routing needs it unshared.
+    let result = REQUEST_ID.scope(id.clone(), next.call(req)).instrument(span.clone()).await;
+    let status = match &result {
+        Ok(res) => res.status(),
+        Err(e) => e.as_response_error().status_code(),
+    };
+    span.record("status", status.as_u16());
+    span.record("latency_ms", started.elapsed().as_millis() as u64);
+
+    let level = if status.is_server_error() { log::Level::Warn } else { log::Level::Info };
+    log::log!(
+        level,
+        "request_id={} method={} route={} status={} latency_ms={} user_id={}",
+        id,
+        method,
+        route,
+        status.as_u16(),
+        started.elapsed().as_millis(),
+        user_id.as_deref().unwrap_or("-")
+    );
+
+    let header = HeaderValue::from_str(&id).ok();
+    match result {
+        Ok(mut res) => {
+            if let Some(value) = header {
+                res.headers_mut().insert(HeaderName::from_static(REQUEST_ID_HEADER), value);
+            }
+            Ok(res)
+        }
+        Err(e) => {
+            let mut response = REQUEST_ID.sync_scope(id, || e.error_response());
+            if let Some(value) = header {
+                response.headers_mut().insert(HeaderName::from_static(REQUEST_ID_HEADER), value);
+            }
+            Err(InternalError::from_response(e, response).into())
+        }
+    }
+}
+
+// IDs from clients end up in logs, so only short, plain tokens are kept
+fn is_valid_request_id(id: &str) -> bool {
+    !id.is_empty()
+        && id.len() <= MAX_REQUEST_ID_LENGTH
+        && id.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accepts_plain_incoming_ids() {
+        assert!(is_valid_request_id("3f2c9a1e-8b7d-4c6e-9f0a-1b2c3d4e5f60"));
+        assert!(is_valid_request_id("lb.1234_abc"));
+        assert!(!is_valid_request_id(""));
+        assert!(!is_valid_request_id("id with spaces"));
+        assert!(!is_valid_request_id("id\nforged=1"));
+        assert!(!is_valid_request_id(&"a".repeat(MAX_REQUEST_ID_LENGTH + 1)));
+    }
+
+    #[test]
+    fn current_id_is_scoped_to_the_request() {
+        assert_eq!(current(), None);
+        REQUEST_ID.sync_scope("abc".to_string(), || assert_eq!(current().as_deref(), Some("abc")));
+    }
+
+    #[actix_web::test]
+    async fn error_bodies_carry_the_id() {
+        use crate::error::ApiError;
+        use actix_web::ResponseError;
+
+        let res = REQUEST_ID.sync_scope("abc".to_string(), || ApiError::NotFound("Tweet not found".to_string()).error_response());
+        let body = actix_web::body::to_bytes(res.into_body()).await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["request_id"], "abc");
+        assert_eq!(body["message"], "Tweet not found");
+        assert_eq!(body["success"], false);
+    }
+}
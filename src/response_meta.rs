@@ -0,0 +1,91 @@
+use actix_web::body::MessageBody;
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::middleware::Next;
+use actix_web::{web, Error};
+use serde::Serialize;
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+
+use crate::{auth, deprecations, AppState};
+
+tokio::task_local! {
+    static META: RefCell<ResponseMeta>;
+}
+
+// Operational hints for the client, sent as `meta` with every API response
+// that has any: how much of a rate limit is left, warnings (the limit running
+// low, a deprecated route) and the experiments the account is assigned to.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ResponseMeta {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rate_limit: Option<RateLimitMeta>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<String>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub experiments: BTreeMap<String, bool>,
+}
+
+// `limit` is the burst the bucket holds when full
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct RateLimitMeta {
+    pub limit: u32,
+    pub remaining: u32,
+}
+
+impl ResponseMeta {
+    fn is_empty(&self) -> bool {
+        self.rate_limit.is_none() && self.warnings.is_empty() && self.experiments.is_empty()
+    }
+}
+
+// Meta of the request being handled, if there's any to send
+pub fn current() -> Option<ResponseMeta> {
+    META.try_with(|meta| meta.borrow().clone()).ok().filter(|meta| !meta.is_empty())
+}
+
+// For middleware further in, e.g. the rate limiter. Outside a request it's a no-op.
+pub fn update(f: impl FnOnce(&mut ResponseMeta)) {
+    let _ = META.try_with(|meta| f(&mut meta.borrow_mut()));
+}
+
+// Starts the request's meta with what's known up front and keeps it for the
+// handler, where `ApiResponse` picks it up as it's serialized.
+pub async fn attach<B: MessageBody + 'static>(req: ServiceRequest, next: Next<B>) -> Result<ServiceResponse<B>, Error> {
+    let mut meta = ResponseMeta::default();
+    if let Some(deprecated) = req.match_pattern().and_then(|route| deprecations::lookup(req.method().as_str(), &route)) {
+        meta.warnings.push(deprecated.warning());
+    }
+    if let Some(state) = req.app_data::<web::Data<AppState>>() {
+        if let Ok(user_id) = auth::request_user_id(req.request()) {
+            meta.experiments = state.experiments.assignments(user_id);
+        }
+    }
+
+    META.scope(RefCell::new(meta), next.call(req)).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::ApiResponse;
+
+    #[test]
+    fn api_responses_carry_meta_only_when_there_is_some() {
+        let response = || ApiResponse::<()> {
+            success: true,
+            data: None,
+            message: None,
+            next_cursor: None,
+        };
+        let plain = serde_json::to_value(response()).unwrap();
+        assert!(plain.get("meta").is_none());
+
+        let with_meta = META.sync_scope(RefCell::new(ResponseMeta::default()), || {
+            update(|meta| meta.rate_limit = Some(RateLimitMeta { limit: 20, remaining: 3 }));
+            serde_json::to_value(response()).unwrap()
+        });
+        assert_eq!(with_meta["meta"]["rate_limit"]["remaining"], 3);
+        assert!(with_meta["meta"].get("warnings").is_none());
+        assert_eq!(with_meta["success"], true);
+    }
+}
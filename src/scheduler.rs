@@ -0,0 +1,67 @@
+use actix_web::web;
+use std::time::Duration;
+
+use crate::{admin, stories, AppState};
+
+const TICK_INTERVAL: Duration = Duration::from_secs(30);
+
+// Background loop for time-driven work. Each job is idempotent, so a missed
+// or repeated tick is harmless.
+pub fn spawn(state: web::Data<AppState>) {
+    actix_web::rt::spawn(async move {
+        let mut interval = actix_web::rt::time::interval(TICK_INTERVAL);
+        loop {
+            interval.tick().await;
+
+            if let Err(e) = state.maintenance.tick(&state.db).await {
+                log::error!("maintenance tick failed: {}", e);
+            }
+
+            if let Err(e) = state.archive.tick(&state.db).await {
+                log::error!("tweet archive tick failed: {}", e);
+            }
+
+            if let Err(e) = stories::delete_expired(&state.db).await {
+                log::error!("story cleanup failed: {}", e);
+            }
+
+            if let Err(e) = admin::lift_expired_suspensions(&state.db).await {
+                log::error!("lifting expired suspensions failed: {}", e);
+            }
+
+            state.rate_limits.prune();
+
+            if let Err(e) = state.trends.tick(&state.db).await {
+                log::error!("trends refresh failed: {}", e);
+            }
+
+            if let Err(e) = state.analytics.flush(&state.db).await {
+                log::error!("analytics flush failed: {}", e);
+            }
+
+            if let Err(e) = state.deprecations.flush(&state.db).await {
+                log::error!("deprecated route call flush failed: {}", e);
+            }
+
+            if let Err(e) = state.bots.tick(&state).await {
+                log::error!("bot rules tick failed: {}", e);
+            }
+
+            if let Err(e) = state.triggers.tick(&state.db, &state.http).await {
+                log::error!("trigger tick failed: {}", e);
+            }
+
+            if let Err(e) = state.cross_posting.tick(&state.db, &state.http, &state.base_url).await {
+                log::error!("cross-posting tick failed: {}", e);
+            }
+
+            if let Err(e) = state.backups.tick(&state).await {
+                log::error!("backup tick failed: {}", e);
+            }
+
+            if let Err(e) = state.jobs.prune(&state.db).await {
+                log::error!("job pruning failed: {}", e);
+            }
+        }
+    });
+}
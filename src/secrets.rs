@@ -0,0 +1,160 @@
+use reqwest::{Method, Url};
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::sync::OnceLock;
+
+use crate::http_client::HttpClient;
+
+// Settings that are credentials. Each can be given as the plain env var, as
+// `<KEY>_FILE` naming a file that holds the value (Docker/Kubernetes secrets),
+// or as a field of the same name in a Vault secret, in that order.
+const SECRETS: &[&str] = &[
+    "DATABASE_URL",
+    "DATABASE_PASSWORD",
+    "JWT_SECRET",
+    "JWT_KEYS",
+    "JWT_PRIVATE_KEYS",
+    "CURSOR_SECRET",
+    "S3_ACCESS_KEY_ID",
+    "S3_SECRET_ACCESS_KEY",
+    "MAIL_HTTP_TOKEN",
+    "SMTP_PASSWORD",
+    "PII_KEYS",
+    "PII_INDEX_KEY",
+];
+
+static RESOLVED: OnceLock<HashMap<&'static str, String>> = OnceLock::new();
+
+// Resolves every secret once at startup; later calls (tests set the app up
+// more than once) keep what was resolved first. Must run before anything
+// calls `var`. Vault is only contacted when VAULT_ADDR and VAULT_SECRET_PATH are
+// set; the path is the full API path, e.g. `secret/data/quicker` for a KV v2
+// mount.
+pub async fn load(http: &HttpClient) -> Result<(), String> {
+    if RESOLVED.get().is_some() {
+        return Ok(());
+    }
+
+    let mut resolved = HashMap::new();
+    for &key in SECRETS {
+        if let Some(value) = from_env_or_file(key)? {
+            resolved.insert(key, value);
+        }
+    }
+
+    if let Some((addr, path, token)) = vault_settings()? {
+        let mut fetched = fetch_vault(http, &addr, &path, &token).await?;
+        for &key in SECRETS {
+            if let Some(value) = fetched.remove(key) {
+                resolved.entry(key).or_insert(value);
+            }
+        }
+        log::info!("loaded secrets from Vault at {}", path);
+    }
+
+    // Another setup may have finished first; both resolved the same values
+    let _ = RESOLVED.set(resolved);
+    Ok(())
+}
+
+// The value of a secret setting, wherever it came from.
+pub fn var(key: &str) -> Option<String> {
+    RESOLVED
+        .get()
+        .and_then(|resolved| resolved.get(key).cloned())
+        .or_else(|| env::var(key).ok())
+}
+
+// Resolves one secret afresh, for the few that can be rotated while the
+// server runs (the JWT signing keys). Env vars are fixed for the life of the
+// process, so only a changed `<KEY>_FILE` or Vault secret is picked up.
+pub async fn reload(http: &HttpClient, key: &str) -> Result<Option<String>, String> {
+    if let Some(value) = from_env_or_file(key)? {
+        return Ok(Some(value));
+    }
+    match vault_settings()? {
+        Some((addr, path, token)) => Ok(fetch_vault(http, &addr, &path, &token).await?.remove(key)),
+        None => Ok(None),
+    }
+}
+
+// Address, secret path and token, when Vault is configured
+fn vault_settings() -> Result<Option<(String, String, String)>, String> {
+    let (Ok(addr), Ok(path)) = (env::var("VAULT_ADDR"), env::var("VAULT_SECRET_PATH")) else {
+        return Ok(None);
+    };
+    let token = from_env_or_file("VAULT_TOKEN")?.ok_or("VAULT_TOKEN must be set when VAULT_ADDR is")?;
+    Ok(Some((addr, path, token)))
+}
+
+fn from_env_or_file(key: &str) -> Result<Option<String>, String> {
+    let file_key = format!("{}_FILE", key);
+    match (env::var(key), env::var(&file_key)) {
+        (Ok(_), Ok(_)) => Err(format!("Set either {} or {}, not both", key, file_key)),
+        (Ok(value), Err(_)) => Ok(Some(value)),
+        (Err(_), Ok(path)) => fs::read_to_string(&path)
+            .map(|value| Some(value.trim_end_matches(['\n', '\r']).to_string()))
+            .map_err(|e| format!("Failed to read {} from {}: {}", key, path, e)),
+        (Err(_), Err(_)) => Ok(None),
+    }
+}
+
+async fn fetch_vault(http: &HttpClient, addr: &str, path: &str, token: &str) -> Result<HashMap<String, String>, String> {
+    let url = format!("{}/v1/{}", addr.trim_end_matches('/'), path.trim_start_matches('/'));
+    let url = Url::parse(&url).map_err(|e| format!("Invalid VAULT_ADDR: {}", e))?;
+
+    let response = http
+        .trusted(Method::GET, url)
+        .header("X-Vault-Token", token)
+        .send()
+        .await
+        .map_err(|e| format!("Vault request failed: {}", e))?;
+
+    let status = response.status();
+    let body = response.text().await.map_err(|e| format!("Vault request failed: {}", e))?;
+    if !status.is_success() {
+        return Err(format!("Vault returned {} for {}", status, path));
+    }
+
+    vault_values(&body)
+}
+
+// KV v2 nests the fields under `data.data` next to `data.metadata`; KV v1
+// has them directly under `data`. Non-string fields are ignored.
+fn vault_values(body: &str) -> Result<HashMap<String, String>, String> {
+    let body: serde_json::Value = serde_json::from_str(body).map_err(|e| format!("Invalid Vault response: {}", e))?;
+    let data = &body["data"];
+    let fields = if data.get("metadata").is_some() { &data["data"] } else { data };
+    let fields = fields.as_object().ok_or("Vault response has no secret data")?;
+
+    Ok(fields
+        .iter()
+        .filter_map(|(key, value)| value.as_str().map(|value| (key.clone(), value.to_string())))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_kv_v2_fields() {
+        let body = r#"{"data": {"data": {"JWT_SECRET": "s3cret", "PORT": 5432}, "metadata": {"version": 3}}}"#;
+        let values = vault_values(body).unwrap();
+        assert_eq!(values.get("JWT_SECRET").map(String::as_str), Some("s3cret"));
+        assert!(!values.contains_key("PORT"));
+    }
+
+    #[test]
+    fn reads_kv_v1_fields() {
+        let body = r#"{"data": {"DATABASE_PASSWORD": "hunter2"}}"#;
+        let values = vault_values(body).unwrap();
+        assert_eq!(values.get("DATABASE_PASSWORD").map(String::as_str), Some("hunter2"));
+    }
+
+    #[test]
+    fn rejects_responses_without_data() {
+        assert!(vault_values(r#"{"errors": []}"#).is_err());
+    }
+}
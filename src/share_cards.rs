@@ -0,0 +1,324 @@
+use actix_web::http::header;
+use actix_web::{web, HttpRequest, HttpResponse};
+use chrono::{DateTime, Utc};
+use image::{ImageFormat, Rgb, RgbImage};
+use sha2::{Digest, Sha256};
+use sqlx::FromRow;
+use std::io::Cursor;
+use std::path::PathBuf;
+use uuid::Uuid;
+
+use crate::bitmap_font;
+use crate::config;
+use crate::error::{ApiError, ApiResult};
+use crate::AppState;
+
+// Bump when the layout changes so cached cards are rendered again
+const TEMPLATE_VERSION: u32 = 1;
+
+const WIDTH: u32 = 1200;
+const HEIGHT: u32 = 630;
+const PANEL_MARGIN: u32 = 48;
+const TEXT_LEFT: u32 = 104;
+const TEXT_WIDTH: u32 = 992;
+
+const NAME_SCALE: u32 = 6;
+const HANDLE_SCALE: u32 = 4;
+const BODY_SCALE: u32 = 4;
+const FOOTER_SCALE: u32 = 3;
+const BODY_TOP: u32 = 232;
+const BODY_LINES: usize = 6;
+
+const DEFAULT_COLOR: Rgb<u8> = Rgb([0x1d, 0xa1, 0xf2]);
+const PANEL: Rgb<u8> = Rgb([0xff, 0xff, 0xff]);
+const INK: Rgb<u8> = Rgb([0x14, 0x17, 0x1a]);
+const MUTED: Rgb<u8> = Rgb([0x65, 0x77, 0x86]);
+
+// Share cards: a 1200x630 PNG of a public tweet's text and author on the
+// instance's colors, for `og:image` and `twitter:image` tags. Cards are
+// rendered once and kept on disk under a hash of everything drawn on them,
+// so a new display name gets a new card and nothing needs invalidating.
+// Clients get a long max-age and the same hash as the ETag.
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.route("/api/tweets/{id}/card.png", web::get().to(get_card));
+}
+
+#[derive(Clone)]
+pub struct ShareCards {
+    pub cache_dir: PathBuf,
+    // SHARE_CARD_COLOR, e.g. `#1da1f2`
+    pub color: Rgb<u8>,
+}
+
+#[derive(Debug, FromRow)]
+struct CardTweet {
+    id: Uuid,
+    content: String,
+    created_at: DateTime<Utc>,
+    username: String,
+    display_name: String,
+}
+
+impl ShareCards {
+    pub fn from_env() -> Result<Self, String> {
+        let color = match config::var("SHARE_CARD_COLOR") {
+            Some(value) => {
+                parse_color(&value).ok_or_else(|| format!("SHARE_CARD_COLOR must be a hex color like #1d9bf0, got '{}'", value))?
+            }
+            None => DEFAULT_COLOR,
+        };
+
+        Ok(ShareCards {
+            cache_dir: config::var("SHARE_CARD_CACHE_DIR")
+                .unwrap_or_else(|| "./share_cards".to_string())
+                .into(),
+            color,
+        })
+    }
+
+    fn key(&self, tweet: &CardTweet, instance_name: &str) -> String {
+        let mut hasher = Sha256::new();
+        for part in [
+            TEMPLATE_VERSION.to_string(),
+            hex::encode(self.color.0),
+            instance_name.to_string(),
+            tweet.id.to_string(),
+            tweet.created_at.to_rfc3339(),
+            tweet.username.clone(),
+            tweet.display_name.clone(),
+            tweet.content.clone(),
+        ] {
+            hasher.update(part.as_bytes());
+            hasher.update([0]);
+        }
+        hex::encode(hasher.finalize())
+    }
+
+    async fn read_cache(&self, key: &str) -> Option<Vec<u8>> {
+        tokio::fs::read(self.cache_dir.join(format!("{}.png", key))).await.ok()
+    }
+
+    // Best effort, written under a temporary name like the media proxy cache.
+    async fn write_cache(&self, key: &str, png: &[u8]) {
+        let path = self.cache_dir.join(format!("{}.png", key));
+        let tmp = self.cache_dir.join(format!("{}.tmp", key));
+
+        let result = async {
+            tokio::fs::create_dir_all(&self.cache_dir).await?;
+            tokio::fs::write(&tmp, png).await?;
+            tokio::fs::rename(&tmp, &path).await
+        }
+        .await;
+
+        if let Err(e) = result {
+            log::warn!("failed to cache share card {}: {}", key, e);
+        }
+    }
+
+    // CPU-bound; run it off the async workers.
+    fn render(&self, tweet: &CardTweet, instance_name: &str) -> Result<Vec<u8>, image::ImageError> {
+        let mut canvas = RgbImage::from_pixel(WIDTH, HEIGHT, self.color);
+        fill_rect(
+            &mut canvas,
+            PANEL_MARGIN,
+            PANEL_MARGIN,
+            WIDTH - 2 * PANEL_MARGIN,
+            HEIGHT - 2 * PANEL_MARGIN,
+            PANEL,
+        );
+
+        let name = if tweet.display_name.trim().is_empty() { &tweet.username } else { &tweet.display_name };
+        draw_text(&mut canvas, &truncate(name, columns(NAME_SCALE)), TEXT_LEFT, 96, NAME_SCALE, INK);
+        let handle = format!("@{}", tweet.username);
+        draw_text(&mut canvas, &truncate(&handle, columns(HANDLE_SCALE)), TEXT_LEFT, 164, HANDLE_SCALE, MUTED);
+
+        let line_height = (bitmap_font::HEIGHT + 3) * BODY_SCALE;
+        for (i, line) in wrap(&tweet.content, columns(BODY_SCALE), BODY_LINES).iter().enumerate() {
+            draw_text(&mut canvas, line, TEXT_LEFT, BODY_TOP + i as u32 * line_height, BODY_SCALE, INK);
+        }
+
+        let footer = format!("{} - {}", instance_name, tweet.created_at.format("%b %-d, %Y"));
+        draw_text(&mut canvas, &truncate(&footer, columns(FOOTER_SCALE)), TEXT_LEFT, 534, FOOTER_SCALE, self.color);
+
+        let mut png = Vec::new();
+        canvas.write_to(&mut Cursor::new(&mut png), ImageFormat::Png)?;
+        Ok(png)
+    }
+}
+
+fn parse_color(value: &str) -> Option<Rgb<u8>> {
+    let value = value.trim().trim_start_matches('#');
+    if value.len() != 6 {
+        return None;
+    }
+    let rgb: [u8; 3] = hex::decode(value).ok()?.try_into().ok()?;
+    Some(Rgb(rgb))
+}
+
+// Characters of text at `scale` that fit the text column, one pixel column
+// of spacing between glyphs.
+fn columns(scale: u32) -> usize {
+    (TEXT_WIDTH / ((bitmap_font::WIDTH + 1) * scale)) as usize
+}
+
+fn truncate(text: &str, columns: usize) -> String {
+    if text.chars().count() <= columns {
+        return text.to_string();
+    }
+    let kept: String = text.chars().take(columns.saturating_sub(3)).collect();
+    format!("{}...", kept.trim_end())
+}
+
+// Word-wraps `text` to `columns` characters, keeping its line breaks and
+// splitting words too long for a line. Anything past `max_lines` is cut and
+// the last line ends in an ellipsis.
+fn wrap(text: &str, columns: usize, max_lines: usize) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    for paragraph in text.lines() {
+        let mut line = String::new();
+        for word in paragraph.split_whitespace() {
+            let mut word: Vec<char> = word.chars().collect();
+            while !word.is_empty() {
+                let used = line.chars().count();
+                let space = usize::from(used > 0);
+                if used + space + word.len() <= columns {
+                    if space == 1 {
+                        line.push(' ');
+                    }
+                    line.extend(word.drain(..));
+                } else if used > 0 {
+                    lines.push(std::mem::take(&mut line));
+                } else {
+                    line.extend(word.drain(..columns));
+                    lines.push(std::mem::take(&mut line));
+                }
+            }
+        }
+        // Blank lines in the tweet are kept, but only one in a row
+        if !line.is_empty() || lines.last().is_some_and(|last| !last.is_empty()) {
+            lines.push(line);
+        }
+    }
+    while lines.last().is_some_and(|last| last.is_empty()) {
+        lines.pop();
+    }
+
+    if lines.len() > max_lines {
+        lines.truncate(max_lines);
+        let last = lines.pop().unwrap_or_default();
+        let kept: String = last.chars().take(columns.saturating_sub(3)).collect();
+        lines.push(format!("{}...", kept.trim_end()));
+    }
+    lines
+}
+
+fn fill_rect(canvas: &mut RgbImage, x: u32, y: u32, width: u32, height: u32, color: Rgb<u8>) {
+    for py in y..(y + height).min(canvas.height()) {
+        for px in x..(x + width).min(canvas.width()) {
+            canvas.put_pixel(px, py, color);
+        }
+    }
+}
+
+fn draw_text(canvas: &mut RgbImage, text: &str, x: u32, y: u32, scale: u32, color: Rgb<u8>) {
+    let advance = (bitmap_font::WIDTH + 1) * scale;
+    for (i, c) in text.chars().enumerate() {
+        let glyph = bitmap_font::glyph(c);
+        let left = x + i as u32 * advance;
+        for gy in 0..bitmap_font::HEIGHT {
+            for gx in 0..bitmap_font::WIDTH {
+                if bitmap_font::is_set(glyph, gx, gy) {
+                    fill_rect(canvas, left + gx * scale, y + gy * scale, scale, scale, color);
+                }
+            }
+        }
+    }
+}
+
+// Anyone can fetch the card of a public tweet; crawlers building link
+// previews aren't signed in.
+async fn get_card(state: web::Data<AppState>, req: HttpRequest, tweet_id: web::Path<Uuid>) -> ApiResult<HttpResponse> {
+    let tweet = sqlx::query_as::<_, CardTweet>(
+        "SELECT t.id, t.content, t.created_at, u.username, u.display_name
+         FROM tweets t
+         INNER JOIN users u ON u.id = t.user_id
+         WHERE t.id = $1 AND t.deleted_at IS NULL AND u.deactivated_at IS NULL
+           AND tweet_visible_to(t.user_id, t.visibility, NULL)"
+    )
+    .bind(tweet_id.into_inner())
+    .fetch_optional(&state.db)
+    .await?
+    .ok_or_else(|| ApiError::NotFound("Tweet not found".to_string()))?;
+
+    let key = state.share_cards.key(&tweet, &state.instance.name);
+    let etag = format!("\"{}\"", key);
+    let cache_control = "public, max-age=86400, stale-while-revalidate=604800";
+
+    let if_none_match = req.headers().get(header::IF_NONE_MATCH).and_then(|v| v.to_str().ok());
+    if if_none_match == Some(etag.as_str()) {
+        return Ok(HttpResponse::NotModified()
+            .insert_header((header::ETAG, etag))
+            .insert_header((header::CACHE_CONTROL, cache_control))
+            .finish());
+    }
+
+    let png = match state.share_cards.read_cache(&key).await {
+        Some(png) => png,
+        None => {
+            let cards = state.share_cards.clone();
+            let instance_name = state.instance.name.clone();
+            let png = web::block(move || cards.render(&tweet, &instance_name))
+                .await
+                .map_err(|e| ApiError::Internal(e.to_string()))?
+                .map_err(|e| ApiError::Internal(format!("Failed to render share card: {}", e)))?;
+            state.share_cards.write_cache(&key, &png).await;
+            png
+        }
+    };
+
+    Ok(HttpResponse::Ok()
+        .content_type("image/png")
+        .insert_header((header::ETAG, etag))
+        .insert_header((header::CACHE_CONTROL, cache_control))
+        .body(png))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wraps_words_and_keeps_line_breaks() {
+        assert_eq!(wrap("the quick brown fox", 10, 5), vec!["the quick", "brown fox"]);
+        assert_eq!(wrap("one\n\n\n\ntwo  three", 20, 5), vec!["one", "", "two three"]);
+        assert_eq!(wrap("abcdefghijklmnop", 6, 5), vec!["abcdef", "ghijkl", "mnop"]);
+        assert_eq!(wrap("  \n", 10, 5), Vec::<String>::new());
+    }
+
+    #[test]
+    fn cuts_overflow_with_an_ellipsis() {
+        assert_eq!(wrap("aaa bbb ccc ddd eee", 7, 2), vec!["aaa bbb", "ccc..."]);
+        assert_eq!(truncate("Display Name", 8), "Displ...");
+        assert_eq!(truncate("Short", 8), "Short");
+    }
+
+    #[test]
+    fn renders_a_png() {
+        let cards = ShareCards {
+            cache_dir: PathBuf::from("unused"),
+            color: DEFAULT_COLOR,
+        };
+        let tweet = CardTweet {
+            id: Uuid::new_v4(),
+            content: "Hello from a share card! \u{1F600}".to_string(),
+            created_at: Utc::now(),
+            username: "alice".to_string(),
+            display_name: "Alice".to_string(),
+        };
+        let png = cards.render(&tweet, "quicker").unwrap();
+        let image = image::load_from_memory_with_format(&png, ImageFormat::Png).unwrap();
+        assert_eq!((image.width(), image.height()), (WIDTH, HEIGHT));
+        assert_eq!(parse_color("#1DA1F2"), Some(DEFAULT_COLOR));
+        assert_eq!(parse_color("blue"), None);
+    }
+}
@@ -0,0 +1,35 @@
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use uuid::Uuid;
+
+// Short ids for `/t/{short_id}` share links: the tweet's UUID as 22
+// characters of unpadded base64url instead of 36 of hex. Nothing is stored;
+// any tweet id maps to exactly one short id and back.
+pub fn encode(tweet_id: Uuid) -> String {
+    URL_SAFE_NO_PAD.encode(tweet_id.as_bytes())
+}
+
+pub fn decode(short_id: &str) -> Option<Uuid> {
+    let bytes = URL_SAFE_NO_PAD.decode(short_id).ok()?;
+    Uuid::from_slice(&bytes).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn short_ids_round_trip() {
+        let id = Uuid::parse_str("6f1c2a3e-9b4d-4e8f-a1b2-c3d4e5f60718").unwrap();
+        let short = encode(id);
+        assert_eq!(short.len(), 22);
+        assert_eq!(decode(&short), Some(id));
+    }
+
+    #[test]
+    fn rejects_malformed_short_ids() {
+        assert_eq!(decode(""), None);
+        assert_eq!(decode("not a short id"), None);
+        assert_eq!(decode("AAAA"), None);
+    }
+}
@@ -0,0 +1,265 @@
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use reqwest::{Method, Url};
+use sha2::{Digest, Sha256};
+use std::future::Future;
+use std::io;
+use std::path::PathBuf;
+use std::pin::Pin;
+use std::time::Duration;
+
+use crate::config;
+use crate::http_client::HttpClient;
+use crate::secrets;
+
+const UPLOAD_TIMEOUT: Duration = Duration::from_secs(30);
+
+// Where uploaded files end up. Chosen with MEDIA_STORAGE: `local` (default)
+// keeps them on the app server's disk, `s3` sends them to any S3-compatible
+// object store so app servers stay stateless.
+pub trait MediaStorage: Send + Sync {
+    fn put<'a>(
+        &'a self,
+        key: &'a str,
+        bytes: Vec<u8>,
+        content_type: &'a str,
+    ) -> Pin<Box<dyn Future<Output = io::Result<()>> + 'a>>;
+
+    // Public URL the stored object is served from
+    fn url(&self, key: &str) -> String;
+
+    // Directory the app itself should serve at /media, if any
+    fn local_dir(&self) -> Option<PathBuf> {
+        None
+    }
+}
+
+pub fn from_env(base_url: &str, http: HttpClient) -> Result<Box<dyn MediaStorage>, String> {
+    match config::var("MEDIA_STORAGE").as_deref() {
+        None | Some("local") => Ok(Box::new(LocalStorage {
+            dir: config::var("MEDIA_UPLOAD_DIR").unwrap_or_else(|| "./uploads".to_string()).into(),
+            base_url: base_url.to_string(),
+        })),
+        Some("s3") => Ok(Box::new(S3Storage::from_env(http)?)),
+        Some(other) => Err(format!("MEDIA_STORAGE must be 'local' or 's3', got '{}'", other)),
+    }
+}
+
+// Stores uploads under `dir`, served by the app at `/media/{key}`.
+pub struct LocalStorage {
+    dir: PathBuf,
+    base_url: String,
+}
+
+impl MediaStorage for LocalStorage {
+    fn put<'a>(
+        &'a self,
+        key: &'a str,
+        bytes: Vec<u8>,
+        _content_type: &'a str,
+    ) -> Pin<Box<dyn Future<Output = io::Result<()>> + 'a>> {
+        Box::pin(async move {
+            tokio::fs::create_dir_all(&self.dir).await?;
+            tokio::fs::write(self.dir.join(key), bytes).await
+        })
+    }
+
+    fn url(&self, key: &str) -> String {
+        format!("{}/media/{}", self.base_url, key)
+    }
+
+    fn local_dir(&self) -> Option<PathBuf> {
+        Some(self.dir.clone())
+    }
+}
+
+// S3-compatible bucket (AWS, MinIO, R2, ...). Objects are written with a
+// SigV4-signed PUT to `{endpoint}/{bucket}/{key}` (path-style, which every
+// implementation supports) and served from S3_PUBLIC_URL, e.g. a CDN in front
+// of the bucket.
+pub struct S3Storage {
+    endpoint: Url,
+    bucket: String,
+    region: String,
+    access_key_id: String,
+    secret_access_key: String,
+    public_url: String,
+    http: HttpClient,
+    // Endpoints users configured go through the SSRF-safe client
+    user_supplied: bool,
+}
+
+impl S3Storage {
+    pub fn from_env(http: HttpClient) -> Result<Self, String> {
+        let required = |key: &str| config::var(key).ok_or_else(|| format!("{} must be set when MEDIA_STORAGE=s3", key));
+        let required_secret = |key: &str| secrets::var(key).ok_or_else(|| format!("{} must be set when MEDIA_STORAGE=s3", key));
+
+        let bucket = required("S3_BUCKET")?;
+        let region = config::var("S3_REGION").unwrap_or_else(|| "us-east-1".to_string());
+        let endpoint = config::var("S3_ENDPOINT").unwrap_or_else(|| format!("https://s3.{}.amazonaws.com", region));
+        let endpoint = Url::parse(endpoint.trim_end_matches('/')).map_err(|e| format!("Invalid S3_ENDPOINT: {}", e))?;
+        let public_url = config::var("S3_PUBLIC_URL")
+            .unwrap_or_else(|| format!("{}/{}", endpoint.as_str().trim_end_matches('/'), bucket));
+
+        Ok(S3Storage {
+            endpoint,
+            bucket,
+            region,
+            access_key_id: required_secret("S3_ACCESS_KEY_ID")?,
+            secret_access_key: required_secret("S3_SECRET_ACCESS_KEY")?,
+            public_url: public_url.trim_end_matches('/').to_string(),
+            http,
+            user_supplied: false,
+        })
+    }
+
+    // A bucket a user connected, e.g. for content backups
+    pub fn user_bucket(
+        endpoint: &str,
+        bucket: &str,
+        region: &str,
+        access_key_id: &str,
+        secret_access_key: &str,
+        http: HttpClient,
+    ) -> Result<Self, String> {
+        let endpoint = Url::parse(endpoint.trim_end_matches('/')).map_err(|e| format!("Invalid endpoint: {}", e))?;
+        if endpoint.scheme() != "https" {
+            return Err("The endpoint must use https".to_string());
+        }
+        if bucket.is_empty() || bucket.contains('/') {
+            return Err("Invalid bucket name".to_string());
+        }
+
+        Ok(S3Storage {
+            public_url: format!("{}/{}", endpoint.as_str().trim_end_matches('/'), bucket),
+            endpoint,
+            bucket: bucket.to_string(),
+            region: region.to_string(),
+            access_key_id: access_key_id.to_string(),
+            secret_access_key: secret_access_key.to_string(),
+            http,
+            user_supplied: true,
+        })
+    }
+}
+
+impl MediaStorage for S3Storage {
+    fn put<'a>(
+        &'a self,
+        key: &'a str,
+        bytes: Vec<u8>,
+        content_type: &'a str,
+    ) -> Pin<Box<dyn Future<Output = io::Result<()>> + 'a>> {
+        Box::pin(async move {
+            let path = format!("{}/{}/{}", self.endpoint.path().trim_end_matches('/'), self.bucket, key);
+            let mut url = self.endpoint.clone();
+            url.set_path(&path);
+
+            let host = match url.port() {
+                Some(port) => format!("{}:{}", url.host_str().unwrap_or_default(), port),
+                None => url.host_str().unwrap_or_default().to_string(),
+            };
+            let payload_hash = hex::encode(Sha256::digest(&bytes));
+            let now = Utc::now();
+            let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+
+            let headers = [
+                ("content-type", content_type),
+                ("host", host.as_str()),
+                ("x-amz-content-sha256", payload_hash.as_str()),
+                ("x-amz-date", amz_date.as_str()),
+            ];
+            let authorization = sign_v4(
+                &SigningKey {
+                    access_key_id: &self.access_key_id,
+                    secret_access_key: &self.secret_access_key,
+                    region: &self.region,
+                },
+                "PUT",
+                url.path(),
+                &headers,
+                &payload_hash,
+                now,
+            );
+
+            let request = if self.user_supplied {
+                self.http.public(Method::PUT, url).map_err(io::Error::other)?
+            } else {
+                self.http.trusted(Method::PUT, url)
+            };
+            let response = request
+                .header("Content-Type", content_type)
+                .header("x-amz-content-sha256", &payload_hash)
+                .header("x-amz-date", &amz_date)
+                .header("Authorization", &authorization)
+                .body(bytes)
+                .timeout(UPLOAD_TIMEOUT)
+                .send()
+                .await
+                .map_err(io::Error::other)?;
+
+            if !response.status().is_success() {
+                let status = response.status();
+                let body = response.text().await.unwrap_or_default();
+                return Err(io::Error::other(format!("S3 PUT {} failed with {}: {}", key, status, body)));
+            }
+
+            Ok(())
+        })
+    }
+
+    fn url(&self, key: &str) -> String {
+        format!("{}/{}", self.public_url, key)
+    }
+}
+
+struct SigningKey<'a> {
+    access_key_id: &'a str,
+    secret_access_key: &'a str,
+    region: &'a str,
+}
+
+// AWS Signature Version 4 `Authorization` header for an S3 request without a
+// query string. `headers` must be lowercase, sorted by name, and include
+// host, x-amz-date and x-amz-content-sha256.
+fn sign_v4(
+    key: &SigningKey,
+    method: &str,
+    path: &str,
+    headers: &[(&str, &str)],
+    payload_hash: &str,
+    now: DateTime<Utc>,
+) -> String {
+    let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+    let date = now.format("%Y%m%d").to_string();
+    let scope = format!("{}/{}/s3/aws4_request", date, key.region);
+
+    let canonical_headers: String = headers.iter().map(|(name, value)| format!("{}:{}\n", name, value.trim())).collect();
+    let signed_headers = headers.iter().map(|(name, _)| *name).collect::<Vec<_>>().join(";");
+    let canonical_request = format!(
+        "{}\n{}\n\n{}\n{}\n{}",
+        method, path, canonical_headers, signed_headers, payload_hash
+    );
+    let string_to_sign = format!(
+        "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+        amz_date,
+        scope,
+        hex::encode(Sha256::digest(canonical_request.as_bytes()))
+    );
+
+    let signing_key = [date.as_str(), key.region, "s3", "aws4_request"]
+        .iter()
+        .fold(format!("AWS4{}", key.secret_access_key).into_bytes(), |k, part| hmac_sha256(&k, part.as_bytes()));
+    let signature = hex::encode(hmac_sha256(&signing_key, string_to_sign.as_bytes()));
+
+    format!(
+        "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders={}, Signature={}",
+        key.access_key_id, scope, signed_headers, signature
+    )
+}
+
+fn hmac_sha256(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts any key length");
+    mac.update(data);
+    mac.finalize().into_bytes().to_vec()
+}
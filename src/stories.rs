@@ -0,0 +1,306 @@
+use actix_web::{web, HttpResponse};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, PgPool};
+use uuid::Uuid;
+
+use crate::auth::AuthenticatedUser;
+use crate::error::{ApiError, ApiResult};
+use crate::models::{ApiResponse, TweetVisibility};
+use crate::AppState;
+
+const STORY_LIFETIME: Duration = Duration::hours(24);
+const MAX_ACTIVE_STORIES: i64 = 50;
+
+// Stories: an uploaded image with a short caption that disappears after 24
+// hours. Followers see a "ring" of accounts with active stories, unseen ones
+// first; opening a story records a view the author can list. The scheduler
+// deletes expired stories along with their views.
+//
+// Visibility follows the tweet rules (`tweet_visible_to`), and blocks hide
+// stories both ways.
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.route("/api/stories", web::post().to(create_story))
+        .route("/api/stories/ring", web::get().to(get_ring))
+        .route("/api/stories/{id}", web::delete().to(delete_story))
+        .route("/api/stories/{id}/views", web::post().to(view_story))
+        .route("/api/stories/{id}/views", web::get().to(get_views))
+        .route("/api/users/{username}/stories", web::get().to(get_user_stories));
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateStoryRequest {
+    // An upload from /api/media/upload
+    pub media_id: Uuid,
+    #[serde(default)]
+    pub caption: String,
+    #[serde(default)]
+    pub visibility: TweetVisibility,
+}
+
+#[derive(Debug, Serialize, FromRow)]
+pub struct Story {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub image_url: String,
+    pub caption: String,
+    pub visibility: String,
+    pub created_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+    // Whether the viewer has opened it; always true for the author
+    pub viewed: bool,
+}
+
+#[derive(Debug, Serialize, FromRow)]
+pub struct RingEntry {
+    pub user_id: Uuid,
+    pub username: String,
+    pub display_name: String,
+    pub profile_image: Option<String>,
+    pub stories: i64,
+    pub unseen: i64,
+    pub latest_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, FromRow)]
+pub struct StoryViewer {
+    pub user_id: Uuid,
+    pub username: String,
+    pub display_name: String,
+    pub profile_image: Option<String>,
+    pub viewed_at: DateTime<Utc>,
+}
+
+// Live stories the viewer may see; `$1` is the viewer
+const VISIBLE_STORY: &str = "s.expires_at > NOW()
+    AND tweet_visible_to(s.user_id, s.visibility, $1)
+    AND NOT EXISTS (
+        SELECT 1 FROM blocks b
+        WHERE (b.blocker_id = s.user_id AND b.blocked_id = $1) OR (b.blocker_id = $1 AND b.blocked_id = s.user_id)
+    )";
+
+// Run by the scheduler. Returns how many stories were deleted.
+pub async fn delete_expired(db: &PgPool) -> Result<u64, sqlx::Error> {
+    let deleted = sqlx::query("DELETE FROM stories WHERE expires_at <= NOW()")
+        .execute(db)
+        .await?
+        .rows_affected();
+
+    if deleted > 0 {
+        log::info!("Deleted {} expired stories", deleted);
+    }
+    Ok(deleted)
+}
+
+async fn create_story(
+    state: web::Data<AppState>,
+    AuthenticatedUser(user_id): AuthenticatedUser,
+    req: web::Json<CreateStoryRequest>,
+) -> ApiResult<HttpResponse> {
+    let req = req.into_inner();
+    let caption = req.caption.trim();
+    if caption.chars().count() > state.instance.max_tweet_length {
+        return Err(ApiError::BadRequest(format!(
+            "Caption must be at most {} characters",
+            state.instance.max_tweet_length
+        )));
+    }
+    if state.instance.max_media_per_tweet == 0 {
+        return Err(ApiError::BadRequest("Media is disabled on this instance".to_string()));
+    }
+    state.instance.ensure_verified(&state.db, user_id).await?;
+    state.abuse.ensure_not_throttled(&state.db, user_id).await?;
+
+    let image_url = sqlx::query_scalar::<_, String>("SELECT url FROM media WHERE id = $1 AND user_id = $2")
+        .bind(req.media_id)
+        .bind(user_id)
+        .fetch_optional(&state.db)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("Media not found".to_string()))?;
+
+    let active = sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM stories WHERE user_id = $1 AND expires_at > NOW()")
+        .bind(user_id)
+        .fetch_one(&state.db)
+        .await?;
+    if active >= MAX_ACTIVE_STORIES {
+        return Err(ApiError::Forbidden(format!("You can have at most {} active stories", MAX_ACTIVE_STORIES)));
+    }
+
+    let story = sqlx::query_as::<_, Story>(
+        "INSERT INTO stories (user_id, media_id, image_url, caption, visibility, expires_at)
+         VALUES ($1, $2, $3, $4, $5, $6)
+         RETURNING id, user_id, image_url, caption, visibility, created_at, expires_at, TRUE AS viewed"
+    )
+    .bind(user_id)
+    .bind(req.media_id)
+    .bind(&image_url)
+    .bind(caption)
+    .bind(req.visibility.as_str())
+    .bind(Utc::now() + STORY_LIFETIME)
+    .fetch_one(&state.db)
+    .await?;
+
+    Ok(HttpResponse::Created().json(ApiResponse {
+        success: true,
+        data: Some(story),
+        message: None,
+        next_cursor: None,
+    }))
+}
+
+// Followed accounts with stories the viewer may see, those with unseen
+// stories first, then most recent first. Muted accounts are left out.
+async fn get_ring(state: web::Data<AppState>, AuthenticatedUser(user_id): AuthenticatedUser) -> ApiResult<HttpResponse> {
+    let ring = sqlx::query_as::<_, RingEntry>(&format!(
+        "SELECT * FROM (
+             SELECT u.id AS user_id, u.username, u.display_name, u.profile_image,
+                    COUNT(*) AS stories,
+                    COUNT(*) FILTER (WHERE NOT EXISTS (
+                        SELECT 1 FROM story_views v WHERE v.story_id = s.id AND v.viewer_id = $1
+                    )) AS unseen,
+                    MAX(s.created_at) AS latest_at
+             FROM follows f
+             INNER JOIN users u ON u.id = f.following_id AND u.deactivated_at IS NULL
+             INNER JOIN stories s ON s.user_id = f.following_id
+             WHERE f.follower_id = $1
+               AND NOT EXISTS (SELECT 1 FROM mutes m WHERE m.muter_id = $1 AND m.muted_id = u.id)
+               AND {}
+             GROUP BY u.id
+         ) ring
+         ORDER BY ring.unseen > 0 DESC, ring.latest_at DESC",
+        VISIBLE_STORY
+    ))
+    .bind(user_id)
+    .fetch_all(&state.db)
+    .await?;
+
+    Ok(HttpResponse::Ok().json(ApiResponse {
+        success: true,
+        data: Some(ring),
+        message: None,
+        next_cursor: None,
+    }))
+}
+
+// Oldest first, the order they're played in.
+async fn get_user_stories(
+    state: web::Data<AppState>,
+    AuthenticatedUser(user_id): AuthenticatedUser,
+    username: web::Path<String>,
+) -> ApiResult<HttpResponse> {
+    let author_id = sqlx::query_scalar::<_, Uuid>("SELECT id FROM users WHERE username = $1 AND deactivated_at IS NULL")
+        .bind(username.into_inner())
+        .fetch_optional(&state.db)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("User not found".to_string()))?;
+
+    let stories = sqlx::query_as::<_, Story>(&format!(
+        "SELECT s.id, s.user_id, s.image_url, s.caption, s.visibility, s.created_at, s.expires_at,
+                s.user_id = $1 OR EXISTS (
+                    SELECT 1 FROM story_views v WHERE v.story_id = s.id AND v.viewer_id = $1
+                ) AS viewed
+         FROM stories s
+         WHERE s.user_id = $2 AND {}
+         ORDER BY s.created_at",
+        VISIBLE_STORY
+    ))
+    .bind(user_id)
+    .bind(author_id)
+    .fetch_all(&state.db)
+    .await?;
+
+    Ok(HttpResponse::Ok().json(ApiResponse {
+        success: true,
+        data: Some(stories),
+        message: None,
+        next_cursor: None,
+    }))
+}
+
+// Marks a story as seen. The author's own views aren't recorded.
+async fn view_story(
+    state: web::Data<AppState>,
+    AuthenticatedUser(user_id): AuthenticatedUser,
+    story_id: web::Path<Uuid>,
+) -> ApiResult<HttpResponse> {
+    let story_id = story_id.into_inner();
+    let author_id = sqlx::query_scalar::<_, Uuid>(&format!("SELECT s.user_id FROM stories s WHERE s.id = $2 AND {}", VISIBLE_STORY))
+        .bind(user_id)
+        .bind(story_id)
+        .fetch_optional(&state.db)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("Story not found".to_string()))?;
+
+    if author_id != user_id {
+        sqlx::query("INSERT INTO story_views (story_id, viewer_id) VALUES ($1, $2) ON CONFLICT DO NOTHING")
+            .bind(story_id)
+            .bind(user_id)
+            .execute(&state.db)
+            .await?;
+    }
+
+    Ok(HttpResponse::Ok().json(ApiResponse::<()> {
+        success: true,
+        data: None,
+        message: None,
+        next_cursor: None,
+    }))
+}
+
+// Author only, most recent first.
+async fn get_views(
+    state: web::Data<AppState>,
+    AuthenticatedUser(user_id): AuthenticatedUser,
+    story_id: web::Path<Uuid>,
+) -> ApiResult<HttpResponse> {
+    let story_id = story_id.into_inner();
+    let owned = sqlx::query_scalar::<_, bool>("SELECT EXISTS(SELECT 1 FROM stories WHERE id = $1 AND user_id = $2)")
+        .bind(story_id)
+        .bind(user_id)
+        .fetch_one(&state.db)
+        .await?;
+    if !owned {
+        return Err(ApiError::NotFound("Story not found".to_string()));
+    }
+
+    let viewers = sqlx::query_as::<_, StoryViewer>(
+        "SELECT u.id AS user_id, u.username, u.display_name, u.profile_image, v.viewed_at
+         FROM story_views v
+         INNER JOIN users u ON u.id = v.viewer_id
+         WHERE v.story_id = $1
+         ORDER BY v.viewed_at DESC"
+    )
+    .bind(story_id)
+    .fetch_all(&state.db)
+    .await?;
+
+    Ok(HttpResponse::Ok().json(ApiResponse {
+        success: true,
+        data: Some(viewers),
+        message: None,
+        next_cursor: None,
+    }))
+}
+
+async fn delete_story(
+    state: web::Data<AppState>,
+    AuthenticatedUser(user_id): AuthenticatedUser,
+    story_id: web::Path<Uuid>,
+) -> ApiResult<HttpResponse> {
+    let result = sqlx::query("DELETE FROM stories WHERE id = $1 AND user_id = $2")
+        .bind(story_id.into_inner())
+        .bind(user_id)
+        .execute(&state.db)
+        .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(ApiError::NotFound("Story not found".to_string()));
+    }
+
+    Ok(HttpResponse::Ok().json(ApiResponse {
+        success: true,
+        data: Some("Story deleted successfully"),
+        message: None,
+        next_cursor: None,
+    }))
+}
@@ -0,0 +1,520 @@
+use actix_web::{web, HttpResponse};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, PgPool};
+use std::collections::{HashMap, HashSet};
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::auth::AdminUser;
+use crate::config;
+use crate::error::{ApiError, ApiResult};
+use crate::hashtags;
+use crate::models::ApiResponse;
+use crate::AppState;
+
+const WORLDWIDE: i32 = 1;
+const MAX_TOPICS: usize = 50;
+const MAX_REGION_RADIUS_KM: f64 = 2000.0;
+const EARTH_RADIUS_KM: f64 = 6371.0;
+
+// Words that never make a phrase on their own
+const STOPWORDS: &[&str] = &[
+    "a", "about", "after", "all", "am", "an", "and", "are", "as", "at", "be", "been", "but", "by", "can", "do", "for",
+    "from", "get", "got", "had", "has", "have", "he", "her", "his", "how", "i", "if", "in", "is", "it", "its", "just",
+    "me", "my", "no", "not", "of", "on", "or", "our", "out", "rt", "she", "so", "that", "the", "their", "them", "they",
+    "this", "to", "up", "us", "was", "we", "what", "when", "who", "will", "with", "you", "your",
+];
+
+// Trending topics: every TRENDS_REFRESH_SECS the scheduler ranks the
+// hashtags and two-word phrases in recent public tweets over the last hour
+// and the last 24 hours, and stores the top of each ranking per region in
+// `trending_topics`. Topics are ranked by how many accounts used them, then
+// by tweet count, and need TRENDS_MIN_AUTHORS accounts to place at all, so
+// one busy account can't trend on its own. At most TRENDS_SAMPLE_SIZE of the
+// newest tweets are looked at per refresh.
+//
+// Regions are picked with `woeid`, as on Twitter: 1 is worldwide, and admins
+// can add circular regions, which count the location-tagged tweets inside.
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.route("/api/trends", web::get().to(get_trends))
+        .route("/api/trends/available", web::get().to(get_regions))
+        .route("/api/admin/trends/regions", web::post().to(create_region))
+        .route("/api/admin/trends/regions/{woeid}", web::delete().to(delete_region));
+}
+
+#[derive(Debug, Clone)]
+pub struct Trends {
+    refresh_secs: i64,
+    sample_size: i64,
+    min_authors: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
+pub enum Window {
+    #[serde(rename = "1h")]
+    Hour,
+    #[serde(rename = "24h")]
+    Day,
+}
+
+impl Window {
+    const ALL: [Window; 2] = [Window::Hour, Window::Day];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Window::Hour => "1h",
+            Window::Day => "24h",
+        }
+    }
+
+    fn duration(&self) -> Duration {
+        match self {
+            Window::Hour => Duration::hours(1),
+            Window::Day => Duration::hours(24),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum TopicKind {
+    Hashtag,
+    Phrase,
+}
+
+impl TopicKind {
+    fn as_str(&self) -> &'static str {
+        match self {
+            TopicKind::Hashtag => "hashtag",
+            TopicKind::Phrase => "phrase",
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TrendsQuery {
+    pub woeid: Option<i32>,
+    pub window: Option<Window>,
+    pub limit: Option<i64>,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct CreateRegionRequest {
+    pub woeid: i32,
+    #[validate(length(min = 1, max = 100))]
+    pub name: String,
+    pub latitude: f64,
+    pub longitude: f64,
+    pub radius_km: f64,
+}
+
+#[derive(Debug, Serialize, FromRow)]
+pub struct Region {
+    pub woeid: i32,
+    pub name: String,
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+    pub radius_km: Option<f64>,
+    pub computed_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Serialize, FromRow)]
+pub struct TrendingTopic {
+    pub topic: String,
+    pub kind: String,
+    pub tweet_count: i32,
+    pub author_count: i32,
+    pub rank: i32,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TrendsResponse {
+    pub woeid: i32,
+    pub name: String,
+    pub window: &'static str,
+    // None until the region's first refresh
+    pub as_of: Option<DateTime<Utc>>,
+    pub topics: Vec<TrendingTopic>,
+}
+
+#[derive(Debug, FromRow)]
+struct SampleTweet {
+    user_id: Uuid,
+    content: String,
+    created_at: DateTime<Utc>,
+    latitude: Option<f64>,
+    longitude: Option<f64>,
+}
+
+#[derive(Debug, PartialEq)]
+struct RankedTopic {
+    text: String,
+    kind: TopicKind,
+    tweet_count: i32,
+    author_count: i32,
+}
+
+impl Trends {
+    pub fn from_env() -> Result<Self, String> {
+        Ok(Trends {
+            refresh_secs: config::positive_var("TRENDS_REFRESH_SECS", 300)?,
+            sample_size: config::positive_var("TRENDS_SAMPLE_SIZE", 20_000)?,
+            min_authors: config::parse_var("TRENDS_MIN_AUTHORS", 3)?,
+        })
+    }
+
+    // Run by the scheduler: refreshes the regions that are due. Regions are
+    // locked while they're computed, so instances never refresh the same one
+    // at once.
+    pub async fn tick(&self, db: &PgPool) -> Result<(), sqlx::Error> {
+        let mut tx = db.begin().await?;
+
+        let regions = sqlx::query_as::<_, Region>(
+            "SELECT woeid, name, latitude, longitude, radius_km, computed_at FROM trend_regions
+             WHERE computed_at IS NULL OR computed_at <= NOW() - make_interval(secs => $1)
+             FOR UPDATE SKIP LOCKED"
+        )
+        .bind(self.refresh_secs as f64)
+        .fetch_all(&mut *tx)
+        .await?;
+        if regions.is_empty() {
+            return Ok(());
+        }
+
+        let sample = sqlx::query_as::<_, SampleTweet>(
+            "SELECT t.user_id, t.content, t.created_at, l.latitude, l.longitude
+             FROM tweets t
+             INNER JOIN users u ON u.id = t.user_id
+             LEFT JOIN tweet_locations l ON l.tweet_id = t.id AND u.share_location
+             WHERE t.created_at > NOW() - INTERVAL '24 hours'
+               AND t.deleted_at IS NULL AND t.visibility = 'public' AND u.deactivated_at IS NULL
+             ORDER BY t.created_at DESC
+             LIMIT $1"
+        )
+        .bind(self.sample_size)
+        .fetch_all(&mut *tx)
+        .await?;
+
+        let now = Utc::now();
+        let topics: Vec<Vec<(String, TopicKind)>> = sample.iter().map(|tweet| topics_in(&tweet.content)).collect();
+
+        for region in &regions {
+            let in_region: Vec<usize> = (0..sample.len()).filter(|&i| region.contains(&sample[i])).collect();
+
+            sqlx::query("DELETE FROM trending_topics WHERE woeid = $1")
+                .bind(region.woeid)
+                .execute(&mut *tx)
+                .await?;
+
+            for window in Window::ALL {
+                let since = now - window.duration();
+                let tweets = in_region
+                    .iter()
+                    .filter(|&&i| sample[i].created_at > since)
+                    .map(|&i| (sample[i].user_id, topics[i].as_slice()));
+                let ranked = rank(tweets, self.min_authors, MAX_TOPICS);
+
+                sqlx::query(
+                    "INSERT INTO trending_topics (woeid, time_window, topic, kind, tweet_count, author_count, rank)
+                     SELECT $1, $2, topic, kind, tweet_count, author_count, rank
+                     FROM UNNEST($3::text[], $4::text[], $5::int[], $6::int[]) WITH ORDINALITY
+                          AS r(topic, kind, tweet_count, author_count, rank)"
+                )
+                .bind(region.woeid)
+                .bind(window.as_str())
+                .bind(ranked.iter().map(|t| t.text.clone()).collect::<Vec<_>>())
+                .bind(ranked.iter().map(|t| t.kind.as_str()).collect::<Vec<_>>())
+                .bind(ranked.iter().map(|t| t.tweet_count).collect::<Vec<_>>())
+                .bind(ranked.iter().map(|t| t.author_count).collect::<Vec<_>>())
+                .execute(&mut *tx)
+                .await?;
+            }
+
+            sqlx::query("UPDATE trend_regions SET computed_at = NOW() WHERE woeid = $1")
+                .bind(region.woeid)
+                .execute(&mut *tx)
+                .await?;
+        }
+
+        tx.commit().await?;
+        Ok(())
+    }
+}
+
+impl Region {
+    fn contains(&self, tweet: &SampleTweet) -> bool {
+        match (self.latitude, self.longitude, self.radius_km) {
+            (Some(lat), Some(lon), Some(radius_km)) => match (tweet.latitude, tweet.longitude) {
+                (Some(tweet_lat), Some(tweet_lon)) => distance_km(lat, lon, tweet_lat, tweet_lon) <= radius_km,
+                _ => false,
+            },
+            _ => true,
+        }
+    }
+}
+
+// Great-circle distance (haversine)
+fn distance_km(lat1: f64, lon1: f64, lat2: f64, lon2: f64) -> f64 {
+    let (lat1, lat2) = (lat1.to_radians(), lat2.to_radians());
+    let d_lat = lat2 - lat1;
+    let d_lon = (lon2 - lon1).to_radians();
+    let a = (d_lat / 2.0).sin().powi(2) + lat1.cos() * lat2.cos() * (d_lon / 2.0).sin().powi(2);
+    2.0 * EARTH_RADIUS_KM * a.sqrt().asin()
+}
+
+// Hashtags (with their '#') and two-word phrases in a tweet, each once.
+fn topics_in(content: &str) -> Vec<(String, TopicKind)> {
+    let mut topics: Vec<(String, TopicKind)> = hashtags::extract(content)
+        .into_iter()
+        .map(|tag| (format!("#{}", tag), TopicKind::Hashtag))
+        .collect();
+    for phrase in phrases(content) {
+        if !topics.iter().any(|(text, _)| *text == phrase) {
+            topics.push((phrase, TopicKind::Phrase));
+        }
+    }
+    topics
+}
+
+// Adjacent pairs of words, neither of them a stopword. Hashtags, mentions
+// and links break a phrase, as does punctuation between the words.
+fn phrases(content: &str) -> Vec<String> {
+    let mut phrases = Vec::new();
+    let mut prev: Option<String> = None;
+
+    for token in content.split_whitespace() {
+        let breaks_after = token.ends_with(['.', ',', ';', ':', '!', '?']);
+        let word = token.trim_matches(|c: char| !c.is_alphanumeric()).to_lowercase();
+        let is_word = !token.starts_with(['#', '@'])
+            && !token.contains("://")
+            && word.chars().count() >= 2
+            && word.chars().all(char::is_alphanumeric)
+            && !word.chars().all(|c| c.is_ascii_digit())
+            && !STOPWORDS.contains(&word.as_str());
+
+        if !is_word {
+            prev = None;
+            continue;
+        }
+        if let Some(prev) = prev.take() {
+            let phrase = format!("{} {}", prev, word);
+            if !phrases.contains(&phrase) {
+                phrases.push(phrase);
+            }
+        }
+        prev = (!breaks_after).then_some(word);
+    }
+
+    phrases
+}
+
+fn rank<'a>(tweets: impl Iterator<Item = (Uuid, &'a [(String, TopicKind)])>, min_authors: usize, limit: usize) -> Vec<RankedTopic> {
+    let mut counts: HashMap<&'a (String, TopicKind), (i32, HashSet<Uuid>)> = HashMap::new();
+    for (author, topics) in tweets {
+        for topic in topics {
+            let (tweet_count, authors) = counts.entry(topic).or_default();
+            *tweet_count += 1;
+            authors.insert(author);
+        }
+    }
+
+    let mut ranked: Vec<RankedTopic> = counts
+        .into_iter()
+        .filter(|(_, (_, authors))| authors.len() >= min_authors.max(1))
+        .map(|((text, kind), (tweet_count, authors))| RankedTopic {
+            text: text.clone(),
+            kind: *kind,
+            tweet_count,
+            author_count: authors.len() as i32,
+        })
+        .collect();
+    ranked.sort_by(|a, b| {
+        b.author_count
+            .cmp(&a.author_count)
+            .then(b.tweet_count.cmp(&a.tweet_count))
+            .then_with(|| a.text.cmp(&b.text))
+    });
+    ranked.truncate(limit);
+    ranked
+}
+
+async fn get_trends(state: web::Data<AppState>, query: web::Query<TrendsQuery>) -> ApiResult<HttpResponse> {
+    let woeid = query.woeid.unwrap_or(WORLDWIDE);
+    let window = query.window.unwrap_or(Window::Day);
+    let limit = query.limit.unwrap_or(20).clamp(1, MAX_TOPICS as i64);
+
+    let region = sqlx::query_as::<_, Region>(
+        "SELECT woeid, name, latitude, longitude, radius_km, computed_at FROM trend_regions WHERE woeid = $1"
+    )
+    .bind(woeid)
+    .fetch_optional(&state.db)
+    .await?
+    .ok_or_else(|| ApiError::NotFound("Unknown woeid".to_string()))?;
+
+    let topics = sqlx::query_as::<_, TrendingTopic>(
+        "SELECT topic, kind, tweet_count, author_count, rank FROM trending_topics
+         WHERE woeid = $1 AND time_window = $2
+         ORDER BY rank
+         LIMIT $3"
+    )
+    .bind(woeid)
+    .bind(window.as_str())
+    .bind(limit)
+    .fetch_all(&state.db)
+    .await?;
+
+    Ok(HttpResponse::Ok().json(ApiResponse {
+        success: true,
+        data: Some(TrendsResponse {
+            woeid,
+            name: region.name,
+            window: window.as_str(),
+            as_of: region.computed_at,
+            topics,
+        }),
+        message: None,
+        next_cursor: None,
+    }))
+}
+
+async fn get_regions(state: web::Data<AppState>) -> ApiResult<HttpResponse> {
+    let regions = sqlx::query_as::<_, Region>(
+        "SELECT woeid, name, latitude, longitude, radius_km, computed_at FROM trend_regions ORDER BY woeid"
+    )
+    .fetch_all(&state.db)
+    .await?;
+
+    Ok(HttpResponse::Ok().json(ApiResponse {
+        success: true,
+        data: Some(regions),
+        message: None,
+        next_cursor: None,
+    }))
+}
+
+async fn create_region(state: web::Data<AppState>, _admin: AdminUser, req: web::Json<CreateRegionRequest>) -> ApiResult<HttpResponse> {
+    req.validate()?;
+    if req.woeid <= WORLDWIDE {
+        return Err(ApiError::BadRequest("woeid must be greater than 1".to_string()));
+    }
+    if !(-90.0..=90.0).contains(&req.latitude) || !(-180.0..=180.0).contains(&req.longitude) {
+        return Err(ApiError::BadRequest("Coordinates are out of range".to_string()));
+    }
+    if !(req.radius_km > 0.0 && req.radius_km <= MAX_REGION_RADIUS_KM) {
+        return Err(ApiError::BadRequest(format!(
+            "radius_km must be more than 0 and at most {}",
+            MAX_REGION_RADIUS_KM
+        )));
+    }
+
+    let region = sqlx::query_as::<_, Region>(
+        "INSERT INTO trend_regions (woeid, name, latitude, longitude, radius_km)
+         VALUES ($1, $2, $3, $4, $5)
+         ON CONFLICT (woeid) DO NOTHING
+         RETURNING woeid, name, latitude, longitude, radius_km, computed_at"
+    )
+    .bind(req.woeid)
+    .bind(req.name.trim())
+    .bind(req.latitude)
+    .bind(req.longitude)
+    .bind(req.radius_km)
+    .fetch_optional(&state.db)
+    .await?
+    .ok_or_else(|| ApiError::Conflict("A region with this woeid already exists".to_string()))?;
+
+    Ok(HttpResponse::Created().json(ApiResponse {
+        success: true,
+        data: Some(region),
+        message: None,
+        next_cursor: None,
+    }))
+}
+
+async fn delete_region(state: web::Data<AppState>, _admin: AdminUser, woeid: web::Path<i32>) -> ApiResult<HttpResponse> {
+    let woeid = woeid.into_inner();
+    if woeid == WORLDWIDE {
+        return Err(ApiError::BadRequest("The worldwide region can't be removed".to_string()));
+    }
+
+    let result = sqlx::query("DELETE FROM trend_regions WHERE woeid = $1")
+        .bind(woeid)
+        .execute(&state.db)
+        .await?;
+    if result.rows_affected() == 0 {
+        return Err(ApiError::NotFound("Unknown woeid".to_string()));
+    }
+
+    Ok(HttpResponse::Ok().json(ApiResponse {
+        success: true,
+        data: Some("Region deleted successfully"),
+        message: None,
+        next_cursor: None,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_hashtags_and_phrases() {
+        let topics = topics_in("Watching the World Cup final tonight #WorldCup @fifa");
+        assert!(topics.contains(&("#worldcup".to_string(), TopicKind::Hashtag)));
+        assert!(topics.contains(&("world cup".to_string(), TopicKind::Phrase)));
+        assert!(topics.contains(&("cup final".to_string(), TopicKind::Phrase)));
+        assert!(!topics.iter().any(|(text, _)| text.contains("the") || text.contains("fifa")));
+    }
+
+    #[test]
+    fn punctuation_and_links_break_phrases() {
+        assert_eq!(phrases("Big news. Market crash"), vec!["big news", "market crash"]);
+        assert_eq!(phrases("read https://example.com today"), Vec::<String>::new());
+        assert_eq!(phrases("year 2026 begins"), Vec::<String>::new());
+    }
+
+    #[test]
+    fn ranks_by_accounts_then_tweets() {
+        let (alice, bob, carol) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+        let spam = vec![("#spam".to_string(), TopicKind::Hashtag)];
+        let news = vec![("#news".to_string(), TopicKind::Hashtag)];
+        let tweets = vec![
+            (alice, spam.as_slice()),
+            (alice, spam.as_slice()),
+            (alice, spam.as_slice()),
+            (alice, news.as_slice()),
+            (bob, news.as_slice()),
+            (carol, spam.as_slice()),
+        ];
+
+        let ranked = rank(tweets.into_iter(), 2, 10);
+        assert_eq!(ranked.len(), 2);
+        assert_eq!((ranked[0].text.as_str(), ranked[0].author_count, ranked[0].tweet_count), ("#spam", 2, 4));
+        assert_eq!((ranked[1].text.as_str(), ranked[1].author_count, ranked[1].tweet_count), ("#news", 2, 2));
+
+        let tweets = vec![(alice, spam.as_slice()), (alice, spam.as_slice())];
+        assert!(rank(tweets.into_iter(), 2, 10).is_empty());
+    }
+
+    #[test]
+    fn regions_are_circles() {
+        let london = Region {
+            woeid: 44418,
+            name: "London".to_string(),
+            latitude: Some(51.507),
+            longitude: Some(-0.128),
+            radius_km: Some(50.0),
+            computed_at: None,
+        };
+        let tweet = |latitude, longitude| SampleTweet {
+            user_id: Uuid::nil(),
+            content: String::new(),
+            created_at: Utc::now(),
+            latitude,
+            longitude,
+        };
+        assert!(london.contains(&tweet(Some(51.45), Some(-0.2))));
+        assert!(!london.contains(&tweet(Some(48.857), Some(2.352))));
+        assert!(!london.contains(&tweet(None, None)));
+        assert!((distance_km(51.507, -0.128, 48.857, 2.352) - 344.0).abs() < 5.0);
+    }
+}
@@ -0,0 +1,437 @@
+use actix_web::{web, HttpResponse};
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use reqwest::{Method, Url};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use sqlx::types::Json;
+use sqlx::{FromRow, PgPool};
+use uuid::Uuid;
+
+use crate::auth::{self, AuthenticatedUser};
+use crate::config;
+use crate::error::{ApiError, ApiResult};
+use crate::http_client::HttpClient;
+use crate::models::ApiResponse;
+use crate::pagination::{self, Cursor, PageQuery};
+use crate::AppState;
+
+// Outbound integrations: a user picks a condition and a URL, and we POST a
+// JSON event to it when the condition is met, IFTTT/Zapier style. The
+// scheduler matches new events into `trigger_deliveries` (an outbox, one row
+// per trigger and subject, so nothing fires twice) and then delivers due rows,
+// retrying failures with exponential backoff up to `max_attempts`.
+//
+// Requests carry `X-Trigger-Timestamp` and `X-Trigger-Signature: sha256=<hex>`,
+// an HMAC-SHA256 of `<timestamp>.<body>` keyed with the trigger's secret,
+// which is only shown when the trigger is created.
+#[derive(Debug, Clone)]
+pub struct Triggers {
+    max_per_user: i64,
+    max_attempts: i32,
+    batch_size: i64,
+}
+
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.route("/api/triggers", web::get().to(get_triggers))
+        .route("/api/triggers", web::post().to(create_trigger))
+        .route("/api/triggers/{id}", web::put().to(update_trigger))
+        .route("/api/triggers/{id}", web::delete().to(delete_trigger))
+        .route("/api/triggers/{id}/deliveries", web::get().to(get_deliveries));
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TriggerKind {
+    TweetLikes,
+    FollowerReach,
+}
+
+impl TriggerKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TriggerKind::TweetLikes => "tweet_likes",
+            TriggerKind::FollowerReach => "follower_reach",
+        }
+    }
+}
+
+#[derive(Debug, Serialize, FromRow)]
+pub struct Trigger {
+    pub id: Uuid,
+    pub kind: String,
+    pub threshold: i32,
+    pub url: String,
+    pub enabled: bool,
+    pub created_at: DateTime<Utc>,
+}
+
+// Returned once, on creation
+#[derive(Debug, Serialize)]
+pub struct CreatedTrigger {
+    #[serde(flatten)]
+    pub trigger: Trigger,
+    pub secret: String,
+}
+
+#[derive(Debug, Serialize, FromRow)]
+pub struct TriggerDelivery {
+    pub id: Uuid,
+    pub payload: Json<serde_json::Value>,
+    pub status: String,
+    pub attempts: i32,
+    pub last_error: Option<String>,
+    pub delivered_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateTriggerRequest {
+    pub kind: TriggerKind,
+    pub threshold: i32,
+    pub url: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateTriggerRequest {
+    pub enabled: Option<bool>,
+    pub threshold: Option<i32>,
+}
+
+#[derive(Debug, FromRow)]
+struct DueDelivery {
+    id: Uuid,
+    payload: Json<serde_json::Value>,
+    attempts: i32,
+    url: String,
+    secret: String,
+}
+
+impl Triggers {
+    pub fn from_env() -> Result<Self, String> {
+        Ok(Triggers {
+            max_per_user: config::parse_var("TRIGGER_MAX_PER_USER", 10)?,
+            max_attempts: config::parse_var("TRIGGER_MAX_ATTEMPTS", 6)?,
+            batch_size: 100,
+        })
+    }
+
+    // Run by the scheduler.
+    pub async fn tick(&self, db: &PgPool, http: &HttpClient) -> Result<(), sqlx::Error> {
+        self.fire(db).await?;
+        self.deliver(db, http).await
+    }
+
+    // Queues a delivery for every newly met condition. Tweets count once any
+    // like arrived after the trigger was armed, so re-arming doesn't replay
+    // tweets that crossed the threshold long ago.
+    async fn fire(&self, db: &PgPool) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            "INSERT INTO trigger_deliveries (trigger_id, subject_id, payload)
+             SELECT tr.id, t.id, json_build_object(
+                 'trigger', tr.kind, 'trigger_id', tr.id, 'threshold', tr.threshold,
+                 'tweet', json_build_object('id', t.id, 'content', t.content, 'likes_count', t.likes_count, 'created_at', t.created_at)
+             )
+             FROM triggers tr
+             INNER JOIN tweets t ON t.user_id = tr.user_id
+             WHERE tr.kind = 'tweet_likes' AND tr.enabled
+               AND t.deleted_at IS NULL AND t.likes_count >= tr.threshold
+               AND EXISTS (SELECT 1 FROM likes l WHERE l.tweet_id = t.id AND l.created_at > tr.armed_at)
+             ON CONFLICT (trigger_id, subject_id) DO NOTHING"
+        )
+        .execute(db)
+        .await?;
+
+        sqlx::query(
+            "INSERT INTO trigger_deliveries (trigger_id, subject_id, payload)
+             SELECT tr.id, u.id, json_build_object(
+                 'trigger', tr.kind, 'trigger_id', tr.id, 'threshold', tr.threshold,
+                 'follower', json_build_object('id', u.id, 'username', u.username, 'display_name', u.display_name,
+                                               'followers_count', u.followers_count, 'followed_at', f.created_at)
+             )
+             FROM triggers tr
+             INNER JOIN follows f ON f.following_id = tr.user_id AND f.created_at > tr.armed_at
+             INNER JOIN users u ON u.id = f.follower_id
+             WHERE tr.kind = 'follower_reach' AND tr.enabled
+               AND u.deactivated_at IS NULL AND u.followers_count >= tr.threshold
+             ON CONFLICT (trigger_id, subject_id) DO NOTHING"
+        )
+        .execute(db)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn deliver(&self, db: &PgPool, http: &HttpClient) -> Result<(), sqlx::Error> {
+        // Leases the batch so another instance's tick doesn't send it too
+        let due = sqlx::query_as::<_, DueDelivery>(
+            "WITH claimed AS (
+                 UPDATE trigger_deliveries SET next_attempt_at = NOW() + INTERVAL '5 minutes'
+                 WHERE id IN (
+                     SELECT id FROM trigger_deliveries
+                     WHERE status = 'pending' AND next_attempt_at <= NOW()
+                     ORDER BY next_attempt_at
+                     LIMIT $1
+                     FOR UPDATE SKIP LOCKED
+                 )
+                 RETURNING id, trigger_id, payload, attempts
+             )
+             SELECT c.id, c.payload, c.attempts, tr.url, tr.secret
+             FROM claimed c
+             INNER JOIN triggers tr ON tr.id = c.trigger_id"
+        )
+        .bind(self.batch_size)
+        .fetch_all(db)
+        .await?;
+
+        for delivery in due {
+            let attempts = delivery.attempts + 1;
+            match send(http, &delivery).await {
+                Ok(()) => {
+                    sqlx::query(
+                        "UPDATE trigger_deliveries SET status = 'delivered', attempts = $2, last_error = NULL, delivered_at = NOW()
+                         WHERE id = $1"
+                    )
+                    .bind(delivery.id)
+                    .bind(attempts)
+                    .execute(db)
+                    .await?;
+                }
+                Err(error) => {
+                    let status = if attempts >= self.max_attempts { "failed" } else { "pending" };
+                    sqlx::query(
+                        "UPDATE trigger_deliveries
+                         SET status = $2, attempts = $3, last_error = $4,
+                             next_attempt_at = NOW() + make_interval(mins => power(2, $3)::int)
+                         WHERE id = $1"
+                    )
+                    .bind(delivery.id)
+                    .bind(status)
+                    .bind(attempts)
+                    .bind(&error)
+                    .execute(db)
+                    .await?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+async fn send(http: &HttpClient, delivery: &DueDelivery) -> Result<(), String> {
+    let url = Url::parse(&delivery.url).map_err(|e| e.to_string())?;
+    let body = serde_json::to_vec(&delivery.payload.0).map_err(|e| e.to_string())?;
+    let timestamp = Utc::now().timestamp().to_string();
+
+    let response = http
+        .public(Method::POST, url)
+        .map_err(|e| e.to_string())?
+        .header("Content-Type", "application/json")
+        .header("X-Trigger-Delivery", &delivery.id.to_string())
+        .header("X-Trigger-Timestamp", &timestamp)
+        .header("X-Trigger-Signature", &format!("sha256={}", sign(&delivery.secret, &timestamp, &body)))
+        .body(body)
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+
+    if response.status().is_success() {
+        Ok(())
+    } else {
+        Err(format!("endpoint responded with {}", response.status()))
+    }
+}
+
+fn sign(secret: &str, timestamp: &str, body: &[u8]) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts any key length");
+    mac.update(timestamp.as_bytes());
+    mac.update(b".");
+    mac.update(body);
+    hex::encode(mac.finalize().into_bytes())
+}
+
+// Only URLs the public HTTP pool would call are accepted (see `HttpClient::public`).
+fn check_url(http: &HttpClient, raw: &str) -> ApiResult<()> {
+    let url = Url::parse(raw.trim()).map_err(|_| ApiError::BadRequest("Invalid trigger URL".to_string()))?;
+    http.public(Method::POST, url)
+        .map_err(|_| ApiError::BadRequest("Trigger URL is not allowed".to_string()))?;
+    Ok(())
+}
+
+fn check_threshold(threshold: i32) -> ApiResult<()> {
+    if threshold < 1 {
+        return Err(ApiError::BadRequest("Threshold must be at least 1".to_string()));
+    }
+    Ok(())
+}
+
+async fn get_triggers(state: web::Data<AppState>, AuthenticatedUser(user_id): AuthenticatedUser) -> ApiResult<HttpResponse> {
+    let triggers = sqlx::query_as::<_, Trigger>(
+        "SELECT id, kind, threshold, url, enabled, created_at FROM triggers WHERE user_id = $1 ORDER BY created_at"
+    )
+    .bind(user_id)
+    .fetch_all(&state.db)
+    .await?;
+
+    Ok(HttpResponse::Ok().json(ApiResponse {
+        success: true,
+        data: Some(triggers),
+        message: None,
+        next_cursor: None,
+    }))
+}
+
+async fn create_trigger(
+    state: web::Data<AppState>,
+    AuthenticatedUser(user_id): AuthenticatedUser,
+    req: web::Json<CreateTriggerRequest>,
+) -> ApiResult<HttpResponse> {
+    check_threshold(req.threshold)?;
+    check_url(&state.http, &req.url)?;
+
+    let count = sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM triggers WHERE user_id = $1")
+        .bind(user_id)
+        .fetch_one(&state.db)
+        .await?;
+    if count >= state.triggers.max_per_user {
+        return Err(ApiError::Forbidden(format!("You can have at most {} triggers", state.triggers.max_per_user)));
+    }
+
+    let secret = auth::generate_token();
+    let trigger = sqlx::query_as::<_, Trigger>(
+        "INSERT INTO triggers (user_id, kind, threshold, url, secret) VALUES ($1, $2, $3, $4, $5)
+         RETURNING id, kind, threshold, url, enabled, created_at"
+    )
+    .bind(user_id)
+    .bind(req.kind.as_str())
+    .bind(req.threshold)
+    .bind(req.url.trim())
+    .bind(&secret)
+    .fetch_one(&state.db)
+    .await?;
+
+    Ok(HttpResponse::Created().json(ApiResponse {
+        success: true,
+        data: Some(CreatedTrigger { trigger, secret }),
+        message: None,
+        next_cursor: None,
+    }))
+}
+
+// Re-enabling re-arms the trigger: nothing that happened while it was off
+// fires.
+async fn update_trigger(
+    state: web::Data<AppState>,
+    AuthenticatedUser(user_id): AuthenticatedUser,
+    trigger_id: web::Path<Uuid>,
+    req: web::Json<UpdateTriggerRequest>,
+) -> ApiResult<HttpResponse> {
+    if let Some(threshold) = req.threshold {
+        check_threshold(threshold)?;
+    }
+
+    let trigger = sqlx::query_as::<_, Trigger>(
+        "UPDATE triggers
+         SET threshold = COALESCE($3, threshold),
+             armed_at = CASE WHEN $4 AND NOT enabled THEN NOW() ELSE armed_at END,
+             enabled = COALESCE($4, enabled)
+         WHERE id = $1 AND user_id = $2
+         RETURNING id, kind, threshold, url, enabled, created_at"
+    )
+    .bind(trigger_id.into_inner())
+    .bind(user_id)
+    .bind(req.threshold)
+    .bind(req.enabled)
+    .fetch_optional(&state.db)
+    .await?
+    .ok_or_else(|| ApiError::NotFound("Trigger not found".to_string()))?;
+
+    Ok(HttpResponse::Ok().json(ApiResponse {
+        success: true,
+        data: Some(trigger),
+        message: None,
+        next_cursor: None,
+    }))
+}
+
+async fn delete_trigger(state: web::Data<AppState>, AuthenticatedUser(user_id): AuthenticatedUser, trigger_id: web::Path<Uuid>) -> ApiResult<HttpResponse> {
+    let result = sqlx::query("DELETE FROM triggers WHERE id = $1 AND user_id = $2")
+        .bind(trigger_id.into_inner())
+        .bind(user_id)
+        .execute(&state.db)
+        .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(ApiError::NotFound("Trigger not found".to_string()));
+    }
+
+    Ok(HttpResponse::Ok().json(ApiResponse {
+        success: true,
+        data: Some("Trigger deleted successfully"),
+        message: None,
+        next_cursor: None,
+    }))
+}
+
+// A trigger's delivery log, newest first, including failures and their last
+// error.
+async fn get_deliveries(
+    state: web::Data<AppState>,
+    AuthenticatedUser(user_id): AuthenticatedUser,
+    trigger_id: web::Path<Uuid>,
+    page: web::Query<PageQuery>,
+) -> ApiResult<HttpResponse> {
+    let trigger_id = trigger_id.into_inner();
+    let limit = page.limit();
+    let scope = format!("trigger_deliveries:{}", trigger_id);
+    let cursor = page.cursor(&state.cursors, &scope).map_err(ApiError::BadRequest)?;
+
+    let owned = sqlx::query_scalar::<_, bool>("SELECT EXISTS(SELECT 1 FROM triggers WHERE id = $1 AND user_id = $2)")
+        .bind(trigger_id)
+        .bind(user_id)
+        .fetch_one(&state.db)
+        .await?;
+    if !owned {
+        return Err(ApiError::NotFound("Trigger not found".to_string()));
+    }
+
+    let mut deliveries = sqlx::query_as::<_, TriggerDelivery>(
+        "SELECT id, payload, status, attempts, last_error, delivered_at, created_at FROM trigger_deliveries
+         WHERE trigger_id = $1
+           AND ($2::timestamptz IS NULL OR (created_at, id) < ($2, $3))
+         ORDER BY created_at DESC, id DESC
+         LIMIT $4"
+    )
+    .bind(trigger_id)
+    .bind(cursor.map(|c| c.created_at))
+    .bind(cursor.map(|c| c.id))
+    .bind(limit + 1)
+    .fetch_all(&state.db)
+    .await?;
+
+    let next_cursor = pagination::next_cursor(&mut deliveries, limit, &state.cursors, &scope, |d| Cursor {
+        created_at: d.created_at,
+        id: d.id,
+    });
+
+    Ok(HttpResponse::Ok().json(ApiResponse {
+        success: true,
+        data: Some(deliveries),
+        message: None,
+        next_cursor,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn signature_covers_timestamp_and_body() {
+        let signature = sign("secret", "1700000000", br#"{"trigger":"tweet_likes"}"#);
+        assert_eq!(signature.len(), 64);
+        assert_ne!(signature, sign("secret", "1700000001", br#"{"trigger":"tweet_likes"}"#));
+        assert_ne!(signature, sign("other", "1700000000", br#"{"trigger":"tweet_likes"}"#));
+    }
+}
@@ -0,0 +1,584 @@
+use actix_multipart::Multipart;
+use actix_web::{web, HttpResponse};
+use chrono::{DateTime, Utc};
+use futures_util::future::LocalBoxFuture;
+use futures_util::StreamExt;
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, PgConnection};
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::auth::AuthenticatedUser;
+use crate::config;
+use crate::error::{ApiError, ApiResult};
+use crate::jobs::{self, Job, JobHandler, JobResult};
+use crate::models::ApiResponse;
+use crate::unzip::{self, ZipArchive, ZipEntry};
+use crate::{hashtags, mentions, AppState};
+
+// tweets.js can be large for prolific accounts, but not unboundedly so
+const MAX_TWEETS_FILE_BYTES: u64 = 256 * 1024 * 1024;
+
+// Imports tweets from the ZIP archive Twitter lets users download. The upload
+// is held in memory while it's checked and then stored in the database until
+// the job is done, which is why TWITTER_ARCHIVE_MAX_BYTES defaults to a modest
+// 64 MiB. A background job walks `data/tweets.js` oldest first, keeping
+// original timestamps, the first photo of each tweet and threads the user
+// replied to themselves. Retweets and replies to other accounts are skipped. Every imported tweet is recorded by its Twitter ID in
+// `imported_tweets`, which makes re-imports (and retried jobs) skip what's
+// already there. Imported tweets notify nobody.
+#[derive(Debug, Clone)]
+pub struct TwitterArchive {
+    max_bytes: usize,
+}
+
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.route("/api/import/twitter-archive", web::post().to(upload_archive))
+        .route("/api/import/twitter-archive/{id}", web::get().to(get_import));
+}
+
+#[derive(Debug, Serialize, FromRow)]
+pub struct TwitterArchiveImport {
+    pub id: Uuid,
+    pub status: String,
+    pub total_tweets: i32,
+    pub processed_tweets: i32,
+    pub imported_count: i32,
+    pub duplicate_count: i32,
+    pub skipped_count: i32,
+    pub error: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub completed_at: Option<DateTime<Utc>>,
+}
+
+const IMPORT_COLUMNS: &str = "id, status, total_tweets, processed_tweets, imported_count, duplicate_count, \
+                              skipped_count, error, created_at, completed_at";
+
+// A tweet as it appears in the archive
+#[derive(Debug, Deserialize)]
+pub struct ArchiveTweet {
+    pub id_str: String,
+    pub full_text: String,
+    pub created_at: String,
+    #[serde(default)]
+    pub in_reply_to_status_id_str: Option<String>,
+    #[serde(default)]
+    pub entities: Entities,
+    #[serde(default)]
+    pub extended_entities: Option<Entities>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct Entities {
+    #[serde(default)]
+    pub urls: Vec<UrlEntity>,
+    #[serde(default)]
+    pub media: Vec<MediaEntity>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UrlEntity {
+    pub url: String,
+    pub expanded_url: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct MediaEntity {
+    pub url: String,
+    pub media_url_https: String,
+    #[serde(rename = "type")]
+    pub kind: String,
+}
+
+// Current archives wrap each tweet as {"tweet": {...}}, older ones don't
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ArchiveItem {
+    Wrapped { tweet: ArchiveTweet },
+    Bare(ArchiveTweet),
+}
+
+#[derive(Serialize, Deserialize)]
+struct ImportPayload {
+    import_id: Uuid,
+    user_id: Uuid,
+}
+
+enum Outcome {
+    Imported,
+    Duplicate,
+    Skipped,
+}
+
+impl Outcome {
+    fn column(&self) -> &'static str {
+        match self {
+            Outcome::Imported => "imported_count",
+            Outcome::Duplicate => "duplicate_count",
+            Outcome::Skipped => "skipped_count",
+        }
+    }
+}
+
+impl TwitterArchive {
+    pub fn from_env() -> Result<Self, String> {
+        Ok(TwitterArchive {
+            max_bytes: config::parse_var("TWITTER_ARCHIVE_MAX_BYTES", 64 * 1024 * 1024)?,
+        })
+    }
+}
+
+impl ArchiveTweet {
+    fn is_retweet(&self) -> bool {
+        self.full_text.starts_with("RT @")
+    }
+
+    // Twitter's text has t.co links, a link to any attached media and
+    // HTML-escaped <, > and &. Links are expanded and the media link dropped.
+    pub fn text(&self) -> String {
+        let mut text = self.full_text.clone();
+        for url in &self.entities.urls {
+            if let Some(expanded) = &url.expanded_url {
+                text = text.replace(&url.url, expanded);
+            }
+        }
+        for media in self.media() {
+            text = text.replace(&media.url, "");
+        }
+        text.replace("&lt;", "<").replace("&gt;", ">").replace("&amp;", "&").trim().to_string()
+    }
+
+    pub fn created_at(&self) -> Option<DateTime<Utc>> {
+        DateTime::parse_from_str(&self.created_at, "%a %b %d %H:%M:%S %z %Y")
+            .ok()
+            .map(|t| t.with_timezone(&Utc))
+    }
+
+    fn media(&self) -> &[MediaEntity] {
+        match &self.extended_entities {
+            Some(extended) if !extended.media.is_empty() => &extended.media,
+            _ => &self.entities.media,
+        }
+    }
+
+    // Photos are stored as `data/tweets_media/<tweet id>-<file name>`
+    // (`tweet_media` in older archives)
+    pub fn photo_path(&self) -> Option<String> {
+        let photo = self.media().iter().find(|media| media.kind == "photo")?;
+        let file_name = photo.media_url_https.rsplit('/').next()?;
+        Some(format!("/{}-{}", self.id_str, file_name))
+    }
+}
+
+// Reads every tweets file in the archive (large archives split them into
+// parts), oldest first.
+pub fn read_tweets(archive: &ZipArchive) -> Result<Vec<ArchiveTweet>, String> {
+    let mut tweets = Vec::new();
+    let mut found = false;
+
+    for entry in archive.entries().iter().filter(|entry| is_tweets_file(&entry.name)) {
+        found = true;
+        let bytes = archive.read(entry, MAX_TWEETS_FILE_BYTES)?;
+        let text = String::from_utf8(bytes).map_err(|_| format!("{} is not valid UTF-8", entry.name))?;
+        // window.YTD.tweets.part0 = [ ... ]
+        let json = text.split_once('=').map_or(text.as_str(), |(_, json)| json);
+        let items: Vec<ArchiveItem> =
+            serde_json::from_str(json.trim().trim_end_matches(';')).map_err(|e| format!("Invalid {}: {}", entry.name, e))?;
+        tweets.extend(items.into_iter().map(|item| match item {
+            ArchiveItem::Wrapped { tweet } | ArchiveItem::Bare(tweet) => tweet,
+        }));
+    }
+
+    if !found {
+        return Err("No data/tweets.js in archive; is this a Twitter archive?".to_string());
+    }
+
+    // Snowflake IDs grow over time, so numeric order is chronological
+    tweets.sort_by(|a, b| (a.id_str.len(), &a.id_str).cmp(&(b.id_str.len(), &b.id_str)));
+    tweets.dedup_by(|a, b| a.id_str == b.id_str);
+    Ok(tweets)
+}
+
+// data/tweets.js and data/tweets-part1.js, or tweet.js in older archives, but
+// not data/tweet-headers.js
+fn is_tweets_file(name: &str) -> bool {
+    let Some(stem) = name.strip_prefix("data/").and_then(|name| name.strip_suffix(".js")) else {
+        return false;
+    };
+    let stem = stem.strip_prefix("tweets").or_else(|| stem.strip_prefix("tweet")).unwrap_or("-");
+    stem.is_empty()
+        || stem
+            .strip_prefix("-part")
+            .is_some_and(|part| !part.is_empty() && part.bytes().all(|b| b.is_ascii_digit()))
+}
+
+async fn upload_archive(
+    state: web::Data<AppState>,
+    AuthenticatedUser(user_id): AuthenticatedUser,
+    mut payload: Multipart,
+) -> ApiResult<HttpResponse> {
+    let mut file = None;
+
+    while let Some(field) = payload.next().await {
+        let mut field = field.map_err(|e| ApiError::BadRequest(format!("Invalid multipart body: {}", e)))?;
+        if field.name() != Some("file") {
+            continue;
+        }
+
+        let mut bytes = Vec::new();
+        while let Some(chunk) = field.next().await {
+            let chunk = chunk.map_err(|e| ApiError::BadRequest(format!("Invalid multipart body: {}", e)))?;
+            if bytes.len() + chunk.len() > state.twitter_archive.max_bytes {
+                return Err(ApiError::BadRequest(format!(
+                    "Archive must be at most {} bytes",
+                    state.twitter_archive.max_bytes
+                )));
+            }
+            bytes.extend_from_slice(&chunk);
+        }
+        file = Some(bytes);
+        break;
+    }
+
+    let bytes = file.ok_or_else(|| ApiError::BadRequest("Missing 'file' field".to_string()))?;
+
+    // Fail bad uploads now rather than in the job
+    let (bytes, total) = web::block(move || {
+        let total = read_tweets(&ZipArchive::parse(&bytes)?)?.len();
+        Ok::<_, String>((bytes, total))
+    })
+    .await
+    .map_err(|e| ApiError::Internal(e.to_string()))?
+    .map_err(ApiError::BadRequest)?;
+
+    let running = sqlx::query_scalar::<_, bool>(
+        "SELECT EXISTS(SELECT 1 FROM twitter_archive_imports WHERE user_id = $1 AND status IN ('pending', 'running'))"
+    )
+    .bind(user_id)
+    .fetch_one(&state.db)
+    .await?;
+
+    if running {
+        return Err(ApiError::Conflict("An import is already in progress".to_string()));
+    }
+
+    let mut tx = state.db.begin().await?;
+
+    let import = sqlx::query_as::<_, TwitterArchiveImport>(&format!(
+        "INSERT INTO twitter_archive_imports (user_id, archive, total_tweets) VALUES ($1, $2, $3) RETURNING {}",
+        IMPORT_COLUMNS
+    ))
+    .bind(user_id)
+    .bind(bytes)
+    .bind(total as i32)
+    .fetch_one(&mut *tx)
+    .await?;
+
+    jobs::enqueue(&mut tx, ImportJob::KIND, &ImportPayload { import_id: import.id, user_id }).await?;
+
+    tx.commit().await?;
+
+    Ok(HttpResponse::Accepted().json(ApiResponse {
+        success: true,
+        data: Some(import),
+        message: Some("Import started".to_string()),
+        next_cursor: None,
+    }))
+}
+
+async fn get_import(
+    state: web::Data<AppState>,
+    AuthenticatedUser(user_id): AuthenticatedUser,
+    import_id: web::Path<Uuid>,
+) -> ApiResult<HttpResponse> {
+    let import = sqlx::query_as::<_, TwitterArchiveImport>(&format!(
+        "SELECT {} FROM twitter_archive_imports WHERE id = $1 AND user_id = $2",
+        IMPORT_COLUMNS
+    ))
+    .bind(import_id.into_inner())
+    .bind(user_id)
+    .fetch_optional(&state.db)
+    .await?
+    .ok_or_else(|| ApiError::NotFound("Import not found".to_string()))?;
+
+    Ok(HttpResponse::Ok().json(ApiResponse {
+        success: true,
+        data: Some(import),
+        message: None,
+        next_cursor: None,
+    }))
+}
+
+pub struct ImportJob;
+
+impl ImportJob {
+    pub const KIND: &'static str = "twitter_archive_import";
+}
+
+impl JobHandler for ImportJob {
+    fn kind(&self) -> &'static str {
+        Self::KIND
+    }
+
+    fn run<'a>(&'a self, state: &'a AppState, job: &'a Job) -> LocalBoxFuture<'a, JobResult> {
+        Box::pin(async move {
+            let payload: ImportPayload = job.payload()?;
+            run_import(state, payload.import_id, payload.user_id).await
+        })
+    }
+
+    fn failed<'a>(&'a self, state: &'a AppState, job: &'a Job, error: &'a str) -> LocalBoxFuture<'a, JobResult> {
+        Box::pin(async move {
+            let payload: ImportPayload = job.payload()?;
+            sqlx::query(
+                "UPDATE twitter_archive_imports SET status = 'failed', error = $2, archive = NULL, completed_at = NOW()
+                 WHERE id = $1"
+            )
+            .bind(payload.import_id)
+            .bind(error)
+            .execute(&state.db)
+            .await?;
+            Ok(())
+        })
+    }
+}
+
+// Picks up after the last processed tweet, so a retried job doesn't start over
+async fn run_import(state: &AppState, import_id: Uuid, user_id: Uuid) -> JobResult {
+    let row = sqlx::query_as::<_, (Option<Vec<u8>>, i32)>(
+        "UPDATE twitter_archive_imports SET status = 'running' WHERE id = $1 RETURNING archive, processed_tweets"
+    )
+    .bind(import_id)
+    .fetch_optional(&state.db)
+    .await?;
+
+    // Deleted with its user, or already finished
+    let Some((Some(bytes), processed)) = row else {
+        return Ok(());
+    };
+
+    let (bytes, entries, tweets) = web::block(move || {
+        let archive = ZipArchive::parse(&bytes)?;
+        let (entries, tweets) = (archive.entries().to_vec(), read_tweets(&archive)?);
+        Ok::<_, String>((Arc::new(bytes), entries, tweets))
+    })
+    .await??;
+
+    for tweet in tweets.iter().skip(processed as usize) {
+        import_tweet(state, &bytes, &entries, import_id, user_id, tweet).await?;
+    }
+
+    sqlx::query(
+        "UPDATE twitter_archive_imports SET status = 'completed', archive = NULL, completed_at = NOW() WHERE id = $1"
+    )
+    .bind(import_id)
+    .execute(&state.db)
+    .await?;
+
+    Ok(())
+}
+
+async fn import_tweet(
+    state: &AppState,
+    archive: &Arc<Vec<u8>>,
+    entries: &[ZipEntry],
+    import_id: Uuid,
+    user_id: Uuid,
+    tweet: &ArchiveTweet,
+) -> Result<(), sqlx::Error> {
+    let imported = sqlx::query_scalar::<_, bool>(
+        "SELECT EXISTS(SELECT 1 FROM imported_tweets WHERE user_id = $1 AND source_id = $2)"
+    )
+    .bind(user_id)
+    .bind(&tweet.id_str)
+    .fetch_one(&state.db)
+    .await?;
+
+    if imported {
+        return record(&mut *state.db.acquire().await?, import_id, Outcome::Duplicate).await;
+    }
+
+    let content = tweet.text();
+    let (Some(created_at), false) = (tweet.created_at(), tweet.is_retweet()) else {
+        return record(&mut *state.db.acquire().await?, import_id, Outcome::Skipped).await;
+    };
+    if content.chars().count() > state.instance.max_tweet_length {
+        return record(&mut *state.db.acquire().await?, import_id, Outcome::Skipped).await;
+    }
+
+    // Replies keep their thread when the parent was imported too. A parent
+    // that's since been deleted or archived makes this a standalone tweet;
+    // one that was never imported is someone else's.
+    let parent_tweet_id = match &tweet.in_reply_to_status_id_str {
+        Some(source_id) => {
+            let parent = sqlx::query_as::<_, (Option<Uuid>,)>(
+                "SELECT t.id FROM imported_tweets i
+                 LEFT JOIN tweets t ON t.id = i.tweet_id AND t.deleted_at IS NULL
+                 WHERE i.user_id = $1 AND i.source_id = $2"
+            )
+            .bind(user_id)
+            .bind(source_id)
+            .fetch_optional(&state.db)
+            .await?;
+            match parent {
+                Some((parent_tweet_id,)) => parent_tweet_id,
+                None => return record(&mut *state.db.acquire().await?, import_id, Outcome::Skipped).await,
+            }
+        }
+        None => None,
+    };
+
+    let image_url = match import_photo(state, archive, entries, user_id, tweet).await {
+        Ok(url) => url,
+        Err(e) => {
+            log::warn!("twitter archive import {}: photo for tweet {} not imported: {}", import_id, tweet.id_str, e);
+            None
+        }
+    };
+
+    if content.is_empty() && image_url.is_none() {
+        return record(&mut *state.db.acquire().await?, import_id, Outcome::Skipped).await;
+    }
+
+    let mut tx = state.db.begin().await?;
+
+    let tweet_id = Uuid::new_v4();
+    let claimed = sqlx::query(
+        "INSERT INTO imported_tweets (user_id, source_id, tweet_id) VALUES ($1, $2, $3) ON CONFLICT DO NOTHING"
+    )
+    .bind(user_id)
+    .bind(&tweet.id_str)
+    .bind(tweet_id)
+    .execute(&mut *tx)
+    .await?
+    .rows_affected()
+        > 0;
+
+    if !claimed {
+        record(&mut tx, import_id, Outcome::Duplicate).await?;
+        return tx.commit().await;
+    }
+
+    sqlx::query(
+        "INSERT INTO tweets (id, user_id, content, image_url, parent_tweet_id, created_at)
+         VALUES ($1, $2, $3, $4, $5, $6)"
+    )
+    .bind(tweet_id)
+    .bind(user_id)
+    .bind(&content)
+    .bind(&image_url)
+    .bind(parent_tweet_id)
+    .bind(created_at)
+    .execute(&mut *tx)
+    .await?;
+
+    hashtags::attach(&mut tx, tweet_id, &content).await?;
+    mentions::attach(&mut tx, tweet_id, user_id, &content, false).await?;
+    record(&mut tx, import_id, Outcome::Imported).await?;
+
+    tx.commit().await
+}
+
+async fn import_photo(
+    state: &AppState,
+    archive: &Arc<Vec<u8>>,
+    entries: &[ZipEntry],
+    user_id: Uuid,
+    tweet: &ArchiveTweet,
+) -> ApiResult<Option<String>> {
+    if state.instance.max_media_per_tweet == 0 {
+        return Ok(None);
+    }
+    let Some(path) = tweet.photo_path() else {
+        return Ok(None);
+    };
+    let Some(entry) = entries
+        .iter()
+        .find(|entry| entry.name.starts_with("data/tweet") && entry.name.ends_with(&path))
+    else {
+        return Ok(None);
+    };
+
+    let (archive, entry, max_size) = (archive.clone(), entry.clone(), state.media.max_bytes as u64);
+    let bytes = web::block(move || unzip::read_entry(&archive, &entry, max_size))
+        .await
+        .map_err(|e| ApiError::Internal(e.to_string()))?
+        .map_err(ApiError::BadRequest)?;
+    Ok(Some(crate::handlers::media::store_image(state, user_id, bytes).await?.url))
+}
+
+async fn record(conn: &mut PgConnection, import_id: Uuid, outcome: Outcome) -> Result<(), sqlx::Error> {
+    sqlx::query(&format!(
+        "UPDATE twitter_archive_imports SET processed_tweets = processed_tweets + 1, {0} = {0} + 1 WHERE id = $1",
+        outcome.column()
+    ))
+    .bind(import_id)
+    .execute(conn)
+    .await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::unzip::tests::build_zip;
+
+    const TWEETS_JS: &str = r#"window.YTD.tweets.part0 = [
+      {"tweet": {
+        "id_str": "1050118621198921728",
+        "full_text": "Reading https://t.co/abc &amp; thinking about &lt;html&gt; https://t.co/pic",
+        "created_at": "Wed Oct 10 20:19:24 +0000 2018",
+        "entities": {
+          "urls": [{"url": "https://t.co/abc", "expanded_url": "https://example.com/post"}],
+          "media": [{"url": "https://t.co/pic", "media_url_https": "https://pbs.twimg.com/media/DpP3.jpg", "type": "photo"}]
+        }
+      }},
+      {"tweet": {
+        "id_str": "999",
+        "full_text": "RT @someone: hello",
+        "created_at": "Mon Jan 01 00:00:00 +0000 2018"
+      }}
+    ]"#;
+
+    #[test]
+    fn reads_tweets_oldest_first() {
+        let zip = build_zip(&[
+            ("data/tweet-headers.js", b"window.YTD.tweet_headers.part0 = []", false),
+            ("data/tweets.js", TWEETS_JS.as_bytes(), true),
+        ]);
+        let tweets = read_tweets(&ZipArchive::parse(&zip).unwrap()).unwrap();
+
+        assert_eq!(tweets.len(), 2);
+        assert_eq!(tweets[0].id_str, "999");
+        assert!(tweets[0].is_retweet());
+        assert!(!tweets[1].is_retweet());
+    }
+
+    #[test]
+    fn rejects_archives_without_tweets() {
+        let zip = build_zip(&[("data/account.js", b"[]", false)]);
+        assert!(read_tweets(&ZipArchive::parse(&zip).unwrap()).is_err());
+    }
+
+    #[test]
+    fn cleans_up_tweet_text() {
+        let zip = build_zip(&[("data/tweets.js", TWEETS_JS.as_bytes(), false)]);
+        let tweets = read_tweets(&ZipArchive::parse(&zip).unwrap()).unwrap();
+        let tweet = &tweets[1];
+
+        assert_eq!(tweet.text(), "Reading https://example.com/post & thinking about <html>");
+        assert_eq!(tweet.created_at().unwrap().to_rfc3339(), "2018-10-10T20:19:24+00:00");
+        assert_eq!(tweet.photo_path().as_deref(), Some("/1050118621198921728-DpP3.jpg"));
+    }
+
+    #[test]
+    fn recognises_tweets_files() {
+        assert!(is_tweets_file("data/tweets.js"));
+        assert!(is_tweets_file("data/tweet.js"));
+        assert!(is_tweets_file("data/tweets-part2.js"));
+        assert!(!is_tweets_file("data/tweet-headers.js"));
+        assert!(!is_tweets_file("data/tweets-part.js"));
+        assert!(!is_tweets_file("data/tweets_media/1-a.jpg"));
+        assert!(!is_tweets_file("tweets.js"));
+    }
+}
@@ -0,0 +1,436 @@
+use actix_web::http::StatusCode;
+use actix_web::{web, HttpResponse, ResponseError};
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sqlx::{FromRow, PgPool};
+use std::fmt;
+use uuid::Uuid;
+
+use crate::auth::AuthenticatedUser;
+use crate::error::ApiError;
+use crate::models::{NewTweet, TweetVisibility};
+use crate::pagination::Cursor;
+use crate::handlers::tweets::{soft_delete_tweet, submit_tweet, Submitted};
+use crate::{pagination, AppState};
+
+const MAX_LOOKUP_IDS: usize = 100;
+const DEFAULT_MAX_RESULTS: i64 = 10;
+const MAX_RESULTS: i64 = 100;
+
+// A Twitter API v2 compatible surface over the regular handlers, so existing
+// SDKs and bots can be pointed at this instance. Only a subset is covered:
+// tweet lookup, posting and deleting, user lookup and a user's recent tweets.
+// Ids are our UUIDs as strings; fields are always returned in full, so
+// `tweet.fields`/`user.fields` are accepted but ignored, and `expansions`
+// aren't supported. Errors use the v2 problem shape instead of the usual
+// envelope.
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.route("/2/tweets", web::get().to(lookup_tweets))
+        .route("/2/tweets", web::post().to(create_tweet))
+        .route("/2/tweets/{id}", web::get().to(get_tweet))
+        .route("/2/tweets/{id}", web::delete().to(delete_tweet))
+        .route("/2/users/me", web::get().to(get_me))
+        .route("/2/users/by/username/{username}", web::get().to(get_user_by_username))
+        .route("/2/users/{id}", web::get().to(get_user))
+        .route("/2/users/{id}/tweets", web::get().to(get_user_tweets));
+}
+
+#[derive(Debug)]
+pub struct V2Error(ApiError);
+
+type V2Result<T> = Result<T, V2Error>;
+
+impl fmt::Display for V2Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl ResponseError for V2Error {
+    fn status_code(&self) -> StatusCode {
+        self.0.status_code()
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        let status = self.status_code();
+        HttpResponse::build(status).json(json!({
+            "title": status.canonical_reason().unwrap_or("Error"),
+            "detail": self.0.to_string(),
+            "status": status.as_u16(),
+        }))
+    }
+}
+
+impl<E: Into<ApiError>> From<E> for V2Error {
+    fn from(e: E) -> Self {
+        V2Error(e.into())
+    }
+}
+
+#[derive(Debug, FromRow)]
+struct TweetRow {
+    id: Uuid,
+    user_id: Uuid,
+    parent_tweet_id: Option<Uuid>,
+    quoted_tweet_id: Option<Uuid>,
+    content: String,
+    likes_count: i32,
+    retweets_count: i32,
+    replies_count: i32,
+    quote_count: i64,
+    created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, FromRow)]
+struct UserRow {
+    id: Uuid,
+    username: String,
+    display_name: String,
+    bio: Option<String>,
+    profile_image: Option<String>,
+    verified: bool,
+    followers_count: i32,
+    following_count: i32,
+    tweet_count: i64,
+    created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize)]
+struct V2Tweet {
+    id: String,
+    text: String,
+    author_id: String,
+    created_at: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    referenced_tweets: Vec<ReferencedTweet>,
+    public_metrics: TweetMetrics,
+}
+
+#[derive(Debug, Serialize)]
+struct ReferencedTweet {
+    #[serde(rename = "type")]
+    kind: &'static str,
+    id: String,
+}
+
+#[derive(Debug, Serialize)]
+struct TweetMetrics {
+    retweet_count: i32,
+    reply_count: i32,
+    like_count: i32,
+    quote_count: i64,
+}
+
+#[derive(Debug, Serialize)]
+struct V2User {
+    id: String,
+    name: String,
+    username: String,
+    created_at: String,
+    description: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    profile_image_url: Option<String>,
+    verified: bool,
+    public_metrics: UserMetrics,
+}
+
+#[derive(Debug, Serialize)]
+struct UserMetrics {
+    followers_count: i32,
+    following_count: i32,
+    tweet_count: i64,
+}
+
+#[derive(Debug, Serialize)]
+struct Meta {
+    result_count: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    newest_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    oldest_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    next_token: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct LookupQuery {
+    ids: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct TimelineQuery {
+    max_results: Option<i64>,
+    pagination_token: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CreateTweetBody {
+    text: String,
+    reply: Option<ReplySettings>,
+    quote_tweet_id: Option<Uuid>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ReplySettings {
+    in_reply_to_tweet_id: Uuid,
+}
+
+impl From<TweetRow> for V2Tweet {
+    fn from(row: TweetRow) -> Self {
+        let replied_to = row.parent_tweet_id.map(|id| ReferencedTweet { kind: "replied_to", id: id.to_string() });
+        let quoted = row.quoted_tweet_id.map(|id| ReferencedTweet { kind: "quoted", id: id.to_string() });
+
+        V2Tweet {
+            id: row.id.to_string(),
+            text: row.content,
+            author_id: row.user_id.to_string(),
+            created_at: timestamp(row.created_at),
+            referenced_tweets: replied_to.into_iter().chain(quoted).collect(),
+            public_metrics: TweetMetrics {
+                retweet_count: row.retweets_count,
+                reply_count: row.replies_count,
+                like_count: row.likes_count,
+                quote_count: row.quote_count,
+            },
+        }
+    }
+}
+
+impl From<UserRow> for V2User {
+    fn from(row: UserRow) -> Self {
+        V2User {
+            id: row.id.to_string(),
+            name: row.display_name,
+            username: row.username,
+            created_at: timestamp(row.created_at),
+            description: row.bio.unwrap_or_default(),
+            profile_image_url: row.profile_image,
+            verified: row.verified,
+            public_metrics: UserMetrics {
+                followers_count: row.followers_count,
+                following_count: row.following_count,
+                tweet_count: row.tweet_count,
+            },
+        }
+    }
+}
+
+// v2 timestamps always carry milliseconds: 2024-05-06T13:47:12.000Z
+fn timestamp(at: DateTime<Utc>) -> String {
+    at.to_rfc3339_opts(SecondsFormat::Millis, true)
+}
+
+fn parse_id(raw: &str) -> V2Result<Uuid> {
+    Uuid::parse_str(raw).map_err(|_| V2Error(ApiError::BadRequest(format!("Invalid id: {}", raw))))
+}
+
+const TWEET_COLUMNS: &str = "t.id, t.user_id, t.parent_tweet_id, t.quoted_tweet_id, t.content,
+        t.likes_count, t.retweets_count, t.replies_count, t.created_at,
+        (SELECT COUNT(*) FROM tweets q WHERE q.quoted_tweet_id = t.id AND q.deleted_at IS NULL) AS quote_count";
+
+const USER_COLUMNS: &str = "u.id, u.username, u.display_name, u.bio, u.profile_image, u.verified,
+        u.followers_count, u.following_count, u.created_at,
+        (SELECT COUNT(*) FROM tweets t WHERE t.user_id = u.id AND t.deleted_at IS NULL) AS tweet_count";
+
+async fn fetch_tweets(db: &PgPool, ids: &[Uuid], viewer_id: Option<Uuid>) -> Result<Vec<TweetRow>, sqlx::Error> {
+    sqlx::query_as::<_, TweetRow>(&format!(
+        "SELECT {} FROM tweets t
+         INNER JOIN users u ON t.user_id = u.id
+         WHERE t.id = ANY($1) AND t.deleted_at IS NULL AND u.deactivated_at IS NULL
+           AND tweet_visible_to(t.user_id, t.visibility, $2)",
+        TWEET_COLUMNS
+    ))
+    .bind(ids)
+    .bind(viewer_id)
+    .fetch_all(db)
+    .await
+}
+
+async fn fetch_user(db: &PgPool, id: Option<Uuid>, username: Option<&str>) -> V2Result<V2User> {
+    let user = sqlx::query_as::<_, UserRow>(&format!(
+        "SELECT {} FROM users u
+         WHERE (u.id = $1 OR u.username = $2) AND u.deactivated_at IS NULL",
+        USER_COLUMNS
+    ))
+    .bind(id)
+    .bind(username)
+    .fetch_optional(db)
+    .await?
+    .ok_or_else(|| ApiError::NotFound("Could not find user".to_string()))?;
+
+    Ok(user.into())
+}
+
+async fn lookup_tweets(
+    state: web::Data<AppState>,
+    viewer: Option<AuthenticatedUser>,
+    query: web::Query<LookupQuery>,
+) -> V2Result<HttpResponse> {
+    let ids = query
+        .ids
+        .split(',')
+        .map(str::trim)
+        .filter(|id| !id.is_empty())
+        .map(parse_id)
+        .collect::<V2Result<Vec<Uuid>>>()?;
+    if ids.is_empty() || ids.len() > MAX_LOOKUP_IDS {
+        return Err(ApiError::BadRequest(format!("ids must list 1 to {} tweet ids", MAX_LOOKUP_IDS)).into());
+    }
+
+    let mut rows = fetch_tweets(&state.db, &ids, viewer.map(|AuthenticatedUser(id)| id)).await?;
+    // Same order as requested
+    rows.sort_by_key(|row| ids.iter().position(|id| *id == row.id));
+    let tweets: Vec<V2Tweet> = rows.into_iter().map(V2Tweet::from).collect();
+
+    Ok(HttpResponse::Ok().json(json!({ "data": tweets })))
+}
+
+async fn get_tweet(
+    state: web::Data<AppState>,
+    viewer: Option<AuthenticatedUser>,
+    tweet_id: web::Path<String>,
+) -> V2Result<HttpResponse> {
+    let tweet_id = parse_id(&tweet_id)?;
+    let tweet = fetch_tweets(&state.db, &[tweet_id], viewer.map(|AuthenticatedUser(id)| id))
+        .await?
+        .pop()
+        .ok_or_else(|| ApiError::NotFound("Could not find tweet".to_string()))?;
+
+    Ok(HttpResponse::Ok().json(json!({ "data": V2Tweet::from(tweet) })))
+}
+
+async fn create_tweet(
+    state: web::Data<AppState>,
+    AuthenticatedUser(user_id): AuthenticatedUser,
+    body: web::Json<CreateTweetBody>,
+) -> V2Result<HttpResponse> {
+    let body = body.into_inner();
+    if body.text.is_empty() {
+        return Err(ApiError::BadRequest("text must not be empty".to_string()).into());
+    }
+
+    let new_tweet = NewTweet {
+        id: None,
+        content: body.text,
+        image_url: None,
+        parent_tweet_id: body.reply.map(|reply| reply.in_reply_to_tweet_id),
+        quoted_tweet_id: body.quote_tweet_id,
+        visibility: TweetVisibility::Public,
+    };
+
+    match submit_tweet(&state, user_id, new_tweet).await? {
+        Submitted::Published(tweet) => Ok(HttpResponse::Created().json(json!({
+            "data": { "id": tweet.id.to_string(), "text": tweet.content }
+        }))),
+        // v2 has no notion of held tweets; report it as a problem the bot can log
+        Submitted::Held(_) => Err(ApiError::Forbidden("Tweet held for review".to_string()).into()),
+    }
+}
+
+async fn delete_tweet(
+    state: web::Data<AppState>,
+    AuthenticatedUser(user_id): AuthenticatedUser,
+    tweet_id: web::Path<String>,
+) -> V2Result<HttpResponse> {
+    soft_delete_tweet(&state.db, Some(user_id), parse_id(&tweet_id)?).await?;
+
+    Ok(HttpResponse::Ok().json(json!({ "data": { "deleted": true } })))
+}
+
+async fn get_me(state: web::Data<AppState>, AuthenticatedUser(user_id): AuthenticatedUser) -> V2Result<HttpResponse> {
+    let user = fetch_user(&state.db, Some(user_id), None).await?;
+    Ok(HttpResponse::Ok().json(json!({ "data": user })))
+}
+
+async fn get_user(state: web::Data<AppState>, user_id: web::Path<String>) -> V2Result<HttpResponse> {
+    let user = fetch_user(&state.db, Some(parse_id(&user_id)?), None).await?;
+    Ok(HttpResponse::Ok().json(json!({ "data": user })))
+}
+
+async fn get_user_by_username(state: web::Data<AppState>, username: web::Path<String>) -> V2Result<HttpResponse> {
+    let user = fetch_user(&state.db, None, Some(username.as_str())).await?;
+    Ok(HttpResponse::Ok().json(json!({ "data": user })))
+}
+
+// Newest first; `pagination_token` is the usual signed cursor.
+async fn get_user_tweets(
+    state: web::Data<AppState>,
+    viewer: Option<AuthenticatedUser>,
+    user_id: web::Path<String>,
+    query: web::Query<TimelineQuery>,
+) -> V2Result<HttpResponse> {
+    let user_id = parse_id(&user_id)?;
+    let limit = query.max_results.unwrap_or(DEFAULT_MAX_RESULTS).clamp(5, MAX_RESULTS);
+    let scope = format!("v2:tweets:{}", user_id);
+    let cursor: Option<Cursor> = query
+        .pagination_token
+        .as_deref()
+        .map(|raw| state.cursors.decode(&scope, raw))
+        .transpose()
+        .map_err(ApiError::BadRequest)?;
+
+    let mut rows = sqlx::query_as::<_, TweetRow>(&format!(
+        "SELECT {} FROM tweets t
+         INNER JOIN users u ON t.user_id = u.id
+         WHERE t.user_id = $1 AND t.deleted_at IS NULL AND u.deactivated_at IS NULL
+           AND tweet_visible_to(t.user_id, t.visibility, $5)
+           AND ($2::timestamptz IS NULL OR (t.created_at, t.id) < ($2, $3))
+         ORDER BY t.created_at DESC, t.id DESC
+         LIMIT $4",
+        TWEET_COLUMNS
+    ))
+    .bind(user_id)
+    .bind(cursor.map(|c| c.created_at))
+    .bind(cursor.map(|c| c.id))
+    .bind(limit + 1)
+    .bind(viewer.map(|AuthenticatedUser(id)| id))
+    .fetch_all(&state.db)
+    .await?;
+
+    let next_token = pagination::next_cursor(&mut rows, limit, &state.cursors, &scope, |t| Cursor {
+        created_at: t.created_at,
+        id: t.id,
+    });
+    let meta = Meta {
+        result_count: rows.len(),
+        newest_id: rows.first().map(|t| t.id.to_string()),
+        oldest_id: rows.last().map(|t| t.id.to_string()),
+        next_token,
+    };
+    let tweets: Vec<V2Tweet> = rows.into_iter().map(V2Tweet::from).collect();
+
+    Ok(HttpResponse::Ok().json(json!({ "data": tweets, "meta": meta })))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn timestamps_use_v2_format() {
+        let at = Utc.with_ymd_and_hms(2024, 5, 6, 13, 47, 12).unwrap();
+        assert_eq!(timestamp(at), "2024-05-06T13:47:12.000Z");
+    }
+
+    #[test]
+    fn replies_and_quotes_become_referenced_tweets() {
+        let parent = Uuid::from_u128(1);
+        let quoted = Uuid::from_u128(2);
+        let tweet = V2Tweet::from(TweetRow {
+            id: Uuid::from_u128(3),
+            user_id: Uuid::from_u128(4),
+            parent_tweet_id: Some(parent),
+            quoted_tweet_id: Some(quoted),
+            content: "hi".to_string(),
+            likes_count: 1,
+            retweets_count: 2,
+            replies_count: 3,
+            quote_count: 4,
+            created_at: Utc::now(),
+        });
+        let kinds: Vec<(&str, String)> = tweet.referenced_tweets.iter().map(|r| (r.kind, r.id.clone())).collect();
+        assert_eq!(kinds, vec![("replied_to", parent.to_string()), ("quoted", quoted.to_string())]);
+    }
+}
@@ -0,0 +1,129 @@
+use actix_web::{web, HttpResponse};
+use serde::Serialize;
+use sqlx::PgPool;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use uuid::Uuid;
+
+use crate::auth::AuthenticatedUser;
+use crate::config;
+use crate::error::ApiResult;
+use crate::models::ApiResponse;
+use crate::AppState;
+
+// Cached users past which expired entries are swept on the next insert
+const SWEEP_AT: usize = 10_000;
+
+// Badge counts in one call, for clients that poll (every 30 seconds or so)
+// rather than hold a WebSocket open. Counts are cached per user for
+// UNREAD_CACHE_SECS (default 10), so several open tabs cost one query per
+// window. Reading notifications on this instance drops the entry; anything
+// else (a new notification, one retracted) shows up once it expires.
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.route("/api/unread", web::get().to(get_unread));
+}
+
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct Unread {
+    pub notifications: i64,
+}
+
+#[derive(Clone)]
+pub struct UnreadCounts {
+    ttl: Duration,
+    cached: Arc<Mutex<HashMap<Uuid, (Instant, Unread)>>>,
+}
+
+impl UnreadCounts {
+    pub fn from_env() -> Result<Self, String> {
+        let secs = config::parse_var("UNREAD_CACHE_SECS", 10)?;
+        Ok(UnreadCounts::new(Duration::from_secs(secs)))
+    }
+
+    fn new(ttl: Duration) -> Self {
+        UnreadCounts {
+            ttl,
+            cached: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    pub async fn get(&self, db: &PgPool, user_id: Uuid) -> Result<Unread, sqlx::Error> {
+        if let Some(unread) = self.cached(user_id, Instant::now()) {
+            return Ok(unread);
+        }
+
+        let unread = Unread {
+            notifications: unread_notifications(db, user_id).await?,
+        };
+        self.store(user_id, unread, Instant::now());
+        Ok(unread)
+    }
+
+    // After the user read something, so the next poll counts afresh
+    pub fn invalidate(&self, user_id: Uuid) {
+        self.cached.lock().unwrap().remove(&user_id);
+    }
+
+    fn cached(&self, user_id: Uuid, now: Instant) -> Option<Unread> {
+        let cached = self.cached.lock().unwrap();
+        let (stored_at, unread) = cached.get(&user_id)?;
+        (now.duration_since(*stored_at) < self.ttl).then_some(*unread)
+    }
+
+    fn store(&self, user_id: Uuid, unread: Unread, now: Instant) {
+        let mut cached = self.cached.lock().unwrap();
+        if cached.len() >= SWEEP_AT {
+            cached.retain(|_, (stored_at, _)| now.duration_since(*stored_at) < self.ttl);
+        }
+        cached.insert(user_id, (now, unread));
+    }
+}
+
+// Unread notifications, leaving out those from muted accounts and about
+// tweets matching a muted keyword, which the notification list hides too
+pub async fn unread_notifications(db: &PgPool, user_id: Uuid) -> Result<i64, sqlx::Error> {
+    sqlx::query_scalar::<_, i64>(
+        "SELECT COUNT(*) FROM notifications n
+         WHERE user_id = $1 AND read_at IS NULL
+           AND NOT EXISTS (SELECT 1 FROM mutes m WHERE m.muter_id = $1 AND m.muted_id = n.actor_id)
+           AND NOT EXISTS (
+               SELECT 1 FROM tweets t INNER JOIN muted_keywords k ON k.user_id = $1
+               WHERE t.id = n.tweet_id AND strpos(lower(t.content), k.keyword) > 0
+           )"
+    )
+    .bind(user_id)
+    .fetch_one(db)
+    .await
+}
+
+async fn get_unread(state: web::Data<AppState>, AuthenticatedUser(user_id): AuthenticatedUser) -> ApiResult<HttpResponse> {
+    let unread = state.unread.get(&state.db, user_id).await?;
+
+    Ok(HttpResponse::Ok().json(ApiResponse {
+        success: true,
+        data: Some(unread),
+        message: None,
+        next_cursor: None,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn serves_counts_until_they_expire() {
+        let counts = UnreadCounts::new(Duration::from_secs(10));
+        let user = Uuid::new_v4();
+        let now = Instant::now();
+        counts.store(user, Unread { notifications: 3 }, now);
+
+        assert_eq!(counts.cached(user, now + Duration::from_secs(9)).map(|u| u.notifications), Some(3));
+        assert!(counts.cached(user, now + Duration::from_secs(10)).is_none());
+        assert!(counts.cached(Uuid::new_v4(), now).is_none());
+
+        counts.invalidate(user);
+        assert!(counts.cached(user, now).is_none());
+    }
+}
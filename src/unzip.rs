@@ -0,0 +1,295 @@
+use flate2::read::DeflateDecoder;
+use std::io::Read;
+
+const LOCAL_HEADER: u32 = 0x0403_4b50;
+const CENTRAL_HEADER: u32 = 0x0201_4b50;
+const END_OF_CENTRAL_DIRECTORY: u32 = 0x0605_4b50;
+const ZIP64_END_OF_CENTRAL_DIRECTORY: u32 = 0x0606_4b50;
+const ZIP64_LOCATOR: u32 = 0x0706_4b50;
+const ZIP64_EXTRA: u16 = 0x0001;
+
+const STORED: u16 = 0;
+const DEFLATED: u16 = 8;
+
+// Minimal in-memory ZIP reader for uploaded archives: stored and deflated
+// entries, ZIP64 sizes and offsets, CRC checked on read. Encryption,
+// multi-disk archives and other compression methods are rejected.
+pub struct ZipArchive<'a> {
+    data: &'a [u8],
+    entries: Vec<ZipEntry>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ZipEntry {
+    pub name: String,
+    pub size: u64,
+    method: u16,
+    encrypted: bool,
+    crc32: u32,
+    compressed_size: u64,
+    header_offset: u64,
+}
+
+impl<'a> ZipArchive<'a> {
+    pub fn parse(data: &'a [u8]) -> Result<Self, String> {
+        let eocd = find_end_of_central_directory(data).ok_or("Not a ZIP archive")?;
+        let mut count = read_u16(data, eocd + 10)? as u64;
+        let mut cd_size = read_u32(data, eocd + 12)? as u64;
+        let mut cd_offset = read_u32(data, eocd + 16)? as u64;
+
+        if count == 0xFFFF || cd_size == 0xFFFF_FFFF || cd_offset == 0xFFFF_FFFF {
+            let locator = eocd.checked_sub(20).ok_or("Truncated ZIP64 locator")?;
+            if read_u32(data, locator)? != ZIP64_LOCATOR {
+                return Err("Missing ZIP64 locator".to_string());
+            }
+            let record = to_usize(read_u64(data, locator + 8)?)?;
+            if read_u32(data, record)? != ZIP64_END_OF_CENTRAL_DIRECTORY {
+                return Err("Invalid ZIP64 end of central directory".to_string());
+            }
+            count = read_u64(data, record + 32)?;
+            cd_size = read_u64(data, record + 40)?;
+            cd_offset = read_u64(data, record + 48)?;
+        }
+
+        let cd_end = cd_offset.checked_add(cd_size).ok_or("Invalid central directory")?;
+        if cd_end > data.len() as u64 {
+            return Err("Truncated central directory".to_string());
+        }
+
+        let mut entries = Vec::new();
+        let mut pos = to_usize(cd_offset)?;
+        for _ in 0..count {
+            if read_u32(data, pos)? != CENTRAL_HEADER {
+                return Err("Invalid central directory entry".to_string());
+            }
+            let flags = read_u16(data, pos + 8)?;
+            let method = read_u16(data, pos + 10)?;
+            let crc32 = read_u32(data, pos + 16)?;
+            let mut compressed_size = read_u32(data, pos + 20)? as u64;
+            let mut size = read_u32(data, pos + 24)? as u64;
+            let name_len = read_u16(data, pos + 28)? as usize;
+            let extra_len = read_u16(data, pos + 30)? as usize;
+            let comment_len = read_u16(data, pos + 32)? as usize;
+            let mut header_offset = read_u32(data, pos + 42)? as u64;
+
+            let name = slice(data, pos + 46, name_len)?;
+            let extra = slice(data, pos + 46 + name_len, extra_len)?;
+
+            // ZIP64 values appear in the extra field, in this order, only for
+            // the fields that overflowed
+            if let Some(mut zip64) = extra_field(extra, ZIP64_EXTRA) {
+                for field in [&mut size, &mut compressed_size, &mut header_offset] {
+                    if *field == 0xFFFF_FFFF {
+                        *field = read_u64(zip64, 0)?;
+                        zip64 = &zip64[8..];
+                    }
+                }
+            }
+
+            entries.push(ZipEntry {
+                name: String::from_utf8_lossy(name).into_owned(),
+                size,
+                method,
+                encrypted: flags & 1 != 0,
+                crc32,
+                compressed_size,
+                header_offset,
+            });
+            pos += 46 + name_len + extra_len + comment_len;
+        }
+
+        Ok(ZipArchive { data, entries })
+    }
+
+    pub fn entries(&self) -> &[ZipEntry] {
+        &self.entries
+    }
+
+    // Decompresses an entry, refusing anything that claims or turns out to be
+    // larger than `max_size` so a small archive can't expand without bound.
+    pub fn read(&self, entry: &ZipEntry, max_size: u64) -> Result<Vec<u8>, String> {
+        read_entry(self.data, entry, max_size)
+    }
+}
+
+// `ZipArchive::read` for callers that keep the entries apart from the data,
+// e.g. to inflate on a blocking thread
+pub fn read_entry(data: &[u8], entry: &ZipEntry, max_size: u64) -> Result<Vec<u8>, String> {
+    if entry.encrypted {
+        return Err(format!("{} is encrypted", entry.name));
+    }
+    if entry.size > max_size {
+        return Err(format!("{} is larger than {} bytes", entry.name, max_size));
+    }
+
+    let header = to_usize(entry.header_offset)?;
+    if read_u32(data, header)? != LOCAL_HEADER {
+        return Err(format!("Invalid local header for {}", entry.name));
+    }
+    let name_len = read_u16(data, header + 26)? as usize;
+    let extra_len = read_u16(data, header + 28)? as usize;
+    let compressed = slice(data, header + 30 + name_len + extra_len, to_usize(entry.compressed_size)?)?;
+
+    let bytes = match entry.method {
+        STORED => compressed.to_vec(),
+        DEFLATED => {
+            let mut bytes = Vec::with_capacity(entry.size as usize);
+            DeflateDecoder::new(compressed)
+                .take(entry.size + 1)
+                .read_to_end(&mut bytes)
+                .map_err(|e| format!("Failed to inflate {}: {}", entry.name, e))?;
+            bytes
+        }
+        method => return Err(format!("{} uses unsupported compression method {}", entry.name, method)),
+    };
+
+    if bytes.len() as u64 != entry.size || crc32fast::hash(&bytes) != entry.crc32 {
+        return Err(format!("{} is corrupt", entry.name));
+    }
+    Ok(bytes)
+}
+
+// The record sits at the very end, followed only by a comment of up to 64 KiB
+fn find_end_of_central_directory(data: &[u8]) -> Option<usize> {
+    let last = data.len().checked_sub(22)?;
+    let first = last.saturating_sub(0xFFFF);
+    (first..=last).rev().find(|&pos| read_u32(data, pos).ok() == Some(END_OF_CENTRAL_DIRECTORY))
+}
+
+fn extra_field(mut extra: &[u8], id: u16) -> Option<&[u8]> {
+    while extra.len() >= 4 {
+        let field_id = u16::from_le_bytes([extra[0], extra[1]]);
+        let len = u16::from_le_bytes([extra[2], extra[3]]) as usize;
+        let body = extra.get(4..4 + len)?;
+        if field_id == id {
+            return Some(body);
+        }
+        extra = &extra[4 + len..];
+    }
+    None
+}
+
+fn slice(data: &[u8], start: usize, len: usize) -> Result<&[u8], String> {
+    start
+        .checked_add(len)
+        .and_then(|end| data.get(start..end))
+        .ok_or_else(|| "Truncated ZIP archive".to_string())
+}
+
+fn read_u16(data: &[u8], pos: usize) -> Result<u16, String> {
+    Ok(u16::from_le_bytes(slice(data, pos, 2)?.try_into().unwrap()))
+}
+
+fn read_u32(data: &[u8], pos: usize) -> Result<u32, String> {
+    Ok(u32::from_le_bytes(slice(data, pos, 4)?.try_into().unwrap()))
+}
+
+fn read_u64(data: &[u8], pos: usize) -> Result<u64, String> {
+    Ok(u64::from_le_bytes(slice(data, pos, 8)?.try_into().unwrap()))
+}
+
+fn to_usize(value: u64) -> Result<usize, String> {
+    usize::try_from(value).map_err(|_| "ZIP offset out of range".to_string())
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use flate2::write::DeflateEncoder;
+    use flate2::Compression;
+    use std::io::Write;
+
+    // Builds an archive from (name, contents, deflate?) triples
+    pub fn build_zip(files: &[(&str, &[u8], bool)]) -> Vec<u8> {
+        let mut out = Vec::new();
+        let mut central = Vec::new();
+
+        for (name, contents, deflate) in files {
+            let (method, body) = if *deflate {
+                let mut encoder = DeflateEncoder::new(Vec::new(), Compression::default());
+                encoder.write_all(contents).unwrap();
+                (DEFLATED, encoder.finish().unwrap())
+            } else {
+                (STORED, contents.to_vec())
+            };
+            let crc = crc32fast::hash(contents);
+            let offset = out.len() as u32;
+
+            out.extend_from_slice(&LOCAL_HEADER.to_le_bytes());
+            out.extend_from_slice(&[20, 0, 0, 0]);
+            out.extend_from_slice(&method.to_le_bytes());
+            out.extend_from_slice(&[0; 4]);
+            out.extend_from_slice(&crc.to_le_bytes());
+            out.extend_from_slice(&(body.len() as u32).to_le_bytes());
+            out.extend_from_slice(&(contents.len() as u32).to_le_bytes());
+            out.extend_from_slice(&(name.len() as u16).to_le_bytes());
+            out.extend_from_slice(&[0, 0]);
+            out.extend_from_slice(name.as_bytes());
+            out.extend_from_slice(&body);
+
+            central.extend_from_slice(&CENTRAL_HEADER.to_le_bytes());
+            central.extend_from_slice(&[20, 0, 20, 0, 0, 0]);
+            central.extend_from_slice(&method.to_le_bytes());
+            central.extend_from_slice(&[0; 4]);
+            central.extend_from_slice(&crc.to_le_bytes());
+            central.extend_from_slice(&(body.len() as u32).to_le_bytes());
+            central.extend_from_slice(&(contents.len() as u32).to_le_bytes());
+            central.extend_from_slice(&(name.len() as u16).to_le_bytes());
+            central.extend_from_slice(&[0; 12]);
+            central.extend_from_slice(&offset.to_le_bytes());
+            central.extend_from_slice(name.as_bytes());
+        }
+
+        let cd_offset = out.len() as u32;
+        out.extend_from_slice(&central);
+        out.extend_from_slice(&END_OF_CENTRAL_DIRECTORY.to_le_bytes());
+        out.extend_from_slice(&[0; 4]);
+        out.extend_from_slice(&(files.len() as u16).to_le_bytes());
+        out.extend_from_slice(&(files.len() as u16).to_le_bytes());
+        out.extend_from_slice(&(central.len() as u32).to_le_bytes());
+        out.extend_from_slice(&cd_offset.to_le_bytes());
+        out.extend_from_slice(&[0, 0]);
+        out
+    }
+
+    #[test]
+    fn reads_stored_and_deflated_entries() {
+        let text = "hello ".repeat(100);
+        let zip = build_zip(&[("a.txt", b"plain", false), ("dir/b.txt", text.as_bytes(), true)]);
+        let archive = ZipArchive::parse(&zip).unwrap();
+
+        let [a, b] = archive.entries() else {
+            panic!("expected two entries");
+        };
+
+        assert_eq!((a.name.as_str(), a.size), ("a.txt", 5));
+        assert_eq!(b.name, "dir/b.txt");
+        assert_eq!(archive.read(a, 1024).unwrap(), b"plain");
+        assert_eq!(archive.read(b, 1024).unwrap(), text.as_bytes());
+    }
+
+    #[test]
+    fn enforces_size_limit() {
+        let zip = build_zip(&[("big.txt", &[b'x'; 2048], true)]);
+        let archive = ZipArchive::parse(&zip).unwrap();
+        assert!(archive.read(&archive.entries()[0], 1024).is_err());
+    }
+
+    #[test]
+    fn detects_corruption() {
+        let mut zip = build_zip(&[("a.txt", b"plain", false)]);
+        let pos = zip.windows(5).position(|w| w == b"plain").unwrap();
+        zip[pos] = b'P';
+        let archive = ZipArchive::parse(&zip).unwrap();
+        assert!(archive.read(&archive.entries()[0], 1024).is_err());
+    }
+
+    #[test]
+    fn rejects_non_zip_input() {
+        assert!(ZipArchive::parse(b"not a zip file at all, just some text").is_err());
+        assert!(ZipArchive::parse(b"").is_err());
+
+        let zip = build_zip(&[("a.txt", b"plain", false)]);
+        assert!(ZipArchive::parse(&zip[..zip.len() - 30]).is_err());
+    }
+}
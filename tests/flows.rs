@@ -0,0 +1,253 @@
+mod common;
+
+use actix_web::http::StatusCode;
+use actix_web::test::{self, TestRequest};
+use serde_json::json;
+
+use common::{call, register};
+
+#[actix_web::test]
+async fn register_then_log_in() {
+    let Some(setup) = common::setup().await else { return };
+    let app = test::init_service(setup.app()).await;
+
+    let account = register(&app, "login").await;
+
+    let (status, body) = call(&app, TestRequest::get().uri("/api/auth/me"), Some(&account.token)).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(body["data"]["username"], account.username.as_str());
+
+    let login = |password: &str| {
+        TestRequest::post()
+            .uri("/api/auth/login")
+            .set_json(json!({ "email": account.email, "password": password }))
+    };
+    let (status, body) = call(&app, login("correct horse"), None).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert!(body["data"]["token"].is_string());
+
+    // Refresh tokens rotate, and replaying a used one ends the session
+    let refresh = |token: &serde_json::Value| {
+        TestRequest::post().uri("/api/auth/refresh").set_json(json!({ "refresh_token": token }))
+    };
+    let first = body["data"]["refresh_token"].clone();
+    let (status, body) = call(&app, refresh(&first), None).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    let second = body["data"]["refresh_token"].clone();
+    assert_ne!(first, second);
+    let (status, _) = call(&app, refresh(&first), None).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    let (status, _) = call(&app, refresh(&second), None).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    let (status, _) = call(&app, login("wrong horse"), None).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    let (status, body) = call(
+        &app,
+        TestRequest::post().uri("/api/auth/register").set_json(json!({
+            "username": common::unique("taken"),
+            "email": account.email,
+            "password": "correct horse",
+            "display_name": "taken",
+        })),
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::CONFLICT, "{}", body);
+
+    // Tokens are signed with the shared secret here, so there's nothing to publish
+    let (status, body) = call(&app, TestRequest::get().uri("/.well-known/jwks.json"), None).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(body["keys"], json!([]), "{}", body);
+}
+
+#[actix_web::test]
+async fn follow_tweet_and_like() {
+    let Some(setup) = common::setup().await else { return };
+    let app = test::init_service(setup.app()).await;
+
+    let alice = register(&app, "alice").await;
+    let bob = register(&app, "bob").await;
+
+    let follow = || TestRequest::post().uri(&format!("/api/users/{}/follow", alice.username));
+    let (status, body) = call(&app, follow(), Some(&bob.token)).await;
+    assert!(status.is_success(), "{} {}", status, body);
+    // Following again is harmless and reports the same state
+    let (status, body) = call(&app, follow(), Some(&bob.token)).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(body["data"]["following"], true, "{}", body);
+    assert_eq!(body["data"]["followers_count"], 1, "{}", body);
+    assert_eq!(body["data"]["following_count"], 1, "{}", body);
+    let (status, body) = call(
+        &app,
+        TestRequest::post()
+            .uri(&format!("/api/users/{}/notify", alice.username))
+            .set_json(json!({ "mode": "all" })),
+        Some(&bob.token),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(body["data"]["notify"], "all", "{}", body);
+    assert_eq!(body["data"]["followed_by"], false, "{}", body);
+    let (status, body) = call(&app, TestRequest::get().uri("/api/unread"), Some(&alice.token)).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(body["data"]["notifications"], 1, "{}", body);
+
+    let (status, body) = call(
+        &app,
+        TestRequest::post().uri("/api/tweets").set_json(json!({ "content": "hello from the tests" })),
+        Some(&alice.token),
+    )
+    .await;
+    assert_eq!(status, StatusCode::CREATED, "{}", body);
+    let tweet_id = body["data"]["id"].as_str().unwrap().to_string();
+
+    let (status, body) = call(
+        &app,
+        TestRequest::put().uri("/api/users/me/pinned_tweet").set_json(json!({ "tweet_id": tweet_id })),
+        Some(&alice.token),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    let (status, body) = call(&app, TestRequest::get().uri(&format!("/api/users/{}", alice.username)), None).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(body["data"]["pinned_tweet"]["id"], tweet_id.as_str(), "{}", body);
+    let (status, body) = call(&app, TestRequest::get().uri(&format!("/api/users/{}/tweets", alice.username)), None).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(body["data"][0]["id"], tweet_id.as_str(), "{}", body);
+
+    // Bob's timeline has the tweet of the account he follows
+    let (status, body) = call(&app, TestRequest::get().uri("/api/tweets/timeline"), Some(&bob.token)).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    let timeline = body["data"].as_array().unwrap();
+    assert!(timeline.iter().any(|tweet| tweet["id"] == tweet_id.as_str()), "{}", body);
+
+    let (status, body) = call(
+        &app,
+        TestRequest::post()
+            .uri("/api/tweets")
+            .set_json(json!({ "content": "a reply", "parent_tweet_id": tweet_id })),
+        Some(&bob.token),
+    )
+    .await;
+    assert_eq!(status, StatusCode::CREATED, "{}", body);
+    let (status, body) = call(&app, TestRequest::get().uri(&format!("/api/tweets/{}/replies", tweet_id)), None).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(body["data"][0]["content"], "a reply", "{}", body);
+
+    let like = || TestRequest::post().uri(&format!("/api/tweets/{}/like", tweet_id));
+    let (status, body) = call(&app, like(), Some(&bob.token)).await;
+    assert!(status.is_success(), "{} {}", status, body);
+    let (status, body) = call(&app, like(), Some(&bob.token)).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(body["data"]["liked"], true, "{}", body);
+    assert_eq!(body["data"]["likes_count"], 1, "{}", body);
+
+    let (status, body) = call(&app, TestRequest::get().uri(&format!("/api/tweets/{}", tweet_id)), Some(&bob.token)).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(body["data"]["tweet"]["likes_count"], 1, "{}", body);
+    let (status, body) = call(&app, TestRequest::get().uri(&format!("/api/users/{}/tweets", alice.username)), Some(&bob.token)).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(body["data"][0]["is_liked"], true, "{}", body);
+
+    let (status, body) = call(
+        &app,
+        TestRequest::post().uri(&format!("/api/tweets/{}/retweet", tweet_id)),
+        Some(&bob.token),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(body["data"]["retweeted"], true, "{}", body);
+    assert_eq!(body["data"]["liked"], true, "{}", body);
+    assert_eq!(body["data"]["retweets_count"], 1, "{}", body);
+    let (status, body) = call(
+        &app,
+        TestRequest::delete().uri(&format!("/api/tweets/{}/unretweet", tweet_id)),
+        Some(&bob.token),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(body["data"]["retweets_count"], 0, "{}", body);
+
+    let unlike = || TestRequest::delete().uri(&format!("/api/tweets/{}/unlike", tweet_id));
+    let (status, body) = call(&app, unlike(), Some(&bob.token)).await;
+    assert!(status.is_success(), "{} {}", status, body);
+    let (status, body) = call(&app, unlike(), Some(&bob.token)).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(body["data"]["liked"], false, "{}", body);
+    assert_eq!(body["data"]["likes_count"], 0, "{}", body);
+
+    // Only the author sees a tweet's analytics
+    let analytics = format!("/api/tweets/{}/analytics", tweet_id);
+    let (status, _) = call(&app, TestRequest::get().uri(&analytics), Some(&bob.token)).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let (status, body) = call(&app, TestRequest::get().uri(&analytics), Some(&alice.token)).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+
+    let export = TestRequest::get()
+        .uri("/api/users/me/analytics/export?range=7d")
+        .insert_header(("Authorization", format!("Bearer {}", alice.token)))
+        .to_request();
+    let res = test::call_service(&app, export).await;
+    assert_eq!(res.status(), StatusCode::OK);
+    let csv = String::from_utf8(test::read_body(res).await.to_vec()).unwrap();
+    let mut lines = csv.lines();
+    assert_eq!(lines.next(), Some("tweet_id,created_at,impressions,views,profile_clicks,likes,retweets,replies"));
+    assert!(lines.any(|line| line.starts_with(tweet_id.as_str())), "{}", csv);
+}
+
+#[actix_web::test]
+async fn lifting_a_suspension_keeps_a_deactivated_account_deactivated() {
+    let Some(setup) = common::setup().await else { return };
+    let app = test::init_service(setup.app()).await;
+
+    let admin = register(&app, "admin").await;
+    let user = register(&app, "suspended").await;
+    let db = sqlx::PgPool::connect(&std::env::var("TEST_DATABASE_URL").unwrap()).await.unwrap();
+    sqlx::query("UPDATE users SET role = 'admin' WHERE username = $1")
+        .bind(&admin.username)
+        .execute(&db)
+        .await
+        .unwrap();
+    let (status, body) = call(&app, TestRequest::get().uri("/api/auth/me"), Some(&user.token)).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    let user_id = body["data"]["id"].as_str().unwrap().to_string();
+
+    let (status, body) = call(&app, TestRequest::post().uri("/api/users/me/deactivate"), Some(&user.token)).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    let (status, body) = call(
+        &app,
+        TestRequest::post()
+            .uri(&format!("/api/admin/users/{}/suspend", user_id))
+            .set_json(json!({ "reason": "spam" })),
+        Some(&admin.token),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    let (status, body) = call(
+        &app,
+        TestRequest::post().uri(&format!("/api/admin/users/{}/unsuspend", user_id)),
+        Some(&admin.token),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert!(body["data"]["deactivated_at"].is_string(), "{}", body);
+
+    let (status, _) = call(&app, TestRequest::get().uri(&format!("/api/users/{}", user.username)), None).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[actix_web::test]
+async fn requires_a_token_to_tweet() {
+    let Some(setup) = common::setup().await else { return };
+    let app = test::init_service(setup.app()).await;
+
+    let (status, _) = call(
+        &app,
+        TestRequest::post().uri("/api/tweets").set_json(json!({ "content": "anonymous" })),
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+}